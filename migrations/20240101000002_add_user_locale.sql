@@ -0,0 +1,2 @@
+-- Preferred locale for human-facing exports (CSV, HTML, statements)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locale VARCHAR(35) NOT NULL DEFAULT 'en-US';
@@ -0,0 +1,2 @@
+-- Whether the user's email address has been confirmed
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email_verified BOOLEAN NOT NULL DEFAULT FALSE;
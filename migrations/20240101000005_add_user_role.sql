@@ -0,0 +1,3 @@
+-- Privilege level of the user: USER or ADMIN
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role VARCHAR(10) NOT NULL DEFAULT 'USER'
+    CHECK (role IN ('USER', 'ADMIN'));
@@ -0,0 +1,3 @@
+-- Client that initiated a money movement, kept for fraud investigations
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS ip_address VARCHAR(45);
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS user_agent TEXT;
@@ -0,0 +1,4 @@
+-- Customer-facing account number; the scheme is chosen per deployment
+-- (ACCOUNT_NUMBER_FORMAT). Accounts opened before this column existed have none.
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS account_number VARCHAR(34);
+ALTER TABLE accounts ADD CONSTRAINT accounts_account_number_key UNIQUE (account_number);
@@ -0,0 +1,12 @@
+-- Results of the periodic sweep that checks global ledger invariants
+CREATE TABLE IF NOT EXISTS integrity_reports (
+    id UUID PRIMARY KEY,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    finished_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    transactions_scanned BIGINT NOT NULL,
+    accounts_scanned BIGINT NOT NULL,
+    violation_count BIGINT NOT NULL,
+    violations JSONB NOT NULL DEFAULT '[]'
+);
+
+CREATE INDEX IF NOT EXISTS idx_integrity_reports_finished ON integrity_reports(finished_at);
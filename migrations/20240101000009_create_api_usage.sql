@@ -0,0 +1,12 @@
+-- Daily request counts per user and route template, for quota planning.
+-- No foreign key on user_id: usage history outlives deleted users and must
+-- never block a flush.
+CREATE TABLE IF NOT EXISTS api_usage (
+    user_id UUID NOT NULL,
+    route VARCHAR(255) NOT NULL,
+    usage_date DATE NOT NULL,
+    request_count BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, route, usage_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_usage_date ON api_usage(usage_date);
@@ -0,0 +1,18 @@
+-- Whether the user wants an email receipt for large transfers they send or receive
+ALTER TABLE users ADD COLUMN IF NOT EXISTS transfer_receipts BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- Every attempt to email a notification, kept so support can answer
+-- "I never got my receipt"
+CREATE TABLE IF NOT EXISTS email_deliveries (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    transaction_id UUID REFERENCES transactions(id),
+    kind VARCHAR(30) NOT NULL,
+    recipient VARCHAR(100) NOT NULL,
+    status VARCHAR(10) NOT NULL CHECK (status IN ('SENT', 'FAILED')),
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_deliveries_user ON email_deliveries(user_id);
+CREATE INDEX IF NOT EXISTS idx_email_deliveries_transaction ON email_deliveries(transaction_id);
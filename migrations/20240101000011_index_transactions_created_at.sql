@@ -0,0 +1,2 @@
+-- Supports exporting the ledger in creation order, optionally within a date range
+CREATE INDEX IF NOT EXISTS idx_transactions_created_at ON transactions(created_at, id);
@@ -0,0 +1,30 @@
+-- Spending category chosen by the client for outgoing transactions
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS category VARCHAR(50);
+
+-- Spending limits per account, category and calendar period
+CREATE TABLE IF NOT EXISTS budgets (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    category VARCHAR(50) NOT NULL,
+    period VARCHAR(10) NOT NULL CHECK (period IN ('WEEKLY', 'MONTHLY')),
+    limit_amount DECIMAL(19, 4) NOT NULL CHECK (limit_amount > 0),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (account_id, category, period)
+);
+
+-- One alert per budget and period, raised by the transaction that first exceeded it
+CREATE TABLE IF NOT EXISTS budget_alerts (
+    id UUID PRIMARY KEY,
+    budget_id UUID NOT NULL REFERENCES budgets(id) ON DELETE CASCADE,
+    period_start DATE NOT NULL,
+    transaction_id UUID NOT NULL REFERENCES transactions(id),
+    spent DECIMAL(19, 4) NOT NULL,
+    limit_amount DECIMAL(19, 4) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (budget_id, period_start)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transactions_sender_category
+    ON transactions(sender_account_id, category, created_at)
+    WHERE category IS NOT NULL;
@@ -0,0 +1,32 @@
+-- An account owner's import of historical transactions, which may span
+-- several uploads of the same or consecutive files
+CREATE TABLE IF NOT EXISTS import_sessions (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    rows_imported BIGINT NOT NULL DEFAULT 0,
+    rows_duplicate BIGINT NOT NULL DEFAULT 0,
+    rows_failed BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Imported history is documentation only: it is kept apart from `transactions`
+-- so it can never move a balance or be picked up by reconciliation
+CREATE TABLE IF NOT EXISTS imported_transactions (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    import_session_id UUID NOT NULL REFERENCES import_sessions(id) ON DELETE CASCADE,
+    -- SHA-256 of the normalized row and its occurrence in the file, so a
+    -- re-uploaded file skips rows that are already stored
+    row_hash CHAR(64) NOT NULL,
+    transaction_date DATE NOT NULL,
+    amount DECIMAL(19, 4) NOT NULL CHECK (amount > 0),
+    direction VARCHAR(3) NOT NULL CHECK (direction IN ('IN', 'OUT')),
+    description TEXT,
+    category VARCHAR(50),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (account_id, row_hash)
+);
+
+CREATE INDEX IF NOT EXISTS idx_imported_transactions_account
+    ON imported_transactions(account_id, transaction_date);
@@ -0,0 +1,37 @@
+-- updated_at is maintained by the database, not by queries. Every table with an
+-- updated_at column gets the trigger below, so any UPDATE moves it, whichever
+-- code path issued it. New tables with an updated_at column must add the same
+-- trigger in their migration:
+--
+--     CREATE TRIGGER <table>_touch_updated_at
+--         BEFORE UPDATE ON <table>
+--         FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+--
+-- clock_timestamp() rather than NOW() so the column still moves when a row is
+-- inserted and updated within the same database transaction.
+CREATE OR REPLACE FUNCTION touch_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = clock_timestamp();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER users_touch_updated_at
+    BEFORE UPDATE ON users
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+CREATE TRIGGER accounts_touch_updated_at
+    BEFORE UPDATE ON accounts
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+CREATE TRIGGER transactions_touch_updated_at
+    BEFORE UPDATE ON transactions
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+CREATE TRIGGER budgets_touch_updated_at
+    BEFORE UPDATE ON budgets
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+CREATE TRIGGER import_sessions_touch_updated_at
+    BEFORE UPDATE ON import_sessions
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
@@ -0,0 +1,6 @@
+-- Marks deposits minted by the sandbox faucet so they can be purged later
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS sandbox BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_sandbox
+    ON transactions(receiver_account_id)
+    WHERE sandbox;
@@ -0,0 +1,17 @@
+-- A payment from one account to several others, booked all or nothing. Each
+-- leg is an ordinary TRANSFER in `transactions` that points back here, so
+-- balances and reconciliation only ever see the legs.
+CREATE TABLE IF NOT EXISTS split_transfers (
+    id UUID PRIMARY KEY,
+    sender_account_id UUID NOT NULL REFERENCES accounts(id),
+    total DECIMAL(19, 4) NOT NULL CHECK (total > 0),
+    currency VARCHAR(3) NOT NULL,
+    description TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS split_transfer_id UUID REFERENCES split_transfers(id);
+
+CREATE INDEX IF NOT EXISTS idx_transactions_split_transfer
+    ON transactions(split_transfer_id) WHERE split_transfer_id IS NOT NULL;
@@ -0,0 +1,14 @@
+-- Client-chosen keys that make retrying a transaction request safe. A key is
+-- claimed in the same database transaction that moves the money, and the
+-- response is stored before that transaction commits, so a committed key
+-- always has the outcome of its request.
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    key VARCHAR(255) NOT NULL,
+    -- SHA-256 of the endpoint and request body, so a key reused for a
+    -- different request can be refused
+    request_hash CHAR(64) NOT NULL,
+    response JSONB,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, key)
+);
@@ -0,0 +1,33 @@
+-- External accounts (IBANs, wallet references) a user may withdraw to. A
+-- destination can be used once `verified_at` has passed: it is set at
+-- registration when a verification delay is configured, and otherwise only
+-- when an administrator approves the destination. Removed destinations are
+-- kept so the withdrawals that used them still point somewhere.
+CREATE TABLE IF NOT EXISTS withdrawal_destinations (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    label VARCHAR(100) NOT NULL,
+    identifier VARCHAR(64) NOT NULL,
+    verified_at TIMESTAMP WITH TIME ZONE,
+    removed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_withdrawal_destinations_active
+    ON withdrawal_destinations(user_id, identifier)
+    WHERE removed_at IS NULL;
+
+-- Audit trail of changes to the registry
+CREATE TABLE IF NOT EXISTS withdrawal_destination_events (
+    id UUID PRIMARY KEY,
+    destination_id UUID NOT NULL REFERENCES withdrawal_destinations(id) ON DELETE CASCADE,
+    actor_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    action VARCHAR(10) NOT NULL CHECK (action IN ('REGISTERED', 'VERIFIED', 'REMOVED')),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_withdrawal_destination_events_destination
+    ON withdrawal_destination_events(destination_id);
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS destination_id UUID REFERENCES withdrawal_destinations(id);
@@ -0,0 +1,5 @@
+-- Link each idempotency key to the transaction it booked. Keys expire after
+-- 24 hours; a user's expired keys are deleted the next time they claim one,
+-- after which the key can be used for a new request.
+ALTER TABLE idempotency_keys
+    ADD COLUMN IF NOT EXISTS transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL;
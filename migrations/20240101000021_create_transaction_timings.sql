@@ -0,0 +1,15 @@
+-- When each step of a money-moving request happened, so support can see
+-- where the time went. Rows are written after the transaction commits and
+-- are purged after a few days by the retention worker.
+CREATE TABLE IF NOT EXISTS transaction_timings (
+    transaction_id UUID PRIMARY KEY REFERENCES transactions(id) ON DELETE CASCADE,
+    request_id VARCHAR(64),
+    instance_id VARCHAR(255) NOT NULL,
+    received_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    lock_acquired_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    committed_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    CHECK (received_at <= lock_acquired_at AND lock_acquired_at <= committed_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_timings_committed_at
+    ON transaction_timings(committed_at);
@@ -0,0 +1,14 @@
+-- Transfers undone by a compensating transfer. The original moves to REVERSED
+-- and the two point at each other; the unique index lets a transfer be
+-- reversed only once.
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transactions_status_check;
+ALTER TABLE transactions ADD CONSTRAINT transactions_status_check
+    CHECK (status IN ('PENDING', 'COMPLETED', 'FAILED', 'REVERSED'));
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS reverses_transaction_id UUID REFERENCES transactions(id);
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS reversed_by UUID REFERENCES transactions(id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_transactions_reverses
+    ON transactions(reverses_transaction_id) WHERE reverses_transaction_id IS NOT NULL;
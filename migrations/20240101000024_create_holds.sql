@@ -0,0 +1,29 @@
+-- Authorization holds reserve funds on an account until they are captured
+-- into a withdrawal or transfer, or released. `held_amount` is the sum of the
+-- account's active holds; what may still be spent is `balance - held_amount`.
+ALTER TABLE accounts
+    ADD COLUMN IF NOT EXISTS held_amount DECIMAL(19, 4) NOT NULL DEFAULT 0;
+
+ALTER TABLE accounts
+    ADD CONSTRAINT held_amount_non_negative CHECK (held_amount >= 0);
+
+CREATE TABLE IF NOT EXISTS holds (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id),
+    amount DECIMAL(19, 4) NOT NULL CHECK (amount > 0),
+    currency VARCHAR(3) NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'ACTIVE'
+        CHECK (status IN ('ACTIVE', 'CAPTURED', 'RELEASED')),
+    description TEXT,
+    -- Set on capture: how much was actually taken, and the transaction booking it
+    captured_amount DECIMAL(19, 4),
+    transaction_id UUID REFERENCES transactions(id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_holds_account ON holds(account_id);
+
+CREATE TRIGGER holds_touch_updated_at
+    BEFORE UPDATE ON holds
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
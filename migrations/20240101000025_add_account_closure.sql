@@ -0,0 +1,5 @@
+-- Closed accounts are kept for their history but can't move money any more.
+-- An account closed by merging it into another one points at that account.
+ALTER TABLE accounts
+    ADD COLUMN IF NOT EXISTS closed_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN IF NOT EXISTS merged_into UUID REFERENCES accounts(id);
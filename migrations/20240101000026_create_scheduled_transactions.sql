@@ -0,0 +1,32 @@
+-- Transfers booked at a future time. A background task picks up SCHEDULED
+-- rows once execute_at has passed and runs them as ordinary transfers, so
+-- funds are only checked then; rows still due after a restart are picked up
+-- on the next poll.
+CREATE TABLE IF NOT EXISTS scheduled_transactions (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    sender_account_id UUID NOT NULL REFERENCES accounts(id),
+    receiver_account_id UUID NOT NULL REFERENCES accounts(id),
+    amount DECIMAL(19, 4) NOT NULL CHECK (amount > 0),
+    currency VARCHAR(3) NOT NULL,
+    description TEXT,
+    category VARCHAR(50),
+    execute_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'SCHEDULED'
+        CHECK (status IN ('SCHEDULED', 'EXECUTED', 'FAILED', 'CANCELLED')),
+    -- Set once run: the transfer booked, or why it couldn't be
+    transaction_id UUID REFERENCES transactions(id),
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_transactions_due
+    ON scheduled_transactions(execute_at) WHERE status = 'SCHEDULED';
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_transactions_user
+    ON scheduled_transactions(user_id);
+
+CREATE TRIGGER scheduled_transactions_touch_updated_at
+    BEFORE UPDATE ON scheduled_transactions
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
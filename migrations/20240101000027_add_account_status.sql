@@ -0,0 +1,6 @@
+-- Frozen accounts keep their funds but can't send or receive money until
+-- unfrozen, e.g. while suspected fraud is looked into
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS status VARCHAR(10) NOT NULL DEFAULT 'ACTIVE'
+    CHECK (status IN ('ACTIVE', 'FROZEN', 'CLOSED'));
+
+UPDATE accounts SET status = 'CLOSED' WHERE closed_at IS NOT NULL;
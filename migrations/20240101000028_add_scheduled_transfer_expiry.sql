@@ -0,0 +1,6 @@
+-- Scheduled transfers that haven't run by expires_at are voided instead
+ALTER TABLE scheduled_transactions ADD COLUMN IF NOT EXISTS expires_at TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE scheduled_transactions DROP CONSTRAINT IF EXISTS scheduled_transactions_status_check;
+ALTER TABLE scheduled_transactions ADD CONSTRAINT scheduled_transactions_status_check
+    CHECK (status IN ('SCHEDULED', 'EXECUTED', 'FAILED', 'CANCELLED', 'EXPIRED'));
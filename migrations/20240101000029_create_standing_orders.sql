@@ -0,0 +1,55 @@
+-- Transfers repeated at a fixed interval. Each occurrence is booked as an
+-- ordinary TRANSFER in `transactions` that points back here; the runs table
+-- keeps every attempt, including the ones that failed.
+CREATE TABLE IF NOT EXISTS standing_orders (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    sender_account_id UUID NOT NULL REFERENCES accounts(id),
+    receiver_account_id UUID NOT NULL REFERENCES accounts(id),
+    amount DECIMAL(19, 4) NOT NULL CHECK (amount > 0),
+    currency VARCHAR(3) NOT NULL,
+    description TEXT,
+    category VARCHAR(50),
+    interval VARCHAR(10) NOT NULL CHECK (interval IN ('DAILY', 'WEEKLY', 'MONTHLY')),
+    -- Occurrences fall on starts_at plus a whole number of intervals, so
+    -- monthly orders keep their day of the month
+    starts_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    -- Last day (UTC) an occurrence may fall on
+    end_date DATE,
+    -- Intervals elapsed since starts_at, including skipped ones
+    occurrence INTEGER NOT NULL DEFAULT 0,
+    next_run_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'ACTIVE'
+        CHECK (status IN ('ACTIVE', 'PAUSED', 'COMPLETED', 'CANCELLED')),
+    -- Why the last occurrence failed; cleared once one succeeds
+    last_error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_standing_orders_due
+    ON standing_orders(next_run_at) WHERE status = 'ACTIVE';
+
+CREATE INDEX IF NOT EXISTS idx_standing_orders_user
+    ON standing_orders(user_id);
+
+CREATE TRIGGER standing_orders_touch_updated_at
+    BEFORE UPDATE ON standing_orders
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+CREATE TABLE IF NOT EXISTS standing_order_runs (
+    id UUID PRIMARY KEY,
+    standing_order_id UUID NOT NULL REFERENCES standing_orders(id) ON DELETE CASCADE,
+    occurrence INTEGER NOT NULL,
+    status VARCHAR(10) NOT NULL CHECK (status IN ('EXECUTED', 'FAILED')),
+    transaction_id UUID REFERENCES transactions(id),
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (standing_order_id, occurrence)
+);
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS standing_order_id UUID REFERENCES standing_orders(id);
+
+CREATE INDEX IF NOT EXISTS idx_transactions_standing_order
+    ON transactions(standing_order_id) WHERE standing_order_id IS NOT NULL;
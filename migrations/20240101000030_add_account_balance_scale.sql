@@ -0,0 +1,18 @@
+-- Accounts for assets like crypto or commodities may hold amounts with more
+-- decimal places than their currency uses. `balance_scale` is the number of
+-- decimal places an account accepts; without one, the currency's applies.
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS balance_scale SMALLINT
+    CHECK (balance_scale BETWEEN 0 AND 8);
+
+-- Store up to 8 decimal places wherever money moves, keeping 15 integer digits
+ALTER TABLE accounts
+    ALTER COLUMN balance TYPE DECIMAL(23, 8),
+    ALTER COLUMN held_amount TYPE DECIMAL(23, 8);
+ALTER TABLE transactions ALTER COLUMN amount TYPE DECIMAL(23, 8);
+ALTER TABLE holds
+    ALTER COLUMN amount TYPE DECIMAL(23, 8),
+    ALTER COLUMN captured_amount TYPE DECIMAL(23, 8);
+ALTER TABLE split_transfers ALTER COLUMN total TYPE DECIMAL(23, 8);
+ALTER TABLE scheduled_transactions ALTER COLUMN amount TYPE DECIMAL(23, 8);
+ALTER TABLE standing_orders ALTER COLUMN amount TYPE DECIMAL(23, 8);
+ALTER TABLE imported_transactions ALTER COLUMN amount TYPE DECIMAL(23, 8);
@@ -0,0 +1,12 @@
+-- Currency codes are stored in uppercase so `usd` and `USD` can't end up as
+-- two different currencies. Rows copy their account's currency, so any
+-- lowercase code is fixed everywhere it was copied to.
+UPDATE accounts SET currency = UPPER(currency) WHERE currency <> UPPER(currency);
+UPDATE transactions SET currency = UPPER(currency) WHERE currency <> UPPER(currency);
+UPDATE holds SET currency = UPPER(currency) WHERE currency <> UPPER(currency);
+UPDATE split_transfers SET currency = UPPER(currency) WHERE currency <> UPPER(currency);
+UPDATE scheduled_transactions SET currency = UPPER(currency) WHERE currency <> UPPER(currency);
+UPDATE standing_orders SET currency = UPPER(currency) WHERE currency <> UPPER(currency);
+
+ALTER TABLE accounts
+    ADD CONSTRAINT accounts_currency_uppercase CHECK (currency = UPPER(currency));
@@ -0,0 +1,6 @@
+-- Users erased on request keep their row, so accounts and transactions still
+-- point at them, but their personal data is replaced with placeholders.
+-- `erased_by` is the administrator who carried out the erasure.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS erased_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN IF NOT EXISTS erased_by UUID REFERENCES users(id);
@@ -0,0 +1,24 @@
+-- Rates used to convert transfers between accounts of different currencies.
+-- `rate` is the number of units of `quote_currency` one unit of
+-- `base_currency` buys; each direction is stored separately.
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    base_currency VARCHAR(3) NOT NULL CHECK (base_currency = UPPER(base_currency)),
+    quote_currency VARCHAR(3) NOT NULL CHECK (quote_currency = UPPER(quote_currency)),
+    rate DECIMAL(24, 12) NOT NULL CHECK (rate > 0),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (base_currency, quote_currency),
+    CHECK (base_currency <> quote_currency)
+);
+
+CREATE TRIGGER exchange_rates_touch_updated_at
+    BEFORE UPDATE ON exchange_rates
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+-- A converted transfer books the amount credited to the receiver in `amount`
+-- and `currency`, like any other transaction seen from the receiving side.
+-- What the sender was debited, and the rate applied, are kept alongside.
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS original_amount DECIMAL(23, 8),
+    ADD COLUMN IF NOT EXISTS original_currency VARCHAR(3),
+    ADD COLUMN IF NOT EXISTS exchange_rate DECIMAL(24, 12);
@@ -0,0 +1,6 @@
+-- Trigram index so case-insensitive substring searches on descriptions
+-- (`description ILIKE '%rent%'`) don't scan the whole table.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_description_trgm
+    ON transactions USING GIN (description gin_trgm_ops);
@@ -0,0 +1,23 @@
+-- Caps on the money an account may send: a maximum per withdrawal or transfer
+-- and a maximum over any rolling 24 hours. NULL means no cap. Accounts get a
+-- row with the configured defaults when they are opened; accounts that existed
+-- before limits did get a row without caps, which keeps their behaviour.
+CREATE TABLE IF NOT EXISTS account_limits (
+    account_id UUID PRIMARY KEY REFERENCES accounts(id) ON DELETE CASCADE,
+    per_transaction_limit DECIMAL(19, 4) CHECK (per_transaction_limit > 0),
+    daily_limit DECIMAL(19, 4) CHECK (daily_limit > 0),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER account_limits_touch_updated_at
+    BEFORE UPDATE ON account_limits
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+INSERT INTO account_limits (account_id)
+SELECT id FROM accounts
+ON CONFLICT (account_id) DO NOTHING;
+
+-- Sums what an account sent recently, for the daily limit
+CREATE INDEX IF NOT EXISTS idx_transactions_sender_created_at
+    ON transactions(sender_account_id, created_at);
@@ -0,0 +1,8 @@
+-- Single-use token emailed at registration to confirm the user's address.
+-- Verifying clears it, so a token can't be used twice.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS verification_token VARCHAR(64),
+    ADD COLUMN IF NOT EXISTS verification_token_expires_at TIMESTAMP WITH TIME ZONE;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_verification_token
+    ON users(verification_token) WHERE verification_token IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Fee charged to the sender of a transfer on top of the amount. The receiver
+-- is credited the amount only; zero for everything that wasn't charged a fee.
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS fee_amount DECIMAL(23, 8) NOT NULL DEFAULT 0
+    CHECK (fee_amount >= 0);
@@ -0,0 +1,18 @@
+-- Fees charged on transfers are booked as FEE transactions from the sender to
+-- the configured fee account. A fee points at the transfer it was charged on.
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transactions_transaction_type_check;
+ALTER TABLE transactions ADD CONSTRAINT transactions_transaction_type_check
+    CHECK (transaction_type IN ('TRANSFER', 'DEPOSIT', 'WITHDRAWAL', 'FEE'));
+
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transaction_not_self;
+ALTER TABLE transactions ADD CONSTRAINT transaction_not_self CHECK (
+    (transaction_type IN ('TRANSFER', 'FEE') AND sender_account_id IS NOT NULL AND receiver_account_id IS NOT NULL AND sender_account_id != receiver_account_id) OR
+    (transaction_type = 'DEPOSIT' AND sender_account_id IS NULL AND receiver_account_id IS NOT NULL) OR
+    (transaction_type = 'WITHDRAWAL' AND sender_account_id IS NOT NULL AND receiver_account_id IS NULL)
+);
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS parent_transaction_id UUID REFERENCES transactions(id);
+
+CREATE INDEX IF NOT EXISTS idx_transactions_parent
+    ON transactions(parent_transaction_id) WHERE parent_transaction_id IS NOT NULL;
@@ -0,0 +1,14 @@
+-- The balance of an account right after each change, with the transaction that
+-- caused it. Rows are written in the same database transaction as the balance
+-- update. clock_timestamp() keeps the changes of one database transaction,
+-- like a transfer and the fee charged on it, in the order they happened.
+CREATE TABLE IF NOT EXISTS account_balance_snapshots (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    transaction_id UUID REFERENCES transactions(id) ON DELETE CASCADE,
+    balance DECIMAL(23, 8) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT clock_timestamp()
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_balance_snapshots_account_created_at
+    ON account_balance_snapshots(account_id, created_at, id);
@@ -0,0 +1,11 @@
+-- Caps on what an account may withdraw and transfer out per calendar day in
+-- UTC, each counted on its own. NULL means no cap.
+ALTER TABLE account_limits
+    ADD COLUMN IF NOT EXISTS daily_withdrawal_limit DECIMAL(19, 4)
+        CHECK (daily_withdrawal_limit > 0),
+    ADD COLUMN IF NOT EXISTS daily_transfer_limit DECIMAL(19, 4)
+        CHECK (daily_transfer_limit > 0);
+
+-- Sums what an account sent today, by type
+CREATE INDEX IF NOT EXISTS idx_transactions_sender_type_created_at
+    ON transactions(sender_account_id, transaction_type, created_at);
@@ -0,0 +1,8 @@
+-- Optional two-factor authentication with time-based one-time passwords.
+-- The secret is stored when setup starts; 2FA is only enabled once a code
+-- from it is confirmed. The last accepted time step keeps a code from being
+-- used twice.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS totp_secret VARCHAR(64),
+    ADD COLUMN IF NOT EXISTS totp_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS totp_last_step BIGINT;
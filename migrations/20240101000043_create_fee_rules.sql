@@ -0,0 +1,35 @@
+-- Fees charged on top of transfers and withdrawals: `flat_amount` plus
+-- `percent` of the amount, in the currency of the paying account. A rule
+-- without a currency applies to every currency without a rule of its own.
+CREATE TABLE IF NOT EXISTS fee_rules (
+    id UUID PRIMARY KEY,
+    transaction_type VARCHAR(20) NOT NULL CHECK (transaction_type IN ('TRANSFER', 'WITHDRAWAL')),
+    currency VARCHAR(3) CHECK (currency = UPPER(currency)),
+    flat_amount DECIMAL(23, 8) NOT NULL DEFAULT 0 CHECK (flat_amount >= 0),
+    percent DECIMAL(7, 4) NOT NULL DEFAULT 0 CHECK (percent >= 0 AND percent <= 100),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_fee_rules_type_currency
+    ON fee_rules (transaction_type, COALESCE(currency, ''));
+
+CREATE TRIGGER fee_rules_touch_updated_at
+    BEFORE UPDATE ON fee_rules
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+-- The currency `fee_amount` is in, which is the payer's; NULL without a fee.
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS fee_currency VARCHAR(3);
+
+UPDATE transactions SET fee_currency = COALESCE(original_currency, currency)
+WHERE fee_amount > 0 AND fee_currency IS NULL;
+
+-- Without a fee account, a FEE transaction has no receiver: like a
+-- withdrawal, the fee leaves the ledger.
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transaction_not_self;
+ALTER TABLE transactions ADD CONSTRAINT transaction_not_self CHECK (
+    (transaction_type = 'TRANSFER' AND sender_account_id IS NOT NULL AND receiver_account_id IS NOT NULL AND sender_account_id != receiver_account_id) OR
+    (transaction_type = 'FEE' AND sender_account_id IS NOT NULL AND sender_account_id IS DISTINCT FROM receiver_account_id) OR
+    (transaction_type = 'DEPOSIT' AND sender_account_id IS NULL AND receiver_account_id IS NOT NULL) OR
+    (transaction_type = 'WITHDRAWAL' AND sender_account_id IS NOT NULL AND receiver_account_id IS NULL)
+);
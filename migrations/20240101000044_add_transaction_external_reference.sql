@@ -0,0 +1,6 @@
+-- Identifier an external processor gave a deposit or withdrawal, so its
+-- records can be matched with ours. At most one transaction per reference.
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS external_reference VARCHAR(255);
+
+ALTER TABLE transactions ADD CONSTRAINT transactions_external_reference_key
+    UNIQUE (external_reference);
@@ -0,0 +1,12 @@
+-- A refunded fee is booked as a FEE transaction in the opposite direction,
+-- from the fee account back to the payer, pointing at the fee it refunds
+-- through reverses_transaction_id. A fee that left the ledger comes back
+-- into it: the refund has no sender, like a deposit.
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transaction_not_self;
+ALTER TABLE transactions ADD CONSTRAINT transaction_not_self CHECK (
+    (transaction_type = 'TRANSFER' AND sender_account_id IS NOT NULL AND receiver_account_id IS NOT NULL AND sender_account_id != receiver_account_id) OR
+    (transaction_type = 'FEE' AND sender_account_id IS NOT NULL AND sender_account_id IS DISTINCT FROM receiver_account_id) OR
+    (transaction_type = 'FEE' AND sender_account_id IS NULL AND receiver_account_id IS NOT NULL) OR
+    (transaction_type = 'DEPOSIT' AND sender_account_id IS NULL AND receiver_account_id IS NOT NULL) OR
+    (transaction_type = 'WITHDRAWAL' AND sender_account_id IS NOT NULL AND receiver_account_id IS NULL)
+);
@@ -0,0 +1,18 @@
+-- The statement generated over an account's whole lifetime when it is closed.
+-- Written in the same database transaction as the closure, so a closed account
+-- always has one, and never changed afterwards.
+CREATE TABLE IF NOT EXISTS final_statements (
+    account_id UUID PRIMARY KEY REFERENCES accounts(id),
+    statement JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE OR REPLACE FUNCTION reject_final_statement_changes() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'final statements are immutable';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER final_statements_immutable
+    BEFORE UPDATE OR DELETE ON final_statements
+    FOR EACH ROW EXECUTE FUNCTION reject_final_statement_changes();
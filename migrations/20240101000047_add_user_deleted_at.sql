@@ -0,0 +1,3 @@
+-- Deactivated users keep their row, accounts and history but can't sign in
+-- until an administrator reactivates them; unlike erasure this is reversible.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
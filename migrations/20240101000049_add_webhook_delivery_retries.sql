@@ -0,0 +1,29 @@
+-- Failed deliveries are retried on a schedule by a background worker instead
+-- of in memory, so they survive restarts. `next_attempt_at` is when a PENDING
+-- delivery is tried next; deliveries out of retries are DEAD_LETTERED and only
+-- sent again on request.
+ALTER TABLE webhook_deliveries ADD COLUMN IF NOT EXISTS next_attempt_at TIMESTAMP WITH TIME ZONE;
+
+UPDATE webhook_deliveries SET status = 'DEAD_LETTERED' WHERE status = 'FAILED';
+ALTER TABLE webhook_deliveries DROP CONSTRAINT IF EXISTS webhook_deliveries_status_check;
+ALTER TABLE webhook_deliveries ADD CONSTRAINT webhook_deliveries_status_check
+    CHECK (status IN ('PENDING', 'DELIVERED', 'DEAD_LETTERED'));
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_next_attempt_at
+    ON webhook_deliveries(next_attempt_at) WHERE status = 'PENDING';
+
+-- Every attempt at a delivery, with how the endpoint answered and how long it
+-- took; `response_status` is NULL when no answer came, and `error` is NULL
+-- for accepted attempts.
+CREATE TABLE IF NOT EXISTS webhook_delivery_attempts (
+    id UUID PRIMARY KEY,
+    delivery_id UUID NOT NULL REFERENCES webhook_deliveries(id) ON DELETE CASCADE,
+    attempt INTEGER NOT NULL,
+    response_status INTEGER,
+    latency_ms INTEGER NOT NULL,
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_delivery_attempts_delivery_id
+    ON webhook_delivery_attempts(delivery_id, attempt);
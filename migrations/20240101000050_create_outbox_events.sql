@@ -0,0 +1,17 @@
+-- Events recorded in the same database transaction as the change they
+-- describe, so none is lost if the server stops right after a commit. A
+-- background task hands rows with no `published_at` to the event sink and
+-- then stamps them; clearing `published_at` publishes an event again.
+CREATE TABLE IF NOT EXISTS outbox_events (
+    id UUID PRIMARY KEY,
+    event_type VARCHAR(64) NOT NULL,
+    aggregate_id UUID NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    published_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_outbox_events_unpublished
+    ON outbox_events(created_at) WHERE published_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_outbox_events_created_at
+    ON outbox_events(created_at);
@@ -0,0 +1,7 @@
+-- Categories may be up to 64 characters, wherever one is stored. Deposits can
+-- now be categorized too, e.g. as salary.
+ALTER TABLE transactions ALTER COLUMN category TYPE VARCHAR(64);
+ALTER TABLE budgets ALTER COLUMN category TYPE VARCHAR(64);
+ALTER TABLE imported_transactions ALTER COLUMN category TYPE VARCHAR(64);
+ALTER TABLE scheduled_transactions ALTER COLUMN category TYPE VARCHAR(64);
+ALTER TABLE standing_orders ALTER COLUMN category TYPE VARCHAR(64);
@@ -1,55 +1,198 @@
+use crate::api::export::csv_attachment;
+use crate::api::transactions::TransactionSummaryParams;
 use crate::middleware::auth::AuthUser;
-use crate::models::account::AccountResponse;
+use crate::models::account::{AccountLimits, AccountMergeResponse, AccountResponse, AccountStatus};
+use crate::models::balance_history::{BalanceHistoryParams, BalanceSnapshotResponse};
+use crate::models::budget::{Budget, BudgetStatus, SetBudgetRequest};
+use crate::models::import::{ImportParams, ImportSummary};
+use crate::models::statement::{
+    AccountStatement, FinalStatement, StatementParams, StatementPeriod,
+};
+use crate::models::transaction::{
+    CategorySpending, SpendingReport, TransactionExportParams, TransactionFilter,
+    ACCOUNT_TRANSACTION_CSV_HEADER,
+};
+use crate::models::webhook::{WebhookPayload, TRANSACTION_COMPLETED};
 use crate::services::account_service::AccountService;
+use crate::services::budget_service::BudgetService;
+use crate::services::import_service::ImportService;
+use crate::services::transaction_service::TransactionService;
+use crate::utils::currency::{to_display_scale, validate_currency};
 use crate::utils::error::AppError;
-use crate::utils::response::ApiResponse;
+use crate::utils::events::{FeedItem, TransactionFeed};
+use crate::utils::name_match::NameMatch;
+use crate::utils::response::{select_fields, ApiResponse, PaginatedResponse, Sparse, TotalCount};
 use axum::{
-    extract::{Json, Path, State},
-    routing::{get, post},
+    extract::{Json, Multipart, Path, Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+    response::Response,
+    routing::{delete, get, post, put},
     Extension, Router,
 };
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 use validator::Validate;
 
-pub fn account_routes(account_service: Arc<AccountService>) -> Router {
+type BudgetState = (Arc<AccountService>, Arc<BudgetService>);
+type ImportState = (Arc<AccountService>, Arc<ImportService>);
+type LedgerState = (Arc<AccountService>, Arc<TransactionService>);
+type FeedState = (Arc<AccountService>, TransactionFeed);
+
+/// Time between heartbeat comments on an account event stream, so proxies
+/// don't close idle connections
+const EVENT_STREAM_HEARTBEAT: Duration = Duration::from_secs(15);
+/// Event telling an event stream's client that it missed events
+const EVENTS_LAGGED: &str = "events.lagged";
+
+pub fn account_routes(
+    account_service: Arc<AccountService>,
+    budget_service: Arc<BudgetService>,
+    import_service: Arc<ImportService>,
+    transaction_service: Arc<TransactionService>,
+    feed: TransactionFeed,
+) -> Router {
+    let budget_routes = Router::new()
+        .route("/:id/budgets", post(set_budget))
+        .route("/:id/budgets/status", get(get_budget_status))
+        .with_state((account_service.clone(), budget_service));
+    let import_routes = Router::new()
+        .route("/:id/transactions/import", post(import_transactions))
+        .with_state((account_service.clone(), import_service));
+    let ledger_routes = Router::new()
+        .route("/:id/transactions/export", get(export_account_transactions))
+        .route("/:id/spending", get(get_spending_by_category))
+        .route("/:id/statement", get(get_account_statement))
+        .with_state((account_service.clone(), transaction_service));
+    let feed_routes = Router::new()
+        .route("/:id/events", get(stream_account_events))
+        .with_state((account_service.clone(), feed));
+
     Router::new()
         .route("/", get(get_user_accounts))
         .route("/", post(create_account))
         .route("/:id", get(get_account))
+        .route("/:id", delete(close_account))
+        .route("/:id/name-check", post(check_account_name))
+        .route("/:id/freeze", post(freeze_account))
+        .route("/:id/unfreeze", post(unfreeze_account))
+        .route("/:id/limits", get(get_account_limits))
+        .route("/:id/limits", put(update_account_limits))
+        .route("/:id/final-statement", get(get_final_statement))
+        .route("/:id/balance-history", get(get_balance_history))
+        .with_state(account_service)
+        .merge(budget_routes)
+        .merge(import_routes)
+        .merge(ledger_routes)
+        .merge(feed_routes)
+}
+
+/// Administrator routes cleaning up accounts, relative to `/admin`
+pub fn account_admin_routes(account_service: Arc<AccountService>) -> Router {
+    Router::new()
+        .route("/accounts/:id/merge", post(merge_account))
+        .route("/accounts/:id/limits", put(set_account_limits))
         .with_state(account_service)
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate, Clone)]
 pub struct CreateAccountRequest {
-    #[validate(length(min = 3, max = 3, message = "Currency must be a 3-letter code"))]
+    /// ISO 4217 currency code; stored in uppercase
+    #[validate(custom = "validate_currency")]
     pub currency: String,
+    /// Decimal places the account accepts, up to 8; defaults to the currency's
+    pub balance_scale: Option<u32>,
+}
+
+/// A sender's claim about who owns an account (confirmation of payee)
+#[derive(Debug, Serialize, Deserialize, Validate, Clone)]
+pub struct NameCheckRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: String,
+}
+
+/// Result of a name check; deliberately omits the owner's actual name
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NameCheckResponse {
+    pub result: NameMatch,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountListParams {
+    /// Maximum number of accounts to return (defaults to 100)
+    pub limit: Option<i64>,
+    /// Number of accounts to skip (defaults to 0)
+    pub offset: Option<i64>,
+    /// Comma-separated list of fields to include in each item
+    pub fields: Option<String>,
+    /// Whether closed accounts are listed too
+    #[serde(default)]
+    pub include_closed: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeParams {
+    /// The account the merged account's transactions and balance move to
+    pub into: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountParams {
+    /// Return the balance held at this time (RFC 3339) instead of the current one
+    pub as_of: Option<DateTime<Utc>>,
 }
 
 async fn get_user_accounts(
     Extension(auth_user): Extension<AuthUser>,
     State(account_service): State<Arc<AccountService>>,
-) -> Result<Json<ApiResponse<Vec<AccountResponse>>>, AppError> {
-    // Get all accounts for the authenticated user
+    Query(params): Query<AccountListParams>,
+) -> Result<
+    (
+        Extension<TotalCount>,
+        Json<ApiResponse<PaginatedResponse<Sparse<AccountResponse>>>>,
+    ),
+    AppError,
+> {
+    let limit = params.limit.unwrap_or(100).max(1);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    // Get all accounts for the authenticated user; there are few enough to page in memory
     let accounts = account_service
-        .get_accounts_by_user_id(auth_user.user_id)
+        .list_accounts_by_user_id(auth_user.user_id, params.include_closed)
         .await?;
+    let total = accounts.len() as i64;
+    let accounts: Vec<AccountResponse> = accounts
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+    let accounts = select_fields(accounts, params.fields.as_deref())?;
 
     // Return success response
-    Ok(Json(ApiResponse::success(
-        "Accounts retrieved successfully",
-        accounts,
-    )))
+    Ok((
+        Extension(TotalCount(total)),
+        Json(ApiResponse::paginated(
+            "Accounts retrieved successfully",
+            accounts,
+            total,
+            limit,
+            offset,
+        )),
+    ))
 }
 
 async fn get_account(
     Extension(auth_user): Extension<AuthUser>,
     State(account_service): State<Arc<AccountService>>,
     Path(id): Path<Uuid>,
+    Query(params): Query<AccountParams>,
 ) -> Result<Json<ApiResponse<AccountResponse>>, AppError> {
-    // Get the account
-    let account = account_service.get_account_by_id(id).await?;
+    // Get the account, with its historical balance if one was asked for
+    let account = account_service.get_account_as_of(id, params.as_of).await?;
 
     // Verify the account belongs to the authenticated user
     if account.user_id != auth_user.user_id {
@@ -65,6 +208,81 @@ async fn get_account(
     )))
 }
 
+/// Reports one calendar month, or any other period, of the caller's account
+async fn get_account_statement(
+    Extension(auth_user): Extension<AuthUser>,
+    State((account_service, transaction_service)): State<LedgerState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<StatementParams>,
+) -> Result<Json<ApiResponse<AccountStatement>>, AppError> {
+    ensure_account_owner(&account_service, &auth_user, id).await?;
+
+    let statement = match params.period()? {
+        StatementPeriod::Month { year, month } => {
+            account_service.get_statement(id, year, month).await?
+        }
+        StatementPeriod::Range { from, to } => {
+            transaction_service.generate_statement(id, from, to).await?
+        }
+    };
+
+    Ok(Json(ApiResponse::success(
+        "Statement retrieved successfully",
+        statement,
+    )))
+}
+
+/// Reports the whole lifetime of one of the caller's closed accounts
+async fn get_final_statement(
+    Extension(auth_user): Extension<AuthUser>,
+    State(account_service): State<Arc<AccountService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<FinalStatement>>, AppError> {
+    ensure_account_owner(&account_service, &auth_user, id).await?;
+
+    let statement = account_service.get_final_statement(id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Final statement retrieved successfully",
+        statement,
+    )))
+}
+
+/// Lists the balance of the caller's account after each change
+async fn get_balance_history(
+    Extension(auth_user): Extension<AuthUser>,
+    State(account_service): State<Arc<AccountService>>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<BalanceHistoryParams>,
+) -> Result<Json<ApiResponse<Vec<BalanceSnapshotResponse>>>, AppError> {
+    ensure_account_owner(&account_service, &auth_user, id).await?;
+
+    let history = account_service
+        .get_balance_history(id, params.from, params.to)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Balance history retrieved successfully",
+        history,
+    )))
+}
+
+/// Closes one of the caller's accounts; closing it again is a no-op
+async fn close_account(
+    Extension(auth_user): Extension<AuthUser>,
+    State(account_service): State<Arc<AccountService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<AccountResponse>>, AppError> {
+    ensure_account_owner(&account_service, &auth_user, id).await?;
+
+    let account = account_service.close_account(id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Account closed successfully",
+        account,
+    )))
+}
+
 async fn create_account(
     Extension(auth_user): Extension<AuthUser>,
     State(account_service): State<Arc<AccountService>>,
@@ -77,7 +295,7 @@ async fn create_account(
 
     // Create new account for the authenticated user
     let account = account_service
-        .create_account(auth_user.user_id, request.currency)
+        .create_account_with_scale(auth_user.user_id, request.currency, request.balance_scale)
         .await?;
 
     // Return success response
@@ -86,3 +304,301 @@ async fn create_account(
         account,
     )))
 }
+
+async fn check_account_name(
+    State(account_service): State<Arc<AccountService>>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<NameCheckRequest>,
+) -> Result<Json<ApiResponse<NameCheckResponse>>, AppError> {
+    // Validate request data
+    request
+        .validate()
+        .map_err(|e| AppError::Validation(format!("Invalid name check data: {}", e)))?;
+
+    // Any authenticated user may check a payee; only the verdict is returned
+    let result = account_service
+        .check_account_name(id, &request.name)
+        .await?;
+
+    // Return success response
+    Ok(Json(ApiResponse::success(
+        "Name check completed",
+        NameCheckResponse { result },
+    )))
+}
+
+async fn merge_account(
+    State(account_service): State<Arc<AccountService>>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<MergeParams>,
+) -> Result<Json<ApiResponse<AccountMergeResponse>>, AppError> {
+    let merge = account_service.merge_accounts(id, params.into).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Accounts merged successfully",
+        merge,
+    )))
+}
+
+async fn get_account_limits(
+    Extension(auth_user): Extension<AuthUser>,
+    State(account_service): State<Arc<AccountService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<AccountLimits>>, AppError> {
+    ensure_account_owner(&account_service, &auth_user, id).await?;
+
+    let limits = account_service.get_limits(id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Account limits retrieved successfully",
+        limits,
+    )))
+}
+
+/// Replaces an account's limits; administrators only, like the admin route
+async fn update_account_limits(
+    Extension(auth_user): Extension<AuthUser>,
+    State(account_service): State<Arc<AccountService>>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AccountLimits>,
+) -> Result<Json<ApiResponse<AccountLimits>>, AppError> {
+    if !auth_user.is_admin() {
+        return Err(AppError::Forbidden(
+            "Only administrators can change account limits".to_string(),
+        ));
+    }
+
+    set_account_limits(State(account_service), Path(id), Json(request)).await
+}
+
+async fn set_account_limits(
+    State(account_service): State<Arc<AccountService>>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AccountLimits>,
+) -> Result<Json<ApiResponse<AccountLimits>>, AppError> {
+    let limits = account_service.set_limits(id, request).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Account limits updated successfully",
+        limits,
+    )))
+}
+
+/// Suspends an account during a fraud investigation; administrators only
+async fn freeze_account(
+    Extension(auth_user): Extension<AuthUser>,
+    State(account_service): State<Arc<AccountService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<AccountResponse>>, AppError> {
+    let account =
+        set_account_status(&account_service, &auth_user, id, AccountStatus::FROZEN).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Account frozen successfully",
+        account,
+    )))
+}
+
+/// Lets a frozen account move money again; administrators only
+async fn unfreeze_account(
+    Extension(auth_user): Extension<AuthUser>,
+    State(account_service): State<Arc<AccountService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<AccountResponse>>, AppError> {
+    let account =
+        set_account_status(&account_service, &auth_user, id, AccountStatus::ACTIVE).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Account unfrozen successfully",
+        account,
+    )))
+}
+
+async fn set_account_status(
+    account_service: &AccountService,
+    auth_user: &AuthUser,
+    id: Uuid,
+    status: AccountStatus,
+) -> Result<AccountResponse, AppError> {
+    if !auth_user.is_admin() {
+        return Err(AppError::Forbidden(
+            "Only administrators can freeze or unfreeze accounts".to_string(),
+        ));
+    }
+    account_service.set_account_status(id, status).await
+}
+
+/// Rejects callers who don't own the account
+async fn ensure_account_owner(
+    account_service: &AccountService,
+    auth_user: &AuthUser,
+    account_id: Uuid,
+) -> Result<(), AppError> {
+    let account = account_service.get_account_by_id(account_id).await?;
+    if account.user_id != auth_user.user_id {
+        return Err(AppError::Forbidden(
+            "You don't have permission to access this account".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn set_budget(
+    Extension(auth_user): Extension<AuthUser>,
+    State((account_service, budget_service)): State<BudgetState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SetBudgetRequest>,
+) -> Result<Json<ApiResponse<Budget>>, AppError> {
+    ensure_account_owner(&account_service, &auth_user, id).await?;
+
+    let budget = budget_service.set_budget(id, request).await?;
+
+    // Return success response
+    Ok(Json(ApiResponse::success(
+        "Budget saved successfully",
+        budget,
+    )))
+}
+
+async fn get_budget_status(
+    Extension(auth_user): Extension<AuthUser>,
+    State((account_service, budget_service)): State<BudgetState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<BudgetStatus>>>, AppError> {
+    ensure_account_owner(&account_service, &auth_user, id).await?;
+
+    // Spend in the current period of each budget
+    let statuses = budget_service
+        .get_budget_status(id, Utc::now().date_naive())
+        .await?;
+
+    // Return success response
+    Ok(Json(ApiResponse::success(
+        "Budget status retrieved successfully",
+        statuses,
+    )))
+}
+
+async fn import_transactions(
+    Extension(auth_user): Extension<AuthUser>,
+    State((account_service, import_service)): State<ImportState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<ImportParams>,
+    mut multipart: Multipart,
+) -> Result<Json<ApiResponse<ImportSummary>>, AppError> {
+    ensure_account_owner(&account_service, &auth_user, id).await?;
+
+    // The CSV is the `file` part; it is parsed as it arrives, never buffered whole
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Invalid multipart body: {}", e)))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let chunks =
+            field.map_err(|e| AppError::BadRequest(format!("Invalid multipart body: {}", e)));
+        let summary = import_service
+            .import_csv(id, params.session_id, chunks)
+            .await?;
+
+        return Ok(Json(ApiResponse::success("Import processed", summary)));
+    }
+
+    Err(AppError::BadRequest(
+        "Multipart body has no 'file' part".to_string(),
+    ))
+}
+
+async fn export_account_transactions(
+    Extension(auth_user): Extension<AuthUser>,
+    State((account_service, transaction_service)): State<LedgerState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<TransactionExportParams>,
+) -> Result<Response, AppError> {
+    ensure_account_owner(&account_service, &auth_user, id).await?;
+
+    let (from, to) = params.time_range()?;
+    let filter = TransactionFilter {
+        from,
+        to,
+        ..TransactionFilter::default()
+    };
+    filter.validate()?;
+
+    csv_attachment(
+        &format!("account-{}-transactions.csv", id),
+        &ACCOUNT_TRANSACTION_CSV_HEADER,
+        transaction_service,
+        move |transaction_service| {
+            transaction_service
+                .stream_transactions_by_account_id(id, &filter)
+                .map_ok(move |transaction| transaction.to_account_csv_record(id))
+                .boxed()
+        },
+    )
+}
+
+async fn get_spending_by_category(
+    Extension(auth_user): Extension<AuthUser>,
+    State((account_service, transaction_service)): State<LedgerState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<TransactionSummaryParams>,
+) -> Result<Json<ApiResponse<SpendingReport>>, AppError> {
+    ensure_account_owner(&account_service, &auth_user, id).await?;
+
+    let account = account_service.get_account_by_id(id).await?;
+    let categories = transaction_service
+        .get_spending_by_category(id, params.from, params.to)
+        .await?
+        .into_iter()
+        .map(|spending| CategorySpending {
+            total: to_display_scale(spending.total, &account.currency),
+            ..spending
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::success(
+        "Spending retrieved successfully",
+        SpendingReport {
+            account_id: id,
+            currency: account.currency,
+            from: params.from,
+            to: params.to,
+            categories,
+        },
+    )))
+}
+
+/// Streams the account's completed transactions as server-sent events
+///
+/// Each `transaction.completed` event carries the webhook payload as JSON, with
+/// the transaction ID as the event ID. A client that falls behind gets an
+/// `events.lagged` event with the number of events it missed, or is
+/// disconnected, as `EVENT_STREAM_LAG` says; either way it should catch up
+/// through the account's transaction history.
+async fn stream_account_events(
+    Extension(auth_user): Extension<AuthUser>,
+    State((account_service, feed)): State<FeedState>,
+    Path(id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, AppError> {
+    ensure_account_owner(&account_service, &auth_user, id).await?;
+
+    let events = feed.subscribe(id).map(|item| match item {
+        FeedItem::Completed(transaction) => Event::default()
+            .event(TRANSACTION_COMPLETED)
+            .id(transaction.id.to_string())
+            .json_data(WebhookPayload::transaction_completed(&transaction)),
+        FeedItem::Lagged(skipped) => Event::default()
+            .event(EVENTS_LAGGED)
+            .json_data(json!({ "skipped": skipped })),
+    });
+
+    Ok(Sse::new(events).keep_alive(
+        KeepAlive::new()
+            .interval(EVENT_STREAM_HEARTBEAT)
+            .text("heartbeat"),
+    ))
+}
@@ -0,0 +1,155 @@
+use crate::api::export::csv_attachment;
+use crate::api::transactions::TransactionQueryParams;
+use crate::models::integrity::IntegrityReport;
+use crate::models::transaction::{
+    AdminTransactionResponse, TransactionExportParams, TransactionResponse, TRANSACTION_CSV_HEADER,
+};
+use crate::models::usage::{UsageQueryParams, UsageRecord};
+use crate::services::integrity_service::IntegrityService;
+use crate::services::transaction_service::TransactionService;
+use crate::services::usage_service::UsageService;
+use crate::utils::error::AppError;
+use crate::utils::response::{
+    pagination_links, select_fields, ApiResponse, PaginatedResponse, Sparse, TotalCount,
+};
+use axum::{
+    extract::{Json, OriginalUri, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::Response,
+    routing::get,
+    Extension, Router,
+};
+use chrono::Utc;
+use futures_util::{StreamExt, TryStreamExt};
+use std::sync::Arc;
+use uuid::Uuid;
+
+type AdminState = (
+    Arc<TransactionService>,
+    Arc<IntegrityService>,
+    Arc<UsageService>,
+);
+
+/// Routes reserved for administrators
+///
+/// Callers must layer `auth_middleware` and `require_admin` on the result.
+pub fn admin_routes(
+    transaction_service: Arc<TransactionService>,
+    integrity_service: Arc<IntegrityService>,
+    usage_service: Arc<UsageService>,
+) -> Router {
+    Router::new()
+        .route("/transactions", get(list_all_transactions))
+        .route("/transactions/stream.csv", get(export_transactions_csv))
+        .route("/transactions/:id", get(get_transaction_detail))
+        .route("/integrity/latest", get(get_latest_integrity_report))
+        .route("/usage", get(get_usage))
+        .with_state((transaction_service, integrity_service, usage_service))
+}
+
+/// Lists the transactions of every user, with the same filters as an account's listing
+async fn list_all_transactions(
+    State((transaction_service, _, _)): State<AdminState>,
+    OriginalUri(uri): OriginalUri,
+    Query(params): Query<TransactionQueryParams>,
+) -> Result<
+    (
+        HeaderMap,
+        Extension<TotalCount>,
+        Json<ApiResponse<PaginatedResponse<Sparse<TransactionResponse>>>>,
+    ),
+    AppError,
+> {
+    if params.cursor.is_some() {
+        return Err(AppError::BadRequest(
+            "This listing pages with limit and offset, not a cursor".to_string(),
+        ));
+    }
+
+    let limit = params.limit.unwrap_or(100).max(1);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let filter = params.filter()?;
+    let transactions = transaction_service
+        .list_all_transactions(Some(limit), Some(offset), &filter)
+        .await?;
+    let transactions = select_fields(transactions, params.fields.as_deref())?;
+    let total = transaction_service.count_all_transactions(&filter).await?;
+
+    let mut headers = HeaderMap::new();
+    let links = pagination_links(&uri, limit, offset, total);
+    headers.insert(
+        header::LINK,
+        HeaderValue::from_str(&links)
+            .map_err(|e| AppError::Internal(format!("Invalid Link header: {}", e)))?,
+    );
+
+    Ok((
+        headers,
+        Extension(TotalCount(total)),
+        Json(ApiResponse::paginated(
+            "Transactions retrieved successfully",
+            transactions,
+            total,
+            limit,
+            offset,
+        )),
+    ))
+}
+
+async fn get_transaction_detail(
+    State((transaction_service, _, _)): State<AdminState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<AdminTransactionResponse>>, AppError> {
+    // Includes the originating IP and user agent, which regular users never see
+    let transaction = transaction_service.get_transaction_with_origin(id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Transaction retrieved successfully",
+        transaction,
+    )))
+}
+
+async fn export_transactions_csv(
+    State((transaction_service, _, _)): State<AdminState>,
+    Query(params): Query<TransactionExportParams>,
+) -> Result<Response, AppError> {
+    let (start, end) = params.time_range()?;
+
+    csv_attachment(
+        "transactions.csv",
+        &TRANSACTION_CSV_HEADER,
+        transaction_service,
+        move |transaction_service| {
+            transaction_service
+                .stream_transactions(start, end)
+                .map_ok(|transaction| transaction.to_csv_record())
+                .boxed()
+        },
+    )
+}
+
+async fn get_latest_integrity_report(
+    State((_, integrity_service, _)): State<AdminState>,
+) -> Result<Json<ApiResponse<IntegrityReport>>, AppError> {
+    let report = integrity_service.latest_report().await?;
+
+    Ok(Json(ApiResponse::success(
+        "Integrity report retrieved successfully",
+        report,
+    )))
+}
+
+async fn get_usage(
+    State((_, _, usage_service)): State<AdminState>,
+    Query(params): Query<UsageQueryParams>,
+) -> Result<Json<ApiResponse<Vec<UsageRecord>>>, AppError> {
+    // Usage of every user, unless narrowed down to one
+    let (from, to) = params.date_range(Utc::now().date_naive())?;
+    let usage = usage_service.get_usage(params.user_id, from, to).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Usage retrieved successfully",
+        usage,
+    )))
+}
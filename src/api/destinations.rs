@@ -0,0 +1,82 @@
+use crate::middleware::auth::AuthUser;
+use crate::models::destination::{DestinationResponse, RegisterDestinationRequest};
+use crate::services::destination_service::DestinationService;
+use crate::utils::error::AppError;
+use crate::utils::response::ApiResponse;
+use axum::{
+    extract::{Json, Path, State},
+    routing::{delete, get, post},
+    Extension, Router,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Routes managing the caller's withdrawal destinations
+///
+/// Callers must layer `auth_middleware` on the result.
+pub fn destination_routes(destination_service: Arc<DestinationService>) -> Router {
+    Router::new()
+        .route("/", get(list_destinations))
+        .route("/", post(register_destination))
+        .route("/:id", delete(remove_destination))
+        .with_state(destination_service)
+}
+
+/// Administrator routes approving destinations, relative to `/admin`
+pub fn destination_admin_routes(destination_service: Arc<DestinationService>) -> Router {
+    Router::new()
+        .route("/destinations/:id/verify", post(verify_destination))
+        .with_state(destination_service)
+}
+
+async fn list_destinations(
+    Extension(auth_user): Extension<AuthUser>,
+    State(destination_service): State<Arc<DestinationService>>,
+) -> Result<Json<ApiResponse<Vec<DestinationResponse>>>, AppError> {
+    let destinations = destination_service.list(auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Destinations retrieved successfully",
+        destinations,
+    )))
+}
+
+async fn register_destination(
+    Extension(auth_user): Extension<AuthUser>,
+    State(destination_service): State<Arc<DestinationService>>,
+    Json(request): Json<RegisterDestinationRequest>,
+) -> Result<Json<ApiResponse<DestinationResponse>>, AppError> {
+    let destination = destination_service
+        .register(auth_user.user_id, request)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Destination registered successfully",
+        destination,
+    )))
+}
+
+async fn remove_destination(
+    Extension(auth_user): Extension<AuthUser>,
+    State(destination_service): State<Arc<DestinationService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    destination_service.remove(auth_user.user_id, id).await?;
+
+    Ok(Json(ApiResponse::<()>::success_no_data(
+        "Destination removed successfully",
+    )))
+}
+
+async fn verify_destination(
+    Extension(auth_user): Extension<AuthUser>,
+    State(destination_service): State<Arc<DestinationService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<DestinationResponse>>, AppError> {
+    let destination = destination_service.verify(auth_user.user_id, id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Destination verified successfully",
+        destination,
+    )))
+}
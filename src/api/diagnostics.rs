@@ -0,0 +1,60 @@
+use crate::models::diagnostics::{
+    LatencyBucket, LatencyParams, ThroughputParams, ThroughputReport, DEFAULT_LATENCY_WINDOW_HOURS,
+};
+use crate::services::diagnostics_service::DiagnosticsService;
+use crate::utils::error::AppError;
+use crate::utils::response::ApiResponse;
+use axum::{
+    extract::{Json, Query, State},
+    routing::get,
+    Router,
+};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+
+/// Longest window the latency histogram may cover
+const MAX_LATENCY_WINDOW_HOURS: i64 = 24 * 30;
+
+/// Administrator routes describing how the system performs, relative to `/admin`
+pub fn diagnostics_admin_routes(diagnostics_service: Arc<DiagnosticsService>) -> Router {
+    Router::new()
+        .route("/diagnostics/latency", get(get_latency_histogram))
+        .route("/diagnostics/throughput", get(get_throughput))
+        .with_state(diagnostics_service)
+}
+
+async fn get_latency_histogram(
+    State(diagnostics_service): State<Arc<DiagnosticsService>>,
+    Query(params): Query<LatencyParams>,
+) -> Result<Json<ApiResponse<Vec<LatencyBucket>>>, AppError> {
+    let hours = params.hours.unwrap_or(DEFAULT_LATENCY_WINDOW_HOURS);
+    if !(1..=MAX_LATENCY_WINDOW_HOURS).contains(&hours) {
+        return Err(AppError::BadRequest(format!(
+            "hours must be between 1 and {}",
+            MAX_LATENCY_WINDOW_HOURS
+        )));
+    }
+    let histogram = diagnostics_service
+        .latency_histogram(Utc::now() - Duration::hours(hours))
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Latency histogram retrieved successfully",
+        histogram,
+    )))
+}
+
+async fn get_throughput(
+    State(diagnostics_service): State<Arc<DiagnosticsService>>,
+    Query(params): Query<ThroughputParams>,
+) -> Result<Json<ApiResponse<ThroughputReport>>, AppError> {
+    let (from, to) = params.time_range(Utc::now().date_naive())?;
+    let report = diagnostics_service
+        .throughput(from, to, params.granularity)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Throughput retrieved successfully",
+        report,
+    )))
+}
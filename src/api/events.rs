@@ -0,0 +1,29 @@
+use crate::models::outbox::{ReplayEventsRequest, ReplayEventsResponse};
+use crate::services::outbox_service::OutboxService;
+use crate::utils::error::AppError;
+use crate::utils::response::ApiResponse;
+use axum::{
+    extract::{Json, State},
+    routing::post,
+    Router,
+};
+use std::sync::Arc;
+
+/// Administrator routes managing the event outbox, relative to `/admin`
+pub fn event_admin_routes(outbox_service: Arc<OutboxService>) -> Router {
+    Router::new()
+        .route("/events/replay", post(replay_events))
+        .with_state(outbox_service)
+}
+
+async fn replay_events(
+    State(outbox_service): State<Arc<OutboxService>>,
+    Json(request): Json<ReplayEventsRequest>,
+) -> Result<Json<ApiResponse<ReplayEventsResponse>>, AppError> {
+    let replayed = outbox_service.replay(request.from, request.to).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Events queued for publishing again",
+        ReplayEventsResponse { replayed },
+    )))
+}
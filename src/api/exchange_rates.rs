@@ -0,0 +1,40 @@
+use crate::models::exchange_rate::{ExchangeRateResponse, SetExchangeRateRequest};
+use crate::services::exchange_service::ExchangeService;
+use crate::utils::error::AppError;
+use crate::utils::response::ApiResponse;
+use axum::{
+    extract::{Json, State},
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+
+/// Administrator routes maintaining exchange rates, relative to `/admin`
+pub fn exchange_rate_admin_routes(exchange_service: Arc<ExchangeService>) -> Router {
+    Router::new()
+        .route("/rates", get(list_rates).put(set_rate))
+        .with_state(exchange_service)
+}
+
+async fn list_rates(
+    State(exchange_service): State<Arc<ExchangeService>>,
+) -> Result<Json<ApiResponse<Vec<ExchangeRateResponse>>>, AppError> {
+    let rates = exchange_service.list_rates().await?;
+
+    Ok(Json(ApiResponse::success(
+        "Exchange rates retrieved successfully",
+        rates,
+    )))
+}
+
+async fn set_rate(
+    State(exchange_service): State<Arc<ExchangeService>>,
+    Json(request): Json<SetExchangeRateRequest>,
+) -> Result<Json<ApiResponse<ExchangeRateResponse>>, AppError> {
+    let rate = exchange_service.set_rate(request).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Exchange rate updated successfully",
+        rate,
+    )))
+}
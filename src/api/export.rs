@@ -0,0 +1,74 @@
+use crate::utils::csv::csv_record;
+use crate::utils::error::AppError;
+use axum::{
+    body::Body,
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use futures_util::stream::{self, BoxStream, TryStreamExt};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Size at which buffered CSV records are handed to the response body
+const CSV_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Streams a CSV file to the client as an attachment named `filename`
+///
+/// The `header` record comes first, followed by the records `records` reads
+/// from `service`. They are read and written by a separate task; the bounded
+/// channel keeps it from running ahead of a slow client, and it stops once the
+/// client is gone.
+pub fn csv_attachment<S, F>(
+    filename: &str,
+    header: &'static [&'static str],
+    service: Arc<S>,
+    records: F,
+) -> Result<Response, AppError>
+where
+    S: Send + Sync + 'static,
+    F: for<'a> FnOnce(&'a S) -> BoxStream<'a, Result<String, AppError>> + Send + 'static,
+{
+    let disposition = HeaderValue::from_str(&format!("attachment; filename=\"{}\"", filename))
+        .map_err(|_| AppError::Internal(format!("Invalid export file name {}", filename)))?;
+
+    let (chunks, receiver) = mpsc::channel::<Result<String, AppError>>(4);
+    tokio::spawn(async move {
+        let mut records = records(&service);
+        let mut chunk = csv_record(header);
+        loop {
+            match records.try_next().await {
+                Ok(Some(record)) => chunk.push_str(&record),
+                Ok(None) => break,
+                Err(e) => {
+                    // The status line is already sent, so the body is cut short instead
+                    tracing::error!("CSV export failed: {}", e);
+                    let _ = chunks.send(Err(e)).await;
+                    return;
+                }
+            }
+
+            if chunk.len() >= CSV_CHUNK_BYTES
+                && chunks.send(Ok(std::mem::take(&mut chunk))).await.is_err()
+            {
+                return;
+            }
+        }
+        let _ = chunks.send(Ok(chunk)).await;
+    });
+
+    let body = Body::from_stream(stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    }));
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("text/csv; charset=utf-8"),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
@@ -0,0 +1,40 @@
+use crate::models::fee_rule::{FeeRuleResponse, SetFeeRuleRequest};
+use crate::services::fee_service::FeeService;
+use crate::utils::error::AppError;
+use crate::utils::response::ApiResponse;
+use axum::{
+    extract::{Json, State},
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+
+/// Administrator routes maintaining fee rules, relative to `/admin`
+pub fn fee_rule_admin_routes(fee_service: Arc<FeeService>) -> Router {
+    Router::new()
+        .route("/fee-rules", get(list_rules).put(set_rule))
+        .with_state(fee_service)
+}
+
+async fn list_rules(
+    State(fee_service): State<Arc<FeeService>>,
+) -> Result<Json<ApiResponse<Vec<FeeRuleResponse>>>, AppError> {
+    let rules = fee_service.list_rules().await?;
+
+    Ok(Json(ApiResponse::success(
+        "Fee rules retrieved successfully",
+        rules,
+    )))
+}
+
+async fn set_rule(
+    State(fee_service): State<Arc<FeeService>>,
+    Json(request): Json<SetFeeRuleRequest>,
+) -> Result<Json<ApiResponse<FeeRuleResponse>>, AppError> {
+    let rule = fee_service.set_rule(request).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Fee rule updated successfully",
+        rule,
+    )))
+}
@@ -1,3 +1,375 @@
 pub mod accounts;
+pub mod admin;
+pub mod destinations;
+pub mod diagnostics;
+pub mod events;
+pub mod exchange_rates;
+mod export;
+pub mod fee_rules;
+#[cfg(feature = "sandbox")]
+pub mod sandbox;
 pub mod transactions;
 pub mod users;
+pub mod webhooks;
+
+use crate::config::Config;
+use crate::middleware::auth::{auth_middleware, require_admin, AuthState};
+use crate::middleware::envelope::strip_envelope;
+use crate::middleware::metrics::{export_metrics, track_requests};
+use crate::middleware::request_context::{stamp_requests, RequestStamp};
+use crate::middleware::request_log::{log_requests, Redactor};
+use crate::middleware::usage::record_usage;
+#[cfg(feature = "sandbox")]
+use crate::services::sandbox_service::SandboxService;
+use crate::services::{
+    account_service::AccountService, budget_service::BudgetService,
+    destination_service::DestinationService, diagnostics_service::DiagnosticsService,
+    exchange_service::ExchangeService, fee_service::FeeService, import_service::ImportService,
+    integrity_service::IntegrityService, notification_service::NotificationService,
+    outbox_service::OutboxService, scheduled_transfer_service::ScheduledTransferService,
+    standing_order_service::StandingOrderService, transaction_service::TransactionService,
+    usage_service::UsageService, user_service::UserService, webhook_service::WebhookService,
+};
+use crate::utils::email::LogEmailSender;
+use crate::utils::events::TransactionFeed;
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::{from_fn, from_fn_with_state},
+    routing::get,
+    Extension, Json, Router,
+};
+use chrono::Duration;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::trace::TraceLayer;
+
+/// Builds the complete application router with all services and middleware
+///
+/// # Arguments
+/// * `config` - Application configuration
+/// * `pool` - Database connection pool shared by all services
+///
+/// # Returns
+/// The router serving the health check, the enveloped `/api/v1` routes and the
+/// same routes without the envelope under `/api/v2`
+pub fn create_router(config: &Config, pool: PgPool) -> Router {
+    let usage_service = Arc::new(UsageService::new(pool.clone()));
+    create_router_with_usage(config, pool, usage_service, transaction_feed(config))
+}
+
+/// Builds the application router, recording API usage into `usage_service`
+/// and streaming the transactions published to `feed`
+///
+/// Use this instead of `create_router` when the caller flushes the recorded
+/// usage itself, or books transactions outside the router that the event
+/// streams should see, as the server binary does.
+pub fn create_router_with_usage(
+    config: &Config,
+    pool: PgPool,
+    usage_service: Arc<UsageService>,
+    feed: TransactionFeed,
+) -> Router {
+    // Initialize services
+    let transaction_service = transaction_service(config, &pool, &feed);
+    let services = Services {
+        user: Arc::new(
+            UserService::new(
+                pool.clone(),
+                config.jwt_secret.clone(),
+                AccountService::new(pool.clone(), config.accounts.clone()),
+            )
+            .with_jwt_expiry(Duration::hours(config.jwt_expiry_hours))
+            .with_notifications(notification_service(config, &pool)),
+        ),
+        account: Arc::new(
+            AccountService::new(pool.clone(), config.accounts.clone())
+                .with_notifications(notification_service(config, &pool)),
+        ),
+        budget: Arc::new(BudgetService::new(pool.clone())),
+        destination: Arc::new(DestinationService::new(
+            pool.clone(),
+            config.destinations.clone(),
+        )),
+        diagnostics: Arc::new(DiagnosticsService::new(pool.clone())),
+        exchange: Arc::new(ExchangeService::new(
+            pool.clone(),
+            config.transactions.exchange_rate_ttl,
+        )),
+        fee: Arc::new(FeeService::new(pool.clone()).with_default_transfer_fee(
+            config.transactions.transfer_fee_flat,
+            config.transactions.transfer_fee_percent,
+        )),
+        import: Arc::new(ImportService::new(pool.clone(), config.imports.clone())),
+        #[cfg(feature = "sandbox")]
+        sandbox: sandbox_service(config, &pool, &transaction_service),
+        scheduled_transfer: Arc::new(ScheduledTransferService::new(
+            pool.clone(),
+            transaction_service.clone(),
+        )),
+        standing_order: Arc::new(StandingOrderService::new(
+            pool.clone(),
+            transaction_service.clone(),
+        )),
+        transaction: transaction_service,
+        integrity: Arc::new(IntegrityService::new(
+            pool.clone(),
+            config.integrity.clone(),
+        )),
+        usage: usage_service,
+        webhook: Arc::new(WebhookService::new(pool.clone())),
+        outbox: Arc::new(OutboxService::new(pool.clone())),
+        feed,
+    };
+
+    let cors = config.cors_allowed_origins.cors_layer();
+
+    let api = api_routes(&config.jwt_secret, services);
+    let redactor = Arc::new(Redactor::new(&config.logging.redacted_fields));
+    let span_redactor = redactor.clone();
+
+    Router::new()
+        .route("/", get(health_check))
+        .route(
+            "/health/ready",
+            get(readiness_check).with_state(pool.clone()),
+        )
+        .route("/metrics", get(export_metrics))
+        .nest("/api/v1", api.clone())
+        // v2 serves the same handlers, unwrapping the envelope on the way out
+        .nest("/api/v2", api.layer(from_fn(strip_envelope)))
+        // A route layer, so requests are counted by the route they matched
+        .route_layer(from_fn(track_requests))
+        // Lets RequestContext decide whether to believe X-Forwarded-For
+        .layer(Extension(config.trusted_proxies.clone()))
+        .layer(from_fn_with_state(redactor, log_requests))
+        .layer(cors)
+        // Span URIs go through the redactor too, so query parameters can't leak
+        .layer(
+            TraceLayer::new_for_http().make_span_with(move |request: &Request| {
+                let request_id = request
+                    .extensions()
+                    .get::<RequestStamp>()
+                    .map(|stamp| stamp.request_id.as_str())
+                    .unwrap_or_default();
+                tracing::info_span!(
+                    "request",
+                    request_id,
+                    method = %request.method(),
+                    uri = %span_redactor.redact_uri(request.uri()),
+                    version = ?request.version(),
+                )
+            }),
+        )
+        .layer(RequestBodyLimitLayer::new(1024 * 1024)) // 1MB limit
+        // Outermost, so received_at covers the time spent in every other layer
+        .layer(from_fn(stamp_requests))
+}
+
+/// Builds the transaction service with notifications, webhooks and live
+/// events published to `feed`, as the handlers use it
+///
+/// Also used by the server binary for the scheduled transfer and standing
+/// order workers, so transfers they run send the same receipts and events.
+pub fn transaction_service(
+    config: &Config,
+    pool: &PgPool,
+    feed: &TransactionFeed,
+) -> Arc<TransactionService> {
+    Arc::new(
+        TransactionService::new(
+            pool.clone(),
+            AccountService::new(pool.clone(), config.accounts.clone()),
+            config.transactions.clone(),
+        )
+        .with_notifications(notification_service(config, pool))
+        .with_webhooks(Arc::new(WebhookService::new(pool.clone())))
+        .with_feed(feed.clone())
+        .with_instance_id(config.diagnostics.instance_id.clone()),
+    )
+}
+
+/// Creates the feed behind the live account event streams, as configured
+pub fn transaction_feed(config: &Config) -> TransactionFeed {
+    TransactionFeed::new(
+        config.event_streams.channel_capacity,
+        config.event_streams.lag_behavior,
+    )
+}
+
+/// Builds the service emailing users, delivering through the configured sender
+fn notification_service(config: &Config, pool: &PgPool) -> Arc<NotificationService> {
+    Arc::new(NotificationService::new(
+        pool.clone(),
+        Arc::new(LogEmailSender),
+        config.notifications.clone(),
+    ))
+}
+
+/// The services shared by the handlers of both API versions
+struct Services {
+    user: Arc<UserService>,
+    account: Arc<AccountService>,
+    budget: Arc<BudgetService>,
+    destination: Arc<DestinationService>,
+    diagnostics: Arc<DiagnosticsService>,
+    exchange: Arc<ExchangeService>,
+    fee: Arc<FeeService>,
+    import: Arc<ImportService>,
+    transaction: Arc<TransactionService>,
+    scheduled_transfer: Arc<ScheduledTransferService>,
+    standing_order: Arc<StandingOrderService>,
+    integrity: Arc<IntegrityService>,
+    usage: Arc<UsageService>,
+    webhook: Arc<WebhookService>,
+    outbox: Arc<OutboxService>,
+    /// Completed transactions, for the live account event streams
+    feed: TransactionFeed,
+    /// Present only when the faucet is enabled outside production
+    #[cfg(feature = "sandbox")]
+    sandbox: Option<Arc<SandboxService>>,
+}
+
+/// Builds the sandbox service if the configuration allows the faucet to run
+#[cfg(feature = "sandbox")]
+fn sandbox_service(
+    config: &Config,
+    pool: &PgPool,
+    transaction_service: &Arc<TransactionService>,
+) -> Option<Arc<SandboxService>> {
+    if let Err(e) = config.sandbox.check_environment(&config.app_env) {
+        tracing::error!("{}; sandbox routes are disabled", e);
+        return None;
+    }
+
+    config.sandbox.faucet_enabled.then(|| {
+        Arc::new(SandboxService::new(
+            pool.clone(),
+            transaction_service.clone(),
+            config.sandbox.clone(),
+        ))
+    })
+}
+
+/// Builds the versioned API routes, relative to the version prefix
+fn api_routes(jwt_secret: &str, services: Services) -> Router {
+    // Requires a valid token, then counts the request towards the caller's usage
+    let usage_aggregator = services.usage.aggregator();
+    let auth = AuthState {
+        jwt_secret: jwt_secret.to_string(),
+        user_service: services.user.clone(),
+    };
+    let protect = |router: Router| {
+        router
+            .route_layer(from_fn_with_state(usage_aggregator.clone(), record_usage))
+            .route_layer(from_fn_with_state(auth.clone(), auth_middleware))
+    };
+
+    let admin = admin::admin_routes(
+        services.transaction.clone(),
+        services.integrity,
+        services.usage.clone(),
+    )
+    .merge(destinations::destination_admin_routes(
+        services.destination.clone(),
+    ))
+    .merge(accounts::account_admin_routes(services.account.clone()))
+    .merge(diagnostics::diagnostics_admin_routes(services.diagnostics))
+    .merge(events::event_admin_routes(services.outbox))
+    .merge(exchange_rates::exchange_rate_admin_routes(
+        services.exchange,
+    ))
+    .merge(fee_rules::fee_rule_admin_routes(services.fee))
+    .merge(users::user_admin_routes(services.user.clone()));
+    #[cfg(feature = "sandbox")]
+    let admin = match &services.sandbox {
+        Some(sandbox_service) => {
+            admin.merge(sandbox::sandbox_admin_routes(sandbox_service.clone()))
+        }
+        None => admin,
+    };
+
+    let router = Router::new()
+        .nest(
+            "/users",
+            users::user_routes(services.user, services.usage.clone(), auth.clone()),
+        )
+        .nest(
+            "/accounts",
+            protect(accounts::account_routes(
+                services.account.clone(),
+                services.budget,
+                services.import,
+                services.transaction.clone(),
+                services.feed,
+            )),
+        )
+        .nest(
+            "/destinations",
+            protect(destinations::destination_routes(services.destination)),
+        )
+        .nest(
+            "/transactions",
+            protect(transactions::transaction_routes(
+                services.transaction.clone(),
+                services.account.clone(),
+                services.scheduled_transfer,
+                services.standing_order.clone(),
+            )),
+        )
+        .nest(
+            "/recurring",
+            protect(transactions::recurring_routes(
+                services.account,
+                services.standing_order,
+            )),
+        )
+        .nest(
+            "/webhooks",
+            protect(webhooks::webhook_routes(services.webhook)),
+        )
+        .nest("/admin", protect(admin.route_layer(from_fn(require_admin))));
+
+    #[cfg(feature = "sandbox")]
+    let router = match services.sandbox {
+        Some(sandbox_service) => router.nest(
+            "/sandbox",
+            protect(sandbox::sandbox_routes(sandbox_service)),
+        ),
+        None => router,
+    };
+
+    router
+}
+
+async fn health_check() -> &'static str {
+    "OK"
+}
+
+/// Readiness probe: unlike `/`, checks the database can be queried
+///
+/// Responds 200 with `"database": "up"`, or 503 with `"database": "down"`,
+/// along with the pool's connection counts.
+async fn readiness_check(State(pool): State<PgPool>) -> (StatusCode, Json<Value>) {
+    let database_up = sqlx::query("SELECT 1").execute(&pool).await.is_ok();
+    let (size, idle) = (pool.size(), pool.num_idle() as u32);
+    let status = if database_up {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(json!({
+            "database": if database_up { "up" } else { "down" },
+            "pool": {
+                "size": size,
+                "active": size.saturating_sub(idle),
+                "idle": idle,
+            },
+        })),
+    )
+}
@@ -0,0 +1,53 @@
+use crate::middleware::auth::AuthUser;
+use crate::models::sandbox::{FaucetRequest, SandboxPurgeSummary};
+use crate::models::transaction::TransactionResponse;
+use crate::services::sandbox_service::SandboxService;
+use crate::utils::error::AppError;
+use crate::utils::response::ApiResponse;
+use axum::{
+    extract::{Json, State},
+    routing::{delete, post},
+    Extension, Router,
+};
+use std::sync::Arc;
+
+/// Routes handing out test funds
+///
+/// Callers must layer `auth_middleware` on the result, and only serve it when
+/// the faucet is enabled outside production.
+pub fn sandbox_routes(sandbox_service: Arc<SandboxService>) -> Router {
+    Router::new()
+        .route("/faucet", post(faucet))
+        .with_state(sandbox_service)
+}
+
+/// Administrator routes cleaning up test funds, relative to `/admin`
+pub fn sandbox_admin_routes(sandbox_service: Arc<SandboxService>) -> Router {
+    Router::new()
+        .route("/sandbox/transactions", delete(purge_sandbox_transactions))
+        .with_state(sandbox_service)
+}
+
+async fn faucet(
+    Extension(auth_user): Extension<AuthUser>,
+    State(sandbox_service): State<Arc<SandboxService>>,
+    Json(request): Json<FaucetRequest>,
+) -> Result<Json<ApiResponse<TransactionResponse>>, AppError> {
+    let transaction = sandbox_service.faucet(auth_user.user_id, request).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Sandbox funds deposited",
+        transaction,
+    )))
+}
+
+async fn purge_sandbox_transactions(
+    State(sandbox_service): State<Arc<SandboxService>>,
+) -> Result<Json<ApiResponse<SandboxPurgeSummary>>, AppError> {
+    let summary = sandbox_service.purge().await?;
+
+    Ok(Json(ApiResponse::success(
+        "Sandbox transactions purged",
+        summary,
+    )))
+}
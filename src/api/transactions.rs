@@ -1,39 +1,165 @@
 use crate::middleware::auth::AuthUser;
+use crate::middleware::request_context::RequestContext;
+use crate::models::hold::{
+    CaptureHoldRequest, CreateHoldRequest, HoldCaptureResponse, HoldResponse,
+};
+use crate::models::idempotency::{IdempotencyKey, IDEMPOTENCY_KEY_HEADER};
+use crate::models::scheduled_transfer::{ScheduleTransferRequest, ScheduledTransferResponse};
+use crate::models::standing_order::{
+    CreateStandingOrderRequest, StandingOrderResponse, UpcomingRunsParams, UpcomingStandingOrder,
+    DEFAULT_UPCOMING_DAYS,
+};
 use crate::models::transaction::{
-    CreateTransactionRequest, DepositRequest, TransactionResponse, TransferRequest,
-    WithdrawalRequest,
+    BatchTransferRequest, BatchTransferResponse, CreateTransactionRequest, DepositRequest,
+    PayoutRequest, SplitTransferRequest, SplitTransferResponse, TransactionCursor,
+    TransactionFilter, TransactionResponse, TransactionReversalResponse, TransactionStatus,
+    TransactionSummary, TransactionType, TransferRequest, TransferValidation,
+    UpdateTransactionRequest, UserTransactionResponse, WithdrawalRequest,
+};
+use crate::services::{
+    account_service::AccountService, scheduled_transfer_service::ScheduledTransferService,
+    standing_order_service::StandingOrderService, transaction_service::TransactionService,
 };
-use crate::services::{account_service::AccountService, transaction_service::TransactionService};
+use crate::utils::currency::{AmountUnits, AMOUNT_UNITS_HEADER};
 use crate::utils::error::AppError;
-use crate::utils::response::ApiResponse;
+use crate::utils::response::{
+    pagination_links, select_fields, ApiResponse, PaginatedResponse, Sparse, TotalCount,
+};
 use axum::{
-    extract::{Json, Path, Query, State},
-    routing::{get, post},
+    body::Bytes,
+    extract::{Json, OriginalUri, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue},
+    routing::{delete, get, post},
     Extension, Router,
 };
-use serde::{Deserialize};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
 use uuid::Uuid;
 use validator::Validate;
 
+type ScheduleState = (Arc<AccountService>, Arc<ScheduledTransferService>);
+type StandingOrderState = (Arc<AccountService>, Arc<StandingOrderService>);
+
+/// Furthest ahead, in days, upcoming standing order runs are projected
+const MAX_UPCOMING_DAYS: i64 = 366;
+
 pub fn transaction_routes(
     transaction_service: Arc<TransactionService>,
     account_service: Arc<AccountService>,
+    scheduled_transfer_service: Arc<ScheduledTransferService>,
+    standing_order_service: Arc<StandingOrderService>,
 ) -> Router {
+    let schedule_routes = Router::new()
+        .route("/schedule", post(schedule_transfer))
+        .route("/schedule", get(get_scheduled_transfers))
+        .route("/schedule/:id", delete(cancel_scheduled_transfer))
+        .with_state((account_service.clone(), scheduled_transfer_service));
+    let standing_order_routes = Router::new()
+        .route("/recurring", post(create_standing_order))
+        .route("/recurring", get(get_standing_orders))
+        .route("/recurring/:id", delete(delete_standing_order))
+        .route("/recurring/:id/pause", post(pause_standing_order))
+        .route("/recurring/:id/resume", post(resume_standing_order))
+        .with_state((account_service.clone(), standing_order_service));
+
     Router::new()
         .route("/", post(create_transaction))
-        .route("/:id", get(get_transaction))
+        .route("/:id", get(get_transaction).patch(update_transaction))
+        .route(
+            "/by-reference/:reference",
+            get(get_transaction_by_reference),
+        )
+        .route("/:id/reverse", post(reverse_transaction))
         .route("/transfer", post(transfer))
+        .route("/transfer/validate", post(validate_transfer))
+        .route("/split", post(split_transfer))
+        .route("/batch", post(batch_transfer))
+        .route("/batch-transfer", post(batch_transfer))
         .route("/deposit", post(deposit))
         .route("/withdrawal", post(withdrawal))
+        .route("/payout", post(payout))
+        .route("/holds", post(create_hold))
+        .route("/holds/:id/capture", post(capture_hold))
+        .route("/holds/:id/release", post(release_hold))
         .route("/account/:id", get(get_account_transactions))
+        .route("/account/:id/summary", get(get_account_summary))
+        .route("/mine", get(get_my_transactions))
         .with_state((transaction_service, account_service))
+        .merge(schedule_routes)
+        .merge(standing_order_routes)
+}
+
+/// Routes looking ahead at the caller's standing orders, relative to `/recurring`
+pub fn recurring_routes(
+    account_service: Arc<AccountService>,
+    standing_order_service: Arc<StandingOrderService>,
+) -> Router {
+    Router::new()
+        .route("/upcoming", get(get_upcoming_standing_orders))
+        .with_state((account_service, standing_order_service))
 }
 
 #[derive(Debug, Deserialize)]
 pub struct TransactionQueryParams {
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    /// `next_cursor` of the previous page; takes precedence over `offset`
+    pub cursor: Option<String>,
+    /// Comma-separated list of fields to include in each item
+    pub fields: Option<String>,
+    /// Earliest creation time to include
+    pub from: Option<DateTime<Utc>>,
+    /// Creation time from which on transactions are excluded
+    pub to: Option<DateTime<Utc>>,
+    /// Kept as text so an unknown type is reported as a validation error
+    pub transaction_type: Option<String>,
+    /// Kept as text so an unknown status is reported as a validation error
+    pub status: Option<String>,
+    /// Smallest amount to include
+    pub min_amount: Option<Decimal>,
+    /// Largest amount to include
+    pub max_amount: Option<Decimal>,
+    /// Text the description must contain, ignoring case
+    pub q: Option<String>,
+}
+
+/// Period an account summary covers; unbounded ends include everything
+#[derive(Debug, Deserialize)]
+pub struct TransactionSummaryParams {
+    /// Earliest creation time to include
+    pub from: Option<DateTime<Utc>>,
+    /// Creation time from which on transactions are excluded
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl TransactionQueryParams {
+    /// The filtering criteria among the parameters
+    ///
+    /// Fails with `AppError::Validation` for an unknown type or status.
+    pub fn filter(&self) -> Result<TransactionFilter, AppError> {
+        Ok(TransactionFilter {
+            from: self.from,
+            to: self.to,
+            transaction_type: self
+                .transaction_type
+                .as_deref()
+                .map(TransactionType::from_str)
+                .transpose()?,
+            status: self
+                .status
+                .as_deref()
+                .map(TransactionStatus::from_str)
+                .transpose()?,
+            min_amount: self.min_amount,
+            max_amount: self.max_amount,
+            search: self.q.clone(),
+        })
+    }
 }
 
 async fn get_transaction(
@@ -46,25 +172,67 @@ async fn get_transaction(
 ) -> Result<Json<ApiResponse<TransactionResponse>>, AppError> {
     // Get the transaction
     let transaction = transaction_service.get_transaction_by_id(id).await?;
+    ensure_involved(&account_service, &auth_user, &transaction).await?;
 
-    // Verify the transaction involves an account owned by the authenticated user
-    if let Some(sender_id) = transaction.sender_account_id {
-        let sender_account = account_service.get_account_by_id(sender_id).await?;
-        if sender_account.user_id == auth_user.user_id {
-            return Ok(Json(ApiResponse::success(
-                "Transaction retrieved successfully",
-                transaction,
-            )));
-        }
-    }
+    Ok(Json(ApiResponse::success(
+        "Transaction retrieved successfully",
+        transaction,
+    )))
+}
 
-    if let Some(receiver_id) = transaction.receiver_account_id {
-        let receiver_account = account_service.get_account_by_id(receiver_id).await?;
-        if receiver_account.user_id == auth_user.user_id {
-            return Ok(Json(ApiResponse::success(
-                "Transaction retrieved successfully",
-                transaction,
-            )));
+/// Changes the description of a completed transaction of the caller's
+async fn update_transaction(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, _)): State<(Arc<TransactionService>, Arc<AccountService>)>,
+    Path(id): Path<Uuid>,
+    body: Bytes,
+) -> Result<Json<ApiResponse<TransactionResponse>>, AppError> {
+    let request = serde_json::from_slice::<UpdateTransactionRequest>(&body)
+        .map_err(|e| AppError::Validation(format!("Invalid transaction update: {}", e)))?;
+
+    let transaction = transaction_service
+        .update_description(id, auth_user.user_id, request.description)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Transaction updated successfully",
+        transaction,
+    )))
+}
+
+async fn get_transaction_by_reference(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, account_service)): State<(
+        Arc<TransactionService>,
+        Arc<AccountService>,
+    )>,
+    Path(reference): Path<String>,
+) -> Result<Json<ApiResponse<TransactionResponse>>, AppError> {
+    let transaction = transaction_service
+        .get_transaction_by_external_reference(&reference)
+        .await?;
+    ensure_involved(&account_service, &auth_user, &transaction).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Transaction retrieved successfully",
+        transaction,
+    )))
+}
+
+/// Verifies the transaction involves an account owned by the authenticated user
+async fn ensure_involved(
+    account_service: &AccountService,
+    auth_user: &AuthUser,
+    transaction: &TransactionResponse,
+) -> Result<(), AppError> {
+    let involved = [
+        transaction.sender_account_id,
+        transaction.receiver_account_id,
+    ];
+    for account_id in involved.into_iter().flatten() {
+        let account = account_service.get_account_by_id(account_id).await?;
+        if account.user_id == auth_user.user_id {
+            return Ok(());
         }
     }
 
@@ -74,13 +242,49 @@ async fn get_transaction(
     ))
 }
 
+/// Reads the optional `Idempotency-Key` header of a request sent to `endpoint`
+fn idempotency_key<T: Serialize>(
+    headers: &HeaderMap,
+    auth_user: &AuthUser,
+    endpoint: &str,
+    request: &T,
+) -> Result<Option<IdempotencyKey>, AppError> {
+    headers
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .map(|value| {
+            let key = value.to_str().map_err(|_| {
+                AppError::BadRequest(format!("Invalid {} header", IDEMPOTENCY_KEY_HEADER))
+            })?;
+            IdempotencyKey::new(auth_user.user_id, key, endpoint, request)
+        })
+        .transpose()
+}
+
+/// Reads the optional `Amount-Units` header; amounts are major units without it
+fn amount_units(headers: &HeaderMap) -> Result<AmountUnits, AppError> {
+    headers
+        .get(AMOUNT_UNITS_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .map_err(|_| {
+                    AppError::BadRequest(format!("Invalid {} header", AMOUNT_UNITS_HEADER))
+                })
+                .and_then(AmountUnits::parse)
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
 async fn create_transaction(
     Extension(auth_user): Extension<AuthUser>,
     State((transaction_service, account_service)): State<(
         Arc<TransactionService>,
         Arc<AccountService>,
     )>,
-    Json(request): Json<CreateTransactionRequest>,
+    context: RequestContext,
+    headers: HeaderMap,
+    Json(mut request): Json<CreateTransactionRequest>,
 ) -> Result<Json<ApiResponse<TransactionResponse>>, AppError> {
     // Validate request data
     request
@@ -88,6 +292,7 @@ async fn create_transaction(
         .map_err(|e| AppError::Validation(format!("Invalid transaction data: {}", e)))?;
 
     // Verify account ownership for sender or receiver
+    let mut currency = None;
     if let Some(sender_id) = request.sender_account_id {
         let sender_account = account_service.get_account_by_id(sender_id).await?;
         if sender_account.user_id != auth_user.user_id {
@@ -95,6 +300,7 @@ async fn create_transaction(
                 "You don't have permission to use this sender account".to_string(),
             ));
         }
+        currency = Some(sender_account.currency);
     }
 
     if let Some(receiver_id) = request.receiver_account_id {
@@ -104,10 +310,19 @@ async fn create_transaction(
                 "You don't have permission to use this receiver account".to_string(),
             ));
         }
+        currency.get_or_insert(receiver_account.currency);
     }
 
-    // Create the transaction
-    let transaction = transaction_service.create_transaction(request).await?;
+    // Minor units are counted in the currency of the accounts involved
+    if let Some(currency) = &currency {
+        request.amount = amount_units(&headers)?.to_major(request.amount, currency)?;
+    }
+
+    // Create the transaction, unless this is a retry of one already created
+    let idempotency = idempotency_key(&headers, &auth_user, "transactions", &request)?;
+    let transaction = transaction_service
+        .create_transaction_idempotent(request, &context, idempotency.as_ref())
+        .await?;
 
     // Return success response
     Ok(Json(ApiResponse::success(
@@ -122,8 +337,54 @@ async fn transfer(
         Arc<TransactionService>,
         Arc<AccountService>,
     )>,
-    Json(request): Json<TransferRequest>,
+    context: RequestContext,
+    headers: HeaderMap,
+    Json(mut request): Json<TransferRequest>,
 ) -> Result<Json<ApiResponse<TransactionResponse>>, AppError> {
+    authorize_transfer(&auth_user, &account_service, &headers, &mut request).await?;
+
+    // Process transfer, unless this is a retry of one already processed
+    let idempotency = idempotency_key(&headers, &auth_user, "transfer", &request)?;
+    let transaction = transaction_service
+        .process_transfer_idempotent(request, &context, idempotency.as_ref())
+        .await?;
+
+    // Return success response
+    Ok(Json(ApiResponse::success(
+        "Transfer successful",
+        transaction,
+    )))
+}
+
+/// Runs every check of a transfer without making it
+async fn validate_transfer(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, account_service)): State<(
+        Arc<TransactionService>,
+        Arc<AccountService>,
+    )>,
+    headers: HeaderMap,
+    Json(mut request): Json<TransferRequest>,
+) -> Result<Json<ApiResponse<TransferValidation>>, AppError> {
+    authorize_transfer(&auth_user, &account_service, &headers, &mut request).await?;
+
+    let validation = transaction_service.validate_transfer(&request).await?;
+    let message = if validation.valid {
+        "Transfer would succeed"
+    } else {
+        "Transfer would be rejected"
+    };
+    Ok(Json(ApiResponse::success(message, validation)))
+}
+
+/// Validates a transfer request, checks the caller owns the sender account
+/// and converts an amount given in minor units to the sender's currency
+async fn authorize_transfer(
+    auth_user: &AuthUser,
+    account_service: &AccountService,
+    headers: &HeaderMap,
+    request: &mut TransferRequest,
+) -> Result<(), AppError> {
     // Validate request data
     request
         .validate()
@@ -138,14 +399,403 @@ async fn transfer(
             "You don't have permission to use this sender account".to_string(),
         ));
     }
+    request.amount = amount_units(headers)?.to_major(request.amount, &sender_account.currency)?;
+    Ok(())
+}
+
+async fn reverse_transaction(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, account_service)): State<(
+        Arc<TransactionService>,
+        Arc<AccountService>,
+    )>,
+    context: RequestContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<TransactionReversalResponse>>, AppError> {
+    // Only the sender, or an admin, may undo a transfer
+    if !auth_user.is_admin() {
+        let transaction = transaction_service.get_transaction_by_id(id).await?;
+        let owns_sender = match transaction.sender_account_id {
+            Some(sender_id) => {
+                account_service.get_account_by_id(sender_id).await?.user_id == auth_user.user_id
+            }
+            None => false,
+        };
+        if !owns_sender {
+            return Err(AppError::Forbidden(
+                "You don't have permission to reverse this transaction".to_string(),
+            ));
+        }
+    }
+
+    // The service checks the transfer can still be reversed
+    let reversal = transaction_service
+        .reverse_transaction(id, &context)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Transaction reversed successfully",
+        reversal,
+    )))
+}
+
+async fn create_hold(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, account_service)): State<(
+        Arc<TransactionService>,
+        Arc<AccountService>,
+    )>,
+    headers: HeaderMap,
+    Json(mut request): Json<CreateHoldRequest>,
+) -> Result<Json<ApiResponse<HoldResponse>>, AppError> {
+    request
+        .validate()
+        .map_err(|e| AppError::Validation(format!("Invalid hold data: {}", e)))?;
+
+    // Verify account ownership
+    let account = account_service
+        .get_account_by_id(request.account_id)
+        .await?;
+    if account.user_id != auth_user.user_id {
+        return Err(AppError::Forbidden(
+            "You don't have permission to use this account".to_string(),
+        ));
+    }
+    request.amount = amount_units(&headers)?.to_major(request.amount, &account.currency)?;
+
+    let hold = transaction_service.create_hold(request).await?;
+
+    Ok(Json(ApiResponse::success("Funds held successfully", hold)))
+}
+
+/// Looks up a hold, checking it is on an account the user owns
+async fn owned_hold(
+    auth_user: &AuthUser,
+    transaction_service: &TransactionService,
+    account_service: &AccountService,
+    id: Uuid,
+) -> Result<HoldResponse, AppError> {
+    let hold = transaction_service.get_hold(id).await?;
+    let account = account_service.get_account_by_id(hold.account_id).await?;
+    if account.user_id != auth_user.user_id {
+        return Err(AppError::Forbidden(
+            "You don't have permission to use this hold".to_string(),
+        ));
+    }
+    Ok(hold)
+}
+
+async fn capture_hold(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, account_service)): State<(
+        Arc<TransactionService>,
+        Arc<AccountService>,
+    )>,
+    context: RequestContext,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<ApiResponse<HoldCaptureResponse>>, AppError> {
+    // Capturing everything into a withdrawal needs no body at all
+    let mut request = (!body.is_empty())
+        .then(|| serde_json::from_slice::<CaptureHoldRequest>(&body))
+        .transpose()
+        .map_err(|e| AppError::Validation(format!("Invalid capture data: {}", e)))?
+        .unwrap_or_default();
+    request
+        .validate()
+        .map_err(|e| AppError::Validation(format!("Invalid capture data: {}", e)))?;
+
+    let hold = owned_hold(&auth_user, &transaction_service, &account_service, id).await?;
+    if let Some(amount) = request.amount {
+        request.amount = Some(amount_units(&headers)?.to_major(amount, &hold.currency)?);
+    }
+
+    let capture = transaction_service
+        .capture_hold(id, request, &context)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Hold captured successfully",
+        capture,
+    )))
+}
+
+async fn release_hold(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, account_service)): State<(
+        Arc<TransactionService>,
+        Arc<AccountService>,
+    )>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<HoldResponse>>, AppError> {
+    owned_hold(&auth_user, &transaction_service, &account_service, id).await?;
+
+    let hold = transaction_service.release_hold(id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Hold released successfully",
+        hold,
+    )))
+}
+
+async fn schedule_transfer(
+    Extension(auth_user): Extension<AuthUser>,
+    State((account_service, scheduled_transfer_service)): State<ScheduleState>,
+    headers: HeaderMap,
+    Json(mut request): Json<ScheduleTransferRequest>,
+) -> Result<Json<ApiResponse<ScheduledTransferResponse>>, AppError> {
+    request
+        .validate()
+        .map_err(|e| AppError::Validation(format!("Invalid transfer data: {}", e)))?;
+
+    // Verify sender account ownership
+    let sender_account = account_service
+        .get_account_by_id(request.sender_account_id)
+        .await?;
+    if sender_account.user_id != auth_user.user_id {
+        return Err(AppError::Forbidden(
+            "You don't have permission to use this sender account".to_string(),
+        ));
+    }
+    request.amount = amount_units(&headers)?.to_major(request.amount, &sender_account.currency)?;
+
+    // Funds are only checked when the transfer runs
+    let scheduled = scheduled_transfer_service
+        .schedule(auth_user.user_id, request)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Transfer scheduled successfully",
+        scheduled,
+    )))
+}
+
+async fn get_scheduled_transfers(
+    Extension(auth_user): Extension<AuthUser>,
+    State((_, scheduled_transfer_service)): State<ScheduleState>,
+) -> Result<Json<ApiResponse<Vec<ScheduledTransferResponse>>>, AppError> {
+    let scheduled = scheduled_transfer_service
+        .list_pending(auth_user.user_id)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Scheduled transfers retrieved successfully",
+        scheduled,
+    )))
+}
+
+async fn cancel_scheduled_transfer(
+    Extension(auth_user): Extension<AuthUser>,
+    State((_, scheduled_transfer_service)): State<ScheduleState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<ScheduledTransferResponse>>, AppError> {
+    let cancelled = scheduled_transfer_service
+        .cancel(auth_user.user_id, id)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Scheduled transfer cancelled successfully",
+        cancelled,
+    )))
+}
+
+async fn create_standing_order(
+    Extension(auth_user): Extension<AuthUser>,
+    State((account_service, standing_order_service)): State<StandingOrderState>,
+    headers: HeaderMap,
+    Json(mut request): Json<CreateStandingOrderRequest>,
+) -> Result<Json<ApiResponse<StandingOrderResponse>>, AppError> {
+    request
+        .validate()
+        .map_err(|e| AppError::Validation(format!("Invalid standing order data: {}", e)))?;
+
+    // Verify sender account ownership
+    let sender_account = account_service
+        .get_account_by_id(request.sender_account_id)
+        .await?;
+    if sender_account.user_id != auth_user.user_id {
+        return Err(AppError::Forbidden(
+            "You don't have permission to use this sender account".to_string(),
+        ));
+    }
+    request.amount = amount_units(&headers)?.to_major(request.amount, &sender_account.currency)?;
+
+    let order = standing_order_service
+        .create(auth_user.user_id, request)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Standing order created successfully",
+        order,
+    )))
+}
+
+async fn get_standing_orders(
+    Extension(auth_user): Extension<AuthUser>,
+    State((_, standing_order_service)): State<StandingOrderState>,
+) -> Result<Json<ApiResponse<Vec<StandingOrderResponse>>>, AppError> {
+    let orders = standing_order_service.list(auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Standing orders retrieved successfully",
+        orders,
+    )))
+}
+
+/// Lists when the caller's active standing orders are due to run
+async fn get_upcoming_standing_orders(
+    Extension(auth_user): Extension<AuthUser>,
+    State((_, standing_order_service)): State<StandingOrderState>,
+    Query(params): Query<UpcomingRunsParams>,
+) -> Result<Json<ApiResponse<Vec<UpcomingStandingOrder>>>, AppError> {
+    let days = params.days.unwrap_or(DEFAULT_UPCOMING_DAYS);
+    if !(1..=MAX_UPCOMING_DAYS).contains(&days) {
+        return Err(AppError::BadRequest(format!(
+            "days must be between 1 and {}",
+            MAX_UPCOMING_DAYS
+        )));
+    }
+    let orders = standing_order_service
+        .upcoming(auth_user.user_id, Utc::now() + Duration::days(days))
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Upcoming standing order runs retrieved successfully",
+        orders,
+    )))
+}
+
+async fn pause_standing_order(
+    Extension(auth_user): Extension<AuthUser>,
+    State((_, standing_order_service)): State<StandingOrderState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<StandingOrderResponse>>, AppError> {
+    let order = standing_order_service.pause(auth_user.user_id, id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Standing order paused successfully",
+        order,
+    )))
+}
+
+async fn resume_standing_order(
+    Extension(auth_user): Extension<AuthUser>,
+    State((_, standing_order_service)): State<StandingOrderState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<StandingOrderResponse>>, AppError> {
+    let order = standing_order_service.resume(auth_user.user_id, id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Standing order resumed successfully",
+        order,
+    )))
+}
+
+async fn delete_standing_order(
+    Extension(auth_user): Extension<AuthUser>,
+    State((_, standing_order_service)): State<StandingOrderState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    standing_order_service.delete(auth_user.user_id, id).await?;
+
+    Ok(Json(ApiResponse::<()>::success_no_data(
+        "Standing order deleted successfully",
+    )))
+}
+
+async fn split_transfer(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, account_service)): State<(
+        Arc<TransactionService>,
+        Arc<AccountService>,
+    )>,
+    context: RequestContext,
+    headers: HeaderMap,
+    Json(mut request): Json<SplitTransferRequest>,
+) -> Result<Json<ApiResponse<SplitTransferResponse>>, AppError> {
+    // Verify sender account ownership
+    let sender_account = account_service
+        .get_account_by_id(request.sender_account_id)
+        .await?;
+    if sender_account.user_id != auth_user.user_id {
+        return Err(AppError::Forbidden(
+            "You don't have permission to use this sender account".to_string(),
+        ));
+    }
+    let units = amount_units(&headers)?;
+    for leg in &mut request.legs {
+        leg.amount = units.to_major(leg.amount, &sender_account.currency)?;
+    }
 
-    // Process transfer
-    let transaction = transaction_service.process_transfer(request).await?;
+    // Process all legs together; the service validates them
+    let split = transaction_service
+        .process_split_transfer(request, &context)
+        .await?;
 
     // Return success response
     Ok(Json(ApiResponse::success(
-        "Transfer successful",
-        transaction,
+        "Split transfer successful",
+        split,
+    )))
+}
+
+async fn batch_transfer(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, account_service)): State<(
+        Arc<TransactionService>,
+        Arc<AccountService>,
+    )>,
+    context: RequestContext,
+    headers: HeaderMap,
+    Json(request): Json<BatchTransferRequest>,
+) -> Result<Json<ApiResponse<BatchTransferResponse>>, AppError> {
+    let units = amount_units(&headers)?;
+    let mut sender_currencies: HashMap<Uuid, String> = HashMap::new();
+    let mut transfers = Vec::with_capacity(request.transfers.len());
+    for transfer in request.transfers {
+        let sender_account_id = transfer
+            .sender_account_id
+            .or(request.sender_account_id)
+            .ok_or_else(|| {
+                AppError::Validation("Every transfer needs a sender_account_id".to_string())
+            })?;
+
+        // Verify ownership of every sender account
+        if let Entry::Vacant(entry) = sender_currencies.entry(sender_account_id) {
+            let sender_account = account_service.get_account_by_id(sender_account_id).await?;
+            if sender_account.user_id != auth_user.user_id {
+                return Err(AppError::Forbidden(
+                    "You don't have permission to use this sender account".to_string(),
+                ));
+            }
+            entry.insert(sender_account.currency);
+        }
+
+        let transfer = TransferRequest {
+            sender_account_id,
+            receiver_account_id: transfer.receiver_account_id,
+            amount: units.to_major(transfer.amount, &sender_currencies[&sender_account_id])?,
+            description: transfer.description,
+            category: transfer.category,
+            allow_duplicate: false,
+        };
+        // Validate request data
+        transfer
+            .validate()
+            .map_err(|e| AppError::Validation(format!("Invalid transfer data: {}", e)))?;
+        transfers.push(transfer);
+    }
+
+    // Book every transfer together; the service checks the batch size
+    let transactions = transaction_service
+        .process_batch_transfer(transfers, &context)
+        .await?;
+
+    // Return success response
+    Ok(Json(ApiResponse::success(
+        "Batch transfer successful",
+        BatchTransferResponse::from(transactions),
     )))
 }
 
@@ -155,7 +805,9 @@ async fn deposit(
         Arc<TransactionService>,
         Arc<AccountService>,
     )>,
-    Json(request): Json<DepositRequest>,
+    context: RequestContext,
+    headers: HeaderMap,
+    Json(mut request): Json<DepositRequest>,
 ) -> Result<Json<ApiResponse<TransactionResponse>>, AppError> {
     // Validate request data
     request
@@ -171,9 +823,14 @@ async fn deposit(
             "You don't have permission to use this account".to_string(),
         ));
     }
+    let currency = request.currency.as_deref().unwrap_or(&account.currency);
+    request.amount = amount_units(&headers)?.to_major(request.amount, currency)?;
 
-    // Process deposit
-    let transaction = transaction_service.process_deposit(request).await?;
+    // Process deposit, unless this is a retry of one already processed
+    let idempotency = idempotency_key(&headers, &auth_user, "deposit", &request)?;
+    let transaction = transaction_service
+        .process_deposit_idempotent(request, &context, idempotency.as_ref())
+        .await?;
 
     // Return success response
     Ok(Json(ApiResponse::success(
@@ -188,7 +845,9 @@ async fn withdrawal(
         Arc<TransactionService>,
         Arc<AccountService>,
     )>,
-    Json(request): Json<WithdrawalRequest>,
+    context: RequestContext,
+    headers: HeaderMap,
+    Json(mut request): Json<WithdrawalRequest>,
 ) -> Result<Json<ApiResponse<TransactionResponse>>, AppError> {
     // Validate request data
     request
@@ -204,9 +863,13 @@ async fn withdrawal(
             "You don't have permission to use this account".to_string(),
         ));
     }
+    request.amount = amount_units(&headers)?.to_major(request.amount, &account.currency)?;
 
-    // Process withdrawal
-    let transaction = transaction_service.process_withdrawal(request).await?;
+    // Process withdrawal, unless this is a retry of one already processed
+    let idempotency = idempotency_key(&headers, &auth_user, "withdrawal", &request)?;
+    let transaction = transaction_service
+        .process_withdrawal_idempotent(request, &context, idempotency.as_ref())
+        .await?;
 
     // Return success response
     Ok(Json(ApiResponse::success(
@@ -215,6 +878,41 @@ async fn withdrawal(
     )))
 }
 
+async fn payout(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, account_service)): State<(
+        Arc<TransactionService>,
+        Arc<AccountService>,
+    )>,
+    context: RequestContext,
+    headers: HeaderMap,
+    Json(mut request): Json<PayoutRequest>,
+) -> Result<Json<ApiResponse<TransactionResponse>>, AppError> {
+    // Validate request data
+    request
+        .validate()
+        .map_err(|e| AppError::Validation(format!("Invalid payout data: {}", e)))?;
+
+    // Verify account ownership
+    let account = account_service
+        .get_account_by_id(request.account_id)
+        .await?;
+    if account.user_id != auth_user.user_id {
+        return Err(AppError::Forbidden(
+            "You don't have permission to use this account".to_string(),
+        ));
+    }
+    request.amount = amount_units(&headers)?.to_major(request.amount, &account.currency)?;
+
+    // Process payout
+    let transaction = transaction_service
+        .process_payout(request, &context)
+        .await?;
+
+    // Return success response
+    Ok(Json(ApiResponse::success("Payout successful", transaction)))
+}
+
 async fn get_account_transactions(
     Extension(auth_user): Extension<AuthUser>,
     State((transaction_service, account_service)): State<(
@@ -222,8 +920,16 @@ async fn get_account_transactions(
         Arc<AccountService>,
     )>,
     Path(id): Path<Uuid>,
+    OriginalUri(uri): OriginalUri,
     Query(params): Query<TransactionQueryParams>,
-) -> Result<Json<ApiResponse<Vec<TransactionResponse>>>, AppError> {
+) -> Result<
+    (
+        HeaderMap,
+        Extension<TotalCount>,
+        Json<ApiResponse<PaginatedResponse<Sparse<TransactionResponse>>>>,
+    ),
+    AppError,
+> {
     // Verify account ownership
     let account = account_service.get_account_by_id(id).await?;
     if account.user_id != auth_user.user_id {
@@ -232,14 +938,154 @@ async fn get_account_transactions(
         ));
     }
 
-    // Get transactions for this account
-    let transactions = transaction_service
-        .get_transactions_by_account_id(id, params.limit, params.offset)
+    // Normalize paging so the Link header always describes valid pages
+    let limit = params.limit.unwrap_or(100).max(1);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    // A cursor continues after the last row of the previous page instead of skipping rows
+    let before = params
+        .cursor
+        .as_deref()
+        .map(TransactionCursor::decode)
+        .transpose()?;
+
+    // Get the transactions for this account that match the filters, plus one
+    // more to tell whether another page follows
+    let filter = params.filter()?;
+    let offset = if before.is_some() { 0 } else { offset };
+    let mut transactions = transaction_service
+        .get_transactions_by_account_id(
+            id,
+            &filter,
+            before.as_ref(),
+            Some(limit.saturating_add(1)),
+            Some(offset),
+        )
         .await?;
+    let next_cursor = if transactions.len() as i64 > limit {
+        transactions.truncate(limit as usize);
+        transactions
+            .last()
+            .map(|transaction| TransactionCursor::after(transaction).encode())
+    } else {
+        None
+    };
+    let transactions = select_fields(transactions, params.fields.as_deref())?;
+    let total = transaction_service
+        .count_transactions_by_account_id(id, &filter)
+        .await?;
+
+    // Advertise neighbouring pages for hypermedia-style clients; offsets mean
+    // nothing once a cursor is used
+    let mut headers = HeaderMap::new();
+    if before.is_none() {
+        let links = pagination_links(&uri, limit, offset, total);
+        headers.insert(
+            header::LINK,
+            HeaderValue::from_str(&links)
+                .map_err(|e| AppError::Internal(format!("Invalid Link header: {}", e)))?,
+        );
+    }
 
     // Return success response
+    Ok((
+        headers,
+        Extension(TotalCount(total)),
+        Json(
+            ApiResponse::paginated(
+                "Transactions retrieved successfully",
+                transactions,
+                total,
+                limit,
+                offset,
+            )
+            .with_next_cursor(next_cursor),
+        ),
+    ))
+}
+
+async fn get_account_summary(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, account_service)): State<(
+        Arc<TransactionService>,
+        Arc<AccountService>,
+    )>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<TransactionSummaryParams>,
+) -> Result<Json<ApiResponse<TransactionSummary>>, AppError> {
+    // Verify account ownership
+    let account = account_service.get_account_by_id(id).await?;
+    if account.user_id != auth_user.user_id {
+        return Err(AppError::Forbidden(
+            "You don't have permission to access this account".to_string(),
+        ));
+    }
+
+    let totals = transaction_service
+        .get_transaction_totals(id, params.from, params.to)
+        .await?;
+
     Ok(Json(ApiResponse::success(
-        "Transactions retrieved successfully",
-        transactions,
+        "Transaction summary retrieved successfully",
+        TransactionSummary {
+            account_id: id,
+            currency: account.currency,
+            from: params.from,
+            to: params.to,
+            totals,
+        },
     )))
 }
+
+async fn get_my_transactions(
+    Extension(auth_user): Extension<AuthUser>,
+    State((transaction_service, _)): State<(Arc<TransactionService>, Arc<AccountService>)>,
+    OriginalUri(uri): OriginalUri,
+    Query(params): Query<TransactionQueryParams>,
+) -> Result<
+    (
+        HeaderMap,
+        Extension<TotalCount>,
+        Json<ApiResponse<PaginatedResponse<Sparse<UserTransactionResponse>>>>,
+    ),
+    AppError,
+> {
+    if params.cursor.is_some() {
+        return Err(AppError::BadRequest(
+            "This listing pages with limit and offset, not a cursor".to_string(),
+        ));
+    }
+
+    let limit = params.limit.unwrap_or(100).max(1);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    // Covers every account the caller owns, so no ownership check is needed
+    let filter = params.filter()?;
+    let transactions = transaction_service
+        .get_transactions_by_user_id(auth_user.user_id, Some(limit), Some(offset), &filter)
+        .await?;
+    let transactions = select_fields(transactions, params.fields.as_deref())?;
+    let total = transaction_service
+        .count_transactions_by_user_id(auth_user.user_id, &filter)
+        .await?;
+
+    let mut headers = HeaderMap::new();
+    let links = pagination_links(&uri, limit, offset, total);
+    headers.insert(
+        header::LINK,
+        HeaderValue::from_str(&links)
+            .map_err(|e| AppError::Internal(format!("Invalid Link header: {}", e)))?,
+    );
+
+    Ok((
+        headers,
+        Extension(TotalCount(total)),
+        Json(ApiResponse::paginated(
+            "Transactions retrieved successfully",
+            transactions,
+            total,
+            limit,
+            offset,
+        )),
+    ))
+}
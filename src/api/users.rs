@@ -1,22 +1,74 @@
-use crate::middleware::auth::AuthUser;
-use crate::models::user::{CreateUserRequest, LoginRequest, UserResponse};
+use crate::middleware::auth::{auth_middleware, bearer_token, AuthState, AuthUser};
+use crate::middleware::request_context::RequestContext;
+use crate::middleware::usage::record_usage;
+use crate::models::usage::{UsageQueryParams, UsageRecord};
+use crate::models::user::{
+    BulkUserResult, ChangePasswordRequest, CreateUserRequest, LoginEvent, LoginOutcome,
+    LoginRequest, LoginResponse, NotificationPreferences, RefreshTokenRequest, TotpCodeRequest,
+    TotpLoginRequest, TotpSetupResponse, UserResponse,
+};
+use crate::services::usage_service::UsageService;
 use crate::services::user_service::UserService;
 use crate::utils::error::AppError;
+use crate::utils::locale::Locale;
 use crate::utils::response::ApiResponse;
 use axum::{
-    extract::{Json, State},
+    body::Bytes,
+    extract::{Json, Path, Query, State},
+    http::{header, HeaderMap},
+    middleware::from_fn_with_state,
     routing::{get, post, put},
     Extension, Router,
 };
+use chrono::Utc;
+use serde::Deserialize;
+use std::str::FromStr;
 use std::sync::Arc;
+use uuid::Uuid;
 use validator::Validate;
 
-pub fn user_routes(user_service: Arc<UserService>) -> Router {
+pub fn user_routes(
+    user_service: Arc<UserService>,
+    usage_service: Arc<UsageService>,
+    auth: AuthState,
+) -> Router {
+    let usage_routes = Router::new()
+        .route("/me/usage", get(get_usage))
+        .with_state(usage_service.clone());
+
+    // Routes acting on the current user need an authenticated caller
+    let protected_routes = Router::new()
+        .route("/me", get(get_current_user).delete(deactivate_current_user))
+        .route("/me/login-history", get(get_login_history))
+        .route(
+            "/me/notification-preferences",
+            get(get_notification_preferences).put(update_notification_preferences),
+        )
+        .route("/profile", put(update_profile))
+        .route("/password", put(change_password))
+        .route("/me/totp", post(enable_totp))
+        .route("/me/totp/verify", post(verify_totp))
+        .merge(usage_routes)
+        .route_layer(from_fn_with_state(usage_service.aggregator(), record_usage))
+        .route_layer(from_fn_with_state(auth, auth_middleware));
+
     Router::new()
         .route("/register", post(register_user))
         .route("/login", post(login))
-        .route("/me", get(get_current_user))
-        .route("/profile", put(update_profile))
+        .route("/login/totp", post(login_totp))
+        .route("/refresh", post(refresh_session))
+        .route("/logout", post(logout))
+        .route("/verify", get(verify_email))
+        .merge(protected_routes)
+        .with_state(user_service)
+}
+
+/// Administrator routes managing users, relative to `/admin`
+pub fn user_admin_routes(user_service: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/users/bulk", post(bulk_create_users))
+        .route("/users/:id/erase", post(erase_user))
+        .route("/users/:id/reactivate", post(reactivate_user))
         .with_state(user_service)
 }
 
@@ -39,8 +91,68 @@ async fn register_user(
     )))
 }
 
+async fn bulk_create_users(
+    State(user_service): State<Arc<UserService>>,
+    Json(users): Json<Vec<CreateUserRequest>>,
+) -> Result<Json<ApiResponse<Vec<BulkUserResult>>>, AppError> {
+    // Entries are validated and created one by one; failures are reported per entry
+    let results = user_service.create_users_bulk(users).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Bulk user creation processed",
+        results,
+    )))
+}
+
+async fn erase_user(
+    Extension(auth_user): Extension<AuthUser>,
+    State(user_service): State<Arc<UserService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<UserResponse>>, AppError> {
+    // Irreversible: personal data is scrubbed, accounts and transactions stay
+    let user = user_service.erase_user(id, auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success("User erased successfully", user)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeactivateParams {
+    /// Also freeze the user's active accounts
+    #[serde(default)]
+    pub freeze_accounts: bool,
+}
+
+/// Deactivates the current user; their access token stops working at once
+async fn deactivate_current_user(
+    Extension(auth_user): Extension<AuthUser>,
+    State(user_service): State<Arc<UserService>>,
+    Query(params): Query<DeactivateParams>,
+) -> Result<Json<ApiResponse<UserResponse>>, AppError> {
+    let user = user_service
+        .deactivate_user(auth_user.user_id, params.freeze_accounts)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "User deactivated successfully",
+        user,
+    )))
+}
+
+async fn reactivate_user(
+    State(user_service): State<Arc<UserService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<UserResponse>>, AppError> {
+    let user = user_service.reactivate_user(id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "User reactivated successfully",
+        user,
+    )))
+}
+
 async fn login(
     State(user_service): State<Arc<UserService>>,
+    context: RequestContext,
     Json(login_data): Json<LoginRequest>,
 ) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
     // Validate request data
@@ -49,18 +161,149 @@ async fn login(
         .map_err(|e| AppError::Validation(format!("Invalid login data: {}", e)))?;
 
     // Authenticate user
-    let login_response = user_service.login(login_data).await?;
+    let login_response = match user_service.login(login_data, &context).await? {
+        LoginOutcome::Session(session) => session,
+        // Users with 2FA get a token to exchange at /login/totp instead
+        LoginOutcome::TotpRequired(challenge) => {
+            return Ok(Json(ApiResponse::success(
+                "One-time password required",
+                serde_json::json!({
+                    "totp_required": true,
+                    "totp_token": challenge.totp_token,
+                    "expires_in": challenge.expires_in
+                }),
+            )));
+        }
+    };
+
+    // Return success response with tokens and user data
+    Ok(Json(ApiResponse::success(
+        "Login successful",
+        session_json(login_response),
+    )))
+}
+
+/// Finishes logging in a user with two-factor authentication
+async fn login_totp(
+    State(user_service): State<Arc<UserService>>,
+    Json(request): Json<TotpLoginRequest>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
+    request
+        .validate()
+        .map_err(|e| AppError::Validation(format!("Invalid login data: {}", e)))?;
+
+    let session = user_service
+        .login_totp(&request.totp_token, &request.code)
+        .await?;
 
-    // Return success response with token and user data
     Ok(Json(ApiResponse::success(
         "Login successful",
-        serde_json::json!({
-            "token": login_response.token,
-            "user": login_response.user
-        }),
+        session_json(session),
+    )))
+}
+
+/// Starts setting up two-factor authentication for the current user
+async fn enable_totp(
+    Extension(auth_user): Extension<AuthUser>,
+    State(user_service): State<Arc<UserService>>,
+) -> Result<Json<ApiResponse<TotpSetupResponse>>, AppError> {
+    let setup = user_service.enable_totp(auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Two-factor authentication setup started",
+        setup,
+    )))
+}
+
+/// Turns on two-factor authentication once a code from the new secret checks out
+async fn verify_totp(
+    Extension(auth_user): Extension<AuthUser>,
+    State(user_service): State<Arc<UserService>>,
+    Json(request): Json<TotpCodeRequest>,
+) -> Result<Json<ApiResponse<UserResponse>>, AppError> {
+    let user = user_service
+        .verify_totp(auth_user.user_id, &request.code)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Two-factor authentication enabled",
+        user,
     )))
 }
 
+async fn refresh_session(
+    State(user_service): State<Arc<UserService>>,
+    Json(request): Json<RefreshTokenRequest>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
+    request
+        .validate()
+        .map_err(|e| AppError::Validation(format!("Invalid refresh request: {}", e)))?;
+
+    let session = user_service.refresh_session(&request.refresh_token).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Session refreshed",
+        session_json(session),
+    )))
+}
+
+async fn logout(
+    State(user_service): State<Arc<UserService>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    // The refresh token is optional, so an empty body is allowed
+    let request = (!body.is_empty())
+        .then(|| serde_json::from_slice::<RefreshTokenRequest>(&body))
+        .transpose()
+        .map_err(|e| AppError::Validation(format!("Invalid logout request: {}", e)))?;
+    if let Some(request) = &request {
+        request
+            .validate()
+            .map_err(|e| AppError::Validation(format!("Invalid logout request: {}", e)))?;
+    }
+
+    // The access token the request was sent with stops working at once
+    if headers.contains_key(header::AUTHORIZATION) {
+        user_service
+            .revoke_access_token(&bearer_token(&headers)?)
+            .await?;
+    } else if request.is_none() {
+        return Err(AppError::Auth("Missing authorization header".to_string()));
+    }
+
+    if let Some(request) = request {
+        user_service.logout(&request.refresh_token).await?;
+    }
+
+    Ok(Json(ApiResponse::<()>::success_no_data("Logged out")))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailParams {
+    /// Token emailed to the user at registration
+    pub token: String,
+}
+
+/// Confirms the user's email address; the token is the credential, so no
+/// login is needed
+async fn verify_email(
+    State(user_service): State<Arc<UserService>>,
+    Query(params): Query<VerifyEmailParams>,
+) -> Result<Json<ApiResponse<UserResponse>>, AppError> {
+    let user = user_service.verify_email(&params.token).await?;
+
+    Ok(Json(ApiResponse::success("Email address verified", user)))
+}
+
+fn session_json(session: LoginResponse) -> serde_json::Value {
+    serde_json::json!({
+        "token": session.token,
+        "refresh_token": session.refresh_token,
+        "user": session.user
+    })
+}
+
 async fn get_current_user(
     Extension(auth_user): Extension<AuthUser>,
     State(user_service): State<Arc<UserService>>,
@@ -72,6 +315,97 @@ async fn get_current_user(
     Ok(Json(ApiResponse::success("User profile retrieved", user)))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LoginHistoryParams {
+    pub limit: Option<i64>,
+}
+
+async fn get_login_history(
+    Extension(auth_user): Extension<AuthUser>,
+    State(user_service): State<Arc<UserService>>,
+    Query(params): Query<LoginHistoryParams>,
+) -> Result<Json<ApiResponse<Vec<LoginEvent>>>, AppError> {
+    // Default to the last 20 attempts and never return more than 100
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+
+    let events = user_service
+        .get_login_history(auth_user.user_id, limit)
+        .await?;
+
+    // Return success response
+    Ok(Json(ApiResponse::success(
+        "Login history retrieved",
+        events,
+    )))
+}
+
+async fn get_notification_preferences(
+    Extension(auth_user): Extension<AuthUser>,
+    State(user_service): State<Arc<UserService>>,
+) -> Result<Json<ApiResponse<NotificationPreferences>>, AppError> {
+    let preferences = user_service
+        .get_notification_preferences(auth_user.user_id)
+        .await?;
+
+    // Return success response
+    Ok(Json(ApiResponse::success(
+        "Notification preferences retrieved",
+        preferences,
+    )))
+}
+
+async fn update_notification_preferences(
+    Extension(auth_user): Extension<AuthUser>,
+    State(user_service): State<Arc<UserService>>,
+    Json(preferences): Json<NotificationPreferences>,
+) -> Result<Json<ApiResponse<NotificationPreferences>>, AppError> {
+    let preferences = user_service
+        .update_notification_preferences(auth_user.user_id, preferences)
+        .await?;
+
+    // Return success response
+    Ok(Json(ApiResponse::success(
+        "Notification preferences updated",
+        preferences,
+    )))
+}
+
+async fn get_usage(
+    Extension(auth_user): Extension<AuthUser>,
+    State(usage_service): State<Arc<UsageService>>,
+    Query(params): Query<UsageQueryParams>,
+) -> Result<Json<ApiResponse<Vec<UsageRecord>>>, AppError> {
+    // Callers only ever see their own counts, whatever user_id they pass
+    let (from, to) = params.date_range(Utc::now().date_naive())?;
+    let usage = usage_service
+        .get_usage(Some(auth_user.user_id), from, to)
+        .await?;
+
+    // Return success response
+    Ok(Json(ApiResponse::success(
+        "Usage retrieved successfully",
+        usage,
+    )))
+}
+
+async fn change_password(
+    Extension(auth_user): Extension<AuthUser>,
+    State(user_service): State<Arc<UserService>>,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    user_service
+        .change_password(
+            auth_user.user_id,
+            &request.current_password,
+            &request.new_password,
+        )
+        .await?;
+
+    Ok(Json(ApiResponse::<()>::success_no_data(
+        "Password changed successfully",
+    )))
+}
+
 async fn update_profile(
     Extension(auth_user): Extension<AuthUser>,
     State(user_service): State<Arc<UserService>>,
@@ -88,9 +422,16 @@ async fn update_profile(
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
 
+    // Locales are restricted to the curated set supported by the export renderers
+    let locale = profile_data
+        .get("locale")
+        .and_then(|v| v.as_str())
+        .map(Locale::from_str)
+        .transpose()?;
+
     // Update user profile
     let user = user_service
-        .update_user(auth_user.user_id, first_name, last_name)
+        .update_user(auth_user.user_id, first_name, last_name, locale)
         .await?;
 
     // Return success response
@@ -0,0 +1,138 @@
+use crate::middleware::auth::AuthUser;
+use crate::models::webhook::{
+    RegisterWebhookRequest, UpdateWebhookRequest, WebhookDeliveryResponse, WebhookResponse,
+};
+use crate::services::webhook_service::WebhookService;
+use crate::utils::error::AppError;
+use crate::utils::response::ApiResponse;
+use axum::{
+    extract::{Json, Path, Query, State},
+    routing::{get, post},
+    Extension, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Routes managing the caller's webhook endpoints
+///
+/// Callers must layer `auth_middleware` on the result.
+pub fn webhook_routes(webhook_service: Arc<WebhookService>) -> Router {
+    Router::new()
+        .route("/", get(list_webhooks))
+        .route("/", post(register_webhook))
+        .route(
+            "/:id",
+            get(get_webhook)
+                .patch(update_webhook)
+                .delete(delete_webhook),
+        )
+        .route("/:id/deliveries", get(list_deliveries))
+        .route("/:id/deliveries/:delivery_id/redeliver", post(redeliver))
+        .with_state(webhook_service)
+}
+
+async fn list_webhooks(
+    Extension(auth_user): Extension<AuthUser>,
+    State(webhook_service): State<Arc<WebhookService>>,
+) -> Result<Json<ApiResponse<Vec<WebhookResponse>>>, AppError> {
+    let webhooks = webhook_service.list(auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Webhooks retrieved successfully",
+        webhooks,
+    )))
+}
+
+async fn register_webhook(
+    Extension(auth_user): Extension<AuthUser>,
+    State(webhook_service): State<Arc<WebhookService>>,
+    Json(request): Json<RegisterWebhookRequest>,
+) -> Result<Json<ApiResponse<WebhookResponse>>, AppError> {
+    let webhook = webhook_service.register(auth_user.user_id, request).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Webhook registered successfully",
+        webhook,
+    )))
+}
+
+async fn get_webhook(
+    Extension(auth_user): Extension<AuthUser>,
+    State(webhook_service): State<Arc<WebhookService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<WebhookResponse>>, AppError> {
+    let webhook = webhook_service.get(auth_user.user_id, id).await?;
+
+    Ok(Json(ApiResponse::success(
+        "Webhook retrieved successfully",
+        webhook,
+    )))
+}
+
+async fn update_webhook(
+    Extension(auth_user): Extension<AuthUser>,
+    State(webhook_service): State<Arc<WebhookService>>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateWebhookRequest>,
+) -> Result<Json<ApiResponse<WebhookResponse>>, AppError> {
+    let webhook = webhook_service
+        .update(auth_user.user_id, id, request)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Webhook updated successfully",
+        webhook,
+    )))
+}
+
+async fn delete_webhook(
+    Extension(auth_user): Extension<AuthUser>,
+    State(webhook_service): State<Arc<WebhookService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    webhook_service.delete(auth_user.user_id, id).await?;
+
+    Ok(Json(ApiResponse::<()>::success_no_data(
+        "Webhook deleted successfully",
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeliveryListParams {
+    pub limit: Option<i64>,
+}
+
+async fn list_deliveries(
+    Extension(auth_user): Extension<AuthUser>,
+    State(webhook_service): State<Arc<WebhookService>>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<DeliveryListParams>,
+) -> Result<Json<ApiResponse<Vec<WebhookDeliveryResponse>>>, AppError> {
+    // Default to the last 20 deliveries and never return more than 100
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+
+    let deliveries = webhook_service
+        .list_deliveries(auth_user.user_id, id, limit)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Webhook deliveries retrieved successfully",
+        deliveries,
+    )))
+}
+
+async fn redeliver(
+    Extension(auth_user): Extension<AuthUser>,
+    State(webhook_service): State<Arc<WebhookService>>,
+    Path((id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ApiResponse<WebhookDeliveryResponse>>, AppError> {
+    let delivery = webhook_service
+        .redeliver(auth_user.user_id, id, delivery_id)
+        .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Webhook redelivery attempted",
+        delivery,
+    )))
+}
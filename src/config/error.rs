@@ -0,0 +1,39 @@
+use std::fmt;
+
+/// Why the configuration couldn't be loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A required setting, named by its environment variable, is set nowhere
+    Missing(String),
+    /// `APP_HOST` holds this value, which isn't an IP address
+    InvalidIp(String),
+    /// `APP_PORT` holds this value, which isn't a port number
+    InvalidPort(String),
+    /// Any other setting is invalid, as described
+    Invalid(String),
+    /// The file named by `CONFIG_FILE` can't be read or parsed, as described
+    File(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Missing(name) => write!(f, "{} must be set", name),
+            ConfigError::InvalidIp(value) => {
+                write!(f, "APP_HOST must be a valid IP address, got '{}'", value)
+            }
+            ConfigError::InvalidPort(value) => {
+                write!(f, "APP_PORT must be a valid port number, got '{}'", value)
+            }
+            ConfigError::Invalid(message) | ConfigError::File(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<String> for ConfigError {
+    fn from(message: String) -> Self {
+        ConfigError::Invalid(message)
+    }
+}
@@ -1,9 +1,17 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+#[cfg(not(debug_assertions))]
+use sqlx::migrate::MigrateDatabase;
+use sqlx::migrate::Migrator;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
-use std::time::Duration;
+#[cfg(not(debug_assertions))]
 use sqlx::Postgres;
-use sqlx::migrate::MigrateDatabase;
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// Migrations embedded into the binary at compile time
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
 #[cfg(not(debug_assertions))]
 pub async fn init_db_pool(database_url: &str) -> Result<PgPool> {
@@ -23,7 +31,7 @@ pub async fn init_db_pool(database_url: &str) -> Result<PgPool> {
         .await?;
 
     // Run migrations
-    sqlx::migrate!("./migrations").run(&pool).await?;
+    run_migrations(&pool).await?;
 
     Ok(pool)
 }
@@ -42,7 +50,7 @@ pub async fn init_db_pool(database_url: &str) -> Result<PgPool> {
     match connect_result {
         Ok(pool) => {
             // Run migrations if connected successfully
-            let _ = sqlx::migrate!("./migrations").run(&pool).await;
+            let _ = run_migrations(&pool).await;
             Ok(pool)
         }
         Err(err) => {
@@ -58,3 +66,133 @@ pub async fn init_db_pool(database_url: &str) -> Result<PgPool> {
         }
     }
 }
+
+/// A migration known to this crate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationInfo {
+    /// Version number taken from the migration file name
+    pub version: i64,
+    /// Human-readable description taken from the migration file name
+    pub description: String,
+}
+
+/// A migration applied by a call to `run_migrations`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    /// Time the migration took to execute, in milliseconds
+    pub duration_ms: u64,
+}
+
+/// Outcome of `run_migrations`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationReport {
+    /// Migrations applied by this run, in version order (empty when up to date)
+    pub applied: Vec<AppliedMigration>,
+}
+
+/// Snapshot of the database schema version relative to the embedded migrations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    /// Migrations that completed successfully
+    pub applied: Vec<MigrationInfo>,
+    /// Migrations that have not been applied yet
+    pub pending: Vec<MigrationInfo>,
+    /// Versions whose last attempt failed part-way and need manual attention
+    pub dirty: Vec<i64>,
+}
+
+/// A row of the `_sqlx_migrations` bookkeeping table
+#[derive(sqlx::FromRow)]
+struct MigrationRecord {
+    version: i64,
+    description: String,
+    success: bool,
+    execution_time: i64,
+}
+
+/// Runs all pending migrations against the given pool
+///
+/// This is independent of `init_db_pool` so services embedding this crate can
+/// migrate as part of their own startup. Running it on an up-to-date database
+/// is a no-op that returns an empty report.
+pub async fn run_migrations(pool: &PgPool) -> Result<MigrationReport> {
+    let before: HashSet<i64> = fetch_migration_records(pool)
+        .await?
+        .into_iter()
+        .filter(|record| record.success)
+        .map(|record| record.version)
+        .collect();
+
+    MIGRATOR.run(pool).await?;
+
+    let applied = fetch_migration_records(pool)
+        .await?
+        .into_iter()
+        .filter(|record| record.success && !before.contains(&record.version))
+        .map(|record| AppliedMigration {
+            version: record.version,
+            description: record.description,
+            // execution_time is recorded in nanoseconds
+            duration_ms: u64::try_from(record.execution_time).unwrap_or_default() / 1_000_000,
+        })
+        .collect();
+
+    Ok(MigrationReport { applied })
+}
+
+/// Reports which embedded migrations are applied, pending, or dirty
+pub async fn migration_status(pool: &PgPool) -> Result<MigrationStatus> {
+    let records = fetch_migration_records(pool).await?;
+
+    let succeeded: HashSet<i64> = records
+        .iter()
+        .filter(|record| record.success)
+        .map(|record| record.version)
+        .collect();
+    let dirty = records
+        .iter()
+        .filter(|record| !record.success)
+        .map(|record| record.version)
+        .collect();
+
+    let (applied, pending) = MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| MigrationInfo {
+            version: migration.version,
+            description: migration.description.to_string(),
+        })
+        .partition(|info| succeeded.contains(&info.version));
+
+    Ok(MigrationStatus {
+        applied,
+        pending,
+        dirty,
+    })
+}
+
+/// Reads the migration bookkeeping table, treating a missing table as no history
+async fn fetch_migration_records(pool: &PgPool) -> Result<Vec<MigrationRecord>> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+
+    if !table_exists {
+        return Ok(Vec::new());
+    }
+
+    let records = sqlx::query_as::<_, MigrationRecord>(
+        r#"
+        SELECT version, description, success, execution_time
+        FROM _sqlx_migrations
+        ORDER BY version
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(records)
+}
@@ -6,18 +6,73 @@ pub mod middleware;
 pub mod models;
 pub mod services;
 pub mod utils;
+pub mod workers;
 
 // Re-export important types
-pub use api::accounts::CreateAccountRequest;
-pub use config::Config;
-pub use db::init_db_pool;
-pub use models::account::{Account, AccountResponse};
+pub use api::accounts::{CreateAccountRequest, NameCheckRequest};
+pub use config::{
+    AccountConfig, Config, ConfigError, ConfigFile, DestinationConfig, DiagnosticsConfig,
+    EventStreamConfig, ImportConfig, IntegrityConfig, LoggingConfig, NotificationConfig,
+    SandboxConfig, TransactionConfig, UsageConfig,
+};
+pub use db::{
+    init_db_pool, migration_status, run_migrations, AppliedMigration, MigrationInfo,
+    MigrationReport, MigrationStatus,
+};
+pub use models::account::{Account, AccountLimits, AccountResponse};
+pub use models::balance_history::BalanceSnapshotResponse;
+pub use models::budget::{Budget, BudgetAlert, BudgetPeriod, BudgetStatus, SetBudgetRequest};
 pub use models::decimal::SqlxDecimal;
+pub use models::destination::{DestinationResponse, RegisterDestinationRequest};
+pub use models::exchange_rate::{ExchangeRateResponse, SetExchangeRateRequest};
+pub use models::fee_rule::{FeeRuleResponse, SetFeeRuleRequest};
+pub use models::hold::{
+    CaptureHoldRequest, CreateHoldRequest, Hold, HoldCaptureResponse, HoldResponse, HoldStatus,
+};
+pub use models::idempotency::IdempotencyKey;
+pub use models::import::{ImportRowError, ImportSummary};
+pub use models::integrity::{IntegrityReport, IntegrityViolation, ViolationKind};
+pub use models::outbox::{OutboxEvent, ReplayEventsRequest, ReplayEventsResponse};
+#[cfg(feature = "sandbox")]
+pub use models::sandbox::{FaucetRequest, SandboxPurgeSummary};
+pub use models::scheduled_transfer::{
+    ScheduleTransferRequest, ScheduledTransfer, ScheduledTransferResponse, ScheduledTransferStatus,
+};
+pub use models::standing_order::{
+    CreateStandingOrderRequest, RecurrenceInterval, StandingOrder, StandingOrderResponse,
+    StandingOrderStatus,
+};
+pub use models::statement::{AccountStatement, FinalStatement};
 pub use models::transaction::{
-    CreateTransactionRequest, DepositRequest, Transaction, TransactionResponse, TransactionStatus,
-    TransactionType, TransferRequest, WithdrawalRequest,
+    AdminTransactionResponse, BatchTransfer, BatchTransferRequest, BatchTransferResponse,
+    CheckStatus, CreateTransactionRequest, DepositRequest, PayoutRequest, SplitTransferLeg,
+    SplitTransferRequest, SplitTransferResponse, Transaction, TransactionFilter,
+    TransactionResponse, TransactionReversalResponse, TransactionStatus, TransactionSummary,
+    TransactionTotals, TransactionType, TransferCheck, TransferRequest, TransferValidation,
+    UpdateTransactionRequest, WithdrawalRequest,
+};
+pub use models::user::{
+    CreateUserRequest, LoginOutcome, LoginRequest, LoginResponse, NotificationPreferences,
+    RefreshTokenRequest, TotpSetupResponse, User, UserResponse, UserRole,
+};
+pub use models::webhook::{
+    RegisterWebhookRequest, UpdateWebhookRequest, WebhookDelivery, WebhookDeliveryAttempt,
+    WebhookDeliveryResponse, WebhookPayload, WebhookResponse,
 };
-pub use models::user::{CreateUserRequest, LoginRequest, LoginResponse, User, UserResponse};
 pub use services::account_service::AccountService;
+pub use services::budget_service::BudgetService;
+pub use services::destination_service::DestinationService;
+pub use services::diagnostics_service::DiagnosticsService;
+pub use services::exchange_service::ExchangeService;
+pub use services::fee_service::FeeService;
+pub use services::import_service::ImportService;
+pub use services::integrity_service::IntegrityService;
+pub use services::notification_service::NotificationService;
+pub use services::outbox_service::OutboxService;
+#[cfg(feature = "sandbox")]
+pub use services::sandbox_service::SandboxService;
+pub use services::scheduled_transfer_service::ScheduledTransferService;
+pub use services::standing_order_service::StandingOrderService;
 pub use services::transaction_service::TransactionService;
 pub use services::user_service::UserService;
+pub use services::webhook_service::WebhookService;
@@ -1,30 +1,37 @@
-mod api;
-mod config;
-mod db;
-mod middleware;
-mod models;
-mod services;
-mod utils;
-
-use crate::api::{accounts, transactions, users};
-use crate::config::Config;
-use crate::db::init_db_pool;
-use crate::middleware::auth::auth_middleware;
-use crate::services::{
-    account_service::AccountService, transaction_service::TransactionService,
-    user_service::UserService,
-};
-use axum::{middleware::from_fn_with_state, routing::get, Router};
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
-use tower_http::limit::RequestBodyLimitLayer;
-use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use txn_manager::api::{create_router_with_usage, transaction_feed, transaction_service};
+use txn_manager::services::usage_service::UsageService;
+use txn_manager::workers::integrity::spawn_integrity_worker;
+use txn_manager::workers::outbox::spawn_outbox_publisher;
+use txn_manager::workers::retention::spawn_retention_worker;
+use txn_manager::workers::revocations::spawn_revocation_purge_worker;
+use txn_manager::workers::scheduled_transfers::spawn_scheduled_transfer_worker;
+use txn_manager::workers::standing_orders::spawn_standing_order_worker;
+use txn_manager::workers::usage::spawn_usage_flush_worker;
+use txn_manager::workers::webhooks::spawn_webhook_retry_worker;
+use txn_manager::{
+    init_db_pool, AccountService, Config, DiagnosticsService, IntegrityService, OutboxService,
+    ScheduledTransferService, StandingOrderService, UserService, WebhookService,
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Load configuration
-    let config = Config::from_env();
+    // Load configuration, stopping with the first problem found
+    let config = match Config::try_load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // `txn-manager check` validates the configuration and prints it without starting
+    if std::env::args().nth(1).as_deref() == Some("check") {
+        println!("{:#?}", config.redacted());
+        return Ok(());
+    }
 
     // Initialize logging
     tracing_subscriber::registry()
@@ -57,42 +64,56 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
-    // Initialize services
-    let user_service = Arc::new(UserService::new(pool.clone(), config.jwt_secret.clone()));
-    let account_service = Arc::new(AccountService::new(pool.clone()));
-    let transaction_service = Arc::new(TransactionService::new(
+    // Periodically sweep the database for broken invariants
+    if let Some(interval) = config.integrity.check_interval {
+        let integrity_service = Arc::new(IntegrityService::new(
+            pool.clone(),
+            config.integrity.clone(),
+        ));
+        spawn_integrity_worker(integrity_service, interval);
+    }
+
+    // Processing timings are diagnostics only and kept for a short while
+    if let Some(retention) = config.diagnostics.timing_retention {
+        let diagnostics_service = Arc::new(DiagnosticsService::new(pool.clone()));
+        spawn_retention_worker(diagnostics_service, retention);
+    }
+
+    // Revoked access tokens only need remembering until they expire
+    let user_service = Arc::new(UserService::new(
         pool.clone(),
-        AccountService::new(pool.clone()),
+        config.jwt_secret.clone(),
+        AccountService::new(pool.clone(), config.accounts.clone()),
     ));
+    spawn_revocation_purge_worker(user_service);
+
+    // Transactions the workers book reach the live event streams too
+    let feed = transaction_feed(&config);
 
-    // Configure CORS
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    // Scheduled transfers and standing orders run once due, including any
+    // that came due while stopped
+    let transaction_service = transaction_service(&config, &pool, &feed);
+    let scheduled_transfer_service = Arc::new(ScheduledTransferService::new(
+        pool.clone(),
+        transaction_service.clone(),
+    ));
+    spawn_scheduled_transfer_worker(scheduled_transfer_service);
+    let standing_order_service =
+        Arc::new(StandingOrderService::new(pool.clone(), transaction_service));
+    spawn_standing_order_worker(standing_order_service);
+
+    // Webhook deliveries the endpoint didn't accept are retried on a schedule
+    spawn_webhook_retry_worker(Arc::new(WebhookService::new(pool.clone())));
+
+    // Events recorded alongside committed changes are published in the background
+    spawn_outbox_publisher(Arc::new(OutboxService::new(pool.clone())));
+
+    // API usage is counted in memory and flushed periodically
+    let usage_service = Arc::new(UsageService::new(pool.clone()));
+    spawn_usage_flush_worker(usage_service.clone(), config.usage.flush_interval);
 
     // Create router
-    let app = Router::new()
-        .route("/", get(health_check))
-        .nest("/api/v1/users", users::user_routes(user_service.clone()))
-        .nest(
-            "/api/v1/accounts",
-            accounts::account_routes(account_service.clone()).route_layer(from_fn_with_state(
-                config.jwt_secret.clone(),
-                auth_middleware,
-            )),
-        )
-        .nest(
-            "/api/v1/transactions",
-            transactions::transaction_routes(transaction_service.clone(), account_service.clone())
-                .route_layer(from_fn_with_state(
-                    config.jwt_secret.clone(),
-                    auth_middleware,
-                )),
-        )
-        .layer(cors)
-        .layer(TraceLayer::new_for_http())
-        .layer(RequestBodyLimitLayer::new(1024 * 1024)); // 1MB limit
+    let app = create_router_with_usage(&config, pool, usage_service.clone(), feed);
 
     // Start server
     let addr = config.server_addr();
@@ -100,11 +121,44 @@ async fn main() -> anyhow::Result<()> {
 
     // Bind to the address and serve the app
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // Connection info is needed to record client IP addresses
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
+
+    // Write out usage counted since the last periodic flush
+    if let Err(e) = usage_service.flush().await {
+        tracing::error!("Final API usage flush failed: {}", e);
+    }
 
     Ok(())
 }
 
-async fn health_check() -> &'static str {
-    "OK"
+/// Resolves when the process is asked to stop (Ctrl+C or SIGTERM)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutdown signal received, draining connections");
 }
@@ -1,12 +1,14 @@
+use crate::models::user::UserRole;
+use crate::services::user_service::{access_token_id, UserService};
 use crate::utils::auth::validate_jwt;
 use crate::utils::error::AppError;
-use axum::extract::FromRef;
-use axum::http::header;
+use axum::http::{header, HeaderMap};
 use axum::{
     extract::{Request, State},
     middleware::Next,
     response::Response,
 };
+use std::sync::Arc;
 use uuid::Uuid;
 
 /// Represents an authenticated user
@@ -16,29 +18,55 @@ pub struct AuthUser {
     pub user_id: Uuid,
     /// The username of the authenticated user
     pub username: String,
+    /// The privilege level of the user at the time the token was issued
+    pub role: String,
 }
 
-pub async fn auth_middleware<AppState>(
-    State(state): State<AppState>,
+impl AuthUser {
+    /// Returns whether the user holds the ADMIN role
+    pub fn is_admin(&self) -> bool {
+        self.role == UserRole::ADMIN.to_string()
+    }
+}
+
+/// What `auth_middleware` needs to accept a token
+#[derive(Clone)]
+pub struct AuthState {
+    /// Secret the access tokens are signed with
+    pub jwt_secret: String,
+    /// Knows which tokens were revoked at logout
+    pub user_service: Arc<UserService>,
+}
+
+pub async fn auth_middleware(
+    State(state): State<AuthState>,
     mut request: Request,
     next: Next,
-) -> Result<Response, AppError>
-where
-    String: FromRef<AppState>,
-{
-    let jwt_secret = String::from_ref(&state);
-
+) -> Result<Response, AppError> {
     // Extract token from Authorization header
-    let token = extract_token_from_header(&request)?;
+    let token = bearer_token(request.headers())?;
 
     // Validate token
-    let token_data = validate_jwt(&token, &jwt_secret)?;
+    let token_data = validate_jwt(&token, &state.jwt_secret)?;
+
+    let user_id = Uuid::parse_str(&token_data.claims.sub)
+        .map_err(|_| AppError::Auth("Invalid user ID in token".to_string()))?;
+
+    // Tokens revoked at logout, or of erased users, stop working before they expire
+    let token_id = access_token_id(&token_data.claims)?;
+    if state
+        .user_service
+        .is_token_revoked(token_id, user_id)
+        .await?
+    {
+        return Err(AppError::Auth("Token has been revoked".to_string()));
+    }
 
     // Create AuthUser from claims
     let auth_user = AuthUser {
-        user_id: Uuid::parse_str(&token_data.claims.sub)
-            .map_err(|_| AppError::Auth("Invalid user ID in token".to_string()))?,
+        user_id,
         username: token_data.claims.username,
+        role: token_data.claims.role,
     };
 
     // Set auth_user as request extension
@@ -48,9 +76,28 @@ where
     Ok(next.run(request).await)
 }
 
-fn extract_token_from_header(request: &Request) -> Result<String, AppError> {
-    let auth_header = request
-        .headers()
+/// Rejects requests from users without the ADMIN role
+///
+/// Must be layered inside `auth_middleware`, which provides the `AuthUser`.
+pub async fn require_admin(request: Request, next: Next) -> Result<Response, AppError> {
+    let is_admin = request
+        .extensions()
+        .get::<AuthUser>()
+        .map(AuthUser::is_admin)
+        .unwrap_or(false);
+
+    if !is_admin {
+        return Err(AppError::Forbidden(
+            "Administrator privileges are required".to_string(),
+        ));
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Extracts the token from the `Authorization: Bearer` header
+pub fn bearer_token(headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
         .get(header::AUTHORIZATION)
         .ok_or_else(|| AppError::Auth("Missing authorization header".to_string()))?
         .to_str()
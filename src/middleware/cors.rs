@@ -0,0 +1,93 @@
+use crate::middleware::request_context::REQUEST_ID_HEADER;
+use crate::models::idempotency::IDEMPOTENCY_KEY_HEADER;
+use crate::utils::currency::AMOUNT_UNITS_HEADER;
+use axum::http::{header, HeaderName, HeaderValue, Method, Uri};
+use std::str::FromStr;
+use tower_http::cors::{AllowHeaders, AllowOrigin, Any, CorsLayer};
+
+/// Origins browsers may call the API from
+///
+/// No origins, the default, allows every origin but without credentials, so
+/// browsers won't send cookies or HTTP authentication along.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AllowedOrigins(Vec<HeaderValue>);
+
+impl AllowedOrigins {
+    /// Whether every origin is allowed
+    pub fn is_any(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Builds the CORS layer answering preflight requests and tagging responses
+    ///
+    /// Specific origins may send credentials, but only with the methods and
+    /// headers the API uses.
+    pub fn cors_layer(&self) -> CorsLayer {
+        if self.is_any() {
+            return CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any);
+        }
+
+        let api_headers = [
+            IDEMPOTENCY_KEY_HEADER,
+            AMOUNT_UNITS_HEADER,
+            REQUEST_ID_HEADER,
+        ]
+        .map(|name| HeaderName::try_from(name).expect("Invalid header name"));
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(self.0.iter().cloned()))
+            .allow_credentials(true)
+            .allow_methods([
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ])
+            .allow_headers(AllowHeaders::list(
+                [header::AUTHORIZATION, header::CONTENT_TYPE]
+                    .into_iter()
+                    .chain(api_headers),
+            ))
+    }
+}
+
+impl FromStr for AllowedOrigins {
+    type Err = String;
+
+    /// Parses a comma-separated list of origins such as `https://app.example.com`;
+    /// an empty list or `*` allows every origin
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let origins: Vec<&str> = value
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        if origins == ["*"] {
+            return Ok(Self::default());
+        }
+
+        origins
+            .into_iter()
+            .map(parse_origin)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Parses an origin, a scheme and a host with an optional port
+fn parse_origin(origin: &str) -> Result<HeaderValue, String> {
+    let invalid = || format!("Invalid origin '{}'", origin);
+    let origin = origin.trim_end_matches('/');
+    let uri: Uri = origin.parse().map_err(|_| invalid())?;
+    let is_origin = matches!(uri.scheme_str(), Some("http" | "https"))
+        && uri.host().is_some()
+        && matches!(uri.path(), "" | "/")
+        && uri.query().is_none();
+    if !is_origin {
+        return Err(invalid());
+    }
+    HeaderValue::from_str(origin).map_err(|_| invalid())
+}
@@ -0,0 +1,78 @@
+use crate::utils::response::TotalCount;
+use axum::body::{to_bytes, Body};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::{extract::Request, middleware::Next, response::Response};
+use serde_json::Value;
+
+/// Unwraps the `{status, message, data}` envelope from successful responses
+///
+/// Used for the `/api/v2` tree, which shares its handlers with `/api/v1`:
+/// - a success with `data` returns the resource JSON directly
+/// - a success without `data` becomes `204 No Content`
+/// - a page of a listing (marked by a `TotalCount` response extension) returns
+///   its bare items, with the count in an `X-Total-Count` header and any
+///   `next_cursor` in an `X-Next-Cursor` header
+///
+/// Error responses already use the bare `ErrorResponse` shape and pass through.
+pub async fn strip_envelope(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !response.status().is_success() || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to buffer response body: {}", e);
+            return Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap_or_default();
+        }
+    };
+
+    // Anything that isn't an envelope is passed through untouched
+    let mut data = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(Value::Object(mut envelope)) if envelope.contains_key("status") => {
+            envelope.remove("data")
+        }
+        _ => return Response::from_parts(parts, Body::from(bytes)),
+    };
+
+    if let Some(TotalCount(total)) = parts.extensions.get::<TotalCount>().copied() {
+        parts
+            .headers
+            .insert("x-total-count", HeaderValue::from(total));
+
+        // Pagination metadata travels in headers, so only the items remain
+        if let Some(Value::Object(page)) = &mut data {
+            if let Some(cursor) = page
+                .get("next_cursor")
+                .and_then(Value::as_str)
+                .and_then(|cursor| HeaderValue::from_str(cursor).ok())
+            {
+                parts.headers.insert("x-next-cursor", cursor);
+            }
+            let items = page.remove("items");
+            data = items;
+        }
+    }
+    // The length of the original envelope no longer applies
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    match data {
+        Some(data) => Response::from_parts(parts, Body::from(data.to_string())),
+        None => {
+            parts.status = StatusCode::NO_CONTENT;
+            parts.headers.remove(header::CONTENT_TYPE);
+            Response::from_parts(parts, Body::empty())
+        }
+    }
+}
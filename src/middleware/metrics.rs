@@ -0,0 +1,247 @@
+use crate::services::integrity_service::{CHECKS_WITH_VIOLATIONS, LAST_CHECK_VIOLATIONS};
+use axum::{
+    extract::{MatchedPath, Request},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Instant;
+
+/// Content type of the Prometheus text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds, in seconds, of the request latency histogram buckets
+pub const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Route label of requests that matched no route, so unknown paths can't
+/// multiply the number of series
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Status code classes requests are counted in
+const STATUS_CLASSES: [&str; 5] = ["1xx", "2xx", "3xx", "4xx", "5xx"];
+
+/// Metrics of the process since startup, exported at `GET /metrics`
+///
+/// Series are created on first use and counted with atomics afterwards, so
+/// recording takes a shared lock and a few relaxed increments on the hot path.
+#[derive(Default)]
+pub struct Metrics {
+    /// Keyed by method, then route pattern
+    routes: Series<RouteMetrics>,
+    /// Keyed by transaction type, then status
+    transactions: Series<AtomicU64>,
+}
+
+/// Series keyed by two labels, nested so lookups don't allocate
+type Series<T> = RwLock<HashMap<String, HashMap<String, Arc<T>>>>;
+
+/// Request counters of one method and route pattern
+#[derive(Default)]
+struct RouteMetrics {
+    /// Requests per status class, in the order of `STATUS_CLASSES`
+    statuses: [AtomicU64; 5],
+    /// Requests no slower than each of `LATENCY_BUCKETS`, not cumulative
+    buckets: [AtomicU64; 11],
+    /// Total latency in microseconds
+    latency_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// The process-wide metrics registry
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    /// Counts a finished request towards its route
+    pub fn record_request(&self, method: &str, route: &str, status: u16, elapsed_secs: f64) {
+        let route_metrics = get_or_insert(&self.routes, method, route);
+        let class = usize::from(status / 100).checked_sub(1);
+        if let Some(counter) = class.and_then(|class| route_metrics.statuses.get(class)) {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(bucket) = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| elapsed_secs <= *bound)
+        {
+            route_metrics.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        route_metrics
+            .latency_micros
+            .fetch_add((elapsed_secs * 1_000_000.0) as u64, Ordering::Relaxed);
+        route_metrics.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a committed transaction, or one that changed status
+    pub fn record_transaction(&self, transaction_type: &str, status: &str) {
+        get_or_insert(&self.transactions, transaction_type, status).fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Renders every series in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let routes = sorted(&self.routes);
+        out.push_str("# HELP http_requests_total Requests handled, by route and status class\n");
+        out.push_str("# TYPE http_requests_total counter\n");
+        for ((method, route), route_metrics) in &routes {
+            for (class, counter) in STATUS_CLASSES.iter().zip(&route_metrics.statuses) {
+                let count = counter.load(Ordering::Relaxed);
+                if count > 0 {
+                    let _ = writeln!(
+                        out,
+                        "http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                        method,
+                        escape(route),
+                        class,
+                        count
+                    );
+                }
+            }
+        }
+
+        out.push_str("# HELP http_request_duration_seconds Time taken to handle requests\n");
+        out.push_str("# TYPE http_request_duration_seconds histogram\n");
+        for ((method, route), route_metrics) in &routes {
+            let labels = format!("method=\"{}\",route=\"{}\"", method, escape(route));
+            let mut cumulative = 0;
+            for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&route_metrics.buckets) {
+                cumulative += bucket.load(Ordering::Relaxed);
+                let _ = writeln!(
+                    out,
+                    "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, cumulative
+                );
+            }
+            let count = route_metrics.count.load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, count
+            );
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_sum{{{}}} {}",
+                labels,
+                route_metrics.latency_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+            );
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_count{{{}}} {}",
+                labels, count
+            );
+        }
+
+        out.push_str("# HELP transactions_total Committed transactions, by type and status\n");
+        out.push_str("# TYPE transactions_total counter\n");
+        for ((transaction_type, status), counter) in sorted(&self.transactions) {
+            let _ = writeln!(
+                out,
+                "transactions_total{{type=\"{}\",status=\"{}\"}} {}",
+                transaction_type,
+                status,
+                counter.load(Ordering::Relaxed)
+            );
+        }
+
+        out.push_str(
+            "# HELP integrity_last_check_violations Violations found by the latest integrity check\n",
+        );
+        out.push_str("# TYPE integrity_last_check_violations gauge\n");
+        let _ = writeln!(
+            out,
+            "integrity_last_check_violations {}",
+            LAST_CHECK_VIOLATIONS.load(Ordering::Relaxed)
+        );
+        out.push_str(
+            "# HELP integrity_checks_with_violations_total Integrity checks that found violations\n",
+        );
+        out.push_str("# TYPE integrity_checks_with_violations_total counter\n");
+        let _ = writeln!(
+            out,
+            "integrity_checks_with_violations_total {}",
+            CHECKS_WITH_VIOLATIONS.load(Ordering::Relaxed)
+        );
+
+        out
+    }
+}
+
+/// Counts each request by method, matched route pattern and status class, and
+/// times it
+///
+/// Must be added with `route_layer` so the matched route is known. Like usage,
+/// requests are labelled with the route pattern (`/api/v1/accounts/:id`) rather
+/// than the raw path.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().clone();
+    let route = request.extensions().get::<MatchedPath>().cloned();
+
+    let response = next.run(request).await;
+
+    metrics().record_request(
+        method.as_str(),
+        route.as_ref().map_or(UNMATCHED_ROUTE, MatchedPath::as_str),
+        response.status().as_u16(),
+        started.elapsed().as_secs_f64(),
+    );
+    response
+}
+
+/// Serves the metrics for Prometheus to scrape
+pub async fn export_metrics() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], metrics().render())
+}
+
+/// Returns the series labelled `first` and `second`, creating it on first use
+fn get_or_insert<T: Default>(series: &Series<T>, first: &str, second: &str) -> Arc<T> {
+    if let Some(existing) = series
+        .read()
+        .unwrap()
+        .get(first)
+        .and_then(|by_second| by_second.get(second))
+    {
+        return existing.clone();
+    }
+    series
+        .write()
+        .unwrap()
+        .entry(first.to_string())
+        .or_default()
+        .entry(second.to_string())
+        .or_default()
+        .clone()
+}
+
+/// The series in label order, so scrapes list them stably
+fn sorted<T>(series: &Series<T>) -> Vec<((String, String), Arc<T>)> {
+    let mut series: Vec<_> = series
+        .read()
+        .unwrap()
+        .iter()
+        .flat_map(|(first, by_second)| {
+            by_second
+                .iter()
+                .map(|(second, value)| ((first.clone(), second.clone()), value.clone()))
+        })
+        .collect();
+    series.sort_by(|a, b| a.0.cmp(&b.0));
+    series
+}
+
+/// Escapes a label value as the exposition format requires
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
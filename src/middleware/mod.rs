@@ -1 +1,7 @@
 pub mod auth;
+pub mod cors;
+pub mod envelope;
+pub mod metrics;
+pub mod request_context;
+pub mod request_log;
+pub mod usage;
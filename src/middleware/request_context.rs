@@ -0,0 +1,243 @@
+use axum::async_trait;
+use axum::extract::{ConnectInfo, FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::{header, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+/// Header carrying the ID of a request, echoed on every response
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest request ID accepted from a client before a new one is generated
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+/// Information about the client that issued a request
+///
+/// Captured for audit trails such as login history and the origin of money
+/// movements. All fields are optional because the server may run without
+/// connection info or `stamp_requests` (e.g. in tests) and clients are not
+/// required to send a User-Agent.
+#[derive(Clone, Debug, Default)]
+pub struct RequestContext {
+    /// IP address of the client
+    pub ip_address: Option<String>,
+    /// Value of the User-Agent header
+    pub user_agent: Option<String>,
+    /// ID assigned to the request by `stamp_requests`
+    pub request_id: Option<String>,
+    /// When the server first saw the request
+    pub received_at: Option<DateTime<Utc>>,
+}
+
+/// Identity and arrival time of a request, added as an extension by `stamp_requests`
+#[derive(Clone, Debug)]
+pub struct RequestStamp {
+    pub request_id: String,
+    pub received_at: DateTime<Utc>,
+}
+
+tokio::task_local! {
+    /// ID of the request the current task is handling, set by `stamp_requests`
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// Returns the ID of the request being handled, for code without access to
+/// the request such as `AppError::into_response`
+///
+/// `None` outside `stamp_requests`, including on tasks spawned by a handler.
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Notes when each request arrived and gives it an ID
+///
+/// Meant to be the outermost layer, so `received_at` includes the time spent
+/// in every other middleware. A client-supplied `X-Request-Id` of up to 64
+/// letters, digits, `-`, `_` or `.` is kept so requests can be followed across
+/// services; otherwise a new ID is generated. The ID is returned in the
+/// `X-Request-Id` response header, and in the body of error responses.
+pub async fn stamp_requests(mut request: Request, next: Next) -> Response {
+    let received_at = Utc::now();
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    request.extensions_mut().insert(RequestStamp {
+        request_id: request_id.clone(),
+        received_at,
+    });
+    let mut response = CURRENT_REQUEST_ID
+        .scope(request_id.clone(), next.run(request))
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// A network range in CIDR notation (e.g. `10.0.0.0/8` or `fd00::/8`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpCidr {
+    /// Returns whether the address falls inside this range
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = String;
+
+    /// Parses `address/prefix`; a bare address is treated as a single host
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let (address, prefix) = match value.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (value, None),
+        };
+
+        let network = IpAddr::from_str(address)
+            .map_err(|_| format!("Invalid IP address in CIDR range '{}'", value))?
+            .to_canonical();
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max_prefix)
+                .ok_or_else(|| format!("Invalid prefix length in CIDR range '{}'", value))?,
+            None => max_prefix,
+        };
+
+        Ok(Self { network, prefix })
+    }
+}
+
+/// Reverse proxies whose `X-Forwarded-For` header is trusted
+///
+/// Added to the router as an extension. When the direct peer is not in one of
+/// these ranges, forwarding headers are ignored because any client can set them.
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies(Vec<IpCidr>);
+
+impl TrustedProxies {
+    /// Returns whether the address belongs to a trusted proxy
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|range| range.contains(ip))
+    }
+
+    /// Determines the client address from the direct peer and `X-Forwarded-For`
+    ///
+    /// The header is walked from right to left, skipping trusted proxies, so the
+    /// result is the nearest address that was not added by our own
+    /// infrastructure. Entries further left are client-controlled and ignored.
+    pub fn resolve_client_ip(&self, peer: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+        if !self.contains(peer) {
+            return peer;
+        }
+
+        let mut client = peer;
+        for hop in forwarded_for.unwrap_or_default().rsplit(',') {
+            match IpAddr::from_str(hop.trim()) {
+                Ok(ip) => {
+                    client = ip.to_canonical();
+                    if !self.contains(client) {
+                        break;
+                    }
+                }
+                // An unparseable hop means the chain can't be followed any further
+                Err(_) => break,
+            }
+        }
+
+        client
+    }
+}
+
+impl FromStr for TrustedProxies {
+    type Err = String;
+
+    /// Parses a comma-separated list of CIDR ranges; an empty list trusts no one
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .split(',')
+            .filter(|range| !range.trim().is_empty())
+            .map(IpCidr::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestContext
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let peer = ConnectInfo::<SocketAddr>::from_request_parts(parts, state)
+            .await
+            .ok()
+            .map(|ConnectInfo(addr)| addr.ip().to_canonical());
+
+        let forwarded_for = parts
+            .headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok());
+
+        let ip_address = peer.map(|peer| {
+            parts
+                .extensions
+                .get::<TrustedProxies>()
+                .map(|proxies| proxies.resolve_client_ip(peer, forwarded_for))
+                .unwrap_or(peer)
+                .to_string()
+        });
+
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        let stamp = parts.extensions.get::<RequestStamp>();
+
+        Ok(Self {
+            ip_address,
+            user_agent,
+            request_id: stamp.map(|stamp| stamp.request_id.clone()),
+            received_at: stamp.map(|stamp| stamp.received_at),
+        })
+    }
+}
@@ -0,0 +1,208 @@
+use crate::utils::error::AppError;
+use axum::body::{to_bytes, Body};
+use axum::http::{header, HeaderMap, Uri};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::Level;
+
+/// Target of the request and response log events, e.g. `RUST_LOG=http_log=debug`
+pub const LOG_TARGET: &str = "http_log";
+
+/// Written in place of the value of a sensitive field
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Field names always redacted; `LOG_REDACT_FIELDS` can only add to them
+pub const DEFAULT_REDACTED_FIELDS: [&str; 6] = [
+    "password",
+    "token",
+    "api_key",
+    "secret",
+    "authorization",
+    "cookie",
+];
+
+/// Hides sensitive values in logged headers, query strings and JSON bodies
+///
+/// A name is sensitive if it contains one of the configured fields, ignoring
+/// case and treating `-` as `_`. So `password` also covers `new_password`,
+/// and `api_key` covers the `X-Api-Key` header.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    fields: Vec<String>,
+}
+
+impl Redactor {
+    /// Creates a redactor for the given field names
+    pub fn new<S: AsRef<str>>(fields: &[S]) -> Self {
+        Self {
+            fields: fields
+                .iter()
+                .map(|field| normalize_name(field.as_ref()))
+                .filter(|field| !field.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Whether values under this field, header or parameter name are hidden
+    pub fn is_sensitive(&self, name: &str) -> bool {
+        let name = normalize_name(name);
+        self.fields
+            .iter()
+            .any(|field| name.contains(field.as_str()))
+    }
+
+    /// Replaces sensitive values anywhere in a JSON document
+    pub fn redact_json(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.is_sensitive(key) {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_json(value);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_json(item)),
+            _ => {}
+        }
+    }
+
+    /// Renders headers for logging, hiding the values of sensitive ones
+    pub fn redact_headers(&self, headers: &HeaderMap) -> String {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.is_sensitive(name.as_str()) {
+                    REDACTED
+                } else {
+                    value.to_str().unwrap_or("<binary>")
+                };
+                format!("{}: {}", name, value)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Renders a URI for logging, hiding the values of sensitive query parameters
+    pub fn redact_uri(&self, uri: &Uri) -> String {
+        let Some(query) = uri.query() else {
+            return uri.path().to_string();
+        };
+
+        let query = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _)) if self.is_sensitive(name) => format!("{}={}", name, REDACTED),
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        format!("{}?{}", uri.path(), query)
+    }
+
+    /// Renders a body for logging
+    ///
+    /// Only JSON bodies are shown, with sensitive fields hidden. Anything else,
+    /// including JSON that doesn't parse, is described by its size alone since
+    /// it can't be redacted reliably.
+    pub fn redact_body(&self, bytes: &[u8]) -> String {
+        if bytes.is_empty() {
+            return String::new();
+        }
+
+        match serde_json::from_slice::<Value>(bytes) {
+            Ok(mut value) => {
+                self.redact_json(&mut value);
+                value.to_string()
+            }
+            Err(_) => format!("<{} bytes>", bytes.len()),
+        }
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(&DEFAULT_REDACTED_FIELDS)
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.trim().to_ascii_lowercase().replace('-', "_")
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"))
+}
+
+/// Logs each request and response at debug level, with sensitive values redacted
+///
+/// Does nothing unless debug logging is enabled for `LOG_TARGET`. Only JSON
+/// bodies are buffered and logged; uploads and streamed downloads pass through
+/// untouched and are logged without their bodies.
+pub async fn log_requests(
+    State(redactor): State<Arc<Redactor>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !tracing::enabled!(target: LOG_TARGET, Level::DEBUG) {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let (body, logged_body) = match read_body(&redactor, &parts.headers, body).await {
+        Ok(read) => read,
+        Err(e) => return e.into_response(),
+    };
+    tracing::debug!(
+        target: LOG_TARGET,
+        method = %parts.method,
+        uri = %redactor.redact_uri(&parts.uri),
+        headers = %redactor.redact_headers(&parts.headers),
+        body = %logged_body,
+        "HTTP request"
+    );
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+
+    let (parts, body) = response.into_parts();
+    let (body, logged_body) = match read_body(&redactor, &parts.headers, body).await {
+        Ok(read) => read,
+        Err(e) => return e.into_response(),
+    };
+    tracing::debug!(
+        target: LOG_TARGET,
+        status = %parts.status,
+        headers = %redactor.redact_headers(&parts.headers),
+        body = %logged_body,
+        "HTTP response"
+    );
+
+    Response::from_parts(parts, body)
+}
+
+/// Buffers a JSON body and renders it for logging; other bodies are left as they are
+async fn read_body(
+    redactor: &Redactor,
+    headers: &HeaderMap,
+    body: Body,
+) -> Result<(Body, String), AppError> {
+    if !is_json(headers) {
+        return Ok((body, String::new()));
+    }
+
+    // Request bodies are already capped by the router's RequestBodyLimitLayer
+    let bytes = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Failed to read body: {}", e)))?;
+    let logged = redactor.redact_body(&bytes);
+    Ok((Body::from(bytes), logged))
+}
@@ -0,0 +1,28 @@
+use crate::middleware::auth::AuthUser;
+use crate::services::usage_service::UsageAggregator;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use std::sync::Arc;
+
+/// Counts the request towards the caller's API usage
+///
+/// Must run after `auth_middleware`; requests without an `AuthUser` are not
+/// counted. The matched route pattern (`/api/v1/accounts/:id`) is recorded
+/// rather than the raw path, so ids never multiply the number of counters.
+pub async fn record_usage(
+    State(aggregator): State<Arc<UsageAggregator>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let auth_user = request.extensions().get::<AuthUser>();
+    let route = request.extensions().get::<MatchedPath>();
+    if let (Some(auth_user), Some(route)) = (auth_user, route) {
+        aggregator.record(auth_user.user_id, route.as_str(), Utc::now().date_naive());
+    }
+
+    next.run(request).await
+}
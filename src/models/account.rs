@@ -5,16 +5,136 @@ use sqlx::FromRow;
 use uuid::Uuid;
 
 use crate::models::decimal::SqlxDecimal;
+use crate::utils::currency::currency_scale;
+use crate::utils::error::AppError;
+use crate::utils::response::SelectableFields;
 
 // Use the Decimal type implementations in transaction.rs
 // We don't need to reimplement them here since they're now in the crate
 
+/// Error code for moving money into or out of a closed account
+pub const ACCOUNT_CLOSED: &str = "ACCOUNT_CLOSED";
+
+/// Most decimal places an account's balance may be kept in
+pub const MAX_BALANCE_SCALE: u32 = 8;
+
+/// Decimal places an account accepts amounts in
+///
+/// Accounts opened without a `balance_scale` use their currency's.
+pub fn balance_scale(balance_scale: Option<i16>, currency: &str) -> u32 {
+    balance_scale
+        .and_then(|scale| u32::try_from(scale).ok())
+        .unwrap_or_else(|| currency_scale(currency))
+}
+
+/// Whether an account may move money
+///
+/// - ACTIVE: The account works normally
+/// - FROZEN: Suspended, e.g. during a fraud investigation; its funds stay put
+/// - CLOSED: Closed for good, e.g. after being merged into another account
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum AccountStatus {
+    ACTIVE,
+    FROZEN,
+    CLOSED,
+}
+
+impl std::fmt::Display for AccountStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountStatus::ACTIVE => write!(f, "ACTIVE"),
+            AccountStatus::FROZEN => write!(f, "FROZEN"),
+            AccountStatus::CLOSED => write!(f, "CLOSED"),
+        }
+    }
+}
+
+/// Rejects moving money into or out of an account that isn't ACTIVE
+///
+/// Frozen accounts fail with `BadRequest`, closed ones with `ACCOUNT_CLOSED`.
+pub fn ensure_account_active(id: Uuid, status: &str) -> Result<(), AppError> {
+    match status {
+        "ACTIVE" => Ok(()),
+        "CLOSED" => Err(AppError::Rejected {
+            code: ACCOUNT_CLOSED,
+            message: format!("Account {} is closed", id),
+        }),
+        _ => Err(AppError::BadRequest("Account is frozen".to_string())),
+    }
+}
+
+/// Caps on the money an account may send; `None` means no cap
+///
+/// Withdrawals, payouts and transfers out of the account count towards the
+/// first two; the daily withdrawal and transfer limits each count only their
+/// own kind, per calendar day in UTC.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountLimits {
+    /// Largest amount a single withdrawal or transfer may move
+    pub per_transaction_limit: Option<Decimal>,
+    /// Most the account may send within any 24 hours
+    pub daily_limit: Option<Decimal>,
+    /// Most the account may withdraw, payouts included, per UTC day
+    #[serde(default)]
+    pub daily_withdrawal_limit: Option<Decimal>,
+    /// Most the account may transfer out per UTC day
+    #[serde(default)]
+    pub daily_transfer_limit: Option<Decimal>,
+}
+
+/// An account's limits as stored in the database
+#[derive(Debug, FromRow)]
+pub struct AccountLimitsRow {
+    pub per_transaction_limit: Option<SqlxDecimal>,
+    pub daily_limit: Option<SqlxDecimal>,
+    pub daily_withdrawal_limit: Option<SqlxDecimal>,
+    pub daily_transfer_limit: Option<SqlxDecimal>,
+}
+
+impl From<AccountLimitsRow> for AccountLimits {
+    fn from(row: AccountLimitsRow) -> Self {
+        Self {
+            per_transaction_limit: row.per_transaction_limit.map(Decimal::from),
+            daily_limit: row.daily_limit.map(Decimal::from),
+            daily_withdrawal_limit: row.daily_withdrawal_limit.map(Decimal::from),
+            daily_transfer_limit: row.daily_transfer_limit.map(Decimal::from),
+        }
+    }
+}
+
+impl AccountLimits {
+    /// Rejects caps that are zero or negative
+    pub fn validate(&self) -> Result<(), AppError> {
+        for (name, limit) in [
+            ("per_transaction_limit", self.per_transaction_limit),
+            ("daily_limit", self.daily_limit),
+            ("daily_withdrawal_limit", self.daily_withdrawal_limit),
+            ("daily_transfer_limit", self.daily_transfer_limit),
+        ] {
+            if limit.is_some_and(|limit| limit <= Decimal::ZERO) {
+                return Err(AppError::Validation(format!("{} must be positive", name)));
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Account {
     pub id: Uuid,
     pub user_id: Uuid,
+    /// Customer-facing account number (absent on accounts opened before numbers existed)
+    pub account_number: Option<String>,
     pub balance: SqlxDecimal,
     pub currency: String,
+    /// Decimal places the account accepts, if it differs from the currency's
+    pub balance_scale: Option<i16>,
+    /// Current status as a string (ACTIVE, FROZEN, CLOSED)
+    pub status: String,
+    /// When the account was closed; closed accounts keep their history but
+    /// can't send or receive money
+    pub closed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -23,9 +143,32 @@ pub struct Account {
 pub struct AccountResponse {
     pub id: Uuid,
     pub user_id: Uuid,
+    pub account_number: Option<String>,
     pub balance: Decimal,
     pub currency: String,
+    /// Decimal places the account accepts amounts in
+    pub balance_scale: u32,
+    pub status: String,
+    /// Only present on closed accounts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// Set when `balance` is the balance held at this past time rather than now
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance_as_of: Option<DateTime<Utc>>,
+}
+
+impl SelectableFields for AccountResponse {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "user_id",
+        "account_number",
+        "balance",
+        "currency",
+        "balance_scale",
+        "status",
+        "created_at",
+    ];
 }
 
 impl From<Account> for AccountResponse {
@@ -33,9 +176,25 @@ impl From<Account> for AccountResponse {
         Self {
             id: account.id,
             user_id: account.user_id,
+            account_number: account.account_number,
             balance: account.balance.into(),
+            balance_scale: balance_scale(account.balance_scale, &account.currency),
             currency: account.currency,
+            status: account.status,
+            closed_at: account.closed_at,
             created_at: account.created_at,
+            balance_as_of: None,
         }
     }
 }
+
+/// Result of merging a duplicate account into another one
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountMergeResponse {
+    /// The merged account, now closed with a zero balance
+    pub source: AccountResponse,
+    /// The account that took over the source's transactions and balance
+    pub target: AccountResponse,
+    /// Transactions moved from the source to the target
+    pub transactions_moved: u64,
+}
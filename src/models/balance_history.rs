@@ -0,0 +1,41 @@
+use crate::models::decimal::SqlxDecimal;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Query parameters bounding a balance history; both ends are inclusive
+#[derive(Debug, Default, Deserialize)]
+pub struct BalanceHistoryParams {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// An account balance right after a change, as stored in the database
+#[derive(Debug, FromRow)]
+pub struct BalanceSnapshot {
+    pub transaction_id: Option<Uuid>,
+    pub balance: SqlxDecimal,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One point of an account's balance history
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BalanceSnapshotResponse {
+    /// The transaction that changed the balance
+    pub transaction_id: Option<Uuid>,
+    /// Balance right after the change
+    pub balance: Decimal,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<BalanceSnapshot> for BalanceSnapshotResponse {
+    fn from(snapshot: BalanceSnapshot) -> Self {
+        Self {
+            transaction_id: snapshot.transaction_id,
+            balance: snapshot.balance.into(),
+            created_at: snapshot.created_at,
+        }
+    }
+}
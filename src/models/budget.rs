@@ -0,0 +1,126 @@
+use crate::models::decimal::SqlxDecimal;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Longest category name accepted, matching the database column
+pub const MAX_CATEGORY_LENGTH: usize = 64;
+
+/// The calendar period a budget limit applies to, in UTC
+///
+/// - WEEKLY: Monday to Sunday
+/// - MONTHLY: The first to the last day of the month
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    WEEKLY,
+    MONTHLY,
+}
+
+impl std::fmt::Display for BudgetPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetPeriod::WEEKLY => write!(f, "WEEKLY"),
+            BudgetPeriod::MONTHLY => write!(f, "MONTHLY"),
+        }
+    }
+}
+
+impl FromStr for BudgetPeriod {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "WEEKLY" => Ok(BudgetPeriod::WEEKLY),
+            "MONTHLY" => Ok(BudgetPeriod::MONTHLY),
+            _ => Err(AppError::Internal(format!("Unknown budget period '{}'", s))),
+        }
+    }
+}
+
+impl BudgetPeriod {
+    /// Returns the first and last day of the period containing `day`
+    pub fn bounds(&self, day: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            BudgetPeriod::WEEKLY => {
+                let start = day - Duration::days(i64::from(day.weekday().num_days_from_monday()));
+                (start, start + Duration::days(6))
+            }
+            BudgetPeriod::MONTHLY => {
+                let start = day.with_day(1).unwrap_or(day);
+                let next_month = if start.month() == 12 {
+                    NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+                };
+                let end = next_month.and_then(|d| d.pred_opt()).unwrap_or(start);
+                (start, end)
+            }
+        }
+    }
+}
+
+/// Normalizes a spending category so `Groceries` and ` groceries ` are the same
+pub fn normalize_category(category: &str) -> Result<String, AppError> {
+    let category = category.trim().to_lowercase();
+    if category.is_empty() || category.chars().count() > MAX_CATEGORY_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "Category must be 1-{} characters",
+            MAX_CATEGORY_LENGTH
+        )));
+    }
+    Ok(category)
+}
+
+/// A spending limit for one category of an account's outgoing transactions
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Budget {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub category: String,
+    /// WEEKLY or MONTHLY
+    pub period: String,
+    pub limit_amount: SqlxDecimal,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to set the limit for a category and period, replacing any existing one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetBudgetRequest {
+    pub category: String,
+    pub period: BudgetPeriod,
+    pub limit: Decimal,
+}
+
+/// Spend against a budget in the current period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub budget_id: Uuid,
+    pub category: String,
+    pub period: String,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub limit: Decimal,
+    pub spent: Decimal,
+    /// Limit minus spend; negative once the budget is exceeded
+    pub remaining: Decimal,
+    pub exceeded: bool,
+}
+
+/// Raised the first time a period's spend in a category goes over its budget
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BudgetAlert {
+    pub id: Uuid,
+    pub budget_id: Uuid,
+    pub period_start: NaiveDate,
+    /// The transaction that took the spend over the limit
+    pub transaction_id: Uuid,
+    pub spent: SqlxDecimal,
+    pub limit_amount: SqlxDecimal,
+    pub created_at: DateTime<Utc>,
+}
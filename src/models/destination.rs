@@ -0,0 +1,148 @@
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Longest identifier accepted, matching the `withdrawal_destinations.identifier` column
+pub const MAX_DESTINATION_IDENTIFIER_LENGTH: usize = 64;
+
+/// Trailing characters of an identifier left visible when it is masked
+const VISIBLE_IDENTIFIER_CHARS: usize = 4;
+
+/// Error code for a withdrawal without a destination while destinations are required
+pub const DESTINATION_REQUIRED: &str = "DESTINATION_REQUIRED";
+/// Error code for a destination that doesn't exist or was removed
+pub const DESTINATION_NOT_FOUND: &str = "DESTINATION_NOT_FOUND";
+/// Error code for a destination registered by someone other than the account owner
+pub const DESTINATION_NOT_OWNED: &str = "DESTINATION_NOT_OWNED";
+/// Error code for a destination that hasn't been verified yet
+pub const DESTINATION_UNVERIFIED: &str = "DESTINATION_UNVERIFIED";
+
+/// A change to the destination registry, recorded in its audit trail
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DestinationAction {
+    REGISTERED,
+    VERIFIED,
+    REMOVED,
+}
+
+impl std::fmt::Display for DestinationAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DestinationAction::REGISTERED => write!(f, "REGISTERED"),
+            DestinationAction::VERIFIED => write!(f, "VERIFIED"),
+            DestinationAction::REMOVED => write!(f, "REMOVED"),
+        }
+    }
+}
+
+/// An external account a user has registered to withdraw to
+#[derive(Debug, Clone, FromRow)]
+pub struct WithdrawalDestination {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub label: String,
+    /// The full IBAN or wallet reference; never returned by the API
+    pub identifier: String,
+    /// When the destination became or becomes usable; `None` until approved
+    pub verified_at: Option<DateTime<Utc>>,
+    pub removed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WithdrawalDestination {
+    /// Whether withdrawals may be sent to the destination at `now`
+    pub fn is_verified(&self, now: DateTime<Utc>) -> bool {
+        self.removed_at.is_none() && self.verified_at.is_some_and(|at| at <= now)
+    }
+}
+
+/// A registered destination as shown to its owner, with the identifier masked
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DestinationResponse {
+    pub id: Uuid,
+    pub label: String,
+    /// The identifier with all but its last four characters hidden
+    pub masked_identifier: String,
+    pub verified: bool,
+    /// When a destination still waiting out its verification delay becomes usable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usable_from: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<WithdrawalDestination> for DestinationResponse {
+    fn from(destination: WithdrawalDestination) -> Self {
+        let verified = destination.is_verified(Utc::now());
+        Self {
+            id: destination.id,
+            masked_identifier: mask_identifier(&destination.identifier),
+            label: destination.label,
+            verified,
+            usable_from: destination.verified_at.filter(|_| !verified),
+            created_at: destination.created_at,
+        }
+    }
+}
+
+/// Request to register a withdrawal destination
+#[derive(Debug, Serialize, Deserialize, Validate, Clone)]
+pub struct RegisterDestinationRequest {
+    /// Name the user knows the destination by, e.g. "Savings at Other Bank"
+    #[validate(length(min = 1, max = 100, message = "Label must be 1-100 characters"))]
+    pub label: String,
+
+    /// IBAN or wallet reference; spaces are ignored
+    pub identifier: String,
+}
+
+impl RegisterDestinationRequest {
+    /// Returns the identifier without spaces, rejecting anything that can't be one
+    ///
+    /// Letters keep their case, since wallet references are case-sensitive.
+    pub fn normalized_identifier(&self) -> Result<String, AppError> {
+        let identifier: String = self
+            .identifier
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+
+        if identifier.len() <= VISIBLE_IDENTIFIER_CHARS
+            || identifier.len() > MAX_DESTINATION_IDENTIFIER_LENGTH
+        {
+            return Err(AppError::Validation(format!(
+                "Identifier must be {}-{} characters",
+                VISIBLE_IDENTIFIER_CHARS + 1,
+                MAX_DESTINATION_IDENTIFIER_LENGTH
+            )));
+        }
+        if !identifier
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+        {
+            return Err(AppError::Validation(
+                "Identifier may only contain letters, digits and - _ . :".to_string(),
+            ));
+        }
+
+        Ok(identifier)
+    }
+}
+
+/// Hides all but the last four characters of an identifier
+///
+/// The hidden part is always four asterisks, so the mask doesn't reveal the
+/// identifier's length either: `DE89370400440532013000` becomes `****3000`.
+pub fn mask_identifier(identifier: &str) -> String {
+    let chars: Vec<char> = identifier.chars().collect();
+    let visible = chars.len().saturating_sub(VISIBLE_IDENTIFIER_CHARS);
+    let tail: String = if visible == 0 {
+        String::new()
+    } else {
+        chars[visible..].iter().collect()
+    };
+    format!("****{}", tail)
+}
@@ -0,0 +1,189 @@
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Upper bounds, in milliseconds, of the latency histogram buckets
+///
+/// A last, unbounded bucket holds everything slower.
+pub const LATENCY_BUCKETS_MS: [f64; 10] = [
+    5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0,
+];
+
+/// Hours of timings the latency histogram covers unless asked otherwise
+pub const DEFAULT_LATENCY_WINDOW_HOURS: i64 = 24;
+
+/// When the steps of a money-moving request happened, for support to look at
+///
+/// Stored in `transaction_timings` after the transaction commits. The
+/// milestones are always in order: received, lock acquired, committed.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TransactionTimings {
+    /// ID of the HTTP request that booked the transaction, if it came over HTTP
+    pub request_id: Option<String>,
+    /// Server instance that processed the request
+    pub instance_id: String,
+    /// When the server first saw the request
+    pub received_at: DateTime<Utc>,
+    /// When every account the transaction touches was locked
+    pub lock_acquired_at: DateTime<Utc>,
+    /// When the database transaction committed
+    pub committed_at: DateTime<Utc>,
+}
+
+impl TransactionTimings {
+    /// Time spent before the accounts were locked, including waiting for the locks
+    pub fn queue_time(&self) -> Duration {
+        self.lock_acquired_at - self.received_at
+    }
+
+    /// Time from acquiring the locks to the commit
+    pub fn processing_time(&self) -> Duration {
+        self.committed_at - self.lock_acquired_at
+    }
+}
+
+/// Milestones of a request that is still being processed
+///
+/// Each milestone is clamped to the one before it, so a clock step backwards
+/// can't produce negative durations.
+#[derive(Debug, Clone, Copy)]
+pub struct Milestones {
+    received_at: DateTime<Utc>,
+    lock_acquired_at: Option<DateTime<Utc>>,
+}
+
+impl Milestones {
+    /// Starts timing a request the server first saw at `received_at`, or now
+    pub fn start(received_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            received_at: received_at.unwrap_or_else(Utc::now),
+            lock_acquired_at: None,
+        }
+    }
+
+    /// Notes that the accounts involved are now locked
+    pub fn lock_acquired(&mut self) {
+        self.lock_acquired_at = Some(Utc::now().max(self.received_at));
+    }
+
+    /// Completes the milestones once the database transaction has committed
+    pub fn committed(self, request_id: Option<String>, instance_id: &str) -> TransactionTimings {
+        let lock_acquired_at = self.lock_acquired_at.unwrap_or(self.received_at);
+        TransactionTimings {
+            request_id,
+            instance_id: instance_id.to_string(),
+            received_at: self.received_at,
+            lock_acquired_at,
+            committed_at: Utc::now().max(lock_acquired_at),
+        }
+    }
+}
+
+/// Query parameters of the latency histogram
+#[derive(Debug, Default, Deserialize)]
+pub struct LatencyParams {
+    /// Hours of recent timings to include; defaults to 24
+    pub hours: Option<i64>,
+}
+
+/// One bucket of the latency histogram
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LatencyBucket {
+    pub transaction_type: String,
+    /// `QUEUE` (received to locked), `PROCESSING` (locked to committed) or
+    /// `TOTAL` (received to committed)
+    pub stage: String,
+    /// Exclusive upper bound of the bucket in milliseconds; `None` for the last one
+    pub below_ms: Option<i64>,
+    pub count: i64,
+}
+
+/// Size of the buckets transactions are counted in when measuring throughput
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ThroughputGranularity {
+    #[default]
+    Second,
+    Minute,
+}
+
+impl ThroughputGranularity {
+    /// The `date_trunc` field for buckets of this size
+    pub fn date_trunc_field(self) -> &'static str {
+        match self {
+            ThroughputGranularity::Second => "second",
+            ThroughputGranularity::Minute => "minute",
+        }
+    }
+
+    /// Length of one bucket in seconds
+    pub fn seconds(self) -> i64 {
+        match self {
+            ThroughputGranularity::Second => 1,
+            ThroughputGranularity::Minute => 60,
+        }
+    }
+}
+
+/// Query parameters of the throughput report
+#[derive(Debug, Default, Deserialize)]
+pub struct ThroughputParams {
+    /// First day (UTC) to include; defaults to `to`
+    pub from: Option<NaiveDate>,
+    /// Last day (UTC) to include; defaults to today
+    pub to: Option<NaiveDate>,
+    #[serde(default)]
+    pub granularity: ThroughputGranularity,
+}
+
+impl ThroughputParams {
+    /// Longest range of days one report may cover
+    pub const MAX_DAYS: i64 = 31;
+
+    /// Resolves the days to the half-open time range `[start, end)` they cover
+    pub fn time_range(&self, today: NaiveDate) -> Result<(DateTime<Utc>, DateTime<Utc>), AppError> {
+        let to = self.to.unwrap_or(today);
+        let from = self.from.unwrap_or(to);
+        if from > to {
+            return Err(AppError::BadRequest(
+                "'from' must not be after 'to'".to_string(),
+            ));
+        }
+        if (to - from).num_days() >= Self::MAX_DAYS {
+            return Err(AppError::BadRequest(format!(
+                "A throughput report covers at most {} days",
+                Self::MAX_DAYS
+            )));
+        }
+
+        let start = from.and_time(NaiveTime::MIN).and_utc();
+        let end = (to + Duration::days(1)).and_time(NaiveTime::MIN).and_utc();
+        Ok((start, end))
+    }
+}
+
+/// The busiest bucket of a throughput report
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ThroughputPeak {
+    /// Start of the bucket
+    pub bucket_start: DateTime<Utc>,
+    /// Transactions created in the bucket
+    pub count: i64,
+    /// `count` divided by the length of the bucket
+    pub per_second: f64,
+}
+
+/// Transactions per second over a range, for capacity planning
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ThroughputReport {
+    pub granularity: ThroughputGranularity,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Transactions created in the range, whatever their status
+    pub total_transactions: i64,
+    /// Total divided by the seconds of the range that have already passed
+    pub average_per_second: f64,
+    /// The busiest bucket; `None` when the range has no transactions
+    pub peak: Option<ThroughputPeak>,
+}
@@ -0,0 +1,44 @@
+use crate::models::decimal::SqlxDecimal;
+use crate::utils::currency::validate_currency;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+/// Decimal places rates are stored with
+pub const EXCHANGE_RATE_SCALE: u32 = 12;
+
+/// A stored exchange rate, as read from the database
+#[derive(Debug, FromRow)]
+pub struct ExchangeRate {
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate: SqlxDecimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to set the rate from one currency to another
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SetExchangeRateRequest {
+    /// ISO 4217 code of the currency converted from
+    #[validate(custom = "validate_currency")]
+    pub base_currency: String,
+    /// ISO 4217 code of the currency converted to
+    #[validate(custom = "validate_currency")]
+    pub quote_currency: String,
+    /// Units of `quote_currency` one unit of `base_currency` buys
+    pub rate: Decimal,
+}
+
+/// A stored exchange rate
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExchangeRateResponse {
+    pub base_currency: String,
+    pub quote_currency: String,
+    /// Units of `quote_currency` one unit of `base_currency` buys
+    pub rate: Decimal,
+    pub updated_at: DateTime<Utc>,
+    /// Whether the rate is too old to convert at
+    pub stale: bool,
+}
@@ -0,0 +1,61 @@
+use crate::models::decimal::SqlxDecimal;
+use crate::utils::currency::validate_currency;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// A stored fee rule, as read from the database
+#[derive(Debug, FromRow)]
+pub struct FeeRule {
+    pub id: Uuid,
+    pub transaction_type: String,
+    pub currency: Option<String>,
+    pub flat_amount: SqlxDecimal,
+    pub percent: SqlxDecimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to set the fee charged on one type of transaction
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SetFeeRuleRequest {
+    /// `TRANSFER` or `WITHDRAWAL`
+    pub transaction_type: String,
+    /// ISO 4217 code of the paying account's currency; every currency
+    /// without a rule of its own when unset
+    #[validate(custom = "validate_currency")]
+    pub currency: Option<String>,
+    /// Fixed part of the fee, in the paying account's currency
+    #[serde(default)]
+    pub flat_amount: Decimal,
+    /// Part of the fee proportional to the amount, where `1.5` means 1.5%
+    #[serde(default)]
+    pub percent: Decimal,
+}
+
+/// A stored fee rule
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeeRuleResponse {
+    pub id: Uuid,
+    pub transaction_type: String,
+    /// Currency the rule applies to; every other currency when absent
+    pub currency: Option<String>,
+    pub flat_amount: Decimal,
+    pub percent: Decimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<FeeRule> for FeeRuleResponse {
+    fn from(rule: FeeRule) -> Self {
+        Self {
+            id: rule.id,
+            transaction_type: rule.transaction_type,
+            currency: rule.currency,
+            flat_amount: rule.flat_amount.0.normalize(),
+            percent: rule.percent.0.normalize(),
+            updated_at: rule.updated_at,
+        }
+    }
+}
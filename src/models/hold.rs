@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::decimal::SqlxDecimal;
+use crate::models::transaction::{validate_positive_amount, TransactionResponse};
+use crate::utils::currency::to_display_scale;
+
+/// Error code for capturing or releasing a hold that is no longer active
+pub const HOLD_NOT_ACTIVE: &str = "HOLD_NOT_ACTIVE";
+/// Error code for capturing more than a hold reserved
+pub const CAPTURE_EXCEEDS_HOLD: &str = "CAPTURE_EXCEEDS_HOLD";
+
+/// Lifecycle of an authorization hold
+///
+/// - ACTIVE: The funds are reserved and can't be spent otherwise
+/// - CAPTURED: The hold was turned into a withdrawal or transfer
+/// - RELEASED: The hold was cancelled and its funds freed
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum HoldStatus {
+    ACTIVE,
+    CAPTURED,
+    RELEASED,
+}
+
+impl std::fmt::Display for HoldStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HoldStatus::ACTIVE => write!(f, "ACTIVE"),
+            HoldStatus::CAPTURED => write!(f, "CAPTURED"),
+            HoldStatus::RELEASED => write!(f, "RELEASED"),
+        }
+    }
+}
+
+/// Funds reserved on an account, as stored in the database
+///
+/// While a hold is ACTIVE its amount counts towards the account's
+/// `held_amount`, lowering the available balance.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Hold {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    /// Amount reserved when the hold was created
+    pub amount: SqlxDecimal,
+    pub currency: String,
+    /// Current status as a string (ACTIVE, CAPTURED, RELEASED)
+    pub status: String,
+    pub description: Option<String>,
+    /// Amount actually taken; set once captured
+    pub captured_amount: Option<SqlxDecimal>,
+    /// The withdrawal or transfer the hold was captured into
+    pub transaction_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Public representation of a hold
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HoldResponse {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub amount: Decimal,
+    pub currency: String,
+    pub status: String,
+    pub description: Option<String>,
+    pub captured_amount: Option<Decimal>,
+    pub transaction_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Hold> for HoldResponse {
+    fn from(hold: Hold) -> Self {
+        Self {
+            id: hold.id,
+            account_id: hold.account_id,
+            amount: to_display_scale(hold.amount.into(), &hold.currency),
+            captured_amount: hold
+                .captured_amount
+                .map(|amount| to_display_scale(amount.into(), &hold.currency)),
+            currency: hold.currency,
+            status: hold.status,
+            description: hold.description,
+            transaction_id: hold.transaction_id,
+            created_at: hold.created_at,
+            updated_at: hold.updated_at,
+        }
+    }
+}
+
+/// Request to reserve funds on an account
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+pub struct CreateHoldRequest {
+    /// Account ID to reserve the funds on
+    pub account_id: Uuid,
+
+    /// Amount to reserve (must be positive)
+    #[validate(custom = "validate_positive_amount")]
+    pub amount: Decimal,
+
+    /// Optional description, also used for the captured transaction
+    pub description: Option<String>,
+}
+
+/// Request to turn a hold into a real transaction
+///
+/// Without a receiver the hold is captured as a WITHDRAWAL, otherwise as a
+/// TRANSFER to that account.
+#[derive(Debug, Default, Deserialize, Serialize, Validate, Clone)]
+pub struct CaptureHoldRequest {
+    /// Amount to take, at most the amount held; defaults to all of it. Whatever
+    /// isn't captured is released.
+    #[validate(custom = "validate_positive_amount")]
+    pub amount: Option<Decimal>,
+
+    /// Account to transfer the captured funds to
+    pub receiver_account_id: Option<Uuid>,
+
+    /// Registered external destination a withdrawal is sent to
+    pub destination_id: Option<Uuid>,
+
+    /// Description of the captured transaction; defaults to the hold's
+    pub description: Option<String>,
+}
+
+/// A captured hold with the transaction that booked it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HoldCaptureResponse {
+    pub hold: HoldResponse,
+    pub transaction: TransactionResponse,
+}
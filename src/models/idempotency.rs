@@ -0,0 +1,62 @@
+use crate::utils::error::AppError;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Header clients set to make a transaction request safe to retry
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Longest idempotency key accepted, matching the `idempotency_keys.key` column
+pub const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+/// Hours a key is honoured for; after that it can be used for a new request
+pub const IDEMPOTENCY_KEY_TTL_HOURS: i32 = 24;
+
+/// A client-chosen key under which a transaction request runs at most once
+///
+/// Keys are scoped to the user sending them. Replaying a key returns the
+/// response stored for it, as long as the request is the same one and the
+/// key is less than [`IDEMPOTENCY_KEY_TTL_HOURS`] old.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyKey {
+    /// User the key belongs to
+    pub user_id: Uuid,
+    /// The key as sent by the client
+    pub key: String,
+    /// SHA-256 of the endpoint and request, to detect a key reused for another request
+    pub request_hash: String,
+}
+
+impl IdempotencyKey {
+    /// Creates the key for `request` sent by `user_id` to `endpoint`
+    ///
+    /// The endpoint is part of the hash, so the same key and body sent to a
+    /// different endpoint counts as a different request.
+    pub fn new<T: Serialize>(
+        user_id: Uuid,
+        key: &str,
+        endpoint: &str,
+        request: &T,
+    ) -> Result<Self, AppError> {
+        let key = key.trim();
+        if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+            return Err(AppError::BadRequest(format!(
+                "{} must be between 1 and {} characters",
+                IDEMPOTENCY_KEY_HEADER, MAX_IDEMPOTENCY_KEY_LENGTH
+            )));
+        }
+
+        let body = serde_json::to_string(request)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize request: {}", e)))?;
+        let request_hash = format!(
+            "{:x}",
+            Sha256::digest(format!("{}\u{1f}{}", endpoint, body).as_bytes())
+        );
+
+        Ok(Self {
+            user_id,
+            key: key.to_string(),
+            request_hash,
+        })
+    }
+}
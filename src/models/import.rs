@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Query parameters of a transaction import upload
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportParams {
+    /// Session returned by an earlier upload, to continue or retry that import
+    pub session_id: Option<Uuid>,
+}
+
+/// A row that could not be imported, and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportRowError {
+    /// Position of the row in the file, counting the header as row 1
+    pub row: u64,
+    pub message: String,
+}
+
+/// Outcome of one upload within an import session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportSummary {
+    /// Pass this back as `session_id` to resume the import
+    pub session_id: Uuid,
+    /// Data rows read from the upload
+    pub rows_read: u64,
+    /// Rows stored by this upload
+    pub imported: u64,
+    /// Rows skipped because an earlier upload already stored them
+    pub duplicates: u64,
+    /// Rows rejected by validation
+    pub failed: u64,
+    /// Reasons for the first rejected rows
+    pub errors: Vec<ImportRowError>,
+}
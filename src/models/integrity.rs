@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// The invariant a violation breaks
+///
+/// - MISSING_ACCOUNT: A transaction references an account that does not exist
+/// - INVALID_STATUS: A transaction's status is outside the allowed set
+/// - INCOMPLETE_TRANSFER: A completed transfer lacks its sender or receiver
+/// - NEGATIVE_BALANCE: An account balance is below its overdraft limit (zero)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ViolationKind {
+    MissingAccount,
+    InvalidStatus,
+    IncompleteTransfer,
+    NegativeBalance,
+}
+
+/// A single broken invariant found by the integrity check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityViolation {
+    pub kind: ViolationKind,
+    /// The offending transaction or account
+    pub entity_id: Uuid,
+    /// Human-readable explanation for whoever investigates
+    pub detail: String,
+}
+
+/// Outcome of one integrity sweep, as persisted in `integrity_reports`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct IntegrityReport {
+    pub id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub transactions_scanned: i64,
+    pub accounts_scanned: i64,
+    pub violation_count: i64,
+    pub violations: Json<Vec<IntegrityViolation>>,
+}
+
+impl IntegrityReport {
+    /// Returns whether the sweep found no violations
+    pub fn is_clean(&self) -> bool {
+        self.violation_count == 0
+    }
+}
@@ -1,4 +1,22 @@
 pub mod account;
+pub mod balance_history;
+pub mod budget;
 pub mod decimal;
+pub mod destination;
+pub mod diagnostics;
+pub mod exchange_rate;
+pub mod fee_rule;
+pub mod hold;
+pub mod idempotency;
+pub mod import;
+pub mod integrity;
+pub mod outbox;
+#[cfg(feature = "sandbox")]
+pub mod sandbox;
+pub mod scheduled_transfer;
+pub mod standing_order;
+pub mod statement;
 pub mod transaction;
+pub mod usage;
 pub mod user;
+pub mod webhook;
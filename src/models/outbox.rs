@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+pub use crate::models::webhook::TRANSACTION_COMPLETED;
+
+/// Event recorded when an account is opened
+pub const ACCOUNT_CREATED: &str = "account.created";
+/// Event recorded when a user registers
+pub const USER_REGISTERED: &str = "user.registered";
+
+/// An event waiting in, or already published from, the transactional outbox
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OutboxEvent {
+    pub id: Uuid,
+    /// What happened, e.g. `transaction.completed`
+    pub event_type: String,
+    /// The transaction, account or user the event is about
+    pub aggregate_id: Uuid,
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    /// When the sink last accepted the event; `None` until then
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// Request to publish the events recorded in `[from, to)` again
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayEventsRequest {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// How many events a replay queued for publishing again
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayEventsResponse {
+    pub replayed: u64,
+}
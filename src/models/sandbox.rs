@@ -0,0 +1,24 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Description given to every faucet deposit, so it stands out in listings
+pub const SANDBOX_DEPOSIT_DESCRIPTION: &str = "[SANDBOX] Faucet deposit";
+
+/// Request for test funds from the sandbox faucet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaucetRequest {
+    /// One of the caller's accounts
+    pub account_id: Uuid,
+    /// Amount to deposit, in the account currency
+    pub amount: Decimal,
+}
+
+/// Outcome of purging faucet deposits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxPurgeSummary {
+    /// Faucet deposits deleted
+    pub transactions_deleted: u64,
+    /// Accounts whose balance was reduced by the deleted deposits
+    pub accounts_adjusted: u64,
+}
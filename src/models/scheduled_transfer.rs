@@ -0,0 +1,152 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::decimal::SqlxDecimal;
+use crate::models::transaction::{validate_positive_amount, TransferRequest};
+use crate::utils::currency::to_display_scale;
+
+/// Error code for cancelling a scheduled transfer that has already run or been cancelled
+pub const SCHEDULED_TRANSFER_NOT_PENDING: &str = "SCHEDULED_TRANSFER_NOT_PENDING";
+
+/// Lifecycle of a scheduled transfer
+///
+/// - SCHEDULED: Waiting for its execution time
+/// - EXECUTED: The transfer was booked
+/// - FAILED: The transfer was attempted but rejected, e.g. for lack of funds
+/// - CANCELLED: Cancelled by the user before it ran
+/// - EXPIRED: Voided because it hadn't run by its expiry time
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum ScheduledTransferStatus {
+    SCHEDULED,
+    EXECUTED,
+    FAILED,
+    CANCELLED,
+    EXPIRED,
+}
+
+impl std::fmt::Display for ScheduledTransferStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduledTransferStatus::SCHEDULED => write!(f, "SCHEDULED"),
+            ScheduledTransferStatus::EXECUTED => write!(f, "EXECUTED"),
+            ScheduledTransferStatus::FAILED => write!(f, "FAILED"),
+            ScheduledTransferStatus::CANCELLED => write!(f, "CANCELLED"),
+            ScheduledTransferStatus::EXPIRED => write!(f, "EXPIRED"),
+        }
+    }
+}
+
+/// A transfer booked to run later, as stored in the database
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ScheduledTransfer {
+    pub id: Uuid,
+    /// User who scheduled the transfer and owns the sending account
+    pub user_id: Uuid,
+    pub sender_account_id: Uuid,
+    pub receiver_account_id: Uuid,
+    pub amount: SqlxDecimal,
+    /// Currency of the sending account when the transfer was scheduled
+    pub currency: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    /// Earliest time the transfer runs
+    pub execute_at: DateTime<Utc>,
+    /// Time after which the transfer is voided instead of run
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Current status as a string (SCHEDULED, EXECUTED, FAILED, CANCELLED, EXPIRED)
+    pub status: String,
+    /// The transfer booked; set once executed
+    pub transaction_id: Option<Uuid>,
+    /// Why the transfer was rejected; set once failed
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ScheduledTransfer {
+    /// The transfer to book when the scheduled time comes
+    pub fn transfer_request(&self) -> TransferRequest {
+        TransferRequest {
+            sender_account_id: self.sender_account_id,
+            receiver_account_id: self.receiver_account_id,
+            amount: self.amount.into(),
+            description: self.description.clone(),
+            category: self.category.clone(),
+            allow_duplicate: true,
+        }
+    }
+}
+
+/// Public representation of a scheduled transfer
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduledTransferResponse {
+    pub id: Uuid,
+    pub sender_account_id: Uuid,
+    pub receiver_account_id: Uuid,
+    pub amount: Decimal,
+    pub currency: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub execute_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub status: String,
+    pub transaction_id: Option<Uuid>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<ScheduledTransfer> for ScheduledTransferResponse {
+    fn from(scheduled: ScheduledTransfer) -> Self {
+        Self {
+            id: scheduled.id,
+            sender_account_id: scheduled.sender_account_id,
+            receiver_account_id: scheduled.receiver_account_id,
+            amount: to_display_scale(scheduled.amount.into(), &scheduled.currency),
+            currency: scheduled.currency,
+            description: scheduled.description,
+            category: scheduled.category,
+            execute_at: scheduled.execute_at,
+            expires_at: scheduled.expires_at,
+            status: scheduled.status,
+            transaction_id: scheduled.transaction_id,
+            error: scheduled.error,
+            created_at: scheduled.created_at,
+            updated_at: scheduled.updated_at,
+        }
+    }
+}
+
+/// Request to transfer money between accounts at a later time
+///
+/// Takes the same fields as a transfer. Funds are only checked when the
+/// transfer runs, not when it is scheduled.
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+pub struct ScheduleTransferRequest {
+    /// Account ID to transfer money from
+    pub sender_account_id: Uuid,
+    /// Account ID to transfer money to
+    pub receiver_account_id: Uuid,
+
+    /// Transfer amount (must be positive)
+    #[validate(custom = "validate_positive_amount")]
+    pub amount: Decimal,
+
+    /// Optional transfer description or notes
+    pub description: Option<String>,
+
+    /// Optional spending category, counted towards the sender's budgets
+    pub category: Option<String>,
+
+    /// When to run the transfer; must be in the future
+    pub execute_at: DateTime<Utc>,
+
+    /// Optional deadline, after `execute_at`; a transfer that hasn't run by
+    /// then, e.g. because the server was down, is voided
+    pub expires_at: Option<DateTime<Utc>>,
+}
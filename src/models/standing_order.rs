@@ -0,0 +1,263 @@
+use chrono::{DateTime, Duration, Months, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::decimal::SqlxDecimal;
+use crate::models::transaction::{validate_positive_amount, TransferRequest};
+use crate::utils::currency::to_display_scale;
+
+/// Error code for pausing a standing order that isn't active
+pub const STANDING_ORDER_NOT_ACTIVE: &str = "STANDING_ORDER_NOT_ACTIVE";
+/// Error code for resuming a standing order that isn't paused
+pub const STANDING_ORDER_NOT_PAUSED: &str = "STANDING_ORDER_NOT_PAUSED";
+
+/// How often a standing order repeats
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RecurrenceInterval {
+    DAILY,
+    WEEKLY,
+    MONTHLY,
+}
+
+impl std::fmt::Display for RecurrenceInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecurrenceInterval::DAILY => write!(f, "DAILY"),
+            RecurrenceInterval::WEEKLY => write!(f, "WEEKLY"),
+            RecurrenceInterval::MONTHLY => write!(f, "MONTHLY"),
+        }
+    }
+}
+
+impl RecurrenceInterval {
+    /// The time `n` intervals after `start`
+    ///
+    /// Months are counted from `start` rather than from the previous
+    /// occurrence, so an order starting on the 31st falls on the last day of
+    /// shorter months and goes back to the 31st afterwards.
+    pub fn nth_after(self, start: DateTime<Utc>, n: u32) -> DateTime<Utc> {
+        match self {
+            RecurrenceInterval::DAILY => start + Duration::days(i64::from(n)),
+            RecurrenceInterval::WEEKLY => start + Duration::weeks(i64::from(n)),
+            RecurrenceInterval::MONTHLY => start
+                .checked_add_months(Months::new(n))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+}
+
+/// Lifecycle of a standing order
+///
+/// - ACTIVE: Runs whenever an occurrence is due
+/// - PAUSED: Skips its occurrences until resumed
+/// - COMPLETED: Its end date has passed
+/// - CANCELLED: Deleted by the user; kept for the transactions that point to it
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum StandingOrderStatus {
+    ACTIVE,
+    PAUSED,
+    COMPLETED,
+    CANCELLED,
+}
+
+impl std::fmt::Display for StandingOrderStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StandingOrderStatus::ACTIVE => write!(f, "ACTIVE"),
+            StandingOrderStatus::PAUSED => write!(f, "PAUSED"),
+            StandingOrderStatus::COMPLETED => write!(f, "COMPLETED"),
+            StandingOrderStatus::CANCELLED => write!(f, "CANCELLED"),
+        }
+    }
+}
+
+/// A transfer repeated at a fixed interval, as stored in the database
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct StandingOrder {
+    pub id: Uuid,
+    /// User who set up the order and owns the sending account
+    pub user_id: Uuid,
+    pub sender_account_id: Uuid,
+    pub receiver_account_id: Uuid,
+    pub amount: SqlxDecimal,
+    /// Currency of the sending account when the order was set up
+    pub currency: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    /// DAILY, WEEKLY or MONTHLY
+    pub interval: String,
+    /// Time of the first occurrence; later ones are whole intervals after it
+    pub starts_at: DateTime<Utc>,
+    /// Last day (UTC) an occurrence may fall on
+    pub end_date: Option<NaiveDate>,
+    /// Intervals elapsed since `starts_at`, including skipped ones
+    pub occurrence: i32,
+    pub next_run_at: DateTime<Utc>,
+    /// Current status as a string (ACTIVE, PAUSED, COMPLETED, CANCELLED)
+    pub status: String,
+    /// Why the last occurrence failed; cleared once one succeeds
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl StandingOrder {
+    fn recurrence_interval(&self) -> RecurrenceInterval {
+        match self.interval.as_str() {
+            "DAILY" => RecurrenceInterval::DAILY,
+            "WEEKLY" => RecurrenceInterval::WEEKLY,
+            _ => RecurrenceInterval::MONTHLY,
+        }
+    }
+
+    /// The first occurrence after the current one that falls after `now`
+    ///
+    /// Occurrences missed in between, e.g. while the order was paused, are
+    /// skipped rather than caught up on.
+    ///
+    /// # Returns
+    /// The occurrence index and its time, or `None` once they pass the end date
+    pub fn next_occurrence_after(&self, now: DateTime<Utc>) -> Option<(i32, DateTime<Utc>)> {
+        let interval = self.recurrence_interval();
+        let mut occurrence = self.occurrence + 1;
+        let mut next_run_at = interval.nth_after(self.starts_at, occurrence as u32);
+        while next_run_at <= now {
+            occurrence += 1;
+            next_run_at = interval.nth_after(self.starts_at, occurrence as u32);
+        }
+
+        match self.end_date {
+            Some(end_date) if next_run_at.date_naive() > end_date => None,
+            _ => Some((occurrence, next_run_at)),
+        }
+    }
+
+    /// The times of the pending occurrence and the ones after it, up to and
+    /// including `until`
+    ///
+    /// Occurrences past the end date are left out. The pending occurrence is
+    /// listed even when it is overdue, since the next worker pass runs it.
+    pub fn runs_until(&self, until: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let interval = self.recurrence_interval();
+        let mut runs = Vec::new();
+        let mut occurrence = self.occurrence;
+        let mut run_at = self.next_run_at;
+        while run_at <= until {
+            if matches!(self.end_date, Some(end_date) if run_at.date_naive() > end_date) {
+                break;
+            }
+            runs.push(run_at);
+            occurrence += 1;
+            run_at = interval.nth_after(self.starts_at, occurrence as u32);
+        }
+
+        runs
+    }
+
+    /// The transfer booked by each occurrence
+    pub fn transfer_request(&self) -> TransferRequest {
+        TransferRequest {
+            sender_account_id: self.sender_account_id,
+            receiver_account_id: self.receiver_account_id,
+            amount: self.amount.into(),
+            description: self.description.clone(),
+            category: self.category.clone(),
+            allow_duplicate: true,
+        }
+    }
+}
+
+/// Public representation of a standing order
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StandingOrderResponse {
+    pub id: Uuid,
+    pub sender_account_id: Uuid,
+    pub receiver_account_id: Uuid,
+    pub amount: Decimal,
+    pub currency: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub interval: String,
+    pub starts_at: DateTime<Utc>,
+    pub end_date: Option<NaiveDate>,
+    /// When the next occurrence runs, if the order is active
+    pub next_run_at: DateTime<Utc>,
+    pub status: String,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<StandingOrder> for StandingOrderResponse {
+    fn from(order: StandingOrder) -> Self {
+        Self {
+            id: order.id,
+            sender_account_id: order.sender_account_id,
+            receiver_account_id: order.receiver_account_id,
+            amount: to_display_scale(order.amount.into(), &order.currency),
+            currency: order.currency,
+            description: order.description,
+            category: order.category,
+            interval: order.interval,
+            starts_at: order.starts_at,
+            end_date: order.end_date,
+            next_run_at: order.next_run_at,
+            status: order.status,
+            last_error: order.last_error,
+            created_at: order.created_at,
+            updated_at: order.updated_at,
+        }
+    }
+}
+
+/// How many days ahead `GET /transactions/recurring/upcoming` looks by default
+pub const DEFAULT_UPCOMING_DAYS: i64 = 30;
+
+/// Query parameters of the upcoming standing order runs
+#[derive(Debug, Deserialize)]
+pub struct UpcomingRunsParams {
+    /// Days from now the projection covers; defaults to `DEFAULT_UPCOMING_DAYS`
+    pub days: Option<i64>,
+}
+
+/// An active standing order with the times it is projected to run
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpcomingStandingOrder {
+    #[serde(flatten)]
+    pub order: StandingOrderResponse,
+    /// Projected run times within the window, oldest first; empty when the
+    /// next one falls after it
+    pub run_dates: Vec<DateTime<Utc>>,
+}
+
+/// Request to repeat a transfer at a fixed interval
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+pub struct CreateStandingOrderRequest {
+    /// Account ID to transfer money from
+    pub sender_account_id: Uuid,
+    /// Account ID to transfer money to
+    pub receiver_account_id: Uuid,
+
+    /// Amount of each transfer (must be positive)
+    #[validate(custom = "validate_positive_amount")]
+    pub amount: Decimal,
+
+    /// Optional description of each transfer
+    pub description: Option<String>,
+
+    /// Optional spending category, counted towards the sender's budgets
+    pub category: Option<String>,
+
+    pub interval: RecurrenceInterval,
+
+    /// Time of the first transfer; defaults to now
+    pub starts_at: Option<DateTime<Utc>>,
+
+    /// Optional last day (UTC) a transfer may be made on
+    pub end_date: Option<NaiveDate>,
+}
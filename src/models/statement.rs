@@ -0,0 +1,137 @@
+use crate::models::decimal::SqlxDecimal;
+use crate::models::transaction::TransactionResponse;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Months, NaiveDate, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Query parameters selecting the period a statement covers: either a
+/// `year` and `month`, or a `from` and `to` time
+#[derive(Debug, Deserialize)]
+pub struct StatementParams {
+    pub year: Option<i32>,
+    /// 1 to 12; a leading zero is accepted
+    pub month: Option<u32>,
+    /// Earliest creation time to include
+    pub from: Option<DateTime<Utc>>,
+    /// Creation time from which on transactions are excluded
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// The period a statement covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementPeriod {
+    /// A calendar month (UTC)
+    Month { year: i32, month: u32 },
+    /// Any time range, from its start up to, but not including, its end
+    Range {
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    },
+}
+
+impl StatementParams {
+    /// The period the parameters select
+    ///
+    /// Fails with `AppError::Validation` unless exactly one of the two forms
+    /// is given in full.
+    pub fn period(&self) -> Result<StatementPeriod, AppError> {
+        match (self.year, self.month, self.from, self.to) {
+            (Some(year), Some(month), None, None) => Ok(StatementPeriod::Month { year, month }),
+            (None, None, Some(from), Some(to)) => Ok(StatementPeriod::Range { from, to }),
+            _ => Err(AppError::Validation(
+                "A statement covers either a year and month, or a from and to time".to_string(),
+            )),
+        }
+    }
+}
+
+/// A calendar month as a UTC time range, from its first instant up to, but
+/// not including, the first instant of the next month
+///
+/// Fails with `AppError::Validation` for a month that doesn't exist.
+pub fn month_period(year: i32, month: u32) -> Result<(DateTime<Utc>, DateTime<Utc>), AppError> {
+    let invalid = || AppError::Validation(format!("Invalid month {}-{}", year, month));
+    let start = NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(invalid)?;
+    let end = start
+        .checked_add_months(Months::new(1))
+        .ok_or_else(invalid)?;
+
+    Ok((
+        start.and_time(NaiveTime::MIN).and_utc(),
+        end.and_time(NaiveTime::MIN).and_utc(),
+    ))
+}
+
+/// Balances and totals of an account over a statement period, as read from
+/// the database
+#[derive(Debug, FromRow)]
+pub struct StatementBalances {
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+    pub opening_balance: SqlxDecimal,
+    pub closing_balance: SqlxDecimal,
+    pub total_credits: SqlxDecimal,
+    pub total_debits: SqlxDecimal,
+}
+
+/// An account's money movements over one calendar month, or over any other
+/// period
+///
+/// `opening_balance + total_credits - total_debits == closing_balance`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountStatement {
+    pub account_id: Uuid,
+    /// Currency of every amount in the statement
+    pub currency: String,
+    /// Year of a monthly statement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+    /// Month of a monthly statement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub month: Option<u32>,
+    /// First instant covered, e.g. the first of the month in UTC
+    pub period_start: DateTime<Utc>,
+    /// First instant no longer covered, e.g. the first of the following month
+    pub period_end: DateTime<Utc>,
+    /// Balance right before the period; zero for accounts opened during it
+    pub opening_balance: Decimal,
+    /// Balance at the end of the period, or now for a period not over yet
+    pub closing_balance: Decimal,
+    /// Sum of the money that came into the account during the period
+    pub total_credits: Decimal,
+    /// Sum of the money that left the account during the period
+    pub total_debits: Decimal,
+    /// Transactions that moved money during the period, oldest first, each
+    /// with the balance right after it
+    pub transactions: Vec<TransactionResponse>,
+}
+
+/// An account's money movements over its whole lifetime, generated when the
+/// account is closed and never changed afterwards
+///
+/// `opening_balance + total_credits - total_debits == closing_balance`, and
+/// the closing balance is zero since only empty accounts can be closed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FinalStatement {
+    pub account_id: Uuid,
+    /// Currency of every amount in the statement
+    pub currency: String,
+    /// When the account was opened
+    pub period_start: DateTime<Utc>,
+    /// When the account was closed
+    pub period_end: DateTime<Utc>,
+    /// Balance the account was opened with
+    pub opening_balance: Decimal,
+    /// Balance at closure
+    pub closing_balance: Decimal,
+    /// Sum of the money that ever came into the account
+    pub total_credits: Decimal,
+    /// Sum of the money that ever left the account
+    pub total_debits: Decimal,
+    /// Every transaction that moved money, oldest first, each with the balance
+    /// right after it
+    pub transactions: Vec<TransactionResponse>,
+}
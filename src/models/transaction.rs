@@ -1,22 +1,35 @@
-use chrono::{DateTime, Utc};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::BTreeMap;
 use uuid::Uuid;
 use validator::{Validate, ValidationError};
 
 use crate::models::decimal::SqlxDecimal;
+use crate::models::diagnostics::TransactionTimings;
+use crate::utils::csv::csv_record;
+use crate::utils::currency::{to_display_scale, validate_currency};
+use crate::utils::error::AppError;
+use crate::utils::response::SelectableFields;
 
 /// Enum representing the different types of transactions supported by the system
 ///
 /// - TRANSFER: Movement of funds between two accounts within the system
 /// - DEPOSIT: External funds coming into an account in the system
 /// - WITHDRAWAL: Funds leaving an account to an external destination
+/// - FEE: A fee moving from the payer of a transfer or withdrawal to the fee
+///   account, or out of the ledger without one, booked alongside the
+///   transaction it was charged on
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum TransactionType {
     TRANSFER,
     DEPOSIT,
     WITHDRAWAL,
+    FEE,
 }
 
 impl std::fmt::Display for TransactionType {
@@ -25,6 +38,24 @@ impl std::fmt::Display for TransactionType {
             TransactionType::TRANSFER => write!(f, "TRANSFER"),
             TransactionType::DEPOSIT => write!(f, "DEPOSIT"),
             TransactionType::WITHDRAWAL => write!(f, "WITHDRAWAL"),
+            TransactionType::FEE => write!(f, "FEE"),
+        }
+    }
+}
+
+impl std::str::FromStr for TransactionType {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "TRANSFER" => Ok(TransactionType::TRANSFER),
+            "DEPOSIT" => Ok(TransactionType::DEPOSIT),
+            "WITHDRAWAL" => Ok(TransactionType::WITHDRAWAL),
+            "FEE" => Ok(TransactionType::FEE),
+            _ => Err(AppError::Validation(format!(
+                "Invalid transaction type '{}'",
+                s
+            ))),
         }
     }
 }
@@ -34,11 +65,14 @@ impl std::fmt::Display for TransactionType {
 /// - PENDING: Transaction has been created but not fully processed
 /// - COMPLETED: Transaction was successfully processed
 /// - FAILED: Transaction processing failed and any partial changes were rolled back
+/// - REVERSED: Transaction completed and was later undone by a compensating transfer
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum TransactionStatus {
     PENDING,
     COMPLETED,
     FAILED,
+    REVERSED,
 }
 
 impl std::fmt::Display for TransactionStatus {
@@ -47,6 +81,24 @@ impl std::fmt::Display for TransactionStatus {
             TransactionStatus::PENDING => write!(f, "PENDING"),
             TransactionStatus::COMPLETED => write!(f, "COMPLETED"),
             TransactionStatus::FAILED => write!(f, "FAILED"),
+            TransactionStatus::REVERSED => write!(f, "REVERSED"),
+        }
+    }
+}
+
+impl std::str::FromStr for TransactionStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "PENDING" => Ok(TransactionStatus::PENDING),
+            "COMPLETED" => Ok(TransactionStatus::COMPLETED),
+            "FAILED" => Ok(TransactionStatus::FAILED),
+            "REVERSED" => Ok(TransactionStatus::REVERSED),
+            _ => Err(AppError::Validation(format!(
+                "Invalid transaction status '{}'",
+                s
+            ))),
         }
     }
 }
@@ -72,7 +124,7 @@ pub struct Transaction {
     pub currency: String,
     /// Type of transaction as a string (TRANSFER, DEPOSIT, WITHDRAWAL)
     pub transaction_type: String,
-    /// Current status as a string (PENDING, COMPLETED, FAILED)
+    /// Current status as a string (PENDING, COMPLETED, FAILED, REVERSED)
     pub status: String,
     /// Optional transaction description or notes
     pub description: Option<String>,
@@ -86,7 +138,7 @@ pub struct Transaction {
 ///
 /// This is the public-facing representation of a transaction,
 /// exposed through the API. It omits updated_at for simplicity.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionResponse {
     /// Unique identifier for the transaction
     pub id: Uuid,
@@ -100,12 +152,56 @@ pub struct TransactionResponse {
     pub currency: String,
     /// Type of transaction as a string (TRANSFER, DEPOSIT, WITHDRAWAL)
     pub transaction_type: String,
-    /// Current status as a string (PENDING, COMPLETED, FAILED)
+    /// Current status as a string (PENDING, COMPLETED, FAILED, REVERSED)
     pub status: String,
     /// Optional transaction description or notes
     pub description: Option<String>,
     /// When the transaction was created
     pub created_at: DateTime<Utc>,
+    /// Balance of the listed account right after this transaction; only
+    /// present in an account's transaction history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance_after: Option<Decimal>,
+    /// Fee the sender paid on top of the amount, in `fee_currency`; only
+    /// present for transfers and withdrawals that were charged one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_amount: Option<Decimal>,
+    /// Currency of `fee_amount`, which is the sender's; only present with it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_currency: Option<String>,
+    /// What the sender was debited, in `original_currency`, when `amount`
+    /// was converted into the receiver's currency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_amount: Option<Decimal>,
+    /// Currency the sender was debited in; only present with `original_amount`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_currency: Option<String>,
+    /// Units of `currency` one unit of `original_currency` bought; reversals
+    /// of converted transfers give back what was paid and carry no rate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exchange_rate: Option<Decimal>,
+    /// Identifier the external processor gave a deposit or withdrawal; only
+    /// present when one was supplied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_reference: Option<String>,
+    /// Category the client filed the transaction under, e.g. `groceries`;
+    /// only present when one was given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+impl SelectableFields for TransactionResponse {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "sender_account_id",
+        "receiver_account_id",
+        "amount",
+        "currency",
+        "transaction_type",
+        "status",
+        "description",
+        "created_at",
+    ];
 }
 
 impl From<Transaction> for TransactionResponse {
@@ -114,16 +210,313 @@ impl From<Transaction> for TransactionResponse {
             id: tx.id,
             sender_account_id: tx.sender_account_id,
             receiver_account_id: tx.receiver_account_id,
-            amount: tx.amount.into(),
+            // Always serialize with the currency's minor units (e.g. "10.10" USD)
+            amount: to_display_scale(tx.amount.into(), &tx.currency),
             currency: tx.currency,
             transaction_type: tx.transaction_type,
             status: tx.status,
             description: tx.description,
             created_at: tx.created_at,
+            balance_after: None,
+            fee_amount: None,
+            fee_currency: None,
+            original_amount: None,
+            original_currency: None,
+            exchange_rate: None,
+            external_reference: None,
+            category: None,
+        }
+    }
+}
+
+impl TransactionResponse {
+    /// Sets the fee charged to the sender, leaving it out when there was none
+    ///
+    /// The fee is in the currency the sender paid in, so conversions must be
+    /// set first with `with_conversion`.
+    pub fn with_fee(self, fee: Decimal) -> Self {
+        if fee.is_zero() {
+            return self;
+        }
+        let currency = self
+            .original_currency
+            .clone()
+            .unwrap_or_else(|| self.currency.clone());
+        Self {
+            fee_amount: Some(fee),
+            fee_currency: Some(currency),
+            ..self
+        }
+    }
+
+    /// Sets the identifier the external processor gave the transaction, if any
+    pub fn with_external_reference(self, external_reference: Option<String>) -> Self {
+        Self {
+            external_reference,
+            ..self
+        }
+    }
+
+    /// Sets the category the transaction was filed under, if any
+    pub fn with_category(self, category: Option<String>) -> Self {
+        Self { category, ..self }
+    }
+
+    /// Sets the side of a conversion the sender saw, leaving it out for
+    /// transactions that weren't converted
+    pub fn with_conversion(self, conversion: TransactionConversion) -> Self {
+        let TransactionConversion {
+            original_amount: Some(original_amount),
+            original_currency: Some(original_currency),
+            exchange_rate,
+        } = conversion
+        else {
+            return self;
+        };
+        Self {
+            original_amount: Some(to_display_scale(original_amount.into(), &original_currency)),
+            original_currency: Some(original_currency),
+            exchange_rate: exchange_rate.map(|rate| rate.0.normalize()),
+            ..self
         }
     }
 }
 
+/// What the sender of a converted transaction was debited, and the rate
+/// applied, as read from the database
+#[derive(Debug, Default, FromRow)]
+pub struct TransactionConversion {
+    pub original_amount: Option<SqlxDecimal>,
+    pub original_currency: Option<String>,
+    pub exchange_rate: Option<SqlxDecimal>,
+}
+
+/// A transaction with the fee charged on it, its conversion, its external
+/// reference and its category, as read from the database
+#[derive(Debug, FromRow)]
+pub struct TransactionDetails {
+    #[sqlx(flatten)]
+    pub transaction: Transaction,
+    pub fee_amount: SqlxDecimal,
+    /// The sender's currency, which the fee was charged in; NULL without a fee
+    pub fee_currency: Option<String>,
+    #[sqlx(flatten)]
+    pub conversion: TransactionConversion,
+    pub external_reference: Option<String>,
+    pub category: Option<String>,
+}
+
+impl From<TransactionDetails> for TransactionResponse {
+    fn from(row: TransactionDetails) -> Self {
+        let fee_currency = row
+            .fee_currency
+            .as_deref()
+            .unwrap_or(&row.transaction.currency);
+        let fee = to_display_scale(row.fee_amount.into(), fee_currency);
+        TransactionResponse::from(row.transaction)
+            .with_conversion(row.conversion)
+            .with_fee(fee)
+            .with_external_reference(row.external_reference)
+            .with_category(row.category)
+    }
+}
+
+/// A transaction read from one account's history, with that account's
+/// balance right after it
+#[derive(Debug, FromRow)]
+pub struct AccountTransaction {
+    #[sqlx(flatten)]
+    pub details: TransactionDetails,
+    pub balance_after: SqlxDecimal,
+}
+
+impl From<AccountTransaction> for TransactionResponse {
+    fn from(row: AccountTransaction) -> Self {
+        Self {
+            balance_after: Some(row.balance_after.into()),
+            ..TransactionResponse::from(row.details)
+        }
+    }
+}
+
+/// Direction of a transaction that credited the user's account
+pub const DIRECTION_INCOMING: &str = "INCOMING";
+/// Direction of a transaction that debited the user's account
+pub const DIRECTION_OUTGOING: &str = "OUTGOING";
+
+/// A transaction read from a user's merged history, as seen from one of
+/// their accounts
+#[derive(Debug, FromRow)]
+pub struct UserTransaction {
+    #[sqlx(flatten)]
+    pub transaction: Transaction,
+    pub account_id: Uuid,
+    pub direction: String,
+    #[sqlx(flatten)]
+    pub conversion: TransactionConversion,
+}
+
+/// A transaction in the merged history of all of a user's accounts
+///
+/// A transfer between two of the user's own accounts is listed twice, once
+/// from each account.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserTransactionResponse {
+    /// The public transaction fields
+    #[serde(flatten)]
+    pub transaction: TransactionResponse,
+    /// The user's account the transaction was booked on
+    pub account_id: Uuid,
+    /// INCOMING when money arrived in `account_id`, OUTGOING when it left it
+    pub direction: String,
+}
+
+impl SelectableFields for UserTransactionResponse {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "sender_account_id",
+        "receiver_account_id",
+        "amount",
+        "currency",
+        "transaction_type",
+        "status",
+        "description",
+        "created_at",
+        "account_id",
+        "direction",
+    ];
+}
+
+impl From<UserTransaction> for UserTransactionResponse {
+    fn from(row: UserTransaction) -> Self {
+        Self {
+            transaction: TransactionResponse::from(row.transaction).with_conversion(row.conversion),
+            account_id: row.account_id,
+            direction: row.direction,
+        }
+    }
+}
+
+/// Totals of an account's transactions sharing a type and status, as read
+/// from the database
+#[derive(Debug, FromRow)]
+pub struct TransactionTotalsRow {
+    pub transaction_type: String,
+    pub status: String,
+    pub count: i64,
+    pub amount_in: SqlxDecimal,
+    pub amount_out: SqlxDecimal,
+}
+
+/// Totals of an account's transactions sharing a type and status
+///
+/// Amounts are in the account's currency. Pending and failed transactions
+/// get groups of their own, although they did not move money.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionTotals {
+    pub transaction_type: String,
+    pub status: String,
+    /// Number of transactions in the group
+    pub count: i64,
+    /// Sum of the amounts credited to the account
+    pub amount_in: Decimal,
+    /// Sum of the amounts debited from the account
+    pub amount_out: Decimal,
+    /// `amount_in` minus `amount_out`
+    pub net_change: Decimal,
+}
+
+impl From<TransactionTotalsRow> for TransactionTotals {
+    fn from(row: TransactionTotalsRow) -> Self {
+        let amount_in = Decimal::from(row.amount_in);
+        let amount_out = Decimal::from(row.amount_out);
+        Self {
+            transaction_type: row.transaction_type,
+            status: row.status,
+            count: row.count,
+            amount_in,
+            amount_out,
+            net_change: amount_in - amount_out,
+        }
+    }
+}
+
+/// An account's transactions over a period, totalled by type and status
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionSummary {
+    pub account_id: Uuid,
+    /// Currency of every amount in the summary
+    pub currency: String,
+    /// Earliest creation time included, if bounded
+    pub from: Option<DateTime<Utc>>,
+    /// Creation time from which on transactions are excluded, if bounded
+    pub to: Option<DateTime<Utc>>,
+    /// One entry per type and status that occurred, ordered by type, then status
+    pub totals: Vec<TransactionTotals>,
+}
+
+/// An account's completed outgoing transactions in one category, as read
+/// from the database
+#[derive(Debug, FromRow)]
+pub struct CategorySpendingRow {
+    pub category: Option<String>,
+    pub count: i64,
+    pub total: SqlxDecimal,
+}
+
+/// An account's completed outgoing transactions in one category
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategorySpending {
+    /// The category; `None` for transactions filed under none, fees included
+    pub category: Option<String>,
+    /// Number of transactions in the category
+    pub count: i64,
+    /// Sum of the amounts debited from the account
+    pub total: Decimal,
+}
+
+impl From<CategorySpendingRow> for CategorySpending {
+    fn from(row: CategorySpendingRow) -> Self {
+        Self {
+            category: row.category,
+            count: row.count,
+            total: row.total.into(),
+        }
+    }
+}
+
+/// An account's spending over a period, totalled by category
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpendingReport {
+    pub account_id: Uuid,
+    /// Currency of every amount in the report
+    pub currency: String,
+    /// Earliest creation time included, if bounded
+    pub from: Option<DateTime<Utc>>,
+    /// Creation time from which on transactions are excluded, if bounded
+    pub to: Option<DateTime<Utc>>,
+    /// One entry per category spent in, largest total first
+    pub categories: Vec<CategorySpending>,
+}
+
+/// Transaction detail shown to administrators
+///
+/// Extends the public representation with the client that initiated the
+/// transaction. Never returned to regular users or counterparties.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminTransactionResponse {
+    /// The public transaction fields
+    #[serde(flatten)]
+    pub transaction: TransactionResponse,
+    /// IP address of the client that initiated the transaction, if captured
+    pub ip_address: Option<String>,
+    /// User-Agent of the client that initiated the transaction, if captured
+    pub user_agent: Option<String>,
+    /// How long each step of booking the transaction took; `None` once purged
+    /// or for transactions booked before timings were recorded
+    pub timings: Option<TransactionTimings>,
+}
+
 /// Request object for creating a generic transaction
 ///
 /// This is a flexible request format that can represent any type of transaction.
@@ -142,12 +535,20 @@ pub struct CreateTransactionRequest {
     #[validate(custom = "validate_positive_amount")]
     pub amount: Decimal,
 
-    /// Three-letter currency code
-    #[validate(length(min = 3, max = 3, message = "Currency must be a 3-letter code"))]
-    pub currency: String,
+    /// ISO 4217 currency code; optional, as the currency is taken from the
+    /// accounts involved and this value is only cross-checked against it
+    #[validate(custom = "validate_currency")]
+    pub currency: Option<String>,
 
     /// Optional transaction description or notes
     pub description: Option<String>,
+
+    /// Optional spending category, counted towards the sender's budgets
+    pub category: Option<String>,
+
+    /// Books a transfer even if it looks like a repeat of a recent one
+    #[serde(default)]
+    pub allow_duplicate: bool,
 }
 
 /// Request object specifically for transfers between accounts
@@ -166,6 +567,14 @@ pub struct TransferRequest {
 
     /// Optional transfer description or notes
     pub description: Option<String>,
+
+    /// Optional spending category, counted towards the sender's budgets
+    pub category: Option<String>,
+
+    /// Books the transfer even if an identical one was made moments ago;
+    /// see `TransactionConfig::duplicate_transfer_window`
+    #[serde(default)]
+    pub allow_duplicate: bool,
 }
 
 /// Request object specifically for deposits into an account
@@ -180,8 +589,29 @@ pub struct DepositRequest {
     #[validate(custom = "validate_positive_amount")]
     pub amount: Decimal,
 
+    /// Optional currency of the deposited funds (defaults to the account currency)
+    #[validate(custom = "validate_currency")]
+    pub currency: Option<String>,
+
+    /// Optional rate converting `currency` into the account currency,
+    /// only honoured when foreign-currency deposits are enabled
+    pub exchange_rate: Option<Decimal>,
+
     /// Optional deposit description or notes
     pub description: Option<String>,
+
+    /// Optional category, e.g. `salary`; deposits don't count towards budgets
+    pub category: Option<String>,
+
+    /// Optional identifier the external processor gave the deposit; no two
+    /// transactions may share one
+    #[serde(default)]
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "External reference must be 1-255 characters"
+    ))]
+    pub external_reference: Option<String>,
 }
 
 /// Request object specifically for withdrawals from an account
@@ -198,6 +628,253 @@ pub struct WithdrawalRequest {
 
     /// Optional withdrawal description or notes
     pub description: Option<String>,
+
+    /// Optional spending category, counted towards the sender's budgets
+    pub category: Option<String>,
+
+    /// Registered external destination the funds are sent to; required when
+    /// `require_withdrawal_destination` is enabled
+    #[serde(default)]
+    pub destination_id: Option<Uuid>,
+
+    /// Optional identifier the external processor gave the withdrawal; no
+    /// two transactions may share one
+    #[serde(default)]
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "External reference must be 1-255 characters"
+    ))]
+    pub external_reference: Option<String>,
+}
+
+/// Request object for paying funds out of an account
+///
+/// Booked as a WITHDRAWAL, but processed as a single unit so additional payout
+/// bookkeeping can be committed atomically with the debit.
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+pub struct PayoutRequest {
+    /// Account ID to pay money out of
+    pub account_id: Uuid,
+
+    /// Payout amount (must be positive)
+    #[validate(custom = "validate_positive_amount")]
+    pub amount: Decimal,
+
+    /// Optional payout description or notes
+    pub description: Option<String>,
+
+    /// Optional spending category, counted towards the sender's budgets
+    pub category: Option<String>,
+
+    /// Registered external destination the funds are paid to; required when
+    /// `require_withdrawal_destination` is enabled
+    #[serde(default)]
+    pub destination_id: Option<Uuid>,
+}
+
+/// Changes to a completed transaction
+///
+/// Only the description can change; any other field is rejected, and so is a
+/// request without `description`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateTransactionRequest {
+    /// New description; `null` removes it
+    #[serde(deserialize_with = "required_nullable")]
+    pub description: Option<String>,
+}
+
+/// Deserializes an optional value whose field must be present, even if only
+/// as `null`
+fn required_nullable<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer)
+}
+
+/// Most destinations a single split transfer may pay
+pub const MAX_SPLIT_TRANSFER_LEGS: usize = 50;
+
+/// One destination of a split transfer
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SplitTransferLeg {
+    /// Account ID to transfer this part of the payment to
+    pub receiver_account_id: Uuid,
+    /// Amount paid to this account (must be positive)
+    pub amount: Decimal,
+}
+
+/// Request object for paying several accounts from one account at once
+///
+/// The legs are booked together: either every destination is paid or none is.
+/// `TransactionService::process_split_transfer` checks there are between 1 and
+/// `MAX_SPLIT_TRANSFER_LEGS` of them, each with a positive amount.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SplitTransferRequest {
+    /// Account ID to transfer money from, debited once for the total
+    pub sender_account_id: Uuid,
+    /// Destinations and the amount each receives
+    pub legs: Vec<SplitTransferLeg>,
+    /// Optional description, stored on the split transfer and on every leg
+    pub description: Option<String>,
+}
+
+/// Most transfers a single batch may contain
+pub const MAX_BATCH_TRANSFERS: usize = 100;
+
+/// One transfer of a batch
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BatchTransfer {
+    /// Account ID to transfer from; defaults to the batch's `sender_account_id`
+    #[serde(default)]
+    pub sender_account_id: Option<Uuid>,
+    /// Account ID to transfer to
+    pub receiver_account_id: Uuid,
+    /// Amount to transfer, in the sender's currency (must be positive)
+    pub amount: Decimal,
+    /// Optional description of this transfer, e.g. the payslip it pays
+    pub description: Option<String>,
+    /// Optional spending category of this transfer
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// Request object for booking many transfers together, e.g. payroll
+///
+/// Either every transfer is booked or none is. Each transfer is processed like
+/// a single transfer, fees and currency conversion included, from its own
+/// sender or, if it names none, from the batch's.
+/// `TransactionService::process_batch_transfer` checks there are between 1 and
+/// `MAX_BATCH_TRANSFERS` of them.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BatchTransferRequest {
+    /// Account ID to transfer from when a transfer doesn't name its own
+    #[serde(default)]
+    pub sender_account_id: Option<Uuid>,
+    /// The transfers to book, in order
+    pub transfers: Vec<BatchTransfer>,
+}
+
+/// A completed batch of transfers
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchTransferResponse {
+    /// The booked transfers, in request order
+    pub transactions: Vec<TransactionResponse>,
+    /// Sum of the amounts the senders paid, by three-letter currency code;
+    /// fees are left out
+    pub total: BTreeMap<String, Decimal>,
+}
+
+impl From<Vec<TransactionResponse>> for BatchTransferResponse {
+    fn from(transactions: Vec<TransactionResponse>) -> Self {
+        let mut total: BTreeMap<String, Decimal> = BTreeMap::new();
+        for transaction in &transactions {
+            // Converted transfers keep what the sender paid as the original
+            let (amount, currency) =
+                match (transaction.original_amount, &transaction.original_currency) {
+                    (Some(amount), Some(currency)) => (amount, currency),
+                    _ => (transaction.amount, &transaction.currency),
+                };
+            *total.entry(currency.clone()).or_default() += amount;
+        }
+        Self {
+            transactions,
+            total,
+        }
+    }
+}
+
+/// A completed split transfer
+///
+/// Each leg is an ordinary TRANSFER that also appears in the accounts'
+/// transaction listings.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SplitTransferResponse {
+    /// Unique identifier of the split transfer, shared by its legs
+    pub id: Uuid,
+    /// Account ID the payment was made from
+    pub sender_account_id: Uuid,
+    /// Sum of all legs, debited from the sender
+    pub total: Decimal,
+    /// Three-letter currency code shared by every account involved
+    pub currency: String,
+    /// Optional description or notes
+    pub description: Option<String>,
+    /// The transfers making up the payment, in request order
+    pub legs: Vec<TransactionResponse>,
+    /// When the split transfer was booked
+    pub created_at: DateTime<Utc>,
+}
+
+/// Error code for reversing anything other than a transfer, or a reversal itself
+pub const TRANSACTION_NOT_REVERSIBLE: &str = "TRANSACTION_NOT_REVERSIBLE";
+/// Error code for reversing a transaction that was already reversed
+pub const TRANSACTION_ALREADY_REVERSED: &str = "TRANSACTION_ALREADY_REVERSED";
+/// Error code for reversing a transaction that never completed
+pub const TRANSACTION_NOT_COMPLETED: &str = "TRANSACTION_NOT_COMPLETED";
+/// Error code for a reversal the original receiver can no longer cover
+pub const REVERSAL_INSUFFICIENT_FUNDS: &str = "REVERSAL_INSUFFICIENT_FUNDS";
+
+/// A transfer and the compensating transfer that undid it
+///
+/// Both appear in the accounts' transaction listings; the original with
+/// status REVERSED.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionReversalResponse {
+    /// The reversed transfer
+    pub original: TransactionResponse,
+    /// The transfer moving the amount back from the original receiver to the sender
+    pub reversal: TransactionResponse,
+}
+
+/// Outcome of one check of a transfer validation
+///
+/// - PASSED: The transfer would get past the check
+/// - FAILED: The transfer would be rejected by the check
+/// - SKIPPED: The check couldn't run because a check it depends on failed
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum CheckStatus {
+    PASSED,
+    FAILED,
+    SKIPPED,
+}
+
+/// One check a transfer must pass, and how it went
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferCheck {
+    /// Name of the check, e.g. `sufficient_funds`
+    pub check: String,
+    pub status: CheckStatus,
+    /// Error code the transfer would be rejected with, if the check failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Why the check failed or was skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Result of validating a transfer without booking it
+///
+/// The checks are listed in the order a transfer runs them. A valid transfer
+/// may still be rejected when it is made, e.g. if the balance changes first.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransferValidation {
+    /// Whether every check passed
+    pub valid: bool,
+    pub checks: Vec<TransferCheck>,
+    /// Amount the sender would be debited before fees, in its currency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Decimal>,
+    /// Fee the sender would be charged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Decimal>,
+    /// The sender's currency, which `amount` and `fee` are in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
 /// Custom validator function to ensure all transaction amounts are positive
@@ -205,7 +882,7 @@ pub struct WithdrawalRequest {
 /// Financial transactions cannot have zero or negative amounts.
 /// This validator ensures all amount fields across transaction types
 /// have a value greater than zero.
-fn validate_positive_amount(amount: &Decimal) -> Result<(), ValidationError> {
+pub(crate) fn validate_positive_amount(amount: &Decimal) -> Result<(), ValidationError> {
     if *amount <= Decimal::ZERO {
         let mut err = ValidationError::new("amount_positive");
         err.message = Some("Amount must be positive".into());
@@ -213,3 +890,220 @@ fn validate_positive_amount(amount: &Decimal) -> Result<(), ValidationError> {
     }
     Ok(())
 }
+
+/// Column names of the transaction CSV export, in order
+pub const TRANSACTION_CSV_HEADER: [&str; 10] = [
+    "id",
+    "sender_account_id",
+    "receiver_account_id",
+    "amount",
+    "currency",
+    "transaction_type",
+    "status",
+    "description",
+    "created_at",
+    "updated_at",
+];
+
+impl Transaction {
+    /// Formats the transaction as a record of the CSV export
+    pub fn to_csv_record(&self) -> String {
+        let optional_id = |id: Option<Uuid>| id.map(|id| id.to_string()).unwrap_or_default();
+        csv_record(&[
+            self.id.to_string(),
+            optional_id(self.sender_account_id),
+            optional_id(self.receiver_account_id),
+            self.amount.to_string(),
+            self.currency.clone(),
+            self.transaction_type.clone(),
+            self.status.clone(),
+            self.description.clone().unwrap_or_default(),
+            self.created_at.to_rfc3339(),
+            self.updated_at.to_rfc3339(),
+        ])
+    }
+}
+
+/// Columns of an account's transaction export, in order
+pub const ACCOUNT_TRANSACTION_CSV_HEADER: [&str; 8] = [
+    "id",
+    "date",
+    "type",
+    "amount",
+    "currency",
+    "counterparty_account_id",
+    "status",
+    "description",
+];
+
+impl TransactionResponse {
+    /// Formats the transaction as a record of `account_id`'s export, naming
+    /// the other account of a transfer as the counterparty
+    pub fn to_account_csv_record(&self, account_id: Uuid) -> String {
+        let counterparty = if self.sender_account_id == Some(account_id) {
+            self.receiver_account_id
+        } else {
+            self.sender_account_id
+        };
+        csv_record(&[
+            self.id.to_string(),
+            self.created_at.to_rfc3339(),
+            self.transaction_type.clone(),
+            self.amount.to_string(),
+            self.currency.clone(),
+            counterparty.map(|id| id.to_string()).unwrap_or_default(),
+            self.status.clone(),
+            self.description.clone().unwrap_or_default(),
+        ])
+    }
+}
+
+/// A half-open time range `[start, end)` where either bound may be open
+pub type TimeRange = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// Optional criteria narrowing an account's transaction listing
+///
+/// Criteria that are set must all match; an empty filter matches everything.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct TransactionFilter {
+    /// Earliest creation time to include
+    pub from: Option<DateTime<Utc>>,
+    /// Creation time from which on transactions are excluded
+    pub to: Option<DateTime<Utc>>,
+    pub transaction_type: Option<TransactionType>,
+    pub status: Option<TransactionStatus>,
+    /// Smallest amount to include
+    pub min_amount: Option<Decimal>,
+    /// Largest amount to include
+    pub max_amount: Option<Decimal>,
+    /// Text the description must contain, ignoring case
+    pub search: Option<String>,
+}
+
+impl TransactionFilter {
+    /// The `ILIKE` pattern matching descriptions that contain `search`
+    ///
+    /// `%`, `_` and `\` in the search text are escaped, so they match
+    /// themselves instead of acting as wildcards.
+    pub fn search_pattern(&self) -> Option<String> {
+        let search = self
+            .search
+            .as_deref()
+            .map(str::trim)
+            .filter(|search| !search.is_empty())?;
+        let mut pattern = String::with_capacity(search.len() + 2);
+        pattern.push('%');
+        for c in search.chars() {
+            if matches!(c, '%' | '_' | '\\') {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+        pattern.push('%');
+        Some(pattern)
+    }
+
+    /// Rejects a time range that ends before it starts, and likewise for amounts
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(AppError::BadRequest(
+                    "'from' must not be after 'to'".to_string(),
+                ));
+            }
+        }
+        if let (Some(min_amount), Some(max_amount)) = (self.min_amount, self.max_amount) {
+            if min_amount > max_amount {
+                return Err(AppError::BadRequest(
+                    "'min_amount' must not be greater than 'max_amount'".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Position in an account's transaction listing, newest first
+///
+/// Clients receive it as an opaque base64 string and pass it back to fetch the
+/// rows strictly older than the last one they saw. Unlike an offset, it stays
+/// valid when new transactions are created between pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl TransactionCursor {
+    /// The cursor pointing just past the given transaction
+    pub fn after(transaction: &TransactionResponse) -> Self {
+        Self {
+            created_at: transaction.created_at,
+            id: transaction.id,
+        }
+    }
+
+    /// Encodes the cursor as a URL-safe base64 string
+    pub fn encode(&self) -> String {
+        let raw = format!(
+            "{}|{}",
+            self.created_at
+                .to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            self.id
+        );
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    /// Decodes a cursor previously returned by `encode`
+    ///
+    /// Fails with `AppError::BadRequest` for anything that isn't such a cursor.
+    pub fn decode(cursor: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::BadRequest("Invalid cursor".to_string());
+
+        let raw = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let (created_at, id) = raw.split_once('|').ok_or_else(invalid)?;
+
+        Ok(Self {
+            created_at: DateTime::parse_from_rfc3339(created_at)
+                .map_err(|_| invalid())?
+                .with_timezone(&Utc),
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Query parameters narrowing a transaction export to a range of days
+///
+/// Both ends are optional and inclusive; without them the whole ledger is exported.
+#[derive(Debug, Default, Deserialize)]
+pub struct TransactionExportParams {
+    /// First day (UTC) to include
+    pub from: Option<NaiveDate>,
+    /// Last day (UTC) to include
+    pub to: Option<NaiveDate>,
+}
+
+impl TransactionExportParams {
+    /// Resolves the days to the half-open time range `[start, end)` they cover
+    pub fn time_range(&self) -> Result<TimeRange, AppError> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(AppError::BadRequest(
+                    "'from' must not be after 'to'".to_string(),
+                ));
+            }
+        }
+
+        let start = self
+            .from
+            .and_then(|day| day.and_hms_opt(0, 0, 0))
+            .map(|t| t.and_utc());
+        let end = self
+            .to
+            .and_then(|day| day.succ_opt())
+            .and_then(|day| day.and_hms_opt(0, 0, 0))
+            .map(|t| t.and_utc());
+        Ok((start, end))
+    }
+}
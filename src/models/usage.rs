@@ -0,0 +1,40 @@
+use crate::utils::error::AppError;
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Number of requests one user made to one route on one day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct UsageRecord {
+    pub user_id: Uuid,
+    /// The matched route pattern, e.g. `/api/v1/accounts/:id`
+    pub route: String,
+    pub usage_date: NaiveDate,
+    pub request_count: i64,
+}
+
+/// Query parameters for usage reports
+#[derive(Debug, Deserialize)]
+pub struct UsageQueryParams {
+    /// First day to include; defaults to 29 days before `to`
+    pub from: Option<NaiveDate>,
+    /// Last day to include; defaults to today
+    pub to: Option<NaiveDate>,
+    /// Restricts the report to one user (admin report only)
+    pub user_id: Option<Uuid>,
+}
+
+impl UsageQueryParams {
+    /// Resolves the inclusive date range, defaulting to the 30 days ending `today`
+    pub fn date_range(&self, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), AppError> {
+        let to = self.to.unwrap_or(today);
+        let from = self.from.unwrap_or(to - Duration::days(29));
+        if from > to {
+            return Err(AppError::BadRequest(
+                "'from' must not be after 'to'".to_string(),
+            ));
+        }
+        Ok((from, to))
+    }
+}
@@ -4,6 +4,26 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use validator::Validate;
 
+/// Privilege levels a user can hold
+///
+/// - USER: Regular customer, limited to their own accounts
+/// - ADMIN: Operator with read access to every account and transaction
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum UserRole {
+    USER,
+    ADMIN,
+}
+
+impl std::fmt::Display for UserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UserRole::USER => write!(f, "USER"),
+            UserRole::ADMIN => write!(f, "ADMIN"),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct User {
     pub id: Uuid,
@@ -13,10 +33,21 @@ pub struct User {
     pub password_hash: String,
     pub first_name: Option<String>,
     pub last_name: Option<String>,
+    /// Preferred BCP-47 locale for exports and statements (e.g. `de-DE`)
+    pub locale: String,
+    /// Whether the user has confirmed their email address
+    pub email_verified: bool,
+    /// Whether logging in also takes a one-time password
+    pub totp_enabled: bool,
+    /// Privilege level as a string (USER, ADMIN)
+    pub role: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Fewest characters a password may have, matching `CreateUserRequest`
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
 #[derive(Debug, Serialize, Deserialize, Validate, Clone)]
 pub struct CreateUserRequest {
     #[validate(length(
@@ -36,6 +67,17 @@ pub struct CreateUserRequest {
     pub last_name: Option<String>,
 }
 
+/// Request to change the password of the signed-in user
+///
+/// The new password must have at least `MIN_PASSWORD_LENGTH` characters,
+/// like the one chosen at registration; `UserService::change_password`
+/// checks it after the current password.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate, Clone)]
 pub struct LoginRequest {
     #[validate(length(min = 1, message = "Username is required"))]
@@ -48,16 +90,68 @@ pub struct LoginRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginResponse {
     pub token: String,
+    pub refresh_token: String,
     pub user: UserResponse,
 }
 
+/// What a correct username and password lead to
+#[derive(Debug)]
+pub enum LoginOutcome {
+    /// The user is signed in
+    Session(LoginResponse),
+    /// The user has two-factor authentication enabled and must still give a
+    /// one-time password, with the challenge's token, at `/users/login/totp`
+    TotpRequired(TotpChallenge),
+}
+
+/// Token standing for a login that is waiting for its one-time password
 #[derive(Debug, Serialize, Deserialize)]
+pub struct TotpChallenge {
+    pub totp_token: String,
+    /// Seconds until the token expires
+    pub expires_in: i64,
+}
+
+/// Completes a login that is waiting for its one-time password
+#[derive(Debug, Serialize, Deserialize, Validate, Clone)]
+pub struct TotpLoginRequest {
+    #[validate(length(min = 1, message = "TOTP token is required"))]
+    pub totp_token: String,
+    pub code: String,
+}
+
+/// A one-time password confirming two-factor authentication setup
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+/// The secret two-factor authentication was set up with, for the user's
+/// authenticator app
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TotpSetupResponse {
+    /// Base32-encoded, for entering by hand
+    pub secret: String,
+    /// `otpauth://` URI, usually shown as a QR code
+    pub provisioning_uri: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, Clone)]
+pub struct RefreshTokenRequest {
+    #[validate(length(min = 1, message = "Refresh token is required"))]
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserResponse {
     pub id: Uuid,
     pub username: String,
     pub email: String,
     pub first_name: Option<String>,
     pub last_name: Option<String>,
+    pub locale: String,
+    pub email_verified: bool,
+    pub totp_enabled: bool,
 }
 
 impl From<User> for UserResponse {
@@ -68,6 +162,69 @@ impl From<User> for UserResponse {
             email: user.email,
             first_name: user.first_name,
             last_name: user.last_name,
+            locale: user.locale,
+            email_verified: user.email_verified,
+            totp_enabled: user.totp_enabled,
         }
     }
 }
+
+/// Domain of the placeholder email addresses erased users are left with
+///
+/// `.invalid` is reserved, so mail to these addresses can't reach anyone.
+pub const ERASED_EMAIL_DOMAIN: &str = "erased.invalid";
+
+/// Most users a single bulk creation request may contain
+pub const MAX_BULK_USERS: usize = 100;
+
+/// How long the email verification token sent at registration stays valid
+pub const VERIFICATION_TOKEN_TTL_HOURS: i64 = 24;
+
+/// Issuer shown next to the account in authenticator apps
+pub const TOTP_ISSUER: &str = "txn-manager";
+
+/// Outcome of one entry of a bulk user creation
+///
+/// - CREATED: The user and their default account were created
+/// - CONFLICT: The username or email is already taken, including by an
+///   earlier entry of the same batch
+/// - INVALID: The entry failed validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BulkUserStatus {
+    Created,
+    Conflict,
+    Invalid,
+}
+
+/// Result of one entry of a bulk user creation
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkUserResult {
+    /// Position of the entry in the request
+    pub index: usize,
+    pub username: String,
+    pub status: BulkUserStatus,
+    /// The new user, when one was created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<UserResponse>,
+    /// Why no user was created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A single recorded login attempt, as shown in a user's login history
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct LoginEvent {
+    pub id: Uuid,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub success: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Which optional notifications a user wants to receive
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NotificationPreferences {
+    /// Email receipts for transfers above the deployment's receipt threshold
+    pub transfer_receipts: bool,
+}
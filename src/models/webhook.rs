@@ -0,0 +1,219 @@
+use crate::models::transaction::TransactionResponse;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Longest URL accepted, matching the `webhooks.url` column
+pub const MAX_WEBHOOK_URL_LENGTH: usize = 2048;
+
+/// Event sent when a transaction involving one of the user's accounts completes
+pub const TRANSACTION_COMPLETED: &str = "transaction.completed";
+
+/// An endpoint a user registered to receive transaction events
+#[derive(Debug, Clone, FromRow)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub url: String,
+    /// Key deliveries are signed with; only returned once, at registration
+    pub secret: String,
+    /// Whether events are sent to the endpoint
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A registered webhook as shown to its owner
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookResponse {
+    pub id: Uuid,
+    pub url: String,
+    /// The signing secret, present only in the response to the registration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookResponse {
+    /// The webhook with its signing secret, for the user who just registered it
+    pub fn with_secret(webhook: Webhook) -> Self {
+        Self {
+            secret: Some(webhook.secret.clone()),
+            ..Self::from(webhook)
+        }
+    }
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            id: webhook.id,
+            url: webhook.url,
+            secret: None,
+            enabled: webhook.enabled,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+/// Request to register a webhook endpoint
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegisterWebhookRequest {
+    /// Absolute `http` or `https` URL events are POSTed to
+    pub url: String,
+}
+
+impl RegisterWebhookRequest {
+    /// Returns the URL in canonical form, rejecting anything that can't be called
+    pub fn normalized_url(&self) -> Result<String, AppError> {
+        normalize_url(&self.url)
+    }
+}
+
+/// Request to change a webhook; fields left out stay as they are
+///
+/// The secret can't be changed: a webhook needing a new one is deleted and
+/// registered again.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+impl UpdateWebhookRequest {
+    /// Returns the new URL in canonical form, if one was given
+    pub fn normalized_url(&self) -> Result<Option<String>, AppError> {
+        self.url.as_deref().map(normalize_url).transpose()
+    }
+}
+
+/// Returns `url` in canonical form, rejecting anything that can't be called
+fn normalize_url(url: &str) -> Result<String, AppError> {
+    let url = Url::parse(url.trim())
+        .map_err(|_| AppError::Validation("URL must be an absolute URL".to_string()))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AppError::Validation(
+            "URL must use http or https".to_string(),
+        ));
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err(AppError::Validation(
+            "URL must not contain credentials".to_string(),
+        ));
+    }
+    if url.as_str().len() > MAX_WEBHOOK_URL_LENGTH {
+        return Err(AppError::Validation(format!(
+            "URL must be at most {} characters",
+            MAX_WEBHOOK_URL_LENGTH
+        )));
+    }
+
+    Ok(url.into())
+}
+
+/// Where a delivery stands
+///
+/// - PENDING: Being attempted, or waiting for its next retry
+/// - DELIVERED: The endpoint answered with a 2xx status
+/// - DEAD_LETTERED: Every retry failed; the event is only sent again on request
+#[allow(clippy::upper_case_acronyms, non_camel_case_types)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum WebhookDeliveryStatus {
+    PENDING,
+    DELIVERED,
+    DEAD_LETTERED,
+}
+
+impl std::fmt::Display for WebhookDeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebhookDeliveryStatus::PENDING => write!(f, "PENDING"),
+            WebhookDeliveryStatus::DELIVERED => write!(f, "DELIVERED"),
+            WebhookDeliveryStatus::DEAD_LETTERED => write!(f, "DEAD_LETTERED"),
+        }
+    }
+}
+
+/// An event sent, or being sent, to a webhook
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub transaction_id: Option<Uuid>,
+    /// The body exactly as signed and sent
+    pub payload: String,
+    /// Status as a string (PENDING, DELIVERED, DEAD_LETTERED)
+    pub status: String,
+    /// Attempts made so far
+    pub attempts: i32,
+    /// HTTP status the endpoint answered the latest attempt with
+    pub response_status: Option<i32>,
+    /// Why the latest attempt failed
+    pub last_error: Option<String>,
+    /// When a pending delivery is retried
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One attempt at a delivery
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookDeliveryAttempt {
+    /// 1 for the first attempt, counting up
+    pub attempt: i32,
+    /// HTTP status of the answer; not set when none came
+    pub response_status: Option<i32>,
+    /// Time from sending the request to the answer or the failure
+    pub latency_ms: i32,
+    /// Why the attempt failed; not set for accepted attempts
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A delivery with each of its attempts, oldest first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookDeliveryResponse {
+    #[serde(flatten)]
+    pub delivery: WebhookDelivery,
+    pub attempt_log: Vec<WebhookDeliveryAttempt>,
+}
+
+/// Body POSTed to webhook endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    /// Kind of event, currently always `transaction.completed`
+    pub event: String,
+    pub transaction_id: Uuid,
+    pub transaction_type: String,
+    pub amount: Decimal,
+    pub currency: String,
+    pub status: String,
+    /// Account debited, if any (not set for deposits)
+    pub sender_account_id: Option<Uuid>,
+    /// Account credited, if any (not set for withdrawals)
+    pub receiver_account_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookPayload {
+    /// The event announcing that `transaction` completed
+    pub fn transaction_completed(transaction: &TransactionResponse) -> Self {
+        Self {
+            event: TRANSACTION_COMPLETED.to_string(),
+            transaction_id: transaction.id,
+            transaction_type: transaction.transaction_type.clone(),
+            amount: transaction.amount,
+            currency: transaction.currency.clone(),
+            status: transaction.status.clone(),
+            sender_account_id: transaction.sender_account_id,
+            receiver_account_id: transaction.receiver_account_id,
+            created_at: transaction.created_at,
+        }
+    }
+}
@@ -1,8 +1,24 @@
-use crate::models::account::{Account, AccountResponse};
+use crate::config::AccountConfig;
+use crate::models::account::{
+    ensure_account_active, Account, AccountLimits, AccountLimitsRow, AccountMergeResponse,
+    AccountResponse, AccountStatus, ACCOUNT_CLOSED, MAX_BALANCE_SCALE,
+};
+use crate::models::balance_history::{BalanceSnapshot, BalanceSnapshotResponse};
 use crate::models::decimal::SqlxDecimal;
+use crate::models::outbox::ACCOUNT_CREATED;
+use crate::models::statement::{month_period, AccountStatement, FinalStatement, StatementBalances};
+use crate::models::transaction::{AccountTransaction, TransactionResponse};
+use crate::services::notification_service::NotificationService;
+use crate::services::outbox_service::record_event;
+use crate::utils::account_number::AccountNumberGenerator;
+use crate::utils::currency::{is_valid_iso4217, normalize_currency};
 use crate::utils::error::AppError;
+use crate::utils::name_match::{match_name, NameMatch};
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
-use sqlx::PgPool;
+use sqlx::types::Json;
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use std::sync::Arc;
 use uuid::Uuid;
 
 /// Service for managing user accounts
@@ -16,12 +32,42 @@ use uuid::Uuid;
 /// all balance operations maintain consistency and prevent negative balances.
 pub struct AccountService {
     pool: PgPool,
+    number_generator: Box<dyn AccountNumberGenerator>,
+    /// Limits new accounts are opened with
+    default_limits: AccountLimits,
+    /// Emails owners the final statement of accounts they close, when configured
+    notifications: Option<Arc<NotificationService>>,
+}
+
+/// How many fresh account numbers to try before giving up on a collision streak
+const ACCOUNT_NUMBER_ATTEMPTS: usize = 5;
+
+/// The columns of an account a merge checks
+#[derive(sqlx::FromRow)]
+struct MergeCandidate {
+    id: Uuid,
+    user_id: Uuid,
+    currency: String,
+    balance: SqlxDecimal,
+    held_amount: SqlxDecimal,
+    status: String,
 }
 
 impl AccountService {
-    /// Creates a new account service with the given database pool
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    /// Creates a new account service with the given database pool and account settings
+    pub fn new(pool: PgPool, config: AccountConfig) -> Self {
+        Self {
+            pool,
+            number_generator: config.number_format.generator(),
+            default_limits: config.default_limits,
+            notifications: None,
+        }
+    }
+
+    /// Emails owners the final statement of the accounts they close
+    pub fn with_notifications(mut self, notifications: Arc<NotificationService>) -> Self {
+        self.notifications = Some(notifications);
+        self
     }
 
     /// Fetches an account by its ID
@@ -32,40 +78,319 @@ impl AccountService {
     /// # Returns
     /// The account details wrapped in an AccountResponse if found
     pub async fn get_account_by_id(&self, id: Uuid) -> Result<AccountResponse, AppError> {
-        let account = sqlx::query_as!(
-            Account,
+        self.get_account_as_of(id, None).await
+    }
+
+    /// Fetches an account by its ID, optionally with its balance at a past moment
+    ///
+    /// # Arguments
+    /// * `id` - The UUID of the account to retrieve
+    /// * `as_of` - When set, the balance is the one the account held at this time
+    ///
+    /// # Returns
+    /// The account details wrapped in an AccountResponse if found, with
+    /// `balance_as_of` set when the balance is historical
+    ///
+    /// # Implementation Details
+    /// Balances are stored on the account rather than kept as a history, so a
+    /// historical balance is derived from the ledger: completed transactions
+    /// created after `as_of` are backed out of the current balance. Reversed
+    /// transfers moved money too, and count alongside their reversals. The
+    /// sender of a converted transfer gets back what it paid, not the converted
+    /// amount. Both are read in one statement so they come from the same
+    /// snapshot. Direct balance adjustments made after `as_of` are not part of
+    /// the ledger and are not backed out.
+    pub async fn get_account_as_of(
+        &self,
+        id: Uuid,
+        as_of: Option<DateTime<Utc>>,
+    ) -> Result<AccountResponse, AppError> {
+        if as_of.is_some_and(|as_of| as_of > Utc::now()) {
+            return Err(AppError::BadRequest(
+                "as_of must not be in the future".to_string(),
+            ));
+        }
+
+        let account = sqlx::query_as::<_, Account>(
             r#"
-            SELECT id, user_id, balance as "balance: SqlxDecimal", currency, created_at, updated_at
-            FROM accounts WHERE id = $1
+            SELECT a.id, a.user_id, a.account_number, a.currency, a.balance_scale, a.status,
+                   a.closed_at, a.created_at, a.updated_at,
+                   a.balance - COALESCE((
+                       SELECT SUM(CASE WHEN t.receiver_account_id = a.id THEN t.amount
+                                       ELSE -COALESCE(t.original_amount, t.amount) END)
+                       FROM transactions t
+                       WHERE (t.receiver_account_id = a.id OR t.sender_account_id = a.id)
+                         AND t.status IN ('COMPLETED', 'REVERSED')
+                         AND t.created_at > $2
+                   ), 0) AS balance
+            FROM accounts a WHERE a.id = $1
             "#,
-            id
         )
+        .bind(id)
+        .bind(as_of)
         .fetch_optional(&self.pool)
         .await?
         .ok_or_else(|| AppError::NotFound(format!("Account with ID {} not found", id)))?;
 
-        Ok(AccountResponse::from(account))
+        Ok(AccountResponse {
+            balance_as_of: as_of,
+            ..AccountResponse::from(account)
+        })
+    }
+
+    /// Builds an account's statement for one calendar month (UTC)
+    ///
+    /// # Arguments
+    /// * `id` - The account to report on
+    /// * `year`, `month` - The month covered
+    ///
+    /// # Returns
+    /// The opening and closing balances, credit and debit totals, and the
+    /// transactions that moved money in the month. Fails with
+    /// `AppError::BadRequest` for a month that hasn't started yet or that
+    /// ended before the account was opened.
+    ///
+    /// # Implementation Details
+    /// Like `get_account_as_of`, balances are derived from the current balance
+    /// by backing out completed and reversed transactions, here those created
+    /// from the start of the month on. A transaction created exactly at
+    /// midnight on the first belongs to that month, not the one before. Both
+    /// statements run in one read-only repeatable read transaction, so the
+    /// totals and the listing agree even while money moves.
+    pub async fn get_statement(
+        &self,
+        id: Uuid,
+        year: i32,
+        month: u32,
+    ) -> Result<AccountStatement, AppError> {
+        let (period_start, period_end) = month_period(year, month)?;
+        if period_start > Utc::now() {
+            return Err(AppError::BadRequest(
+                "Statements can't cover months that haven't started".to_string(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *tx)
+            .await?;
+        let (balances, transactions) = self
+            .read_statement(&mut tx, id, period_start, period_end)
+            .await?;
+        tx.commit().await?;
+        if balances.created_at >= period_end {
+            return Err(AppError::BadRequest(
+                "The account was opened after this month".to_string(),
+            ));
+        }
+
+        Ok(AccountStatement {
+            account_id: id,
+            currency: balances.currency,
+            year: Some(year),
+            month: Some(month),
+            period_start,
+            period_end,
+            opening_balance: balances.opening_balance.into(),
+            closing_balance: balances.closing_balance.into(),
+            total_credits: balances.total_credits.into(),
+            total_debits: balances.total_debits.into(),
+            transactions,
+        })
+    }
+
+    /// Fetches the statement of an account closed with `close_account`
+    ///
+    /// # Returns
+    /// The final statement, or `AppError::NotFound` if the account doesn't
+    /// exist or was never closed. Merged accounts have none, since their
+    /// transactions moved to the account they were merged into.
+    pub async fn get_final_statement(&self, id: Uuid) -> Result<FinalStatement, AppError> {
+        let statement = sqlx::query_scalar::<_, Json<FinalStatement>>(
+            "SELECT statement FROM final_statements WHERE account_id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Account {} has no final statement", id)))?;
+
+        Ok(statement.0)
+    }
+
+    /// Reads an account's balances and money movements within a time range
+    ///
+    /// Balances are derived backwards from the current one, so the range can
+    /// end in the future. Runs on `tx` so callers choose its isolation.
+    async fn read_statement(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: Uuid,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> Result<(StatementBalances, Vec<TransactionResponse>), AppError> {
+        let balances = sqlx::query_as::<_, StatementBalances>(
+            r#"
+            WITH changes AS (
+                SELECT created_at,
+                       CASE WHEN receiver_account_id = $1 THEN amount
+                            ELSE -COALESCE(original_amount, amount) END AS change
+                FROM transactions
+                WHERE (sender_account_id = $1 OR receiver_account_id = $1)
+                  AND status IN ('COMPLETED', 'REVERSED')
+                  AND created_at >= $2
+            )
+            SELECT a.currency, a.created_at,
+                   a.balance - COALESCE((SELECT SUM(change) FROM changes), 0)
+                       AS opening_balance,
+                   a.balance - COALESCE((SELECT SUM(change) FROM changes
+                                         WHERE created_at >= $3), 0)
+                       AS closing_balance,
+                   COALESCE((SELECT SUM(change) FROM changes
+                             WHERE change > 0 AND created_at < $3), 0)
+                       AS total_credits,
+                   COALESCE((SELECT -SUM(change) FROM changes
+                             WHERE change < 0 AND created_at < $3), 0)
+                       AS total_debits
+            FROM accounts a
+            WHERE a.id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(period_start)
+        .bind(period_end)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Account with ID {} not found", id)))?;
+        let transactions = sqlx::query_as::<_, AccountTransaction>(
+            r#"
+            WITH ledger AS (
+                SELECT t.id, t.sender_account_id, t.receiver_account_id, t.amount, t.currency,
+                       t.transaction_type, t.status, t.description, t.created_at, t.updated_at,
+                       t.fee_amount, t.fee_currency,
+                       t.original_amount, t.original_currency, t.exchange_rate, t.external_reference,
+                       t.category,
+                       SUM(CASE WHEN t.receiver_account_id = $1 THEN t.amount
+                                ELSE -COALESCE(t.original_amount, t.amount) END)
+                           OVER (ORDER BY t.created_at DESC, t.id DESC
+                                 ROWS BETWEEN UNBOUNDED PRECEDING AND 1 PRECEDING)
+                           AS newer_change
+                FROM transactions t
+                WHERE (t.sender_account_id = $1 OR t.receiver_account_id = $1)
+                  AND t.status IN ('COMPLETED', 'REVERSED')
+                  AND t.created_at >= $2
+            )
+            SELECT l.id, l.sender_account_id, l.receiver_account_id, l.amount, l.currency,
+                   l.transaction_type, l.status, l.description, l.created_at, l.updated_at,
+                   a.balance - COALESCE(l.newer_change, 0) AS balance_after,
+                   l.fee_amount, l.fee_currency,
+                   l.original_amount, l.original_currency, l.exchange_rate, l.external_reference,
+                   l.category
+            FROM ledger l
+            JOIN accounts a ON a.id = $1
+            WHERE l.created_at < $3
+            ORDER BY l.created_at, l.id
+            "#,
+        )
+        .bind(id)
+        .bind(period_start)
+        .bind(period_end)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok((
+            balances,
+            transactions
+                .into_iter()
+                .map(TransactionResponse::from)
+                .collect(),
+        ))
+    }
+
+    /// Lists an account's balance after each change, oldest first
+    ///
+    /// # Arguments
+    /// * `id` - The account to report on
+    /// * `from`, `to` - Optional inclusive bounds on when the changes happened
+    ///
+    /// # Returns
+    /// The balance snapshots in the range, or `AppError::Validation` if `from`
+    /// is after `to`. Only changes made since snapshots were introduced are
+    /// recorded.
+    pub async fn get_balance_history(
+        &self,
+        id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<BalanceSnapshotResponse>, AppError> {
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                return Err(AppError::Validation(
+                    "'from' must not be after 'to'".to_string(),
+                ));
+            }
+        }
+        // Fails with NotFound for an unknown account
+        self.get_account_by_id(id).await?;
+
+        let snapshots = sqlx::query_as::<_, BalanceSnapshot>(
+            r#"
+            SELECT transaction_id, balance, created_at
+            FROM account_balance_snapshots
+            WHERE account_id = $1
+              AND ($2::TIMESTAMPTZ IS NULL OR created_at >= $2)
+              AND ($3::TIMESTAMPTZ IS NULL OR created_at <= $3)
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(snapshots
+            .into_iter()
+            .map(BalanceSnapshotResponse::from)
+            .collect())
     }
 
-    /// Retrieves all accounts for a user
+    /// Retrieves all open accounts for a user
     ///
     /// # Arguments
     /// * `user_id` - The UUID of the user whose accounts should be retrieved
     ///
     /// # Returns
-    /// A vector of account responses
+    /// A vector of account responses, leaving out closed accounts
     pub async fn get_accounts_by_user_id(
         &self,
         user_id: Uuid,
     ) -> Result<Vec<AccountResponse>, AppError> {
-        let accounts = sqlx::query_as!(
-            Account,
+        self.list_accounts_by_user_id(user_id, false).await
+    }
+
+    /// Retrieves a user's accounts, optionally including closed ones
+    ///
+    /// # Arguments
+    /// * `user_id` - The UUID of the user whose accounts should be retrieved
+    /// * `include_closed` - Whether closed accounts are listed too
+    ///
+    /// # Returns
+    /// A vector of account responses
+    pub async fn list_accounts_by_user_id(
+        &self,
+        user_id: Uuid,
+        include_closed: bool,
+    ) -> Result<Vec<AccountResponse>, AppError> {
+        let accounts = sqlx::query_as::<_, Account>(
             r#"
-            SELECT id, user_id, balance as "balance: SqlxDecimal", currency, created_at, updated_at
-            FROM accounts WHERE user_id = $1
+            SELECT id, user_id, account_number, balance, currency, balance_scale, status, closed_at,
+                   created_at, updated_at
+            FROM accounts WHERE user_id = $1 AND ($2 OR status <> 'CLOSED')
+            ORDER BY created_at, id
             "#,
-            user_id
         )
+        .bind(user_id)
+        .bind(include_closed)
         .fetch_all(&self.pool)
         .await?;
 
@@ -86,7 +411,9 @@ impl AccountService {
     /// 1. Verifies the user exists
     /// 2. Creates a new account with zero initial balance
     /// 3. Associates the account with the user
-    /// 
+    /// 4. Assigns an account number in the configured format, drawing a new one
+    ///    if the database reports it is already taken
+    ///
     /// New accounts always start with a zero balance. The balance can only
     /// be modified through proper transaction operations.
     pub async fn create_account(
@@ -94,6 +421,41 @@ impl AccountService {
         user_id: Uuid,
         currency: String,
     ) -> Result<AccountResponse, AppError> {
+        self.create_account_with_scale(user_id, currency, None)
+            .await
+    }
+
+    /// Creates a new account that keeps its balance in `balance_scale` decimal places
+    ///
+    /// Like `create_account`, for assets such as crypto that need more decimal
+    /// places than their currency uses. Without a scale the currency's applies.
+    /// Fails with `BadRequest` if the currency isn't an ISO 4217 code or the
+    /// scale exceeds `MAX_BALANCE_SCALE`.
+    pub async fn create_account_with_scale(
+        &self,
+        user_id: Uuid,
+        currency: String,
+        balance_scale: Option<u32>,
+    ) -> Result<AccountResponse, AppError> {
+        // Store codes in uppercase so `usd` and `USD` are the same currency
+        let currency = normalize_currency(&currency);
+        if !is_valid_iso4217(&currency) {
+            return Err(AppError::BadRequest(format!(
+                "Unknown currency code {}",
+                currency
+            )));
+        }
+        let balance_scale = match balance_scale {
+            Some(scale) if scale > MAX_BALANCE_SCALE => {
+                return Err(AppError::BadRequest(format!(
+                    "Balance scale must be at most {}",
+                    MAX_BALANCE_SCALE
+                )))
+            }
+            Some(scale) => Some(scale as i16),
+            None => None,
+        };
+
         // Check if user exists - we don't want orphaned accounts
         let user_exists = sqlx::query!(
             r#"
@@ -111,37 +473,90 @@ impl AccountService {
             )));
         }
 
-        // Create account with a new UUID and initial zero balance
+        // Create account with a new UUID and initial zero balance, together
+        // with its default limits
         let id = Uuid::new_v4();
 
-        // For SQLx offline mode with type safety, use raw query text
-        // This bypasses the SQLx type checking for our custom SqlxDecimal type
-        // We explicitly use a raw query to handle the custom decimal type properly
-        let query = format!(
-            "INSERT INTO accounts (id, user_id, balance, currency) 
-             VALUES ('{}', '{}', '0', '{}') 
-             RETURNING id, user_id, balance::TEXT, currency, created_at, updated_at",
-            id, user_id, currency
-        );
-
-        let row = sqlx::query(&query).fetch_one(&self.pool).await?;
-
-        // Extract fields from row using fully qualified syntax
-        // This manual construction is needed because we can't use query_as! with a dynamic query
-        let account = Account {
-            id: sqlx::Row::get(&row, "id"),
-            user_id: sqlx::Row::get(&row, "user_id"),
-            balance: SqlxDecimal(
-                sqlx::Row::get::<&str, _>(&row, "balance")
-                    .parse()
-                    .unwrap_or(Decimal::ZERO),
-            ),
-            currency: sqlx::Row::get(&row, "currency"),
-            created_at: sqlx::Row::get(&row, "created_at"),
-            updated_at: sqlx::Row::get(&row, "updated_at"),
-        };
+        for _ in 0..ACCOUNT_NUMBER_ATTEMPTS {
+            let account_number = self.number_generator.generate();
+            let mut tx = self.pool.begin().await?;
+            let result = sqlx::query_as::<_, Account>(
+                r#"
+                WITH account AS (
+                    INSERT INTO accounts (id, user_id, account_number, balance, currency, balance_scale)
+                    VALUES ($1, $2, $3, 0, $4, $5)
+                    RETURNING id, user_id, account_number, balance, currency, balance_scale, status,
+                              closed_at, created_at, updated_at
+                ), limits AS (
+                    INSERT INTO account_limits (account_id, per_transaction_limit, daily_limit,
+                                                daily_withdrawal_limit, daily_transfer_limit)
+                    SELECT id, $6, $7, $8, $9 FROM account
+                )
+                SELECT * FROM account
+                "#,
+            )
+            .bind(id)
+            .bind(user_id)
+            .bind(&account_number)
+            .bind(&currency)
+            .bind(balance_scale)
+            .bind(self.default_limits.per_transaction_limit.map(SqlxDecimal))
+            .bind(self.default_limits.daily_limit.map(SqlxDecimal))
+            .bind(self.default_limits.daily_withdrawal_limit.map(SqlxDecimal))
+            .bind(self.default_limits.daily_transfer_limit.map(SqlxDecimal))
+            .fetch_one(&mut *tx)
+            .await;
+
+            match result {
+                Ok(account) => {
+                    let account = AccountResponse::from(account);
+                    record_event(&mut tx, ACCOUNT_CREATED, account.id, &account).await?;
+                    tx.commit().await?;
+                    return Ok(account);
+                }
+                // Another account already holds this number; draw a new one
+                Err(sqlx::Error::Database(e))
+                    if e.constraint() == Some("accounts_account_number_key") =>
+                {
+                    continue
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
 
-        Ok(AccountResponse::from(account))
+        Err(AppError::Internal(
+            "Could not allocate a unique account number".to_string(),
+        ))
+    }
+
+    /// Checks a payee name against the name of the account's owner
+    ///
+    /// # Arguments
+    /// * `id` - The UUID of the account the sender intends to pay
+    /// * `name` - The name the sender believes owns the account
+    ///
+    /// # Returns
+    /// How closely the names match; the owner's name itself is never returned
+    pub async fn check_account_name(&self, id: Uuid, name: &str) -> Result<NameMatch, AppError> {
+        let (first_name, last_name) = sqlx::query_as::<_, (Option<String>, Option<String>)>(
+            r#"
+            SELECT u.first_name, u.last_name
+            FROM accounts a JOIN users u ON u.id = a.user_id
+            WHERE a.id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Account with ID {} not found", id)))?;
+
+        let owner_name = [first_name, last_name]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Ok(match_name(name, &owner_name))
     }
 
     /// Updates an account's balance by adding or subtracting the specified amount
@@ -179,13 +594,13 @@ impl AccountService {
         // Get current account with an exclusive lock (FOR UPDATE)
         // This prevents concurrent updates to the same account, avoiding race conditions
         // that could lead to inconsistencies like double-spending or incorrect balances
-        let query = format!(
-            "SELECT id, user_id, balance::TEXT, currency, created_at, updated_at 
-             FROM accounts WHERE id = '{}' FOR UPDATE",
-            id
-        );
-
-        let row_option = sqlx::query(&query).fetch_optional(&mut *tx).await?;
+        let row_option = sqlx::query(
+            "SELECT id, user_id, account_number, balance::TEXT, currency, created_at, updated_at
+             FROM accounts WHERE id = $1 FOR UPDATE",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
 
         // Verify account exists
         let row = row_option
@@ -206,29 +621,33 @@ impl AccountService {
             return Err(AppError::BadRequest("Insufficient funds".to_string()));
         }
 
-        // Update balance using a raw query 
-        // We use string formatting for the balance to maintain precision
-        let update_query = format!(
-            "UPDATE accounts 
-             SET balance = '{}', updated_at = NOW() 
-             WHERE id = '{}' 
-             RETURNING id, user_id, balance::TEXT, currency, created_at, updated_at",
-            new_balance.to_string(),
-            id
-        );
-
-        let updated_row = sqlx::query(&update_query).fetch_one(&mut *tx).await?;
+        // Update balance, reading it back as text to maintain precision
+        let updated_row = sqlx::query(
+            "UPDATE accounts
+             SET balance = $2
+             WHERE id = $1
+             RETURNING id, user_id, account_number, balance::TEXT, currency, balance_scale, status,
+                       closed_at, created_at, updated_at",
+        )
+        .bind(id)
+        .bind(SqlxDecimal(new_balance))
+        .fetch_one(&mut *tx)
+        .await?;
 
         // Manually create the Account struct with updated balance
         let updated_account = Account {
             id: sqlx::Row::get(&updated_row, "id"),
             user_id: sqlx::Row::get(&updated_row, "user_id"),
+            account_number: sqlx::Row::get(&updated_row, "account_number"),
             balance: SqlxDecimal(
                 sqlx::Row::get::<&str, _>(&updated_row, "balance")
                     .parse()
                     .unwrap_or(Decimal::ZERO),
             ),
             currency: sqlx::Row::get(&updated_row, "currency"),
+            balance_scale: sqlx::Row::get(&updated_row, "balance_scale"),
+            status: sqlx::Row::get(&updated_row, "status"),
+            closed_at: sqlx::Row::get(&updated_row, "closed_at"),
             created_at: sqlx::Row::get(&updated_row, "created_at"),
             updated_at: sqlx::Row::get(&updated_row, "updated_at"),
         };
@@ -240,4 +659,375 @@ impl AccountService {
         // Return the updated account information
         Ok(AccountResponse::from(updated_account))
     }
+
+    /// Helper function to change an account balance within a database transaction
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use
+    /// * `account_id` - ID of the account to update
+    /// * `amount` - Amount to add to the balance (negative for subtraction)
+    /// * `transaction_id` - The transaction moving the money; `None` for
+    ///   changes no transaction stands for, like merging accounts
+    ///
+    /// # Implementation Note
+    /// The account balance check is handled at the database level with a
+    /// CHECK constraint. Closed accounts are rejected with `ACCOUNT_CLOSED`.
+    /// The new balance is recorded as a snapshot in the same database
+    /// transaction, so the balance history can't disagree with the balance.
+    pub(crate) async fn change_balance(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: Uuid,
+        amount: Decimal,
+        transaction_id: Option<Uuid>,
+    ) -> Result<(), AppError> {
+        let balance = sqlx::query_scalar::<_, SqlxDecimal>(
+            "UPDATE accounts SET balance = balance + $2 WHERE id = $1 AND closed_at IS NULL
+             RETURNING balance",
+        )
+        .bind(account_id)
+        .bind(SqlxDecimal(amount))
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        // Closed accounts keep their history but take no new money movements
+        let Some(balance) = balance else {
+            return Err(AppError::Rejected {
+                code: ACCOUNT_CLOSED,
+                message: format!("Account {} is closed", account_id),
+            });
+        };
+
+        sqlx::query(
+            "INSERT INTO account_balance_snapshots (id, account_id, transaction_id, balance)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(account_id)
+        .bind(transaction_id)
+        .bind(balance)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Merges a duplicate account into another account of the same user
+    ///
+    /// # Arguments
+    /// * `source_id` - The account to merge and close
+    /// * `target_id` - The account taking over its transactions and balance
+    ///
+    /// # Returns
+    /// Both accounts after the merge, and how many transactions were moved
+    ///
+    /// # Implementation Details
+    /// In one database transaction, this method:
+    /// 1. Locks both accounts, in ID order like split transfers and reversals
+    /// 2. Checks both are active, belong to the same user and use the same
+    ///    currency, and that the source has no active holds
+    /// 3. Moves the source's transactions and split transfers to the target.
+    ///    Transfers between the two accounts stay as they are, since an account
+    ///    can't transfer to itself
+    /// 4. Moves the source balance to the target with `change_balance`, so
+    ///    both balance histories record it, and closes the source, recording
+    ///    the target in `merged_into`
+    pub async fn merge_accounts(
+        &self,
+        source_id: Uuid,
+        target_id: Uuid,
+    ) -> Result<AccountMergeResponse, AppError> {
+        if source_id == target_id {
+            return Err(AppError::BadRequest(
+                "Cannot merge an account into itself".to_string(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let mut account_ids = vec![source_id, target_id];
+        account_ids.sort();
+        let accounts = sqlx::query_as::<_, MergeCandidate>(
+            r#"
+            SELECT id, user_id, currency, balance, held_amount, status FROM accounts
+            WHERE id = ANY($1) ORDER BY id FOR UPDATE
+            "#,
+        )
+        .bind(&account_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+        let find = |id: Uuid| {
+            accounts
+                .iter()
+                .find(|account| account.id == id)
+                .ok_or_else(|| AppError::NotFound(format!("Account with ID {} not found", id)))
+        };
+        let source = find(source_id)?;
+        let target = find(target_id)?;
+
+        for account in [source, target] {
+            ensure_account_active(account.id, &account.status)?;
+        }
+        if source.user_id != target.user_id {
+            return Err(AppError::BadRequest(
+                "Only accounts of the same user can be merged".to_string(),
+            ));
+        }
+        if source.currency != target.currency {
+            return Err(AppError::BadRequest(
+                "Currency mismatch between accounts".to_string(),
+            ));
+        }
+        if source.held_amount.0 > Decimal::ZERO {
+            return Err(AppError::Conflict(format!(
+                "Account {} has active holds; capture or release them first",
+                source_id
+            )));
+        }
+
+        let sent = sqlx::query(
+            r#"
+            UPDATE transactions SET sender_account_id = $2
+            WHERE sender_account_id = $1 AND receiver_account_id IS DISTINCT FROM $2
+            "#,
+        )
+        .bind(source_id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let received = sqlx::query(
+            r#"
+            UPDATE transactions SET receiver_account_id = $2
+            WHERE receiver_account_id = $1 AND sender_account_id IS DISTINCT FROM $2
+            "#,
+        )
+        .bind(source_id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        sqlx::query(
+            "UPDATE split_transfers SET sender_account_id = $2 WHERE sender_account_id = $1",
+        )
+        .bind(source_id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // Both balance changes show up in the accounts' balance histories
+        self.change_balance(&mut tx, source_id, -source.balance.0, None)
+            .await?;
+        self.change_balance(&mut tx, target_id, source.balance.0, None)
+            .await?;
+        sqlx::query(
+            r#"
+            UPDATE accounts
+            SET status = 'CLOSED', closed_at = NOW(), merged_into = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(source_id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(AccountMergeResponse {
+            source: self.get_account_by_id(source_id).await?,
+            target: self.get_account_by_id(target_id).await?,
+            transactions_moved: sent + received,
+        })
+    }
+
+    /// Freezes or unfreezes an account
+    ///
+    /// Transactions check the status after locking the accounts they touch,
+    /// and this update waits for those locks, so a transaction in flight
+    /// either completes before the freeze or sees it.
+    ///
+    /// # Arguments
+    /// * `id` - The UUID of the account
+    /// * `status` - ACTIVE or FROZEN; accounts are closed with `close_account`
+    ///   or by merging them
+    ///
+    /// # Returns
+    /// The updated account, or `ACCOUNT_CLOSED` if the account was closed
+    pub async fn set_account_status(
+        &self,
+        id: Uuid,
+        status: AccountStatus,
+    ) -> Result<AccountResponse, AppError> {
+        if status == AccountStatus::CLOSED {
+            return Err(AppError::BadRequest(
+                "Accounts are closed by closing or merging them".to_string(),
+            ));
+        }
+
+        let updated =
+            sqlx::query("UPDATE accounts SET status = $2 WHERE id = $1 AND status <> 'CLOSED'")
+                .bind(id)
+                .bind(status.to_string())
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+
+        // Nothing updated means the account is closed, unless it doesn't exist
+        let account = self.get_account_by_id(id).await?;
+        if updated == 0 {
+            return Err(AppError::Rejected {
+                code: ACCOUNT_CLOSED,
+                message: format!("Account {} is closed", id),
+            });
+        }
+
+        Ok(account)
+    }
+
+    /// Returns the caps on what an account may send
+    ///
+    /// Accounts without stored limits have no caps.
+    pub async fn get_limits(&self, id: Uuid) -> Result<AccountLimits, AppError> {
+        // Fails with NotFound for an unknown account
+        self.get_account_by_id(id).await?;
+
+        let limits = sqlx::query_as::<_, AccountLimitsRow>(
+            r#"
+            SELECT per_transaction_limit, daily_limit, daily_withdrawal_limit, daily_transfer_limit
+            FROM account_limits WHERE account_id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(AccountLimits::from)
+        .unwrap_or_default();
+
+        Ok(limits)
+    }
+
+    /// Replaces the caps on what an account may send
+    ///
+    /// All caps are replaced; a `None` removes that cap. Transactions that
+    /// already completed are not affected, but they keep counting towards the
+    /// new daily limit for the rest of their 24 hours, and towards the new
+    /// daily withdrawal and transfer limits for the rest of their UTC day.
+    ///
+    /// # Returns
+    /// The limits now in force, or `AppError::Validation` if a cap isn't positive
+    pub async fn set_limits(
+        &self,
+        id: Uuid,
+        limits: AccountLimits,
+    ) -> Result<AccountLimits, AppError> {
+        limits.validate()?;
+        self.get_account_by_id(id).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO account_limits (account_id, per_transaction_limit, daily_limit,
+                                        daily_withdrawal_limit, daily_transfer_limit)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (account_id) DO UPDATE
+            SET per_transaction_limit = EXCLUDED.per_transaction_limit,
+                daily_limit = EXCLUDED.daily_limit,
+                daily_withdrawal_limit = EXCLUDED.daily_withdrawal_limit,
+                daily_transfer_limit = EXCLUDED.daily_transfer_limit
+            "#,
+        )
+        .bind(id)
+        .bind(limits.per_transaction_limit.map(SqlxDecimal))
+        .bind(limits.daily_limit.map(SqlxDecimal))
+        .bind(limits.daily_withdrawal_limit.map(SqlxDecimal))
+        .bind(limits.daily_transfer_limit.map(SqlxDecimal))
+        .execute(&self.pool)
+        .await?;
+
+        self.get_limits(id).await
+    }
+
+    /// Closes an account for good
+    ///
+    /// The account is kept, with status CLOSED, so the transactions that
+    /// reference it stay intact. Closing an account that is already closed
+    /// succeeds without changing it.
+    ///
+    /// # Arguments
+    /// * `id` - The UUID of the account to close
+    ///
+    /// # Returns
+    /// The closed account, or `AppError::BadRequest` if it still holds funds
+    /// or is frozen
+    ///
+    /// # Implementation Details
+    /// The account is locked while its balance is checked, so a deposit in
+    /// flight either lands first (and blocks the closure) or sees the account
+    /// closed. The final statement, covering the account from its opening to
+    /// `closed_at`, is stored in the same database transaction, so the
+    /// closure fails if it can't be generated. The owner is emailed it once
+    /// the closure has committed.
+    pub async fn close_account(&self, id: Uuid) -> Result<AccountResponse, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let (balance, status, created_at) =
+            sqlx::query_as::<_, (SqlxDecimal, String, DateTime<Utc>)>(
+                "SELECT balance, status, created_at FROM accounts WHERE id = $1 FOR UPDATE",
+            )
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Account with ID {} not found", id)))?;
+
+        let mut final_statement = None;
+        if status != AccountStatus::CLOSED.to_string() {
+            ensure_account_active(id, &status)?;
+            if !balance.0.is_zero() {
+                return Err(AppError::BadRequest(format!(
+                    "Account {} still holds {}; transfer or withdraw it before closing",
+                    id,
+                    balance.0.normalize()
+                )));
+            }
+
+            // The wall clock rather than NOW(), so transactions that committed
+            // after this database transaction began still fall in the period
+            let closed_at: DateTime<Utc> = sqlx::query_scalar(
+                "UPDATE accounts SET status = 'CLOSED', closed_at = clock_timestamp()
+                 WHERE id = $1 RETURNING closed_at",
+            )
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            let (balances, transactions) = self
+                .read_statement(&mut tx, id, created_at, closed_at)
+                .await?;
+            let statement = FinalStatement {
+                account_id: id,
+                currency: balances.currency,
+                period_start: created_at,
+                period_end: closed_at,
+                opening_balance: balances.opening_balance.into(),
+                closing_balance: balances.closing_balance.into(),
+                total_credits: balances.total_credits.into(),
+                total_debits: balances.total_debits.into(),
+                transactions,
+            };
+            sqlx::query("INSERT INTO final_statements (account_id, statement) VALUES ($1, $2)")
+                .bind(id)
+                .bind(Json(&statement))
+                .execute(&mut *tx)
+                .await?;
+            final_statement = Some(statement);
+        }
+
+        tx.commit().await?;
+
+        if let (Some(notifications), Some(statement)) = (&self.notifications, final_statement) {
+            notifications.spawn_final_statement(statement);
+        }
+
+        self.get_account_by_id(id).await
+    }
 }
@@ -0,0 +1,211 @@
+use crate::models::budget::{
+    normalize_category, Budget, BudgetAlert, BudgetPeriod, BudgetStatus, SetBudgetRequest,
+};
+use crate::models::decimal::SqlxDecimal;
+use crate::models::transaction::TransactionStatus;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Service for per-account spending budgets
+///
+/// Spend is the sum of completed outgoing transactions (transfers sent,
+/// withdrawals and payouts) in a category during the budget's current period.
+pub struct BudgetService {
+    pool: PgPool,
+}
+
+impl BudgetService {
+    /// Creates a new budget service with the given database pool
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Sets the limit for a category and period of an account
+    ///
+    /// An existing budget for the same category and period gets the new limit.
+    pub async fn set_budget(
+        &self,
+        account_id: Uuid,
+        request: SetBudgetRequest,
+    ) -> Result<Budget, AppError> {
+        if request.limit <= Decimal::ZERO {
+            return Err(AppError::BadRequest(
+                "Budget limit must be positive".to_string(),
+            ));
+        }
+        let category = normalize_category(&request.category)?;
+
+        let budget = sqlx::query_as::<_, Budget>(
+            r#"
+            INSERT INTO budgets (id, account_id, category, period, limit_amount)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (account_id, category, period)
+            DO UPDATE SET limit_amount = EXCLUDED.limit_amount
+            RETURNING id, account_id, category, period, limit_amount, created_at, updated_at
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(account_id)
+        .bind(category)
+        .bind(request.period.to_string())
+        .bind(SqlxDecimal(request.limit))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(budget)
+    }
+
+    /// Returns spend against each of an account's budgets in the period containing `day`
+    pub async fn get_budget_status(
+        &self,
+        account_id: Uuid,
+        day: NaiveDate,
+    ) -> Result<Vec<BudgetStatus>, AppError> {
+        let budgets = self.get_budgets(account_id, None).await?;
+
+        let mut statuses = Vec::with_capacity(budgets.len());
+        for budget in budgets {
+            let period = BudgetPeriod::from_str(&budget.period)?;
+            let (period_start, period_end) = period.bounds(day);
+            let spent = self
+                .spent(account_id, &budget.category, period_start, period_end)
+                .await?;
+
+            statuses.push(BudgetStatus {
+                budget_id: budget.id,
+                category: budget.category,
+                period: budget.period,
+                period_start,
+                period_end,
+                limit: budget.limit_amount.0,
+                spent,
+                remaining: budget.limit_amount.0 - spent,
+                exceeded: spent > budget.limit_amount.0,
+            });
+        }
+
+        Ok(statuses)
+    }
+
+    /// Raises alerts for budgets that a completed outgoing transaction pushed over their limit
+    ///
+    /// Each budget alerts at most once per period, for the first transaction that
+    /// exceeded it. Alerts are stored in `budget_alerts` and logged.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account the money left
+    /// * `category` - The transaction's normalized category
+    /// * `transaction_id` - The transaction that was just committed
+    /// * `at` - When the transaction was created, which selects the period
+    ///
+    /// # Returns
+    /// The alerts raised by this transaction
+    pub async fn check_budgets(
+        &self,
+        account_id: Uuid,
+        category: &str,
+        transaction_id: Uuid,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<BudgetAlert>, AppError> {
+        let mut alerts = Vec::new();
+
+        for budget in self.get_budgets(account_id, Some(category)).await? {
+            let period = BudgetPeriod::from_str(&budget.period)?;
+            let (period_start, period_end) = period.bounds(at.date_naive());
+            let spent = self
+                .spent(account_id, category, period_start, period_end)
+                .await?;
+            if spent <= budget.limit_amount.0 {
+                continue;
+            }
+
+            let alert = sqlx::query_as::<_, BudgetAlert>(
+                r#"
+                INSERT INTO budget_alerts
+                    (id, budget_id, period_start, transaction_id, spent, limit_amount)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (budget_id, period_start) DO NOTHING
+                RETURNING id, budget_id, period_start, transaction_id, spent, limit_amount,
+                          created_at
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(budget.id)
+            .bind(period_start)
+            .bind(transaction_id)
+            .bind(SqlxDecimal(spent))
+            .bind(budget.limit_amount)
+            .fetch_optional(&self.pool)
+            .await?;
+
+            if let Some(alert) = alert {
+                tracing::warn!(
+                    account_id = %account_id,
+                    budget_id = %budget.id,
+                    category = %category,
+                    spent = %spent,
+                    limit = %budget.limit_amount,
+                    "Budget exceeded"
+                );
+                alerts.push(alert);
+            }
+        }
+
+        Ok(alerts)
+    }
+
+    async fn get_budgets(
+        &self,
+        account_id: Uuid,
+        category: Option<&str>,
+    ) -> Result<Vec<Budget>, AppError> {
+        let budgets = sqlx::query_as::<_, Budget>(
+            r#"
+            SELECT id, account_id, category, period, limit_amount, created_at, updated_at
+            FROM budgets
+            WHERE account_id = $1 AND ($2::text IS NULL OR category = $2)
+            ORDER BY category, period
+            "#,
+        )
+        .bind(account_id)
+        .bind(category)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(budgets)
+    }
+
+    /// Sums completed outgoing transactions in a category over whole days `[from, to]`
+    async fn spent(
+        &self,
+        account_id: Uuid,
+        category: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Decimal, AppError> {
+        let spent = sqlx::query_scalar::<_, SqlxDecimal>(
+            r#"
+            SELECT COALESCE(SUM(COALESCE(original_amount, amount)), 0)
+            FROM transactions
+            WHERE sender_account_id = $1
+              AND category = $2
+              AND status = $3
+              AND created_at >= $4
+              AND created_at < $5
+            "#,
+        )
+        .bind(account_id)
+        .bind(category)
+        .bind(TransactionStatus::COMPLETED.to_string())
+        .bind(from.and_time(NaiveTime::MIN).and_utc())
+        .bind((to + Duration::days(1)).and_time(NaiveTime::MIN).and_utc())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(spent.0)
+    }
+}
@@ -0,0 +1,201 @@
+use crate::config::DestinationConfig;
+use crate::models::destination::{
+    DestinationAction, DestinationResponse, RegisterDestinationRequest, WithdrawalDestination,
+};
+use crate::utils::error::AppError;
+use chrono::Utc;
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+use validator::Validate;
+
+/// Service for the registry of external accounts users may withdraw to
+///
+/// A destination has to be verified before withdrawals can use it: either an
+/// administrator approves it, or, when a verification delay is configured, it
+/// becomes usable on its own once the delay has passed. Every change to the
+/// registry is recorded in `withdrawal_destination_events`.
+pub struct DestinationService {
+    pool: PgPool,
+    config: DestinationConfig,
+}
+
+impl DestinationService {
+    /// Creates a new destination service with the given database pool and settings
+    pub fn new(pool: PgPool, config: DestinationConfig) -> Self {
+        Self { pool, config }
+    }
+
+    /// Registers a destination for a user
+    ///
+    /// # Arguments
+    /// * `user_id` - The user the destination belongs to
+    /// * `request` - Label and identifier of the destination
+    ///
+    /// # Returns
+    /// The new destination, unverified unless the verification delay is zero,
+    /// or `AppError::Conflict` if the user already registered the identifier
+    pub async fn register(
+        &self,
+        user_id: Uuid,
+        request: RegisterDestinationRequest,
+    ) -> Result<DestinationResponse, AppError> {
+        request
+            .validate()
+            .map_err(|e| AppError::Validation(format!("Invalid destination data: {}", e)))?;
+        let identifier = request.normalized_identifier()?;
+
+        // With a delay, the time it runs out is known now; without one it stays
+        // unverified until an administrator approves it
+        let verified_at = self
+            .config
+            .verification_delay
+            .map(|delay| chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::MAX))
+            .and_then(|delay| Utc::now().checked_add_signed(delay));
+
+        let mut tx = self.pool.begin().await?;
+        let destination = sqlx::query_as::<_, WithdrawalDestination>(
+            r#"
+            INSERT INTO withdrawal_destinations (id, user_id, label, identifier, verified_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id, identifier) WHERE removed_at IS NULL DO NOTHING
+            RETURNING id, user_id, label, identifier, verified_at, removed_at, created_at
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(request.label.trim())
+        .bind(&identifier)
+        .bind(verified_at)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::Conflict("This destination is already registered".to_string()))?;
+        record_event(
+            &mut tx,
+            &destination,
+            user_id,
+            DestinationAction::REGISTERED,
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(DestinationResponse::from(destination))
+    }
+
+    /// Lists a user's destinations, oldest first, leaving out removed ones
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<DestinationResponse>, AppError> {
+        let destinations = sqlx::query_as::<_, WithdrawalDestination>(
+            r#"
+            SELECT id, user_id, label, identifier, verified_at, removed_at, created_at
+            FROM withdrawal_destinations
+            WHERE user_id = $1 AND removed_at IS NULL
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(destinations
+            .into_iter()
+            .map(DestinationResponse::from)
+            .collect())
+    }
+
+    /// Removes one of a user's destinations so it can no longer be withdrawn to
+    ///
+    /// The row is kept, marked as removed, so earlier withdrawals still
+    /// reference it. Another user's destination is reported as not found.
+    pub async fn remove(&self, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        let destination = sqlx::query_as::<_, WithdrawalDestination>(
+            r#"
+            UPDATE withdrawal_destinations SET removed_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND removed_at IS NULL
+            RETURNING id, user_id, label, identifier, verified_at, removed_at, created_at
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| destination_not_found(id))?;
+        record_event(&mut tx, &destination, user_id, DestinationAction::REMOVED).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Approves a destination on behalf of an administrator, making it usable at once
+    ///
+    /// Approving a destination that is already verified changes nothing and
+    /// records no event.
+    pub async fn verify(&self, admin_id: Uuid, id: Uuid) -> Result<DestinationResponse, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let destination = sqlx::query_as::<_, WithdrawalDestination>(
+            r#"
+            SELECT id, user_id, label, identifier, verified_at, removed_at, created_at
+            FROM withdrawal_destinations
+            WHERE id = $1 AND removed_at IS NULL
+            FOR UPDATE
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| destination_not_found(id))?;
+
+        if destination.is_verified(Utc::now()) {
+            return Ok(DestinationResponse::from(destination));
+        }
+
+        let destination = sqlx::query_as::<_, WithdrawalDestination>(
+            r#"
+            UPDATE withdrawal_destinations SET verified_at = NOW()
+            WHERE id = $1
+            RETURNING id, user_id, label, identifier, verified_at, removed_at, created_at
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        record_event(&mut tx, &destination, admin_id, DestinationAction::VERIFIED).await?;
+        tx.commit().await?;
+
+        Ok(DestinationResponse::from(destination))
+    }
+}
+
+fn destination_not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Destination with ID {} not found", id))
+}
+
+/// Adds a registry change to the audit trail and the log
+async fn record_event(
+    tx: &mut SqlxTransaction<'_, Postgres>,
+    destination: &WithdrawalDestination,
+    actor_id: Uuid,
+    action: DestinationAction,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO withdrawal_destination_events (id, destination_id, actor_id, action)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(destination.id)
+    .bind(actor_id)
+    .bind(action.to_string())
+    .execute(&mut **tx)
+    .await?;
+
+    tracing::info!(
+        destination_id = %destination.id,
+        owner_id = %destination.user_id,
+        actor_id = %actor_id,
+        action = %action,
+        "Withdrawal destination registry changed"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use crate::models::diagnostics::{
+    LatencyBucket, ThroughputGranularity, ThroughputPeak, ThroughputReport, LATENCY_BUCKETS_MS,
+};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+/// Service for the operational diagnostics administrators look at
+///
+/// Reads the processing timings `TransactionService` records with each
+/// transaction, and the transactions themselves, to describe how the system
+/// performs. Nothing here moves money.
+pub struct DiagnosticsService {
+    pool: PgPool,
+}
+
+impl DiagnosticsService {
+    /// Creates a new diagnostics service with the given database pool
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Builds a histogram of how long recent transactions took to process
+    ///
+    /// Counts transactions committed since `since` by type, stage and
+    /// `LATENCY_BUCKETS_MS` bucket, from the same timings shown on the admin
+    /// transaction detail. Empty buckets are left out.
+    pub async fn latency_histogram(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<LatencyBucket>, AppError> {
+        let rows: Vec<(String, String, i32, i64)> = sqlx::query_as(
+            r#"
+            SELECT t.transaction_type, s.stage,
+                   width_bucket(EXTRACT(EPOCH FROM s.duration) * 1000, $2::FLOAT8[]),
+                   COUNT(*)
+            FROM transaction_timings tt
+            JOIN transactions t ON t.id = tt.transaction_id
+            CROSS JOIN LATERAL (VALUES
+                ('QUEUE', tt.lock_acquired_at - tt.received_at),
+                ('PROCESSING', tt.committed_at - tt.lock_acquired_at),
+                ('TOTAL', tt.committed_at - tt.received_at)
+            ) AS s(stage, duration)
+            WHERE tt.committed_at >= $1
+            GROUP BY 1, 2, 3
+            ORDER BY 1, 2, 3
+            "#,
+        )
+        .bind(since)
+        .bind(LATENCY_BUCKETS_MS.as_slice())
+        .fetch_all(&self.pool)
+        .await?;
+
+        // width_bucket returns how many bounds the value reached, which makes
+        // the matching bound the bucket's exclusive upper limit
+        Ok(rows
+            .into_iter()
+            .map(|(transaction_type, stage, bucket, count)| LatencyBucket {
+                transaction_type,
+                stage,
+                below_ms: LATENCY_BUCKETS_MS
+                    .get(bucket as usize)
+                    .map(|bound| *bound as i64),
+                count,
+            })
+            .collect())
+    }
+
+    /// Measures transactions per second between `from` and `to`
+    ///
+    /// Transactions are counted in buckets of `granularity` using
+    /// `date_trunc`; the busiest bucket is the peak. The average only counts
+    /// the part of the range that has already passed.
+    pub async fn throughput(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        granularity: ThroughputGranularity,
+    ) -> Result<ThroughputReport, AppError> {
+        let total_transactions: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM transactions WHERE created_at >= $1 AND created_at < $2",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let peak: Option<(DateTime<Utc>, i64)> = sqlx::query_as(
+            r#"
+            SELECT date_trunc($3, created_at) AS bucket_start, COUNT(*)
+            FROM transactions
+            WHERE created_at >= $1 AND created_at < $2
+            GROUP BY bucket_start
+            ORDER BY COUNT(*) DESC, bucket_start
+            LIMIT 1
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .bind(granularity.date_trunc_field())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let elapsed = (to.min(Utc::now()) - from).num_milliseconds();
+        let average_per_second = if elapsed > 0 {
+            total_transactions as f64 * 1000.0 / elapsed as f64
+        } else {
+            0.0
+        };
+
+        Ok(ThroughputReport {
+            granularity,
+            from,
+            to,
+            total_transactions,
+            average_per_second,
+            peak: peak.map(|(bucket_start, count)| ThroughputPeak {
+                bucket_start,
+                count,
+                per_second: count as f64 / granularity.seconds() as f64,
+            }),
+        })
+    }
+
+    /// Deletes the timings of transactions committed before `before`
+    ///
+    /// # Returns
+    /// The number of timings deleted
+    pub async fn purge_timings(&self, before: DateTime<Utc>) -> Result<u64, AppError> {
+        let deleted = sqlx::query("DELETE FROM transaction_timings WHERE committed_at < $1")
+            .bind(before)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted)
+    }
+}
@@ -0,0 +1,176 @@
+use crate::models::decimal::SqlxDecimal;
+use crate::models::exchange_rate::{
+    ExchangeRate, ExchangeRateResponse, SetExchangeRateRequest, EXCHANGE_RATE_SCALE,
+};
+use crate::utils::currency::normalize_currency;
+use crate::utils::error::AppError;
+use crate::utils::money::round_to;
+use chrono::{DateTime, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::PgPool;
+use std::time::Duration;
+use validator::Validate;
+
+/// Service maintaining the rates transfers between currencies are converted at
+///
+/// Rates are kept in the `exchange_rates` table, one row per direction. When
+/// only the opposite direction is stored, its inverse is used. A rate not
+/// updated within `rate_ttl` is stale, and conversions at it are refused.
+pub struct ExchangeService {
+    pool: PgPool,
+    rate_ttl: Option<Duration>,
+}
+
+impl ExchangeService {
+    /// Creates a new exchange service with the given database pool, refusing
+    /// rates older than `rate_ttl` when it is set
+    pub fn new(pool: PgPool, rate_ttl: Option<Duration>) -> Self {
+        Self { pool, rate_ttl }
+    }
+
+    /// Returns how many units of `to` one unit of `from` buys
+    ///
+    /// # Arguments
+    /// * `from` - ISO 4217 code of the currency converted from
+    /// * `to` - ISO 4217 code of the currency converted to
+    ///
+    /// # Returns
+    /// The rate, which is 1 when both currencies are the same, or
+    /// `AppError::BadRequest` when no rate is known for the pair in either
+    /// direction or the rate is stale
+    ///
+    /// # Implementation Details
+    /// A rate stored for `from` to `to` wins. Otherwise the inverse of the
+    /// `to` to `from` rate is used, rounded half-even to `EXCHANGE_RATE_SCALE`
+    /// decimal places.
+    pub async fn get_rate(&self, from: &str, to: &str) -> Result<Decimal, AppError> {
+        let (from, to) = (normalize_currency(from), normalize_currency(to));
+        if from == to {
+            return Ok(Decimal::ONE);
+        }
+
+        // The direct rate sorts first, so the inverse is only used without one
+        let (stored, updated_at) = sqlx::query_as::<_, (String, SqlxDecimal, DateTime<Utc>)>(
+            r#"
+            SELECT base_currency, rate, updated_at FROM exchange_rates
+            WHERE (base_currency = $1 AND quote_currency = $2)
+               OR (base_currency = $2 AND quote_currency = $1)
+            ORDER BY base_currency = $1 DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(&from)
+        .bind(&to)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|(base, rate, updated_at)| {
+            let rate = if base == from {
+                rate.0
+            } else {
+                round_to(
+                    Decimal::ONE / rate.0,
+                    EXCHANGE_RATE_SCALE,
+                    RoundingStrategy::MidpointNearestEven,
+                )
+            };
+            (rate, updated_at)
+        })
+        .ok_or_else(|| AppError::BadRequest(format!("No exchange rate from {} to {}", from, to)))?;
+
+        if self.is_stale(updated_at) {
+            return Err(AppError::BadRequest(format!(
+                "The exchange rate from {} to {} is out of date",
+                from, to
+            )));
+        }
+
+        Ok(stored.normalize())
+    }
+
+    /// Stores the rate from one currency to another, replacing any rate
+    /// stored for that direction
+    ///
+    /// # Returns
+    /// The stored rate, or `AppError::Validation` if a currency isn't an ISO
+    /// 4217 code, both are the same, or the rate isn't positive
+    pub async fn set_rate(
+        &self,
+        request: SetExchangeRateRequest,
+    ) -> Result<ExchangeRateResponse, AppError> {
+        request
+            .validate()
+            .map_err(|e| AppError::Validation(format!("Invalid exchange rate: {}", e)))?;
+        let base_currency = normalize_currency(&request.base_currency);
+        let quote_currency = normalize_currency(&request.quote_currency);
+        if base_currency == quote_currency {
+            return Err(AppError::Validation(
+                "A rate needs two different currencies".to_string(),
+            ));
+        }
+        if request.rate <= Decimal::ZERO {
+            return Err(AppError::Validation("Rate must be positive".to_string()));
+        }
+        let rate = round_to(
+            request.rate,
+            EXCHANGE_RATE_SCALE,
+            RoundingStrategy::MidpointNearestEven,
+        );
+        if rate.is_zero() {
+            return Err(AppError::Validation(format!(
+                "Rate is too small to store with {} decimal places",
+                EXCHANGE_RATE_SCALE
+            )));
+        }
+
+        let stored = sqlx::query_as::<_, ExchangeRate>(
+            r#"
+            INSERT INTO exchange_rates (base_currency, quote_currency, rate)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (base_currency, quote_currency) DO UPDATE SET rate = EXCLUDED.rate
+            RETURNING base_currency, quote_currency, rate, updated_at
+            "#,
+        )
+        .bind(&base_currency)
+        .bind(&quote_currency)
+        .bind(SqlxDecimal(rate))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(self.to_response(stored))
+    }
+
+    /// Lists every stored rate, by base and then quote currency
+    pub async fn list_rates(&self) -> Result<Vec<ExchangeRateResponse>, AppError> {
+        let rates = sqlx::query_as::<_, ExchangeRate>(
+            r#"
+            SELECT base_currency, quote_currency, rate, updated_at
+            FROM exchange_rates
+            ORDER BY base_currency, quote_currency
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rates
+            .into_iter()
+            .map(|rate| self.to_response(rate))
+            .collect())
+    }
+
+    fn to_response(&self, rate: ExchangeRate) -> ExchangeRateResponse {
+        ExchangeRateResponse {
+            stale: self.is_stale(rate.updated_at),
+            base_currency: rate.base_currency,
+            quote_currency: rate.quote_currency,
+            rate: rate.rate.0.normalize(),
+            updated_at: rate.updated_at,
+        }
+    }
+
+    /// Whether a rate last updated at `updated_at` is too old to convert at
+    fn is_stale(&self, updated_at: DateTime<Utc>) -> bool {
+        self.rate_ttl.is_some_and(|ttl| {
+            chrono::Duration::from_std(ttl).is_ok_and(|ttl| updated_at + ttl < Utc::now())
+        })
+    }
+}
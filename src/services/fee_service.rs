@@ -0,0 +1,163 @@
+use crate::models::decimal::SqlxDecimal;
+use crate::models::fee_rule::{FeeRule, FeeRuleResponse, SetFeeRuleRequest};
+use crate::models::transaction::TransactionType;
+use crate::utils::currency::normalize_currency;
+use crate::utils::error::AppError;
+use crate::utils::money::{percentage_of, round_to};
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+/// Service computing the fees charged on transfers and withdrawals
+///
+/// Fees follow the rules in the `fee_rules` table: a flat amount plus a
+/// percentage of the amount, per transaction type and, optionally, per
+/// currency of the paying account. Transfers matched by no rule are charged
+/// the default transfer fee, if one is set.
+pub struct FeeService {
+    pool: PgPool,
+    default_transfer_fee: (Decimal, Decimal),
+}
+
+impl FeeService {
+    /// Creates a new fee service with the given database pool, charging no
+    /// fee where no rule applies
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            default_transfer_fee: (Decimal::ZERO, Decimal::ZERO),
+        }
+    }
+
+    /// Charges transfers no rule applies to a flat fee plus `percent` of the amount
+    pub fn with_default_transfer_fee(mut self, flat: Decimal, percent: Decimal) -> Self {
+        self.default_transfer_fee = (flat, percent);
+        self
+    }
+
+    /// Computes the fee for a transaction of `amount` paid from an account in `currency`
+    ///
+    /// # Arguments
+    /// * `conn` - Connection to read the rules with, usually the caller's
+    ///   database transaction
+    /// * `transaction_type` - `TRANSFER` or `WITHDRAWAL`; other types are free
+    /// * `currency` - Currency of the paying account, which the fee is in
+    /// * `amount` - Amount of the transaction, in `currency`
+    /// * `scale` - Decimal places of the paying account's balance
+    ///
+    /// # Returns
+    /// The flat amount plus the percentage of `amount`, each rounded
+    /// half-even to `scale`, or zero when no rule applies
+    ///
+    /// # Implementation Details
+    /// A rule for `currency` wins over a rule for every currency.
+    pub async fn fee_for(
+        &self,
+        conn: &mut PgConnection,
+        transaction_type: &TransactionType,
+        currency: &str,
+        amount: Decimal,
+        scale: u32,
+    ) -> Result<Decimal, AppError> {
+        if !matches!(
+            transaction_type,
+            TransactionType::TRANSFER | TransactionType::WITHDRAWAL
+        ) {
+            return Ok(Decimal::ZERO);
+        }
+
+        let rule = sqlx::query_as::<_, (SqlxDecimal, SqlxDecimal)>(
+            r#"
+            SELECT flat_amount, percent FROM fee_rules
+            WHERE transaction_type = $1 AND (currency = $2 OR currency IS NULL)
+            ORDER BY currency IS NULL
+            LIMIT 1
+            "#,
+        )
+        .bind(transaction_type.to_string())
+        .bind(currency)
+        .fetch_optional(conn)
+        .await?;
+
+        let (flat, percent) = match rule {
+            Some((flat, percent)) => (flat.0, percent.0),
+            None if *transaction_type == TransactionType::TRANSFER => self.default_transfer_fee,
+            None => return Ok(Decimal::ZERO),
+        };
+        if flat.is_zero() && percent.is_zero() {
+            return Ok(Decimal::ZERO);
+        }
+
+        let strategy = RoundingStrategy::MidpointNearestEven;
+        Ok(round_to(flat, scale, strategy) + percentage_of(amount, percent, scale, strategy)?)
+    }
+
+    /// Stores the fee for a transaction type and currency, replacing any rule
+    /// stored for the same pair
+    ///
+    /// # Returns
+    /// The stored rule, or `AppError::Validation` if the transaction type
+    /// can't be charged a fee, the currency isn't an ISO 4217 code, or the
+    /// flat amount or percentage is out of range
+    pub async fn set_rule(&self, request: SetFeeRuleRequest) -> Result<FeeRuleResponse, AppError> {
+        request
+            .validate()
+            .map_err(|e| AppError::Validation(format!("Invalid fee rule: {}", e)))?;
+        let transaction_type: TransactionType = request.transaction_type.to_uppercase().parse()?;
+        if !matches!(
+            transaction_type,
+            TransactionType::TRANSFER | TransactionType::WITHDRAWAL
+        ) {
+            return Err(AppError::Validation(
+                "Fees can only be charged on transfers and withdrawals".to_string(),
+            ));
+        }
+        if request.flat_amount < Decimal::ZERO {
+            return Err(AppError::Validation(
+                "Flat fee must not be negative".to_string(),
+            ));
+        }
+        if request.percent < Decimal::ZERO || request.percent > Decimal::ONE_HUNDRED {
+            return Err(AppError::Validation(
+                "Fee percentage must be between 0 and 100".to_string(),
+            ));
+        }
+        let currency = request.currency.as_deref().map(normalize_currency);
+
+        let rule = sqlx::query_as::<_, FeeRule>(
+            r#"
+            INSERT INTO fee_rules (id, transaction_type, currency, flat_amount, percent)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (transaction_type, (COALESCE(currency, ''))) DO UPDATE
+            SET flat_amount = EXCLUDED.flat_amount, percent = EXCLUDED.percent
+            RETURNING id, transaction_type, currency, flat_amount, percent, updated_at
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(transaction_type.to_string())
+        .bind(currency)
+        .bind(SqlxDecimal(request.flat_amount))
+        .bind(SqlxDecimal(request.percent))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(FeeRuleResponse::from(rule))
+    }
+
+    /// Lists every stored rule, by transaction type and then currency, with
+    /// the rule for every currency last
+    pub async fn list_rules(&self) -> Result<Vec<FeeRuleResponse>, AppError> {
+        let rules = sqlx::query_as::<_, FeeRule>(
+            r#"
+            SELECT id, transaction_type, currency, flat_amount, percent, updated_at
+            FROM fee_rules
+            ORDER BY transaction_type, currency NULLS LAST
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rules.into_iter().map(FeeRuleResponse::from).collect())
+    }
+}
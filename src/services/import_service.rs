@@ -0,0 +1,373 @@
+use crate::config::ImportConfig;
+use crate::models::account::balance_scale;
+use crate::models::budget::normalize_category;
+use crate::models::import::{ImportRowError, ImportSummary};
+use crate::utils::csv::CsvRecordReader;
+use crate::utils::currency::normalize_amount_to_scale;
+use crate::utils::error::AppError;
+use axum::body::Bytes;
+use chrono::NaiveDate;
+use futures_util::{Stream, StreamExt};
+use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Rows written per insert; each batch is committed on its own, so an
+/// interrupted upload keeps the rows stored before the interruption
+const IMPORT_BATCH_SIZE: usize = 500;
+
+/// Rejected rows described in a summary; the rest are only counted
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// A validated row, ready to be stored
+struct ImportRow {
+    row_hash: String,
+    transaction_date: NaiveDate,
+    amount: Decimal,
+    direction: &'static str,
+    description: Option<String>,
+    category: Option<String>,
+}
+
+/// Positions of the known columns in an import file
+struct ImportColumns {
+    date: usize,
+    amount: usize,
+    direction: usize,
+    description: Option<usize>,
+    category: Option<usize>,
+}
+
+impl ImportColumns {
+    /// Locates the columns by their header names, in any order and case
+    fn from_header(header: &[String]) -> Result<Self, AppError> {
+        let find = |name: &str| {
+            header
+                .iter()
+                .position(|column| column.trim().eq_ignore_ascii_case(name))
+        };
+        let require = |name: &str| {
+            find(name).ok_or_else(|| {
+                AppError::BadRequest(format!("CSV header is missing the '{}' column", name))
+            })
+        };
+
+        Ok(Self {
+            date: require("date")?,
+            amount: require("amount")?,
+            direction: require("direction")?,
+            description: find("description"),
+            category: find("category"),
+        })
+    }
+}
+
+/// Service importing an account owner's historical transactions from CSV
+///
+/// Imported rows are documentation only. They are stored in
+/// `imported_transactions`, apart from the ledger, so they never change a
+/// balance, count towards budgets or show up in integrity checks.
+pub struct ImportService {
+    pool: PgPool,
+    config: ImportConfig,
+}
+
+impl ImportService {
+    /// Creates a new import service with the given database pool and limits
+    pub fn new(pool: PgPool, config: ImportConfig) -> Self {
+        Self { pool, config }
+    }
+
+    /// Imports a CSV file into an account's history, reading it as it arrives
+    ///
+    /// The file needs a header row with `date` (`YYYY-MM-DD`), `amount`
+    /// (positive, in the account currency) and `direction` (`IN` or `OUT`)
+    /// columns, and may have `description` and `category` columns.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account the history belongs to
+    /// * `session_id` - Session of an earlier upload to continue, if any
+    /// * `chunks` - The file contents
+    ///
+    /// # Returns
+    /// A summary of the upload; invalid rows are reported in it rather than
+    /// failing the import. Rows stored by any earlier upload to the account are
+    /// skipped as duplicates.
+    pub async fn import_csv<S>(
+        &self,
+        account_id: Uuid,
+        session_id: Option<Uuid>,
+        chunks: S,
+    ) -> Result<ImportSummary, AppError>
+    where
+        S: Stream<Item = Result<Bytes, AppError>>,
+    {
+        let (currency, scale) = sqlx::query_as::<_, (String, Option<i16>)>(
+            "SELECT currency, balance_scale FROM accounts WHERE id = $1",
+        )
+        .bind(account_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Account with ID {} not found", account_id)))?;
+        let scale = balance_scale(scale, &currency);
+        let session_id = self.open_session(account_id, session_id).await?;
+
+        let mut summary = ImportSummary {
+            session_id,
+            rows_read: 0,
+            imported: 0,
+            duplicates: 0,
+            failed: 0,
+            errors: Vec::new(),
+        };
+        let mut reader = CsvRecordReader::new(self.config.max_row_bytes);
+        let mut columns = None;
+        let mut occurrences: HashMap<String, u32> = HashMap::new();
+        let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+        let mut row_number = 0u64;
+        let mut over_limit = 0u64;
+
+        let mut chunks = std::pin::pin!(chunks);
+        let mut finished = false;
+        while !finished {
+            let records = match chunks.next().await {
+                Some(chunk) => reader.feed(&chunk?)?,
+                None => {
+                    finished = true;
+                    reader.finish()?
+                }
+            };
+
+            for record in records {
+                row_number += 1;
+                let Some(columns) = &columns else {
+                    columns = Some(ImportColumns::from_header(&record)?);
+                    continue;
+                };
+
+                // Rows past the limit are only counted
+                if summary.rows_read as usize >= self.config.max_rows {
+                    over_limit += 1;
+                    continue;
+                }
+                summary.rows_read += 1;
+
+                match parse_row(&record, columns, &currency, scale, &mut occurrences) {
+                    Ok(row) => batch.push(row),
+                    Err(message) => {
+                        summary.failed += 1;
+                        if summary.errors.len() < MAX_REPORTED_ERRORS {
+                            summary.errors.push(ImportRowError {
+                                row: row_number,
+                                message,
+                            });
+                        }
+                    }
+                }
+
+                if batch.len() >= IMPORT_BATCH_SIZE {
+                    self.store_batch(account_id, session_id, &mut batch, &mut summary)
+                        .await?;
+                }
+            }
+        }
+
+        if columns.is_none() {
+            return Err(AppError::BadRequest("CSV file is empty".to_string()));
+        }
+        self.store_batch(account_id, session_id, &mut batch, &mut summary)
+            .await?;
+        if over_limit > 0 {
+            summary.failed += over_limit;
+            summary.errors.push(ImportRowError {
+                row: row_number - over_limit + 1,
+                message: format!(
+                    "Imports are limited to {} rows; this and the {} rows after it were not imported",
+                    self.config.max_rows,
+                    over_limit - 1
+                ),
+            });
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE import_sessions
+            SET rows_imported = rows_imported + $2,
+                rows_duplicate = rows_duplicate + $3,
+                rows_failed = rows_failed + $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(session_id)
+        .bind(summary.imported as i64)
+        .bind(summary.duplicates as i64)
+        .bind(summary.failed as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(summary)
+    }
+
+    /// Returns the session to record an upload in, creating one if none was given
+    async fn open_session(
+        &self,
+        account_id: Uuid,
+        session_id: Option<Uuid>,
+    ) -> Result<Uuid, AppError> {
+        let Some(session_id) = session_id else {
+            let id = Uuid::new_v4();
+            sqlx::query("INSERT INTO import_sessions (id, account_id) VALUES ($1, $2)")
+                .bind(id)
+                .bind(account_id)
+                .execute(&self.pool)
+                .await?;
+            return Ok(id);
+        };
+
+        sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM import_sessions WHERE id = $1 AND account_id = $2",
+        )
+        .bind(session_id)
+        .bind(account_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("Import session with ID {} not found", session_id))
+        })
+    }
+
+    /// Stores a batch of rows, skipping any already imported, and empties it
+    async fn store_batch(
+        &self,
+        account_id: Uuid,
+        session_id: Uuid,
+        batch: &mut Vec<ImportRow>,
+        summary: &mut ImportSummary,
+    ) -> Result<(), AppError> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let ids: Vec<Uuid> = batch.iter().map(|_| Uuid::new_v4()).collect();
+        let hashes: Vec<&str> = batch.iter().map(|row| row.row_hash.as_str()).collect();
+        let dates: Vec<NaiveDate> = batch.iter().map(|row| row.transaction_date).collect();
+        let amounts: Vec<String> = batch.iter().map(|row| row.amount.to_string()).collect();
+        let directions: Vec<&str> = batch.iter().map(|row| row.direction).collect();
+        let descriptions: Vec<Option<&str>> =
+            batch.iter().map(|row| row.description.as_deref()).collect();
+        let categories: Vec<Option<&str>> =
+            batch.iter().map(|row| row.category.as_deref()).collect();
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO imported_transactions
+                (id, account_id, import_session_id, row_hash, transaction_date, amount,
+                 direction, description, category)
+            SELECT id, $2, $3, row_hash, transaction_date, amount, direction, description, category
+            FROM UNNEST($1::uuid[], $4::text[], $5::date[], $6::numeric[], $7::text[],
+                        $8::text[], $9::text[])
+                AS t(id, row_hash, transaction_date, amount, direction, description, category)
+            ON CONFLICT (account_id, row_hash) DO NOTHING
+            "#,
+        )
+        .bind(&ids)
+        .bind(account_id)
+        .bind(session_id)
+        .bind(&hashes)
+        .bind(&dates)
+        .bind(&amounts)
+        .bind(&directions)
+        .bind(&descriptions)
+        .bind(&categories)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        summary.imported += inserted;
+        summary.duplicates += batch.len() as u64 - inserted;
+        batch.clear();
+
+        Ok(())
+    }
+}
+
+/// Validates one data row and fingerprints it
+///
+/// The fingerprint covers the normalized row and how often the same row
+/// appeared earlier in the file, so genuinely repeated rows (two identical
+/// coffees on one day) are kept while a re-uploaded file is recognised.
+fn parse_row(
+    record: &[String],
+    columns: &ImportColumns,
+    currency: &str,
+    scale: u32,
+    occurrences: &mut HashMap<String, u32>,
+) -> Result<ImportRow, String> {
+    let field = |index: usize| record.get(index).map(|value| value.trim()).unwrap_or("");
+    let optional = |index: Option<usize>| {
+        index
+            .map(field)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+
+    let transaction_date =
+        NaiveDate::parse_from_str(field(columns.date), "%Y-%m-%d").map_err(|_| {
+            format!(
+                "Invalid date '{}'; expected YYYY-MM-DD",
+                field(columns.date)
+            )
+        })?;
+
+    let amount = Decimal::from_str(field(columns.amount))
+        .map_err(|_| format!("Invalid amount '{}'", field(columns.amount)))?;
+    if amount <= Decimal::ZERO {
+        return Err("Amount must be positive".to_string());
+    }
+    let amount = normalize_amount_to_scale(amount, scale, currency, true).map_err(|e| match e {
+        AppError::BadRequest(message) | AppError::Validation(message) => message,
+        other => other.to_string(),
+    })?;
+
+    let direction = match field(columns.direction).to_ascii_uppercase().as_str() {
+        "IN" => "IN",
+        "OUT" => "OUT",
+        other => return Err(format!("Invalid direction '{}'; expected IN or OUT", other)),
+    };
+
+    let description = optional(columns.description);
+    let category = optional(columns.category)
+        .map(|category| normalize_category(&category))
+        .transpose()
+        .map_err(|e| match e {
+            AppError::BadRequest(message) => message,
+            other => other.to_string(),
+        })?;
+
+    let content = [
+        transaction_date.to_string(),
+        amount.to_string(),
+        direction.to_string(),
+        description.clone().unwrap_or_default(),
+        category.clone().unwrap_or_default(),
+    ]
+    .join("\u{1f}");
+    let occurrence = occurrences.entry(content.clone()).or_default();
+    *occurrence += 1;
+    let row_hash = format!(
+        "{:x}",
+        Sha256::digest(format!("{}\u{1f}{}", content, occurrence).as_bytes())
+    );
+
+    Ok(ImportRow {
+        row_hash,
+        transaction_date,
+        amount,
+        direction,
+        description,
+        category,
+    })
+}
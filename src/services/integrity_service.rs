@@ -0,0 +1,263 @@
+use crate::config::IntegrityConfig;
+use crate::models::decimal::SqlxDecimal;
+use crate::models::integrity::{IntegrityReport, IntegrityViolation, ViolationKind};
+use crate::models::transaction::{TransactionStatus, TransactionType};
+use crate::utils::error::AppError;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use uuid::Uuid;
+
+/// Number of violations found by the most recent integrity check
+pub static LAST_CHECK_VIOLATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of integrity checks since startup that found at least one violation
+pub static CHECKS_WITH_VIOLATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Statuses a stored transaction may legitimately have
+const ALLOWED_STATUSES: [TransactionStatus; 4] = [
+    TransactionStatus::PENDING,
+    TransactionStatus::COMPLETED,
+    TransactionStatus::FAILED,
+    TransactionStatus::REVERSED,
+];
+
+/// A transaction together with whether the accounts it references exist
+#[derive(Debug, FromRow)]
+pub struct TransactionRefs {
+    pub id: Uuid,
+    pub sender_account_id: Option<Uuid>,
+    pub receiver_account_id: Option<Uuid>,
+    pub transaction_type: String,
+    pub status: String,
+    pub sender_exists: bool,
+    pub receiver_exists: bool,
+}
+
+#[derive(Debug, FromRow)]
+struct AccountBalance {
+    id: Uuid,
+    balance: SqlxDecimal,
+}
+
+/// Service that sweeps the whole database for broken global invariants
+///
+/// Per-operation checks keep individual transactions consistent; this service
+/// catches anything that slipped past them (manual fixes, bugs, restores):
+/// - Every account a transaction references exists
+/// - Every transaction status is in the allowed set
+/// - Every completed transfer has both a sender and a receiver
+/// - No account balance is negative (there is no overdraft facility)
+///
+/// Balances are stored directly on accounts rather than derived from ledger
+/// entries, so there is no per-transaction ledger sum to verify.
+///
+/// Tables are read in keyset-paginated batches with plain `SELECT`s, so the
+/// sweep never holds row locks or blocks payments while it runs.
+pub struct IntegrityService {
+    pool: PgPool,
+    batch_size: i64,
+}
+
+impl IntegrityService {
+    /// Creates a new integrity service with the given database pool and settings
+    pub fn new(pool: PgPool, config: IntegrityConfig) -> Self {
+        Self {
+            pool,
+            batch_size: config.batch_size.max(1),
+        }
+    }
+
+    /// Runs a full sweep, persists the resulting report and returns it
+    ///
+    /// Violations are logged at error level and reflected in
+    /// `LAST_CHECK_VIOLATIONS` and `CHECKS_WITH_VIOLATIONS`.
+    pub async fn run_check(&self) -> Result<IntegrityReport, AppError> {
+        let started_at = Utc::now();
+        let mut violations = Vec::new();
+
+        // Transactions, in id order
+        let mut transactions_scanned = 0i64;
+        let mut cursor = Uuid::nil();
+        loop {
+            let batch = sqlx::query_as::<_, TransactionRefs>(
+                r#"
+                SELECT t.id, t.sender_account_id, t.receiver_account_id, t.transaction_type, t.status,
+                       s.id IS NOT NULL AS sender_exists, r.id IS NOT NULL AS receiver_exists
+                FROM transactions t
+                LEFT JOIN accounts s ON s.id = t.sender_account_id
+                LEFT JOIN accounts r ON r.id = t.receiver_account_id
+                WHERE t.id > $1
+                ORDER BY t.id
+                LIMIT $2
+                "#,
+            )
+            .bind(cursor)
+            .bind(self.batch_size)
+            .fetch_all(&self.pool)
+            .await?;
+
+            let Some(last) = batch.last() else { break };
+            cursor = last.id;
+            transactions_scanned += batch.len() as i64;
+            violations.extend(batch.iter().flat_map(transaction_violations));
+        }
+
+        // Accounts, in id order
+        let mut accounts_scanned = 0i64;
+        let mut cursor = Uuid::nil();
+        loop {
+            let batch = sqlx::query_as::<_, AccountBalance>(
+                r#"
+                SELECT id, balance FROM accounts
+                WHERE id > $1
+                ORDER BY id
+                LIMIT $2
+                "#,
+            )
+            .bind(cursor)
+            .bind(self.batch_size)
+            .fetch_all(&self.pool)
+            .await?;
+
+            let Some(last) = batch.last() else { break };
+            cursor = last.id;
+            accounts_scanned += batch.len() as i64;
+            violations.extend(
+                batch
+                    .iter()
+                    .filter(|account| account.balance.0 < Decimal::ZERO)
+                    .map(|account| IntegrityViolation {
+                        kind: ViolationKind::NegativeBalance,
+                        entity_id: account.id,
+                        detail: format!("Balance {} is below zero", account.balance),
+                    }),
+            );
+        }
+
+        let report = sqlx::query_as::<_, IntegrityReport>(
+            r#"
+            INSERT INTO integrity_reports
+                (id, started_at, finished_at, transactions_scanned, accounts_scanned,
+                 violation_count, violations)
+            VALUES ($1, $2, NOW(), $3, $4, $5, $6)
+            RETURNING id, started_at, finished_at, transactions_scanned, accounts_scanned,
+                      violation_count, violations
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(started_at)
+        .bind(transactions_scanned)
+        .bind(accounts_scanned)
+        .bind(violations.len() as i64)
+        .bind(Json(&violations))
+        .fetch_one(&self.pool)
+        .await?;
+
+        record_outcome(&report);
+
+        Ok(report)
+    }
+
+    /// Fetches the most recently finished integrity report
+    pub async fn latest_report(&self) -> Result<IntegrityReport, AppError> {
+        sqlx::query_as::<_, IntegrityReport>(
+            r#"
+            SELECT id, started_at, finished_at, transactions_scanned, accounts_scanned,
+                   violation_count, violations
+            FROM integrity_reports
+            ORDER BY finished_at DESC
+            LIMIT 1
+            "#,
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("No integrity check has run yet".to_string()))
+    }
+}
+
+/// Checks a single transaction against the per-transaction invariants
+pub fn transaction_violations(transaction: &TransactionRefs) -> Vec<IntegrityViolation> {
+    let mut violations = Vec::new();
+    let mut violation = |kind, detail: String| {
+        violations.push(IntegrityViolation {
+            kind,
+            entity_id: transaction.id,
+            detail,
+        })
+    };
+
+    if let Some(sender_id) = transaction
+        .sender_account_id
+        .filter(|_| !transaction.sender_exists)
+    {
+        violation(
+            ViolationKind::MissingAccount,
+            format!("Sender account {} does not exist", sender_id),
+        );
+    }
+    if let Some(receiver_id) = transaction
+        .receiver_account_id
+        .filter(|_| !transaction.receiver_exists)
+    {
+        violation(
+            ViolationKind::MissingAccount,
+            format!("Receiver account {} does not exist", receiver_id),
+        );
+    }
+
+    if !ALLOWED_STATUSES
+        .iter()
+        .any(|status| status.to_string() == transaction.status)
+    {
+        violation(
+            ViolationKind::InvalidStatus,
+            format!("Status '{}' is not allowed", transaction.status),
+        );
+    }
+
+    let is_completed_transfer = transaction.transaction_type
+        == TransactionType::TRANSFER.to_string()
+        && transaction.status == TransactionStatus::COMPLETED.to_string();
+    if is_completed_transfer
+        && (transaction.sender_account_id.is_none() || transaction.receiver_account_id.is_none())
+    {
+        violation(
+            ViolationKind::IncompleteTransfer,
+            "Completed transfer is missing its sender or receiver".to_string(),
+        );
+    }
+
+    violations
+}
+
+/// Publishes a finished report to the logs and the integrity metrics
+fn record_outcome(report: &IntegrityReport) {
+    LAST_CHECK_VIOLATIONS.store(report.violation_count as u64, Ordering::Relaxed);
+
+    if report.is_clean() {
+        tracing::info!(
+            report_id = %report.id,
+            transactions_scanned = report.transactions_scanned,
+            accounts_scanned = report.accounts_scanned,
+            "Integrity check passed"
+        );
+        return;
+    }
+
+    CHECKS_WITH_VIOLATIONS.fetch_add(1, Ordering::Relaxed);
+
+    let mut by_kind: BTreeMap<String, usize> = BTreeMap::new();
+    for violation in report.violations.iter() {
+        *by_kind.entry(format!("{:?}", violation.kind)).or_default() += 1;
+    }
+    tracing::error!(
+        report_id = %report.id,
+        violation_count = report.violation_count,
+        by_kind = ?by_kind,
+        "Integrity check found violations"
+    );
+}
@@ -1,3 +1,18 @@
 pub mod account_service;
+pub mod budget_service;
+pub mod destination_service;
+pub mod diagnostics_service;
+pub mod exchange_service;
+pub mod fee_service;
+pub mod import_service;
+pub mod integrity_service;
+pub mod notification_service;
+pub mod outbox_service;
+#[cfg(feature = "sandbox")]
+pub mod sandbox_service;
+pub mod scheduled_transfer_service;
+pub mod standing_order_service;
 pub mod transaction_service;
+pub mod usage_service;
 pub mod user_service;
+pub mod webhook_service;
@@ -0,0 +1,358 @@
+use crate::config::NotificationConfig;
+use crate::models::statement::FinalStatement;
+use crate::models::transaction::TransactionResponse;
+use crate::models::user::{UserResponse, VERIFICATION_TOKEN_TTL_HOURS};
+use crate::utils::email::{EmailMessage, EmailSender};
+use crate::utils::error::AppError;
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// The notification an email delivery belongs to
+///
+/// - TRANSFER_SENT: Receipt for the sender of a transfer
+/// - TRANSFER_RECEIVED: Notice to the receiver that money arrived
+/// - EMAIL_VERIFICATION: Link confirming a new user's email address
+/// - FINAL_STATEMENT: Lifetime statement of an account its owner closed
+#[allow(clippy::upper_case_acronyms, non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailKind {
+    TRANSFER_SENT,
+    TRANSFER_RECEIVED,
+    EMAIL_VERIFICATION,
+    FINAL_STATEMENT,
+}
+
+impl std::fmt::Display for EmailKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmailKind::TRANSFER_SENT => write!(f, "TRANSFER_SENT"),
+            EmailKind::TRANSFER_RECEIVED => write!(f, "TRANSFER_RECEIVED"),
+            EmailKind::EMAIL_VERIFICATION => write!(f, "EMAIL_VERIFICATION"),
+            EmailKind::FINAL_STATEMENT => write!(f, "FINAL_STATEMENT"),
+        }
+    }
+}
+
+/// The owner of one side of a transfer, as needed to address a receipt
+#[derive(Debug, FromRow)]
+struct TransferParty {
+    account_id: Uuid,
+    account_number: Option<String>,
+    user_id: Uuid,
+    username: String,
+    email: String,
+    transfer_receipts: bool,
+}
+
+impl TransferParty {
+    /// How the account is shown to the other party
+    fn account_label(&self) -> String {
+        self.account_number
+            .clone()
+            .unwrap_or_else(|| self.account_id.to_string())
+    }
+}
+
+/// Service that emails users about activity on their accounts
+///
+/// Notifications are sent after the triggering operation has committed and
+/// never affect its outcome: sending happens on a background task, and every
+/// attempt, successful or not, is recorded in `email_deliveries` for support.
+pub struct NotificationService {
+    pool: PgPool,
+    email_sender: Arc<dyn EmailSender>,
+    config: NotificationConfig,
+}
+
+impl NotificationService {
+    /// Creates a new notification service delivering through `email_sender`
+    pub fn new(
+        pool: PgPool,
+        email_sender: Arc<dyn EmailSender>,
+        config: NotificationConfig,
+    ) -> Self {
+        Self {
+            pool,
+            email_sender,
+            config,
+        }
+    }
+
+    /// Returns whether a completed transfer is large enough to warrant receipts
+    pub fn wants_transfer_receipts(&self, transaction: &TransactionResponse) -> bool {
+        self.config
+            .transfer_receipt_threshold
+            .is_some_and(|threshold| transaction.amount > threshold)
+    }
+
+    /// Sends transfer receipts on a background task, if the transfer qualifies
+    ///
+    /// # Returns
+    /// The background task, or `None` when no receipts are due
+    pub fn spawn_transfer_receipts(
+        self: &Arc<Self>,
+        transaction: &TransactionResponse,
+    ) -> Option<JoinHandle<()>> {
+        if !self.wants_transfer_receipts(transaction) {
+            return None;
+        }
+
+        let service = self.clone();
+        let transaction = transaction.clone();
+        Some(tokio::spawn(async move {
+            if let Err(e) = service.send_transfer_receipts(&transaction).await {
+                tracing::error!(
+                    transaction_id = %transaction.id,
+                    "Could not send transfer receipts: {}",
+                    e
+                );
+            }
+        }))
+    }
+
+    /// Emails a receipt to the sender and a notice to the receiver of a transfer
+    ///
+    /// Users who turned off transfer receipts are skipped. A failed delivery is
+    /// recorded and does not stop the other party's email.
+    pub async fn send_transfer_receipts(
+        &self,
+        transaction: &TransactionResponse,
+    ) -> Result<(), AppError> {
+        let (Some(sender_account_id), Some(receiver_account_id)) = (
+            transaction.sender_account_id,
+            transaction.receiver_account_id,
+        ) else {
+            return Ok(());
+        };
+
+        let sender = self.get_party(sender_account_id).await?;
+        let receiver = self.get_party(receiver_account_id).await?;
+
+        for (kind, party, counterparty) in [
+            (EmailKind::TRANSFER_SENT, &sender, &receiver),
+            (EmailKind::TRANSFER_RECEIVED, &receiver, &sender),
+        ] {
+            if !party.transfer_receipts {
+                continue;
+            }
+
+            let message = render_transfer_receipt(kind, transaction, party, counterparty);
+            let result = self.email_sender.send(&message).await;
+            if let Err(e) = &result {
+                tracing::warn!(
+                    transaction_id = %transaction.id,
+                    kind = %kind,
+                    "Email delivery failed: {}",
+                    e
+                );
+            }
+            self.record_delivery(
+                party.user_id,
+                Some(transaction.id),
+                kind,
+                &message.to,
+                result.err(),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends a new user their email verification token on a background task
+    pub fn spawn_email_verification(
+        self: &Arc<Self>,
+        user: &UserResponse,
+        token: String,
+    ) -> JoinHandle<()> {
+        let service = self.clone();
+        let user = user.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service.send_email_verification(&user, &token).await {
+                tracing::error!(user_id = %user.id, "Could not send email verification: {}", e);
+            }
+        })
+    }
+
+    /// Emails a user the token that confirms their address
+    ///
+    /// The attempt is recorded whether or not the email could be delivered.
+    pub async fn send_email_verification(
+        &self,
+        user: &UserResponse,
+        token: &str,
+    ) -> Result<(), AppError> {
+        let message = EmailMessage {
+            to: user.email.clone(),
+            subject: "Confirm your email address".to_string(),
+            body: format!(
+                "Hello {},\n\nPlease confirm your email address with this token:\n\n{}\n\n\
+                 It expires in {} hours and can be used once.\n",
+                user.username, token, VERIFICATION_TOKEN_TTL_HOURS
+            ),
+        };
+
+        let result = self.email_sender.send(&message).await;
+        if let Err(e) = &result {
+            tracing::warn!(user_id = %user.id, "Email delivery failed: {}", e);
+        }
+        self.record_delivery(
+            user.id,
+            None,
+            EmailKind::EMAIL_VERIFICATION,
+            &message.to,
+            result.err(),
+        )
+        .await
+    }
+
+    /// Sends the owner of a closed account its final statement on a background task
+    pub fn spawn_final_statement(self: &Arc<Self>, statement: FinalStatement) -> JoinHandle<()> {
+        let service = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service.send_final_statement(&statement).await {
+                tracing::error!(
+                    account_id = %statement.account_id,
+                    "Could not send final statement: {}",
+                    e
+                );
+            }
+        })
+    }
+
+    /// Emails the owner of a closed account the totals of its final statement
+    ///
+    /// The attempt is recorded whether or not the email could be delivered;
+    /// the statement itself stays available through the API either way.
+    pub async fn send_final_statement(&self, statement: &FinalStatement) -> Result<(), AppError> {
+        let owner = self.get_party(statement.account_id).await?;
+        let amount = |value| format!("{} {}", value, statement.currency);
+        let message = EmailMessage {
+            to: owner.email.clone(),
+            subject: format!("Final statement for account {}", owner.account_label()),
+            body: format!(
+                "Hello {},\n\nAccount {} was closed on {}. Its final statement covers \
+                 the account since it was opened on {}:\n\n\
+                 Opening balance: {}\nMoney in: {}\nMoney out: {}\nClosing balance: {}\n\
+                 Transactions: {}\n\nThe full statement stays available in the app.\n",
+                owner.username,
+                owner.account_label(),
+                statement.period_end.to_rfc3339(),
+                statement.period_start.to_rfc3339(),
+                amount(statement.opening_balance),
+                amount(statement.total_credits),
+                amount(statement.total_debits),
+                amount(statement.closing_balance),
+                statement.transactions.len()
+            ),
+        };
+
+        let result = self.email_sender.send(&message).await;
+        if let Err(e) = &result {
+            tracing::warn!(account_id = %statement.account_id, "Email delivery failed: {}", e);
+        }
+        self.record_delivery(
+            owner.user_id,
+            None,
+            EmailKind::FINAL_STATEMENT,
+            &message.to,
+            result.err(),
+        )
+        .await
+    }
+
+    async fn get_party(&self, account_id: Uuid) -> Result<TransferParty, AppError> {
+        sqlx::query_as::<_, TransferParty>(
+            r#"
+            SELECT a.id AS account_id, a.account_number, u.id AS user_id, u.username, u.email,
+                   u.transfer_receipts
+            FROM accounts a JOIN users u ON u.id = a.user_id
+            WHERE a.id = $1
+            "#,
+        )
+        .bind(account_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Account with ID {} not found", account_id)))
+    }
+
+    async fn record_delivery(
+        &self,
+        user_id: Uuid,
+        transaction_id: Option<Uuid>,
+        kind: EmailKind,
+        recipient: &str,
+        error: Option<String>,
+    ) -> Result<(), AppError> {
+        let status = if error.is_none() { "SENT" } else { "FAILED" };
+
+        sqlx::query(
+            r#"
+            INSERT INTO email_deliveries (id, user_id, transaction_id, kind, recipient, status, error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(transaction_id)
+        .bind(kind.to_string())
+        .bind(recipient)
+        .bind(status)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Renders the email one party of a transfer receives
+fn render_transfer_receipt(
+    kind: EmailKind,
+    transaction: &TransactionResponse,
+    party: &TransferParty,
+    counterparty: &TransferParty,
+) -> EmailMessage {
+    let amount = format!("{} {}", transaction.amount, transaction.currency);
+    let (subject, summary) = match kind {
+        EmailKind::TRANSFER_SENT => (
+            format!("Receipt: you sent {}", amount),
+            format!(
+                "You sent {} to account {}.",
+                amount,
+                counterparty.account_label()
+            ),
+        ),
+        EmailKind::TRANSFER_RECEIVED => (
+            format!("You received {}", amount),
+            format!(
+                "You received {} from account {}.",
+                amount,
+                counterparty.account_label()
+            ),
+        ),
+        EmailKind::EMAIL_VERIFICATION | EmailKind::FINAL_STATEMENT => {
+            unreachable!("not a transfer receipt")
+        }
+    };
+
+    let mut body = format!(
+        "Hello {},\n\n{}\n\nTransaction: {}\nDate: {}\n",
+        party.username,
+        summary,
+        transaction.id,
+        transaction.created_at.to_rfc3339()
+    );
+    if let Some(description) = &transaction.description {
+        body.push_str(&format!("Description: {}\n", description));
+    }
+    body.push_str("\nYou can turn off transfer receipts in your notification preferences.\n");
+
+    EmailMessage {
+        to: party.email.clone(),
+        subject,
+        body,
+    }
+}
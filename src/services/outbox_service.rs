@@ -0,0 +1,135 @@
+use crate::models::outbox::OutboxEvent;
+use crate::utils::error::AppError;
+use crate::utils::events::{EventSink, LogEventSink};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Most events handed to the sink by one call of `publish_pending`
+const PUBLISH_BATCH_SIZE: i64 = 100;
+
+/// Records an event in the outbox, to be published once `conn`'s transaction commits
+///
+/// Call this in the database transaction making the change the event
+/// describes: the event is then stored exactly when the change is, and a
+/// rolled back change leaves no event behind.
+pub async fn record_event(
+    conn: &mut PgConnection,
+    event_type: &str,
+    aggregate_id: Uuid,
+    payload: &impl Serialize,
+) -> Result<(), AppError> {
+    let payload = serde_json::to_value(payload)
+        .map_err(|e| AppError::Internal(format!("Serialization failed: {}", e)))?;
+    sqlx::query(
+        r#"
+        INSERT INTO outbox_events (id, event_type, aggregate_id, payload)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(event_type)
+    .bind(aggregate_id)
+    .bind(payload)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Service publishing the events recorded in the transactional outbox
+///
+/// Events are published at least once, roughly in the order they were
+/// recorded: an event is marked published only after the sink accepted it,
+/// and one the sink refuses holds back the events after it until the next call.
+pub struct OutboxService {
+    pool: PgPool,
+    sink: Arc<dyn EventSink>,
+}
+
+impl OutboxService {
+    /// Creates an outbox service publishing to the log
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            sink: Arc::new(LogEventSink),
+        }
+    }
+
+    /// Publishes events to `sink` instead of the log
+    pub fn with_sink(mut self, sink: Arc<dyn EventSink>) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// Hands the oldest unpublished events to the sink and marks those it accepted
+    ///
+    /// The events stay locked until they are marked, so several server
+    /// instances never publish the same event at once.
+    ///
+    /// # Returns
+    /// How many events were published
+    pub async fn publish_pending(&self) -> Result<usize, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let events = sqlx::query_as::<_, OutboxEvent>(
+            r#"
+            SELECT id, event_type, aggregate_id, payload, created_at, published_at
+            FROM outbox_events
+            WHERE published_at IS NULL
+            ORDER BY created_at, id
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(PUBLISH_BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut published = Vec::with_capacity(events.len());
+        for event in &events {
+            if let Err(e) = self.sink.publish(event).await {
+                tracing::warn!(event_id = %event.id, "Could not publish event: {}", e);
+                break;
+            }
+            published.push(event.id);
+        }
+
+        sqlx::query("UPDATE outbox_events SET published_at = NOW() WHERE id = ANY($1)")
+            .bind(&published)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(published.len())
+    }
+
+    /// Queues the published events recorded in `[from, to)` to be published again
+    ///
+    /// Lets a consumer that lost events, or joined late, catch up. Events
+    /// still waiting to be published are left as they are.
+    ///
+    /// # Returns
+    /// How many events were queued again
+    pub async fn replay(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<u64, AppError> {
+        if from >= to {
+            return Err(AppError::BadRequest(
+                "'from' must be before 'to'".to_string(),
+            ));
+        }
+
+        let result = sqlx::query(
+            r#"
+            UPDATE outbox_events SET published_at = NULL
+            WHERE created_at >= $1 AND created_at < $2 AND published_at IS NOT NULL
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
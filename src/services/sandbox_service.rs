@@ -0,0 +1,169 @@
+use crate::config::SandboxConfig;
+use crate::models::decimal::SqlxDecimal;
+use crate::models::sandbox::{FaucetRequest, SandboxPurgeSummary, SANDBOX_DEPOSIT_DESCRIPTION};
+use crate::models::transaction::{DepositRequest, TransactionResponse};
+use crate::services::transaction_service::TransactionService;
+use crate::utils::error::AppError;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Service minting and purging test funds in sandbox environments
+///
+/// Faucet deposits are booked through `TransactionService` like any other
+/// deposit, flagged with `transactions.sandbox` so they can be deleted again.
+pub struct SandboxService {
+    pool: PgPool,
+    transaction_service: Arc<TransactionService>,
+    config: SandboxConfig,
+}
+
+impl SandboxService {
+    /// Creates a new sandbox service with the given database pool and faucet limits
+    pub fn new(
+        pool: PgPool,
+        transaction_service: Arc<TransactionService>,
+        config: SandboxConfig,
+    ) -> Self {
+        Self {
+            pool,
+            transaction_service,
+            config,
+        }
+    }
+
+    /// Deposits test funds into one of a user's accounts
+    ///
+    /// # Arguments
+    /// * `user_id` - The user asking for funds, who must own the account
+    /// * `request` - The account and amount, which may not exceed the faucet maximum
+    ///
+    /// # Returns
+    /// The completed deposit, or `TooManyRequests` once the user has used up the
+    /// faucet's hourly allowance
+    pub async fn faucet(
+        &self,
+        user_id: Uuid,
+        request: FaucetRequest,
+    ) -> Result<TransactionResponse, AppError> {
+        if request.amount <= Decimal::ZERO || request.amount > self.config.faucet_max_amount {
+            return Err(AppError::BadRequest(format!(
+                "Faucet amount must be positive and at most {}",
+                self.config.faucet_max_amount
+            )));
+        }
+
+        let owner: Uuid = sqlx::query_scalar("SELECT user_id FROM accounts WHERE id = $1")
+            .bind(request.account_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Account with ID {} not found", request.account_id))
+            })?;
+        if owner != user_id {
+            return Err(AppError::Forbidden(
+                "You don't have permission to use this account".to_string(),
+            ));
+        }
+
+        // Counted from the ledger, so the limit holds across server instances
+        let recent: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM transactions t
+            JOIN accounts a ON a.id = t.receiver_account_id
+            WHERE t.sandbox
+              AND a.user_id = $1
+              AND t.created_at > NOW() - INTERVAL '1 hour'
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+        if recent >= self.config.faucet_hourly_limit {
+            return Err(AppError::TooManyRequests(format!(
+                "The faucet allows {} requests per hour",
+                self.config.faucet_hourly_limit
+            )));
+        }
+
+        self.transaction_service
+            .process_sandbox_deposit(DepositRequest {
+                account_id: request.account_id,
+                amount: request.amount,
+                currency: None,
+                exchange_rate: None,
+                description: Some(SANDBOX_DEPOSIT_DESCRIPTION.to_string()),
+                category: None,
+                external_reference: None,
+            })
+            .await
+    }
+
+    /// Deletes every faucet deposit and takes its amount back out of the account
+    ///
+    /// The purge is all or nothing. It fails with `Conflict` if an account no
+    /// longer holds the test funds it received, e.g. because they were
+    /// transferred away or are reserved by a hold, since removing them would
+    /// make its balance negative or smaller than its holds.
+    pub async fn purge(&self) -> Result<SandboxPurgeSummary, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the affected accounts so no transfer spends the funds mid-purge
+        let credits = sqlx::query_as::<_, (Uuid, SqlxDecimal, SqlxDecimal)>(
+            r#"
+            SELECT a.id, a.balance - a.held_amount, s.total
+            FROM accounts a
+            JOIN (
+                SELECT receiver_account_id AS account_id, SUM(amount) AS total
+                FROM transactions
+                WHERE sandbox
+                GROUP BY receiver_account_id
+            ) s ON s.account_id = a.id
+            ORDER BY a.id
+            FOR UPDATE OF a
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut account_ids = Vec::with_capacity(credits.len());
+        for (account_id, balance, total) in &credits {
+            if balance.0 < total.0 {
+                return Err(AppError::Conflict(format!(
+                    "Account {} no longer holds the {} of sandbox funds it received",
+                    account_id, total
+                )));
+            }
+            // Recorded in the balance history without a transaction, since
+            // the deposits are deleted below
+            self.transaction_service
+                .account_service
+                .change_balance(&mut tx, *account_id, -total.0, None)
+                .await?;
+            account_ids.push(*account_id);
+        }
+
+        // Only the locked accounts' deposits, whose sum was just taken back
+        let deleted =
+            sqlx::query("DELETE FROM transactions WHERE sandbox AND receiver_account_id = ANY($1)")
+                .bind(&account_ids)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+        tx.commit().await?;
+
+        tracing::info!(
+            transactions_deleted = deleted,
+            accounts_adjusted = credits.len(),
+            "Sandbox transactions purged"
+        );
+
+        Ok(SandboxPurgeSummary {
+            transactions_deleted: deleted,
+            accounts_adjusted: credits.len() as u64,
+        })
+    }
+}
@@ -0,0 +1,286 @@
+use crate::middleware::request_context::RequestContext;
+use crate::models::decimal::SqlxDecimal;
+use crate::models::idempotency::IdempotencyKey;
+use crate::models::scheduled_transfer::{
+    ScheduleTransferRequest, ScheduledTransfer, ScheduledTransferResponse,
+    SCHEDULED_TRANSFER_NOT_PENDING,
+};
+use crate::services::transaction_service::TransactionService;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Most scheduled transfers run by one call to `execute_due`
+const EXECUTION_BATCH_SIZE: usize = 100;
+
+const SCHEDULED_TRANSFER_COLUMNS: &str = r#"
+    id, user_id, sender_account_id, receiver_account_id, amount, currency,
+    description, category, execute_at, expires_at, status, transaction_id, error,
+    created_at, updated_at
+"#;
+
+/// Service for transfers booked to run at a later time
+///
+/// Scheduled transfers are stored in `scheduled_transactions` and run through
+/// `TransactionService` like any other transfer once they are due, so every
+/// check, including the balance check, happens at execution time.
+pub struct ScheduledTransferService {
+    pool: PgPool,
+    transaction_service: Arc<TransactionService>,
+}
+
+impl ScheduledTransferService {
+    /// Creates a new scheduled transfer service with the given database pool
+    pub fn new(pool: PgPool, transaction_service: Arc<TransactionService>) -> Self {
+        Self {
+            pool,
+            transaction_service,
+        }
+    }
+
+    /// Schedules a transfer for a user
+    ///
+    /// # Arguments
+    /// * `user_id` - The user scheduling the transfer, who must own the sending account
+    /// * `request` - The transfer and when to run it, which must be in the future
+    ///
+    /// # Returns
+    /// The scheduled transfer, in SCHEDULED state
+    pub async fn schedule(
+        &self,
+        user_id: Uuid,
+        request: ScheduleTransferRequest,
+    ) -> Result<ScheduledTransferResponse, AppError> {
+        if request.execute_at <= Utc::now() {
+            return Err(AppError::BadRequest(
+                "execute_at must be in the future".to_string(),
+            ));
+        }
+        if request
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= request.execute_at)
+        {
+            return Err(AppError::BadRequest(
+                "expires_at must be after execute_at".to_string(),
+            ));
+        }
+        if request.sender_account_id == request.receiver_account_id {
+            return Err(AppError::BadRequest(
+                "Cannot transfer to the same account".to_string(),
+            ));
+        }
+
+        let currency: String = sqlx::query_scalar("SELECT currency FROM accounts WHERE id = $1")
+            .bind(request.sender_account_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "Account with ID {} not found",
+                    request.sender_account_id
+                ))
+            })?;
+
+        let scheduled = sqlx::query_as::<_, ScheduledTransfer>(&format!(
+            r#"
+            INSERT INTO scheduled_transactions
+                (id, user_id, sender_account_id, receiver_account_id, amount, currency,
+                 description, category, execute_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING {}
+            "#,
+            SCHEDULED_TRANSFER_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(request.sender_account_id)
+        .bind(request.receiver_account_id)
+        .bind(SqlxDecimal(request.amount))
+        .bind(currency)
+        .bind(request.description)
+        .bind(request.category)
+        .bind(request.execute_at)
+        .bind(request.expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(scheduled.into())
+    }
+
+    /// Lists a user's transfers that have not run yet, soonest first
+    pub async fn list_pending(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ScheduledTransferResponse>, AppError> {
+        let scheduled = sqlx::query_as::<_, ScheduledTransfer>(&format!(
+            r#"
+            SELECT {}
+            FROM scheduled_transactions
+            WHERE user_id = $1 AND status = 'SCHEDULED'
+            ORDER BY execute_at, created_at
+            "#,
+            SCHEDULED_TRANSFER_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(scheduled.into_iter().map(Into::into).collect())
+    }
+
+    /// Cancels one of a user's scheduled transfers before it runs
+    ///
+    /// Transfers of other users are reported as not found. A transfer that
+    /// has already run, or is running right now, is rejected with
+    /// `SCHEDULED_TRANSFER_NOT_PENDING`.
+    pub async fn cancel(
+        &self,
+        user_id: Uuid,
+        id: Uuid,
+    ) -> Result<ScheduledTransferResponse, AppError> {
+        // Waits for a running execution, which holds the row lock until it is done
+        let cancelled = sqlx::query_as::<_, ScheduledTransfer>(&format!(
+            r#"
+            UPDATE scheduled_transactions
+            SET status = 'CANCELLED'
+            WHERE id = $1 AND user_id = $2 AND status = 'SCHEDULED'
+            RETURNING {}
+            "#,
+            SCHEDULED_TRANSFER_COLUMNS
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some(cancelled) = cancelled {
+            return Ok(cancelled.into());
+        }
+
+        let status: Option<String> = sqlx::query_scalar(
+            "SELECT status FROM scheduled_transactions WHERE id = $1 AND user_id = $2",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        match status {
+            Some(status) => Err(AppError::Rejected {
+                code: SCHEDULED_TRANSFER_NOT_PENDING,
+                message: format!("Scheduled transfer {} is already {}", id, status),
+            }),
+            None => Err(AppError::NotFound(format!(
+                "Scheduled transfer with ID {} not found",
+                id
+            ))),
+        }
+    }
+
+    /// Voids the scheduled transfers whose expiry time has passed by `now`
+    ///
+    /// Transfers past their expiry are never run, even if they are due, so
+    /// calling this before `execute_due` only records what already holds.
+    /// Scheduled transfers reserve no funds, so there is nothing to release.
+    ///
+    /// # Returns
+    /// How many scheduled transfers expired
+    pub async fn expire_overdue(&self, now: DateTime<Utc>) -> Result<u64, AppError> {
+        // Skips a transfer that is running right now; it was claimed before expiring
+        let expired = sqlx::query(
+            r#"
+            UPDATE scheduled_transactions
+            SET status = 'EXPIRED'
+            WHERE id IN (
+                SELECT id FROM scheduled_transactions
+                WHERE status = 'SCHEDULED' AND expires_at <= $1
+                FOR UPDATE SKIP LOCKED
+            )
+            "#,
+        )
+        .bind(now)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(expired)
+    }
+
+    /// Runs the scheduled transfers that are due
+    ///
+    /// Each transfer is claimed with its row locked, so several server
+    /// instances never run the same one. Transfers past their expiry are left
+    /// for `expire_overdue`. A transfer that is rejected is marked
+    /// FAILED with the reason and not retried. Transfers are booked under an
+    /// idempotency key derived from their ID, so one whose outcome wasn't
+    /// recorded, e.g. because the server stopped in between, is not booked
+    /// twice when it is picked up again.
+    ///
+    /// # Returns
+    /// How many scheduled transfers were run, whether they succeeded or not
+    pub async fn execute_due(&self) -> Result<usize, AppError> {
+        let mut executed = 0;
+        while executed < EXECUTION_BATCH_SIZE {
+            let mut tx = self.pool.begin().await?;
+            let scheduled = sqlx::query_as::<_, ScheduledTransfer>(&format!(
+                r#"
+                SELECT {}
+                FROM scheduled_transactions
+                WHERE status = 'SCHEDULED' AND execute_at <= NOW()
+                  AND (expires_at IS NULL OR expires_at > NOW())
+                ORDER BY execute_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+                "#,
+                SCHEDULED_TRANSFER_COLUMNS
+            ))
+            .fetch_optional(&mut *tx)
+            .await?;
+            let Some(scheduled) = scheduled else {
+                break;
+            };
+
+            let request = scheduled.transfer_request();
+            let idempotency = IdempotencyKey::new(
+                scheduled.user_id,
+                &format!("scheduled-transfer-{}", scheduled.id),
+                "scheduled_transfer",
+                &request,
+            )?;
+            let result = self
+                .transaction_service
+                .process_transfer_idempotent(
+                    request,
+                    &RequestContext::default(),
+                    Some(&idempotency),
+                )
+                .await;
+
+            let (status, transaction_id, error) = match result {
+                Ok(transaction) => ("EXECUTED", Some(transaction.id), None),
+                Err(e) => {
+                    tracing::warn!("Scheduled transfer {} failed: {}", scheduled.id, e);
+                    ("FAILED", None, Some(e.to_string()))
+                }
+            };
+            sqlx::query(
+                r#"
+                UPDATE scheduled_transactions
+                SET status = $2, transaction_id = $3, error = $4
+                WHERE id = $1
+                "#,
+            )
+            .bind(scheduled.id)
+            .bind(status)
+            .bind(transaction_id)
+            .bind(error)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+
+            executed += 1;
+        }
+
+        Ok(executed)
+    }
+}
@@ -0,0 +1,394 @@
+use crate::middleware::request_context::RequestContext;
+use crate::models::decimal::SqlxDecimal;
+use crate::models::idempotency::IdempotencyKey;
+use crate::models::standing_order::{
+    CreateStandingOrderRequest, StandingOrder, StandingOrderResponse, UpcomingStandingOrder,
+    STANDING_ORDER_NOT_ACTIVE, STANDING_ORDER_NOT_PAUSED,
+};
+use crate::services::transaction_service::TransactionService;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Most occurrences run by one call to `execute_due`
+const EXECUTION_BATCH_SIZE: usize = 100;
+
+const STANDING_ORDER_COLUMNS: &str = r#"
+    id, user_id, sender_account_id, receiver_account_id, amount, currency,
+    description, category, interval, starts_at, end_date, occurrence, next_run_at,
+    status, last_error, created_at, updated_at
+"#;
+
+/// Service for standing orders, transfers repeated at a fixed interval
+///
+/// Each occurrence is booked through `TransactionService` like any other
+/// transfer and linked to its order with `transactions.standing_order_id`.
+/// Every attempt is kept in `standing_order_runs`.
+pub struct StandingOrderService {
+    pool: PgPool,
+    transaction_service: Arc<TransactionService>,
+}
+
+impl StandingOrderService {
+    /// Creates a new standing order service with the given database pool
+    pub fn new(pool: PgPool, transaction_service: Arc<TransactionService>) -> Self {
+        Self {
+            pool,
+            transaction_service,
+        }
+    }
+
+    /// Sets up a standing order for a user
+    ///
+    /// # Arguments
+    /// * `user_id` - The user setting up the order, who must own the sending account
+    /// * `request` - The transfer, how often to repeat it, and from and until when
+    ///
+    /// # Returns
+    /// The new standing order, in ACTIVE state
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        request: CreateStandingOrderRequest,
+    ) -> Result<StandingOrderResponse, AppError> {
+        let now = Utc::now();
+        let starts_at = request.starts_at.unwrap_or(now);
+        if starts_at < now - Duration::minutes(1) {
+            return Err(AppError::BadRequest(
+                "starts_at must not be in the past".to_string(),
+            ));
+        }
+        if request
+            .end_date
+            .is_some_and(|end_date| end_date < starts_at.date_naive())
+        {
+            return Err(AppError::BadRequest(
+                "end_date must not be before starts_at".to_string(),
+            ));
+        }
+        if request.sender_account_id == request.receiver_account_id {
+            return Err(AppError::BadRequest(
+                "Cannot transfer to the same account".to_string(),
+            ));
+        }
+
+        let currency: String = sqlx::query_scalar("SELECT currency FROM accounts WHERE id = $1")
+            .bind(request.sender_account_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "Account with ID {} not found",
+                    request.sender_account_id
+                ))
+            })?;
+
+        let order = sqlx::query_as::<_, StandingOrder>(&format!(
+            r#"
+            INSERT INTO standing_orders
+                (id, user_id, sender_account_id, receiver_account_id, amount, currency,
+                 description, category, interval, starts_at, end_date, next_run_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $10)
+            RETURNING {}
+            "#,
+            STANDING_ORDER_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(request.sender_account_id)
+        .bind(request.receiver_account_id)
+        .bind(SqlxDecimal(request.amount))
+        .bind(currency)
+        .bind(request.description)
+        .bind(request.category)
+        .bind(request.interval.to_string())
+        .bind(starts_at)
+        .bind(request.end_date)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(order.into())
+    }
+
+    /// Lists a user's standing orders that haven't been deleted, oldest first
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<StandingOrderResponse>, AppError> {
+        let orders = sqlx::query_as::<_, StandingOrder>(&format!(
+            r#"
+            SELECT {}
+            FROM standing_orders
+            WHERE user_id = $1 AND status <> 'CANCELLED'
+            ORDER BY created_at, id
+            "#,
+            STANDING_ORDER_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders.into_iter().map(Into::into).collect())
+    }
+
+    /// Projects when a user's active standing orders run, up to `until`
+    ///
+    /// Runs are worked out from each order's interval and next run, as the
+    /// worker will execute them; paused orders are left out.
+    pub async fn upcoming(
+        &self,
+        user_id: Uuid,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<UpcomingStandingOrder>, AppError> {
+        let orders = sqlx::query_as::<_, StandingOrder>(&format!(
+            r#"
+            SELECT {}
+            FROM standing_orders
+            WHERE user_id = $1 AND status = 'ACTIVE'
+            ORDER BY next_run_at, id
+            "#,
+            STANDING_ORDER_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders
+            .into_iter()
+            .map(|order| UpcomingStandingOrder {
+                run_dates: order.runs_until(until),
+                order: order.into(),
+            })
+            .collect())
+    }
+
+    /// Stops a user's active standing order from running until it is resumed
+    pub async fn pause(&self, user_id: Uuid, id: Uuid) -> Result<StandingOrderResponse, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let order = self.lock_order(&mut tx, user_id, id).await?;
+        if order.status != "ACTIVE" {
+            return Err(AppError::Rejected {
+                code: STANDING_ORDER_NOT_ACTIVE,
+                message: format!("Standing order {} is {}", id, order.status),
+            });
+        }
+
+        let order = self
+            .update_schedule(&mut tx, id, "PAUSED", order.occurrence, order.next_run_at)
+            .await?;
+        tx.commit().await?;
+
+        Ok(order.into())
+    }
+
+    /// Lets a user's paused standing order run again
+    ///
+    /// Occurrences that fell while the order was paused are skipped; it
+    /// continues with the next one. An order whose end date passed in the
+    /// meantime is COMPLETED instead.
+    pub async fn resume(&self, user_id: Uuid, id: Uuid) -> Result<StandingOrderResponse, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let order = self.lock_order(&mut tx, user_id, id).await?;
+        if order.status != "PAUSED" {
+            return Err(AppError::Rejected {
+                code: STANDING_ORDER_NOT_PAUSED,
+                message: format!("Standing order {} is {}", id, order.status),
+            });
+        }
+
+        // The pending occurrence still runs if it hasn't come due yet
+        let now = Utc::now();
+        let (status, occurrence, next_run_at) = if order.next_run_at > now {
+            ("ACTIVE", order.occurrence, order.next_run_at)
+        } else {
+            next_schedule(&order, now)
+        };
+        let order = self
+            .update_schedule(&mut tx, id, status, occurrence, next_run_at)
+            .await?;
+        tx.commit().await?;
+
+        Ok(order.into())
+    }
+
+    /// Deletes one of a user's standing orders so it never runs again
+    ///
+    /// The order is kept as CANCELLED for the transactions that point to it,
+    /// but no longer listed.
+    pub async fn delete(&self, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        let order = self.lock_order(&mut tx, user_id, id).await?;
+        self.update_schedule(
+            &mut tx,
+            id,
+            "CANCELLED",
+            order.occurrence,
+            order.next_run_at,
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Runs the standing order occurrences that are due
+    ///
+    /// Each order is claimed with its row locked, so several server instances
+    /// never run the same occurrence. A failed occurrence is recorded with its
+    /// reason and the order moves on to the next one, which is attempted as
+    /// usual. Occurrences missed while the server was down are run once, not
+    /// once per missed interval. Transfers are booked under an idempotency key
+    /// derived from the order and occurrence, so an occurrence whose outcome
+    /// wasn't recorded is not booked twice when it is picked up again.
+    ///
+    /// # Returns
+    /// How many occurrences were run, whether they succeeded or not
+    pub async fn execute_due(&self) -> Result<usize, AppError> {
+        let mut executed = 0;
+        while executed < EXECUTION_BATCH_SIZE {
+            let mut tx = self.pool.begin().await?;
+            let order = sqlx::query_as::<_, StandingOrder>(&format!(
+                r#"
+                SELECT {}
+                FROM standing_orders
+                WHERE status = 'ACTIVE' AND next_run_at <= NOW()
+                ORDER BY next_run_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+                "#,
+                STANDING_ORDER_COLUMNS
+            ))
+            .fetch_optional(&mut *tx)
+            .await?;
+            let Some(order) = order else {
+                break;
+            };
+
+            let request = order.transfer_request();
+            let idempotency = IdempotencyKey::new(
+                order.user_id,
+                &format!("standing-order-{}-{}", order.id, order.occurrence),
+                "standing_order",
+                &request,
+            )?;
+            let result = self
+                .transaction_service
+                .process_transfer_idempotent(
+                    request,
+                    &RequestContext::default(),
+                    Some(&idempotency),
+                )
+                .await;
+
+            let (status, transaction_id, error) = match result {
+                Ok(transaction) => ("EXECUTED", Some(transaction.id), None),
+                Err(e) => {
+                    tracing::warn!(
+                        "Occurrence {} of standing order {} failed: {}",
+                        order.occurrence,
+                        order.id,
+                        e
+                    );
+                    ("FAILED", None, Some(e.to_string()))
+                }
+            };
+            if let Some(transaction_id) = transaction_id {
+                sqlx::query("UPDATE transactions SET standing_order_id = $2 WHERE id = $1")
+                    .bind(transaction_id)
+                    .bind(order.id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            sqlx::query(
+                r#"
+                INSERT INTO standing_order_runs
+                    (id, standing_order_id, occurrence, status, transaction_id, error)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(order.id)
+            .bind(order.occurrence)
+            .bind(status)
+            .bind(transaction_id)
+            .bind(&error)
+            .execute(&mut *tx)
+            .await?;
+
+            // Move on to the next occurrence, whether this one succeeded or not
+            let (status, occurrence, next_run_at) = next_schedule(&order, Utc::now());
+            self.update_schedule(&mut tx, order.id, status, occurrence, next_run_at)
+                .await?;
+            sqlx::query("UPDATE standing_orders SET last_error = $2 WHERE id = $1")
+                .bind(order.id)
+                .bind(error)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            executed += 1;
+        }
+
+        Ok(executed)
+    }
+
+    /// Locks one of a user's standing orders; deleted ones and other users'
+    /// orders are reported as not found
+    async fn lock_order(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        user_id: Uuid,
+        id: Uuid,
+    ) -> Result<StandingOrder, AppError> {
+        sqlx::query_as::<_, StandingOrder>(&format!(
+            r#"
+            SELECT {}
+            FROM standing_orders
+            WHERE id = $1 AND user_id = $2 AND status <> 'CANCELLED'
+            FOR UPDATE
+            "#,
+            STANDING_ORDER_COLUMNS
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Standing order with ID {} not found", id)))
+    }
+
+    /// Moves a standing order to `status` with its next occurrence
+    async fn update_schedule(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: Uuid,
+        status: &str,
+        occurrence: i32,
+        next_run_at: DateTime<Utc>,
+    ) -> Result<StandingOrder, AppError> {
+        let order = sqlx::query_as::<_, StandingOrder>(&format!(
+            r#"
+            UPDATE standing_orders
+            SET status = $2, occurrence = $3, next_run_at = $4
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            STANDING_ORDER_COLUMNS
+        ))
+        .bind(id)
+        .bind(status)
+        .bind(occurrence)
+        .bind(next_run_at)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(order)
+    }
+}
+
+/// The status, occurrence and time of the next run of an order after `now`;
+/// an order past its end date is COMPLETED
+fn next_schedule(order: &StandingOrder, now: DateTime<Utc>) -> (&'static str, i32, DateTime<Utc>) {
+    match order.next_occurrence_after(now) {
+        Some((occurrence, next_run_at)) => ("ACTIVE", occurrence, next_run_at),
+        None => ("COMPLETED", order.occurrence, order.next_run_at),
+    }
+}
@@ -1,12 +1,63 @@
+// Amounts are only ever handled as `Decimal`; see `utils::money`
+#![deny(
+    clippy::float_arithmetic,
+    clippy::float_cmp,
+    clippy::float_cmp_const,
+    clippy::lossy_float_literal,
+    clippy::cast_precision_loss
+)]
+
+use crate::config::{DiagnosticsConfig, TransactionConfig};
+use crate::middleware::metrics::metrics;
+use crate::middleware::request_context::RequestContext;
+use crate::models::account::{
+    balance_scale, ensure_account_active, AccountLimits, AccountLimitsRow, ACCOUNT_CLOSED,
+};
+use crate::models::budget::normalize_category;
 use crate::models::decimal::SqlxDecimal;
+use crate::models::destination::{
+    WithdrawalDestination, DESTINATION_NOT_FOUND, DESTINATION_NOT_OWNED, DESTINATION_REQUIRED,
+    DESTINATION_UNVERIFIED,
+};
+use crate::models::diagnostics::{Milestones, TransactionTimings};
+use crate::models::hold::{
+    CaptureHoldRequest, CreateHoldRequest, Hold, HoldCaptureResponse, HoldResponse, HoldStatus,
+    CAPTURE_EXCEEDS_HOLD, HOLD_NOT_ACTIVE,
+};
+use crate::models::idempotency::{IdempotencyKey, IDEMPOTENCY_KEY_TTL_HOURS};
+use crate::models::outbox::TRANSACTION_COMPLETED;
+use crate::models::statement::AccountStatement;
 use crate::models::transaction::{
-    CreateTransactionRequest, DepositRequest, Transaction, TransactionResponse, TransactionStatus,
-    TransactionType, TransferRequest, WithdrawalRequest,
+    AccountTransaction, AdminTransactionResponse, CategorySpending, CategorySpendingRow,
+    CheckStatus, CreateTransactionRequest, DepositRequest, PayoutRequest, SplitTransferRequest,
+    SplitTransferResponse, Transaction, TransactionConversion, TransactionCursor,
+    TransactionDetails, TransactionFilter, TransactionResponse, TransactionReversalResponse,
+    TransactionStatus, TransactionTotals, TransactionTotalsRow, TransactionType, TransferCheck,
+    TransferRequest, TransferValidation, UserTransaction, UserTransactionResponse,
+    WithdrawalRequest, DIRECTION_INCOMING, DIRECTION_OUTGOING, MAX_BATCH_TRANSFERS,
+    MAX_SPLIT_TRANSFER_LEGS, REVERSAL_INSUFFICIENT_FUNDS, TRANSACTION_ALREADY_REVERSED,
+    TRANSACTION_NOT_COMPLETED, TRANSACTION_NOT_REVERSIBLE,
 };
+use crate::models::webhook::WebhookPayload;
 use crate::services::account_service::AccountService;
+use crate::services::budget_service::BudgetService;
+use crate::services::exchange_service::ExchangeService;
+use crate::services::fee_service::FeeService;
+use crate::services::notification_service::NotificationService;
+use crate::services::outbox_service::record_event;
+use crate::services::webhook_service::WebhookService;
+use crate::utils::currency::{normalize_amount_to_scale, to_display_scale};
 use crate::utils::error::AppError;
-use rust_decimal::Decimal;
+use crate::utils::events::TransactionFeed;
+use crate::utils::money::convert;
+use chrono::{DateTime, Utc};
+use futures_util::stream::{BoxStream, StreamExt, TryStreamExt};
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::postgres::PgArguments;
+use sqlx::query::QueryAs;
 use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use std::collections::HashMap;
+use std::sync::Arc;
 use uuid::Uuid;
 
 /// Service for managing transactions between accounts
@@ -22,17 +73,98 @@ pub struct TransactionService {
     pool: PgPool,
     /// Account service for account-related operations
     pub account_service: AccountService,
+    /// Behavioural settings for transaction processing
+    config: TransactionConfig,
+    /// Sends receipts after transfers complete, when configured
+    notifications: Option<Arc<NotificationService>>,
+    /// Tells integrators about completed transactions, when configured
+    webhooks: Option<Arc<WebhookService>>,
+    /// Pushes completed transactions to live account event streams, when configured
+    feed: Option<TransactionFeed>,
+    /// Raises alerts when categorized spending exceeds a budget
+    budgets: BudgetService,
+    /// Converts transfers between accounts of different currencies
+    exchange: ExchangeService,
+    /// Computes the fees charged on transfers and withdrawals
+    fees: FeeService,
+    /// Name of this server instance, stored with each transaction's timings
+    instance_id: String,
 }
 
 impl TransactionService {
-    /// Creates a new transaction service with the given database pool and account service
-    pub fn new(pool: PgPool, account_service: AccountService) -> Self {
+    /// Creates a new transaction service with the given database pool, account service
+    /// and transaction settings
+    pub fn new(pool: PgPool, account_service: AccountService, config: TransactionConfig) -> Self {
         Self {
+            budgets: BudgetService::new(pool.clone()),
+            exchange: ExchangeService::new(pool.clone(), config.exchange_rate_ttl),
+            fees: FeeService::new(pool.clone())
+                .with_default_transfer_fee(config.transfer_fee_flat, config.transfer_fee_percent),
             pool,
             account_service,
+            config,
+            notifications: None,
+            webhooks: None,
+            feed: None,
+            instance_id: DiagnosticsConfig::default().instance_id,
+        }
+    }
+
+    /// Records `instance_id` as the instance that processed each transaction
+    pub fn with_instance_id(mut self, instance_id: String) -> Self {
+        self.instance_id = instance_id;
+        self
+    }
+
+    /// Sends transfer receipts through `notifications` once transfers commit
+    pub fn with_notifications(mut self, notifications: Arc<NotificationService>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// Posts completed transactions to the owners' webhooks through `webhooks`
+    pub fn with_webhooks(mut self, webhooks: Arc<WebhookService>) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// Publishes completed transactions to `feed` once they commit
+    pub fn with_feed(mut self, feed: TransactionFeed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+
+    /// Counts a committed transaction in the metrics, sends its webhooks in
+    /// the background and pushes it to the live event streams
+    fn announce(&self, transaction: &TransactionResponse) {
+        metrics().record_transaction(&transaction.transaction_type, &transaction.status);
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.spawn_transaction_completed(transaction);
+        }
+        if let Some(feed) = &self.feed {
+            feed.publish(transaction);
         }
     }
 
+    /// Records a `transaction.completed` event in the outbox, if the
+    /// transaction completed, in the database transaction booking it
+    async fn record_completed(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction: &TransactionResponse,
+    ) -> Result<(), AppError> {
+        if transaction.status != TransactionStatus::COMPLETED.to_string() {
+            return Ok(());
+        }
+        record_event(
+            tx,
+            TRANSACTION_COMPLETED,
+            transaction.id,
+            &WebhookPayload::transaction_completed(transaction),
+        )
+        .await
+    }
+
     /// Retrieves a transaction by its unique ID
     ///
     /// # Arguments
@@ -41,15 +173,16 @@ impl TransactionService {
     /// # Returns
     /// The transaction details wrapped in a TransactionResponse if found
     pub async fn get_transaction_by_id(&self, id: Uuid) -> Result<TransactionResponse, AppError> {
-        let transaction = sqlx::query_as!(
-            Transaction,
+        let transaction = sqlx::query_as::<_, TransactionDetails>(
             r#"
-            SELECT id, sender_account_id, receiver_account_id, amount as "amount: SqlxDecimal", currency, 
-                   transaction_type, status, description, created_at, updated_at
+            SELECT id, sender_account_id, receiver_account_id, amount, currency,
+                   transaction_type, status, description, created_at, updated_at,
+                   fee_amount, fee_currency,
+                   original_amount, original_currency, exchange_rate, external_reference, category
             FROM transactions WHERE id = $1
             "#,
-            id
         )
+        .bind(id)
         .fetch_optional(&self.pool)
         .await?
         .ok_or_else(|| AppError::NotFound(format!("Transaction with ID {} not found", id)))?;
@@ -57,37 +190,83 @@ impl TransactionService {
         Ok(TransactionResponse::from(transaction))
     }
 
+    /// Retrieves a transaction by the identifier an external processor gave it
+    ///
+    /// # Arguments
+    /// * `external_reference` - Reference supplied with the deposit or withdrawal
+    ///
+    /// # Returns
+    /// The transaction details, including the reference, if found
+    pub async fn get_transaction_by_external_reference(
+        &self,
+        external_reference: &str,
+    ) -> Result<TransactionResponse, AppError> {
+        let transaction = sqlx::query_as::<_, TransactionDetails>(
+            r#"
+            SELECT id, sender_account_id, receiver_account_id, amount, currency,
+                   transaction_type, status, description, created_at, updated_at,
+                   fee_amount, fee_currency,
+                   original_amount, original_currency, exchange_rate, external_reference, category
+            FROM transactions WHERE external_reference = $1
+            "#,
+        )
+        .bind(external_reference)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Transaction with external reference {} not found",
+                external_reference
+            ))
+        })?;
+
+        Ok(TransactionResponse::from(transaction))
+    }
+
     /// Gets all transactions associated with a specific account
     ///
     /// This will find transactions where the account is either the sender or receiver
     ///
     /// # Arguments
     /// * `account_id` - The UUID of the account to get transactions for
+    /// * `filter` - Creation time range, type, status, amount range and description
+    ///   text the transactions must match
+    /// * `before` - Optional cursor; only transactions strictly older than it are returned
     /// * `limit` - Optional limit on the number of transactions to return (defaults to 100)
     /// * `offset` - Optional offset for pagination (defaults to 0)
     ///
     /// # Returns
-    /// A vector of transaction responses, sorted by creation date (newest first)
+    /// A vector of transaction responses, sorted by creation date (newest first,
+    /// ties broken by ID), each with the account's `balance_after` it, or
+    /// `AppError::BadRequest` if one of the filter's ranges ends before it starts
+    ///
+    /// # Implementation Details
+    /// Paging with `before` (keyset pagination) is preferred over `offset`: it
+    /// neither skips nor repeats rows when transactions are created between pages.
+    ///
+    /// Running balances are anchored on the account's current balance, like
+    /// `AccountService::get_account_as_of`: a window sum over the account's whole
+    /// ledger backs out every balance-changing transaction newer than each row.
+    /// They are therefore right on any page and under any filter. Credits count
+    /// positive and debits negative; only completed and reversed transactions
+    /// moved money, and the sender of a converted transfer paid the original
+    /// amount.
     pub async fn get_transactions_by_account_id(
         &self,
         account_id: Uuid,
+        filter: &TransactionFilter,
+        before: Option<&TransactionCursor>,
         limit: Option<i64>,
         offset: Option<i64>,
     ) -> Result<Vec<TransactionResponse>, AppError> {
-        let transactions = sqlx::query_as!(
-            Transaction,
-            r#"
-            SELECT id, sender_account_id, receiver_account_id, amount as "amount: SqlxDecimal", currency, 
-                   transaction_type, status, description, created_at, updated_at
-            FROM transactions
-            WHERE sender_account_id = $1 OR receiver_account_id = $1
-            ORDER BY created_at DESC
-            LIMIT $2
-            OFFSET $3
-            "#,
+        filter.validate()?;
+
+        let transactions = account_transactions_query(
             account_id,
-            limit.unwrap_or(100),
-            offset.unwrap_or(0)
+            filter,
+            before,
+            Some(limit.unwrap_or(100)),
+            offset.unwrap_or(0),
         )
         .fetch_all(&self.pool)
         .await?;
@@ -98,493 +277,3805 @@ impl TransactionService {
             .collect())
     }
 
-    /// Generic transaction creation endpoint that routes to the appropriate
-    /// specialized transaction handler based on transaction type
+    /// Streams every transaction of an account matching `filter`, in the order
+    /// and with the running balances of `get_transactions_by_account_id`
+    ///
+    /// Rows are read as the stream is polled, so no page size applies. The
+    /// filter is expected to have passed `TransactionFilter::validate`.
+    pub fn stream_transactions_by_account_id(
+        &self,
+        account_id: Uuid,
+        filter: &TransactionFilter,
+    ) -> BoxStream<'_, Result<TransactionResponse, AppError>> {
+        account_transactions_query(account_id, filter, None, None, 0)
+            .fetch(&self.pool)
+            .map_ok(TransactionResponse::from)
+            .map_err(AppError::from)
+            .boxed()
+    }
+
+    /// Gets the transactions of all of a user's accounts as one history
+    ///
+    /// Each row names the user's account involved and whether money came into
+    /// it or left it. A transfer between two of the user's own accounts is
+    /// listed once from each side. The user's accounts are resolved in the same
+    /// query rather than looked up first.
     ///
     /// # Arguments
-    /// * `request` - The transaction request containing all necessary details
+    /// * `user_id` - The user whose transactions to list
+    /// * `limit` - Optional limit on the number of rows to return (defaults to 100)
+    /// * `offset` - Optional offset for pagination (defaults to 0)
+    /// * `filter` - Creation time range, type, status, amount range and description
+    ///   text the transactions must match
     ///
     /// # Returns
-    /// The created transaction response upon success
+    /// The matching rows, newest first, or `AppError::BadRequest` if one of the
+    /// filter's ranges ends before it starts
+    pub async fn get_transactions_by_user_id(
+        &self,
+        user_id: Uuid,
+        limit: Option<i64>,
+        offset: Option<i64>,
+        filter: &TransactionFilter,
+    ) -> Result<Vec<UserTransactionResponse>, AppError> {
+        filter.validate()?;
+
+        let transactions = sqlx::query_as::<_, UserTransaction>(
+            r#"
+            WITH mine AS (
+                SELECT t.id, t.sender_account_id, t.receiver_account_id, t.amount, t.currency,
+                       t.transaction_type, t.status, t.description, t.created_at, t.updated_at,
+                       a.id AS account_id, $2::text AS direction,
+                       t.original_amount, t.original_currency, t.exchange_rate
+                FROM transactions t
+                JOIN accounts a ON a.id = t.sender_account_id
+                WHERE a.user_id = $1
+                UNION ALL
+                SELECT t.id, t.sender_account_id, t.receiver_account_id, t.amount, t.currency,
+                       t.transaction_type, t.status, t.description, t.created_at, t.updated_at,
+                       a.id AS account_id, $3::text AS direction,
+                       t.original_amount, t.original_currency, t.exchange_rate
+                FROM transactions t
+                JOIN accounts a ON a.id = t.receiver_account_id
+                WHERE a.user_id = $1
+            )
+            SELECT id, sender_account_id, receiver_account_id, amount, currency,
+                   transaction_type, status, description, created_at, updated_at,
+                   account_id, direction, original_amount, original_currency, exchange_rate
+            FROM mine
+            WHERE ($4::timestamptz IS NULL OR created_at >= $4)
+              AND ($5::timestamptz IS NULL OR created_at < $5)
+              AND ($6::text IS NULL OR transaction_type = $6)
+              AND ($7::text IS NULL OR status = $7)
+              AND ($8::numeric IS NULL OR amount >= $8)
+              AND ($9::numeric IS NULL OR amount <= $9)
+              AND ($10::text IS NULL OR description ILIKE $10 ESCAPE '\')
+            ORDER BY created_at DESC, id DESC, direction DESC
+            LIMIT $11
+            OFFSET $12
+            "#,
+        )
+        .bind(user_id)
+        .bind(DIRECTION_OUTGOING)
+        .bind(DIRECTION_INCOMING)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.transaction_type.as_ref().map(|t| t.to_string()))
+        .bind(filter.status.as_ref().map(|s| s.to_string()))
+        .bind(filter.min_amount.map(SqlxDecimal))
+        .bind(filter.max_amount.map(SqlxDecimal))
+        .bind(filter.search_pattern())
+        .bind(limit.unwrap_or(100))
+        .bind(offset.unwrap_or(0))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(transactions
+            .into_iter()
+            .map(UserTransactionResponse::from)
+            .collect())
+    }
+
+    /// Counts the rows of a user's merged history that match the filter
     ///
-    /// # Implementation Note
-    /// This method acts as a facade that maps the generic request to specialized
-    /// transaction types (transfer, deposit, withdrawal) with appropriate validation.
-    pub async fn create_transaction(
+    /// Used alongside `get_transactions_by_user_id` to describe pagination.
+    pub async fn count_transactions_by_user_id(
         &self,
-        request: CreateTransactionRequest,
-    ) -> Result<TransactionResponse, AppError> {
-        // Convert the string transaction type to the appropriate enum variant
-        let transaction_type = match request.transaction_type.as_str() {
-            "TRANSFER" => TransactionType::TRANSFER,
-            "DEPOSIT" => TransactionType::DEPOSIT,
-            "WITHDRAWAL" => TransactionType::WITHDRAWAL,
-            _ => {
-                return Err(AppError::BadRequest(format!(
-                    "Invalid transaction type: {}",
-                    request.transaction_type
-                )))
-            }
-        };
+        user_id: Uuid,
+        filter: &TransactionFilter,
+    ) -> Result<i64, AppError> {
+        filter.validate()?;
 
-        // Route to the appropriate specialized handler based on transaction type
-        match transaction_type {
-            TransactionType::TRANSFER => {
-                // For transfers, both sender and receiver accounts are required
-                if request.sender_account_id.is_none() || request.receiver_account_id.is_none() {
-                    return Err(AppError::BadRequest(
-                        "Sender and receiver account IDs are required for transfers".to_string(),
-                    ));
-                }
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            WITH mine AS (
+                SELECT t.* FROM transactions t
+                JOIN accounts a ON a.id = t.sender_account_id
+                WHERE a.user_id = $1
+                UNION ALL
+                SELECT t.* FROM transactions t
+                JOIN accounts a ON a.id = t.receiver_account_id
+                WHERE a.user_id = $1
+            )
+            SELECT COUNT(*) FROM mine
+            WHERE ($2::timestamptz IS NULL OR created_at >= $2)
+              AND ($3::timestamptz IS NULL OR created_at < $3)
+              AND ($4::text IS NULL OR transaction_type = $4)
+              AND ($5::text IS NULL OR status = $5)
+              AND ($6::numeric IS NULL OR amount >= $6)
+              AND ($7::numeric IS NULL OR amount <= $7)
+              AND ($8::text IS NULL OR description ILIKE $8 ESCAPE '\')
+            "#,
+        )
+        .bind(user_id)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.transaction_type.as_ref().map(|t| t.to_string()))
+        .bind(filter.status.as_ref().map(|s| s.to_string()))
+        .bind(filter.min_amount.map(SqlxDecimal))
+        .bind(filter.max_amount.map(SqlxDecimal))
+        .bind(filter.search_pattern())
+        .fetch_one(&self.pool)
+        .await?;
 
-                let transfer_request = TransferRequest {
-                    sender_account_id: request.sender_account_id.unwrap(),
-                    receiver_account_id: request.receiver_account_id.unwrap(),
-                    amount: request.amount,
-                    description: request.description,
-                };
+        Ok(count)
+    }
 
-                self.process_transfer(transfer_request).await
-            }
-            TransactionType::DEPOSIT => {
-                // For deposits, only the receiver account is required
-                if request.receiver_account_id.is_none() {
-                    return Err(AppError::BadRequest(
-                        "Receiver account ID is required for deposits".to_string(),
-                    ));
-                }
+    /// Lists every transaction in the system, regardless of who owns the accounts
+    ///
+    /// Meant for administrators; callers must check the role themselves.
+    ///
+    /// # Arguments
+    /// * `limit` - Optional limit on the number of transactions to return (defaults to 100)
+    /// * `offset` - Optional offset for pagination (defaults to 0)
+    /// * `filter` - Creation time range, type, status, amount range and description
+    ///   text the transactions must match
+    ///
+    /// # Returns
+    /// The matching transactions, newest first, or `AppError::BadRequest` if one
+    /// of the filter's ranges ends before it starts
+    pub async fn list_all_transactions(
+        &self,
+        limit: Option<i64>,
+        offset: Option<i64>,
+        filter: &TransactionFilter,
+    ) -> Result<Vec<TransactionResponse>, AppError> {
+        filter.validate()?;
 
-                let deposit_request = DepositRequest {
-                    account_id: request.receiver_account_id.unwrap(),
-                    amount: request.amount,
-                    description: request.description,
-                };
+        let transactions = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT id, sender_account_id, receiver_account_id, amount, currency,
+                   transaction_type, status, description, created_at, updated_at
+            FROM transactions
+            WHERE ($1::timestamptz IS NULL OR created_at >= $1)
+              AND ($2::timestamptz IS NULL OR created_at < $2)
+              AND ($3::text IS NULL OR transaction_type = $3)
+              AND ($4::text IS NULL OR status = $4)
+              AND ($5::numeric IS NULL OR amount >= $5)
+              AND ($6::numeric IS NULL OR amount <= $6)
+              AND ($7::text IS NULL OR description ILIKE $7 ESCAPE '\')
+            ORDER BY created_at DESC, id DESC
+            LIMIT $8
+            OFFSET $9
+            "#,
+        )
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.transaction_type.as_ref().map(|t| t.to_string()))
+        .bind(filter.status.as_ref().map(|s| s.to_string()))
+        .bind(filter.min_amount.map(SqlxDecimal))
+        .bind(filter.max_amount.map(SqlxDecimal))
+        .bind(filter.search_pattern())
+        .bind(limit.unwrap_or(100))
+        .bind(offset.unwrap_or(0))
+        .fetch_all(&self.pool)
+        .await?;
 
-                self.process_deposit(deposit_request).await
-            }
-            TransactionType::WITHDRAWAL => {
-                // For withdrawals, only the sender account is required
-                if request.sender_account_id.is_none() {
-                    return Err(AppError::BadRequest(
-                        "Sender account ID is required for withdrawals".to_string(),
-                    ));
-                }
+        Ok(transactions
+            .into_iter()
+            .map(TransactionResponse::from)
+            .collect())
+    }
 
-                let withdrawal_request = WithdrawalRequest {
-                    account_id: request.sender_account_id.unwrap(),
-                    amount: request.amount,
-                    description: request.description,
-                };
+    /// Counts every transaction in the system that matches the filter
+    ///
+    /// Used alongside `list_all_transactions` to describe pagination.
+    pub async fn count_all_transactions(
+        &self,
+        filter: &TransactionFilter,
+    ) -> Result<i64, AppError> {
+        filter.validate()?;
 
-                self.process_withdrawal(withdrawal_request).await
-            }
-        }
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM transactions
+            WHERE ($1::timestamptz IS NULL OR created_at >= $1)
+              AND ($2::timestamptz IS NULL OR created_at < $2)
+              AND ($3::text IS NULL OR transaction_type = $3)
+              AND ($4::text IS NULL OR status = $4)
+              AND ($5::numeric IS NULL OR amount >= $5)
+              AND ($6::numeric IS NULL OR amount <= $6)
+              AND ($7::text IS NULL OR description ILIKE $7 ESCAPE '\')
+            "#,
+        )
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.transaction_type.as_ref().map(|t| t.to_string()))
+        .bind(filter.status.as_ref().map(|s| s.to_string()))
+        .bind(filter.min_amount.map(SqlxDecimal))
+        .bind(filter.max_amount.map(SqlxDecimal))
+        .bind(filter.search_pattern())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
     }
 
-    /// Processes a transfer between two accounts
+    /// Searches an account's transactions by description
     ///
     /// # Arguments
-    /// * `request` - Transfer request containing sender and receiver accounts, amount, and description
+    /// * `account_id` - The UUID of the account to search
+    /// * `query` - Text the description must contain, ignoring case; `%` and `_`
+    ///   match themselves rather than acting as wildcards
+    /// * `limit` - Optional limit on the number of transactions to return (defaults to 100)
+    /// * `offset` - Optional offset for pagination (defaults to 0)
     ///
     /// # Returns
-    /// The completed transaction response upon success
-    ///
-    /// # Implementation Details
-    /// This method:
-    /// 1. Begins a database transaction for atomicity
-    /// 2. Validates both accounts exist and are different
-    /// 3. Checks that both accounts use the same currency
-    /// 4. Verifies the sender has sufficient funds
-    /// 5. Creates a pending transaction record
-    /// 6. Updates both account balances
-    /// 7. Marks the transaction as completed
-    /// 8. Commits the database transaction
+    /// The matching transactions, newest first
+    pub async fn search_transactions(
+        &self,
+        account_id: Uuid,
+        query: &str,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<TransactionResponse>, AppError> {
+        let filter = TransactionFilter {
+            search: Some(query.to_string()),
+            ..TransactionFilter::default()
+        };
+        self.get_transactions_by_account_id(account_id, &filter, None, limit, offset)
+            .await
+    }
+
+    /// Streams every transaction created within `[start, end)`, oldest first
     ///
-    /// If any step fails, the entire database transaction is rolled back.
-    pub async fn process_transfer(
+    /// Rows are read from a single cursor as the stream is polled rather than
+    /// loaded up front, so the whole ledger can be exported in constant memory.
+    /// Either bound may be omitted.
+    pub fn stream_transactions(
         &self,
-        request: TransferRequest,
-    ) -> Result<TransactionResponse, AppError> {
-        // Start a database transaction to ensure atomicity and isolation
-        // This ensures that either all operations succeed or all fail together
-        let mut tx = self.pool.begin().await?;
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> BoxStream<'_, Result<Transaction, AppError>> {
+        sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT id, sender_account_id, receiver_account_id, amount, currency,
+                   transaction_type, status, description, created_at, updated_at
+            FROM transactions
+            WHERE ($1::timestamptz IS NULL OR created_at >= $1)
+              AND ($2::timestamptz IS NULL OR created_at < $2)
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch(&self.pool)
+        .map_err(AppError::from)
+        .boxed()
+    }
 
-        // Validate accounts exist and are different - prevents self-transfers
-        // which could be used for fraudulent activity or money laundering
-        if request.sender_account_id == request.receiver_account_id {
-            return Err(AppError::BadRequest(
-                "Cannot transfer to the same account".to_string(),
-            ));
-        }
+    /// Retrieves a transaction together with the client that initiated it
+    ///
+    /// The origin is sensitive and must only be exposed to administrators.
+    ///
+    /// # Arguments
+    /// * `id` - The UUID of the transaction to retrieve
+    pub async fn get_transaction_with_origin(
+        &self,
+        id: Uuid,
+    ) -> Result<AdminTransactionResponse, AppError> {
+        let transaction = self.get_transaction_by_id(id).await?;
 
-        // Lock the sender account for the duration of this transaction
-        // FOR UPDATE clause ensures exclusive access to prevent race conditions
-        // This is critical to prevent double-spending
-        let sender_account = sqlx::query!(
+        let (ip_address, user_agent): (Option<String>, Option<String>) = sqlx::query_as(
             r#"
-            SELECT id, currency, balance FROM accounts WHERE id = $1 FOR UPDATE
+            SELECT ip_address, user_agent FROM transactions WHERE id = $1
             "#,
-            request.sender_account_id
         )
-        .fetch_optional(&mut *tx)
-        .await?
-        .ok_or_else(|| {
-            AppError::NotFound(format!(
-                "Sender account with ID {} not found",
-                request.sender_account_id
-            ))
-        })?;
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
 
-        // Lock the receiver account for the duration of this transaction
-        // FOR UPDATE clause again for race condition prevention
-        let receiver_account = sqlx::query!(
+        let timings = sqlx::query_as::<_, TransactionTimings>(
             r#"
-            SELECT id, currency FROM accounts WHERE id = $1 FOR UPDATE
+            SELECT request_id, instance_id, received_at, lock_acquired_at, committed_at
+            FROM transaction_timings WHERE transaction_id = $1
             "#,
-            request.receiver_account_id
         )
-        .fetch_optional(&mut *tx)
-        .await?
-        .ok_or_else(|| {
-            AppError::NotFound(format!(
-                "Receiver account with ID {} not found",
-                request.receiver_account_id
-            ))
-        })?;
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
 
-        // Ensure matching currencies - prevents currency conversion issues
-        // We don't handle currency exchange in this system
-        if sender_account.currency != receiver_account.currency {
-            return Err(AppError::BadRequest(
-                "Currency mismatch between accounts".to_string(),
-            ));
-        }
+        Ok(AdminTransactionResponse {
+            transaction,
+            ip_address,
+            user_agent,
+            timings,
+        })
+    }
 
-        // Ensure sufficient balance in the sender account
-        // Get balance as string and convert to Decimal for precise comparison
-        // We use a raw query with format! to handle our custom SqlxDecimal type
-        let query = format!(
-            "SELECT balance::TEXT FROM accounts WHERE id = '{}' FOR UPDATE",
-            request.sender_account_id
-        );
+    /// Counts the transactions of a specific account that match `filter`
+    ///
+    /// Used alongside `get_transactions_by_account_id` to describe pagination.
+    ///
+    /// # Arguments
+    /// * `account_id` - The UUID of the account to count transactions for
+    /// * `filter` - Creation time range, type, status, amount range and description
+    ///   text the transactions must match
+    pub async fn count_transactions_by_account_id(
+        &self,
+        account_id: Uuid,
+        filter: &TransactionFilter,
+    ) -> Result<i64, AppError> {
+        filter.validate()?;
 
-        let row = sqlx::query(&query).fetch_one(&mut *tx).await?;
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM transactions
+            WHERE (sender_account_id = $1 OR receiver_account_id = $1)
+              AND ($2::timestamptz IS NULL OR created_at >= $2)
+              AND ($3::timestamptz IS NULL OR created_at < $3)
+              AND ($4::text IS NULL OR transaction_type = $4)
+              AND ($5::text IS NULL OR status = $5)
+              AND ($6::numeric IS NULL OR amount >= $6)
+              AND ($7::numeric IS NULL OR amount <= $7)
+              AND ($8::text IS NULL OR description ILIKE $8 ESCAPE '\')
+            "#,
+        )
+        .bind(account_id)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.transaction_type.as_ref().map(|t| t.to_string()))
+        .bind(filter.status.as_ref().map(|s| s.to_string()))
+        .bind(filter.min_amount.map(SqlxDecimal))
+        .bind(filter.max_amount.map(SqlxDecimal))
+        .bind(filter.search_pattern())
+        .fetch_one(&self.pool)
+        .await?;
 
-        // Parse the balance text to a Decimal for precise financial calculations
-        // ZERO is the fallback in case of parsing error
-        let sender_balance: Decimal = sqlx::Row::get::<&str, _>(&row, "balance")
-            .parse()
-            .unwrap_or(Decimal::ZERO);
+        Ok(count)
+    }
 
-        // Ensure the sender has enough funds for the transfer
-        if sender_balance < request.amount {
-            return Err(AppError::BadRequest("Insufficient funds".to_string()));
+    /// Totals an account's transactions by type and status
+    ///
+    /// # Arguments
+    /// * `account_id` - The account to summarize
+    /// * `from` - Optional earliest creation time to include
+    /// * `to` - Optional creation time from which on transactions are excluded
+    ///
+    /// # Returns
+    /// One entry per type and status that occurred in the period, ordered by
+    /// type and then status, or `AppError::BadRequest` if `to` is before `from`
+    ///
+    /// # Implementation Details
+    /// A single `GROUP BY` over the account's transactions computes every
+    /// total, so the cost doesn't grow with the number of groups. Amounts
+    /// are counted as in the running balances of
+    /// `get_transactions_by_account_id`: the sender of a converted transfer
+    /// paid the original amount.
+    pub async fn get_transaction_totals(
+        &self,
+        account_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<TransactionTotals>, AppError> {
+        TransactionFilter {
+            from,
+            to,
+            ..TransactionFilter::default()
         }
+        .validate()?;
 
-        // Create a transaction record in PENDING state - this serves as an audit trail
-        // We use a UUID v4 for a globally unique transaction identifier
-        let transaction_id = Uuid::new_v4();
-        let _transaction = self
-            .create_transaction_record(
+        let totals = sqlx::query_as::<_, TransactionTotalsRow>(
+            r#"
+            SELECT transaction_type, status, COUNT(*) AS count,
+                   COALESCE(SUM(amount) FILTER (WHERE receiver_account_id = $1), 0)
+                       AS amount_in,
+                   COALESCE(SUM(COALESCE(original_amount, amount))
+                                FILTER (WHERE sender_account_id = $1), 0)
+                       AS amount_out
+            FROM transactions
+            WHERE (sender_account_id = $1 OR receiver_account_id = $1)
+              AND ($2::timestamptz IS NULL OR created_at >= $2)
+              AND ($3::timestamptz IS NULL OR created_at < $3)
+            GROUP BY transaction_type, status
+            ORDER BY transaction_type, status
+            "#,
+        )
+        .bind(account_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(totals.into_iter().map(TransactionTotals::from).collect())
+    }
+
+    /// Totals an account's completed outgoing transactions by category
+    ///
+    /// # Arguments
+    /// * `account_id` - The account whose spending to total
+    /// * `from` - Optional earliest creation time to include
+    /// * `to` - Optional creation time from which on transactions are excluded
+    ///
+    /// # Returns
+    /// One entry per category, largest total first, with the uncategorized
+    /// transactions, fees among them, under `None`; or `AppError::BadRequest`
+    /// if `to` is before `from`
+    ///
+    /// # Implementation Details
+    /// Amounts are counted as budgets count them: the sender of a converted
+    /// transfer spent the original amount, in the account's currency.
+    pub async fn get_spending_by_category(
+        &self,
+        account_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<CategorySpending>, AppError> {
+        TransactionFilter {
+            from,
+            to,
+            ..TransactionFilter::default()
+        }
+        .validate()?;
+
+        let spending = sqlx::query_as::<_, CategorySpendingRow>(
+            r#"
+            SELECT category, COUNT(*) AS count,
+                   SUM(COALESCE(original_amount, amount)) AS total
+            FROM transactions
+            WHERE sender_account_id = $1
+              AND status = $2
+              AND ($3::timestamptz IS NULL OR created_at >= $3)
+              AND ($4::timestamptz IS NULL OR created_at < $4)
+            GROUP BY category
+            ORDER BY total DESC, category NULLS LAST
+            "#,
+        )
+        .bind(account_id)
+        .bind(TransactionStatus::COMPLETED.to_string())
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(spending.into_iter().map(CategorySpending::from).collect())
+    }
+
+    /// Builds an account's statement for any period
+    ///
+    /// # Arguments
+    /// * `account_id` - The account to report on
+    /// * `from` - Earliest creation time to include
+    /// * `to` - Creation time from which on transactions are excluded
+    ///
+    /// # Returns
+    /// The opening balance, the transactions that moved money in the period,
+    /// oldest first, each with the balance right after it, the credit and
+    /// debit totals and the closing balance; or `AppError::BadRequest` if `to`
+    /// is before `from`
+    ///
+    /// # Implementation Details
+    /// Unlike `AccountService::get_statement`, balances are worked out
+    /// forwards. The opening balance is that of the latest balance snapshot of
+    /// a transaction created before `from`. Without one, e.g. for history older
+    /// than the snapshots, the completed and reversed transactions before
+    /// `from` are replayed from zero. The period's transactions are then
+    /// applied to it one by one, so the closing balance is always the opening
+    /// balance plus the signed amounts listed. Amounts are signed as in
+    /// `get_transactions_by_account_id`: the sender of a converted transfer
+    /// paid the original amount. Both reads run in one read-only repeatable
+    /// read transaction, so they agree even while money moves.
+    pub async fn generate_statement(
+        &self,
+        account_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<AccountStatement, AppError> {
+        TransactionFilter {
+            from: Some(from),
+            to: Some(to),
+            ..TransactionFilter::default()
+        }
+        .validate()?;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *tx)
+            .await?;
+        let (currency, opening_balance) = sqlx::query_as::<_, (String, SqlxDecimal)>(
+            r#"
+            SELECT a.currency,
+                   COALESCE(
+                       (SELECT s.balance
+                        FROM account_balance_snapshots s
+                        JOIN transactions t ON t.id = s.transaction_id
+                        WHERE s.account_id = $1 AND t.created_at < $2
+                        ORDER BY s.created_at DESC, s.id DESC
+                        LIMIT 1),
+                       (SELECT SUM(CASE WHEN receiver_account_id = $1 THEN amount
+                                        ELSE -COALESCE(original_amount, amount) END)
+                        FROM transactions
+                        WHERE (sender_account_id = $1 OR receiver_account_id = $1)
+                          AND status IN ('COMPLETED', 'REVERSED')
+                          AND created_at < $2),
+                       0
+                   ) AS opening_balance
+            FROM accounts a
+            WHERE a.id = $1
+            "#,
+        )
+        .bind(account_id)
+        .bind(from)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Account with ID {} not found", account_id)))?;
+        let rows = sqlx::query_as::<_, TransactionDetails>(
+            r#"
+            SELECT id, sender_account_id, receiver_account_id, amount, currency,
+                   transaction_type, status, description, created_at, updated_at,
+                   fee_amount, fee_currency,
+                   original_amount, original_currency, exchange_rate, external_reference, category
+            FROM transactions
+            WHERE (sender_account_id = $1 OR receiver_account_id = $1)
+              AND status IN ('COMPLETED', 'REVERSED')
+              AND created_at >= $2 AND created_at < $3
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(account_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        let opening_balance: Decimal = opening_balance.into();
+        let mut balance = opening_balance;
+        let mut total_credits = Decimal::ZERO;
+        let mut total_debits = Decimal::ZERO;
+        let mut transactions = Vec::with_capacity(rows.len());
+        for row in rows {
+            let transaction = &row.transaction;
+            if transaction.receiver_account_id == Some(account_id) {
+                let credited: Decimal = transaction.amount.into();
+                total_credits += credited;
+                balance += credited;
+            } else {
+                let debited: Decimal = row
+                    .conversion
+                    .original_amount
+                    .unwrap_or(transaction.amount)
+                    .into();
+                total_debits += debited;
+                balance -= debited;
+            }
+            transactions.push(TransactionResponse {
+                balance_after: Some(balance),
+                ..TransactionResponse::from(row)
+            });
+        }
+
+        Ok(AccountStatement {
+            account_id,
+            currency,
+            year: None,
+            month: None,
+            period_start: from,
+            period_end: to,
+            opening_balance,
+            closing_balance: balance,
+            total_credits,
+            total_debits,
+            transactions,
+        })
+    }
+
+    /// Generic transaction creation endpoint that routes to the appropriate
+    /// specialized transaction handler based on transaction type
+    ///
+    /// # Arguments
+    /// * `request` - The transaction request containing all necessary details
+    ///
+    /// # Returns
+    /// The created transaction response upon success
+    ///
+    /// # Implementation Note
+    /// This method acts as a facade that maps the generic request to specialized
+    /// transaction types (transfer, deposit, withdrawal) with appropriate validation.
+    /// The currency always comes from the accounts involved; the request's
+    /// `currency` is only cross-checked against it.
+    pub async fn create_transaction(
+        &self,
+        request: CreateTransactionRequest,
+        context: &RequestContext,
+    ) -> Result<TransactionResponse, AppError> {
+        self.create(request, context, None).await
+    }
+
+    /// Creates a transaction at most once per idempotency key
+    ///
+    /// Behaves like `create_transaction`. When the key was already used for the
+    /// same request, the stored response is returned and no money moves.
+    pub async fn create_transaction_idempotent(
+        &self,
+        request: CreateTransactionRequest,
+        context: &RequestContext,
+        idempotency: Option<&IdempotencyKey>,
+    ) -> Result<TransactionResponse, AppError> {
+        self.create(request, context, idempotency).await
+    }
+
+    async fn create(
+        &self,
+        request: CreateTransactionRequest,
+        context: &RequestContext,
+        idempotency: Option<&IdempotencyKey>,
+    ) -> Result<TransactionResponse, AppError> {
+        // Convert the string transaction type to the appropriate enum variant
+        let transaction_type = match request.transaction_type.as_str() {
+            "TRANSFER" => TransactionType::TRANSFER,
+            "DEPOSIT" => TransactionType::DEPOSIT,
+            "WITHDRAWAL" => TransactionType::WITHDRAWAL,
+            "FEE" => TransactionType::FEE,
+            _ => {
+                return Err(AppError::BadRequest(format!(
+                    "Invalid transaction type: {}",
+                    request.transaction_type
+                )))
+            }
+        };
+
+        // The accounts, not the request, decide the currency: funds are booked in
+        // the currency of the account they leave, or arrive in for deposits
+        let currency_account_id = match transaction_type {
+            TransactionType::DEPOSIT => request.receiver_account_id,
+            _ => request.sender_account_id,
+        };
+        if let Some(account_id) = currency_account_id {
+            self.check_request_currency(request.currency.as_deref(), account_id)
+                .await?;
+        }
+
+        // Route to the appropriate specialized handler based on transaction type
+        match transaction_type {
+            TransactionType::TRANSFER => {
+                // For transfers, both sender and receiver accounts are required
+                if request.sender_account_id.is_none() || request.receiver_account_id.is_none() {
+                    return Err(AppError::BadRequest(
+                        "Sender and receiver account IDs are required for transfers".to_string(),
+                    ));
+                }
+
+                let transfer_request = TransferRequest {
+                    sender_account_id: request.sender_account_id.unwrap(),
+                    receiver_account_id: request.receiver_account_id.unwrap(),
+                    amount: request.amount,
+                    description: request.description,
+                    category: request.category,
+                    allow_duplicate: request.allow_duplicate,
+                };
+
+                self.transfer(transfer_request, context, idempotency).await
+            }
+            TransactionType::DEPOSIT => {
+                // For deposits, only the receiver account is required
+                if request.receiver_account_id.is_none() {
+                    return Err(AppError::BadRequest(
+                        "Receiver account ID is required for deposits".to_string(),
+                    ));
+                }
+
+                // The currency was already checked against the account above
+                let deposit_request = DepositRequest {
+                    account_id: request.receiver_account_id.unwrap(),
+                    amount: request.amount,
+                    currency: None,
+                    exchange_rate: None,
+                    description: request.description,
+                    category: request.category,
+                    external_reference: None,
+                };
+
+                self.book_deposit(deposit_request, false, context, idempotency)
+                    .await
+            }
+            TransactionType::WITHDRAWAL => {
+                // For withdrawals, only the sender account is required
+                if request.sender_account_id.is_none() {
+                    return Err(AppError::BadRequest(
+                        "Sender account ID is required for withdrawals".to_string(),
+                    ));
+                }
+
+                let withdrawal_request = WithdrawalRequest {
+                    account_id: request.sender_account_id.unwrap(),
+                    amount: request.amount,
+                    description: request.description,
+                    category: request.category,
+                    destination_id: None,
+                    external_reference: None,
+                };
+
+                self.withdraw(withdrawal_request, context, idempotency)
+                    .await
+            }
+            TransactionType::FEE => Err(AppError::BadRequest(
+                "Fees are only booked alongside the transfer they are charged on".to_string(),
+            )),
+        }
+    }
+
+    /// Cross-checks the currency of a generic transaction request against the
+    /// account whose currency the transaction is booked in
+    ///
+    /// A requested currency that differs is rejected, unless
+    /// `ignore_request_currency` is enabled; an omitted one is always fine.
+    async fn check_request_currency(
+        &self,
+        requested: Option<&str>,
+        account_id: Uuid,
+    ) -> Result<(), AppError> {
+        let Some(requested) = requested else {
+            return Ok(());
+        };
+        if self.config.ignore_request_currency {
+            return Ok(());
+        }
+
+        let account = self.account_service.get_account_by_id(account_id).await?;
+
+        if !requested.eq_ignore_ascii_case(&account.currency) {
+            return Err(AppError::BadRequest(format!(
+                "Transaction currency {} does not match account currency {}",
+                requested, account.currency
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Processes a transfer between two accounts
+    ///
+    /// # Arguments
+    /// * `request` - Transfer request containing sender and receiver accounts, amount, and description
+    /// * `context` - Client that initiated the transfer, stored for fraud investigations
+    ///
+    /// # Returns
+    /// The completed transaction response upon success
+    ///
+    /// # Implementation Details
+    /// This method:
+    /// 1. Begins a database transaction for atomicity
+    /// 2. Validates both accounts exist and are different
+    /// 3. Normalizes the amount to the currency's scale; when the accounts use
+    ///    different currencies, converts it at the rate from `ExchangeService`,
+    ///    rounding half-even to the receiver's balance scale (`BadRequest` when
+    ///    no rate is known)
+    /// 4. Checks both owners are email-verified when the amount exceeds the
+    ///    configured `verified_transfer_threshold`, and the amount against the
+    ///    sender's per-transaction and daily limits
+    /// 5. Verifies the sender has sufficient funds not reserved by active holds
+    ///    for the amount plus the configured transfer fee
+    /// 6. Creates a pending transaction record, with the fee in `fee_amount`
+    /// 7. Debits the sender the amount plus the fee and credits the receiver
+    ///    the amount
+    /// 8. Marks the transaction as completed
+    /// 9. Commits the database transaction
+    /// 10. Queues email receipts for large transfers, without waiting for them
+    /// 11. Checks the sender's budgets if the transfer has a category
+    ///
+    /// If any step fails, the entire database transaction is rolled back.
+    pub async fn process_transfer(
+        &self,
+        request: TransferRequest,
+        context: &RequestContext,
+    ) -> Result<TransactionResponse, AppError> {
+        self.transfer(request, context, None).await
+    }
+
+    /// Processes a transfer at most once per idempotency key
+    ///
+    /// Behaves like `process_transfer`. When the key was already used for the
+    /// same request, the stored response is returned and no money moves; see
+    /// `claim_idempotency_key`.
+    pub async fn process_transfer_idempotent(
+        &self,
+        request: TransferRequest,
+        context: &RequestContext,
+        idempotency: Option<&IdempotencyKey>,
+    ) -> Result<TransactionResponse, AppError> {
+        self.transfer(request, context, idempotency).await
+    }
+
+    /// Runs every check `process_transfer` makes, without booking anything
+    ///
+    /// # Arguments
+    /// * `request` - The transfer to check
+    ///
+    /// # Returns
+    /// Each check and whether the transfer passed it, in the order a transfer
+    /// runs them, with what the sender would be debited once that is known
+    ///
+    /// # Implementation Details
+    /// The checks are the helpers `process_transfer` calls, run in a database
+    /// transaction that is rolled back. Nothing is locked: the accounts and the
+    /// balance are read with plain SELECTs, so a validation never waits for
+    /// transfers in flight, and a transfer may still fail if they change the
+    /// balance first. A check failing with a client error is reported as
+    /// FAILED, and the checks needing its result as SKIPPED; server errors
+    /// fail the validation.
+    pub async fn validate_transfer(
+        &self,
+        request: &TransferRequest,
+    ) -> Result<TransferValidation, AppError> {
+        let sender_account_id = request.sender_account_id;
+        let receiver_account_id = request.receiver_account_id;
+        let mut checks = TransferChecks::default();
+        let mut tx = self.pool.begin().await?;
+
+        let positive = checks
+            .record("amount", Self::ensure_positive_amount(request.amount))?
+            .is_some();
+        if let Some(category) = &request.category {
+            checks.record("category", normalize_category(category))?;
+        }
+        if !request.allow_duplicate {
+            let duplicate = self
+                .ensure_not_duplicate(&mut tx, request, RowLock::None)
+                .await;
+            checks.record("duplicate", duplicate)?;
+        }
+        checks.record(
+            "distinct_accounts",
+            Self::ensure_distinct_accounts(sender_account_id, receiver_account_id),
+        )?;
+        let sender = self
+            .transfer_account_currency(&mut tx, sender_account_id, "Sender", RowLock::None)
+            .await;
+        let sender_currency = checks.record("sender_account", sender)?;
+        let receiver = self
+            .transfer_account_currency(&mut tx, receiver_account_id, "Receiver", RowLock::None)
+            .await;
+        let receiver_currency = checks.record("receiver_account", receiver)?;
+
+        let (Some(sender_currency), Some(receiver_currency)) = (sender_currency, receiver_currency)
+        else {
+            checks.skip(&[
+                "accounts_active",
+                "amount_conversion",
+                "verified_owners",
+                "limits",
+                "sufficient_funds",
+            ]);
+            tx.rollback().await?;
+            return Ok(checks.finish(None));
+        };
+        let active = self
+            .ensure_accounts_active(&mut tx, &[sender_account_id, receiver_account_id])
+            .await;
+        checks.record("accounts_active", active)?;
+
+        let amounts = if positive {
+            let amounts = self
+                .transfer_amounts(
+                    &mut tx,
+                    sender_account_id,
+                    &sender_currency,
+                    receiver_account_id,
+                    &receiver_currency,
+                    request.amount,
+                )
+                .await;
+            checks.record("amount_conversion", amounts)?
+        } else {
+            checks.skip(&["amount_conversion"]);
+            None
+        };
+        let Some((amount, _)) = amounts else {
+            checks.skip(&["verified_owners", "limits", "sufficient_funds"]);
+            tx.rollback().await?;
+            return Ok(checks.finish(None));
+        };
+
+        let verified = self
+            .ensure_transfer_verified(&mut tx, sender_account_id, receiver_account_id, amount)
+            .await;
+        checks.record("verified_owners", verified)?;
+        let limits = self
+            .check_account_limits(
                 &mut tx,
-                transaction_id,
-                Some(request.sender_account_id),
-                Some(request.receiver_account_id),
-                request.amount,
-                sender_account.currency.clone(),
-                TransactionType::TRANSFER.to_string(),
-                request.description,
+                sender_account_id,
+                amount,
+                TransactionType::TRANSFER,
+            )
+            .await;
+        checks.record("limits", limits)?;
+        let fee = self
+            .fee_for(
+                &mut tx,
+                TransactionType::TRANSFER,
+                sender_account_id,
+                &sender_currency,
+                amount,
             )
             .await?;
+        let funds = self
+            .ensure_available_funds(&mut tx, sender_account_id, amount + fee, RowLock::None)
+            .await;
+        checks.record("sufficient_funds", funds)?;
 
-        // Update sender balance by REDUCING it by the transfer amount
-        // Note the negative amount to indicate funds leaving the account
-        self.update_account_balance(&mut tx, request.sender_account_id, -request.amount)
-            .await?;
+        tx.rollback().await?;
+        Ok(checks.finish(Some((amount, fee, sender_currency))))
+    }
 
-        // Update receiver balance by INCREASING it by the transfer amount
-        self.update_account_balance(&mut tx, request.receiver_account_id, request.amount)
-            .await?;
+    async fn transfer(
+        &self,
+        request: TransferRequest,
+        context: &RequestContext,
+        idempotency: Option<&IdempotencyKey>,
+    ) -> Result<TransactionResponse, AppError> {
+        Self::ensure_positive_amount(request.amount)?;
+        let category = request
+            .category
+            .as_deref()
+            .map(normalize_category)
+            .transpose()?;
+        let mut milestones = Milestones::start(context.received_at);
 
-        // Update transaction status to COMPLETED now that both accounts are updated
-        // This final state indicates the successful completion of the transfer
-        let updated_transaction = self
-            .update_transaction_status(
+        // Start a database transaction to ensure atomicity and isolation
+        // This ensures that either all operations succeed or all fail together
+        let mut tx = self.pool.begin().await?;
+
+        // A retried request gets its original response and books nothing
+        if let Some(stored) = self.claim_idempotency_key(&mut tx, idempotency).await? {
+            return Ok(stored);
+        }
+        if !request.allow_duplicate {
+            self.ensure_not_duplicate(&mut tx, &request, RowLock::ForUpdate)
+                .await?;
+        }
+
+        let (transaction, fee, conversion) = self
+            .transfer_between_accounts(
                 &mut tx,
-                transaction_id,
-                TransactionStatus::COMPLETED.to_string(),
+                request.sender_account_id,
+                request.receiver_account_id,
+                request.amount,
+                request.description,
+                category.as_deref(),
+                context,
+                &mut milestones,
             )
             .await?;
 
+        let response = TransactionResponse::from(transaction)
+            .with_conversion(conversion)
+            .with_fee(fee)
+            .with_category(category.clone());
+        self.store_idempotent_response(&mut tx, idempotency, &response)
+            .await?;
+        self.record_completed(&mut tx, &response).await?;
+
         // Commit the database transaction to persist all changes atomically
         // If any step above failed, the transaction would be rolled back automatically
         tx.commit().await?;
+        self.record_timings(&[response.id], milestones, context)
+            .await;
+
+        // Receipts are sent in the background; email problems never fail a transfer
+        if let Some(notifications) = &self.notifications {
+            notifications.spawn_transfer_receipts(&response);
+        }
+        self.announce(&response);
+        self.check_budgets(&response, category.as_deref()).await;
 
         // Return the transaction details to the caller
-        Ok(TransactionResponse::from(updated_transaction))
+        Ok(response)
+    }
+
+    /// Helper function to move funds between two accounts
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use; the caller commits it
+    /// * `sender_account_id` - ID of the account to debit
+    /// * `receiver_account_id` - ID of the account to credit
+    /// * `amount` - Amount requested by the client
+    /// * `description` - Optional transaction description
+    /// * `category` - Optional normalized spending category
+    /// * `context` - Client that initiated the transfer
+    /// * `milestones` - Timings of the request, noting when the accounts are locked
+    ///
+    /// # Returns
+    /// The completed TRANSFER transaction record
+    ///
+    /// # Implementation Details
+    /// Performs steps 2 to 8 of `process_transfer`. The sender must be able to
+    /// cover the amount and the transfer fee from its available balance, i.e.
+    /// net of active holds.
+    ///
+    /// Returns the completed transfer, the fee charged to the sender, and what
+    /// the sender was debited when the amount was converted.
+    #[allow(clippy::too_many_arguments)]
+    async fn transfer_between_accounts(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        sender_account_id: Uuid,
+        receiver_account_id: Uuid,
+        amount: Decimal,
+        description: Option<String>,
+        category: Option<&str>,
+        context: &RequestContext,
+        milestones: &mut Milestones,
+    ) -> Result<(Transaction, Decimal, TransactionConversion), AppError> {
+        Self::ensure_distinct_accounts(sender_account_id, receiver_account_id)?;
+
+        // Lock both accounts for the duration of this transaction, sender first
+        // FOR UPDATE clause ensures exclusive access to prevent race conditions
+        // This is critical to prevent double-spending
+        let sender_currency = self
+            .transfer_account_currency(tx, sender_account_id, "Sender", RowLock::ForUpdate)
+            .await?;
+        let receiver_currency = self
+            .transfer_account_currency(tx, receiver_account_id, "Receiver", RowLock::ForUpdate)
+            .await?;
+        milestones.lock_acquired();
+        self.ensure_accounts_active(tx, &[sender_account_id, receiver_account_id])
+            .await?;
+
+        let (amount, conversion) = self
+            .transfer_amounts(
+                tx,
+                sender_account_id,
+                &sender_currency,
+                receiver_account_id,
+                &receiver_currency,
+                amount,
+            )
+            .await?;
+        let credited = conversion.map_or(amount, |(credited, _)| credited);
+
+        self.ensure_transfer_verified(tx, sender_account_id, receiver_account_id, amount)
+            .await?;
+        self.check_account_limits(tx, sender_account_id, amount, TransactionType::TRANSFER)
+            .await?;
+        let fee = self
+            .fee_for(
+                tx,
+                TransactionType::TRANSFER,
+                sender_account_id,
+                &sender_currency,
+                amount,
+            )
+            .await?;
+        let debited = amount + fee;
+        self.ensure_available_funds(tx, sender_account_id, debited, RowLock::ForUpdate)
+            .await?;
+
+        // Create a transaction record in PENDING state - this serves as an audit trail
+        // We use a UUID v4 for a globally unique transaction identifier
+        // A converted transfer is recorded in the receiver's currency, with what
+        // the sender paid and the rate kept alongside
+        let transaction_id = Uuid::new_v4();
+        let _transaction = self
+            .create_transaction_record(
+                tx,
+                transaction_id,
+                Some(sender_account_id),
+                Some(receiver_account_id),
+                credited,
+                receiver_currency,
+                TransactionType::TRANSFER.to_string(),
+                description,
+            )
+            .await?;
+        if let Some((_, rate)) = conversion {
+            self.record_conversion(tx, transaction_id, amount, &sender_currency, Some(rate))
+                .await?;
+        }
+        self.record_transaction_origin(tx, transaction_id, context)
+            .await?;
+        if let Some(category) = category {
+            self.record_transaction_category(tx, transaction_id, category)
+                .await?;
+        }
+        if fee > Decimal::ZERO {
+            self.book_fee(tx, transaction_id, sender_account_id, &sender_currency, fee)
+                .await?;
+        }
+
+        // Update sender balance by REDUCING it by the transfer amount; the fee
+        // was debited on its own when it was booked
+        // Note the negative amount to indicate funds leaving the account
+        self.update_account_balance(tx, sender_account_id, -amount, transaction_id)
+            .await?;
+
+        // Update receiver balance by INCREASING it by the (converted) transfer amount
+        self.update_account_balance(tx, receiver_account_id, credited, transaction_id)
+            .await?;
+
+        // Update transaction status to COMPLETED now that both accounts are updated
+        // This final state indicates the successful completion of the transfer
+        let transaction = self
+            .update_transaction_status(tx, transaction_id, TransactionStatus::COMPLETED.to_string())
+            .await?;
+        let conversion = match conversion {
+            Some((_, rate)) => TransactionConversion {
+                original_amount: Some(SqlxDecimal(amount)),
+                original_currency: Some(sender_currency),
+                exchange_rate: Some(SqlxDecimal(rate)),
+            },
+            None => TransactionConversion::default(),
+        };
+        Ok((transaction, fee, conversion))
+    }
+
+    /// Rejects a transfer from an account to itself, which could be used for
+    /// fraudulent activity or money laundering
+    fn ensure_distinct_accounts(
+        sender_account_id: Uuid,
+        receiver_account_id: Uuid,
+    ) -> Result<(), AppError> {
+        if sender_account_id == receiver_account_id {
+            return Err(AppError::BadRequest(
+                "Cannot transfer to the same account".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Helper function to look up the currency of an account taking part in a transfer
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use
+    /// * `account_id` - ID of the account
+    /// * `party` - Which side of the transfer the account is on, for the error message
+    /// * `lock` - Whether to lock the account until the transaction ends
+    ///
+    /// # Returns
+    /// `AppError::NotFound` if there is no such account
+    async fn transfer_account_currency(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: Uuid,
+        party: &str,
+        lock: RowLock,
+    ) -> Result<String, AppError> {
+        sqlx::query_scalar::<_, String>(&format!(
+            "SELECT currency FROM accounts WHERE id = $1{}",
+            lock.clause()
+        ))
+        .bind(account_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "{} account with ID {} not found",
+                party, account_id
+            ))
+        })
+    }
+
+    /// Helper function to work out what a transfer debits and credits
+    ///
+    /// # Returns
+    /// The amount debited from the sender, and when the currencies differ, the
+    /// amount credited to the receiver and the exchange rate used
+    ///
+    /// # Implementation Details
+    /// The amount is expressed in the currency's minor units so equal amounts
+    /// are stored and serialized identically (10.1 and 10.10 both become
+    /// 10.10). Both accounts must be able to hold it, so the coarser balance
+    /// scale of the two applies. Between currencies, the receiver is credited
+    /// the amount converted at the current rate, rounded half-even to its own
+    /// balance scale.
+    async fn transfer_amounts(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        sender_account_id: Uuid,
+        sender_currency: &str,
+        receiver_account_id: Uuid,
+        receiver_currency: &str,
+        amount: Decimal,
+    ) -> Result<(Decimal, Option<(Decimal, Decimal)>), AppError> {
+        if sender_currency == receiver_currency {
+            let scale = self
+                .balance_scale(tx, &[sender_account_id, receiver_account_id])
+                .await?;
+            let amount = self.normalize_amount(amount, sender_currency, scale)?;
+            return Ok((amount, None));
+        }
+
+        let rate = self
+            .exchange
+            .get_rate(sender_currency, receiver_currency)
+            .await?;
+        let scale = self.balance_scale(tx, &[sender_account_id]).await?;
+        let amount = self.normalize_amount(amount, sender_currency, scale)?;
+        let receiver_scale = self.balance_scale(tx, &[receiver_account_id]).await?;
+        let credited = convert(
+            amount,
+            rate,
+            receiver_scale,
+            RoundingStrategy::MidpointNearestEven,
+        )?;
+        if credited <= Decimal::ZERO {
+            return Err(AppError::BadRequest(
+                "Converted transfer amount is too small".to_string(),
+            ));
+        }
+        Ok((amount, Some((credited, rate))))
+    }
+
+    /// Checks both owners are email-verified when the amount exceeds the
+    /// configured `verified_transfer_threshold`; high-value transfers may only
+    /// move money between verified users
+    async fn ensure_transfer_verified(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        sender_account_id: Uuid,
+        receiver_account_id: Uuid,
+        amount: Decimal,
+    ) -> Result<(), AppError> {
+        if let Some(threshold) = self.config.verified_transfer_threshold {
+            if amount > threshold {
+                self.ensure_owner_verified(tx, sender_account_id, "Sender")
+                    .await?;
+                self.ensure_owner_verified(tx, receiver_account_id, "Receiver")
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Helper function to check an account can cover a debit from its
+    /// available balance; funds reserved by active holds can't be spent
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use
+    /// * `account_id` - ID of the account the funds leave
+    /// * `debited` - Everything about to leave the account, fees included
+    /// * `lock` - Whether to lock the account until the transaction ends
+    ///
+    /// # Returns
+    /// `AppError::BadRequest` if the available balance is too low
+    async fn ensure_available_funds(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: Uuid,
+        debited: Decimal,
+        lock: RowLock,
+    ) -> Result<(), AppError> {
+        // Get balance as string and convert to Decimal for precise comparison
+        let row = sqlx::query(&format!(
+            "SELECT (balance - held_amount)::TEXT AS balance FROM accounts WHERE id = $1{}",
+            lock.clause()
+        ))
+        .bind(account_id)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        // Parse the balance text to a Decimal for precise financial calculations
+        // ZERO is the fallback in case of parsing error
+        let balance: Decimal = sqlx::Row::get::<&str, _>(&row, "balance")
+            .parse()
+            .unwrap_or(Decimal::ZERO);
+
+        if balance < debited {
+            return Err(AppError::BadRequest("Insufficient funds".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Pays several accounts from one account as a single, all-or-nothing payment
+    ///
+    /// # Arguments
+    /// * `request` - Sender account, the destinations with their amounts, and a description
+    /// * `context` - Client that initiated the payment, stored on every leg
+    ///
+    /// # Returns
+    /// The split transfer with its completed legs
+    ///
+    /// # Implementation Details
+    /// This method checks there are between 1 and `MAX_SPLIT_TRANSFER_LEGS` legs,
+    /// then books them with `book_split_transfer`.
+    pub async fn process_split_transfer(
+        &self,
+        request: SplitTransferRequest,
+        context: &RequestContext,
+    ) -> Result<SplitTransferResponse, AppError> {
+        if request.legs.is_empty() || request.legs.len() > MAX_SPLIT_TRANSFER_LEGS {
+            return Err(AppError::Validation(format!(
+                "A split transfer needs between 1 and {} legs",
+                MAX_SPLIT_TRANSFER_LEGS
+            )));
+        }
+
+        let legs = request
+            .legs
+            .into_iter()
+            .map(|leg| {
+                (
+                    leg.receiver_account_id,
+                    leg.amount,
+                    request.description.clone(),
+                )
+            })
+            .collect();
+        self.book_split_transfer(
+            request.sender_account_id,
+            legs,
+            request.description,
+            context,
+        )
+        .await
+    }
+
+    /// Books many transfers as a single, all-or-nothing batch, e.g. for payroll
+    ///
+    /// # Arguments
+    /// * `requests` - The transfers to book, each with its own sender
+    /// * `context` - Client that initiated the batch, stored on every transfer
+    ///
+    /// # Returns
+    /// The completed transfers, in request order
+    ///
+    /// # Implementation Details
+    /// This method:
+    /// 1. Checks there are between 1 and `MAX_BATCH_TRANSFERS` transfers, and
+    ///    normalizes their categories
+    /// 2. Begins a database transaction and locks every account involved,
+    ///    including the fee account, in ID order so concurrent batches touching
+    ///    the same accounts can't deadlock
+    /// 3. Books each transfer in turn with `transfer_between_accounts`, so a
+    ///    later transfer sees the balances left by the earlier ones
+    /// 4. Commits the database transaction
+    /// 5. Queues receipts, webhooks and budget checks, like `process_transfer`
+    ///
+    /// If any transfer fails, e.g. for insufficient funds or a missing exchange
+    /// rate, the entire database transaction is rolled back and no balance changes.
+    pub async fn process_batch_transfer(
+        &self,
+        requests: Vec<TransferRequest>,
+        context: &RequestContext,
+    ) -> Result<Vec<TransactionResponse>, AppError> {
+        if requests.is_empty() || requests.len() > MAX_BATCH_TRANSFERS {
+            return Err(AppError::Validation(format!(
+                "A batch needs between 1 and {} transfers",
+                MAX_BATCH_TRANSFERS
+            )));
+        }
+        for request in &requests {
+            Self::ensure_positive_amount(request.amount)?;
+        }
+        let categories = requests
+            .iter()
+            .map(|request| {
+                request
+                    .category
+                    .as_deref()
+                    .map(normalize_category)
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        let mut milestones = Milestones::start(context.received_at);
+        let mut tx = self.pool.begin().await?;
+
+        // Lock every account up front, always in the same order; the locks
+        // taken again by each transfer are then already held
+        let mut account_ids: Vec<Uuid> = requests
+            .iter()
+            .flat_map(|request| [request.sender_account_id, request.receiver_account_id])
+            .chain(self.config.fee_account_id)
+            .collect();
+        account_ids.sort();
+        account_ids.dedup();
+        sqlx::query("SELECT id FROM accounts WHERE id = ANY($1) ORDER BY id FOR UPDATE")
+            .bind(&account_ids)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut responses = Vec::with_capacity(requests.len());
+        for (request, category) in requests.into_iter().zip(&categories) {
+            let (transaction, fee, conversion) = self
+                .transfer_between_accounts(
+                    &mut tx,
+                    request.sender_account_id,
+                    request.receiver_account_id,
+                    request.amount,
+                    request.description,
+                    category.as_deref(),
+                    context,
+                    &mut milestones,
+                )
+                .await?;
+            responses.push(
+                TransactionResponse::from(transaction)
+                    .with_conversion(conversion)
+                    .with_fee(fee)
+                    .with_category(category.clone()),
+            );
+        }
+        for response in &responses {
+            self.record_completed(&mut tx, response).await?;
+        }
+
+        tx.commit().await?;
+        let ids: Vec<Uuid> = responses.iter().map(|response| response.id).collect();
+        self.record_timings(&ids, milestones, context).await;
+
+        for (response, category) in responses.iter().zip(&categories) {
+            if let Some(notifications) = &self.notifications {
+                notifications.spawn_transfer_receipts(response);
+            }
+            self.announce(response);
+            self.check_budgets(response, category.as_deref()).await;
+        }
+
+        Ok(responses)
+    }
+
+    /// Books transfers from one account to several as a single, all-or-nothing payment
+    ///
+    /// # Arguments
+    /// * `sender_account_id` - Account to transfer money from
+    /// * `legs` - Receiver, amount and description of each leg
+    /// * `description` - Optional description of the payment as a whole
+    /// * `context` - Client that initiated the payment, stored on every leg
+    ///
+    /// # Returns
+    /// The split transfer with its completed legs
+    ///
+    /// # Implementation Details
+    /// This method:
+    /// 1. Checks each leg has a positive amount and doesn't pay the sender itself
+    /// 2. Begins a database transaction and locks every account involved, in ID
+    ///    order so concurrent payments touching the same accounts can't deadlock
+    /// 3. Checks every account exists and uses the sender's currency, and normalizes
+    ///    each leg amount to that currency's scale
+    /// 4. Applies `verified_transfer_threshold` to the total for the sender and to
+    ///    each leg for its receiver
+    /// 5. Checks the total against the sender's limits, works out the fee on
+    ///    each leg as for a single transfer, and verifies the sender can cover
+    ///    the total and the fees from its available balance before anything
+    ///    is booked
+    /// 6. Records the split transfer, then each leg as a TRANSFER linked to it,
+    ///    charging its fee, debiting the sender and crediting its receiver
+    /// 7. Commits the database transaction
+    /// 8. Queues email receipts for large legs, like `process_transfer`
+    ///
+    /// If any step fails, the entire database transaction is rolled back and no
+    /// balance changes.
+    async fn book_split_transfer(
+        &self,
+        sender_account_id: Uuid,
+        legs: Vec<(Uuid, Decimal, Option<String>)>,
+        description: Option<String>,
+        context: &RequestContext,
+    ) -> Result<SplitTransferResponse, AppError> {
+        for (receiver_account_id, amount, _) in &legs {
+            Self::ensure_positive_amount(*amount)?;
+            if *receiver_account_id == sender_account_id {
+                return Err(AppError::BadRequest(
+                    "Cannot transfer to the same account".to_string(),
+                ));
+            }
+        }
+
+        let mut milestones = Milestones::start(context.received_at);
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the sender, every receiver and the fee account at once, always
+        // in the same order
+        let mut account_ids: Vec<Uuid> = legs
+            .iter()
+            .map(|(receiver_account_id, _, _)| *receiver_account_id)
+            .collect();
+        account_ids.push(sender_account_id);
+        account_ids.sort();
+        account_ids.dedup();
+        let mut locked_ids = account_ids.clone();
+        locked_ids.extend(self.config.fee_account_id);
+        locked_ids.sort();
+        locked_ids.dedup();
+
+        let accounts: HashMap<Uuid, (String, SqlxDecimal)> =
+            sqlx::query_as::<_, (Uuid, String, SqlxDecimal)>(
+                r#"
+                SELECT id, currency, balance - held_amount AS balance FROM accounts
+                WHERE id = ANY($1) ORDER BY id FOR UPDATE
+                "#,
+            )
+            .bind(&locked_ids)
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .map(|(id, currency, balance)| (id, (currency, balance)))
+            .collect();
+        milestones.lock_acquired();
+        self.ensure_accounts_active(&mut tx, &account_ids).await?;
+
+        let (currency, sender_balance) =
+            accounts.get(&sender_account_id).cloned().ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "Sender account with ID {} not found",
+                    sender_account_id
+                ))
+            })?;
+
+        let mut normalized = Vec::with_capacity(legs.len());
+        for (receiver_account_id, amount, leg_description) in legs {
+            let (receiver_currency, _) = accounts.get(&receiver_account_id).ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "Receiver account with ID {} not found",
+                    receiver_account_id
+                ))
+            })?;
+            if *receiver_currency != currency {
+                return Err(AppError::BadRequest(
+                    "Currency mismatch between accounts".to_string(),
+                ));
+            }
+            let scale = self
+                .balance_scale(&mut tx, &[sender_account_id, receiver_account_id])
+                .await?;
+            normalized.push((
+                receiver_account_id,
+                self.normalize_amount(amount, &currency, scale)?,
+                leg_description,
+            ));
+        }
+        let legs = normalized;
+        let total: Decimal = legs.iter().map(|(_, amount, _)| *amount).sum();
+
+        // High-value payments may only move money between verified users
+        if let Some(threshold) = self.config.verified_transfer_threshold {
+            if total > threshold {
+                self.ensure_owner_verified(&mut tx, sender_account_id, "Sender")
+                    .await?;
+            }
+            for (receiver_account_id, amount, _) in &legs {
+                if *amount > threshold {
+                    self.ensure_owner_verified(&mut tx, *receiver_account_id, "Receiver")
+                        .await?;
+                }
+            }
+        }
+
+        // Limits apply to the payment as a whole, like to a single transfer
+        self.check_account_limits(&mut tx, sender_account_id, total, TransactionType::TRANSFER)
+            .await?;
+
+        let mut fees = Vec::with_capacity(legs.len());
+        for (_, amount, _) in &legs {
+            fees.push(
+                self.fee_for(
+                    &mut tx,
+                    TransactionType::TRANSFER,
+                    sender_account_id,
+                    &currency,
+                    *amount,
+                )
+                .await?,
+            );
+        }
+        let total_fees: Decimal = fees.iter().sum();
+
+        // The whole payment and its fees must be covered before any leg is booked
+        if *sender_balance < total + total_fees {
+            return Err(AppError::BadRequest("Insufficient funds".to_string()));
+        }
+
+        let split_id = Uuid::new_v4();
+        let created_at: DateTime<Utc> = sqlx::query_scalar(
+            r#"
+            INSERT INTO split_transfers (id, sender_account_id, total, currency, description)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING created_at
+            "#,
+        )
+        .bind(split_id)
+        .bind(sender_account_id)
+        .bind(SqlxDecimal(total))
+        .bind(&currency)
+        .bind(&description)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut completed = Vec::with_capacity(legs.len());
+        for ((receiver_account_id, amount, leg_description), fee) in legs.into_iter().zip(fees) {
+            let transaction_id = Uuid::new_v4();
+            self.create_transaction_record(
+                &mut tx,
+                transaction_id,
+                Some(sender_account_id),
+                Some(receiver_account_id),
+                amount,
+                currency.clone(),
+                TransactionType::TRANSFER.to_string(),
+                leg_description,
+            )
+            .await?;
+            self.record_split_transfer(&mut tx, transaction_id, split_id)
+                .await?;
+            self.record_transaction_origin(&mut tx, transaction_id, context)
+                .await?;
+            if fee > Decimal::ZERO {
+                self.book_fee(&mut tx, transaction_id, sender_account_id, &currency, fee)
+                    .await?;
+            }
+            self.update_account_balance(&mut tx, receiver_account_id, amount, transaction_id)
+                .await?;
+            self.update_account_balance(&mut tx, sender_account_id, -amount, transaction_id)
+                .await?;
+            let transaction = self
+                .update_transaction_status(
+                    &mut tx,
+                    transaction_id,
+                    TransactionStatus::COMPLETED.to_string(),
+                )
+                .await?;
+            completed.push(TransactionResponse::from(transaction).with_fee(fee));
+        }
+        for leg in &completed {
+            self.record_completed(&mut tx, leg).await?;
+        }
+
+        tx.commit().await?;
+        let leg_ids: Vec<Uuid> = completed.iter().map(|leg| leg.id).collect();
+        self.record_timings(&leg_ids, milestones, context).await;
+
+        if let Some(notifications) = &self.notifications {
+            for leg in &completed {
+                notifications.spawn_transfer_receipts(leg);
+            }
+        }
+        for leg in &completed {
+            self.announce(leg);
+        }
+
+        Ok(SplitTransferResponse {
+            id: split_id,
+            sender_account_id,
+            total: to_display_scale(total, &currency),
+            currency,
+            description,
+            legs: completed,
+            created_at,
+        })
+    }
+
+    /// Processes a deposit into an account
+    ///
+    /// A deposit represents money coming into the system from outside.
+    /// For example, this could be a bank transfer, cash deposit, or other external funds.
+    ///
+    /// # Arguments
+    /// * `request` - Deposit request containing account ID, amount, and description
+    ///
+    /// # Returns
+    /// The completed transaction response upon success
+    ///
+    /// # Implementation Details
+    /// This method:
+    /// 1. Begins a database transaction for atomicity
+    /// 2. Validates the target account exists
+    /// 3. Resolves the amount in the account currency (see `resolve_deposit_amount`)
+    /// 4. Creates a pending transaction record with no sender (external source)
+    /// 5. Updates the account balance
+    /// 6. Marks the transaction as completed
+    /// 7. Commits the database transaction
+    pub async fn process_deposit(
+        &self,
+        request: DepositRequest,
+    ) -> Result<TransactionResponse, AppError> {
+        self.book_deposit(request, false, &RequestContext::default(), None)
+            .await
+    }
+
+    /// Processes a deposit at most once per idempotency key
+    ///
+    /// Behaves like `process_deposit`. When the key was already used for the
+    /// same request, the stored response is returned and nothing is booked.
+    pub async fn process_deposit_idempotent(
+        &self,
+        request: DepositRequest,
+        context: &RequestContext,
+        idempotency: Option<&IdempotencyKey>,
+    ) -> Result<TransactionResponse, AppError> {
+        self.book_deposit(request, false, context, idempotency)
+            .await
+    }
+
+    /// Processes a deposit of test funds minted by the sandbox faucet
+    ///
+    /// The deposit is booked exactly like `process_deposit`, but the transaction
+    /// is flagged as sandbox data so it can be purged later.
+    #[cfg(feature = "sandbox")]
+    pub async fn process_sandbox_deposit(
+        &self,
+        request: DepositRequest,
+    ) -> Result<TransactionResponse, AppError> {
+        self.book_deposit(request, true, &RequestContext::default(), None)
+            .await
+    }
+
+    /// Books a deposit, flagging it as sandbox data when `sandbox` is set
+    async fn book_deposit(
+        &self,
+        request: DepositRequest,
+        sandbox: bool,
+        context: &RequestContext,
+        idempotency: Option<&IdempotencyKey>,
+    ) -> Result<TransactionResponse, AppError> {
+        Self::ensure_positive_amount(request.amount)?;
+        let category = request
+            .category
+            .as_deref()
+            .map(normalize_category)
+            .transpose()?;
+        let mut milestones = Milestones::start(context.received_at);
+
+        // Start a database transaction to ensure atomicity of operations
+        let mut tx = self.pool.begin().await?;
+
+        // A retried request gets its original response and books nothing
+        if let Some(stored) = self.claim_idempotency_key(&mut tx, idempotency).await? {
+            return Ok(stored);
+        }
+
+        // Verify account exists and lock it for update to prevent race conditions
+        let account = sqlx::query!(
+            r#"
+            SELECT id, currency FROM accounts WHERE id = $1 FOR UPDATE
+            "#,
+            request.account_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("Account with ID {} not found", request.account_id))
+        })?;
+        milestones.lock_acquired();
+        self.ensure_accounts_active(&mut tx, &[request.account_id])
+            .await?;
+
+        // Make sure the funds are expressed in the account currency before booking them
+        let scale = self.balance_scale(&mut tx, &[request.account_id]).await?;
+        let amount = self.resolve_deposit_amount(&request, &account.currency, scale)?;
+        Self::check_kind_bounds(
+            amount,
+            "Deposit",
+            self.config.min_deposit_amount,
+            self.config.max_deposit_amount,
+        )?;
+
+        // Create a transaction record with no sender_account_id (money comes from outside)
+        // but with the receiver_account_id set to the deposit account
+        let transaction_id = Uuid::new_v4();
+        let _transaction = self
+            .create_transaction_record(
+                &mut tx,
+                transaction_id,
+                None, // No sender account for deposits (external source)
+                Some(request.account_id),
+                amount,
+                account.currency.clone(),
+                TransactionType::DEPOSIT.to_string(),
+                request.description,
+            )
+            .await?;
+        if sandbox {
+            sqlx::query("UPDATE transactions SET sandbox = TRUE WHERE id = $1")
+                .bind(transaction_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        self.record_external_reference(
+            &mut tx,
+            transaction_id,
+            request.external_reference.as_deref(),
+        )
+        .await?;
+        if let Some(category) = &category {
+            self.record_transaction_category(&mut tx, transaction_id, category)
+                .await?;
+        }
+
+        // Increase the account balance by the deposit amount
+        // Since deposits always increase the balance, we pass a positive amount
+        self.update_account_balance(&mut tx, request.account_id, amount, transaction_id)
+            .await?;
+
+        // Update transaction status to COMPLETED
+        let updated_transaction = self
+            .update_transaction_status(
+                &mut tx,
+                transaction_id,
+                TransactionStatus::COMPLETED.to_string(),
+            )
+            .await?;
+
+        let response = TransactionResponse::from(updated_transaction)
+            .with_external_reference(request.external_reference)
+            .with_category(category);
+        self.store_idempotent_response(&mut tx, idempotency, &response)
+            .await?;
+        self.record_completed(&mut tx, &response).await?;
+
+        // Commit all changes as a single atomic operation
+        tx.commit().await?;
+        self.record_timings(&[response.id], milestones, context)
+            .await;
+        self.announce(&response);
+
+        // Return transaction details
+        Ok(response)
+    }
+
+    /// Processes a withdrawal from an account
+    ///
+    /// A withdrawal represents money leaving the system entirely.
+    /// For example, this could be an ATM withdrawal, bank transfer out, or other external payment.
+    ///
+    /// # Arguments
+    /// * `request` - Withdrawal request containing account ID, amount, and description
+    /// * `context` - Client that initiated the withdrawal, stored for fraud investigations
+    ///
+    /// # Returns
+    /// The completed transaction response upon success
+    ///
+    /// # Implementation Details
+    /// This method begins a database transaction, debits the account through
+    /// `debit_to_external` and commits.
+    pub async fn process_withdrawal(
+        &self,
+        request: WithdrawalRequest,
+        context: &RequestContext,
+    ) -> Result<TransactionResponse, AppError> {
+        self.withdraw(request, context, None).await
+    }
+
+    /// Processes a withdrawal at most once per idempotency key
+    ///
+    /// Behaves like `process_withdrawal`. When the key was already used for the
+    /// same request, the stored response is returned and no money moves.
+    pub async fn process_withdrawal_idempotent(
+        &self,
+        request: WithdrawalRequest,
+        context: &RequestContext,
+        idempotency: Option<&IdempotencyKey>,
+    ) -> Result<TransactionResponse, AppError> {
+        self.withdraw(request, context, idempotency).await
+    }
+
+    async fn withdraw(
+        &self,
+        request: WithdrawalRequest,
+        context: &RequestContext,
+        idempotency: Option<&IdempotencyKey>,
+    ) -> Result<TransactionResponse, AppError> {
+        Self::ensure_positive_amount(request.amount)?;
+        let category = request
+            .category
+            .as_deref()
+            .map(normalize_category)
+            .transpose()?;
+        let mut milestones = Milestones::start(context.received_at);
+
+        // Start a database transaction to ensure atomicity
+        let mut tx = self.pool.begin().await?;
+
+        // A retried request gets its original response and books nothing
+        if let Some(stored) = self.claim_idempotency_key(&mut tx, idempotency).await? {
+            return Ok(stored);
+        }
+
+        let (transaction, fee) = self
+            .debit_to_external(
+                &mut tx,
+                request.account_id,
+                request.amount,
+                request.description,
+                category.as_deref(),
+                request.destination_id,
+                context,
+                &mut milestones,
+            )
+            .await?;
+        self.record_external_reference(
+            &mut tx,
+            transaction.id,
+            request.external_reference.as_deref(),
+        )
+        .await?;
+
+        let response = TransactionResponse::from(transaction)
+            .with_fee(fee)
+            .with_external_reference(request.external_reference)
+            .with_category(category.clone());
+        self.store_idempotent_response(&mut tx, idempotency, &response)
+            .await?;
+        self.record_completed(&mut tx, &response).await?;
+
+        // Commit all changes as a single atomic operation
+        tx.commit().await?;
+        self.record_timings(&[response.id], milestones, context)
+            .await;
+
+        self.announce(&response);
+
+        // Return transaction details
+        self.check_budgets(&response, category.as_deref()).await;
+        Ok(response)
+    }
+
+    /// Pays funds out of an account to an external destination
+    ///
+    /// A payout is booked as a WITHDRAWAL. Unlike `process_withdrawal`, it is the
+    /// entry point for pay-out flows that need further bookkeeping (such as fees or
+    /// external references) committed in the same database transaction as the debit.
+    ///
+    /// # Arguments
+    /// * `request` - Payout request containing account ID, amount, and description
+    /// * `context` - Client that initiated the payout, stored for fraud investigations
+    ///
+    /// # Returns
+    /// The completed withdrawal transaction
+    ///
+    /// # Implementation Details
+    /// All steps run in one database transaction; if any fails (for example on
+    /// insufficient funds) nothing is recorded and the balance is untouched.
+    pub async fn process_payout(
+        &self,
+        request: PayoutRequest,
+        context: &RequestContext,
+    ) -> Result<TransactionResponse, AppError> {
+        Self::ensure_positive_amount(request.amount)?;
+        let category = request
+            .category
+            .as_deref()
+            .map(normalize_category)
+            .transpose()?;
+        let mut milestones = Milestones::start(context.received_at);
+        let mut tx = self.pool.begin().await?;
+
+        let (transaction, fee) = self
+            .debit_to_external(
+                &mut tx,
+                request.account_id,
+                request.amount,
+                request.description,
+                category.as_deref(),
+                request.destination_id,
+                context,
+                &mut milestones,
+            )
+            .await?;
+        let response = TransactionResponse::from(transaction)
+            .with_fee(fee)
+            .with_category(category.clone());
+        self.record_completed(&mut tx, &response).await?;
+
+        tx.commit().await?;
+        self.record_timings(&[response.id], milestones, context)
+            .await;
+        self.announce(&response);
+        self.check_budgets(&response, category.as_deref()).await;
+        Ok(response)
+    }
+
+    /// Helper function to move funds out of an account to an external destination
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use; the caller commits it
+    /// * `account_id` - ID of the account to debit
+    /// * `amount` - Amount requested by the client
+    /// * `description` - Optional transaction description
+    /// * `category` - Optional normalized spending category
+    /// * `destination_id` - Registered destination the funds go to, if any
+    /// * `context` - Client that initiated the debit
+    /// * `milestones` - Timings of the request, noting when the account is locked
+    ///
+    /// # Returns
+    /// The completed WITHDRAWAL transaction record, and the fee charged on it
+    ///
+    /// # Implementation Details
+    /// This method:
+    /// 1. Validates the source account exists and normalizes the amount to its
+    ///    currency's scale
+    /// 2. Checks the destination against the account owner's registry, and the
+    ///    amount against the account's per-transaction and daily limits
+    /// 3. Verifies the account has sufficient funds not reserved by active holds
+    ///    for the amount plus the withdrawal fee
+    /// 4. Creates a pending transaction record with no receiver (external destination)
+    /// 5. Books the fee, if any, and updates the account balance
+    /// 6. Marks the transaction as completed
+    #[allow(clippy::too_many_arguments)]
+    async fn debit_to_external(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: Uuid,
+        amount: Decimal,
+        description: Option<String>,
+        category: Option<&str>,
+        destination_id: Option<Uuid>,
+        context: &RequestContext,
+        milestones: &mut Milestones,
+    ) -> Result<(Transaction, Decimal), AppError> {
+        // Verify account exists and lock it for update
+        let account = sqlx::query!(
+            r#"
+            SELECT id, currency, balance FROM accounts WHERE id = $1 FOR UPDATE
+            "#,
+            account_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Account with ID {} not found", account_id)))?;
+        milestones.lock_acquired();
+        self.ensure_accounts_active(tx, &[account_id]).await?;
+
+        // Express the amount in the currency's minor units
+        let scale = self.balance_scale(tx, &[account_id]).await?;
+        let amount = self.normalize_amount(amount, &account.currency, scale)?;
+        Self::check_kind_bounds(
+            amount,
+            "Withdrawal",
+            self.config.min_withdrawal_amount,
+            self.config.max_withdrawal_amount,
+        )?;
+
+        self.check_destination(tx, account_id, destination_id)
+            .await?;
+        self.check_account_limits(tx, account_id, amount, TransactionType::WITHDRAWAL)
+            .await?;
+        let fee = self
+            .fee_for(
+                tx,
+                TransactionType::WITHDRAWAL,
+                account_id,
+                &account.currency,
+                amount,
+            )
+            .await?;
+
+        // Ensure sufficient available balance for withdrawal - prevent overdrafts
+        // and spending funds reserved by active holds
+        // Read the balance as text for precise decimal handling
+        let row = sqlx::query(
+            "SELECT (balance - held_amount)::TEXT AS balance FROM accounts WHERE id = $1 FOR UPDATE",
+        )
+        .bind(account_id)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        // Parse balance from text to Decimal for accurate comparison
+        let account_balance: Decimal = sqlx::Row::get::<&str, _>(&row, "balance")
+            .parse()
+            .unwrap_or(Decimal::ZERO);
+
+        // Verify sufficient funds for the withdrawal and its fee
+        if account_balance < amount + fee {
+            return Err(AppError::BadRequest("Insufficient funds".to_string()));
+        }
+
+        // Create transaction record with sender_account_id set but no receiver_account_id
+        // This pattern indicates money leaving the system to an external destination
+        let transaction_id = Uuid::new_v4();
+        let _transaction = self
+            .create_transaction_record(
+                tx,
+                transaction_id,
+                Some(account_id),
+                None, // No receiver account for withdrawals (external destination)
+                amount,
+                account.currency.clone(),
+                TransactionType::WITHDRAWAL.to_string(),
+                description,
+            )
+            .await?;
+        self.record_transaction_origin(tx, transaction_id, context)
+            .await?;
+        if let Some(category) = category {
+            self.record_transaction_category(tx, transaction_id, category)
+                .await?;
+        }
+        if let Some(destination_id) = destination_id {
+            sqlx::query("UPDATE transactions SET destination_id = $2 WHERE id = $1")
+                .bind(transaction_id)
+                .bind(destination_id)
+                .execute(&mut **tx)
+                .await?;
+        }
+        if fee > Decimal::ZERO {
+            self.book_fee(tx, transaction_id, account_id, &account.currency, fee)
+                .await?;
+        }
+
+        // Decrease account balance by withdrawal amount; the fee was debited
+        // on its own when it was booked
+        // Negative amount indicates funds leaving the account
+        self.update_account_balance(tx, account_id, -amount, transaction_id)
+            .await?;
+
+        // Update transaction status to COMPLETED
+        let transaction = self
+            .update_transaction_status(tx, transaction_id, TransactionStatus::COMPLETED.to_string())
+            .await?;
+        Ok((transaction, fee))
+    }
+
+    /// Changes the description of a completed transaction
+    ///
+    /// # Arguments
+    /// * `id` - The transaction to change
+    /// * `user_id` - The user asking; must own its sender or receiver account
+    /// * `description` - The new description; `None` removes it
+    ///
+    /// # Returns
+    /// The transaction with its new description. `AppError::Forbidden` if the
+    /// user owns neither account, `TRANSACTION_NOT_COMPLETED` if it isn't
+    /// COMPLETED.
+    ///
+    /// # Implementation Details
+    /// The transaction is locked while it is checked and changed, so it can't
+    /// be reversed in between. Its `updated_at` moves through the
+    /// `touch_updated_at` trigger. Nothing else about the transaction, and no
+    /// balance, changes.
+    pub async fn update_description(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        description: Option<String>,
+    ) -> Result<TransactionResponse, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let (status, owned) = sqlx::query_as::<_, (String, bool)>(
+            r#"
+            SELECT t.status,
+                   EXISTS (SELECT 1 FROM accounts a
+                           WHERE a.id IN (t.sender_account_id, t.receiver_account_id)
+                             AND a.user_id = $2) AS owned
+            FROM transactions t WHERE t.id = $1 FOR UPDATE OF t
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Transaction with ID {} not found", id)))?;
+        if !owned {
+            return Err(AppError::Forbidden(
+                "You don't have permission to update this transaction".to_string(),
+            ));
+        }
+        if status != TransactionStatus::COMPLETED.to_string() {
+            return Err(AppError::Rejected {
+                code: TRANSACTION_NOT_COMPLETED,
+                message: format!("Only completed transactions can be updated, not {}", status),
+            });
+        }
+
+        sqlx::query("UPDATE transactions SET description = $2 WHERE id = $1")
+            .bind(id)
+            .bind(&description)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        tracing::info!(
+            transaction_id = %id,
+            actor_id = %user_id,
+            "Transaction description updated"
+        );
+        self.get_transaction_by_id(id).await
+    }
+
+    /// Undoes a completed transfer by moving its amount back to the sender
+    ///
+    /// # Arguments
+    /// * `id` - The transfer to reverse
+    /// * `context` - Client that asked for the reversal, stored on the reversal
+    ///
+    /// # Returns
+    /// The original transfer, now REVERSED, and the compensating transfer
+    ///
+    /// # Implementation Details
+    /// This method:
+    /// 1. Locks the original transaction, so concurrent reversals of it queue up
+    /// 2. Checks it is a COMPLETED transfer that is not itself a reversal
+    /// 3. Locks both accounts, in ID order like `process_split_transfer`, and
+    ///    checks both are still active
+    /// 4. Verifies the original receiver can still cover the amount from its
+    ///    available balance
+    /// 5. Books a transfer from the receiver back to the sender, linked to the
+    ///    original through `reverses_transaction_id`
+    /// 6. Refunds the fee the sender paid on the original with `refund_fee`
+    /// 7. Marks the original REVERSED, pointing at the reversal through `reversed_by`
+    /// 8. Commits, then sends webhooks for the reversal like `process_transfer`
+    ///
+    /// Each rule has its own error code: `TRANSACTION_NOT_REVERSIBLE`,
+    /// `TRANSACTION_ALREADY_REVERSED`, `TRANSACTION_NOT_COMPLETED` and
+    /// `REVERSAL_INSUFFICIENT_FUNDS`. If any step fails, nothing changes.
+    pub async fn reverse_transaction(
+        &self,
+        id: Uuid,
+        context: &RequestContext,
+    ) -> Result<TransactionReversalResponse, AppError> {
+        let mut milestones = Milestones::start(context.received_at);
+        let mut tx = self.pool.begin().await?;
+
+        let original = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT id, sender_account_id, receiver_account_id, amount, currency,
+                   transaction_type, status, description, created_at, updated_at
+            FROM transactions WHERE id = $1 FOR UPDATE
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Transaction with ID {} not found", id)))?;
+        let (reverses, original_amount, original_currency, exchange_rate): (
+            Option<Uuid>,
+            Option<SqlxDecimal>,
+            Option<String>,
+            Option<SqlxDecimal>,
+        ) = sqlx::query_as(
+            r#"
+            SELECT reverses_transaction_id, original_amount, original_currency, exchange_rate
+            FROM transactions WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let original_conversion = TransactionConversion {
+            original_amount,
+            original_currency: original_currency.clone(),
+            exchange_rate,
+        };
+
+        let (Some(sender_account_id), Some(receiver_account_id)) =
+            (original.sender_account_id, original.receiver_account_id)
+        else {
+            return Err(AppError::Rejected {
+                code: TRANSACTION_NOT_REVERSIBLE,
+                message: "Only transfers can be reversed".to_string(),
+            });
+        };
+        if original.transaction_type != TransactionType::TRANSFER.to_string() || reverses.is_some()
+        {
+            return Err(AppError::Rejected {
+                code: TRANSACTION_NOT_REVERSIBLE,
+                message: "Only transfers that are not reversals themselves can be reversed"
+                    .to_string(),
+            });
+        }
+        if original.status == TransactionStatus::REVERSED.to_string() {
+            return Err(AppError::Rejected {
+                code: TRANSACTION_ALREADY_REVERSED,
+                message: format!("Transaction {} has already been reversed", id),
+            });
+        }
+        if original.status != TransactionStatus::COMPLETED.to_string() {
+            return Err(AppError::Rejected {
+                code: TRANSACTION_NOT_COMPLETED,
+                message: format!(
+                    "Only completed transactions can be reversed, not {}",
+                    original.status
+                ),
+            });
+        }
+
+        // Lock both accounts, always in the same order
+        let mut account_ids = vec![sender_account_id, receiver_account_id];
+        account_ids.sort();
+        let balances: HashMap<Uuid, SqlxDecimal> = sqlx::query_as::<_, (Uuid, SqlxDecimal)>(
+            r#"
+            SELECT id, balance - held_amount AS balance FROM accounts
+            WHERE id = ANY($1) ORDER BY id FOR UPDATE
+            "#,
+        )
+        .bind(&account_ids)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .collect();
+        milestones.lock_acquired();
+        self.ensure_accounts_active(&mut tx, &account_ids).await?;
+
+        let amount = original.amount.0;
+        let receiver_balance = balances
+            .get(&receiver_account_id)
+            .map(|balance| balance.0)
+            .unwrap_or(Decimal::ZERO);
+        if receiver_balance < amount {
+            return Err(AppError::Rejected {
+                code: REVERSAL_INSUFFICIENT_FUNDS,
+                message: "The receiving account no longer has enough funds to reverse the transfer"
+                    .to_string(),
+            });
+        }
+
+        // The reversal is an ordinary transfer in the opposite direction. A
+        // converted transfer is undone without converting again: the sender gets
+        // back exactly what it paid, so the reversal records no rate.
+        let (refund, refund_currency) = match (original_amount, original_currency) {
+            (Some(original_amount), Some(original_currency)) => {
+                (original_amount.0, original_currency)
+            }
+            _ => (amount, original.currency.clone()),
+        };
+        let reversal_id = Uuid::new_v4();
+        self.create_transaction_record(
+            &mut tx,
+            reversal_id,
+            Some(receiver_account_id),
+            Some(sender_account_id),
+            refund,
+            refund_currency.clone(),
+            TransactionType::TRANSFER.to_string(),
+            Some(format!("Reversal of transaction {}", id)),
+        )
+        .await?;
+        let mut reversal_conversion = TransactionConversion::default();
+        if refund_currency != original.currency {
+            self.record_conversion(&mut tx, reversal_id, amount, &original.currency, None)
+                .await?;
+            reversal_conversion = TransactionConversion {
+                original_amount: Some(SqlxDecimal(amount)),
+                original_currency: Some(original.currency.clone()),
+                exchange_rate: None,
+            };
+        }
+        self.record_transaction_origin(&mut tx, reversal_id, context)
+            .await?;
+        sqlx::query("UPDATE transactions SET reverses_transaction_id = $2 WHERE id = $1")
+            .bind(reversal_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        self.update_account_balance(&mut tx, receiver_account_id, -amount, reversal_id)
+            .await?;
+        self.update_account_balance(&mut tx, sender_account_id, refund, reversal_id)
+            .await?;
+        let reversal = self
+            .update_transaction_status(
+                &mut tx,
+                reversal_id,
+                TransactionStatus::COMPLETED.to_string(),
+            )
+            .await?;
+        self.refund_fee(&mut tx, id, context).await?;
+
+        sqlx::query("UPDATE transactions SET reversed_by = $2 WHERE id = $1")
+            .bind(id)
+            .bind(reversal_id)
+            .execute(&mut *tx)
+            .await?;
+        let original = self
+            .update_transaction_status(&mut tx, id, TransactionStatus::REVERSED.to_string())
+            .await?;
+        let reversal = TransactionResponse::from(reversal).with_conversion(reversal_conversion);
+        self.record_completed(&mut tx, &reversal).await?;
+
+        tx.commit().await?;
+        self.record_timings(&[reversal_id], milestones, context)
+            .await;
+
+        let response = TransactionReversalResponse {
+            original: TransactionResponse::from(original).with_conversion(original_conversion),
+            reversal,
+        };
+        self.announce(&response.reversal);
+        metrics().record_transaction(
+            &response.original.transaction_type,
+            &response.original.status,
+        );
+
+        Ok(response)
+    }
+
+    /// Gives back the fee charged on a transaction, e.g. when it is waived after
+    /// the fact, leaving the transaction itself as it is
+    ///
+    /// # Arguments
+    /// * `parent_id` - The transfer or withdrawal the fee was charged on
+    /// * `context` - Client that asked for the refund, stored on it
+    ///
+    /// # Returns
+    /// The FEE transaction crediting the fee back to the payer
+    ///
+    /// # Implementation Details
+    /// This method locks the charged transaction, so concurrent refunds of its
+    /// fee queue up, and checks the payer's account is still active before
+    /// booking the refund with `refund_fee`. A transaction charged no fee is
+    /// rejected with `TRANSACTION_NOT_REVERSIBLE`, and one whose fee was
+    /// already refunded, by this method or by reversing the transfer, with
+    /// `TRANSACTION_ALREADY_REVERSED`. If any step fails, nothing changes.
+    pub async fn reverse_fee(
+        &self,
+        parent_id: Uuid,
+        context: &RequestContext,
+    ) -> Result<TransactionResponse, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query_scalar::<_, Uuid>("SELECT id FROM transactions WHERE id = $1 FOR UPDATE")
+            .bind(parent_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Transaction with ID {} not found", parent_id))
+            })?;
+        let fee = sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT sender_account_id, status FROM transactions
+            WHERE parent_transaction_id = $1 AND transaction_type = 'FEE'
+              AND reverses_transaction_id IS NULL
+            "#,
+        )
+        .bind(parent_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((payer_account_id, status)) = fee else {
+            return Err(AppError::Rejected {
+                code: TRANSACTION_NOT_REVERSIBLE,
+                message: format!("Transaction {} was charged no fee", parent_id),
+            });
+        };
+        if status == TransactionStatus::REVERSED.to_string() {
+            return Err(AppError::Rejected {
+                code: TRANSACTION_ALREADY_REVERSED,
+                message: format!(
+                    "The fee on transaction {} has already been reversed",
+                    parent_id
+                ),
+            });
+        }
+
+        sqlx::query("SELECT id FROM accounts WHERE id = $1 FOR UPDATE")
+            .bind(payer_account_id)
+            .execute(&mut *tx)
+            .await?;
+        self.ensure_accounts_active(&mut tx, &[payer_account_id])
+            .await?;
+        let refund = self
+            .refund_fee(&mut tx, parent_id, context)
+            .await?
+            .ok_or_else(|| {
+                AppError::Internal(format!(
+                    "Fee on transaction {} could not be refunded",
+                    parent_id
+                ))
+            })?;
+
+        tx.commit().await?;
+
+        Ok(TransactionResponse::from(refund))
+    }
+
+    /// Gets a hold by its ID
+    ///
+    /// # Arguments
+    /// * `id` - The hold's ID
+    ///
+    /// # Returns
+    /// The hold, whatever its status
+    pub async fn get_hold(&self, id: Uuid) -> Result<HoldResponse, AppError> {
+        let hold = sqlx::query_as::<_, Hold>(
+            r#"
+            SELECT id, account_id, amount, currency, status, description,
+                   captured_amount, transaction_id, created_at, updated_at
+            FROM holds WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Hold with ID {} not found", id)))?;
+
+        Ok(HoldResponse::from(hold))
+    }
+
+    /// Reserves funds on an account for a later capture
+    ///
+    /// # Arguments
+    /// * `request` - Account, amount to reserve and an optional description
+    ///
+    /// # Returns
+    /// The new, ACTIVE hold
+    ///
+    /// # Implementation Details
+    /// No money moves: the amount is added to the account's `held_amount`, which
+    /// withdrawals and transfers subtract from the balance before checking for
+    /// sufficient funds. The hold itself needs the amount to be available too.
+    pub async fn create_hold(&self, request: CreateHoldRequest) -> Result<HoldResponse, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let (currency, available) = sqlx::query_as::<_, (String, SqlxDecimal)>(
+            "SELECT currency, balance - held_amount FROM accounts WHERE id = $1 FOR UPDATE",
+        )
+        .bind(request.account_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("Account with ID {} not found", request.account_id))
+        })?;
+        self.ensure_accounts_active(&mut tx, &[request.account_id])
+            .await?;
+
+        let scale = self.balance_scale(&mut tx, &[request.account_id]).await?;
+        let amount = self.normalize_amount(request.amount, &currency, scale)?;
+        if available.0 < amount {
+            return Err(AppError::BadRequest("Insufficient funds".to_string()));
+        }
+
+        self.adjust_held_amount(&mut tx, request.account_id, amount)
+            .await?;
+        let hold = sqlx::query_as::<_, Hold>(
+            r#"
+            INSERT INTO holds (id, account_id, amount, currency, description)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, account_id, amount, currency, status, description,
+                      captured_amount, transaction_id, created_at, updated_at
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(request.account_id)
+        .bind(SqlxDecimal(amount))
+        .bind(&currency)
+        .bind(&request.description)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(HoldResponse::from(hold))
+    }
+
+    /// Turns an active hold into a withdrawal or a transfer
+    ///
+    /// # Arguments
+    /// * `id` - The hold to capture
+    /// * `request` - How much to capture and where the funds go
+    /// * `context` - Client that asked for the capture, stored on the transaction
+    ///
+    /// # Returns
+    /// The CAPTURED hold and the completed transaction
+    ///
+    /// # Implementation Details
+    /// This method:
+    /// 1. Locks the hold and checks it is still ACTIVE
+    /// 2. Checks the capture doesn't exceed the amount held
+    /// 3. Frees the whole hold, so a partial capture releases the rest
+    /// 4. Books a transfer to `receiver_account_id` like `process_transfer`, or
+    ///    without a receiver a withdrawal like `process_withdrawal`
+    /// 5. Marks the hold CAPTURED with the amount taken and the transaction
+    ///
+    /// Fails with `HOLD_NOT_ACTIVE` or `CAPTURE_EXCEEDS_HOLD`, or for any reason
+    /// the transaction itself would. If any step fails, the hold stays ACTIVE.
+    pub async fn capture_hold(
+        &self,
+        id: Uuid,
+        request: CaptureHoldRequest,
+        context: &RequestContext,
+    ) -> Result<HoldCaptureResponse, AppError> {
+        if request.receiver_account_id.is_some() && request.destination_id.is_some() {
+            return Err(AppError::BadRequest(
+                "A destination can only be given when capturing into a withdrawal".to_string(),
+            ));
+        }
+
+        let mut milestones = Milestones::start(context.received_at);
+        let mut tx = self.pool.begin().await?;
+
+        let hold = self.lock_active_hold(&mut tx, id).await?;
+        let held = hold.amount.0;
+        let amount = match request.amount {
+            Some(amount) => {
+                let scale = self.balance_scale(&mut tx, &[hold.account_id]).await?;
+                self.normalize_amount(amount, &hold.currency, scale)?
+            }
+            None => held,
+        };
+        if amount > held {
+            return Err(AppError::Rejected {
+                code: CAPTURE_EXCEEDS_HOLD,
+                message: format!("Cannot capture {} from a hold of {}", amount, held),
+            });
+        }
+
+        // The reserved funds become available again, for the transaction below
+        self.adjust_held_amount(&mut tx, hold.account_id, -held)
+            .await?;
+
+        let description = request.description.or(hold.description);
+        let (transaction, fee, conversion) = match request.receiver_account_id {
+            Some(receiver_account_id) => {
+                self.transfer_between_accounts(
+                    &mut tx,
+                    hold.account_id,
+                    receiver_account_id,
+                    amount,
+                    description,
+                    None,
+                    context,
+                    &mut milestones,
+                )
+                .await?
+            }
+            None => {
+                let (transaction, fee) = self
+                    .debit_to_external(
+                        &mut tx,
+                        hold.account_id,
+                        amount,
+                        description,
+                        None,
+                        request.destination_id,
+                        context,
+                        &mut milestones,
+                    )
+                    .await?;
+                (transaction, fee, TransactionConversion::default())
+            }
+        };
+        // A transfer into another currency is booked at the converted amount,
+        // but the hold was drawn on for the amount in its own currency
+        let captured = if transaction.currency == hold.currency {
+            transaction.amount
+        } else {
+            SqlxDecimal(amount)
+        };
+
+        let hold = sqlx::query_as::<_, Hold>(
+            r#"
+            UPDATE holds
+            SET status = $2, captured_amount = $3, transaction_id = $4
+            WHERE id = $1
+            RETURNING id, account_id, amount, currency, status, description,
+                      captured_amount, transaction_id, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .bind(HoldStatus::CAPTURED.to_string())
+        .bind(captured)
+        .bind(transaction.id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let transaction = TransactionResponse::from(transaction)
+            .with_conversion(conversion)
+            .with_fee(fee);
+        self.record_completed(&mut tx, &transaction).await?;
+
+        tx.commit().await?;
+        self.record_timings(&[transaction.id], milestones, context)
+            .await;
+        if transaction.receiver_account_id.is_some() {
+            if let Some(notifications) = &self.notifications {
+                notifications.spawn_transfer_receipts(&transaction);
+            }
+        }
+        self.announce(&transaction);
+
+        Ok(HoldCaptureResponse {
+            hold: HoldResponse::from(hold),
+            transaction,
+        })
+    }
+
+    /// Cancels an active hold, making its funds available again
+    ///
+    /// # Arguments
+    /// * `id` - The hold to release
+    ///
+    /// # Returns
+    /// The RELEASED hold
+    ///
+    /// A hold can only be released once, and not after it was captured; both
+    /// fail with `HOLD_NOT_ACTIVE`.
+    pub async fn release_hold(&self, id: Uuid) -> Result<HoldResponse, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let hold = self.lock_active_hold(&mut tx, id).await?;
+        self.adjust_held_amount(&mut tx, hold.account_id, -hold.amount.0)
+            .await?;
+        let hold = sqlx::query_as::<_, Hold>(
+            r#"
+            UPDATE holds SET status = $2 WHERE id = $1
+            RETURNING id, account_id, amount, currency, status, description,
+                      captured_amount, transaction_id, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .bind(HoldStatus::RELEASED.to_string())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(HoldResponse::from(hold))
+    }
+
+    /// Locks a hold for capture or release, rejecting it unless it is ACTIVE
+    async fn lock_active_hold(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: Uuid,
+    ) -> Result<Hold, AppError> {
+        let hold = sqlx::query_as::<_, Hold>(
+            r#"
+            SELECT id, account_id, amount, currency, status, description,
+                   captured_amount, transaction_id, created_at, updated_at
+            FROM holds WHERE id = $1 FOR UPDATE
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Hold with ID {} not found", id)))?;
+
+        if hold.status != HoldStatus::ACTIVE.to_string() {
+            return Err(AppError::Rejected {
+                code: HOLD_NOT_ACTIVE,
+                message: format!(
+                    "Hold {} has already been {}",
+                    id,
+                    hold.status.to_lowercase()
+                ),
+            });
+        }
+        Ok(hold)
+    }
+
+    /// Changes the funds reserved on an account by `amount`, unless it is closed
+    async fn adjust_held_amount(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: Uuid,
+        amount: Decimal,
+    ) -> Result<(), AppError> {
+        let updated = sqlx::query(
+            "UPDATE accounts SET held_amount = held_amount + $2 WHERE id = $1 AND closed_at IS NULL",
+        )
+        .bind(account_id)
+        .bind(SqlxDecimal(amount))
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+        if updated == 0 {
+            return Err(AppError::Rejected {
+                code: ACCOUNT_CLOSED,
+                message: format!("Account {} is closed", account_id),
+            });
+        }
+        Ok(())
+    }
+
+    /// Determines the amount to credit for a deposit, in the account currency
+    ///
+    /// # Arguments
+    /// * `request` - The deposit request, possibly carrying its own currency and rate
+    /// * `account_currency` - Currency of the account receiving the funds
+    ///
+    /// # Returns
+    /// The amount to book against the account
+    ///
+    /// # Implementation Details
+    /// Deposits without a currency, or in the account currency, are normalized to
+    /// the account's balance scale. A foreign-currency deposit is rejected unless
+    /// `allow_foreign_currency_deposits` is enabled and a positive exchange rate is
+    /// supplied, in which case the amount is converted and rounded half-even to the
+    /// account's balance scale.
+    fn resolve_deposit_amount(
+        &self,
+        request: &DepositRequest,
+        account_currency: &str,
+        scale: u32,
+    ) -> Result<Decimal, AppError> {
+        let currency = match &request.currency {
+            Some(currency) if !currency.eq_ignore_ascii_case(account_currency) => currency,
+            _ => return self.normalize_amount(request.amount, account_currency, scale),
+        };
+
+        if !self.config.allow_foreign_currency_deposits {
+            return Err(AppError::BadRequest(format!(
+                "Deposit currency {} does not match account currency {}",
+                currency, account_currency
+            )));
+        }
+
+        let rate = request
+            .exchange_rate
+            .filter(|rate| *rate > Decimal::ZERO)
+            .ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "A positive exchange rate is required to deposit {} into a {} account",
+                    currency, account_currency
+                ))
+            })?;
+
+        // Conversion naturally produces extra digits, so it is rounded even in strict mode
+        let converted = convert(
+            request.amount,
+            rate,
+            scale,
+            RoundingStrategy::MidpointNearestEven,
+        )?;
+
+        if converted <= Decimal::ZERO {
+            return Err(AppError::BadRequest(
+                "Converted deposit amount is too small".to_string(),
+            ));
+        }
+
+        self.check_amount_bounds(converted)
+    }
+
+    /// Normalizes a requested amount to the balance scale of the accounts it is booked on
+    ///
+    /// Excess precision is rounded half-even, or rejected when
+    /// `strict_amount_precision` is enabled. The result must lie within the
+    /// configured minimum and maximum transaction amounts.
+    fn normalize_amount(
+        &self,
+        amount: Decimal,
+        currency: &str,
+        scale: u32,
+    ) -> Result<Decimal, AppError> {
+        let amount = normalize_amount_to_scale(
+            amount,
+            scale,
+            currency,
+            self.config.strict_amount_precision,
+        )?;
+        self.check_amount_bounds(amount)
+    }
+
+    /// Rejects zero and negative amounts before anything is booked
+    ///
+    /// Requests arriving through the API are validated already, but the
+    /// `process_*` methods are also called directly, e.g. by the workers.
+    fn ensure_positive_amount(amount: Decimal) -> Result<(), AppError> {
+        if amount <= Decimal::ZERO {
+            return Err(AppError::BadRequest("Amount must be positive".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Rejects amounts outside the optional `min..=max` bounds configured for
+    /// one kind of transaction, on top of the general transaction bounds
+    fn check_kind_bounds(
+        amount: Decimal,
+        kind: &str,
+        min: Option<Decimal>,
+        max: Option<Decimal>,
+    ) -> Result<(), AppError> {
+        if let Some(min) = min.filter(|min| amount < *min) {
+            return Err(AppError::Validation(format!(
+                "{} amount must be at least {}",
+                kind,
+                min.normalize()
+            )));
+        }
+        if let Some(max) = max.filter(|max| amount > *max) {
+            return Err(AppError::Validation(format!(
+                "{} amount must not exceed {}",
+                kind,
+                max.normalize()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Rejects amounts outside `min_transaction_amount..=max_transaction_amount`
+    fn check_amount_bounds(&self, amount: Decimal) -> Result<Decimal, AppError> {
+        if amount < self.config.min_transaction_amount {
+            return Err(AppError::Validation(format!(
+                "Amount must be at least {}",
+                self.config.min_transaction_amount.normalize()
+            )));
+        }
+        if amount > self.config.max_transaction_amount {
+            return Err(AppError::Validation(format!(
+                "Amount must not exceed {}",
+                self.config.max_transaction_amount.normalize()
+            )));
+        }
+        Ok(amount)
+    }
+
+    /// Returns the fewest decimal places any of the accounts accepts
+    async fn balance_scale(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        account_ids: &[Uuid],
+    ) -> Result<u32, AppError> {
+        let scales = sqlx::query_as::<_, (String, Option<i16>)>(
+            "SELECT currency, balance_scale FROM accounts WHERE id = ANY($1)",
+        )
+        .bind(account_ids)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        scales
+            .iter()
+            .map(|(currency, scale)| balance_scale(*scale, currency))
+            .min()
+            .ok_or_else(|| AppError::NotFound("Account not found".to_string()))
+    }
+
+    /// Helper function to check that the user owning an account has verified their email
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use
+    /// * `account_id` - ID of the account whose owner is checked
+    /// * `party` - Which side of the transfer the account is on, for the error message
+    ///
+    /// # Returns
+    /// `AppError::Forbidden` if the owner's email address is not verified
+    async fn ensure_owner_verified(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: Uuid,
+        party: &str,
+    ) -> Result<(), AppError> {
+        let verified: bool = sqlx::query_scalar(
+            r#"
+            SELECT u.email_verified
+            FROM accounts a
+            JOIN users u ON u.id = a.user_id
+            WHERE a.id = $1
+            "#,
+        )
+        .bind(account_id)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        if !verified {
+            return Err(AppError::Forbidden(format!(
+                "{} account owner must verify their email for transfers of this size",
+                party
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Helper function to check a debit against the account's sending limits
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use; the account must already be locked
+    ///   by it, so concurrent debits are counted one after the other
+    /// * `account_id` - ID of the account the funds leave
+    /// * `amount` - Amount about to leave the account, in its currency
+    /// * `transaction_type` - WITHDRAWAL or TRANSFER, picking the daily limit
+    ///   that counts only that kind
+    ///
+    /// # Returns
+    /// `AppError::BadRequest` if the amount exceeds the per-transaction limit,
+    /// or would take what the account sent in the last 24 hours past the daily
+    /// limit. `AppError::LimitExceeded` if it would take what the account
+    /// withdrew or transferred out since midnight UTC past the daily limit for
+    /// that kind. Only completed transactions count; the sender's side of a
+    /// converted transfer counts in the sender's currency.
+    async fn check_account_limits(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: Uuid,
+        amount: Decimal,
+        transaction_type: TransactionType,
+    ) -> Result<(), AppError> {
+        let Some(limits) = sqlx::query_as::<_, AccountLimitsRow>(
+            r#"
+            SELECT per_transaction_limit, daily_limit, daily_withdrawal_limit, daily_transfer_limit
+            FROM account_limits WHERE account_id = $1
+            "#,
+        )
+        .bind(account_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .map(AccountLimits::from) else {
+            return Ok(());
+        };
+
+        if limits
+            .per_transaction_limit
+            .is_some_and(|limit| amount > limit)
+        {
+            return Err(AppError::BadRequest(
+                "Transaction limit exceeded".to_string(),
+            ));
+        }
+
+        if let Some(limit) = limits.daily_limit {
+            let sent: SqlxDecimal = sqlx::query_scalar(
+                r#"
+                SELECT COALESCE(SUM(COALESCE(original_amount, amount)), 0)
+                FROM transactions
+                WHERE sender_account_id = $1
+                  AND status = 'COMPLETED'
+                  AND transaction_type <> 'FEE'
+                  AND created_at > NOW() - INTERVAL '24 hours'
+                "#,
+            )
+            .bind(account_id)
+            .fetch_one(&mut **tx)
+            .await?;
+
+            if sent.0 + amount > limit {
+                return Err(AppError::BadRequest("Daily limit exceeded".to_string()));
+            }
+        }
+
+        let (kind, limit) = match transaction_type {
+            TransactionType::WITHDRAWAL => ("withdrawal", limits.daily_withdrawal_limit),
+            _ => ("transfer", limits.daily_transfer_limit),
+        };
+        if let Some(limit) = limit {
+            // Days start at midnight UTC, whatever the database's time zone
+            let sent_today: SqlxDecimal = sqlx::query_scalar(
+                r#"
+                SELECT COALESCE(SUM(COALESCE(original_amount, amount)), 0)
+                FROM transactions
+                WHERE sender_account_id = $1
+                  AND status = 'COMPLETED'
+                  AND transaction_type = $2
+                  AND created_at >= date_trunc('day', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+                "#,
+            )
+            .bind(account_id)
+            .bind(transaction_type.to_string())
+            .fetch_one(&mut **tx)
+            .await?;
+
+            if sent_today.0 + amount > limit {
+                return Err(AppError::LimitExceeded(format!(
+                    "Daily {} limit of {} exceeded",
+                    kind,
+                    limit.normalize()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Helper function to check a withdrawal's destination against the account owner's registry
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use
+    /// * `account_id` - ID of the account the funds leave
+    /// * `destination_id` - Destination named by the request, if any
+    ///
+    /// # Returns
+    /// `AppError::Rejected` with one of the `DESTINATION_*` codes if a required
+    /// destination is missing, or the destination is unknown or removed, was
+    /// registered by someone other than the account owner, or isn't verified yet
+    ///
+    /// # Implementation Details
+    /// The destination row is share-locked, so it can't be removed while the
+    /// withdrawal is being booked.
+    async fn check_destination(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: Uuid,
+        destination_id: Option<Uuid>,
+    ) -> Result<(), AppError> {
+        let Some(destination_id) = destination_id else {
+            if self.config.require_withdrawal_destination {
+                return Err(AppError::Rejected {
+                    code: DESTINATION_REQUIRED,
+                    message: "Withdrawals must name a registered destination".to_string(),
+                });
+            }
+            return Ok(());
+        };
+
+        let destination = sqlx::query_as::<_, WithdrawalDestination>(
+            r#"
+            SELECT id, user_id, label, identifier, verified_at, removed_at, created_at
+            FROM withdrawal_destinations
+            WHERE id = $1 AND removed_at IS NULL
+            FOR SHARE
+            "#,
+        )
+        .bind(destination_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| AppError::Rejected {
+            code: DESTINATION_NOT_FOUND,
+            message: format!("Destination with ID {} not found", destination_id),
+        })?;
+
+        let owner_id: Uuid = sqlx::query_scalar("SELECT user_id FROM accounts WHERE id = $1")
+            .bind(account_id)
+            .fetch_one(&mut **tx)
+            .await?;
+        if destination.user_id != owner_id {
+            return Err(AppError::Rejected {
+                code: DESTINATION_NOT_OWNED,
+                message: "Destination is not registered to the account owner".to_string(),
+            });
+        }
+
+        if !destination.is_verified(Utc::now()) {
+            return Err(AppError::Rejected {
+                code: DESTINATION_UNVERIFIED,
+                message: "Destination has not been verified yet".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Helper function to create a transaction record in the database
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use
+    /// * `id` - Unique ID for the transaction
+    /// * `sender_account_id` - Optional sender account ID
+    /// * `receiver_account_id` - Optional receiver account ID
+    /// * `amount` - Transaction amount
+    /// * `currency` - Currency code
+    /// * `transaction_type` - Type of transaction (TRANSFER, DEPOSIT, WITHDRAWAL)
+    /// * `description` - Optional transaction description
+    ///
+    /// # Returns
+    /// The created transaction record
+    ///
+    /// # Implementation Note
+    /// Every value is a bound parameter, so client-supplied text such as the
+    /// description is stored verbatim and can never alter the statement. The
+    /// transaction is created in PENDING status initially.
+    #[allow(clippy::too_many_arguments)]
+    async fn create_transaction_record(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: Uuid,
+        sender_account_id: Option<Uuid>,
+        receiver_account_id: Option<Uuid>,
+        amount: Decimal,
+        currency: String,
+        transaction_type: String,
+        description: Option<String>,
+    ) -> Result<Transaction, AppError> {
+        // We explicitly cast the amount to TEXT in the RETURNING clause
+        // for consistent handling of our custom decimal type
+        let row = sqlx::query(
+            "INSERT INTO transactions
+            (id, sender_account_id, receiver_account_id, amount, currency, transaction_type, status, description)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, sender_account_id, receiver_account_id, amount::TEXT, currency,
+                     transaction_type, status, description, created_at, updated_at",
+        )
+        .bind(id)
+        .bind(sender_account_id)
+        .bind(receiver_account_id)
+        .bind(SqlxDecimal(amount))
+        .bind(currency)
+        .bind(transaction_type)
+        .bind(TransactionStatus::PENDING.to_string()) // All transactions start as PENDING
+        .bind(description)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        // Manually construct the Transaction struct from the SQL row
+        let transaction = Transaction {
+            id: sqlx::Row::get(&row, "id"),
+            sender_account_id: sqlx::Row::get(&row, "sender_account_id"),
+            receiver_account_id: sqlx::Row::get(&row, "receiver_account_id"),
+            amount: SqlxDecimal(
+                sqlx::Row::get::<&str, _>(&row, "amount")
+                    .parse()
+                    .unwrap_or(Decimal::ZERO),
+            ),
+            currency: sqlx::Row::get(&row, "currency"),
+            transaction_type: sqlx::Row::get(&row, "transaction_type"),
+            status: sqlx::Row::get(&row, "status"),
+            description: sqlx::Row::get(&row, "description"),
+            created_at: sqlx::Row::get(&row, "created_at"),
+            updated_at: sqlx::Row::get(&row, "updated_at"),
+        };
+
+        Ok(transaction)
+    }
+
+    /// Helper function to store the client that initiated a transaction
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use
+    /// * `transaction_id` - ID of the transaction being recorded
+    /// * `context` - IP address and user agent of the client
+    async fn record_transaction_origin(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction_id: Uuid,
+        context: &RequestContext,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE transactions SET ip_address = $2, user_agent = $3 WHERE id = $1
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(&context.ip_address)
+        .bind(&context.user_agent)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Helper function to store the identifier an external processor gave a
+    /// transaction
+    ///
+    /// Does nothing without a reference. A reference already stored on another
+    /// transaction fails with `AppError::Conflict`.
+    async fn record_external_reference(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction_id: Uuid,
+        external_reference: Option<&str>,
+    ) -> Result<(), AppError> {
+        let Some(external_reference) = external_reference else {
+            return Ok(());
+        };
+
+        sqlx::query("UPDATE transactions SET external_reference = $2 WHERE id = $1")
+            .bind(transaction_id)
+            .bind(external_reference)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Helper function to store what the sender of a converted transfer paid
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use
+    /// * `transaction_id` - ID of the transfer, booked in the receiver's currency
+    /// * `amount` - Amount debited from the sender
+    /// * `currency` - Currency of the sender's account
+    /// * `rate` - Exchange rate applied, if any
+    async fn record_conversion(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction_id: Uuid,
+        amount: Decimal,
+        currency: &str,
+        rate: Option<Decimal>,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE transactions
+            SET original_amount = $2, original_currency = $3, exchange_rate = $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(SqlxDecimal(amount))
+        .bind(currency)
+        .bind(rate.map(SqlxDecimal))
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Fee the payer of a transfer or withdrawal pays on top of `amount`, as
+    /// computed by the fee service, rounded half-even to the payer's balance
+    /// scale
+    ///
+    /// Transactions out of the fee account itself are free.
+    async fn fee_for(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction_type: TransactionType,
+        payer_account_id: Uuid,
+        payer_currency: &str,
+        amount: Decimal,
+    ) -> Result<Decimal, AppError> {
+        if self.config.fee_account_id == Some(payer_account_id) {
+            return Ok(Decimal::ZERO);
+        }
+
+        let scale = self.balance_scale(tx, &[payer_account_id]).await?;
+        self.fees
+            .fee_for(tx, &transaction_type, payer_currency, amount, scale)
+            .await
+    }
+
+    /// Helper function to book the fee charged on a transfer or withdrawal
+    ///
+    /// Stores the fee and its currency on the charged transaction and debits
+    /// it from the payer with a completed FEE transaction, whose
+    /// `parent_transaction_id` is the charged transaction. The FEE transaction
+    /// credits the fee account when one is set, converted at the current rate
+    /// if the fee account uses another currency; without one, the fee leaves
+    /// the ledger. The caller checks the payer can cover the fee along with
+    /// the transaction.
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction that also books the charged transaction
+    /// * `parent_id` - ID of the transaction the fee is charged on
+    /// * `payer_account_id` - Account paying the fee
+    /// * `payer_currency` - Currency of the payer's account, which the fee is in
+    /// * `fee` - The fee, as returned by `fee_for`
+    async fn book_fee(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        parent_id: Uuid,
+        payer_account_id: Uuid,
+        payer_currency: &str,
+        fee: Decimal,
+    ) -> Result<(), AppError> {
+        let fee_account = match self.config.fee_account_id {
+            Some(fee_account_id) => {
+                let currency = sqlx::query_scalar::<_, String>(
+                    "SELECT currency FROM accounts WHERE id = $1 FOR UPDATE",
+                )
+                .bind(fee_account_id)
+                .fetch_optional(&mut **tx)
+                .await?
+                .ok_or_else(|| {
+                    AppError::Internal(format!("Fee account {} not found", fee_account_id))
+                })?;
+                Some((fee_account_id, currency))
+            }
+            None => None,
+        };
+
+        let rate = match &fee_account {
+            Some((_, currency)) if currency != payer_currency => {
+                Some(self.exchange.get_rate(payer_currency, currency).await?)
+            }
+            _ => None,
+        };
+        let credited = match (&fee_account, rate) {
+            (Some((fee_account_id, _)), Some(rate)) => {
+                let scale = self.balance_scale(tx, &[*fee_account_id]).await?;
+                convert(fee, rate, scale, RoundingStrategy::MidpointNearestEven)?
+            }
+            _ => fee,
+        };
+        if credited <= Decimal::ZERO {
+            return Err(AppError::BadRequest(
+                "Converted fee is too small".to_string(),
+            ));
+        }
+
+        sqlx::query("UPDATE transactions SET fee_amount = $2, fee_currency = $3 WHERE id = $1")
+            .bind(parent_id)
+            .bind(SqlxDecimal(fee))
+            .bind(payer_currency)
+            .execute(&mut **tx)
+            .await?;
+
+        let fee_transaction_id = Uuid::new_v4();
+        self.create_transaction_record(
+            tx,
+            fee_transaction_id,
+            Some(payer_account_id),
+            fee_account.as_ref().map(|(id, _)| *id),
+            credited,
+            fee_account.as_ref().map_or_else(
+                || payer_currency.to_string(),
+                |(_, currency)| currency.clone(),
+            ),
+            TransactionType::FEE.to_string(),
+            Some(format!("Fee for transaction {}", parent_id)),
+        )
+        .await?;
+        if let Some(rate) = rate {
+            self.record_conversion(tx, fee_transaction_id, fee, payer_currency, Some(rate))
+                .await?;
+        }
+        sqlx::query("UPDATE transactions SET parent_transaction_id = $2 WHERE id = $1")
+            .bind(fee_transaction_id)
+            .bind(parent_id)
+            .execute(&mut **tx)
+            .await?;
+
+        self.update_account_balance(tx, payer_account_id, -fee, fee_transaction_id)
+            .await?;
+        if let Some((fee_account_id, _)) = fee_account {
+            self.update_account_balance(tx, fee_account_id, credited, fee_transaction_id)
+                .await?;
+        }
+        self.update_transaction_status(
+            tx,
+            fee_transaction_id,
+            TransactionStatus::COMPLETED.to_string(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Helper function to give back the fee charged on a transaction
+    ///
+    /// Books a completed FEE transaction in the opposite direction of the one
+    /// `book_fee` booked: from the fee account back to the payer or, if the fee
+    /// left the ledger, into the payer's account from outside it. The refund
+    /// points at the fee through `reverses_transaction_id` and, like the fee,
+    /// at the charged transaction through `parent_transaction_id`; the fee is
+    /// marked REVERSED, pointing back through `reversed_by`. Like a reversed
+    /// transfer, the payer gets back exactly what it paid, without converting
+    /// again.
+    ///
+    /// # Arguments
+    /// * `tx` - Database transaction to use
+    /// * `parent_id` - ID of the transaction the fee was charged on
+    /// * `context` - Client that asked for the refund, stored on it
+    ///
+    /// # Returns
+    /// The refund, or `None` if no fee was charged or it was already refunded
+    async fn refund_fee(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        parent_id: Uuid,
+        context: &RequestContext,
+    ) -> Result<Option<Transaction>, AppError> {
+        let fee = sqlx::query_as::<_, (Uuid, Option<Uuid>, Option<Uuid>, SqlxDecimal, String)>(
+            r#"
+            SELECT id, sender_account_id, receiver_account_id, amount, currency
+            FROM transactions
+            WHERE parent_transaction_id = $1 AND transaction_type = 'FEE' AND status = 'COMPLETED'
+              AND reverses_transaction_id IS NULL
+            FOR UPDATE
+            "#,
+        )
+        .bind(parent_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+        let Some((fee_id, Some(payer_account_id), fee_account_id, credited, fee_account_currency)) =
+            fee
+        else {
+            return Ok(None);
+        };
+        let (paid, payer_currency): (Option<SqlxDecimal>, Option<String>) =
+            sqlx::query_as("SELECT fee_amount, fee_currency FROM transactions WHERE id = $1")
+                .bind(parent_id)
+                .fetch_one(&mut **tx)
+                .await?;
+        let (Some(paid), Some(payer_currency)) = (paid, payer_currency) else {
+            return Ok(None);
+        };
+
+        // The fee account must still hold what it was credited
+        if let Some(fee_account_id) = fee_account_id {
+            let available = sqlx::query_scalar::<_, SqlxDecimal>(
+                "SELECT balance - held_amount FROM accounts WHERE id = $1 FOR UPDATE",
+            )
+            .bind(fee_account_id)
+            .fetch_one(&mut **tx)
+            .await?;
+            if available.0 < credited.0 {
+                return Err(AppError::Rejected {
+                    code: REVERSAL_INSUFFICIENT_FUNDS,
+                    message: "The fee account no longer has enough funds to refund the fee"
+                        .to_string(),
+                });
+            }
+        }
+
+        let refund_id = Uuid::new_v4();
+        self.create_transaction_record(
+            tx,
+            refund_id,
+            fee_account_id,
+            Some(payer_account_id),
+            paid.0,
+            payer_currency.clone(),
+            TransactionType::FEE.to_string(),
+            Some(format!("Refund of fee for transaction {}", parent_id)),
+        )
+        .await?;
+        if fee_account_id.is_some() && fee_account_currency != payer_currency {
+            self.record_conversion(tx, refund_id, credited.0, &fee_account_currency, None)
+                .await?;
+        }
+        self.record_transaction_origin(tx, refund_id, context)
+            .await?;
+        sqlx::query(
+            "UPDATE transactions SET reverses_transaction_id = $2, parent_transaction_id = $3 WHERE id = $1",
+        )
+        .bind(refund_id)
+        .bind(fee_id)
+        .bind(parent_id)
+        .execute(&mut **tx)
+        .await?;
+
+        if let Some(fee_account_id) = fee_account_id {
+            self.update_account_balance(tx, fee_account_id, -credited.0, refund_id)
+                .await?;
+        }
+        self.update_account_balance(tx, payer_account_id, paid.0, refund_id)
+            .await?;
+        let refund = self
+            .update_transaction_status(tx, refund_id, TransactionStatus::COMPLETED.to_string())
+            .await?;
+
+        sqlx::query("UPDATE transactions SET reversed_by = $2 WHERE id = $1")
+            .bind(fee_id)
+            .bind(refund_id)
+            .execute(&mut **tx)
+            .await?;
+        self.update_transaction_status(tx, fee_id, TransactionStatus::REVERSED.to_string())
+            .await?;
+
+        Ok(Some(refund))
+    }
+
+    /// Helper function to store the category of a transaction
+    async fn record_transaction_category(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction_id: Uuid,
+        category: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query("UPDATE transactions SET category = $2 WHERE id = $1")
+            .bind(transaction_id)
+            .bind(category)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
     }
 
-    /// Processes a deposit into an account
-    ///
-    /// A deposit represents money coming into the system from outside.
-    /// For example, this could be a bank transfer, cash deposit, or other external funds.
+    /// Rejects a transfer identical to one made within the configured
+    /// `duplicate_transfer_window`, most likely the same form submitted twice
     ///
-    /// # Arguments
-    /// * `request` - Deposit request containing account ID, amount, and description
+    /// Identical means the same sender, receiver, amount in the sender's
+    /// currency and description, among completed and pending transfers.
     ///
     /// # Returns
-    /// The completed transaction response upon success
+    /// `AppError::DuplicateSuspected` with the earlier transfer's ID
     ///
     /// # Implementation Details
-    /// This method:
-    /// 1. Begins a database transaction for atomicity
-    /// 2. Validates the target account exists
-    /// 3. Creates a pending transaction record with no sender (external source)
-    /// 4. Updates the account balance
-    /// 5. Marks the transaction as completed
-    /// 6. Commits the database transaction
-    pub async fn process_deposit(
+    /// With `RowLock::ForUpdate` the sender is locked first, as
+    /// `transfer_between_accounts` does next, so of two identical requests
+    /// racing each other the second waits for the first to commit and then
+    /// finds it.
+    async fn ensure_not_duplicate(
         &self,
-        request: DepositRequest,
-    ) -> Result<TransactionResponse, AppError> {
-        // Start a database transaction to ensure atomicity of operations
-        let mut tx = self.pool.begin().await?;
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        request: &TransferRequest,
+        lock: RowLock,
+    ) -> Result<(), AppError> {
+        let Some(window) = self.config.duplicate_transfer_window else {
+            return Ok(());
+        };
 
-        // Verify account exists and lock it for update to prevent race conditions
-        let account = sqlx::query!(
+        if lock == RowLock::ForUpdate {
+            sqlx::query("SELECT id FROM accounts WHERE id = $1 FOR UPDATE")
+                .bind(request.sender_account_id)
+                .execute(&mut **tx)
+                .await?;
+        }
+        let earlier = sqlx::query_scalar::<_, Uuid>(
             r#"
-            SELECT id, currency FROM accounts WHERE id = $1 FOR UPDATE
+            SELECT id FROM transactions
+            WHERE sender_account_id = $1 AND receiver_account_id = $2
+              AND transaction_type = 'TRANSFER' AND status IN ('COMPLETED', 'PENDING')
+              AND COALESCE(original_amount, amount) = $3
+              AND description IS NOT DISTINCT FROM $4
+              AND created_at > clock_timestamp() - make_interval(secs => $5)
+            ORDER BY created_at DESC
+            LIMIT 1
             "#,
-            request.account_id
         )
-        .fetch_optional(&mut *tx)
-        .await?
-        .ok_or_else(|| {
-            AppError::NotFound(format!("Account with ID {} not found", request.account_id))
-        })?;
-
-        // Create a transaction record with no sender_account_id (money comes from outside)
-        // but with the receiver_account_id set to the deposit account
-        let transaction_id = Uuid::new_v4();
-        let _transaction = self
-            .create_transaction_record(
-                &mut tx,
-                transaction_id,
-                None, // No sender account for deposits (external source)
-                Some(request.account_id),
-                request.amount,
-                account.currency.clone(),
-                TransactionType::DEPOSIT.to_string(),
-                request.description,
-            )
-            .await?;
-
-        // Increase the account balance by the deposit amount
-        // Since deposits always increase the balance, we pass a positive amount
-        self.update_account_balance(&mut tx, request.account_id, request.amount)
-            .await?;
-
-        // Update transaction status to COMPLETED
-        let updated_transaction = self
-            .update_transaction_status(
-                &mut tx,
-                transaction_id,
-                TransactionStatus::COMPLETED.to_string(),
-            )
-            .await?;
-
-        // Commit all changes as a single atomic operation
-        tx.commit().await?;
+        .bind(request.sender_account_id)
+        .bind(request.receiver_account_id)
+        .bind(SqlxDecimal(request.amount))
+        .bind(&request.description)
+        .bind(window.as_secs_f64())
+        .fetch_optional(&mut **tx)
+        .await?;
 
-        // Return transaction details
-        Ok(TransactionResponse::from(updated_transaction))
+        match earlier {
+            Some(transaction_id) => Err(AppError::DuplicateSuspected { transaction_id }),
+            None => Ok(()),
+        }
     }
 
-    /// Processes a withdrawal from an account
-    ///
-    /// A withdrawal represents money leaving the system entirely.
-    /// For example, this could be an ATM withdrawal, bank transfer out, or other external payment.
+    /// Helper function to claim an idempotency key inside a database transaction
     ///
     /// # Arguments
-    /// * `request` - Withdrawal request containing account ID, amount, and description
+    /// * `tx` - Database transaction that will also book the request
+    /// * `idempotency` - The key sent with the request, if any
     ///
     /// # Returns
-    /// The completed transaction response upon success
+    /// The stored response if the key was already used for the same request,
+    /// or `None` if the request should be booked
     ///
     /// # Implementation Details
-    /// This method:
-    /// 1. Begins a database transaction for atomicity
-    /// 2. Validates the source account exists
-    /// 3. Verifies the account has sufficient funds
-    /// 4. Creates a pending transaction record with no receiver (external destination)
-    /// 5. Updates the account balance
-    /// 6. Marks the transaction as completed
-    /// 7. Commits the database transaction
-    pub async fn process_withdrawal(
+    /// The key is inserted before anything is booked. A concurrent request with
+    /// the same key blocks on that insert until this transaction ends, then
+    /// either replays the stored response or, if this one rolled back, claims
+    /// the key itself. A key reused for a different request is a `Conflict`.
+    /// The user's expired keys are deleted first, so an expired key is claimed
+    /// afresh instead of replaying its old response.
+    async fn claim_idempotency_key(
         &self,
-        request: WithdrawalRequest,
-    ) -> Result<TransactionResponse, AppError> {
-        // Start a database transaction to ensure atomicity
-        let mut tx = self.pool.begin().await?;
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        idempotency: Option<&IdempotencyKey>,
+    ) -> Result<Option<TransactionResponse>, AppError> {
+        let Some(idempotency) = idempotency else {
+            return Ok(None);
+        };
 
-        // Verify account exists and lock it for update
-        let account = sqlx::query!(
+        sqlx::query(
             r#"
-            SELECT id, currency, balance FROM accounts WHERE id = $1 FOR UPDATE
+            DELETE FROM idempotency_keys
+            WHERE user_id = $1 AND created_at <= NOW() - make_interval(hours => $2)
             "#,
-            request.account_id
         )
-        .fetch_optional(&mut *tx)
+        .bind(idempotency.user_id)
+        .bind(IDEMPOTENCY_KEY_TTL_HOURS)
+        .execute(&mut **tx)
+        .await?;
+
+        let claimed = sqlx::query(
+            r#"
+            INSERT INTO idempotency_keys (user_id, key, request_hash)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, key) DO NOTHING
+            "#,
+        )
+        .bind(idempotency.user_id)
+        .bind(&idempotency.key)
+        .bind(&idempotency.request_hash)
+        .execute(&mut **tx)
         .await?
-        .ok_or_else(|| {
-            AppError::NotFound(format!("Account with ID {} not found", request.account_id))
-        })?;
+        .rows_affected();
+        if claimed == 1 {
+            return Ok(None);
+        }
 
-        // Ensure sufficient balance for withdrawal - prevent overdrafts
-        // Use raw query to get balance as text for precise decimal handling
-        let query = format!(
-            "SELECT balance::TEXT FROM accounts WHERE id = '{}' FOR UPDATE",
-            request.account_id
-        );
+        let (request_hash, response): (String, Option<serde_json::Value>) = sqlx::query_as(
+            "SELECT request_hash, response FROM idempotency_keys WHERE user_id = $1 AND key = $2",
+        )
+        .bind(idempotency.user_id)
+        .bind(&idempotency.key)
+        .fetch_one(&mut **tx)
+        .await?;
 
-        let row = sqlx::query(&query).fetch_one(&mut *tx).await?;
+        if request_hash != idempotency.request_hash {
+            return Err(AppError::Conflict(
+                "Idempotency key was already used for a different request".to_string(),
+            ));
+        }
 
-        // Parse balance from text to Decimal for accurate comparison
-        let account_balance: Decimal = sqlx::Row::get::<&str, _>(&row, "balance")
-            .parse()
-            .unwrap_or(Decimal::ZERO);
+        let response = response.ok_or_else(|| {
+            AppError::Internal("Idempotency key has no stored response".to_string())
+        })?;
+        serde_json::from_value(response)
+            .map(Some)
+            .map_err(|e| AppError::Internal(format!("Failed to read stored response: {}", e)))
+    }
 
-        // Verify sufficient funds
-        if account_balance < request.amount {
-            return Err(AppError::BadRequest("Insufficient funds".to_string()));
-        }
+    /// Helper function to store the response of a request booked under an idempotency key
+    ///
+    /// Runs in the same database transaction as the booking, so the key and the
+    /// money movement are committed together or not at all.
+    async fn store_idempotent_response(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        idempotency: Option<&IdempotencyKey>,
+        response: &TransactionResponse,
+    ) -> Result<(), AppError> {
+        let Some(idempotency) = idempotency else {
+            return Ok(());
+        };
 
-        // Create transaction record with sender_account_id set but no receiver_account_id
-        // This pattern indicates money leaving the system to an external destination
-        let transaction_id = Uuid::new_v4();
-        let _transaction = self
-            .create_transaction_record(
-                &mut tx,
-                transaction_id,
-                Some(request.account_id),
-                None, // No receiver account for withdrawals (external destination)
-                request.amount,
-                account.currency.clone(),
-                TransactionType::WITHDRAWAL.to_string(),
-                request.description,
-            )
-            .await?;
+        let transaction_id = response.id;
+        let response = serde_json::to_value(response)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize response: {}", e)))?;
+        sqlx::query(
+            r#"
+            UPDATE idempotency_keys SET response = $3, transaction_id = $4
+            WHERE user_id = $1 AND key = $2
+            "#,
+        )
+        .bind(idempotency.user_id)
+        .bind(&idempotency.key)
+        .bind(response)
+        .bind(transaction_id)
+        .execute(&mut **tx)
+        .await?;
 
-        // Decrease account balance by withdrawal amount
-        // Negative amount indicates funds leaving the account
-        self.update_account_balance(&mut tx, request.account_id, -request.amount)
-            .await?;
+        Ok(())
+    }
 
-        // Update transaction status to COMPLETED
-        let updated_transaction = self
-            .update_transaction_status(
-                &mut tx,
-                transaction_id,
-                TransactionStatus::COMPLETED.to_string(),
-            )
+    /// Helper function to link a transaction to the split transfer it is a leg of
+    async fn record_split_transfer(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction_id: Uuid,
+        split_transfer_id: Uuid,
+    ) -> Result<(), AppError> {
+        sqlx::query("UPDATE transactions SET split_transfer_id = $2 WHERE id = $1")
+            .bind(transaction_id)
+            .bind(split_transfer_id)
+            .execute(&mut **tx)
             .await?;
 
-        // Commit all changes as a single atomic operation
-        tx.commit().await?;
-
-        // Return transaction details
-        Ok(TransactionResponse::from(updated_transaction))
+        Ok(())
     }
 
-    /// Helper function to create a transaction record in the database
-    ///
-    /// # Arguments
-    /// * `tx` - Database transaction to use
-    /// * `id` - Unique ID for the transaction
-    /// * `sender_account_id` - Optional sender account ID
-    /// * `receiver_account_id` - Optional receiver account ID
-    /// * `amount` - Transaction amount
-    /// * `currency` - Currency code
-    /// * `transaction_type` - Type of transaction (TRANSFER, DEPOSIT, WITHDRAWAL)
-    /// * `description` - Optional transaction description
-    ///
-    /// # Returns
-    /// The created transaction record
+    /// Stores the timings of committed transactions and logs their durations
     ///
-    /// # Implementation Note
-    /// This uses raw SQL queries due to complexities with the SQLx type system and our
-    /// custom SqlxDecimal type. The transaction is created in PENDING status initially.
-    async fn create_transaction_record(
+    /// Runs after the commit, so failures are logged and never undo the
+    /// transactions. Every leg of a split transfer gets the same milestones.
+    async fn record_timings(
         &self,
-        tx: &mut SqlxTransaction<'_, Postgres>,
-        id: Uuid,
-        sender_account_id: Option<Uuid>,
-        receiver_account_id: Option<Uuid>,
-        amount: Decimal,
-        currency: String,
-        transaction_type: String,
-        description: Option<String>,
-    ) -> Result<Transaction, AppError> {
-        // Format nullable fields for SQL insertion
-        // Using NULL for SQL when the field is None
-        let sender_id_str = match sender_account_id {
-            Some(id) => format!("'{}'", id),
-            None => "NULL".to_string(),
-        };
-
-        let receiver_id_str = match receiver_account_id {
-            Some(id) => format!("'{}'", id),
-            None => "NULL".to_string(),
-        };
+        transaction_ids: &[Uuid],
+        milestones: Milestones,
+        context: &RequestContext,
+    ) {
+        let timings = milestones.committed(context.request_id.clone(), &self.instance_id);
 
-        // Handle SQL injection prevention for the description field
-        // Escape single quotes in the description text
-        let description_str = match &description {
-            Some(desc) => format!("'{}'", desc.replace("'", "''")), // Escape single quotes
-            None => "NULL".to_string(),
-        };
+        let result = sqlx::query(
+            r#"
+            INSERT INTO transaction_timings
+                (transaction_id, request_id, instance_id, received_at, lock_acquired_at, committed_at)
+            SELECT id, $2, $3, $4, $5, $6 FROM UNNEST($1::UUID[]) AS id
+            "#,
+        )
+        .bind(transaction_ids)
+        .bind(&timings.request_id)
+        .bind(&timings.instance_id)
+        .bind(timings.received_at)
+        .bind(timings.lock_acquired_at)
+        .bind(timings.committed_at)
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = result {
+            tracing::error!("Failed to record transaction timings: {}", e);
+        }
 
-        // Construct and execute the raw SQL query
-        // We explicitly cast the amount to TEXT in the RETURNING clause
-        // for consistent handling of our custom decimal type
-        let query = format!(
-            "INSERT INTO transactions 
-            (id, sender_account_id, receiver_account_id, amount, currency, transaction_type, status, description)
-            VALUES ('{}', {}, {}, '{}', '{}', '{}', '{}', {})
-            RETURNING id, sender_account_id, receiver_account_id, amount::TEXT, currency, 
-                     transaction_type, status, description, created_at, updated_at",
-            id,
-            sender_id_str,
-            receiver_id_str,
-            amount.to_string(),
-            currency,
-            transaction_type,
-            TransactionStatus::PENDING.to_string(), // All transactions start as PENDING
-            description_str
+        tracing::debug!(
+            transaction_ids = ?transaction_ids,
+            request_id = timings.request_id.as_deref().unwrap_or_default(),
+            queue_ms = timings.queue_time().num_milliseconds(),
+            processing_ms = timings.processing_time().num_milliseconds(),
+            "Transaction committed"
         );
+    }
 
-        let row = sqlx::query(&query).fetch_one(&mut **tx).await?;
-
-        // Manually construct the Transaction struct from the SQL row
-        // This is needed because we can't use query_as! with our dynamic query
-        let transaction = Transaction {
-            id: sqlx::Row::get(&row, "id"),
-            sender_account_id: sqlx::Row::get(&row, "sender_account_id"),
-            receiver_account_id: sqlx::Row::get(&row, "receiver_account_id"),
-            amount: SqlxDecimal(
-                sqlx::Row::get::<&str, _>(&row, "amount")
-                    .parse()
-                    .unwrap_or(Decimal::ZERO),
-            ),
-            currency: sqlx::Row::get(&row, "currency"),
-            transaction_type: sqlx::Row::get(&row, "transaction_type"),
-            status: sqlx::Row::get(&row, "status"),
-            description: sqlx::Row::get(&row, "description"),
-            created_at: sqlx::Row::get(&row, "created_at"),
-            updated_at: sqlx::Row::get(&row, "updated_at"),
+    /// Raises budget alerts for a committed, categorized outgoing transaction
+    ///
+    /// Runs after the commit, so failures are logged and never undo the transaction.
+    async fn check_budgets(&self, transaction: &TransactionResponse, category: Option<&str>) {
+        let (Some(category), Some(account_id)) = (category, transaction.sender_account_id) else {
+            return;
         };
 
-        Ok(transaction)
+        if let Err(e) = self
+            .budgets
+            .check_budgets(account_id, category, transaction.id, transaction.created_at)
+            .await
+        {
+            tracing::error!(
+                transaction_id = %transaction.id,
+                "Could not check budgets: {}",
+                e
+            );
+        }
     }
 
     /// Helper function to update an account balance within a database transaction
@@ -593,34 +4084,44 @@ impl TransactionService {
     /// * `tx` - Database transaction to use
     /// * `account_id` - ID of the account to update
     /// * `amount` - Amount to add to the balance (negative for subtraction)
+    /// * `transaction_id` - The transaction moving the money
     ///
     /// # Returns
     /// Nothing if successful, error otherwise
     ///
     /// # Implementation Note
-    /// This uses a raw SQL query to avoid issues with the SQLx type system and
-    /// our custom SqlxDecimal type. The account balance check is handled at the
-    /// database level with a CHECK constraint.
+    /// Delegates to `AccountService::change_balance`, which records the new
+    /// balance as a snapshot of `transaction_id`.
     async fn update_account_balance(
         &self,
         tx: &mut SqlxTransaction<'_, Postgres>,
         account_id: Uuid,
         amount: Decimal,
+        transaction_id: Uuid,
     ) -> Result<(), AppError> {
-        // Convert Decimal to string for PostgreSQL compatibility using raw query
-        // This precision-preserving conversion is critical for financial calculations
-        let query = format!(
-            "UPDATE accounts
-             SET balance = balance + '{}',
-                 updated_at = NOW()
-             WHERE id = '{}'",
-            amount.to_string(),
-            account_id
-        );
+        self.account_service
+            .change_balance(tx, account_id, amount, Some(transaction_id))
+            .await
+    }
 
-        // Execute the query within the provided transaction
-        // The database constraint balance_non_negative will prevent negative balances
-        sqlx::query(&query).execute(&mut **tx).await?;
+    /// Rejects the transaction if any of the accounts it touches isn't ACTIVE
+    ///
+    /// Must be called once the accounts are locked, so a concurrent freeze is
+    /// either already visible or waits for this transaction to finish.
+    async fn ensure_accounts_active(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        account_ids: &[Uuid],
+    ) -> Result<(), AppError> {
+        let statuses = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, status FROM accounts WHERE id = ANY($1) ORDER BY id",
+        )
+        .bind(account_ids)
+        .fetch_all(&mut **tx)
+        .await?;
+        for (account_id, status) in statuses {
+            ensure_account_active(account_id, &status)?;
+        }
 
         Ok(())
     }
@@ -636,7 +4137,6 @@ impl TransactionService {
     /// The updated transaction record
     ///
     /// # Implementation Note
-    /// This uses a raw SQL query for consistency with our other methods.
     /// The updated transaction's fields are returned for audit purposes.
     async fn update_transaction_status(
         &self,
@@ -644,18 +4144,17 @@ impl TransactionService {
         transaction_id: Uuid,
         status: String,
     ) -> Result<Transaction, AppError> {
-        // Use raw query to bypass type checking challenges
-        let query = format!(
+        let row = sqlx::query(
             "UPDATE transactions
-             SET status = '{}',
-                 updated_at = NOW()
-             WHERE id = '{}'
-             RETURNING id, sender_account_id, receiver_account_id, amount::TEXT, currency, 
+             SET status = $2
+             WHERE id = $1
+             RETURNING id, sender_account_id, receiver_account_id, amount::TEXT, currency,
                       transaction_type, status, description, created_at, updated_at",
-            status, transaction_id
-        );
-
-        let row = sqlx::query(&query).fetch_one(&mut **tx).await?;
+        )
+        .bind(transaction_id)
+        .bind(status)
+        .fetch_one(&mut **tx)
+        .await?;
 
         // Manually create the Transaction struct from row data
         let transaction = Transaction {
@@ -678,3 +4177,146 @@ impl TransactionService {
         Ok(transaction)
     }
 }
+
+/// Builds the query listing an account's transactions, newest first, each
+/// with the account's balance right after it
+///
+/// Without a `limit` every matching transaction is listed.
+fn account_transactions_query(
+    account_id: Uuid,
+    filter: &TransactionFilter,
+    before: Option<&TransactionCursor>,
+    limit: Option<i64>,
+    offset: i64,
+) -> QueryAs<'static, Postgres, AccountTransaction, PgArguments> {
+    sqlx::query_as::<_, AccountTransaction>(
+        r#"
+        WITH ledger AS (
+            SELECT id,
+                   SUM(CASE WHEN status NOT IN ('COMPLETED', 'REVERSED') THEN 0
+                            WHEN receiver_account_id = $1 THEN amount
+                            ELSE -COALESCE(original_amount, amount) END)
+                       OVER (ORDER BY created_at DESC, id DESC
+                             ROWS BETWEEN UNBOUNDED PRECEDING AND 1 PRECEDING)
+                       AS newer_change
+            FROM transactions
+            WHERE sender_account_id = $1 OR receiver_account_id = $1
+        )
+        SELECT t.id, t.sender_account_id, t.receiver_account_id, t.amount, t.currency,
+               t.transaction_type, t.status, t.description, t.created_at, t.updated_at,
+               a.balance - COALESCE(l.newer_change, 0) AS balance_after,
+               t.fee_amount, t.fee_currency,
+               t.original_amount, t.original_currency, t.exchange_rate, t.external_reference,
+               t.category
+        FROM transactions t
+        JOIN ledger l ON l.id = t.id
+        JOIN accounts a ON a.id = $1
+        WHERE ($2::timestamptz IS NULL OR t.created_at >= $2)
+          AND ($3::timestamptz IS NULL OR t.created_at < $3)
+          AND ($4::text IS NULL OR t.transaction_type = $4)
+          AND ($5::text IS NULL OR t.status = $5)
+          AND ($6::numeric IS NULL OR t.amount >= $6)
+          AND ($7::numeric IS NULL OR t.amount <= $7)
+          AND ($8::text IS NULL OR t.description ILIKE $8 ESCAPE '\')
+          AND ($9::timestamptz IS NULL OR (t.created_at, t.id) < ($9, $10))
+        ORDER BY t.created_at DESC, t.id DESC
+        LIMIT $11
+        OFFSET $12
+        "#,
+    )
+    .bind(account_id)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.transaction_type.as_ref().map(|t| t.to_string()))
+    .bind(filter.status.as_ref().map(|s| s.to_string()))
+    .bind(filter.min_amount.map(SqlxDecimal))
+    .bind(filter.max_amount.map(SqlxDecimal))
+    .bind(filter.search_pattern())
+    .bind(before.map(|cursor| cursor.created_at))
+    .bind(before.map(|cursor| cursor.id))
+    .bind(limit)
+    .bind(offset)
+}
+
+/// Whether a query reading rows a transfer depends on locks them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowLock {
+    /// Locks the rows until the database transaction ends, as booking needs
+    ForUpdate,
+    /// Reads the rows without waiting for, or holding up, other transactions
+    None,
+}
+
+impl RowLock {
+    /// The clause appended to a `SELECT` to take the lock
+    fn clause(self) -> &'static str {
+        match self {
+            RowLock::ForUpdate => " FOR UPDATE",
+            RowLock::None => "",
+        }
+    }
+}
+
+/// Outcomes of the checks run by `TransactionService::validate_transfer`
+#[derive(Debug, Default)]
+struct TransferChecks(Vec<TransferCheck>);
+
+impl TransferChecks {
+    /// Records the outcome of a check, returning its value if it passed
+    ///
+    /// Client errors fail the check; server errors are returned, since they
+    /// say nothing about the transfer.
+    fn record<T>(
+        &mut self,
+        check: &str,
+        result: Result<T, AppError>,
+    ) -> Result<Option<T>, AppError> {
+        let (status, error, message, value) = match result {
+            Ok(value) => (CheckStatus::PASSED, None, None, Some(value)),
+            Err(err) => {
+                let (status, code, message) = err.describe();
+                if status.is_server_error() {
+                    return Err(err);
+                }
+                (CheckStatus::FAILED, Some(code), Some(message), None)
+            }
+        };
+        self.0.push(TransferCheck {
+            check: check.to_string(),
+            status,
+            error,
+            message,
+        });
+        Ok(value)
+    }
+
+    /// Records checks that couldn't run because a check they need failed
+    fn skip(&mut self, checks: &[&str]) {
+        self.0.extend(checks.iter().map(|check| TransferCheck {
+            check: check.to_string(),
+            status: CheckStatus::SKIPPED,
+            error: None,
+            message: Some("Depends on a check that failed".to_string()),
+        }));
+    }
+
+    /// The validation result, with the amount, fee and currency the sender
+    /// would be debited in when every check could run
+    fn finish(self, debit: Option<(Decimal, Decimal, String)>) -> TransferValidation {
+        let valid = self
+            .0
+            .iter()
+            .all(|check| check.status == CheckStatus::PASSED);
+        let (amount, fee, currency) = match debit {
+            Some((amount, fee, currency)) => (Some(amount), Some(fee), Some(currency)),
+            None => (None, None, None),
+        };
+        TransferValidation {
+            valid,
+            checks: self.0,
+            amount,
+            fee,
+            currency,
+        }
+    }
+}
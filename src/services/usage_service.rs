@@ -0,0 +1,146 @@
+use crate::models::usage::UsageRecord;
+use crate::utils::error::AppError;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Identifies one counter in the usage aggregator
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UsageKey {
+    pub user_id: Uuid,
+    pub route: String,
+    pub usage_date: NaiveDate,
+}
+
+/// In-memory request counters, drained to the database by `UsageService::flush`
+///
+/// Recording is a hash map increment under a short-lived lock, so it is cheap
+/// enough to run on every request.
+#[derive(Debug, Default)]
+pub struct UsageAggregator {
+    counts: Mutex<HashMap<UsageKey, i64>>,
+}
+
+impl UsageAggregator {
+    /// Counts one request by `user_id` to `route` on `usage_date`
+    pub fn record(&self, user_id: Uuid, route: &str, usage_date: NaiveDate) {
+        let key = UsageKey {
+            user_id,
+            route: route.to_string(),
+            usage_date,
+        };
+        *self.counts.lock().unwrap().entry(key).or_default() += 1;
+    }
+
+    /// Takes all pending counts, leaving the aggregator empty
+    pub fn drain(&self) -> HashMap<UsageKey, i64> {
+        std::mem::take(&mut *self.counts.lock().unwrap())
+    }
+
+    /// Adds counts back, e.g. after a failed flush
+    pub fn restore(&self, counts: HashMap<UsageKey, i64>) {
+        let mut pending = self.counts.lock().unwrap();
+        for (key, count) in counts {
+            *pending.entry(key).or_default() += count;
+        }
+    }
+}
+
+/// Service for recording and reporting API usage per user, route and day
+///
+/// Requests are counted in memory and periodically flushed to `api_usage`.
+/// A failed flush puts its counts back, so at most one flush interval of
+/// counts is lost if the process dies without shutting down cleanly.
+pub struct UsageService {
+    pool: PgPool,
+    aggregator: Arc<UsageAggregator>,
+}
+
+impl UsageService {
+    /// Creates a new usage service with the given database pool and an empty aggregator
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            aggregator: Arc::new(UsageAggregator::default()),
+        }
+    }
+
+    /// Returns the aggregator the usage middleware records into
+    pub fn aggregator(&self) -> Arc<UsageAggregator> {
+        self.aggregator.clone()
+    }
+
+    /// Writes pending counts to the database, adding to any stored totals
+    ///
+    /// # Returns
+    /// The number of counters written
+    pub async fn flush(&self) -> Result<usize, AppError> {
+        let counts = self.aggregator.drain();
+        if counts.is_empty() {
+            return Ok(0);
+        }
+
+        let mut user_ids = Vec::with_capacity(counts.len());
+        let mut routes = Vec::with_capacity(counts.len());
+        let mut dates = Vec::with_capacity(counts.len());
+        let mut request_counts = Vec::with_capacity(counts.len());
+        for (key, count) in &counts {
+            user_ids.push(key.user_id);
+            routes.push(key.route.clone());
+            dates.push(key.usage_date);
+            request_counts.push(*count);
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO api_usage (user_id, route, usage_date, request_count)
+            SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::date[], $4::bigint[])
+            ON CONFLICT (user_id, route, usage_date)
+            DO UPDATE SET request_count = api_usage.request_count + EXCLUDED.request_count
+            "#,
+        )
+        .bind(&user_ids)
+        .bind(&routes)
+        .bind(&dates)
+        .bind(&request_counts)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(counts.len()),
+            Err(e) => {
+                // Keep the counts for the next attempt
+                self.aggregator.restore(counts);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Retrieves stored usage between two dates (inclusive), optionally for one user
+    ///
+    /// Counts recorded since the last flush are not included.
+    pub async fn get_usage(
+        &self,
+        user_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<UsageRecord>, AppError> {
+        let records = sqlx::query_as::<_, UsageRecord>(
+            r#"
+            SELECT user_id, route, usage_date, request_count
+            FROM api_usage
+            WHERE ($1::uuid IS NULL OR user_id = $1) AND usage_date BETWEEN $2 AND $3
+            ORDER BY usage_date DESC, user_id, route
+            "#,
+        )
+        .bind(user_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+}
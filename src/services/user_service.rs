@@ -1,17 +1,61 @@
-use crate::models::user::{CreateUserRequest, LoginRequest, LoginResponse, User, UserResponse};
-use crate::utils::auth::{generate_jwt, hash_password, verify_password};
+use crate::middleware::request_context::RequestContext;
+use crate::models::outbox::USER_REGISTERED;
+use crate::models::user::{
+    BulkUserResult, BulkUserStatus, CreateUserRequest, LoginEvent, LoginOutcome, LoginRequest,
+    LoginResponse, NotificationPreferences, TotpChallenge, TotpSetupResponse, User, UserResponse,
+    ERASED_EMAIL_DOMAIN, MAX_BULK_USERS, MIN_PASSWORD_LENGTH, TOTP_ISSUER,
+    VERIFICATION_TOKEN_TTL_HOURS,
+};
+use crate::services::account_service::AccountService;
+use crate::services::notification_service::NotificationService;
+use crate::services::outbox_service::record_event;
+use crate::utils::auth::{
+    generate_jwt, generate_refresh_token, generate_totp_pending_token, hash_password, validate_jwt,
+    validate_refresh_token, validate_totp_pending_token, verify_password, Claims,
+    DEFAULT_JWT_EXPIRY_HOURS, REFRESH_TOKEN_TTL_DAYS, TOTP_PENDING_TOKEN_TTL_MINUTES,
+};
 use crate::utils::error::AppError;
-use sqlx::PgPool;
+use crate::utils::locale::Locale;
+use crate::utils::totp::{generate_secret, provisioning_uri, verify_code};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction as SqlxTransaction};
+use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
 pub struct UserService {
     pool: PgPool,
     jwt_secret: String,
+    /// How long access tokens stay valid
+    jwt_expiry: Duration,
+    /// Opens the default account, so it gets a number like any other account
+    account_service: AccountService,
+    /// Emails new users their verification token, when configured
+    notifications: Option<Arc<NotificationService>>,
 }
 
 impl UserService {
-    pub fn new(pool: PgPool, jwt_secret: String) -> Self {
-        Self { pool, jwt_secret }
+    pub fn new(pool: PgPool, jwt_secret: String, account_service: AccountService) -> Self {
+        Self {
+            pool,
+            jwt_secret,
+            jwt_expiry: Duration::hours(DEFAULT_JWT_EXPIRY_HOURS),
+            account_service,
+            notifications: None,
+        }
+    }
+
+    /// Issues access tokens valid for `expiry` instead of `DEFAULT_JWT_EXPIRY_HOURS`
+    pub fn with_jwt_expiry(mut self, expiry: Duration) -> Self {
+        self.jwt_expiry = expiry;
+        self
+    }
+
+    /// Emails new users the token that verifies their address
+    pub fn with_notifications(mut self, notifications: Arc<NotificationService>) -> Self {
+        self.notifications = Some(notifications);
+        self
     }
 
     pub async fn create_user(
@@ -41,77 +85,471 @@ impl UserService {
         // Generate UUID
         let id = Uuid::new_v4();
 
-        // Insert user
-        let user = sqlx::query_as!(
-            User,
+        // The address stays unverified until the emailed token comes back
+        let verification_token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        // Insert user, recording its registration in the same database transaction
+        let mut tx = self.pool.begin().await?;
+        let user = sqlx::query_as::<_, User>(
             r#"
-            INSERT INTO users (id, username, email, password_hash, first_name, last_name)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, username, email, password_hash, first_name, last_name, created_at, updated_at
+            INSERT INTO users (id, username, email, password_hash, first_name, last_name,
+                               verification_token, verification_token_expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW() + make_interval(hours => $8))
+            RETURNING id, username, email, password_hash, first_name, last_name, locale,
+                      email_verified, totp_enabled, role, created_at, updated_at
             "#,
+        )
+        .bind(id)
+        .bind(&user_data.username)
+        .bind(&user_data.email)
+        .bind(&password_hash)
+        .bind(&user_data.first_name)
+        .bind(&user_data.last_name)
+        .bind(&verification_token)
+        .bind(VERIFICATION_TOKEN_TTL_HOURS as i32)
+        .fetch_one(&mut *tx)
+        .await?;
+        // The event names the user only; subscribers look further details up,
+        // so erasing a user leaves no copy of them in the outbox
+        record_event(
+            &mut tx,
+            USER_REGISTERED,
             id,
-            user_data.username,
-            user_data.email,
-            password_hash,
-            user_data.first_name,
-            user_data.last_name
+            &json!({ "user_id": id, "created_at": user.created_at }),
         )
-        .fetch_one(&self.pool)
         .await?;
+        tx.commit().await?;
 
         // Create default account for user
-        let account_id = Uuid::new_v4();
-        sqlx::query!(
+        self.account_service
+            .create_account(id, "USD".to_string())
+            .await?;
+
+        let user = UserResponse::from(user);
+        if let Some(notifications) = &self.notifications {
+            notifications.spawn_email_verification(&user, verification_token);
+        }
+
+        Ok(user)
+    }
+
+    /// Confirms a user's email address with the token sent at registration
+    ///
+    /// Each token works once and only for `VERIFICATION_TOKEN_TTL_HOURS`.
+    ///
+    /// # Returns
+    /// The verified user, or `AppError::BadRequest` if the token is unknown,
+    /// was already used or has expired
+    pub async fn verify_email(&self, token: &str) -> Result<UserResponse, AppError> {
+        // Clearing the token in the statement that checks it keeps two
+        // concurrent requests from both using it
+        let user = sqlx::query_as::<_, User>(
             r#"
-            INSERT INTO accounts (id, user_id, balance, currency)
-            VALUES ($1, $2, 0, 'USD')
+            UPDATE users
+            SET email_verified = TRUE, verification_token = NULL,
+                verification_token_expires_at = NULL
+            WHERE verification_token = $1 AND verification_token_expires_at > NOW()
+              AND erased_at IS NULL
+            RETURNING id, username, email, password_hash, first_name, last_name, locale,
+                      email_verified, totp_enabled, role, created_at, updated_at
             "#,
-            account_id,
-            id
         )
-        .execute(&self.pool)
+        .bind(token)
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(UserResponse::from(user))
+        match user {
+            Some(user) => Ok(UserResponse::from(user)),
+            None => {
+                let expired = sqlx::query_scalar::<_, bool>(
+                    "SELECT EXISTS (SELECT 1 FROM users WHERE verification_token = $1)",
+                )
+                .bind(token)
+                .fetch_one(&self.pool)
+                .await?;
+                Err(AppError::BadRequest(if expired {
+                    "Verification token has expired".to_string()
+                } else {
+                    "Invalid or already used verification token".to_string()
+                }))
+            }
+        }
+    }
+
+    /// Creates several users at once, for onboarding an organization
+    ///
+    /// # Arguments
+    /// * `requests` - The users to create, at most `MAX_BULK_USERS`
+    ///
+    /// # Returns
+    /// One result per request, in request order
+    ///
+    /// # Implementation Details
+    /// Entries are created one after another exactly as `create_user` would,
+    /// each with its own password hash and default account. An entry that
+    /// fails validation or whose username or email is taken is reported and
+    /// the batch carries on, so a later duplicate of an earlier entry is a
+    /// conflict. Any other error stops the batch; users created before it are kept.
+    pub async fn create_users_bulk(
+        &self,
+        requests: Vec<CreateUserRequest>,
+    ) -> Result<Vec<BulkUserResult>, AppError> {
+        if requests.is_empty() || requests.len() > MAX_BULK_USERS {
+            return Err(AppError::BadRequest(format!(
+                "A bulk request must contain between 1 and {} users",
+                MAX_BULK_USERS
+            )));
+        }
+
+        let mut results = Vec::with_capacity(requests.len());
+        for (index, request) in requests.into_iter().enumerate() {
+            let username = request.username.clone();
+            let outcome = match request.validate() {
+                Ok(()) => self.create_user(request).await,
+                Err(e) => Err(AppError::Validation(format!("Invalid user data: {}", e))),
+            };
+
+            let (status, user, error) = match outcome {
+                Ok(user) => (BulkUserStatus::Created, Some(user), None),
+                Err(AppError::Conflict(message)) => (BulkUserStatus::Conflict, None, Some(message)),
+                Err(AppError::Validation(message)) => {
+                    (BulkUserStatus::Invalid, None, Some(message))
+                }
+                Err(e) => return Err(e),
+            };
+            results.push(BulkUserResult {
+                index,
+                username,
+                status,
+                user,
+                error,
+            });
+        }
+
+        Ok(results)
     }
 
-    pub async fn login(&self, login_data: LoginRequest) -> Result<LoginResponse, AppError> {
-        let user = sqlx::query_as!(
-            User,
+    /// Checks a username and password
+    ///
+    /// # Returns
+    /// A session, or for users with two-factor authentication enabled, a
+    /// short-lived token to pass to `login_totp` along with a one-time password
+    pub async fn login(
+        &self,
+        login_data: LoginRequest,
+        context: &RequestContext,
+    ) -> Result<LoginOutcome, AppError> {
+        let user = sqlx::query_as::<_, User>(
             r#"
-            SELECT id, username, email, password_hash, first_name, last_name, created_at, updated_at
-            FROM users WHERE username = $1
+            SELECT id, username, email, password_hash, first_name, last_name, locale,
+                   email_verified, totp_enabled, role, created_at, updated_at
+            FROM users WHERE username = $1 AND erased_at IS NULL AND deleted_at IS NULL
             "#,
-            login_data.username
         )
+        .bind(&login_data.username)
         .fetch_optional(&self.pool)
         .await?
         .ok_or_else(|| AppError::Auth("Invalid username or password".to_string()))?;
 
-        // Verify password
+        // Verify password, recording the attempt either way so the user can review it
         let is_valid = verify_password(&login_data.password, &user.password_hash)?;
+        self.record_login_event(user.id, context, is_valid).await?;
         if !is_valid {
             return Err(AppError::Auth("Invalid username or password".to_string()));
         }
 
-        // Generate JWT
-        let token = generate_jwt(user.id, &user.username, &self.jwt_secret)?;
+        if user.totp_enabled {
+            let totp_token =
+                generate_totp_pending_token(user.id, &user.username, &user.role, &self.jwt_secret)?;
+            return Ok(LoginOutcome::TotpRequired(TotpChallenge {
+                totp_token,
+                expires_in: TOTP_PENDING_TOKEN_TTL_MINUTES * 60,
+            }));
+        }
 
-        Ok(LoginResponse {
-            token,
-            user: UserResponse::from(user),
+        Ok(LoginOutcome::Session(
+            self.start_session(&self.pool, user).await?,
+        ))
+    }
+
+    /// Completes a login that is waiting for its one-time password
+    ///
+    /// # Arguments
+    /// * `totp_token` - Token `login` returned for the user
+    /// * `code` - Current code from the user's authenticator app
+    ///
+    /// # Returns
+    /// A session, or `AppError::Auth` if the token is invalid or expired, or
+    /// the code is wrong or was already used
+    pub async fn login_totp(
+        &self,
+        totp_token: &str,
+        code: &str,
+    ) -> Result<LoginResponse, AppError> {
+        let claims = validate_totp_pending_token(totp_token, &self.jwt_secret)?.claims;
+        let user_id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| AppError::Auth("Invalid user ID in token".to_string()))?;
+
+        let mut tx = self.pool.begin().await?;
+        let secret = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT totp_secret FROM users
+            WHERE id = $1 AND totp_enabled AND erased_at IS NULL AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::Auth("Two-factor authentication is not enabled".to_string()))?;
+        if !self
+            .accept_totp_code(&mut tx, user_id, &secret, code)
+            .await?
+        {
+            return Err(AppError::Auth("Invalid one-time password".to_string()));
+        }
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            SELECT id, username, email, password_hash, first_name, last_name, locale,
+                   email_verified, totp_enabled, role, created_at, updated_at
+            FROM users WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let session = self.start_session(&mut *tx, user).await?;
+        tx.commit().await?;
+
+        Ok(session)
+    }
+
+    /// Starts setting up two-factor authentication for a user
+    ///
+    /// A new secret is stored, replacing that of an unfinished setup, but 2FA
+    /// stays disabled until `verify_totp` confirms a code from it.
+    ///
+    /// # Returns
+    /// The secret and an `otpauth://` URI for the user's authenticator app, or
+    /// `AppError::Conflict` if 2FA is already enabled
+    pub async fn enable_totp(&self, user_id: Uuid) -> Result<TotpSetupResponse, AppError> {
+        let secret = generate_secret();
+
+        let username = sqlx::query_scalar::<_, String>(
+            r#"
+            UPDATE users SET totp_secret = $2, totp_last_step = NULL
+            WHERE id = $1 AND NOT totp_enabled AND erased_at IS NULL
+            RETURNING username
+            "#,
+        )
+        .bind(user_id)
+        .bind(&secret)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(username) = username else {
+            // Fails with NotFound for an unknown user
+            self.get_user_by_id(user_id).await?;
+            return Err(AppError::Conflict(
+                "Two-factor authentication is already enabled".to_string(),
+            ));
+        };
+
+        Ok(TotpSetupResponse {
+            provisioning_uri: provisioning_uri(&secret, &username, TOTP_ISSUER),
+            secret,
         })
     }
 
+    /// Confirms two-factor authentication setup with a code from the new secret
+    ///
+    /// # Returns
+    /// The user, now with `totp_enabled`, or `AppError::BadRequest` if setup
+    /// wasn't started or the code is wrong
+    pub async fn verify_totp(&self, user_id: Uuid, code: &str) -> Result<UserResponse, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let (secret, enabled) = sqlx::query_as::<_, (Option<String>, bool)>(
+            "SELECT totp_secret, totp_enabled FROM users WHERE id = $1 AND erased_at IS NULL FOR UPDATE",
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("User with ID {} not found", user_id)))?;
+        if enabled {
+            return Err(AppError::Conflict(
+                "Two-factor authentication is already enabled".to_string(),
+            ));
+        }
+        let secret = secret.ok_or_else(|| {
+            AppError::BadRequest("Two-factor authentication setup hasn't been started".to_string())
+        })?;
+        if !self
+            .accept_totp_code(&mut tx, user_id, &secret, code)
+            .await?
+        {
+            return Err(AppError::BadRequest(
+                "Invalid one-time password".to_string(),
+            ));
+        }
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users SET totp_enabled = TRUE WHERE id = $1
+            RETURNING id, username, email, password_hash, first_name, last_name, locale,
+                      email_verified, totp_enabled, role, created_at, updated_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(UserResponse::from(user))
+    }
+
+    /// Exchanges a refresh token for a new session
+    ///
+    /// Each refresh token works once: it is revoked here and the response
+    /// carries its replacement. The user is reloaded, so the new access token
+    /// reflects their current role.
+    pub async fn refresh_session(&self, refresh_token: &str) -> Result<LoginResponse, AppError> {
+        let (token_id, user_id) = refresh_token_ids(&self.jwt_secret, refresh_token)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        // Revoking in the same statement that checks the token keeps two
+        // concurrent refreshes from both succeeding
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE refresh_tokens SET revoked_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+            RETURNING id
+            "#,
+        )
+        .bind(token_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| {
+            AppError::Auth("Refresh token has been revoked or has expired".to_string())
+        })?;
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            SELECT id, username, email, password_hash, first_name, last_name, locale,
+                   email_verified, totp_enabled, role, created_at, updated_at
+            FROM users WHERE id = $1 AND erased_at IS NULL AND deleted_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::Auth("Invalid user ID in token".to_string()))?;
+
+        let session = self.start_session(&mut *tx, user).await?;
+        tx.commit().await?;
+
+        Ok(session)
+    }
+
+    /// Revokes a refresh token so it can no longer be used
+    ///
+    /// Access tokens already issued stay valid until they expire. Logging out
+    /// with a token that is already revoked succeeds.
+    pub async fn logout(&self, refresh_token: &str) -> Result<(), AppError> {
+        let (token_id, user_id) = refresh_token_ids(&self.jwt_secret, refresh_token)?;
+
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens SET revoked_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(token_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revokes an access token so it stops authenticating requests before it expires
+    ///
+    /// Tokens issued before access tokens carried an ID can't be revoked and
+    /// stay valid until they expire. Revoking a token twice succeeds.
+    pub async fn revoke_access_token(&self, token: &str) -> Result<(), AppError> {
+        let claims = validate_jwt(token, &self.jwt_secret)?.claims;
+        let Some(token_id) = access_token_id(&claims)? else {
+            return Ok(());
+        };
+        let user_id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| AppError::Auth("Invalid user ID in token".to_string()))?;
+        let expires_at = DateTime::<Utc>::from_timestamp(claims.exp, 0)
+            .ok_or_else(|| AppError::Auth("Invalid expiry in token".to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO revoked_tokens (jti, user_id, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+        )
+        .bind(token_id)
+        .bind(user_id)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns whether an access token of `user_id` may no longer be used
+    ///
+    /// That is the case once the token with ID `token_id` was revoked, or while
+    /// its user is erased or deactivated. Tokens without an ID are only checked
+    /// for the latter.
+    pub async fn is_token_revoked(
+        &self,
+        token_id: Option<Uuid>,
+        user_id: Uuid,
+    ) -> Result<bool, AppError> {
+        let revoked = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM revoked_tokens WHERE jti = $1)
+                OR EXISTS (
+                    SELECT 1 FROM users
+                    WHERE id = $2 AND (erased_at IS NOT NULL OR deleted_at IS NOT NULL)
+                )
+            "#,
+        )
+        .bind(token_id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(revoked)
+    }
+
+    /// Forgets revocations of tokens that have expired anyway
+    ///
+    /// Returns the number of revocations deleted.
+    pub async fn purge_expired_revocations(&self) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM revoked_tokens WHERE expires_at <= NOW()")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
     pub async fn get_user_by_id(&self, id: Uuid) -> Result<UserResponse, AppError> {
-        let user = sqlx::query_as!(
-            User,
+        let user = sqlx::query_as::<_, User>(
             r#"
-            SELECT id, username, email, password_hash, first_name, last_name, created_at, updated_at
+            SELECT id, username, email, password_hash, first_name, last_name, locale,
+                   email_verified, totp_enabled, role, created_at, updated_at
             FROM users WHERE id = $1
             "#,
-            id
         )
+        .bind(id)
         .fetch_optional(&self.pool)
         .await?
         .ok_or_else(|| AppError::NotFound(format!("User with ID {} not found", id)))?;
@@ -124,6 +562,7 @@ impl UserService {
         id: Uuid,
         first_name: Option<String>,
         last_name: Option<String>,
+        locale: Option<Locale>,
     ) -> Result<UserResponse, AppError> {
         // Check if user exists
         let existing_user = sqlx::query!(
@@ -140,23 +579,446 @@ impl UserService {
         }
 
         // Update user
-        let user = sqlx::query_as!(
-            User,
+        let user = sqlx::query_as::<_, User>(
             r#"
             UPDATE users
             SET first_name = COALESCE($2, first_name),
                 last_name = COALESCE($3, last_name),
-                updated_at = NOW()
+                locale = COALESCE($4, locale)
             WHERE id = $1
-            RETURNING id, username, email, password_hash, first_name, last_name, created_at, updated_at
+            RETURNING id, username, email, password_hash, first_name, last_name, locale,
+                      email_verified, totp_enabled, role, created_at, updated_at
             "#,
-            id,
-            first_name,
-            last_name
         )
+        .bind(id)
+        .bind(first_name)
+        .bind(last_name)
+        .bind(locale.map(|locale| locale.as_str()))
         .fetch_one(&self.pool)
         .await?;
 
         Ok(UserResponse::from(user))
     }
+
+    /// Replaces a user's password after checking their current one
+    ///
+    /// Fails with `Auth` when `current_password` is wrong and with `Validation`
+    /// when `new_password` is shorter than `MIN_PASSWORD_LENGTH`. Every refresh
+    /// token of the user is revoked, so other sessions end once their access
+    /// token expires.
+    pub async fn change_password(
+        &self,
+        id: Uuid,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<(), AppError> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            SELECT id, username, email, password_hash, first_name, last_name, locale,
+                   email_verified, totp_enabled, role, created_at, updated_at
+            FROM users WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("User with ID {} not found", id)))?;
+
+        if !verify_password(current_password, &user.password_hash)? {
+            return Err(AppError::Auth("Current password is incorrect".to_string()));
+        }
+        if new_password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Password must be at least {} characters",
+                MIN_PASSWORD_LENGTH
+            )));
+        }
+        let password_hash = hash_password(new_password)?;
+
+        // updated_at is moved by the users_touch_updated_at trigger
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE users SET password_hash = $2 WHERE id = $1")
+            .bind(id)
+            .bind(&password_hash)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Irreversibly erases a user's personal data, e.g. on a GDPR erasure request
+    ///
+    /// # Arguments
+    /// * `id` - The user to erase
+    /// * `erased_by` - The administrator carrying out the erasure
+    ///
+    /// # Returns
+    /// The user as left behind, with placeholder username and email
+    ///
+    /// # Implementation Details
+    /// The user row is kept, so their accounts and transactions stay intact for
+    /// audit under the same, now anonymous, user ID. This method:
+    /// 1. Replaces the username and email with placeholders derived from the ID,
+    ///    and clears the names and password hash
+    /// 2. Deletes the login history and replaces the recipient of past emails
+    /// 3. Revokes every refresh token; access tokens are refused from then on
+    ///    because `is_token_revoked` checks for erased users
+    ///
+    /// Erasing a user twice fails with `Conflict`, and administrators can't
+    /// erase themselves.
+    pub async fn erase_user(&self, id: Uuid, erased_by: Uuid) -> Result<UserResponse, AppError> {
+        if id == erased_by {
+            return Err(AppError::BadRequest(
+                "Administrators can't erase themselves".to_string(),
+            ));
+        }
+
+        let username = format!("erased-{}", id.simple());
+        let email = format!("{}@{}", id.simple(), ERASED_EMAIL_DOMAIN);
+
+        let mut tx = self.pool.begin().await?;
+
+        let erased_at = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+            "SELECT erased_at FROM users WHERE id = $1 FOR UPDATE",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("User with ID {} not found", id)))?;
+        if erased_at.is_some() {
+            return Err(AppError::Conflict(
+                "User has already been erased".to_string(),
+            ));
+        }
+
+        // An empty hash matches no password, and login skips erased users anyway
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET username = $2, email = $3, first_name = NULL, last_name = NULL,
+                password_hash = '', email_verified = FALSE, verification_token = NULL,
+                verification_token_expires_at = NULL, totp_secret = NULL, totp_enabled = FALSE,
+                totp_last_step = NULL, erased_at = NOW(), erased_by = $4
+            WHERE id = $1
+            RETURNING id, username, email, password_hash, first_name, last_name, locale,
+                      email_verified, totp_enabled, role, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .bind(&username)
+        .bind(&email)
+        .bind(erased_by)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Where and with what the user signed in is personal data too
+        sqlx::query("DELETE FROM login_history WHERE user_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE email_deliveries SET recipient = $2 WHERE user_id = $1")
+            .bind(id)
+            .bind(&email)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::warn!(user_id = %id, erased_by = %erased_by, "User erased");
+
+        Ok(UserResponse::from(user))
+    }
+
+    /// Deactivates a user, e.g. when they close their profile
+    ///
+    /// # Arguments
+    /// * `id` - The user to deactivate
+    /// * `freeze_accounts` - Whether to also freeze the user's active accounts
+    ///
+    /// # Returns
+    /// The deactivated user, or `Conflict` if they already are
+    ///
+    /// # Implementation Details
+    /// Nothing is deleted: the user row, accounts and transactions stay, and
+    /// `get_user_by_id` still finds the user. Logging in, refreshing a session
+    /// and access tokens already issued stop working until `reactivate_user`
+    /// is called; every refresh token is revoked outright.
+    pub async fn deactivate_user(
+        &self,
+        id: Uuid,
+        freeze_accounts: bool,
+    ) -> Result<UserResponse, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users SET deleted_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING id, username, email, password_hash, first_name, last_name, locale,
+                      email_verified, totp_enabled, role, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(user) = user else {
+            // Nothing updated means the user is deactivated, unless they don't exist
+            self.get_user_by_id(id).await?;
+            return Err(AppError::Conflict(
+                "User has already been deactivated".to_string(),
+            ));
+        };
+
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        if freeze_accounts {
+            sqlx::query(
+                "UPDATE accounts SET status = 'FROZEN' WHERE user_id = $1 AND status = 'ACTIVE'",
+            )
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        tracing::info!(user_id = %id, freeze_accounts, "User deactivated");
+
+        Ok(UserResponse::from(user))
+    }
+
+    /// Lets a deactivated user log in again
+    ///
+    /// Accounts frozen on deactivation stay frozen; administrators unfreeze
+    /// them one by one, as they may have been frozen for other reasons too.
+    ///
+    /// # Returns
+    /// The reactivated user, or `Conflict` if they weren't deactivated
+    pub async fn reactivate_user(&self, id: Uuid) -> Result<UserResponse, AppError> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users SET deleted_at = NULL
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING id, username, email, password_hash, first_name, last_name, locale,
+                      email_verified, totp_enabled, role, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(user) = user else {
+            self.get_user_by_id(id).await?;
+            return Err(AppError::Conflict("User is not deactivated".to_string()));
+        };
+
+        tracing::info!(user_id = %id, "User reactivated");
+
+        Ok(UserResponse::from(user))
+    }
+
+    /// Returns the most recent login attempts for a user, newest first
+    ///
+    /// # Arguments
+    /// * `user_id` - The UUID of the user whose history should be returned
+    /// * `limit` - Maximum number of events to return
+    pub async fn get_login_history(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<LoginEvent>, AppError> {
+        let events = sqlx::query_as::<_, LoginEvent>(
+            r#"
+            SELECT id, ip_address, user_agent, success, created_at
+            FROM login_history
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Returns which optional notifications a user receives
+    pub async fn get_notification_preferences(
+        &self,
+        user_id: Uuid,
+    ) -> Result<NotificationPreferences, AppError> {
+        sqlx::query_as::<_, NotificationPreferences>(
+            "SELECT transfer_receipts FROM users WHERE id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("User with ID {} not found", user_id)))
+    }
+
+    /// Replaces a user's notification preferences
+    pub async fn update_notification_preferences(
+        &self,
+        user_id: Uuid,
+        preferences: NotificationPreferences,
+    ) -> Result<NotificationPreferences, AppError> {
+        sqlx::query_as::<_, NotificationPreferences>(
+            r#"
+            UPDATE users SET transfer_receipts = $2
+            WHERE id = $1
+            RETURNING transfer_receipts
+            "#,
+        )
+        .bind(user_id)
+        .bind(preferences.transfer_receipts)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("User with ID {} not found", user_id)))
+    }
+
+    /// Issues an access token and a refresh token, recording the refresh token
+    async fn start_session<'e, E: PgExecutor<'e>>(
+        &self,
+        executor: E,
+        user: User,
+    ) -> Result<LoginResponse, AppError> {
+        let token = generate_jwt(
+            user.id,
+            &user.username,
+            &user.role,
+            &self.jwt_secret,
+            self.jwt_expiry,
+        )?;
+
+        let token_id = Uuid::new_v4();
+        let refresh_token = generate_refresh_token(
+            user.id,
+            &user.username,
+            &user.role,
+            token_id,
+            &self.jwt_secret,
+        )?;
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (id, user_id, expires_at)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(token_id)
+        .bind(user.id)
+        .bind(Utc::now() + Duration::days(REFRESH_TOKEN_TTL_DAYS))
+        .execute(executor)
+        .await?;
+
+        Ok(LoginResponse {
+            token,
+            refresh_token,
+            user: UserResponse::from(user),
+        })
+    }
+
+    /// Checks a one-time password and records its time step as used
+    ///
+    /// Codes are accepted from the current time step and the ones either side
+    /// of it, but never from a step at or before the last accepted one, so a
+    /// code can't be replayed. The user's row must be locked by `tx`.
+    ///
+    /// # Returns
+    /// Whether the code was accepted
+    async fn accept_totp_code(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        user_id: Uuid,
+        secret: &str,
+        code: &str,
+    ) -> Result<bool, AppError> {
+        let Some(step) = verify_code(secret, code.trim(), Utc::now()) else {
+            return Ok(false);
+        };
+
+        let accepted = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE users SET totp_last_step = $2
+            WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(step)
+        .fetch_optional(&mut **tx)
+        .await?
+        .is_some();
+
+        Ok(accepted)
+    }
+
+    /// Records a login attempt for an existing user
+    ///
+    /// Attempts against unknown usernames are not recorded since there is no
+    /// user to attribute them to.
+    async fn record_login_event(
+        &self,
+        user_id: Uuid,
+        context: &RequestContext,
+        success: bool,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO login_history (id, user_id, ip_address, user_agent, success)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(&context.ip_address)
+        .bind(&context.user_agent)
+        .bind(success)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Reads the ID of an access token; `None` for tokens issued without one
+pub fn access_token_id(claims: &Claims) -> Result<Option<Uuid>, AppError> {
+    claims
+        .jti
+        .as_deref()
+        .map(|jti| {
+            Uuid::parse_str(jti)
+                .map_err(|_| AppError::Auth("Invalid token ID in token".to_string()))
+        })
+        .transpose()
+}
+
+/// Validates a refresh token and returns its (token ID, user ID)
+fn refresh_token_ids(jwt_secret: &str, refresh_token: &str) -> Result<(Uuid, Uuid), AppError> {
+    let Claims { sub, jti, .. } = validate_refresh_token(refresh_token, jwt_secret)?.claims;
+
+    let token_id = jti
+        .and_then(|jti| Uuid::parse_str(&jti).ok())
+        .ok_or_else(|| AppError::Auth("Invalid token ID in token".to_string()))?;
+    let user_id = Uuid::parse_str(&sub)
+        .map_err(|_| AppError::Auth("Invalid user ID in token".to_string()))?;
+
+    Ok((token_id, user_id))
 }
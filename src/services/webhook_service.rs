@@ -0,0 +1,639 @@
+use crate::models::transaction::{TransactionResponse, TransactionStatus};
+use crate::models::webhook::{
+    RegisterWebhookRequest, UpdateWebhookRequest, Webhook, WebhookDelivery, WebhookDeliveryAttempt,
+    WebhookDeliveryResponse, WebhookDeliveryStatus, WebhookPayload, WebhookResponse,
+    TRANSACTION_COMPLETED,
+};
+use crate::utils::error::AppError;
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::{FromRow, PgConnection, PgExecutor, PgPool};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Header carrying `sha256=<hex HMAC-SHA256 of the body keyed with the secret>`
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// Header naming the event, e.g. `transaction.completed`
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// Waits before each retry of a failed delivery, after which it is dead-lettered
+pub const DEFAULT_RETRY_SCHEDULE: [Duration; 4] = [
+    Duration::from_secs(60),
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(2 * 60 * 60),
+];
+/// Time an endpoint has to answer a delivery
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Wait before `retry_due` picks up a new delivery whose first attempt never
+/// recorded an outcome, e.g. because the server stopped during it
+const FIRST_ATTEMPT_LEASE: Duration = Duration::from_secs(60);
+/// Most retries made by one call of `retry_due`
+const RETRY_BATCH_SIZE: usize = 100;
+
+/// Service posting transaction events to the webhooks users registered
+///
+/// Events are delivered after the transaction has committed and never affect
+/// its outcome: the first attempt is made on a background task, and a
+/// delivery the endpoint doesn't accept with a 2xx status is retried by
+/// `retry_due` on the retry schedule, then dead-lettered.
+pub struct WebhookService {
+    pool: PgPool,
+    client: reqwest::Client,
+    /// Waits before each retry, in order
+    retry_schedule: Vec<Duration>,
+}
+
+/// A delivery due for an attempt, with the webhook it goes to
+#[derive(FromRow)]
+struct DueDelivery {
+    id: Uuid,
+    event: String,
+    payload: String,
+    attempts: i32,
+    webhook_id: Uuid,
+    url: String,
+    secret: String,
+}
+
+/// How an endpoint answered one attempt
+struct AttemptOutcome {
+    response_status: Option<u16>,
+    latency: Duration,
+    /// Why the attempt failed; `None` once the endpoint accepted it
+    error: Option<String>,
+}
+
+impl WebhookService {
+    /// Creates a new webhook service with the given database pool
+    pub fn new(pool: PgPool) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            pool,
+            client,
+            retry_schedule: DEFAULT_RETRY_SCHEDULE.to_vec(),
+        }
+    }
+
+    /// Retries failed deliveries after `schedule` instead of `DEFAULT_RETRY_SCHEDULE`
+    ///
+    /// A delivery gets one retry per entry; an empty schedule dead-letters
+    /// deliveries whose first attempt fails.
+    pub fn with_retry_schedule(mut self, schedule: Vec<Duration>) -> Self {
+        self.retry_schedule = schedule;
+        self
+    }
+
+    /// Registers a webhook endpoint for a user
+    ///
+    /// # Arguments
+    /// * `user_id` - The user whose accounts' events are sent to the endpoint
+    /// * `request` - URL of the endpoint
+    ///
+    /// # Returns
+    /// The new webhook together with its signing secret, which is not shown again
+    pub async fn register(
+        &self,
+        user_id: Uuid,
+        request: RegisterWebhookRequest,
+    ) -> Result<WebhookResponse, AppError> {
+        let url = request.normalized_url()?;
+        let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        let webhook = sqlx::query_as::<_, Webhook>(
+            r#"
+            INSERT INTO webhooks (id, user_id, url, secret)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, user_id, url, secret, enabled, created_at
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(&url)
+        .bind(&secret)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(WebhookResponse::with_secret(webhook))
+    }
+
+    /// Lists a user's webhooks, oldest first, without their secrets
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<WebhookResponse>, AppError> {
+        let webhooks = sqlx::query_as::<_, Webhook>(
+            r#"
+            SELECT id, user_id, url, secret, enabled, created_at
+            FROM webhooks
+            WHERE user_id = $1
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(webhooks.into_iter().map(WebhookResponse::from).collect())
+    }
+
+    /// Returns one of a user's webhooks, without its secret
+    ///
+    /// Other users' webhooks are `NotFound`, like ones that don't exist.
+    pub async fn get(&self, user_id: Uuid, id: Uuid) -> Result<WebhookResponse, AppError> {
+        let webhook = sqlx::query_as::<_, Webhook>(
+            r#"
+            SELECT id, user_id, url, secret, enabled, created_at
+            FROM webhooks
+            WHERE id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| webhook_not_found(id))?;
+
+        Ok(WebhookResponse::from(webhook))
+    }
+
+    /// Changes the URL of one of a user's webhooks, or turns it on or off
+    ///
+    /// Deliveries already under way finish with the webhook as it was.
+    pub async fn update(
+        &self,
+        user_id: Uuid,
+        id: Uuid,
+        request: UpdateWebhookRequest,
+    ) -> Result<WebhookResponse, AppError> {
+        let url = request.normalized_url()?;
+
+        let webhook = sqlx::query_as::<_, Webhook>(
+            r#"
+            UPDATE webhooks
+            SET url = COALESCE($3, url), enabled = COALESCE($4, enabled)
+            WHERE id = $1 AND user_id = $2
+            RETURNING id, user_id, url, secret, enabled, created_at
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(url)
+        .bind(request.enabled)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| webhook_not_found(id))?;
+
+        Ok(WebhookResponse::from(webhook))
+    }
+
+    /// Deletes one of a user's webhooks along with its delivery history
+    pub async fn delete(&self, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+        let deleted = sqlx::query("DELETE FROM webhooks WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        if deleted == 0 {
+            return Err(webhook_not_found(id));
+        }
+
+        Ok(())
+    }
+
+    /// Announces a transaction on a background task, if it completed
+    ///
+    /// # Returns
+    /// The background task, or `None` when the transaction didn't complete
+    pub fn spawn_transaction_completed(
+        self: &Arc<Self>,
+        transaction: &TransactionResponse,
+    ) -> Option<JoinHandle<()>> {
+        if transaction.status != TransactionStatus::COMPLETED.to_string() {
+            return None;
+        }
+
+        let service = self.clone();
+        let transaction = transaction.clone();
+        Some(tokio::spawn(async move {
+            if let Err(e) = service.send_transaction_completed(&transaction).await {
+                tracing::error!(
+                    transaction_id = %transaction.id,
+                    "Could not send webhooks: {}",
+                    e
+                );
+            }
+        }))
+    }
+
+    /// Posts a `transaction.completed` event to the enabled webhooks of the
+    /// owners of the accounts involved
+    ///
+    /// A user owning both sides of a transfer gets the event once per webhook.
+    /// Endpoints are called concurrently, so a slow one doesn't hold up the rest.
+    pub async fn send_transaction_completed(
+        &self,
+        transaction: &TransactionResponse,
+    ) -> Result<(), AppError> {
+        let account_ids: Vec<Uuid> = [
+            transaction.sender_account_id,
+            transaction.receiver_account_id,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let webhooks = sqlx::query_as::<_, Webhook>(
+            r#"
+            SELECT w.id, w.user_id, w.url, w.secret, w.enabled, w.created_at
+            FROM webhooks w
+            WHERE w.enabled AND w.user_id IN (SELECT user_id FROM accounts WHERE id = ANY($1))
+            "#,
+        )
+        .bind(&account_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        if webhooks.is_empty() {
+            return Ok(());
+        }
+
+        let body = serde_json::to_string(&WebhookPayload::transaction_completed(transaction))
+            .map_err(|e| AppError::Internal(format!("Serialization failed: {}", e)))?;
+        join_all(
+            webhooks
+                .iter()
+                .map(|webhook| self.deliver(webhook, TRANSACTION_COMPLETED, transaction.id, &body)),
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /// Lists the deliveries made to one of a user's webhooks, newest first,
+    /// each with its attempts
+    pub async fn list_deliveries(
+        &self,
+        user_id: Uuid,
+        webhook_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<WebhookDeliveryResponse>, AppError> {
+        // Fails with NotFound for other users' webhooks
+        self.get(user_id, webhook_id).await?;
+
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(&format!(
+            r#"
+            SELECT {}
+            FROM webhook_deliveries
+            WHERE webhook_id = $1
+            ORDER BY created_at DESC, id
+            LIMIT $2
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(webhook_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut responses = Vec::with_capacity(deliveries.len());
+        for delivery in deliveries {
+            let attempt_log = self.attempt_log(&self.pool, delivery.id).await?;
+            responses.push(WebhookDeliveryResponse {
+                delivery,
+                attempt_log,
+            });
+        }
+
+        Ok(responses)
+    }
+
+    /// Sends a dead-lettered delivery again, once, and waits for the outcome
+    ///
+    /// # Returns
+    /// The delivery, DELIVERED if the endpoint accepted it this time and
+    /// DEAD_LETTERED again otherwise. Deliveries that aren't dead-lettered
+    /// and webhooks that are disabled are a `Conflict`.
+    pub async fn redeliver(
+        &self,
+        user_id: Uuid,
+        webhook_id: Uuid,
+        delivery_id: Uuid,
+    ) -> Result<WebhookDeliveryResponse, AppError> {
+        let webhook = self.get(user_id, webhook_id).await?;
+        if !webhook.enabled {
+            return Err(AppError::Conflict(
+                "Enable the webhook before redelivering to it".to_string(),
+            ));
+        }
+
+        // The lock keeps the worker and a second redelivery off the delivery
+        let mut tx = self.pool.begin().await?;
+        let delivery = sqlx::query_as::<_, DueDelivery>(
+            r#"
+            SELECT d.id, d.event, d.payload, d.attempts, w.id AS webhook_id, w.url, w.secret
+            FROM webhook_deliveries d
+            JOIN webhooks w ON w.id = d.webhook_id
+            WHERE d.id = $1 AND d.webhook_id = $2 AND d.status = 'DEAD_LETTERED'
+            FOR UPDATE OF d
+            "#,
+        )
+        .bind(delivery_id)
+        .bind(webhook_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(delivery) = delivery else {
+            let exists = sqlx::query_scalar::<_, bool>(
+                r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM webhook_deliveries WHERE id = $1 AND webhook_id = $2
+                )
+                "#,
+            )
+            .bind(delivery_id)
+            .bind(webhook_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            return Err(if exists {
+                AppError::Conflict("Only dead-lettered deliveries can be redelivered".to_string())
+            } else {
+                AppError::NotFound(format!("Delivery with ID {} not found", delivery_id))
+            });
+        };
+
+        let outcome = self.post(&delivery).await;
+        // A manual redelivery is a single attempt, so a failure dead-letters it again
+        let status = match outcome.error {
+            None => WebhookDeliveryStatus::DELIVERED,
+            Some(_) => WebhookDeliveryStatus::DEAD_LETTERED,
+        };
+        self.record_attempt(&mut tx, &delivery, &outcome, &status, None)
+            .await?;
+        let delivery = sqlx::query_as::<_, WebhookDelivery>(&format!(
+            "SELECT {} FROM webhook_deliveries WHERE id = $1",
+            DELIVERY_COLUMNS
+        ))
+        .bind(delivery_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let attempt_log = self.attempt_log(&mut *tx, delivery_id).await?;
+        tx.commit().await?;
+
+        Ok(WebhookDeliveryResponse {
+            delivery,
+            attempt_log,
+        })
+    }
+
+    /// Retries the pending deliveries that are due, to enabled webhooks
+    ///
+    /// Each delivery is claimed with its row locked, so several server
+    /// instances never send the same one at once. A delivery whose retries are
+    /// used up is dead-lettered.
+    ///
+    /// # Returns
+    /// How many deliveries were attempted, whether they succeeded or not
+    pub async fn retry_due(&self) -> Result<usize, AppError> {
+        let mut attempted = 0;
+        while attempted < RETRY_BATCH_SIZE {
+            let mut tx = self.pool.begin().await?;
+            let delivery = sqlx::query_as::<_, DueDelivery>(
+                r#"
+                SELECT d.id, d.event, d.payload, d.attempts, w.id AS webhook_id, w.url, w.secret
+                FROM webhook_deliveries d
+                JOIN webhooks w ON w.id = d.webhook_id
+                WHERE d.status = 'PENDING' AND d.next_attempt_at <= NOW() AND w.enabled
+                ORDER BY d.next_attempt_at
+                LIMIT 1
+                FOR UPDATE OF d SKIP LOCKED
+                "#,
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+            let Some(delivery) = delivery else {
+                break;
+            };
+
+            let outcome = self.post(&delivery).await;
+            let (status, retry_in) = self.next_step(&delivery, &outcome);
+            self.record_attempt(&mut tx, &delivery, &outcome, &status, retry_in)
+                .await?;
+            tx.commit().await?;
+
+            attempted += 1;
+        }
+
+        Ok(attempted)
+    }
+
+    /// Records a delivery of `body` to a webhook and makes its first attempt
+    ///
+    /// Failing to record the delivery is logged, and the event is not sent
+    /// then, as it couldn't be retried.
+    ///
+    /// # Returns
+    /// Whether the endpoint accepted the delivery at the first attempt
+    async fn deliver(
+        &self,
+        webhook: &Webhook,
+        event: &str,
+        transaction_id: Uuid,
+        body: &str,
+    ) -> bool {
+        let delivery = DueDelivery {
+            id: Uuid::new_v4(),
+            event: event.to_string(),
+            payload: body.to_string(),
+            attempts: 0,
+            webhook_id: webhook.id,
+            url: webhook.url.clone(),
+            secret: webhook.secret.clone(),
+        };
+        let recorded = sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries
+                (id, webhook_id, event, transaction_id, payload, next_attempt_at)
+            VALUES ($1, $2, $3, $4, $5, NOW() + make_interval(secs => $6))
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(webhook.id)
+        .bind(event)
+        .bind(transaction_id)
+        .bind(body)
+        .bind(FIRST_ATTEMPT_LEASE.as_secs_f64())
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = recorded {
+            tracing::error!(webhook_id = %webhook.id, "Could not record webhook delivery: {}", e);
+            return false;
+        }
+
+        let outcome = self.post(&delivery).await;
+        let (status, retry_in) = self.next_step(&delivery, &outcome);
+        let recorded = match self.pool.acquire().await {
+            Ok(mut conn) => {
+                self.record_attempt(&mut conn, &delivery, &outcome, &status, retry_in)
+                    .await
+            }
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = recorded {
+            tracing::error!(delivery_id = %delivery.id, "Could not record webhook attempt: {}", e);
+        }
+        outcome.error.is_none()
+    }
+
+    /// Posts a delivery to its webhook once
+    async fn post(&self, delivery: &DueDelivery) -> AttemptOutcome {
+        let started = Instant::now();
+        let result = self
+            .client
+            .post(&delivery.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, sign(&delivery.secret, &delivery.payload))
+            .header(EVENT_HEADER, &delivery.event)
+            .body(delivery.payload.clone())
+            .send()
+            .await;
+
+        let response_status = result.as_ref().ok().map(|response| response.status());
+        let error = match result {
+            Ok(response) if response.status().is_success() => None,
+            Ok(response) => Some(format!("endpoint answered {}", response.status())),
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(error) = &error {
+            tracing::warn!(
+                webhook_id = %delivery.webhook_id,
+                attempt = delivery.attempts + 1,
+                "Webhook delivery failed: {}",
+                error
+            );
+        }
+
+        AttemptOutcome {
+            response_status: response_status.map(|status| status.as_u16()),
+            latency: started.elapsed(),
+            error,
+        }
+    }
+
+    /// Where a delivery goes after an attempt: done, retried after a wait, or
+    /// dead-lettered once the retry schedule is used up
+    fn next_step(
+        &self,
+        delivery: &DueDelivery,
+        outcome: &AttemptOutcome,
+    ) -> (WebhookDeliveryStatus, Option<Duration>) {
+        if outcome.error.is_none() {
+            return (WebhookDeliveryStatus::DELIVERED, None);
+        }
+        // The first attempt isn't a retry, so the nth attempt waits out entry n - 1
+        let retries_made = usize::try_from(delivery.attempts).unwrap_or_default();
+        match self.retry_schedule.get(retries_made) {
+            Some(wait) => (WebhookDeliveryStatus::PENDING, Some(*wait)),
+            None => {
+                tracing::error!(
+                    webhook_id = %delivery.webhook_id,
+                    delivery_id = %delivery.id,
+                    "Dead-lettering webhook delivery after {} attempts",
+                    delivery.attempts + 1
+                );
+                (WebhookDeliveryStatus::DEAD_LETTERED, None)
+            }
+        }
+    }
+
+    /// Stores an attempt and moves the delivery to `status`, to be retried
+    /// after `retry_in` if given
+    async fn record_attempt(
+        &self,
+        conn: &mut PgConnection,
+        delivery: &DueDelivery,
+        outcome: &AttemptOutcome,
+        status: &WebhookDeliveryStatus,
+        retry_in: Option<Duration>,
+    ) -> Result<(), AppError> {
+        let attempt = delivery.attempts + 1;
+        let response_status = outcome.response_status.map(i32::from);
+        let latency_ms = i32::try_from(outcome.latency.as_millis()).unwrap_or(i32::MAX);
+
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_delivery_attempts
+                (id, delivery_id, attempt, response_status, latency_ms, error)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(delivery.id)
+        .bind(attempt)
+        .bind(response_status)
+        .bind(latency_ms)
+        .bind(&outcome.error)
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $2, attempts = $3, response_status = $4, last_error = $5,
+                next_attempt_at = NOW() + make_interval(secs => $6), updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(status.to_string())
+        .bind(attempt)
+        .bind(response_status)
+        .bind(&outcome.error)
+        .bind(retry_in.map(|wait| wait.as_secs_f64()))
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The attempts made at a delivery, oldest first
+    async fn attempt_log<'e, E: PgExecutor<'e>>(
+        &self,
+        executor: E,
+        delivery_id: Uuid,
+    ) -> Result<Vec<WebhookDeliveryAttempt>, AppError> {
+        let attempts = sqlx::query_as::<_, WebhookDeliveryAttempt>(
+            r#"
+            SELECT attempt, response_status, latency_ms, error, created_at
+            FROM webhook_delivery_attempts
+            WHERE delivery_id = $1
+            ORDER BY attempt
+            "#,
+        )
+        .bind(delivery_id)
+        .fetch_all(executor)
+        .await?;
+
+        Ok(attempts)
+    }
+}
+
+/// Columns of `webhook_deliveries` as `WebhookDelivery` reads them
+const DELIVERY_COLUMNS: &str = "id, webhook_id, event, transaction_id, payload, status, attempts, \
+    response_status, last_error, next_attempt_at, created_at, updated_at";
+
+fn webhook_not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Webhook with ID {} not found", id))
+}
+
+/// Signs a delivery body with a webhook's secret
+///
+/// Receivers recompute the HMAC-SHA256 of the raw body with their secret and
+/// compare it with the `X-Webhook-Signature` header.
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
@@ -0,0 +1,181 @@
+use std::fmt::Debug;
+use uuid::Uuid;
+
+/// Produces customer-facing account numbers in a deployment-specific scheme
+///
+/// Generators only need to make collisions unlikely; uniqueness is enforced by
+/// the database and `AccountService` retries with a fresh number on conflict.
+pub trait AccountNumberGenerator: Send + Sync + Debug {
+    /// Returns a new candidate account number
+    fn generate(&self) -> String;
+
+    /// Returns whether a number is well-formed for this scheme
+    fn is_valid(&self, number: &str) -> bool;
+}
+
+/// Ten digits, the last being a Luhn check digit (e.g. `4829103755`)
+#[derive(Debug, Clone, Default)]
+pub struct Numeric10;
+
+/// A fixed prefix followed by a `Numeric10` number (e.g. `TXN-4829103755`)
+#[derive(Debug, Clone)]
+pub struct Prefixed {
+    pub prefix: String,
+}
+
+/// An IBAN-shaped number: the pseudo country code `XX`, two ISO 7064 mod 97-10
+/// check digits, a four-character bank code and 14 digits
+/// (e.g. `XX79TXNM00012345678901`)
+#[derive(Debug, Clone)]
+pub struct IbanLike {
+    pub bank_code: String,
+}
+
+/// Country code used by `IbanLike`; `XX` is not assigned, so the numbers
+/// can never be mistaken for real IBANs
+const IBAN_COUNTRY: &str = "XX";
+
+/// Returns `count` random decimal digits
+fn random_digits(count: usize) -> String {
+    let mut digits = String::with_capacity(count);
+    while digits.len() < count {
+        // A v4 UUID carries 122 random bits, enough for 36 decimal digits
+        let random = Uuid::new_v4().as_u128() & ((1u128 << 122) - 1);
+        let chunk = format!("{:036}", random % 10u128.pow(36));
+        digits.push_str(&chunk[..(count - digits.len()).min(36)]);
+    }
+    digits
+}
+
+/// Computes the Luhn check digit for a string of decimal digits
+fn luhn_check_digit(payload: &str) -> u32 {
+    let sum: u32 = payload
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(index, digit)| {
+            if index % 2 == 0 {
+                let doubled = digit * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                digit
+            }
+        })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+/// Computes the remainder mod 97 of an alphanumeric string, letters counting as 10-35
+fn mod97(value: &str) -> Option<u32> {
+    value.chars().try_fold(0u32, |remainder, c| {
+        let digit = c.to_digit(36)?;
+        Some(if digit > 9 {
+            (remainder * 100 + digit) % 97
+        } else {
+            (remainder * 10 + digit) % 97
+        })
+    })
+}
+
+impl AccountNumberGenerator for Numeric10 {
+    fn generate(&self) -> String {
+        let payload = random_digits(9);
+        format!("{}{}", payload, luhn_check_digit(&payload))
+    }
+
+    fn is_valid(&self, number: &str) -> bool {
+        number.len() == 10
+            && number.chars().all(|c| c.is_ascii_digit())
+            && luhn_check_digit(&number[..9]) == number[9..].parse::<u32>().unwrap_or(u32::MAX)
+    }
+}
+
+impl AccountNumberGenerator for Prefixed {
+    fn generate(&self) -> String {
+        format!("{}-{}", self.prefix, Numeric10.generate())
+    }
+
+    fn is_valid(&self, number: &str) -> bool {
+        number
+            .strip_prefix(&self.prefix)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .is_some_and(|rest| Numeric10.is_valid(rest))
+    }
+}
+
+impl AccountNumberGenerator for IbanLike {
+    fn generate(&self) -> String {
+        let bban = format!("{}{}", self.bank_code, random_digits(14));
+        let remainder = mod97(&format!("{}{}00", bban, IBAN_COUNTRY)).unwrap_or_default();
+        format!("{}{:02}{}", IBAN_COUNTRY, 98 - remainder, bban)
+    }
+
+    fn is_valid(&self, number: &str) -> bool {
+        number.len() == 22
+            && number.is_ascii()
+            && number.starts_with(IBAN_COUNTRY)
+            && number[4..8] == self.bank_code
+            && number[8..].chars().all(|c| c.is_ascii_digit())
+            && mod97(&format!("{}{}", &number[4..], &number[..4])) == Some(1)
+    }
+}
+
+/// The account-number scheme configured for a deployment
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AccountNumberFormat {
+    /// See [`Numeric10`]
+    #[default]
+    Numeric10,
+    /// See [`Prefixed`]; holds the prefix
+    Prefixed(String),
+    /// See [`IbanLike`]; holds the bank code
+    IbanLike(String),
+}
+
+impl AccountNumberFormat {
+    /// Builds the format from its name (`numeric10`, `prefixed` or `iban`) and
+    /// the prefix used by `prefixed` (as is) and `iban` (as the bank code)
+    pub fn from_parts(name: &str, prefix: &str) -> Result<Self, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "numeric10" => Ok(Self::Numeric10),
+            "prefixed" => {
+                if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(format!("Invalid account number prefix '{}'", prefix));
+                }
+                Ok(Self::Prefixed(prefix.to_string()))
+            }
+            "iban" => {
+                let bank_code = prefix.to_ascii_uppercase();
+                if bank_code.len() != 4 || !bank_code.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(format!(
+                        "IBAN bank code must be 4 letters or digits, got '{}'",
+                        prefix
+                    ));
+                }
+                Ok(Self::IbanLike(bank_code))
+            }
+            other => Err(format!(
+                "Unknown account number format '{}'. Supported formats: numeric10, prefixed, iban",
+                other
+            )),
+        }
+    }
+
+    /// Returns the generator implementing this format
+    pub fn generator(&self) -> Box<dyn AccountNumberGenerator> {
+        match self {
+            Self::Numeric10 => Box::new(Numeric10),
+            Self::Prefixed(prefix) => Box::new(Prefixed {
+                prefix: prefix.clone(),
+            }),
+            Self::IbanLike(bank_code) => Box::new(IbanLike {
+                bank_code: bank_code.clone(),
+            }),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::models::user::UserRole;
 use crate::utils::error::AppError;
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
@@ -8,24 +9,124 @@ use uuid::Uuid;
 pub struct Claims {
     pub sub: String,      // Subject (user ID)
     pub username: String, // Username
+    #[serde(default = "default_role")]
+    pub role: String, // Privilege level (USER, ADMIN)
     pub exp: i64,         // Expiration time
     pub iat: i64,         // Issued at
+    #[serde(default)]
+    pub token_type: TokenType, // Access or refresh
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>, // Token ID, so the token can be revoked before it expires
 }
 
-pub fn generate_jwt(user_id: Uuid, username: &str, secret: &str) -> Result<String, AppError> {
+/// What a token may be used for
+///
+/// Access tokens authenticate API requests; refresh tokens can only be
+/// exchanged for a new session at `/users/refresh`, and TOTP pending tokens
+/// only for one at `/users/login/totp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenType {
+    /// Tokens issued before refresh tokens existed carry no type and are access tokens
+    #[default]
+    Access,
+    Refresh,
+    #[serde(rename = "totp_pending")]
+    TotpPending,
+}
+
+/// How long an access token is valid unless configured otherwise
+pub const DEFAULT_JWT_EXPIRY_HOURS: i64 = 24;
+
+/// How long a refresh token can be exchanged for a new session
+pub const REFRESH_TOKEN_TTL_DAYS: i64 = 7;
+
+/// How long a user has to give their one-time password after their password
+pub const TOTP_PENDING_TOKEN_TTL_MINUTES: i64 = 5;
+
+/// Tokens issued before roles existed carry no role claim and are treated as regular users
+fn default_role() -> String {
+    UserRole::USER.to_string()
+}
+
+/// Generates an access token valid for `expiry`
+///
+/// Each token gets its own ID, which logging out records in `revoked_tokens`.
+pub fn generate_jwt(
+    user_id: Uuid,
+    username: &str,
+    role: &str,
+    secret: &str,
+    expiry: Duration,
+) -> Result<String, AppError> {
+    let now = Utc::now();
+    let expires_at = now + expiry;
+
+    let claims = Claims {
+        sub: user_id.to_string(),
+        username: username.to_string(),
+        role: role.to_string(),
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+        token_type: TokenType::Access,
+        jti: Some(Uuid::new_v4().to_string()),
+    };
+
+    encode_claims(&claims, secret)
+}
+
+/// Generates a refresh token identified by `token_id`, valid for `REFRESH_TOKEN_TTL_DAYS`
+pub fn generate_refresh_token(
+    user_id: Uuid,
+    username: &str,
+    role: &str,
+    token_id: Uuid,
+    secret: &str,
+) -> Result<String, AppError> {
+    let now = Utc::now();
+    let expires_at = now + Duration::days(REFRESH_TOKEN_TTL_DAYS);
+
+    let claims = Claims {
+        sub: user_id.to_string(),
+        username: username.to_string(),
+        role: role.to_string(),
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+        token_type: TokenType::Refresh,
+        jti: Some(token_id.to_string()),
+    };
+
+    encode_claims(&claims, secret)
+}
+
+/// Generates a token for a login that still needs its one-time password,
+/// valid for `TOTP_PENDING_TOKEN_TTL_MINUTES`
+pub fn generate_totp_pending_token(
+    user_id: Uuid,
+    username: &str,
+    role: &str,
+    secret: &str,
+) -> Result<String, AppError> {
     let now = Utc::now();
-    let expires_at = now + Duration::hours(24);
+    let expires_at = now + Duration::minutes(TOTP_PENDING_TOKEN_TTL_MINUTES);
 
     let claims = Claims {
         sub: user_id.to_string(),
         username: username.to_string(),
+        role: role.to_string(),
         iat: now.timestamp(),
         exp: expires_at.timestamp(),
+        token_type: TokenType::TotpPending,
+        jti: None,
     };
 
+    encode_claims(&claims, secret)
+}
+
+fn encode_claims(claims: &Claims, secret: &str) -> Result<String, AppError> {
     let token = encode(
         &Header::default(),
-        &claims,
+        claims,
         &EncodingKey::from_secret(secret.as_bytes()),
     )
     .map_err(|e| AppError::Internal(format!("Failed to generate JWT: {}", e)))?;
@@ -33,14 +134,51 @@ pub fn generate_jwt(user_id: Uuid, username: &str, secret: &str) -> Result<Strin
     Ok(token)
 }
 
+/// Validates an access token; refresh tokens are rejected
+///
+/// Like `validate_refresh_token`, this doesn't know about revocations.
 pub fn validate_jwt(token: &str, secret: &str) -> Result<TokenData<Claims>, AppError> {
+    decode_token(token, secret, TokenType::Access)
+}
+
+/// Validates a refresh token; access tokens are rejected
+///
+/// This only checks the signature, expiry and type. Whether the token has
+/// been revoked is recorded in the database.
+pub fn validate_refresh_token(token: &str, secret: &str) -> Result<TokenData<Claims>, AppError> {
+    decode_token(token, secret, TokenType::Refresh)
+}
+
+/// Validates a TOTP pending token; other tokens are rejected
+pub fn validate_totp_pending_token(
+    token: &str,
+    secret: &str,
+) -> Result<TokenData<Claims>, AppError> {
+    decode_token(token, secret, TokenType::TotpPending)
+}
+
+fn decode_token(
+    token: &str,
+    secret: &str,
+    expected: TokenType,
+) -> Result<TokenData<Claims>, AppError> {
+    // Tokens are only checked by the service that issued them, so there is
+    // no clock skew to allow for and a token stops working as soon as it expires
+    let mut validation = Validation::default();
+    validation.leeway = 0;
     let token_data = decode::<Claims>(
         token,
         &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
+        &validation,
     )
     .map_err(|e| AppError::Auth(format!("Invalid token: {}", e)))?;
 
+    if token_data.claims.token_type != expected {
+        return Err(AppError::Auth(
+            "Invalid token: wrong token type".to_string(),
+        ));
+    }
+
     Ok(token_data)
 }
 
@@ -0,0 +1,127 @@
+use crate::utils::error::AppError;
+use csv_core::{ReadRecordResult, Reader};
+
+/// Most fields accepted in one CSV record
+const MAX_FIELDS: usize = 64;
+
+/// Formats one CSV record, terminated by CRLF as RFC 4180 specifies
+///
+/// Fields containing a comma, quote or line break are quoted, with embedded
+/// quotes doubled; all other fields are written as they are.
+pub fn csv_record<S: AsRef<str>>(fields: &[S]) -> String {
+    let mut record = String::new();
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            record.push(',');
+        }
+        let field = field.as_ref();
+        if field.contains([',', '"', '\r', '\n']) {
+            record.push('"');
+            record.push_str(&field.replace('"', "\"\""));
+            record.push('"');
+        } else {
+            record.push_str(field);
+        }
+    }
+    record.push_str("\r\n");
+    record
+}
+
+/// Parses CSV input that arrives in chunks of any size, such as an upload body
+///
+/// Only the record currently being read is buffered, so memory use is bounded
+/// by `max_record_bytes` however large the input is. Fields that are not valid
+/// UTF-8 have the offending bytes replaced.
+pub struct CsvRecordReader {
+    reader: Reader,
+    output: Vec<u8>,
+    output_len: usize,
+    ends: Vec<usize>,
+    ends_len: usize,
+    max_record_bytes: usize,
+}
+
+impl CsvRecordReader {
+    pub fn new(max_record_bytes: usize) -> Self {
+        Self {
+            reader: Reader::new(),
+            output: vec![0; max_record_bytes.min(1024)],
+            output_len: 0,
+            ends: vec![0; 16],
+            ends_len: 0,
+            max_record_bytes,
+        }
+    }
+
+    /// Reads the next chunk of input, returning the records it completed
+    ///
+    /// # Returns
+    /// The completed records, or a `BadRequest` error if a record is too long
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<Vec<String>>, AppError> {
+        let mut records = Vec::new();
+        // An empty slice would tell the parser the input has ended
+        if !chunk.is_empty() {
+            self.read(chunk, &mut records)?;
+        }
+        Ok(records)
+    }
+
+    /// Marks the end of input, returning the last record if it had no line ending
+    pub fn finish(&mut self) -> Result<Vec<Vec<String>>, AppError> {
+        let mut records = Vec::new();
+        self.read(&[], &mut records)?;
+        Ok(records)
+    }
+
+    fn read(&mut self, mut input: &[u8], records: &mut Vec<Vec<String>>) -> Result<(), AppError> {
+        loop {
+            let (result, read, written, ended) = self.reader.read_record(
+                input,
+                &mut self.output[self.output_len..],
+                &mut self.ends[self.ends_len..],
+            );
+            input = &input[read..];
+            self.output_len += written;
+            self.ends_len += ended;
+
+            match result {
+                ReadRecordResult::InputEmpty | ReadRecordResult::End => return Ok(()),
+                ReadRecordResult::OutputFull => {
+                    if self.output.len() >= self.max_record_bytes {
+                        return Err(AppError::BadRequest(format!(
+                            "CSV rows must not be longer than {} bytes",
+                            self.max_record_bytes
+                        )));
+                    }
+                    let grown = (self.output.len() * 2).min(self.max_record_bytes);
+                    self.output.resize(grown, 0);
+                }
+                ReadRecordResult::OutputEndsFull => {
+                    if self.ends.len() >= MAX_FIELDS {
+                        return Err(AppError::BadRequest(format!(
+                            "CSV rows must not have more than {} fields",
+                            MAX_FIELDS
+                        )));
+                    }
+                    let grown = (self.ends.len() * 2).min(MAX_FIELDS);
+                    self.ends.resize(grown, 0);
+                }
+                ReadRecordResult::Record => {
+                    let mut start = 0;
+                    let record = self.ends[..self.ends_len]
+                        .iter()
+                        .map(|&end| {
+                            let field =
+                                String::from_utf8_lossy(&self.output[start..end]).into_owned();
+                            start = end;
+                            field
+                        })
+                        .collect();
+                    records.push(record);
+                    self.output_len = 0;
+                    self.ends_len = 0;
+                }
+            }
+        }
+    }
+}
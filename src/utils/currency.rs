@@ -0,0 +1,201 @@
+// Amounts are only ever handled as `Decimal`; see `utils::money`
+#![deny(
+    clippy::float_arithmetic,
+    clippy::float_cmp,
+    clippy::float_cmp_const,
+    clippy::lossy_float_literal,
+    clippy::cast_precision_loss
+)]
+
+use crate::utils::error::AppError;
+use crate::utils::money::round_to;
+use rust_decimal::{Decimal, RoundingStrategy};
+use validator::ValidationError;
+
+/// Active ISO 4217 currency codes, including precious metals and fund codes
+///
+/// Codes that don't denote money, such as `XXX` (no currency) and `XTS`
+/// (testing), are left out.
+static ISO_4217_CODES: [&str; 173] = [
+    "AED", "AFN", "ALL", "AMD", "ANG", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT",
+    "BGN", "BHD", "BIF", "BMD", "BND", "BOB", "BOV", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD",
+    "CAD", "CDF", "CHE", "CHF", "CHW", "CLF", "CLP", "CNY", "COP", "COU", "CRC", "CUP", "CVE",
+    "CZK", "DJF", "DKK", "DOP", "DZD", "EGP", "ERN", "ETB", "EUR", "FJD", "FKP", "GBP", "GEL",
+    "GHS", "GIP", "GMD", "GNF", "GTQ", "GYD", "HKD", "HNL", "HTG", "HUF", "IDR", "ILS", "INR",
+    "IQD", "IRR", "ISK", "JMD", "JOD", "JPY", "KES", "KGS", "KHR", "KMF", "KPW", "KRW", "KWD",
+    "KYD", "KZT", "LAK", "LBP", "LKR", "LRD", "LSL", "LYD", "MAD", "MDL", "MGA", "MKD", "MMK",
+    "MNT", "MOP", "MRU", "MUR", "MVR", "MWK", "MXN", "MXV", "MYR", "MZN", "NAD", "NGN", "NIO",
+    "NOK", "NPR", "NZD", "OMR", "PAB", "PEN", "PGK", "PHP", "PKR", "PLN", "PYG", "QAR", "RON",
+    "RSD", "RUB", "RWF", "SAR", "SBD", "SCR", "SDG", "SEK", "SGD", "SHP", "SLE", "SOS", "SRD",
+    "SSP", "STN", "SVC", "SYP", "SZL", "THB", "TJS", "TMT", "TND", "TOP", "TRY", "TTD", "TWD",
+    "TZS", "UAH", "UGX", "USD", "USN", "UYI", "UYU", "UYW", "UZS", "VED", "VES", "VND", "VUV",
+    "WST", "XAF", "XAG", "XAU", "XCD", "XCG", "XDR", "XOF", "XPD", "XPF", "XPT", "XSU", "XUA",
+    "YER", "ZAR", "ZMW", "ZWG",
+];
+
+/// Returns whether `code` is an active ISO 4217 currency code
+///
+/// Codes are matched exactly, so lowercase `usd` is not valid; normalize it
+/// with `normalize_currency` first.
+pub fn is_valid_iso4217(code: &str) -> bool {
+    ISO_4217_CODES.contains(&code)
+}
+
+/// Uppercases a client-supplied currency code so `usd` and `USD` are stored alike
+pub fn normalize_currency(code: &str) -> String {
+    code.trim().to_ascii_uppercase()
+}
+
+/// Validates that a requested currency is an ISO 4217 code, in any case
+///
+/// Used in `#[validate(custom = "validate_currency")]` attributes; the code
+/// is normalized with `normalize_currency` before it is stored.
+pub fn validate_currency(code: &str) -> Result<(), ValidationError> {
+    if !is_valid_iso4217(&normalize_currency(code)) {
+        let mut err = ValidationError::new("currency_iso4217");
+        err.message = Some("Currency must be an ISO 4217 code".into());
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Returns the number of minor-unit digits used by an ISO 4217 currency
+///
+/// Most currencies use two decimal places; the exceptions below are listed
+/// explicitly. Unknown codes fall back to two.
+pub fn currency_scale(currency: &str) -> u32 {
+    match currency.to_ascii_uppercase().as_str() {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+/// Represents an amount with exactly the currency's number of decimal places
+///
+/// Extra digits are rounded half-even; missing ones are padded, so `10.1` USD
+/// becomes `10.10`.
+pub fn to_currency_scale(amount: Decimal, currency: &str) -> Decimal {
+    round_to(
+        amount,
+        currency_scale(currency),
+        RoundingStrategy::MidpointNearestEven,
+    )
+}
+
+/// Represents a stored amount with at least the currency's number of decimal places
+///
+/// Unlike `to_currency_scale`, digits beyond the currency's scale are kept:
+/// they can only have been stored for an account with a finer balance scale.
+pub fn to_display_scale(amount: Decimal, currency: &str) -> Decimal {
+    let scale = currency_scale(currency).max(amount.normalize().scale());
+    round_to(amount, scale, RoundingStrategy::MidpointNearestEven)
+}
+
+/// Normalizes a client-supplied amount to the currency's scale
+///
+/// # Arguments
+/// * `amount` - The positive amount from the request
+/// * `currency` - Currency the amount is expressed in
+/// * `strict` - Reject amounts with more decimal places than the currency allows
+///   instead of rounding them
+///
+/// # Returns
+/// The normalized amount, a `Validation` error naming the currency and its
+/// scale if it has too many decimal places (strict mode), or a `BadRequest`
+/// error if it rounds down to zero
+pub fn normalize_amount(
+    amount: Decimal,
+    currency: &str,
+    strict: bool,
+) -> Result<Decimal, AppError> {
+    normalize_amount_to_scale(amount, currency_scale(currency), currency, strict)
+}
+
+/// Normalizes a client-supplied amount to `scale` decimal places
+///
+/// Like `normalize_amount`, for accounts whose balance scale differs from
+/// their currency's.
+pub fn normalize_amount_to_scale(
+    amount: Decimal,
+    scale: u32,
+    currency: &str,
+    strict: bool,
+) -> Result<Decimal, AppError> {
+    if strict && amount.normalize().scale() > scale {
+        return Err(AppError::Validation(format!(
+            "Amount {} has more than {} decimal places allowed for {}",
+            amount, scale, currency
+        )));
+    }
+
+    let normalized = round_to(amount, scale, RoundingStrategy::MidpointNearestEven);
+    if normalized <= Decimal::ZERO {
+        return Err(AppError::BadRequest(format!(
+            "Amount {} is too small for {}",
+            amount, currency
+        )));
+    }
+
+    Ok(normalized)
+}
+
+/// Header clients set to choose the units of the amounts they send
+pub const AMOUNT_UNITS_HEADER: &str = "Amount-Units";
+
+/// Units of the amounts in a request
+///
+/// Amounts are major units (`10.50` USD) unless the client asks for minor
+/// units (`1050` USD cents). Responses always use major units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AmountUnits {
+    #[default]
+    Major,
+    Minor,
+}
+
+impl AmountUnits {
+    /// Parses the value of the `Amount-Units` header, `major` or `minor`
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "major" => Ok(AmountUnits::Major),
+            "minor" => Ok(AmountUnits::Minor),
+            _ => Err(AppError::BadRequest(format!(
+                "Invalid {} header: expected 'major' or 'minor'",
+                AMOUNT_UNITS_HEADER
+            ))),
+        }
+    }
+
+    /// Expresses a request amount in major units of `currency`
+    pub fn to_major(self, amount: Decimal, currency: &str) -> Result<Decimal, AppError> {
+        match self {
+            AmountUnits::Major => Ok(amount),
+            AmountUnits::Minor => from_minor_units(amount, currency),
+        }
+    }
+}
+
+/// Converts a whole number of minor units to an amount in major units
+///
+/// The currency's scale decides the conversion: `1050` is `10.50` USD but
+/// `1050` JPY, and `1050` KWD is `1.050`.
+///
+/// # Returns
+/// The amount in major units, or a `BadRequest` error if `minor_units` has a
+/// fractional part
+pub fn from_minor_units(minor_units: Decimal, currency: &str) -> Result<Decimal, AppError> {
+    if !minor_units.fract().is_zero() {
+        return Err(AppError::BadRequest(format!(
+            "Amount {} in minor units must be a whole number",
+            minor_units
+        )));
+    }
+
+    let mut amount = minor_units.trunc();
+    amount
+        .set_scale(currency_scale(currency))
+        .map_err(|e| AppError::BadRequest(format!("Invalid amount {}: {}", minor_units, e)))?;
+    Ok(amount)
+}
@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+
+/// A plain-text email ready to be delivered
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers emails to users
+///
+/// Implementations wrap a concrete transport (SMTP relay, provider API). They
+/// are only called off the request path, so they may be slow, but must report
+/// failures through the returned error rather than panicking.
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    /// Sends a message, returning a description of the failure if it could not be delivered
+    async fn send(&self, message: &EmailMessage) -> Result<(), String>;
+}
+
+/// Email sender that writes messages to the log instead of delivering them
+///
+/// Used until a real transport is configured for a deployment.
+#[derive(Debug, Default)]
+pub struct LogEmailSender;
+
+#[async_trait]
+impl EmailSender for LogEmailSender {
+    async fn send(&self, message: &EmailMessage) -> Result<(), String> {
+        tracing::info!(to = %message.to, subject = %message.subject, "Email (not delivered)");
+        Ok(())
+    }
+}
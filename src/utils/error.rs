@@ -1,3 +1,4 @@
+use crate::middleware::request_context::current_request_id;
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
@@ -5,6 +6,7 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use uuid::Uuid;
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -23,14 +25,29 @@ pub enum AppError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
     #[error("Internal server error: {0}")]
     Internal(String),
 
     #[error("Database error: {0}")]
-    Database(#[from] sqlx::Error),
+    Database(sqlx::Error),
 
     #[error("Validation error: {0}")]
     Validation(String),
+
+    /// A request refused by a business rule, reported under the rule's own error code
+    #[error("Rejected ({code}): {message}")]
+    Rejected { code: &'static str, message: String },
+
+    /// A withdrawal or transfer that would take an account past a daily limit
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+
+    /// A transfer identical to one made moments before, likely submitted twice
+    #[error("Duplicate suspected: transfer matches {transaction_id}")]
+    DuplicateSuspected { transaction_id: Uuid },
 }
 
 #[derive(Serialize, Deserialize)]
@@ -39,47 +56,149 @@ pub struct ErrorResponse {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
+    /// ID of the failed request, for clients to quote when reporting it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, error, message) = match self {
-            AppError::Auth(msg) => (StatusCode::UNAUTHORIZED, "UNAUTHORIZED".to_string(), msg),
-            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, "FORBIDDEN".to_string(), msg),
-            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, "NOT_FOUND".to_string(), msg),
-            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "BAD_REQUEST".to_string(), msg),
-            AppError::Conflict(msg) => (StatusCode::CONFLICT, "CONFLICT".to_string(), msg),
-            AppError::Validation(msg) => {
-                (StatusCode::BAD_REQUEST, "VALIDATION_ERROR".to_string(), msg)
-            }
-            AppError::Database(e) => {
-                tracing::error!("Database error: {:?}", e);
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "DATABASE_ERROR".to_string(),
-                    "A database error occurred".to_string(),
-                )
+impl AppError {
+    /// The HTTP status, error code and message the error is reported with
+    ///
+    /// Server errors are reported with a generic message, so their details
+    /// never reach clients.
+    pub fn describe(&self) -> (StatusCode, String, String) {
+        match self {
+            AppError::Auth(msg) => (
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED".to_string(),
+                msg.clone(),
+            ),
+            AppError::Forbidden(msg) => {
+                (StatusCode::FORBIDDEN, "FORBIDDEN".to_string(), msg.clone())
             }
-            AppError::Internal(msg) => {
-                tracing::error!("Internal error: {}", msg);
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "INTERNAL_SERVER_ERROR".to_string(),
-                    "An internal server error occurred".to_string(),
-                )
+            AppError::NotFound(msg) => {
+                (StatusCode::NOT_FOUND, "NOT_FOUND".to_string(), msg.clone())
             }
+            AppError::BadRequest(msg) => (
+                StatusCode::BAD_REQUEST,
+                "BAD_REQUEST".to_string(),
+                msg.clone(),
+            ),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, "CONFLICT".to_string(), msg.clone()),
+            AppError::TooManyRequests(msg) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "TOO_MANY_REQUESTS".to_string(),
+                msg.clone(),
+            ),
+            AppError::Validation(msg) => (
+                StatusCode::BAD_REQUEST,
+                "VALIDATION_ERROR".to_string(),
+                msg.clone(),
+            ),
+            AppError::Rejected { code, message } => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                code.to_string(),
+                message.clone(),
+            ),
+            AppError::LimitExceeded(msg) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "LIMIT_EXCEEDED".to_string(),
+                msg.clone(),
+            ),
+            AppError::Database(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "DATABASE_ERROR".to_string(),
+                "A database error occurred".to_string(),
+            ),
+            AppError::Internal(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_SERVER_ERROR".to_string(),
+                "An internal server error occurred".to_string(),
+            ),
+            AppError::DuplicateSuspected { transaction_id } => (
+                StatusCode::CONFLICT,
+                "DUPLICATE_SUSPECTED".to_string(),
+                format!(
+                    "An identical transfer {} was just made; set allow_duplicate to make \
+                     this one as well",
+                    transaction_id
+                ),
+            ),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        match &self {
+            AppError::Database(e) => tracing::error!("Database error: {:?}", e),
+            AppError::Internal(msg) => tracing::error!("Internal error: {}", msg),
+            _ => {}
+        }
+        // The earlier transaction, so clients can show it instead of retrying
+        let details = match &self {
+            AppError::DuplicateSuspected { transaction_id } => Some(transaction_id.to_string()),
+            _ => None,
         };
+        let (status, error, message) = self.describe();
 
         let body = Json(ErrorResponse {
             error,
             message,
-            details: None,
+            details,
+            request_id: current_request_id(),
         });
 
         (status, body).into_response()
     }
 }
 
+/// Postgres error code for a row that duplicates a unique key
+const UNIQUE_VIOLATION: &str = "23505";
+
+/// Postgres error code for a row that fails a CHECK constraint
+const CHECK_VIOLATION: &str = "23514";
+
+/// The CHECK constraint keeping account balances from going negative
+const BALANCE_NON_NEGATIVE: &str = "balance_non_negative";
+
+/// The UNIQUE constraint allowing each external reference on one transaction
+const EXTERNAL_REFERENCE_UNIQUE: &str = "transactions_external_reference_key";
+
+impl From<sqlx::Error> for AppError {
+    /// Maps constraint violations to the client errors they stand for
+    ///
+    /// They usually mean a concurrent request got there first, e.g. a transfer
+    /// that passed the balance check before another one drained the account,
+    /// so they are reported as 409/400 rather than as a failing database.
+    fn from(err: sqlx::Error) -> Self {
+        if let sqlx::Error::Database(db_err) = &err {
+            match db_err.code().as_deref() {
+                Some(UNIQUE_VIOLATION)
+                    if db_err.constraint() == Some(EXTERNAL_REFERENCE_UNIQUE) =>
+                {
+                    return AppError::Conflict(
+                        "A transaction with this external reference already exists".to_string(),
+                    )
+                }
+                Some(UNIQUE_VIOLATION) => {
+                    return AppError::Conflict("The resource already exists".to_string())
+                }
+                Some(CHECK_VIOLATION) if db_err.constraint() == Some(BALANCE_NON_NEGATIVE) => {
+                    return AppError::BadRequest("Insufficient funds".to_string())
+                }
+                Some(CHECK_VIOLATION) => {
+                    return AppError::BadRequest(
+                        "The request violates a data constraint".to_string(),
+                    )
+                }
+                _ => {}
+            }
+        }
+        AppError::Database(err)
+    }
+}
+
 impl From<anyhow::Error> for AppError {
     fn from(err: anyhow::Error) -> Self {
         AppError::Internal(err.to_string())
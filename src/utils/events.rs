@@ -0,0 +1,135 @@
+use crate::models::outbox::OutboxEvent;
+use crate::models::transaction::{TransactionResponse, TransactionStatus};
+use async_trait::async_trait;
+use futures_util::stream::{self, Stream};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+/// Publishes events from the transactional outbox
+///
+/// Implementations wrap a concrete destination (a message broker, webhooks).
+/// Delivery is at least once: an event is handed over again if the server
+/// stops before recording that the sink accepted it, so consumers should
+/// de-duplicate on the event ID.
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    /// Publishes an event, returning a description of the failure if it could not be published
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), String>;
+}
+
+/// Event sink that writes events to the log
+///
+/// Used until a deployment configures a real destination.
+#[derive(Debug, Default)]
+pub struct LogEventSink;
+
+#[async_trait]
+impl EventSink for LogEventSink {
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), String> {
+        tracing::info!(
+            event_id = %event.id,
+            event_type = %event.event_type,
+            aggregate_id = %event.aggregate_id,
+            payload = %event.payload,
+            "Event published"
+        );
+        Ok(())
+    }
+}
+
+/// What a live event stream does when its subscriber falls further behind
+/// than the feed's channel holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LagBehavior {
+    /// Drops the missed events and tells the subscriber how many it lost
+    #[default]
+    Skip,
+    /// Ends the stream, so the client reconnects and catches up by polling
+    Disconnect,
+}
+
+impl FromStr for LagBehavior {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "skip" => Ok(Self::Skip),
+            "disconnect" => Ok(Self::Disconnect),
+            other => Err(format!("Unknown lag behavior '{}'", other)),
+        }
+    }
+}
+
+/// An item of an account's live event stream
+#[derive(Debug, Clone)]
+pub enum FeedItem {
+    /// A transaction involving the account completed
+    Completed(Arc<TransactionResponse>),
+    /// The subscriber fell behind and this many events were dropped, not
+    /// all of them necessarily involving the account
+    Lagged(u64),
+}
+
+/// Fans completed transactions out to live account event streams
+///
+/// Transactions are published once committed, and only reach the streams
+/// subscribed at that moment; clients catch up on anything earlier through
+/// the account history. Clones share the channel.
+#[derive(Debug, Clone)]
+pub struct TransactionFeed {
+    sender: broadcast::Sender<Arc<TransactionResponse>>,
+    lag_behavior: LagBehavior,
+}
+
+impl TransactionFeed {
+    /// Creates a feed holding up to `capacity` events for slow subscribers
+    ///
+    /// # Panics
+    /// If `capacity` is zero
+    pub fn new(capacity: usize, lag_behavior: LagBehavior) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            lag_behavior,
+        }
+    }
+
+    /// Hands a committed transaction to the current subscribers, if it completed
+    pub fn publish(&self, transaction: &TransactionResponse) {
+        if transaction.status != TransactionStatus::COMPLETED.to_string() {
+            return;
+        }
+        // Nobody listening is not an error
+        let _ = self.sender.send(Arc::new(transaction.clone()));
+    }
+
+    /// Streams the transactions involving `account_id` published from now on
+    ///
+    /// A subscriber that falls behind by more than the channel holds gets a
+    /// `FeedItem::Lagged`, or has its stream ended, as the feed's
+    /// `LagBehavior` says.
+    pub fn subscribe(&self, account_id: Uuid) -> impl Stream<Item = FeedItem> + Send + 'static {
+        let receiver = self.sender.subscribe();
+        let lag_behavior = self.lag_behavior;
+        stream::unfold(receiver, move |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(transaction) => {
+                        let involved = transaction.sender_account_id == Some(account_id)
+                            || transaction.receiver_account_id == Some(account_id);
+                        if involved {
+                            return Some((FeedItem::Completed(transaction), receiver));
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => match lag_behavior {
+                        LagBehavior::Skip => return Some((FeedItem::Lagged(skipped), receiver)),
+                        LagBehavior::Disconnect => return None,
+                    },
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,156 @@
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Locales supported for human-facing exports (CSV, HTML and statements)
+///
+/// This is a curated subset of BCP-47 `language-REGION` tags. The JSON API is
+/// never localized: it always uses RFC 3339 timestamps and dot-decimal amounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en-US")]
+    EnUs,
+    #[serde(rename = "en-GB")]
+    EnGb,
+    #[serde(rename = "de-DE")]
+    DeDe,
+    #[serde(rename = "fr-FR")]
+    FrFr,
+    #[serde(rename = "es-ES")]
+    EsEs,
+    #[serde(rename = "ja-JP")]
+    JaJp,
+}
+
+/// Separators and date layout used when rendering values for a locale
+struct LocaleFormat {
+    group_separator: &'static str,
+    decimal_separator: &'static str,
+    date_pattern: &'static str,
+}
+
+impl Locale {
+    /// All supported locales, in the order they are documented
+    pub const ALL: [Locale; 6] = [
+        Locale::EnUs,
+        Locale::EnGb,
+        Locale::DeDe,
+        Locale::FrFr,
+        Locale::EsEs,
+        Locale::JaJp,
+    ];
+
+    /// Returns the canonical BCP-47 tag of the locale (e.g. `de-DE`)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::EnGb => "en-GB",
+            Locale::DeDe => "de-DE",
+            Locale::FrFr => "fr-FR",
+            Locale::EsEs => "es-ES",
+            Locale::JaJp => "ja-JP",
+        }
+    }
+
+    fn format(&self) -> LocaleFormat {
+        match self {
+            Locale::EnUs => LocaleFormat {
+                group_separator: ",",
+                decimal_separator: ".",
+                date_pattern: "%m/%d/%Y",
+            },
+            Locale::EnGb => LocaleFormat {
+                group_separator: ",",
+                decimal_separator: ".",
+                date_pattern: "%d/%m/%Y",
+            },
+            Locale::DeDe => LocaleFormat {
+                group_separator: ".",
+                decimal_separator: ",",
+                date_pattern: "%d.%m.%Y",
+            },
+            Locale::FrFr => LocaleFormat {
+                // French typography groups digits with a narrow no-break space
+                group_separator: "\u{202f}",
+                decimal_separator: ",",
+                date_pattern: "%d/%m/%Y",
+            },
+            Locale::EsEs => LocaleFormat {
+                group_separator: ".",
+                decimal_separator: ",",
+                date_pattern: "%d/%m/%Y",
+            },
+            Locale::JaJp => LocaleFormat {
+                group_separator: ",",
+                decimal_separator: ".",
+                date_pattern: "%Y/%m/%d",
+            },
+        }
+    }
+
+    /// Formats an amount with the locale's digit grouping and decimal separator
+    ///
+    /// The scale of the amount is preserved, so callers should round to the
+    /// currency's minor units before rendering.
+    pub fn format_amount(&self, amount: Decimal) -> String {
+        let format = self.format();
+        let plain = amount.abs().to_string();
+        let (integer, fraction) = match plain.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (plain.as_str(), None),
+        };
+
+        let mut grouped = String::new();
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                grouped.push_str(format.group_separator);
+            }
+            grouped.push(digit);
+        }
+
+        if let Some(fraction) = fraction {
+            grouped.push_str(format.decimal_separator);
+            grouped.push_str(fraction);
+        }
+
+        if amount.is_sign_negative() && !amount.is_zero() {
+            format!("-{}", grouped)
+        } else {
+            grouped
+        }
+    }
+
+    /// Formats the calendar date (in UTC) of a timestamp using the locale's layout
+    pub fn format_date(&self, timestamp: DateTime<Utc>) -> String {
+        timestamp.format(self.format().date_pattern).to_string()
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = AppError;
+
+    /// Parses a `language-REGION` tag case-insensitively (`de-de` becomes `de-DE`)
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.as_str().eq_ignore_ascii_case(tag.trim()))
+            .ok_or_else(|| {
+                let supported: Vec<&str> = Locale::ALL.iter().map(|l| l.as_str()).collect();
+                AppError::Validation(format!(
+                    "Unsupported locale '{}'. Supported locales: {}",
+                    tag,
+                    supported.join(", ")
+                ))
+            })
+    }
+}
@@ -1,3 +1,12 @@
+pub mod account_number;
 pub mod auth;
+pub mod csv;
+pub mod currency;
+pub mod email;
 pub mod error;
+pub mod events;
+pub mod locale;
+pub mod money;
+pub mod name_match;
 pub mod response;
+pub mod totp;
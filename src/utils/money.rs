@@ -0,0 +1,86 @@
+// The only sanctioned arithmetic for fees, interest and currency conversion.
+// Everything here works on `Decimal`, checks for overflow instead of panicking,
+// and makes the caller choose the scale and rounding of every result. Floating
+// point is denied outright so a lossy shortcut cannot creep in.
+#![deny(
+    clippy::float_arithmetic,
+    clippy::float_cmp,
+    clippy::float_cmp_const,
+    clippy::lossy_float_literal,
+    clippy::cast_precision_loss
+)]
+
+use crate::utils::error::AppError;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Rounds an amount and represents it with exactly `scale` decimal places
+///
+/// Missing digits are padded, so `10.1` at scale 2 becomes `10.10`.
+pub fn round_to(amount: Decimal, scale: u32, strategy: RoundingStrategy) -> Decimal {
+    let mut rounded = amount.round_dp_with_strategy(scale, strategy);
+    rounded.rescale(scale);
+    rounded
+}
+
+/// Calculates `percent` percent of an amount, e.g. a 1.5% fee on 200.00
+///
+/// # Arguments
+/// * `amount` - The amount the percentage applies to
+/// * `percent` - The percentage, where `1.5` means 1.5%
+/// * `scale` - Decimal places of the result
+/// * `strategy` - How to round digits beyond `scale`
+pub fn percentage_of(
+    amount: Decimal,
+    percent: Decimal,
+    scale: u32,
+    strategy: RoundingStrategy,
+) -> Result<Decimal, AppError> {
+    let product = amount.checked_mul(percent).ok_or_else(overflow)?;
+    Ok(round_to(product / Decimal::ONE_HUNDRED, scale, strategy))
+}
+
+/// Scales an amount by `numerator / denominator`, e.g. 12 of 30 days of a charge
+///
+/// The multiplication happens before the division, so the only rounding is the
+/// final one to `scale`.
+///
+/// # Returns
+/// The pro-rated amount, or a `BadRequest` error if `denominator` is zero
+pub fn prorate(
+    amount: Decimal,
+    numerator: Decimal,
+    denominator: Decimal,
+    scale: u32,
+    strategy: RoundingStrategy,
+) -> Result<Decimal, AppError> {
+    if denominator.is_zero() {
+        return Err(AppError::BadRequest(
+            "Cannot pro-rate over a zero period".to_string(),
+        ));
+    }
+
+    let product = amount.checked_mul(numerator).ok_or_else(overflow)?;
+    let share = product.checked_div(denominator).ok_or_else(overflow)?;
+    Ok(round_to(share, scale, strategy))
+}
+
+/// Converts an amount into another currency at `rate` units of it per unit
+///
+/// # Arguments
+/// * `amount` - The amount in the source currency
+/// * `rate` - Units of the target currency per unit of the source currency
+/// * `scale` - Decimal places of the target currency
+/// * `strategy` - How to round digits beyond `scale`
+pub fn convert(
+    amount: Decimal,
+    rate: Decimal,
+    scale: u32,
+    strategy: RoundingStrategy,
+) -> Result<Decimal, AppError> {
+    let converted = amount.checked_mul(rate).ok_or_else(overflow)?;
+    Ok(round_to(converted, scale, strategy))
+}
+
+fn overflow() -> AppError {
+    AppError::BadRequest("Amount is too large to calculate with".to_string())
+}
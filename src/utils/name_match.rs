@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// Outcome of comparing a payee name with an account owner's name
+///
+/// - MATCH: The names are the same once case, punctuation and spacing are ignored
+/// - CLOSE_MATCH: The names differ only by a small typo or by word order
+/// - NO_MATCH: The names are unrelated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NameMatch {
+    Match,
+    CloseMatch,
+    NoMatch,
+}
+
+/// Lowercases a name and reduces it to its words, dropping punctuation
+fn name_words(name: &str) -> Vec<String> {
+    name.split(|c: char| c.is_whitespace() || c == '-' || c == '.' || c == ',')
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Number of single-character insertions, deletions, substitutions or
+/// adjacent transpositions turning `a` into `b` (optimal string alignment)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut distance = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in distance.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distance[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (distance[i - 1][j] + 1)
+                .min(distance[i][j - 1] + 1)
+                .min(distance[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(distance[i - 2][j - 2] + 1);
+            }
+            distance[i][j] = best;
+        }
+    }
+
+    distance[a.len()][b.len()]
+}
+
+/// Compares the name a sender typed with the name on record
+///
+/// Allows roughly one typo per five letters, and treats reordered words
+/// ("Doe Jane" for "Jane Doe") as a close match.
+pub fn match_name(candidate: &str, on_record: &str) -> NameMatch {
+    let candidate = name_words(candidate);
+    let on_record = name_words(on_record);
+    if candidate.is_empty() || on_record.is_empty() {
+        return NameMatch::NoMatch;
+    }
+
+    if candidate == on_record {
+        return NameMatch::Match;
+    }
+
+    let joined_candidate = candidate.join(" ");
+    let joined_on_record = on_record.join(" ");
+    let mut sorted_candidate = candidate.clone();
+    let mut sorted_on_record = on_record.clone();
+    sorted_candidate.sort();
+    sorted_on_record.sort();
+
+    let tolerance = (joined_on_record.chars().count() / 5).max(1);
+    let distance = edit_distance(&joined_candidate, &joined_on_record).min(edit_distance(
+        &sorted_candidate.join(" "),
+        &sorted_on_record.join(" "),
+    ));
+
+    if distance <= tolerance {
+        NameMatch::CloseMatch
+    } else {
+        NameMatch::NoMatch
+    }
+}
@@ -1,4 +1,6 @@
+use crate::utils::error::AppError;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 /// Standard API response structure for consistent response formats
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,3 +42,193 @@ impl<T> ApiResponse<T> {
         }
     }
 }
+
+/// One page of a list endpoint, with what a client needs to fetch the others
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PaginatedResponse<T> {
+    /// The items on this page
+    pub items: Vec<T>,
+    /// Number of items matching the query across all pages
+    pub total_count: i64,
+    /// Page size the items were fetched with
+    pub limit: i64,
+    /// Number of matching items skipped before this page
+    pub offset: i64,
+    /// Cursor fetching the next page of a keyset-paginated listing - only
+    /// included when there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> ApiResponse<PaginatedResponse<T>> {
+    /// Creates a success response carrying one page of a listing
+    ///
+    /// # Arguments
+    /// * `message` - A message describing the successful operation
+    /// * `items` - The items on the page
+    /// * `total_count` - Number of items matching the query across all pages
+    /// * `limit` - Page size the items were fetched with
+    /// * `offset` - Number of matching items skipped before this page
+    pub fn paginated(
+        message: impl Into<String>,
+        items: Vec<T>,
+        total_count: i64,
+        limit: i64,
+        offset: i64,
+    ) -> Self {
+        Self::success(
+            message,
+            PaginatedResponse {
+                items,
+                total_count,
+                limit,
+                offset,
+                next_cursor: None,
+            },
+        )
+    }
+
+    /// Attaches the cursor of the page following this one, if any
+    ///
+    /// # Arguments
+    /// * `next_cursor` - The opaque cursor clients pass back to continue the listing
+    pub fn with_next_cursor(mut self, next_cursor: Option<String>) -> Self {
+        if let Some(page) = self.data.as_mut() {
+            page.next_cursor = next_cursor;
+        }
+        self
+    }
+}
+
+/// Response types whose items can be trimmed with a `?fields=` query parameter
+///
+/// `FIELDS` must list every key the type serializes; it is the allowlist that
+/// requested field names are checked against.
+pub trait SelectableFields {
+    const FIELDS: &'static [&'static str];
+}
+
+/// A list item that is either returned in full or trimmed to the requested fields
+///
+/// Full items serialize exactly like `T`, so omitting `?fields=` leaves the
+/// response shape unchanged.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Sparse<T> {
+    Full(T),
+    Partial(Map<String, Value>),
+}
+
+/// Applies a comma-separated `fields` selection to a list of response items
+///
+/// # Arguments
+/// * `items` - The items to return
+/// * `fields` - The raw `fields` query parameter, if any (e.g. `id,amount,created_at`)
+///
+/// # Returns
+/// The items, trimmed to the requested fields when a selection was given, or
+/// `AppError::BadRequest` naming any field that `T` does not have
+pub fn select_fields<T>(items: Vec<T>, fields: Option<&str>) -> Result<Vec<Sparse<T>>, AppError>
+where
+    T: Serialize + SelectableFields,
+{
+    let requested: Vec<&str> = fields
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .collect();
+
+    if requested.is_empty() {
+        return Ok(items.into_iter().map(Sparse::Full).collect());
+    }
+
+    let unknown: Vec<&str> = requested
+        .iter()
+        .copied()
+        .filter(|field| !T::FIELDS.contains(field))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(AppError::BadRequest(format!(
+            "Unknown field(s): {}. Allowed fields: {}",
+            unknown.join(", "),
+            T::FIELDS.join(", ")
+        )));
+    }
+
+    items
+        .into_iter()
+        .map(|item| {
+            let mut object = match serde_json::to_value(item) {
+                Ok(Value::Object(object)) => object,
+                Ok(_) => {
+                    return Err(AppError::Internal(
+                        "Field selection requires items that serialize to objects".to_string(),
+                    ))
+                }
+                Err(e) => return Err(AppError::Internal(format!("Serialization failed: {}", e))),
+            };
+            object.retain(|key, _| requested.contains(&key.as_str()));
+            Ok(Sparse::Partial(object))
+        })
+        .collect()
+}
+
+/// Total number of items behind a paginated response
+///
+/// Handlers returning a `PaginatedResponse` attach it as a response extension;
+/// the `/api/v2` tree then returns the bare items and turns the count into an
+/// `X-Total-Count` header, while `/api/v1` responses are left as they are.
+#[derive(Debug, Clone, Copy)]
+pub struct TotalCount(pub i64);
+
+/// Builds an RFC 5988 `Link` header value for an offset-paginated listing
+///
+/// Links reuse the request path and query string, replacing only `limit` and
+/// `offset`, so filters and field selections carry over between pages.
+///
+/// # Arguments
+/// * `uri` - The original request URI (before any router nesting strips it)
+/// * `limit` - Page size used for the current page (must be positive)
+/// * `offset` - Offset of the current page
+/// * `total` - Total number of items matching the query
+///
+/// # Returns
+/// A header value containing `first` and `last` links, plus `prev` and `next`
+/// when those pages exist
+pub fn pagination_links(uri: &axum::http::Uri, limit: i64, offset: i64, total: i64) -> String {
+    let retained: Vec<&str> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| {
+            let key = pair.split('=').next().unwrap_or_default();
+            !pair.is_empty() && key != "limit" && key != "offset"
+        })
+        .collect();
+
+    let page_url = |page_offset: i64| {
+        let mut query = retained.clone();
+        let paging = format!("limit={}&offset={}", limit, page_offset);
+        query.push(&paging);
+        format!("<{}?{}>", uri.path(), query.join("&"))
+    };
+
+    let last_offset = if total > 0 {
+        (total - 1) / limit * limit
+    } else {
+        0
+    };
+
+    let mut links = vec![format!("{}; rel=\"first\"", page_url(0))];
+    if offset > 0 {
+        let prev_offset = (offset - limit).max(0);
+        links.push(format!("{}; rel=\"prev\"", page_url(prev_offset)));
+    }
+    if offset + limit < total {
+        links.push(format!("{}; rel=\"next\"", page_url(offset + limit)));
+    }
+    links.push(format!("{}; rel=\"last\"", page_url(last_offset)));
+
+    links.join(", ")
+}
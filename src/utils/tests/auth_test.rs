@@ -1,7 +1,11 @@
 #[cfg(test)]
 mod tests {
-    use crate::utils::auth::{generate_jwt, hash_password, validate_jwt, verify_password};
+    use crate::utils::auth::{
+        generate_jwt, generate_refresh_token, hash_password, validate_jwt, validate_refresh_token,
+        verify_password, TokenType,
+    };
     use crate::utils::error::AppError;
+    use chrono::Duration;
     use uuid::Uuid;
 
     #[test]
@@ -31,7 +35,7 @@ mod tests {
         let secret = "test_secret_key";
         
         // Generate JWT
-        let jwt_result = generate_jwt(user_id, username, secret);
+        let jwt_result = generate_jwt(user_id, username, "USER", secret, Duration::hours(24));
         assert!(jwt_result.is_ok());
         
         let token = jwt_result.unwrap();
@@ -43,9 +47,37 @@ mod tests {
         let token_data = validate_result.unwrap();
         assert_eq!(token_data.claims.sub, user_id.to_string());
         assert_eq!(token_data.claims.username, username);
+
+        // Every access token has its own ID, so it can be revoked on its own
+        let other = generate_jwt(user_id, username, "USER", secret, Duration::hours(24)).unwrap();
+        let other_id = validate_jwt(&other, secret).unwrap().claims.jti;
+        assert!(token_data.claims.jti.is_some());
+        assert_ne!(token_data.claims.jti, other_id);
         
         // Validate with wrong secret
         let validate_result = validate_jwt(&token, "wrong_secret");
         assert!(validate_result.is_err());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_access_and_refresh_tokens_are_not_interchangeable() {
+        let user_id = Uuid::new_v4();
+        let token_id = Uuid::new_v4();
+        let secret = "test_secret_key";
+
+        let access = generate_jwt(user_id, "testuser", "USER", secret, Duration::hours(24)).unwrap();
+        let refresh = generate_refresh_token(user_id, "testuser", "USER", token_id, secret).unwrap();
+
+        // Refresh tokens can't authenticate requests, and access tokens can't be refreshed
+        assert!(matches!(validate_jwt(&refresh, secret), Err(AppError::Auth(_))));
+        assert!(matches!(
+            validate_refresh_token(&access, secret),
+            Err(AppError::Auth(_))
+        ));
+
+        let claims = validate_refresh_token(&refresh, secret).unwrap().claims;
+        assert_eq!(claims.token_type, TokenType::Refresh);
+        assert_eq!(claims.jti, Some(token_id.to_string()));
+        assert!(claims.exp - claims.iat >= 7 * 24 * 60 * 60);
+    }
+}
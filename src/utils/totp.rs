@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use uuid::Uuid;
+
+/// Seconds each code is valid for, as assumed by authenticator apps
+pub const TOTP_STEP_SECONDS: i64 = 30;
+
+/// Digits in a code
+pub const TOTP_DIGITS: u32 = 6;
+
+/// Steps either side of the current one whose codes are still accepted, to
+/// tolerate clocks that drift apart
+pub const TOTP_ALLOWED_DRIFT_STEPS: i64 = 1;
+
+/// Bytes in a generated secret; 160 bits, as RFC 4226 recommends for SHA-1
+const SECRET_BYTES: usize = 20;
+
+/// RFC 4648 base32 alphabet, which authenticator apps expect secrets in
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Returns a new random secret, base32-encoded without padding
+pub fn generate_secret() -> String {
+    let mut bytes = Vec::with_capacity(SECRET_BYTES);
+    while bytes.len() < SECRET_BYTES {
+        // The last 7 bytes of a v4 UUID are random; the others carry its
+        // version and variant
+        let uuid = Uuid::new_v4();
+        let random = &uuid.as_bytes()[9..];
+        bytes.extend_from_slice(&random[..random.len().min(SECRET_BYTES - bytes.len())]);
+    }
+    base32_encode(&bytes)
+}
+
+/// Builds the `otpauth://` URI authenticator apps import a secret from,
+/// usually through a QR code
+pub fn provisioning_uri(secret: &str, account_name: &str, issuer: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(issuer),
+        percent_encode(account_name),
+        secret,
+        percent_encode(issuer),
+        TOTP_DIGITS,
+        TOTP_STEP_SECONDS
+    )
+}
+
+/// Returns the time step a moment falls in
+pub fn time_step(at: DateTime<Utc>) -> i64 {
+    at.timestamp().div_euclid(TOTP_STEP_SECONDS)
+}
+
+/// Computes the code for a time step (RFC 6238), zero-padded to `TOTP_DIGITS`
+///
+/// Returns `None` if the secret isn't valid base32.
+pub fn code_at_step(secret: &str, step: i64) -> Option<String> {
+    let key = base32_decode(secret)?;
+    let mut mac = Hmac::<Sha1>::new_from_slice(&key).expect("HMAC accepts keys of any length");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    // Dynamic truncation (RFC 4226, section 5.3)
+    let offset = usize::from(digest[digest.len() - 1] & 0x0f);
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    let code = binary % 10u32.pow(TOTP_DIGITS);
+    Some(format!("{:0width$}", code, width = TOTP_DIGITS as usize))
+}
+
+/// Checks a code against the steps around `at`
+///
+/// # Returns
+/// The time step the code belongs to, so callers can refuse to accept a
+/// step twice, or `None` if it matches none of them
+pub fn verify_code(secret: &str, code: &str, at: DateTime<Utc>) -> Option<i64> {
+    if code.len() != TOTP_DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let current = time_step(at);
+    (current - TOTP_ALLOWED_DRIFT_STEPS..=current + TOTP_ALLOWED_DRIFT_STEPS)
+        .find(|&step| code_at_step(secret, step).is_some_and(|expected| expected == code))
+}
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+/// Decodes base32, ignoring case, padding and spaces
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(encoded.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in encoded.bytes().filter(|&c| c != b'=' && c != b' ') {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// Percent-encodes everything but RFC 3986 unreserved characters
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,29 @@
+use crate::services::integrity_service::IntegrityService;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// Spawns a task that runs the integrity sweep every `interval`
+///
+/// The first sweep runs one full interval after startup, so restarts don't
+/// trigger a scan. A sweep that fails to complete is logged and retried at the
+/// next tick; the task runs until the runtime shuts down.
+pub fn spawn_integrity_worker(
+    integrity_service: Arc<IntegrityService>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            if let Err(e) = integrity_service.run_check().await {
+                tracing::error!("Integrity check could not complete: {}", e);
+            }
+        }
+    })
+}
@@ -0,0 +1,8 @@
+pub mod integrity;
+pub mod outbox;
+pub mod retention;
+pub mod revocations;
+pub mod scheduled_transfers;
+pub mod standing_orders;
+pub mod usage;
+pub mod webhooks;
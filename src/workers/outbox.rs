@@ -0,0 +1,34 @@
+use crate::services::outbox_service::OutboxService;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// How often the outbox is checked for events to publish
+pub const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Spawns a task that publishes the events recorded in the outbox
+///
+/// Events recorded while the server was down are published at the first poll
+/// after it starts. Each poll publishes until nothing is left or the sink
+/// refuses an event; a failed poll is logged and retried at the next tick.
+pub fn spawn_outbox_publisher(outbox_service: Arc<OutboxService>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(OUTBOX_POLL_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            loop {
+                match outbox_service.publish_pending().await {
+                    Ok(0) => break,
+                    Ok(published) => tracing::debug!("Published {} outbox events", published),
+                    Err(e) => {
+                        tracing::error!("Publishing outbox events failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
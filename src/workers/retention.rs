@@ -0,0 +1,37 @@
+use crate::services::diagnostics_service::DiagnosticsService;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// Time between retention runs
+pub const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Spawns a task that deletes transaction timings older than `retention`
+/// every `RETENTION_INTERVAL`
+///
+/// The first run happens at startup, so timings don't outlive their retention
+/// just because the server restarts often. A failed run is logged and retried
+/// at the next tick.
+pub fn spawn_retention_worker(
+    diagnostics_service: Arc<DiagnosticsService>,
+    retention: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let retention = chrono::Duration::from_std(retention).unwrap_or(chrono::Duration::MAX);
+        let mut ticker = tokio::time::interval(RETENTION_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            let Some(cutoff) = chrono::Utc::now().checked_sub_signed(retention) else {
+                continue;
+            };
+            match diagnostics_service.purge_timings(cutoff).await {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!("Purged {} expired transaction timings", deleted),
+                Err(e) => tracing::error!("Transaction timing purge failed: {}", e),
+            }
+        }
+    })
+}
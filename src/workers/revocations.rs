@@ -0,0 +1,28 @@
+use crate::services::user_service::UserService;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// Time between purges of expired token revocations
+pub const REVOCATION_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Spawns a task that forgets revocations of expired tokens every
+/// `REVOCATION_PURGE_INTERVAL`, so `revoked_tokens` doesn't grow unbounded
+///
+/// A failed purge is logged and retried at the next tick.
+pub fn spawn_revocation_purge_worker(user_service: Arc<UserService>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(REVOCATION_PURGE_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            match user_service.purge_expired_revocations().await {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!("Purged {} expired token revocations", deleted),
+                Err(e) => tracing::error!("Token revocation purge failed: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,38 @@
+use crate::services::scheduled_transfer_service::ScheduledTransferService;
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// How often due scheduled transfers are looked for
+pub const SCHEDULED_TRANSFER_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Spawns a task that expires overdue scheduled transfers and runs due ones
+/// every poll interval
+///
+/// Transfers that came due while the server was down are run at the first
+/// poll after it starts, unless they expired in the meantime. A failed poll is
+/// logged and retried at the next tick.
+pub fn spawn_scheduled_transfer_worker(
+    scheduled_transfer_service: Arc<ScheduledTransferService>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(SCHEDULED_TRANSFER_POLL_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            match scheduled_transfer_service.expire_overdue(Utc::now()).await {
+                Ok(0) => {}
+                Ok(expired) => tracing::info!("Expired {} scheduled transfers", expired),
+                Err(e) => tracing::error!("Expiring scheduled transfers failed: {}", e),
+            }
+            match scheduled_transfer_service.execute_due().await {
+                Ok(0) => {}
+                Ok(executed) => tracing::info!("Ran {} scheduled transfers", executed),
+                Err(e) => tracing::error!("Running scheduled transfers failed: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,29 @@
+use crate::services::standing_order_service::StandingOrderService;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// How often due standing order occurrences are looked for
+pub const STANDING_ORDER_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Spawns a task that runs due standing order occurrences every poll interval
+///
+/// A failed poll is logged and retried at the next tick.
+pub fn spawn_standing_order_worker(
+    standing_order_service: Arc<StandingOrderService>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(STANDING_ORDER_POLL_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            match standing_order_service.execute_due().await {
+                Ok(0) => {}
+                Ok(executed) => tracing::info!("Ran {} standing order occurrences", executed),
+                Err(e) => tracing::error!("Running standing orders failed: {}", e),
+            }
+        }
+    })
+}
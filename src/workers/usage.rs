@@ -0,0 +1,29 @@
+use crate::services::usage_service::UsageService;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// Spawns a task that flushes recorded API usage every `interval`
+///
+/// A failed flush is logged and its counts are retried at the next tick.
+/// Counts recorded after the last tick are written by the final flush the
+/// server performs on shutdown.
+pub fn spawn_usage_flush_worker(
+    usage_service: Arc<UsageService>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            if let Err(e) = usage_service.flush().await {
+                tracing::error!("API usage flush failed: {}", e);
+            }
+        }
+    })
+}
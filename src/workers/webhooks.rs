@@ -0,0 +1,28 @@
+use crate::services::webhook_service::WebhookService;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// How often webhook deliveries due for a retry are looked for
+pub const WEBHOOK_RETRY_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Spawns a task that retries failed webhook deliveries once they are due
+///
+/// Retries that came due while the server was down are made at the first
+/// poll after it starts. A failed poll is logged and retried at the next tick.
+pub fn spawn_webhook_retry_worker(webhook_service: Arc<WebhookService>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(WEBHOOK_RETRY_POLL_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            match webhook_service.retry_due().await {
+                Ok(0) => {}
+                Ok(retried) => tracing::info!("Retried {} webhook deliveries", retried),
+                Err(e) => tracing::error!("Retrying webhook deliveries failed: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,180 @@
+use crate::integration::setup::{
+    create_account_service_with_config, create_user_service, setup, teardown,
+};
+use std::collections::HashSet;
+use txn_manager::utils::account_number::AccountNumberFormat;
+use txn_manager::{AccountConfig, CreateUserRequest};
+use uuid::Uuid;
+
+fn all_formats() -> Vec<AccountNumberFormat> {
+    vec![
+        AccountNumberFormat::Numeric10,
+        AccountNumberFormat::Prefixed("TXNM".to_string()),
+        AccountNumberFormat::IbanLike("TXNM".to_string()),
+    ]
+}
+
+#[test]
+fn test_each_format_generates_valid_unique_numbers() {
+    for format in all_formats() {
+        let generator = format.generator();
+        let mut seen = HashSet::new();
+
+        for _ in 0..10_000 {
+            let number = generator.generate();
+            assert!(
+                generator.is_valid(&number),
+                "{:?} produced invalid number {}",
+                format,
+                number
+            );
+            seen.insert(number);
+        }
+
+        // Collisions are handled by the database, but should be vanishingly rare
+        assert!(
+            seen.len() > 9_990,
+            "{:?} produced too many duplicates",
+            format
+        );
+    }
+}
+
+#[test]
+fn test_generators_reject_malformed_numbers() {
+    let numeric = AccountNumberFormat::Numeric10.generator();
+    assert!(numeric.is_valid("4829103755"));
+    assert!(!numeric.is_valid("4829103757"), "wrong check digit");
+    assert!(!numeric.is_valid("482910375"), "too short");
+    assert!(!numeric.is_valid("48291037a5"), "not numeric");
+
+    let prefixed = AccountNumberFormat::Prefixed("TXNM".to_string()).generator();
+    assert!(prefixed.is_valid("TXNM-4829103755"));
+    assert!(!prefixed.is_valid("ACME-4829103755"), "wrong prefix");
+    assert!(!prefixed.is_valid("TXNM4829103755"), "missing separator");
+
+    let iban = AccountNumberFormat::IbanLike("TXNM".to_string()).generator();
+    assert!(iban.is_valid("XX79TXNM00012345678901"));
+    assert!(
+        !iban.is_valid("XX78TXNM00012345678901"),
+        "wrong check digits"
+    );
+    assert!(!iban.is_valid("XX79ACME00012345678901"), "wrong bank code");
+    assert!(!iban.is_valid("XX79TXNM0001234567890é"), "non-ASCII");
+}
+
+#[test]
+fn test_format_from_parts() {
+    assert_eq!(
+        AccountNumberFormat::from_parts("numeric10", "TXNM"),
+        Ok(AccountNumberFormat::Numeric10)
+    );
+    assert_eq!(
+        AccountNumberFormat::from_parts("Prefixed", "ACME"),
+        Ok(AccountNumberFormat::Prefixed("ACME".to_string()))
+    );
+    assert_eq!(
+        AccountNumberFormat::from_parts("iban", "acme"),
+        Ok(AccountNumberFormat::IbanLike("ACME".to_string()))
+    );
+
+    assert!(AccountNumberFormat::from_parts("swift", "TXNM").is_err());
+    assert!(AccountNumberFormat::from_parts("prefixed", "").is_err());
+    assert!(AccountNumberFormat::from_parts("prefixed", "AC-ME").is_err());
+    assert!(AccountNumberFormat::from_parts("iban", "TOOLONG").is_err());
+}
+
+#[tokio::test]
+async fn test_accounts_get_numbers_in_configured_format() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+
+    let user = user_service
+        .create_user(CreateUserRequest {
+            username: "numbereduser".to_string(),
+            email: "numbered@example.com".to_string(),
+            password: "securepassword".to_string(),
+            first_name: None,
+            last_name: None,
+        })
+        .await
+        .unwrap();
+
+    let mut numbers = HashSet::new();
+    for format in all_formats() {
+        let generator = format.generator();
+        let account_service = create_account_service_with_config(
+            pool.clone(),
+            AccountConfig {
+                number_format: format.clone(),
+                ..AccountConfig::default()
+            },
+        );
+
+        for _ in 0..5 {
+            let account = account_service
+                .create_account(user.id, "EUR".to_string())
+                .await
+                .unwrap();
+            let number = account
+                .account_number
+                .clone()
+                .expect("account number assigned");
+            assert!(generator.is_valid(&number), "{:?}: {}", format, number);
+            assert!(numbers.insert(number), "duplicate account number");
+
+            // The number is stored, not just returned
+            let stored = account_service.get_account_by_id(account.id).await.unwrap();
+            assert_eq!(stored.account_number, account.account_number);
+        }
+    }
+
+    // The default account opened at registration is numbered too
+    let accounts = create_account_service_with_config(pool.clone(), AccountConfig::default())
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap();
+    let default_account = accounts.iter().find(|a| a.currency == "USD").unwrap();
+    assert!(AccountNumberFormat::Numeric10
+        .generator()
+        .is_valid(default_account.account_number.as_deref().unwrap()));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_account_numbers_are_unique_in_database() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+
+    let user = user_service
+        .create_user(CreateUserRequest {
+            username: "duplicatenumber".to_string(),
+            email: "duplicatenumber@example.com".to_string(),
+            password: "securepassword".to_string(),
+            first_name: None,
+            last_name: None,
+        })
+        .await
+        .unwrap();
+
+    let taken: String =
+        sqlx::query_scalar("SELECT account_number FROM accounts WHERE user_id = $1")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    let result = sqlx::query(
+        "INSERT INTO accounts (id, user_id, account_number, balance, currency) \
+         VALUES ($1, $2, $3, 0, 'EUR')",
+    )
+    .bind(Uuid::new_v4())
+    .bind(user.id)
+    .bind(&taken)
+    .execute(&pool)
+    .await;
+    assert!(result.is_err(), "duplicate account number was accepted");
+
+    teardown(&db_url).await;
+}
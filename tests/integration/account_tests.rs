@@ -1,6 +1,17 @@
-use crate::integration::setup::{create_account_service, create_user_service, setup, teardown};
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, promote_to_admin, register_user, send_request, setup, teardown,
+};
+use axum::http::{Method, StatusCode};
+use chrono::{SecondsFormat, Utc};
 use rust_decimal::Decimal;
-use txn_manager::CreateUserRequest;
+use serde_json::json;
+use std::str::FromStr;
+use std::time::Duration;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::utils::error::AppError;
+use txn_manager::utils::name_match::{match_name, NameMatch};
+use txn_manager::{CreateUserRequest, DepositRequest, TransferRequest, WithdrawalRequest};
 use uuid::Uuid;
 
 #[tokio::test]
@@ -293,3 +304,1529 @@ async fn test_retrieve_non_existent_account() {
     // Clean up test environment
     teardown(&db_url).await;
 }
+
+#[test]
+fn test_name_matching_rules() {
+    assert_eq!(match_name("Jane Doe", "Jane Doe"), NameMatch::Match);
+    assert_eq!(match_name("  jane   DOE ", "Jane Doe"), NameMatch::Match);
+    assert_eq!(
+        match_name("Mary-Jane O'Neil", "Mary Jane ONeil"),
+        NameMatch::Match
+    );
+
+    assert_eq!(match_name("Jane Deo", "Jane Doe"), NameMatch::CloseMatch);
+    assert_eq!(match_name("Jayne Doe", "Jane Doe"), NameMatch::CloseMatch);
+    assert_eq!(match_name("Doe Jane", "Jane Doe"), NameMatch::CloseMatch);
+
+    assert_eq!(match_name("John Smith", "Jane Doe"), NameMatch::NoMatch);
+    assert_eq!(match_name("Jane", "Jane Doe"), NameMatch::NoMatch);
+    assert_eq!(match_name("Jane Doe", ""), NameMatch::NoMatch);
+}
+
+#[tokio::test]
+async fn test_account_name_check() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+
+    // register_user names everyone "Test User"
+    let payee = register_user(&user_service, "namecheckpayee").await;
+    register_user(&user_service, "namechecksender").await;
+    let token = login_token(&app, "namechecksender").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let uri = format!("/api/v1/accounts/{}/name-check", account_id);
+
+    for (name, expected) in [
+        ("test user", "MATCH"),
+        ("Test Usr", "CLOSE_MATCH"),
+        ("Someone Else", "NO_MATCH"),
+    ] {
+        let (status, _, body) = send_request(
+            &app,
+            Method::POST,
+            &uri,
+            Some(&token),
+            &[],
+            Some(json!({ "name": name })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["data"],
+            json!({ "result": expected }),
+            "name {:?}",
+            name
+        );
+        assert!(
+            !body.to_string().contains("Test User"),
+            "Owner name leaked: {}",
+            body
+        );
+    }
+
+    // Unknown accounts and empty names are rejected
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        &format!("/api/v1/accounts/{}/name-check", Uuid::new_v4()),
+        Some(&token),
+        &[],
+        Some(json!({ "name": "Test User" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        &uri,
+        Some(&token),
+        &[],
+        Some(json!({ "name": "" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_account_balance_as_of() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "asofuser").await;
+    let payee = register_user(&user_service, "asofpayee").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let deposit = |amount| DepositRequest {
+        account_id: account,
+        amount: Decimal::from(amount),
+        currency: None,
+        exchange_rate: None,
+        description: None,
+        category: None,
+        external_reference: None,
+    };
+
+    let before_any = Utc::now();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    transaction_service
+        .process_deposit(deposit(100))
+        .await
+        .unwrap();
+    transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account,
+                receiver_account_id: payee_account,
+                amount: Decimal::from(30),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let checkpoint = Utc::now();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    transaction_service
+        .process_deposit(deposit(50))
+        .await
+        .unwrap();
+
+    // The later deposit is not part of the balance at the checkpoint
+    let historical = account_service
+        .get_account_as_of(account, Some(checkpoint))
+        .await
+        .unwrap();
+    assert_eq!(historical.balance, Decimal::from(70));
+    assert_eq!(historical.balance_as_of, Some(checkpoint));
+    let payee_historical = account_service
+        .get_account_as_of(payee_account, Some(checkpoint))
+        .await
+        .unwrap();
+    assert_eq!(payee_historical.balance, Decimal::from(30));
+    let empty = account_service
+        .get_account_as_of(account, Some(before_any))
+        .await
+        .unwrap();
+    assert_eq!(empty.balance, Decimal::ZERO);
+
+    let current = account_service.get_account_by_id(account).await.unwrap();
+    assert_eq!(current.balance, Decimal::from(120));
+    assert_eq!(current.balance_as_of, None);
+
+    // Over HTTP, a historical balance is labelled as such
+    let token = login_token(&app, "asofuser").await;
+    let timestamp = checkpoint.to_rfc3339_opts(SecondsFormat::Micros, true);
+    let uri = format!("/api/v1/accounts/{}?as_of={}", account, timestamp);
+    let (status, _, body) = send_request(&app, Method::GET, &uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["balance"], "70");
+    assert_eq!(body["data"]["balance_as_of"], timestamp.as_str());
+
+    let uri = format!("/api/v1/accounts/{}", account);
+    let (_, _, body) = send_request(&app, Method::GET, &uri, Some(&token), &[], None).await;
+    assert_eq!(body["data"]["balance"], "120");
+    assert!(body["data"].get("balance_as_of").is_none());
+
+    let future =
+        (Utc::now() + chrono::Duration::days(1)).to_rfc3339_opts(SecondsFormat::Secs, true);
+    let uri = format!("/api/v1/accounts/{}?as_of={}", account, future);
+    let (status, _, _) = send_request(&app, Method::GET, &uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    teardown(&db_url).await;
+}
+#[tokio::test]
+async fn test_merge_duplicate_accounts() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "mergeowner").await;
+    let other = register_user(&user_service, "mergeother").await;
+    register_user(&user_service, "mergeadmin").await;
+    promote_to_admin(&pool, "mergeadmin").await;
+    let target = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let source = account_service
+        .create_account(user.id, "USD".to_string())
+        .await
+        .unwrap()
+        .id;
+    let other_account = account_service
+        .get_accounts_by_user_id(other.id)
+        .await
+        .unwrap()[0]
+        .id;
+    for (account_id, amount) in [(target, 100), (source, 50), (other_account, 100)] {
+        transaction_service
+            .process_deposit(DepositRequest {
+                account_id,
+                amount: Decimal::from(amount),
+                currency: None,
+                exchange_rate: None,
+                description: None,
+                category: None,
+                external_reference: None,
+            })
+            .await
+            .unwrap();
+    }
+    let transfer = |sender_account_id, receiver_account_id, amount| {
+        let transaction_service = &transaction_service;
+        async move {
+            transaction_service
+                .process_transfer(
+                    TransferRequest {
+                        sender_account_id,
+                        receiver_account_id,
+                        amount: Decimal::from(amount),
+                        description: None,
+                        category: None,
+                        allow_duplicate: false,
+                    },
+                    &RequestContext::default(),
+                )
+                .await
+                .unwrap()
+        }
+    };
+    transfer(other_account, source, 5).await;
+    transfer(source, other_account, 15).await;
+    let between = transfer(target, source, 20).await;
+
+    // Merging is for administrators only
+    let merge_uri = format!("/api/v1/admin/accounts/{}/merge?into={}", source, target);
+    let owner_token = login_token(&app, "mergeowner").await;
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        &merge_uri,
+        Some(&owner_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let admin_token = login_token(&app, "mergeadmin").await;
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        &format!(
+            "/api/v1/admin/accounts/{}/merge?into={}",
+            other_account, target
+        ),
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        &merge_uri,
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let merge = &body["data"];
+    assert_eq!(merge["transactions_moved"], 3);
+    assert_eq!(merge["source"]["balance"], "0");
+    assert!(merge["source"]["closed_at"].is_string(), "{}", merge);
+    assert_eq!(merge["source"]["status"], "CLOSED");
+    assert_eq!(merge["target"]["balance"], "140");
+    assert!(merge["target"].get("closed_at").is_none());
+
+    // Both balance histories end with the merge, which no transaction stands for
+    for (account_id, balance) in [(source, 0), (target, 140)] {
+        let history = account_service
+            .get_balance_history(account_id, None, None)
+            .await
+            .unwrap();
+        let last = history.last().unwrap();
+        assert_eq!(last.balance, Decimal::from(balance));
+        assert_eq!(last.transaction_id, None);
+    }
+
+    // The target now carries the source's history; the transfer between the two stays put
+    let moved: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM transactions WHERE sender_account_id = $1 OR receiver_account_id = $1",
+    )
+    .bind(source)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(moved, 1);
+    let history = transaction_service
+        .get_transaction_by_id(between.id)
+        .await
+        .unwrap();
+    assert_eq!(history.receiver_account_id, Some(source));
+    let (_, _, body) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/transactions/account/{}?limit=100", target),
+        Some(&owner_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(
+        body["data"]["items"].as_array().unwrap().len(),
+        5,
+        "{}",
+        body
+    );
+
+    // A closed account can't move money or be merged again
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/deposit",
+        Some(&owner_token),
+        &[],
+        Some(json!({ "account_id": source, "amount": "10" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(body["error"], "ACCOUNT_CLOSED");
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        &merge_uri,
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(
+        account_service
+            .get_account_by_id(target)
+            .await
+            .unwrap()
+            .balance,
+        Decimal::from(140)
+    );
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_frozen_account_cannot_move_money() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let owner = register_user(&user_service, "freezeowner").await;
+    let payee = register_user(&user_service, "freezepayee").await;
+    register_user(&user_service, "freezeadmin").await;
+    promote_to_admin(&pool, "freezeadmin").await;
+    let sender = account_service
+        .get_accounts_by_user_id(owner.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let receiver = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let deposit = |account_id| DepositRequest {
+        account_id,
+        amount: Decimal::from(100),
+        currency: None,
+        exchange_rate: None,
+        description: None,
+        category: None,
+        external_reference: None,
+    };
+    for account_id in [sender, receiver] {
+        transaction_service
+            .process_deposit(deposit(account_id))
+            .await
+            .unwrap();
+    }
+
+    // Only administrators can freeze an account
+    let owner_token = login_token(&app, "freezeowner").await;
+    let admin_token = login_token(&app, "freezeadmin").await;
+    let freeze_uri = format!("/api/v1/accounts/{}/freeze", sender);
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        &freeze_uri,
+        Some(&owner_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        &freeze_uri,
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "FROZEN");
+
+    // A frozen sender can't transfer, and nothing can be paid into the account either
+    let transfer = json!({
+        "sender_account_id": sender,
+        "receiver_account_id": receiver,
+        "amount": "10"
+    });
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&owner_token),
+        &[],
+        Some(transfer.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(body["message"], "Account is frozen");
+    let result = transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: receiver,
+                receiver_account_id: sender,
+                amount: Decimal::from(10),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(_))),
+        "{:?}",
+        result
+    );
+    let result = transaction_service.process_deposit(deposit(sender)).await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(_))),
+        "{:?}",
+        result
+    );
+    for account_id in [sender, receiver] {
+        assert_eq!(
+            account_service
+                .get_account_by_id(account_id)
+                .await
+                .unwrap()
+                .balance,
+            Decimal::from(100)
+        );
+    }
+
+    // Unfreezing lets the money move again
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        &format!("/api/v1/accounts/{}/unfreeze", sender),
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "ACTIVE");
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&owner_token),
+        &[],
+        Some(transfer),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_account_balance_scale() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&create_user_service(pool.clone()), "satoshi").await;
+    let token = login_token(&app, "satoshi").await;
+    let fiat = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    assert_eq!(
+        account_service
+            .get_account_by_id(fiat)
+            .await
+            .unwrap()
+            .balance_scale,
+        2
+    );
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/accounts",
+        Some(&token),
+        &[],
+        Some(json!({ "currency": "USD", "balance_scale": 8 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["balance_scale"], 8);
+    let fine: Uuid = body["data"]["id"].as_str().unwrap().parse().unwrap();
+    let other_fine = account_service
+        .create_account_with_scale(user.id, "USD".to_string(), Some(8))
+        .await
+        .unwrap()
+        .id;
+
+    // The scale is bounded
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/accounts",
+        Some(&token),
+        &[],
+        Some(json!({ "currency": "USD", "balance_scale": 9 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let satoshi = Decimal::from_str_exact("0.00000001").unwrap();
+    let deposit = |account_id: Uuid| {
+        let transaction_service = &transaction_service;
+        async move {
+            transaction_service
+                .process_deposit(DepositRequest {
+                    account_id,
+                    amount: satoshi,
+                    currency: None,
+                    exchange_rate: None,
+                    description: None,
+                    category: None,
+                    external_reference: None,
+                })
+                .await
+        }
+    };
+
+    // An 8-decimal account takes the smallest unit, a 2-decimal one doesn't
+    let booked = deposit(fine).await.unwrap();
+    assert_eq!(booked.amount, satoshi);
+    assert_eq!(
+        serde_json::to_value(&booked).unwrap()["amount"],
+        "0.00000001"
+    );
+    assert!(matches!(deposit(fiat).await, Err(AppError::Validation(_))));
+    assert_eq!(
+        account_service
+            .get_account_by_id(fine)
+            .await
+            .unwrap()
+            .balance,
+        satoshi
+    );
+
+    // A transfer must fit both accounts' scales
+    let transfer = |receiver_account_id: Uuid| {
+        let transaction_service = &transaction_service;
+        async move {
+            transaction_service
+                .process_transfer(
+                    TransferRequest {
+                        sender_account_id: fine,
+                        receiver_account_id,
+                        amount: satoshi,
+                        description: None,
+                        category: None,
+                        allow_duplicate: false,
+                    },
+                    &RequestContext::default(),
+                )
+                .await
+        }
+    };
+    assert!(matches!(transfer(fiat).await, Err(AppError::Validation(_))));
+    transfer(other_fine).await.unwrap();
+    assert_eq!(
+        account_service
+            .get_account_by_id(other_fine)
+            .await
+            .unwrap()
+            .balance,
+        satoshi
+    );
+    assert_eq!(
+        account_service
+            .get_account_by_id(fine)
+            .await
+            .unwrap()
+            .balance,
+        Decimal::ZERO
+    );
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_account_currency_is_iso4217() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let account_service = create_account_service(pool.clone());
+
+    let user = register_user(&create_user_service(pool.clone()), "currencyowner").await;
+    let token = login_token(&app, "currencyowner").await;
+    let create = |currency: &str| {
+        send_request(
+            &app,
+            Method::POST,
+            "/api/v1/accounts",
+            Some(&token),
+            &[],
+            Some(json!({ "currency": currency })),
+        )
+    };
+
+    for currency in ["XXX", "123", "usdd"] {
+        let (status, _, body) = create(currency).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    }
+
+    // Lowercase codes are stored in uppercase, so both name the same currency
+    let (status, _, body) = create("eur").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["currency"], "EUR");
+    let account = account_service
+        .create_account(user.id, "Eur".to_string())
+        .await
+        .unwrap();
+    assert_eq!(account.currency, "EUR");
+    let result = account_service
+        .create_account(user.id, "XXX".to_string())
+        .await;
+    assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+    // A transaction can't name a currency that doesn't exist either
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/deposit",
+        Some(&token),
+        &[],
+        Some(json!({ "account_id": account.id, "amount": "10", "currency": "ABC" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_close_account() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "closeowner").await;
+    register_user(&user_service, "closeother").await;
+    let funded = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let empty = account_service
+        .create_account(user.id, "EUR".to_string())
+        .await
+        .unwrap()
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: funded,
+            amount: Decimal::from(25),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    let owner_token = login_token(&app, "closeowner").await;
+    let close = |account_id: Uuid, token: String| {
+        let app = app.clone();
+        async move {
+            send_request(
+                &app,
+                Method::DELETE,
+                &format!("/api/v1/accounts/{}", account_id),
+                Some(&token),
+                &[],
+                None,
+            )
+            .await
+        }
+    };
+
+    // Only the owner may close an account
+    let other_token = login_token(&app, "closeother").await;
+    let (status, _, _) = close(empty, other_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // A funded account is refused and stays open
+    let (status, _, body) = close(funded, owner_token.clone()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(
+        account_service
+            .get_account_by_id(funded)
+            .await
+            .unwrap()
+            .status,
+        "ACTIVE"
+    );
+
+    // An empty account is closed, and closing it again changes nothing
+    let (status, _, body) = close(empty, owner_token.clone()).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "CLOSED");
+    let closed_at = body["data"]["closed_at"].clone();
+    assert!(closed_at.is_string(), "{}", body);
+    let (status, _, body) = close(empty, owner_token.clone()).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "CLOSED");
+    assert_eq!(body["data"]["closed_at"], closed_at);
+
+    // Closed accounts are only listed when asked for
+    let open: Vec<Uuid> = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()
+        .iter()
+        .map(|account| account.id)
+        .collect();
+    assert_eq!(open, vec![funded]);
+    let list = |query: &'static str| {
+        let app = app.clone();
+        let token = owner_token.clone();
+        async move {
+            let (status, _, body) = send_request(
+                &app,
+                Method::GET,
+                &format!("/api/v1/accounts{}", query),
+                Some(&token),
+                &[],
+                None,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            body["data"]["total_count"].as_i64().unwrap()
+        }
+    };
+    assert_eq!(list("").await, 1);
+    assert_eq!(list("?include_closed=true").await, 2);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_close_account_final_statement() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let context = RequestContext::default();
+
+    let user = register_user(&user_service, "finalowner").await;
+    let payee = register_user(&user_service, "finalpayee").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account,
+                receiver_account_id: payee_account,
+                amount: Decimal::from(40),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            },
+            &context,
+        )
+        .await
+        .unwrap();
+    transaction_service
+        .process_withdrawal(
+            WithdrawalRequest {
+                account_id: account,
+                amount: Decimal::from(60),
+                description: None,
+                category: None,
+                destination_id: None,
+                external_reference: None,
+            },
+            &context,
+        )
+        .await
+        .unwrap();
+
+    let token = login_token(&app, "finalowner").await;
+    let final_statement = |account_id: Uuid| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            send_request(
+                &app,
+                Method::GET,
+                &format!("/api/v1/accounts/{}/final-statement", account_id),
+                Some(&token),
+                &[],
+                None,
+            )
+            .await
+        }
+    };
+
+    // Open accounts have no final statement yet
+    let (status, _, _) = final_statement(account).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::DELETE,
+        &format!("/api/v1/accounts/{}", account),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let closed_at = body["data"]["closed_at"].clone();
+
+    // The statement covers the whole lifetime and its totals match the ledger
+    let (status, _, body) = final_statement(account).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let statement = &body["data"];
+    let created_at = account_service
+        .get_account_by_id(account)
+        .await
+        .unwrap()
+        .created_at;
+    let period_start = statement["period_start"].as_str().unwrap();
+    assert_eq!(
+        chrono::DateTime::parse_from_rfc3339(period_start).unwrap(),
+        created_at
+    );
+    assert_eq!(statement["period_end"], closed_at);
+    let (credits, debits, count): (String, String, i64) = sqlx::query_as(
+        r#"
+        SELECT COALESCE(SUM(amount) FILTER (WHERE receiver_account_id = $1), 0)::TEXT,
+               COALESCE(SUM(amount) FILTER (WHERE sender_account_id = $1), 0)::TEXT,
+               COUNT(*)
+        FROM transactions
+        WHERE (sender_account_id = $1 OR receiver_account_id = $1) AND status = 'COMPLETED'
+        "#,
+    )
+    .bind(account)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let decimal = |value: &serde_json::Value| Decimal::from_str(value.as_str().unwrap()).unwrap();
+    let (credits, debits) = (credits.parse::<Decimal>().unwrap(), debits.parse().unwrap());
+    assert_eq!(decimal(&statement["total_credits"]), credits);
+    assert_eq!(decimal(&statement["total_debits"]), debits);
+    assert_eq!(credits, Decimal::from(100));
+    assert_eq!(decimal(&statement["opening_balance"]), Decimal::ZERO);
+    assert_eq!(decimal(&statement["closing_balance"]), Decimal::ZERO);
+    let transactions = statement["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len() as i64, count);
+    assert_eq!(decimal(&transactions[0]["balance_after"]), credits);
+    assert_eq!(decimal(&transactions[2]["balance_after"]), Decimal::ZERO);
+
+    // The statement is immutable and closing again doesn't replace it
+    let update = sqlx::query("UPDATE final_statements SET statement = '{}' WHERE account_id = $1")
+        .bind(account)
+        .execute(&pool)
+        .await;
+    assert!(update.is_err());
+    send_request(
+        &app,
+        Method::DELETE,
+        &format!("/api/v1/accounts/{}", account),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    let (_, _, again) = final_statement(account).await;
+    assert_eq!(again["data"], *statement);
+
+    // Only the owner can read it
+    let payee_token = login_token(&app, "finalpayee").await;
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/accounts/{}/final-statement", account),
+        Some(&payee_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // The owner is emailed the statement in the background
+    let mut kind = None;
+    for _ in 0..50 {
+        kind = sqlx::query_scalar::<_, String>(
+            "SELECT kind FROM email_deliveries WHERE user_id = $1 AND kind = 'FINAL_STATEMENT'",
+        )
+        .bind(user.id)
+        .fetch_optional(&pool)
+        .await
+        .unwrap();
+        if kind.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(kind.as_deref(), Some("FINAL_STATEMENT"));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_negative_balance_is_reported_as_insufficient_funds() {
+    let (pool, db_url) = setup().await;
+    let user = register_user(&create_user_service(pool.clone()), "checkowner").await;
+    let account_id = create_account_service(pool.clone())
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    // A debit that slipped past the balance check trips the CHECK constraint
+    let err = sqlx::query("UPDATE accounts SET balance = balance - 1 WHERE id = $1")
+        .bind(account_id)
+        .execute(&pool)
+        .await
+        .map_err(AppError::from)
+        .unwrap_err();
+    match err {
+        AppError::BadRequest(message) => assert_eq!(message, "Insufficient funds"),
+        other => panic!("Expected BadRequest, got {:?}", other),
+    }
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_monthly_statement() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let owner = register_user(&user_service, "statementowner").await;
+    let payee = register_user(&user_service, "statementpayee").await;
+    register_user(&user_service, "statementstranger").await;
+    let account = account_service
+        .get_accounts_by_user_id(owner.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let context = RequestContext::default();
+
+    // Booked now and moved into the past, so the balance matches the ledger
+    let deposit = |amount: i64| DepositRequest {
+        account_id: account,
+        amount: Decimal::from(amount),
+        currency: None,
+        exchange_rate: None,
+        description: None,
+        category: None,
+        external_reference: None,
+    };
+    let opening_deposit = transaction_service
+        .process_deposit(deposit(100))
+        .await
+        .unwrap();
+    let boundary_deposit = transaction_service
+        .process_deposit(deposit(50))
+        .await
+        .unwrap();
+    let transfer = transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account,
+                receiver_account_id: payee_account,
+                amount: Decimal::from(30),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            },
+            &context,
+        )
+        .await
+        .unwrap();
+    let withdrawal = transaction_service
+        .process_withdrawal(
+            WithdrawalRequest {
+                account_id: account,
+                amount: Decimal::from(20),
+                description: None,
+                category: None,
+                destination_id: None,
+                external_reference: None,
+            },
+            &context,
+        )
+        .await
+        .unwrap();
+    sqlx::query("UPDATE accounts SET created_at = '2024-05-20 09:00:00+00' WHERE id = $1")
+        .bind(account)
+        .execute(&pool)
+        .await
+        .unwrap();
+    for (id, created_at) in [
+        (opening_deposit.id, "2024-05-20 10:00:00+00"),
+        // Midnight on the first belongs to the month it starts
+        (boundary_deposit.id, "2024-06-01 00:00:00+00"),
+        (transfer.id, "2024-06-15 12:00:00+00"),
+        (withdrawal.id, "2024-07-01 00:00:00+00"),
+    ] {
+        sqlx::query("UPDATE transactions SET created_at = $2::timestamptz WHERE id = $1")
+            .bind(id)
+            .bind(created_at)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    let token = login_token(&app, "statementowner").await;
+    let statement = |query: &'static str| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            send_request(
+                &app,
+                Method::GET,
+                &format!("/api/v1/accounts/{}/statement?{}", account, query),
+                Some(&token),
+                &[],
+                None,
+            )
+            .await
+        }
+    };
+    let decimal = |value: &serde_json::Value| Decimal::from_str(value.as_str().unwrap()).unwrap();
+    let balances = |data: &serde_json::Value| {
+        [
+            "opening_balance",
+            "total_credits",
+            "total_debits",
+            "closing_balance",
+        ]
+        .map(|field| decimal(&data[field]))
+    };
+    let listed = |data: &serde_json::Value| {
+        data["transactions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| {
+                (
+                    t["id"].as_str().unwrap().to_string(),
+                    decimal(&t["balance_after"]),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let (status, _, body) = statement("year=2024&month=06").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let june = &body["data"];
+    assert_eq!(june["period_start"], "2024-06-01T00:00:00Z");
+    assert_eq!(june["period_end"], "2024-07-01T00:00:00Z");
+    assert_eq!(june["currency"], "USD");
+    assert_eq!(balances(june), [100, 50, 30, 120].map(Decimal::from));
+    assert_eq!(
+        listed(june),
+        [
+            (boundary_deposit.id.to_string(), Decimal::from(150)),
+            (transfer.id.to_string(), Decimal::from(120)),
+        ]
+    );
+
+    // The account was opened in May, so it starts from nothing
+    let (_, _, body) = statement("year=2024&month=5").await;
+    assert_eq!(balances(&body["data"]), [0, 100, 0, 100].map(Decimal::from));
+
+    let (_, _, body) = statement("year=2024&month=7").await;
+    assert_eq!(
+        balances(&body["data"]),
+        [120, 0, 20, 100].map(Decimal::from)
+    );
+    assert_eq!(
+        listed(&body["data"]),
+        [(withdrawal.id.to_string(), Decimal::from(100))]
+    );
+
+    // A month without activity opens and closes at the same balance
+    let (_, _, body) = statement("year=2024&month=8").await;
+    assert_eq!(balances(&body["data"]), [100, 0, 0, 100].map(Decimal::from));
+    assert_eq!(body["data"]["transactions"], json!([]));
+
+    for query in [
+        "year=2024&month=4",
+        "year=2024&month=13",
+        "year=2999&month=1",
+    ] {
+        let (status, _, body) = statement(query).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", query, body);
+    }
+
+    let stranger_token = login_token(&app, "statementstranger").await;
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/accounts/{}/statement?year=2024&month=6", account),
+        Some(&stranger_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_statement_for_date_range() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let owner = register_user(&user_service, "rangeowner").await;
+    let payee = register_user(&user_service, "rangepayee").await;
+    let account = account_service
+        .get_accounts_by_user_id(owner.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let context = RequestContext::default();
+    let amount = |value: &str| Decimal::from_str(value).unwrap();
+
+    // Booked in order and moved into the past
+    let deposit = |value: &str| DepositRequest {
+        account_id: account,
+        amount: amount(value),
+        currency: None,
+        exchange_rate: None,
+        description: None,
+        category: None,
+        external_reference: None,
+    };
+    let withdrawal = |value: &str| WithdrawalRequest {
+        account_id: account,
+        amount: amount(value),
+        description: None,
+        category: None,
+        destination_id: None,
+        external_reference: None,
+    };
+    let mut booked = Vec::new();
+    booked.push(
+        transaction_service
+            .process_deposit(deposit("200"))
+            .await
+            .unwrap(),
+    );
+    booked.push(
+        transaction_service
+            .process_withdrawal(withdrawal("50"), &context)
+            .await
+            .unwrap(),
+    );
+    booked.push(
+        transaction_service
+            .process_deposit(deposit("80.25"))
+            .await
+            .unwrap(),
+    );
+    booked.push(
+        transaction_service
+            .process_withdrawal(withdrawal("30"), &context)
+            .await
+            .unwrap(),
+    );
+    booked.push(
+        transaction_service
+            .process_transfer(
+                TransferRequest {
+                    sender_account_id: account,
+                    receiver_account_id: payee_account,
+                    amount: amount("45.50"),
+                    description: None,
+                    category: None,
+                    allow_duplicate: false,
+                },
+                &context,
+            )
+            .await
+            .unwrap(),
+    );
+    booked.push(
+        transaction_service
+            .process_deposit(deposit("10"))
+            .await
+            .unwrap(),
+    );
+    for (transaction, created_at) in booked.iter().zip([
+        "2024-03-01 09:00:00+00",
+        "2024-03-10 09:00:00+00",
+        // The start of the period is included
+        "2024-04-01 00:00:00+00",
+        "2024-04-05 09:00:00+00",
+        "2024-04-20 09:00:00+00",
+        // The end of the period is not
+        "2024-05-01 00:00:00+00",
+    ]) {
+        sqlx::query("UPDATE transactions SET created_at = $2::timestamptz WHERE id = $1")
+            .bind(transaction.id)
+            .bind(created_at)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    let token = login_token(&app, "rangeowner").await;
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        &format!(
+            "/api/v1/accounts/{}/statement?from=2024-04-01T00:00:00Z&to=2024-05-01T00:00:00Z",
+            account
+        ),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let data = &body["data"];
+    assert_eq!(data["period_start"], "2024-04-01T00:00:00Z");
+    assert_eq!(data["period_end"], "2024-05-01T00:00:00Z");
+    assert!(data.get("year").is_none(), "{}", data);
+    let decimal = |value: &serde_json::Value| Decimal::from_str(value.as_str().unwrap()).unwrap();
+    assert_eq!(decimal(&data["opening_balance"]), amount("150"));
+    assert_eq!(decimal(&data["total_credits"]), amount("80.25"));
+    assert_eq!(decimal(&data["total_debits"]), amount("75.50"));
+    assert_eq!(decimal(&data["closing_balance"]), amount("154.75"));
+    let listed: Vec<_> = data["transactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| {
+            (
+                t["id"].as_str().unwrap().to_string(),
+                decimal(&t["balance_after"]),
+            )
+        })
+        .collect();
+    assert_eq!(
+        listed,
+        [
+            (booked[2].id.to_string(), amount("230.25")),
+            (booked[3].id.to_string(), amount("200.25")),
+            (booked[4].id.to_string(), amount("154.75")),
+        ]
+    );
+
+    // Opening balance plus the signed amounts listed is the closing balance
+    let signed: Decimal = data["transactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| {
+            if t["receiver_account_id"] == json!(account) {
+                decimal(&t["amount"])
+            } else {
+                -decimal(&t["amount"])
+            }
+        })
+        .sum();
+    assert_eq!(
+        decimal(&data["opening_balance"]) + signed,
+        decimal(&data["closing_balance"])
+    );
+
+    // Without balance snapshots, the earlier transactions are replayed
+    let from = "2024-04-01T00:00:00Z".parse().unwrap();
+    let to = "2024-05-01T00:00:00Z".parse().unwrap();
+    let from_snapshots = transaction_service
+        .generate_statement(account, from, to)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM account_balance_snapshots WHERE account_id = $1")
+        .bind(account)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let replayed = transaction_service
+        .generate_statement(account, from, to)
+        .await
+        .unwrap();
+    assert_eq!(replayed.opening_balance, from_snapshots.opening_balance);
+    assert_eq!(replayed.closing_balance, from_snapshots.closing_balance);
+
+    // Nothing before the period opens it at zero
+    let statement = transaction_service
+        .generate_statement(
+            account,
+            "2024-01-01T00:00:00Z".parse().unwrap(),
+            "2024-03-05T00:00:00Z".parse().unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(statement.opening_balance, Decimal::ZERO);
+    assert_eq!(statement.total_credits, amount("200"));
+    assert_eq!(statement.closing_balance, amount("200"));
+
+    assert!(matches!(
+        transaction_service
+            .generate_statement(account, to, from)
+            .await,
+        Err(AppError::BadRequest(_))
+    ));
+    for query in [
+        "from=2024-04-01T00:00:00Z",
+        "year=2024&month=4&from=2024-04-01T00:00:00Z&to=2024-05-01T00:00:00Z",
+        "",
+    ] {
+        let (status, _, body) = send_request(
+            &app,
+            Method::GET,
+            &format!("/api/v1/accounts/{}/statement?{}", account, query),
+            Some(&token),
+            &[],
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", query, body);
+    }
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_balance_history() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let owner = register_user(&user_service, "historyowner").await;
+    register_user(&user_service, "historystranger").await;
+    let account = account_service
+        .get_accounts_by_user_id(owner.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    let deposit = transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let withdrawal = transaction_service
+        .process_withdrawal(
+            WithdrawalRequest {
+                account_id: account,
+                amount: Decimal::from_str("30.50").unwrap(),
+                description: None,
+                category: None,
+                destination_id: None,
+                external_reference: None,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+
+    let history = account_service
+        .get_balance_history(account, None, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|snapshot| (snapshot.transaction_id, snapshot.balance))
+            .collect::<Vec<_>>(),
+        [
+            (Some(deposit.id), Decimal::from(100)),
+            (Some(withdrawal.id), Decimal::from_str("69.50").unwrap()),
+        ]
+    );
+    assert!(history[0].created_at <= history[1].created_at);
+
+    // Both bounds are inclusive
+    let only_withdrawal = account_service
+        .get_balance_history(account, Some(history[1].created_at), Some(Utc::now()))
+        .await
+        .unwrap();
+    assert_eq!(only_withdrawal.len(), 1);
+    assert_eq!(only_withdrawal[0].transaction_id, Some(withdrawal.id));
+
+    let owner_token = login_token(&app, "historyowner").await;
+    let stranger_token = login_token(&app, "historystranger").await;
+    let uri = format!("/api/v1/accounts/{}/balance-history", account);
+
+    let (status, _, body) =
+        send_request(&app, Method::GET, &uri, Some(&owner_token), &[], None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    assert_eq!(body["data"][1]["transaction_id"], withdrawal.id.to_string());
+
+    let (status, _, _) =
+        send_request(&app, Method::GET, &uri, Some(&stranger_token), &[], None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        &format!("{}?from=2024-06-02T00:00:00Z&to=2024-06-01T00:00:00Z", uri),
+        Some(&owner_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,356 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, register_user, send_request, setup, teardown,
+};
+use axum::http::{Method, StatusCode};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::str::FromStr;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::{BudgetPeriod, DepositRequest, TransferRequest};
+
+async fn alert_count(pool: &PgPool) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM budget_alerts")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+fn amount(value: &Value) -> Decimal {
+    Decimal::from_str(value.as_str().unwrap()).unwrap()
+}
+
+/// Finds the status of the budget for a category in a budget status response
+fn status_for<'a>(body: &'a Value, category: &str) -> &'a Value {
+    body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|status| status["category"] == category)
+        .unwrap()
+}
+
+#[test]
+fn test_budget_period_bounds() {
+    let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+    // 2024-01-03 is a Wednesday
+    assert_eq!(
+        BudgetPeriod::WEEKLY.bounds(day(2024, 1, 3)),
+        (day(2024, 1, 1), day(2024, 1, 7))
+    );
+    assert_eq!(
+        BudgetPeriod::WEEKLY.bounds(day(2024, 1, 1)),
+        (day(2024, 1, 1), day(2024, 1, 7))
+    );
+    assert_eq!(
+        BudgetPeriod::MONTHLY.bounds(day(2024, 2, 15)),
+        (day(2024, 2, 1), day(2024, 2, 29))
+    );
+    assert_eq!(
+        BudgetPeriod::MONTHLY.bounds(day(2023, 12, 31)),
+        (day(2023, 12, 1), day(2023, 12, 31))
+    );
+}
+
+#[tokio::test]
+async fn test_budget_alerts_only_when_exceeded() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "budgetuser").await;
+    let payee = register_user(&user_service, "budgetpayee").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account,
+            amount: Decimal::from(1000),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    let token = login_token(&app, "budgetuser").await;
+    let budgets_uri = format!("/api/v1/accounts/{}/budgets", account);
+    let status_uri = format!("{}/status", budgets_uri);
+    for (category, limit) in [("Groceries", "100"), ("travel", "500")] {
+        let (status, _, body) = send_request(
+            &app,
+            Method::POST,
+            &budgets_uri,
+            Some(&token),
+            &[],
+            Some(json!({ "category": category, "period": "MONTHLY", "limit": limit })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+
+    // Staying under the limit raises nothing
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/withdrawal",
+        Some(&token),
+        &[],
+        Some(json!({ "account_id": account, "amount": "60", "category": "groceries" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(alert_count(&pool).await, 0);
+
+    let (_, _, body) = send_request(&app, Method::GET, &status_uri, Some(&token), &[], None).await;
+    let groceries = status_for(&body, "groceries");
+    assert_eq!(amount(&groceries["spent"]), Decimal::from(60));
+    assert_eq!(groceries["exceeded"], false);
+
+    // Uncategorized and other categories' spending doesn't count
+    transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account,
+                receiver_account_id: payee_account,
+                amount: Decimal::from(300),
+                description: None,
+                category: Some("Travel".to_string()),
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    let (_, _, body) = send_request(&app, Method::GET, &status_uri, Some(&token), &[], None).await;
+    assert_eq!(
+        amount(&status_for(&body, "groceries")["spent"]),
+        Decimal::from(60)
+    );
+    assert_eq!(
+        amount(&status_for(&body, "travel")["spent"]),
+        Decimal::from(300)
+    );
+    assert_eq!(alert_count(&pool).await, 0);
+
+    // Going over raises one alert, for the transaction that crossed the limit
+    let crossing = transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account,
+                receiver_account_id: payee_account,
+                amount: Decimal::from(50),
+                description: None,
+                category: Some(" Groceries ".to_string()),
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(alert_count(&pool).await, 1);
+    let alerted: uuid::Uuid = sqlx::query_scalar("SELECT transaction_id FROM budget_alerts")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(alerted, crossing.id);
+
+    let (_, _, body) = send_request(&app, Method::GET, &status_uri, Some(&token), &[], None).await;
+    let groceries = status_for(&body, "groceries");
+    assert_eq!(amount(&groceries["spent"]), Decimal::from(110));
+    assert_eq!(amount(&groceries["remaining"]), Decimal::from(-10));
+    assert_eq!(groceries["exceeded"], true);
+
+    // Further spending in the same period doesn't alert again
+    transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account,
+                receiver_account_id: payee_account,
+                amount: Decimal::from(5),
+                description: None,
+                category: Some("groceries".to_string()),
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(alert_count(&pool).await, 1);
+
+    // Only the account owner sees its budgets
+    let payee_token = login_token(&app, "budgetpayee").await;
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        &status_uri,
+        Some(&payee_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_spending_by_category() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "spendinguser").await;
+    let payee = register_user(&user_service, "spendingpayee").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let token = login_token(&app, "spendinguser").await;
+    let post = |uri: &'static str, body: Value| {
+        let app = app.clone();
+        let token = token.clone();
+        async move { send_request(&app, Method::POST, uri, Some(&token), &[], Some(body)).await }
+    };
+
+    // Deposits carry a category too, but aren't spending
+    let (status, _, body) = post(
+        "/api/v1/transactions/deposit",
+        json!({ "account_id": account, "amount": "1000", "category": "Salary" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["category"], "salary");
+
+    for (amount, category) in [("40", json!("groceries")), ("25.50", json!(" Groceries "))] {
+        let (status, _, body) = post(
+            "/api/v1/transactions/withdrawal",
+            json!({ "account_id": account, "amount": amount, "category": category }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["data"]["category"], "groceries");
+    }
+    let (status, _, body) = post(
+        "/api/v1/transactions/withdrawal",
+        json!({ "account_id": account, "amount": "10" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["data"].get("category").is_none());
+    let rent = transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account,
+                receiver_account_id: payee_account,
+                amount: Decimal::from(300),
+                description: None,
+                category: Some("rent".to_string()),
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rent.category.as_deref(), Some("rent"));
+
+    // Categories are limited to 64 characters
+    let (status, _, body) = post(
+        "/api/v1/transactions/withdrawal",
+        json!({ "account_id": account, "amount": "1", "category": "x".repeat(65) }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    let (status, _, body) = post(
+        "/api/v1/transactions/withdrawal",
+        json!({ "account_id": account, "amount": "1", "category": "x".repeat(64) }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/accounts/{}/spending", account),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["currency"], "USD");
+    let categories = body["data"]["categories"].as_array().unwrap();
+    let summary: Vec<_> = categories
+        .iter()
+        .map(|c| {
+            (
+                c["category"].clone(),
+                c["count"].clone(),
+                c["total"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (json!("rent"), json!(1), json!("300.00")),
+            (json!("groceries"), json!(2), json!("65.50")),
+            (Value::Null, json!(1), json!("10.00")),
+            (json!("x".repeat(64)), json!(1), json!("1.00")),
+        ]
+    );
+
+    // Nothing was spent before the transactions, nor by the payee
+    let spending = transaction_service
+        .get_spending_by_category(
+            account,
+            None,
+            Some(rent.created_at - chrono::Duration::days(1)),
+        )
+        .await
+        .unwrap();
+    assert!(spending.is_empty());
+    let spending = transaction_service
+        .get_spending_by_category(payee_account, None, None)
+        .await
+        .unwrap();
+    assert!(spending.is_empty());
+
+    // Only the owner sees an account's spending
+    let payee_token = login_token(&app, "spendingpayee").await;
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/accounts/{}/spending", account),
+        Some(&payee_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    teardown(&db_url).await;
+}
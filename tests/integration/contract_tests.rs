@@ -0,0 +1,422 @@
+//! Golden-file contract tests for the JSON wire format
+//!
+//! Each test serializes a response and compares it with `tests/golden/<name>.json`.
+//! After an intentional change to a response shape, regenerate the files with
+//! `UPDATE_GOLDEN=1 cargo test contract_tests` and review the diff before committing.
+
+use crate::integration::setup::{create_app, login_token, send_request, setup, teardown};
+use axum::body::to_bytes;
+use axum::http::{Method, StatusCode};
+use axum::response::IntoResponse;
+use axum::Router;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::path::PathBuf;
+use std::str::FromStr;
+use txn_manager::models::diagnostics::TransactionTimings;
+use txn_manager::models::user::LoginEvent;
+use txn_manager::utils::error::AppError;
+use txn_manager::utils::response::{select_fields, ApiResponse};
+use txn_manager::{
+    AccountResponse, AdminTransactionResponse, LoginResponse, SqlxDecimal, Transaction,
+    TransactionResponse, UserResponse,
+};
+use uuid::Uuid;
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.json", name))
+}
+
+/// Renders the lines of `expected` and `actual` as a minimal line diff
+///
+/// Unchanged lines are prefixed with two spaces, removed lines with `-` and
+/// added lines with `+`.
+fn line_diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    // Longest common subsequence table, filled from the end
+    let mut lcs = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = Vec::new();
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            diff.push(format!("  {}", expected[i]));
+            i += 1;
+            j += 1;
+        } else if i < expected.len() && (j == actual.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push(format!("- {}", expected[i]));
+            i += 1;
+        } else {
+            diff.push(format!("+ {}", actual[j]));
+            j += 1;
+        }
+    }
+
+    diff.join("\n")
+}
+
+/// Compares a value with its golden file, or rewrites the file when `UPDATE_GOLDEN` is set
+fn assert_golden(name: &str, value: &impl Serialize) {
+    // Going through `Value` sorts object keys, so field order never causes churn
+    let value = serde_json::to_value(value).unwrap();
+    let actual = format!("{}\n", serde_json::to_string_pretty(&value).unwrap());
+    let path = golden_path(name);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "Golden file {} is missing; run with UPDATE_GOLDEN=1 to create it",
+            path.display()
+        )
+    });
+
+    if expected != actual {
+        panic!(
+            "Response shape for '{}' differs from {} (- golden, + actual):\n{}\n\n\
+             If the change is intended, rerun with UPDATE_GOLDEN=1 and commit the updated file.",
+            name,
+            path.display(),
+            line_diff(&expected, &actual)
+        );
+    }
+}
+
+/// Replaces values that change on every run (ids, tokens, account numbers,
+/// timestamps) with placeholders
+fn normalize_volatile(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                match field {
+                    Value::String(_) if key == "token" => *field = json!("<token>"),
+                    Value::String(_) if key == "account_number" => {
+                        *field = json!("<account_number>")
+                    }
+                    Value::String(_) if key.ends_with("_at") => *field = json!("<timestamp>"),
+                    _ => normalize_volatile(field),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(normalize_volatile),
+        Value::String(text) if Uuid::parse_str(text).is_ok() => *value = json!("<uuid>"),
+        _ => {}
+    }
+}
+
+fn fixed_uuid(n: u128) -> Uuid {
+    Uuid::from_u128(n)
+}
+
+fn fixed_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap()
+}
+
+fn sample_user() -> UserResponse {
+    UserResponse {
+        id: fixed_uuid(1),
+        username: "jdoe".to_string(),
+        email: "jdoe@example.com".to_string(),
+        first_name: Some("Jane".to_string()),
+        last_name: None,
+        locale: "de-DE".to_string(),
+        email_verified: true,
+        totp_enabled: false,
+    }
+}
+
+fn sample_transaction() -> TransactionResponse {
+    // Built from the stored entity so the decimal formatting path is covered too
+    TransactionResponse::from(Transaction {
+        id: fixed_uuid(10),
+        sender_account_id: Some(fixed_uuid(2)),
+        receiver_account_id: Some(fixed_uuid(3)),
+        amount: SqlxDecimal(Decimal::from_str("10.1000").unwrap()),
+        currency: "USD".to_string(),
+        transaction_type: "TRANSFER".to_string(),
+        status: "COMPLETED".to_string(),
+        description: Some("Rent".to_string()),
+        created_at: fixed_time(),
+        updated_at: fixed_time(),
+    })
+}
+
+#[test]
+fn test_contract_user_responses() {
+    assert_golden(
+        "user_response",
+        &ApiResponse::success("User retrieved successfully", sample_user()),
+    );
+    assert_golden(
+        "login_response",
+        &ApiResponse::success(
+            "Login successful",
+            LoginResponse {
+                token: "header.payload.signature".to_string(),
+                refresh_token: "header.refresh-payload.signature".to_string(),
+                user: sample_user(),
+            },
+        ),
+    );
+    assert_golden(
+        "login_history_response",
+        &ApiResponse::success(
+            "Login history retrieved successfully",
+            vec![LoginEvent {
+                id: fixed_uuid(20),
+                ip_address: Some("198.51.100.23".to_string()),
+                user_agent: None,
+                success: false,
+                created_at: fixed_time(),
+            }],
+        ),
+    );
+}
+
+#[test]
+fn test_contract_account_and_transaction_responses() {
+    assert_golden(
+        "account_response",
+        &ApiResponse::success(
+            "Account retrieved successfully",
+            AccountResponse {
+                id: fixed_uuid(2),
+                user_id: fixed_uuid(1),
+                account_number: Some("4829103755".to_string()),
+                balance: Decimal::from_str("1234.5000").unwrap(),
+                currency: "USD".to_string(),
+                balance_scale: 2,
+                status: "ACTIVE".to_string(),
+                closed_at: None,
+                created_at: fixed_time(),
+                balance_as_of: None,
+            },
+        ),
+    );
+    assert_golden(
+        "transaction_response",
+        &ApiResponse::success("Transaction retrieved successfully", sample_transaction()),
+    );
+    assert_golden(
+        "admin_transaction_response",
+        &ApiResponse::success(
+            "Transaction retrieved successfully",
+            AdminTransactionResponse {
+                transaction: sample_transaction(),
+                ip_address: Some("198.51.100.23".to_string()),
+                user_agent: Some("Mozilla/5.0".to_string()),
+                timings: Some(TransactionTimings {
+                    request_id: Some("0b6d4c9e-1f0a-4d2b-8a3e-5c7f9e1d2a4b".to_string()),
+                    instance_id: "api-1".to_string(),
+                    received_at: fixed_time(),
+                    lock_acquired_at: fixed_time() + Duration::milliseconds(12),
+                    committed_at: fixed_time() + Duration::milliseconds(40),
+                }),
+            },
+        ),
+    );
+    assert_golden(
+        "sparse_transaction_list",
+        &ApiResponse::success(
+            "Transactions retrieved successfully",
+            select_fields(vec![sample_transaction()], Some("id,amount,status")).unwrap(),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn test_contract_error_responses() {
+    let errors = vec![
+        ("auth", AppError::Auth("Invalid token".to_string())),
+        ("forbidden", AppError::Forbidden("Not yours".to_string())),
+        (
+            "not_found",
+            AppError::NotFound("No such account".to_string()),
+        ),
+        (
+            "bad_request",
+            AppError::BadRequest("Insufficient funds".to_string()),
+        ),
+        ("conflict", AppError::Conflict("Already exists".to_string())),
+        ("validation", AppError::Validation("Bad email".to_string())),
+        (
+            "rejected",
+            AppError::Rejected {
+                code: "DESTINATION_UNVERIFIED",
+                message: "Destination has not been verified yet".to_string(),
+            },
+        ),
+        ("internal", AppError::Internal("secret detail".to_string())),
+        ("database", AppError::Database(sqlx::Error::RowNotFound)),
+    ];
+
+    let mut rendered = Map::new();
+    for (name, error) in errors {
+        let response = error.into_response();
+        let status = response.status().as_u16();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        rendered.insert(name.to_string(), json!({ "status": status, "body": body }));
+    }
+
+    assert_golden("error_responses", &rendered);
+}
+
+/// Drives a representative set of requests against one API version
+///
+/// Returns the normalized status and body of each response, keyed by step. For
+/// the paginated listing the pagination headers are recorded as well, as v2
+/// clients rely on them instead of body metadata.
+async fn handler_flow(app: &Router, version: &str) -> Map<String, Value> {
+    let username = match version {
+        "v1" => "contractuser".to_string(),
+        _ => format!("contractuser{}", version),
+    };
+    let api = format!("/api/{}", version);
+
+    let mut responses = Map::new();
+    let mut record = |name: &str, status: StatusCode, mut body: Value| {
+        normalize_volatile(&mut body);
+        responses.insert(
+            name.to_string(),
+            json!({ "status": status.as_u16(), "body": body }),
+        );
+    };
+
+    let (status, _, body) = send_request(
+        app,
+        Method::POST,
+        &format!("{}/users/register", api),
+        None,
+        &[],
+        Some(json!({
+            "username": username,
+            "email": format!("{}@example.com", username),
+            "password": "securepassword",
+            "first_name": "Contract"
+        })),
+    )
+    .await;
+    record("register", status, body);
+
+    let token = login_token(app, &username).await;
+
+    let (status, _, body) = send_request(
+        app,
+        Method::GET,
+        &format!("{}/users/me", api),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    record("current_user", status, body);
+
+    let (status, _, body) = send_request(
+        app,
+        Method::GET,
+        &format!("{}/accounts", api),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    let accounts = if version == "v1" {
+        &body["data"]["items"]
+    } else {
+        &body
+    };
+    let account_id = accounts[0]["id"].as_str().unwrap().to_string();
+    record("list_accounts", status, body);
+
+    let (status, _, body) = send_request(
+        app,
+        Method::POST,
+        &format!("{}/transactions/deposit", api),
+        Some(&token),
+        &[],
+        Some(json!({ "account_id": account_id, "amount": "25.5", "description": "Salary" })),
+    )
+    .await;
+    record("deposit", status, body);
+
+    let (status, headers, body) = send_request(
+        app,
+        Method::GET,
+        &format!("{}/transactions/account/{}?limit=1", api, account_id),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    if version == "v1" {
+        record("account_transactions", status, body);
+    } else {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .map(|value| value.to_str().unwrap().replace(&account_id, "<uuid>"))
+        };
+        let body = json!({
+            "items": body,
+            "link": header("link"),
+            "x_total_count": header("x-total-count"),
+        });
+        record("account_transactions", status, body);
+    }
+
+    let (status, _, body) = send_request(
+        app,
+        Method::POST,
+        &format!("{}/transactions/withdrawal", api),
+        Some(&token),
+        &[],
+        Some(json!({ "account_id": account_id, "amount": "1000" })),
+    )
+    .await;
+    record("insufficient_funds", status, body);
+
+    let (status, _, body) = send_request(
+        app,
+        Method::GET,
+        &format!("{}/accounts", api),
+        None,
+        &[],
+        None,
+    )
+    .await;
+    record("missing_token", status, body);
+
+    responses
+}
+
+#[tokio::test]
+async fn test_contract_handler_responses() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+
+    assert_golden("handler_responses", &handler_flow(&app, "v1").await);
+    assert_golden("handler_responses_v2", &handler_flow(&app, "v2").await);
+
+    teardown(&db_url).await;
+}
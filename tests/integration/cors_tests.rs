@@ -0,0 +1,104 @@
+use axum::body::Body;
+use axum::http::{header, HeaderMap, Method, Request, StatusCode};
+use axum::routing::get;
+use axum::Router;
+use tower::ServiceExt;
+use txn_manager::middleware::cors::AllowedOrigins;
+use txn_manager::{Config, ConfigError};
+
+fn config_with_origins(origins: &str) -> Result<Config, ConfigError> {
+    let vars = [
+        ("DATABASE_URL", "postgres://localhost/txn_manager"),
+        ("JWT_SECRET", "secret"),
+        ("CORS_ALLOWED_ORIGINS", origins),
+    ];
+    Config::from_sources(None, |name| {
+        vars.iter()
+            .find(|(var, _)| *var == name)
+            .map(|(_, value)| value.to_string())
+    })
+}
+
+/// Sends a CORS preflight for a `method` request from `origin` through a router
+/// guarded by `config`
+async fn preflight(config: &Config, origin: &str, method: &str) -> (StatusCode, HeaderMap) {
+    let app = Router::new()
+        .route("/", get(|| async { "OK" }))
+        .layer(config.cors_allowed_origins.cors_layer());
+    let request = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/")
+        .header(header::ORIGIN, origin)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, method)
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    (response.status(), response.headers().clone())
+}
+
+#[test]
+fn test_allowed_origins_parsing() {
+    assert!("".parse::<AllowedOrigins>().unwrap().is_any());
+    assert!(" * ".parse::<AllowedOrigins>().unwrap().is_any());
+    assert!(!"https://app.example.com/"
+        .parse::<AllowedOrigins>()
+        .unwrap()
+        .is_any());
+    for invalid in [
+        "app.example.com",
+        "ftp://files.example.com",
+        "https://app.example.com/login",
+    ] {
+        assert!(invalid.parse::<AllowedOrigins>().is_err(), "{}", invalid);
+    }
+
+    let error = config_with_origins("https://app.example.com,example.com").unwrap_err();
+    assert!(
+        error.to_string().contains("CORS_ALLOWED_ORIGINS"),
+        "{}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn test_cors_allows_only_configured_origins() {
+    let config = config_with_origins("https://app.example.com, http://localhost:3000").unwrap();
+
+    for origin in ["https://app.example.com", "http://localhost:3000"] {
+        let (status, headers) = preflight(&config, origin, "POST").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], origin);
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap();
+        assert!(
+            methods.contains("POST") && !methods.contains('*'),
+            "{}",
+            methods
+        );
+    }
+
+    // Other origins get no CORS headers, so browsers refuse the response
+    let (_, headers) = preflight(&config, "https://evil.example.com", "POST").await;
+    assert!(headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+    // Methods the API doesn't use aren't offered
+    let (_, headers) = preflight(&config, "https://app.example.com", "TRACE").await;
+    let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+        .to_str()
+        .unwrap();
+    assert!(!methods.contains("TRACE"), "{}", methods);
+}
+
+#[tokio::test]
+async fn test_cors_allows_any_origin_without_credentials_by_default() {
+    let config = config_with_origins("*").unwrap();
+
+    let (_, headers) = preflight(&config, "https://anywhere.example.com", "POST").await;
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    assert!(headers
+        .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+        .is_none());
+}
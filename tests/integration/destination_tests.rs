@@ -0,0 +1,304 @@
+use crate::integration::setup::{
+    create_account_service, create_transaction_service, create_user_service, login_token,
+    promote_to_admin, register_user, send_request, setup, teardown, test_config,
+};
+use axum::http::{Method, StatusCode};
+use axum::Router;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::time::Duration;
+use txn_manager::api::create_router;
+use txn_manager::models::destination::mask_identifier;
+use txn_manager::DepositRequest;
+use uuid::Uuid;
+
+const IBAN: &str = "DE89 3704 0044 0532 0130 00";
+
+/// Registers a destination over the API and returns its ID
+async fn register_destination(app: &Router, token: &str, identifier: &str) -> Uuid {
+    let (status, _, body) = send_request(
+        app,
+        Method::POST,
+        "/api/v1/destinations",
+        Some(token),
+        &[],
+        Some(json!({ "label": "Savings", "identifier": identifier })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    Uuid::parse_str(body["data"]["id"].as_str().unwrap()).unwrap()
+}
+
+/// Withdraws 10 from `account_id` to `destination_id`
+async fn withdraw(
+    app: &Router,
+    token: &str,
+    account_id: Uuid,
+    destination_id: Option<Uuid>,
+) -> (StatusCode, Value) {
+    let (status, _, body) = send_request(
+        app,
+        Method::POST,
+        "/api/v1/transactions/withdrawal",
+        Some(token),
+        &[],
+        Some(json!({
+            "account_id": account_id,
+            "amount": "10",
+            "destination_id": destination_id,
+        })),
+    )
+    .await;
+    (status, body)
+}
+
+/// Opens a funded account for a new user and returns its ID
+async fn funded_account(pool: &PgPool, username: &str) -> Uuid {
+    let user = register_user(&create_user_service(pool.clone()), username).await;
+    let account_id = create_account_service(pool.clone())
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    create_transaction_service(pool.clone())
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    account_id
+}
+
+async fn audit_actions(pool: &PgPool, destination_id: Uuid) -> Vec<String> {
+    sqlx::query_scalar(
+        "SELECT action FROM withdrawal_destination_events WHERE destination_id = $1 ORDER BY created_at",
+    )
+    .bind(destination_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[test]
+fn test_destination_identifier_masking() {
+    assert_eq!(mask_identifier("DE89370400440532013000"), "****3000");
+    assert_eq!(mask_identifier("0xAbCdEf"), "****CdEf");
+    assert_eq!(mask_identifier("abc"), "****");
+}
+
+#[tokio::test]
+async fn test_destination_rejection_matrix() {
+    let (pool, db_url) = setup().await;
+    let mut config = test_config(&db_url);
+    config.transactions.require_withdrawal_destination = true;
+    let app = create_router(&config, pool.clone());
+
+    let account = funded_account(&pool, "destowner").await;
+    funded_account(&pool, "destother").await;
+    funded_account(&pool, "destadmin").await;
+    promote_to_admin(&pool, "destadmin").await;
+    let token = login_token(&app, "destowner").await;
+    let other_token = login_token(&app, "destother").await;
+    let admin_token = login_token(&app, "destadmin").await;
+
+    let destination = register_destination(&app, &token, IBAN).await;
+    let foreign = register_destination(&app, &other_token, "NL91ABNA0417164300").await;
+
+    // Only the masked identifier is ever returned
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/destinations",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["masked_identifier"], "****3000");
+    assert_eq!(body["data"][0]["verified"], false);
+    assert!(!body.to_string().contains("DE89"), "{}", body);
+
+    // The same identifier can't be registered twice
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/destinations",
+        Some(&token),
+        &[],
+        Some(json!({ "label": "Again", "identifier": "DE89370400440532013000" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    for (destination_id, code) in [
+        (None, "DESTINATION_REQUIRED"),
+        (Some(Uuid::new_v4()), "DESTINATION_NOT_FOUND"),
+        (Some(foreign), "DESTINATION_NOT_OWNED"),
+        (Some(destination), "DESTINATION_UNVERIFIED"),
+    ] {
+        let (status, body) = withdraw(&app, &token, account, destination_id).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+        assert_eq!(body["error"], code);
+    }
+
+    // Payouts and generic withdrawals can't get around the registry
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/payout",
+        Some(&token),
+        &[],
+        Some(json!({ "account_id": account, "amount": "10" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "DESTINATION_REQUIRED");
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions",
+        Some(&token),
+        &[],
+        Some(json!({
+            "sender_account_id": account,
+            "amount": "10",
+            "currency": "USD",
+            "transaction_type": "WITHDRAWAL",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+
+    // Only administrators approve destinations
+    let verify_uri = format!("/api/v1/admin/destinations/{}/verify", destination);
+    let (status, _, _) =
+        send_request(&app, Method::POST, &verify_uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        &verify_uri,
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["verified"], true);
+
+    let (status, body) = withdraw(&app, &token, account, Some(destination)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let transaction_id = Uuid::parse_str(body["data"]["id"].as_str().unwrap()).unwrap();
+    let recorded: Option<Uuid> =
+        sqlx::query_scalar("SELECT destination_id FROM transactions WHERE id = $1")
+            .bind(transaction_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(recorded, Some(destination));
+
+    // Removing is limited to the owner, and a removed destination can't be used
+    let remove_uri = format!("/api/v1/destinations/{}", destination);
+    let (status, _, _) = send_request(
+        &app,
+        Method::DELETE,
+        &remove_uri,
+        Some(&other_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _, _) =
+        send_request(&app, Method::DELETE, &remove_uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = withdraw(&app, &token, account, Some(destination)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "DESTINATION_NOT_FOUND");
+
+    assert_eq!(
+        audit_actions(&pool, destination).await,
+        vec!["REGISTERED", "VERIFIED", "REMOVED"]
+    );
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_destination_usable_after_delay() {
+    let (pool, db_url) = setup().await;
+    let mut config = test_config(&db_url);
+    config.transactions.require_withdrawal_destination = true;
+    config.destinations.verification_delay = Some(Duration::from_secs(24 * 60 * 60));
+    let app = create_router(&config, pool.clone());
+
+    let account = funded_account(&pool, "delayowner").await;
+    let token = login_token(&app, "delayowner").await;
+    let destination =
+        register_destination(&app, &token, "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq").await;
+
+    // Within the delay the destination reports when it becomes usable
+    let (_, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/destinations",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(body["data"][0]["verified"], false);
+    assert!(body["data"][0]["usable_from"].is_string(), "{}", body);
+    let (status, body) = withdraw(&app, &token, account, Some(destination)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "DESTINATION_UNVERIFIED");
+
+    // Just before the delay runs out it is still refused
+    sqlx::query(
+        "UPDATE withdrawal_destinations SET verified_at = NOW() + INTERVAL '1 minute' WHERE id = $1",
+    )
+    .bind(destination)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let (status, _) = withdraw(&app, &token, account, Some(destination)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Once it has passed, no approval is needed
+    sqlx::query(
+        "UPDATE withdrawal_destinations SET verified_at = NOW() - INTERVAL '1 minute' WHERE id = $1",
+    )
+    .bind(destination)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let (status, body) = withdraw(&app, &token, account, Some(destination)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (_, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/destinations",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(body["data"][0]["verified"], true);
+    assert!(body["data"][0].get("usable_from").is_none());
+
+    // With the flag off, withdrawals without a destination are unaffected
+    let app = create_router(&test_config(&db_url), pool.clone());
+    let (status, body) = withdraw(&app, &token, account, None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,388 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, promote_to_admin, register_user, send_request, setup, teardown,
+};
+use axum::http::{Method, StatusCode};
+use axum::Router;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use txn_manager::{DepositRequest, DiagnosticsService};
+use uuid::Uuid;
+
+/// Opens a funded account for a new user and returns its ID
+async fn funded_account(pool: &PgPool, username: &str) -> Uuid {
+    let user = register_user(&create_user_service(pool.clone()), username).await;
+    let account_id = create_account_service(pool.clone())
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    create_transaction_service(pool.clone())
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    account_id
+}
+
+/// Sends a transaction request tagged with `request_id` and returns the response data
+async fn book(app: &Router, token: &str, path: &str, request_id: &str, body: Value) -> Value {
+    let (status, headers, body) = send_request(
+        app,
+        Method::POST,
+        &format!("/api/v1/transactions/{}", path),
+        Some(token),
+        &[("X-Request-Id", request_id)],
+        Some(body),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(headers["x-request-id"], request_id);
+    body["data"].clone()
+}
+
+fn timestamp(value: &Value) -> DateTime<Utc> {
+    value.as_str().unwrap().parse().unwrap()
+}
+
+#[tokio::test]
+async fn test_transaction_timings_recorded_for_each_type() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+
+    let account = funded_account(&pool, "timingowner").await;
+    let payee = funded_account(&pool, "timingpayee").await;
+    funded_account(&pool, "timingadmin").await;
+    promote_to_admin(&pool, "timingadmin").await;
+    let token = login_token(&app, "timingowner").await;
+    let admin_token = login_token(&app, "timingadmin").await;
+
+    let mut booked = vec![
+        (
+            "DEPOSIT",
+            "req-deposit",
+            book(
+                &app,
+                &token,
+                "deposit",
+                "req-deposit",
+                json!({ "account_id": account, "amount": "10" }),
+            )
+            .await,
+        ),
+        (
+            "TRANSFER",
+            "req-transfer",
+            book(
+                &app,
+                &token,
+                "transfer",
+                "req-transfer",
+                json!({
+                    "sender_account_id": account,
+                    "receiver_account_id": payee,
+                    "amount": "10"
+                }),
+            )
+            .await,
+        ),
+        (
+            "WITHDRAWAL",
+            "req-withdrawal",
+            book(
+                &app,
+                &token,
+                "withdrawal",
+                "req-withdrawal",
+                json!({ "account_id": account, "amount": "5" }),
+            )
+            .await,
+        ),
+        (
+            "WITHDRAWAL",
+            "req-payout",
+            book(
+                &app,
+                &token,
+                "payout",
+                "req-payout",
+                json!({ "account_id": account, "amount": "5" }),
+            )
+            .await,
+        ),
+    ];
+    let split = book(
+        &app,
+        &token,
+        "split",
+        "req-split",
+        json!({
+            "sender_account_id": account,
+            "legs": [{ "receiver_account_id": payee, "amount": "3" }]
+        }),
+    )
+    .await;
+    booked.push(("TRANSFER", "req-split", split["legs"][0].clone()));
+
+    for (transaction_type, request_id, transaction) in &booked {
+        let (status, _, body) = send_request(
+            &app,
+            Method::GET,
+            &format!(
+                "/api/v1/admin/transactions/{}",
+                transaction["id"].as_str().unwrap()
+            ),
+            Some(&admin_token),
+            &[],
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["data"]["transaction_type"], *transaction_type);
+
+        let timings = &body["data"]["timings"];
+        assert_eq!(timings["request_id"], *request_id, "{}", body);
+        assert!(!timings["instance_id"].as_str().unwrap().is_empty());
+        let received_at = timestamp(&timings["received_at"]);
+        let lock_acquired_at = timestamp(&timings["lock_acquired_at"]);
+        let committed_at = timestamp(&timings["committed_at"]);
+        assert!(received_at <= lock_acquired_at, "{}", timings);
+        assert!(lock_acquired_at <= committed_at, "{}", timings);
+        assert!(committed_at <= Utc::now());
+    }
+
+    // Deposits booked directly through the service are timed too, without a request ID
+    let recorded: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM transaction_timings WHERE request_id IS NULL")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(recorded, 3);
+
+    // A request ID the server can't use is replaced by one it generates
+    let (_, headers, _) = send_request(
+        &app,
+        Method::GET,
+        "/",
+        None,
+        &[("X-Request-Id", "not a valid id")],
+        None,
+    )
+    .await;
+    let generated = headers["x-request-id"].to_str().unwrap();
+    assert!(Uuid::parse_str(generated).is_ok(), "{}", generated);
+
+    // The histogram counts the same timings the detail shows
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/admin/diagnostics/latency?hours=1",
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let total = |transaction_type: &str| -> i64 {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|bucket| bucket["stage"] == "TOTAL")
+            .filter(|bucket| bucket["transaction_type"] == transaction_type)
+            .map(|bucket| bucket["count"].as_i64().unwrap())
+            .sum()
+    };
+    assert_eq!(total("DEPOSIT"), 4);
+    assert_eq!(total("TRANSFER"), 2);
+    assert_eq!(total("WITHDRAWAL"), 2);
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/admin/diagnostics/latency",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Purged timings disappear from the detail, leaving the transaction alone
+    let purged = DiagnosticsService::new(pool.clone())
+        .purge_timings(Utc::now() + Duration::minutes(1))
+        .await
+        .unwrap();
+    assert_eq!(purged, 8);
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        &format!(
+            "/api/v1/admin/transactions/{}",
+            booked[0].2["id"].as_str().unwrap()
+        ),
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"]["timings"].is_null(), "{}", body);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_throughput_peak_of_clustered_transactions() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+
+    // Five transactions in one second and two in the next
+    let accounts = [
+        funded_account(&pool, "tpsone").await,
+        funded_account(&pool, "tpstwo").await,
+    ];
+    let transaction_service = create_transaction_service(pool.clone());
+    for account_id in accounts.iter().cycle().take(5) {
+        transaction_service
+            .process_deposit(DepositRequest {
+                account_id: *account_id,
+                amount: Decimal::from(1),
+                currency: None,
+                exchange_rate: None,
+                description: None,
+                category: None,
+                external_reference: None,
+            })
+            .await
+            .unwrap();
+    }
+    let ids: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM transactions ORDER BY created_at")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(ids.len(), 7);
+    sqlx::query(
+        r#"
+        UPDATE transactions
+        SET created_at = CASE WHEN id = ANY($1)
+            THEN '2024-03-05 10:15:07.100+00'::TIMESTAMPTZ
+            ELSE '2024-03-05 10:15:08.900+00'::TIMESTAMPTZ END
+        "#,
+    )
+    .bind(&ids[..5])
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    funded_account(&pool, "tpsadmin").await;
+    promote_to_admin(&pool, "tpsadmin").await;
+    let token = login_token(&app, "tpsadmin").await;
+    let throughput = |query: &str| {
+        let app = app.clone();
+        let token = token.clone();
+        let uri = format!("/api/v1/admin/diagnostics/throughput?{}", query);
+        async move {
+            let (status, _, body) =
+                send_request(&app, Method::GET, &uri, Some(&token), &[], None).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            body["data"].clone()
+        }
+    };
+
+    let report = throughput("from=2024-03-05&to=2024-03-05").await;
+    assert_eq!(report["granularity"], "second");
+    assert_eq!(report["total_transactions"], 7);
+    assert_eq!(report["peak"]["bucket_start"], "2024-03-05T10:15:07Z");
+    assert_eq!(report["peak"]["count"], 5);
+    assert_eq!(report["peak"]["per_second"], 5.0);
+    let average = report["average_per_second"].as_f64().unwrap();
+    assert!((average - 7.0 / 86400.0).abs() < 1e-12, "{}", average);
+
+    let report = throughput("from=2024-03-05&to=2024-03-05&granularity=minute").await;
+    assert_eq!(report["peak"]["bucket_start"], "2024-03-05T10:15:00Z");
+    assert_eq!(report["peak"]["count"], 7);
+    let per_second = report["peak"]["per_second"].as_f64().unwrap();
+    assert!((per_second - 7.0 / 60.0).abs() < 1e-9, "{}", per_second);
+
+    // Days without transactions have no peak
+    let report = throughput("from=2024-03-06&to=2024-03-07").await;
+    assert_eq!(report["total_transactions"], 0);
+    assert!(report["peak"].is_null());
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/admin/diagnostics/throughput?from=2024-03-06&to=2024-03-05",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_error_responses_carry_request_id() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    register_user(&create_user_service(pool.clone()), "requestidowner").await;
+    let token = login_token(&app, "requestidowner").await;
+
+    // The ID sent is echoed in the header and, for errors, in the body too
+    for (uri, token, expected) in [
+        ("/api/v1/accounts", None, StatusCode::UNAUTHORIZED),
+        (
+            "/api/v2/transactions/00000000-0000-0000-0000-000000000000",
+            Some(token.as_str()),
+            StatusCode::NOT_FOUND,
+        ),
+    ] {
+        let (status, headers, body) = send_request(
+            &app,
+            Method::GET,
+            uri,
+            token,
+            &[("X-Request-Id", "support-ticket-42")],
+            None,
+        )
+        .await;
+        assert_eq!(status, expected, "{}", body);
+        assert_eq!(headers["x-request-id"], "support-ticket-42");
+        assert_eq!(body["request_id"], "support-ticket-42", "{}", uri);
+    }
+
+    // Generated IDs are reported the same way
+    let (_, headers, body) =
+        send_request(&app, Method::GET, "/api/v1/accounts", None, &[], None).await;
+    assert_eq!(
+        body["request_id"],
+        headers["x-request-id"].to_str().unwrap()
+    );
+
+    // Successful responses keep their body as it was
+    let (status, headers, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me",
+        Some(&token),
+        &[("X-Request-Id", "support-ticket-43")],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["x-request-id"], "support-ticket-43");
+    assert!(body.get("request_id").is_none());
+
+    teardown(&db_url).await;
+}
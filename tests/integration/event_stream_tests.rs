@@ -0,0 +1,220 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_user_service, login_token, register_user,
+    send_request, setup, teardown,
+};
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use chrono::Utc;
+use futures_util::{Stream, StreamExt};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tower::ServiceExt;
+use txn_manager::utils::events::{FeedItem, LagBehavior, TransactionFeed};
+use txn_manager::TransactionResponse;
+use uuid::Uuid;
+
+fn deposit(account_id: Uuid, status: &str) -> TransactionResponse {
+    TransactionResponse {
+        id: Uuid::new_v4(),
+        sender_account_id: None,
+        receiver_account_id: Some(account_id),
+        amount: Decimal::from(10),
+        currency: "USD".to_string(),
+        transaction_type: "DEPOSIT".to_string(),
+        status: status.to_string(),
+        description: None,
+        created_at: Utc::now(),
+        balance_after: None,
+        fee_amount: None,
+        fee_currency: None,
+        original_amount: None,
+        original_currency: None,
+        exchange_rate: None,
+        external_reference: None,
+        category: None,
+    }
+}
+
+/// The next item of a feed stream, `None` once the stream ended
+async fn next_item(stream: &mut (impl Stream<Item = FeedItem> + Unpin)) -> Option<FeedItem> {
+    tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("Timed out waiting for the feed")
+}
+
+/// Reads server-sent events until one named `name` arrives, returning its data
+async fn next_event(
+    body: &mut (impl Stream<Item = Result<axum::body::Bytes, axum::Error>> + Unpin),
+    buffer: &mut String,
+    name: &str,
+) -> Value {
+    loop {
+        while let Some(end) = buffer.find("\n\n") {
+            let event: String = buffer.drain(..end + 2).collect();
+            if event.lines().any(|line| line == format!("event: {}", name)) {
+                let data = event
+                    .lines()
+                    .find_map(|line| line.strip_prefix("data: "))
+                    .unwrap();
+                return serde_json::from_str(data).unwrap();
+            }
+        }
+        let chunk = tokio::time::timeout(Duration::from_secs(5), body.next())
+            .await
+            .expect("Timed out waiting for an event")
+            .expect("Event stream ended")
+            .unwrap();
+        buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+}
+
+async fn open_stream(app: &Router, account: Uuid, token: &str) -> axum::response::Response {
+    let request = Request::builder()
+        .uri(format!("/api/v1/accounts/{}/events", account))
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_feed_streams_completed_transactions_of_the_account() {
+    let account = Uuid::new_v4();
+    let feed = TransactionFeed::new(8, LagBehavior::Skip);
+    let mut stream = Box::pin(feed.subscribe(account));
+
+    // Pending transactions and other accounts' transactions are left out
+    feed.publish(&deposit(account, "PENDING"));
+    feed.publish(&deposit(Uuid::new_v4(), "COMPLETED"));
+    let completed = deposit(account, "COMPLETED");
+    feed.publish(&completed);
+
+    match next_item(&mut stream).await {
+        Some(FeedItem::Completed(transaction)) => assert_eq!(transaction.id, completed.id),
+        other => panic!("Expected the completed deposit, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_feed_lag_behavior() {
+    let account = Uuid::new_v4();
+    let transactions: Vec<_> = (0..4).map(|_| deposit(account, "COMPLETED")).collect();
+
+    // Skipping reports how many events were missed and carries on
+    let feed = TransactionFeed::new(2, LagBehavior::Skip);
+    let mut stream = Box::pin(feed.subscribe(account));
+    for transaction in &transactions {
+        feed.publish(transaction);
+    }
+    assert!(matches!(
+        next_item(&mut stream).await,
+        Some(FeedItem::Lagged(2))
+    ));
+    for expected in &transactions[2..] {
+        match next_item(&mut stream).await {
+            Some(FeedItem::Completed(transaction)) => assert_eq!(transaction.id, expected.id),
+            other => panic!("Expected a completed deposit, got {:?}", other),
+        }
+    }
+
+    // Disconnecting ends the stream instead
+    let feed = TransactionFeed::new(2, LagBehavior::Disconnect);
+    let mut stream = Box::pin(feed.subscribe(account));
+    for transaction in &transactions {
+        feed.publish(transaction);
+    }
+    assert!(next_item(&mut stream).await.is_none());
+}
+
+#[tokio::test]
+async fn test_account_event_stream() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+
+    let user = register_user(&user_service, "streamuser").await;
+    let payer = register_user(&user_service, "streampayer").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payer_account = account_service
+        .get_accounts_by_user_id(payer.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let token = login_token(&app, "streamuser").await;
+    let payer_token = login_token(&app, "streampayer").await;
+
+    // Only the owner can follow an account
+    let response = open_stream(&app, account, &payer_token).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let request = Request::builder()
+        .uri(format!("/api/v1/accounts/{}/events", account))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = open_stream(&app, account, &token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/event-stream"
+    );
+    let mut body = response.into_body().into_data_stream();
+    let mut buffer = String::new();
+
+    // Money in from outside and from another account both show up
+    let (status, _, deposit) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/deposit",
+        Some(&token),
+        &[],
+        Some(json!({ "account_id": account, "amount": "100" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", deposit);
+    let event = next_event(&mut body, &mut buffer, "transaction.completed").await;
+    assert_eq!(event["transaction_id"], deposit["data"]["id"]);
+    assert_eq!(event["transaction_type"], "DEPOSIT");
+    assert_eq!(event["receiver_account_id"], json!(account));
+
+    let (status, _, body_json) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/deposit",
+        Some(&payer_token),
+        &[],
+        Some(json!({ "account_id": payer_account, "amount": "50" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body_json);
+    let (status, _, transfer) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&payer_token),
+        &[],
+        Some(json!({
+            "sender_account_id": payer_account,
+            "receiver_account_id": account,
+            "amount": "20"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", transfer);
+
+    // The payer's own deposit doesn't involve the account
+    let event = next_event(&mut body, &mut buffer, "transaction.completed").await;
+    assert_eq!(event["transaction_id"], transfer["data"]["id"]);
+    assert_eq!(event["sender_account_id"], json!(payer_account));
+
+    drop(body);
+    teardown(&db_url).await;
+}
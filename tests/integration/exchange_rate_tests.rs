@@ -0,0 +1,254 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service_with_config,
+    create_user_service, login_token, promote_to_admin, register_user, send_request, setup,
+    teardown,
+};
+use axum::http::{Method, StatusCode};
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::str::FromStr;
+use std::time::Duration;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::utils::error::AppError;
+use txn_manager::{
+    DepositRequest, ExchangeService, SetExchangeRateRequest, TransactionConfig, TransferRequest,
+};
+
+#[tokio::test]
+async fn test_admins_maintain_exchange_rates() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+
+    register_user(&user_service, "ratesuser").await;
+    register_user(&user_service, "ratesadmin").await;
+    promote_to_admin(&pool, "ratesadmin").await;
+    let token = login_token(&app, "ratesuser").await;
+    let admin_token = login_token(&app, "ratesadmin").await;
+
+    let put = |token: String, body: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            send_request(
+                &app,
+                Method::PUT,
+                "/api/v1/admin/rates",
+                Some(&token),
+                &[],
+                Some(body),
+            )
+            .await
+        }
+    };
+
+    // Only administrators may set rates
+    let rate = json!({ "base_currency": "usd", "quote_currency": "EUR", "rate": "0.9225" });
+    let (status, _, _) = put(token.clone(), rate.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, body) = put(admin_token.clone(), rate).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["base_currency"], "USD");
+    assert_eq!(body["data"]["quote_currency"], "EUR");
+    assert_eq!(body["data"]["rate"], "0.9225");
+    assert_eq!(body["data"]["stale"], false);
+
+    // Setting a direction again replaces its rate
+    let (status, _, _) = put(
+        admin_token.clone(),
+        json!({ "base_currency": "USD", "quote_currency": "EUR", "rate": "0.93" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, _) = put(
+        admin_token.clone(),
+        json!({ "base_currency": "GBP", "quote_currency": "USD", "rate": "1.27" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    for invalid in [
+        json!({ "base_currency": "USD", "quote_currency": "EUR", "rate": "0" }),
+        json!({ "base_currency": "USD", "quote_currency": "EUR", "rate": "-1" }),
+        json!({ "base_currency": "USD", "quote_currency": "usd", "rate": "1" }),
+        json!({ "base_currency": "USD", "quote_currency": "XYZ", "rate": "1" }),
+    ] {
+        let (status, _, body) = put(admin_token.clone(), invalid.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", invalid, body);
+    }
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/admin/rates",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/admin/rates",
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let rates: Vec<_> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|rate| {
+            (
+                rate["base_currency"].as_str().unwrap(),
+                rate["quote_currency"].as_str().unwrap(),
+                rate["rate"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(rates, [("GBP", "USD", "1.27"), ("USD", "EUR", "0.93")]);
+
+    // A stored direction wins; the other falls back to the inverse, rounded
+    // half-even to 12 decimal places
+    let exchange_service = ExchangeService::new(pool.clone(), None);
+    let rate = |from: &'static str, to: &'static str| {
+        let exchange_service = &exchange_service;
+        async move { exchange_service.get_rate(from, to).await }
+    };
+    assert_eq!(
+        rate("USD", "EUR").await.unwrap(),
+        Decimal::from_str("0.93").unwrap()
+    );
+    assert_eq!(
+        rate("EUR", "USD").await.unwrap(),
+        Decimal::from_str("1.075268817204").unwrap()
+    );
+    assert_eq!(
+        rate("usd", "gbp").await.unwrap(),
+        Decimal::from_str("0.787401574803").unwrap()
+    );
+    assert_eq!(rate("EUR", "EUR").await.unwrap(), Decimal::ONE);
+    let missing = rate("EUR", "GBP").await;
+    assert!(
+        matches!(missing, Err(AppError::BadRequest(_))),
+        "{:?}",
+        missing
+    );
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_stale_exchange_rates_are_refused() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let ttl = Duration::from_secs(60 * 60);
+    let exchange_service = ExchangeService::new(pool.clone(), Some(ttl));
+    let transaction_service = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            exchange_rate_ttl: Some(ttl),
+            ..TransactionConfig::default()
+        },
+    );
+
+    let user = register_user(&user_service, "staleuser").await;
+    let dollar_account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let euro_account = account_service
+        .create_account(user.id, "EUR".to_string())
+        .await
+        .unwrap()
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: dollar_account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    // Last updated two hours ago, beyond the hour rates are trusted for
+    sqlx::query(
+        r#"
+        INSERT INTO exchange_rates (base_currency, quote_currency, rate, updated_at)
+        VALUES ('USD', 'EUR', 0.9225, NOW() - INTERVAL '2 hours')
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let rates = exchange_service.list_rates().await.unwrap();
+    assert!(rates[0].stale);
+    // In both directions, as the inverse is as old as the rate it comes from
+    for (from, to) in [("USD", "EUR"), ("EUR", "USD")] {
+        match exchange_service.get_rate(from, to).await {
+            Err(AppError::BadRequest(message)) => {
+                assert!(message.contains("out of date"), "{}", message)
+            }
+            other => panic!("expected a stale rate, got {:?}", other),
+        }
+    }
+    let transfer = || TransferRequest {
+        sender_account_id: dollar_account,
+        receiver_account_id: euro_account,
+        amount: Decimal::from(10),
+        description: None,
+        category: None,
+        allow_duplicate: false,
+    };
+    let result = transaction_service
+        .process_transfer(transfer(), &RequestContext::default())
+        .await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(_))),
+        "{:?}",
+        result
+    );
+
+    // Confirming the rate makes it current again
+    let refreshed = exchange_service
+        .set_rate(SetExchangeRateRequest {
+            base_currency: "USD".to_string(),
+            quote_currency: "EUR".to_string(),
+            rate: Decimal::from_str("0.9225").unwrap(),
+        })
+        .await
+        .unwrap();
+    assert!(!refreshed.stale);
+    let converted = transaction_service
+        .process_transfer(transfer(), &RequestContext::default())
+        .await
+        .unwrap();
+    assert_eq!(converted.amount, Decimal::from_str("9.22").unwrap());
+
+    // Without a TTL, rates never go stale
+    sqlx::query("ALTER TABLE exchange_rates DISABLE TRIGGER exchange_rates_touch_updated_at")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE exchange_rates SET updated_at = NOW() - INTERVAL '1 year'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let rate = ExchangeService::new(pool.clone(), None)
+        .get_rate("USD", "EUR")
+        .await
+        .unwrap();
+    assert_eq!(rate, Decimal::from_str("0.9225").unwrap());
+
+    teardown(&db_url).await;
+}
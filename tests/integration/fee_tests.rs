@@ -0,0 +1,751 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service,
+    create_transaction_service_with_config, create_user_service, login_token, promote_to_admin,
+    register_user, send_request, setup, teardown,
+};
+use axum::http::{Method, StatusCode};
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::str::FromStr;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::utils::error::AppError;
+use txn_manager::{
+    DepositRequest, FeeService, SetFeeRuleRequest, SplitTransferLeg, SplitTransferRequest,
+    TransactionConfig, TransferRequest, WithdrawalRequest,
+};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_withdrawal_fees_follow_fee_rules() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "feepayer").await;
+    register_user(&user_service, "feeadmin").await;
+    promote_to_admin(&pool, "feeadmin").await;
+    let token = login_token(&app, "feepayer").await;
+    let admin_token = login_token(&app, "feeadmin").await;
+    let put = |token: String, body: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            send_request(
+                &app,
+                Method::PUT,
+                "/api/v1/admin/fee-rules",
+                Some(&token),
+                &[],
+                Some(body),
+            )
+            .await
+        }
+    };
+
+    // Only administrators may set fee rules
+    let rule = json!({ "transaction_type": "withdrawal", "flat_amount": "1", "percent": "1" });
+    let (status, _, _) = put(token.clone(), rule.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, body) = put(admin_token.clone(), rule).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["transaction_type"], "WITHDRAWAL");
+    assert_eq!(body["data"]["currency"], serde_json::Value::Null);
+    assert_eq!(body["data"]["flat_amount"], "1");
+    assert_eq!(body["data"]["percent"], "1");
+
+    for invalid in [
+        json!({ "transaction_type": "DEPOSIT", "flat_amount": "1" }),
+        json!({ "transaction_type": "REFUND", "flat_amount": "1" }),
+        json!({ "transaction_type": "WITHDRAWAL", "flat_amount": "-1" }),
+        json!({ "transaction_type": "WITHDRAWAL", "percent": "101" }),
+        json!({ "transaction_type": "WITHDRAWAL", "currency": "XYZ", "percent": "1" }),
+    ] {
+        let (status, _, body) = put(admin_token.clone(), invalid.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", invalid, body);
+    }
+
+    let account_id = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let context = RequestContext::default();
+    let withdraw = |amount: i64| {
+        transaction_service.process_withdrawal(
+            WithdrawalRequest {
+                account_id,
+                amount: Decimal::from(amount),
+                description: None,
+                category: None,
+                destination_id: None,
+                external_reference: None,
+            },
+            &context,
+        )
+    };
+
+    // 1 plus 1% of 50 on top of the amount
+    let response = withdraw(50).await.unwrap();
+    assert_eq!(response.amount, Decimal::from(50));
+    assert_eq!(
+        response.fee_amount,
+        Some(Decimal::from_str("1.50").unwrap())
+    );
+    assert_eq!(response.fee_currency.as_deref(), Some("USD"));
+    let balance = || async {
+        account_service
+            .get_account_by_id(account_id)
+            .await
+            .unwrap()
+            .balance
+    };
+    assert_eq!(balance().await, Decimal::from_str("48.50").unwrap());
+
+    let (fee_amount, fee_currency): (String, Option<String>) =
+        sqlx::query_as("SELECT fee_amount::TEXT, fee_currency FROM transactions WHERE id = $1")
+            .bind(response.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        Decimal::from_str(&fee_amount).unwrap(),
+        Decimal::from_str("1.50").unwrap()
+    );
+    assert_eq!(fee_currency.as_deref(), Some("USD"));
+
+    // Without a fee account, the fee leaves the ledger like a withdrawal
+    let (fee_type, fee_receiver, fee): (String, Option<Uuid>, String) = sqlx::query_as(
+        r#"
+        SELECT transaction_type, receiver_account_id, amount::TEXT
+        FROM transactions WHERE parent_transaction_id = $1
+        "#,
+    )
+    .bind(response.id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(fee_type, "FEE");
+    assert_eq!(fee_receiver, None);
+    assert_eq!(
+        Decimal::from_str(&fee).unwrap(),
+        Decimal::from_str("1.50").unwrap()
+    );
+
+    // 48 is covered, but not with its fee of 1.48
+    let result = withdraw(48).await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(ref msg)) if msg == "Insufficient funds"),
+        "{:?}",
+        result
+    );
+    assert_eq!(balance().await, Decimal::from_str("48.50").unwrap());
+
+    let history = transaction_service
+        .get_transactions_by_account_id(account_id, &Default::default(), None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 3);
+    assert_eq!(
+        history[0].balance_after,
+        Some(Decimal::from_str("48.50").unwrap())
+    );
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/admin/fee-rules",
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_transfer_fee_rules_by_currency() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let fee_service = FeeService::new(pool.clone());
+
+    let sender = register_user(&user_service, "rulesender").await;
+    let receiver = register_user(&user_service, "rulereceiver").await;
+    let collector = register_user(&user_service, "rulecollector").await;
+    let first_account = |user_id: Uuid| {
+        let account_service = &account_service;
+        async move {
+            account_service
+                .get_accounts_by_user_id(user_id)
+                .await
+                .unwrap()[0]
+                .id
+        }
+    };
+    let euro_account = |user_id: Uuid| {
+        let account_service = &account_service;
+        async move {
+            account_service
+                .create_account(user_id, "EUR".to_string())
+                .await
+                .unwrap()
+                .id
+        }
+    };
+    let (dollar_sender, dollar_receiver) = (
+        first_account(sender.id).await,
+        first_account(receiver.id).await,
+    );
+    let (euro_sender, euro_receiver) = (
+        euro_account(sender.id).await,
+        euro_account(receiver.id).await,
+    );
+    let fee_account = first_account(collector.id).await;
+    // The configured transfer fee only applies where no rule does
+    let transaction_service = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            transfer_fee_flat: Decimal::from(5),
+            fee_account_id: Some(fee_account),
+            ..TransactionConfig::default()
+        },
+    );
+    sqlx::query(
+        "INSERT INTO exchange_rates (base_currency, quote_currency, rate) VALUES ('EUR', 'USD', 1.1)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    for account_id in [dollar_sender, euro_sender] {
+        transaction_service
+            .process_deposit(DepositRequest {
+                account_id,
+                amount: Decimal::from(100),
+                currency: None,
+                exchange_rate: None,
+                description: None,
+                category: None,
+                external_reference: None,
+            })
+            .await
+            .unwrap();
+    }
+    let context = RequestContext::default();
+    let transfer = |sender_account_id, receiver_account_id| {
+        transaction_service.process_transfer(
+            TransferRequest {
+                sender_account_id,
+                receiver_account_id,
+                amount: Decimal::from(20),
+                description: None,
+                category: None,
+                allow_duplicate: true,
+            },
+            &context,
+        )
+    };
+    let balance = |id| {
+        let account_service = &account_service;
+        async move { account_service.get_account_by_id(id).await.unwrap().balance }
+    };
+
+    let response = transfer(dollar_sender, dollar_receiver).await.unwrap();
+    assert_eq!(response.fee_amount, Some(Decimal::from(5)));
+
+    // A rule for the currency wins over the rule for every currency
+    for (currency, flat_amount, percent) in [(None, 2, 0), (Some("usd"), 0, 10)] {
+        fee_service
+            .set_rule(SetFeeRuleRequest {
+                transaction_type: "TRANSFER".to_string(),
+                currency: currency.map(str::to_string),
+                flat_amount: Decimal::from(flat_amount),
+                percent: Decimal::from(percent),
+            })
+            .await
+            .unwrap();
+    }
+    let response = transfer(dollar_sender, dollar_receiver).await.unwrap();
+    assert_eq!(response.fee_amount, Some(Decimal::from(2)));
+    assert_eq!(balance(dollar_sender).await, Decimal::from(53));
+
+    // A fee in another currency is credited to the fee account converted
+    let response = transfer(euro_sender, euro_receiver).await.unwrap();
+    assert_eq!(response.fee_amount, Some(Decimal::from(2)));
+    assert_eq!(response.fee_currency.as_deref(), Some("EUR"));
+    assert_eq!(balance(euro_sender).await, Decimal::from(78));
+    assert_eq!(balance(euro_receiver).await, Decimal::from(20));
+    assert_eq!(
+        balance(fee_account).await,
+        Decimal::from_str("9.20").unwrap()
+    );
+
+    let rules = fee_service.list_rules().await.unwrap();
+    let currencies: Vec<_> = rules.iter().map(|rule| rule.currency.as_deref()).collect();
+    assert_eq!(currencies, [Some("USD"), None]);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_get_transaction_shows_fee_and_conversion() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let sender = register_user(&user_service, "detailsender").await;
+    let receiver = register_user(&user_service, "detailreceiver").await;
+    let token = login_token(&app, "detailsender").await;
+    let euro_account = account_service
+        .create_account(sender.id, "EUR".to_string())
+        .await
+        .unwrap()
+        .id;
+    let dollar_account = account_service
+        .get_accounts_by_user_id(receiver.id)
+        .await
+        .unwrap()[0]
+        .id;
+    sqlx::query(
+        "INSERT INTO exchange_rates (base_currency, quote_currency, rate) VALUES ('EUR', 'USD', 1.1)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    FeeService::new(pool.clone())
+        .set_rule(SetFeeRuleRequest {
+            transaction_type: "TRANSFER".to_string(),
+            currency: None,
+            flat_amount: Decimal::from(2),
+            percent: Decimal::ZERO,
+        })
+        .await
+        .unwrap();
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: euro_account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&token),
+        &[],
+        Some(json!({
+            "sender_account_id": euro_account,
+            "receiver_account_id": dollar_account,
+            "amount": "20",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let transfer = body["data"].clone();
+
+    // Reading the transfer back shows what the transfer response did
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/transactions/{}", transfer["id"].as_str().unwrap()),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["fee_currency"], "EUR");
+    assert_eq!(body["data"]["original_currency"], "EUR");
+    for field in [
+        "amount",
+        "currency",
+        "fee_amount",
+        "fee_currency",
+        "original_amount",
+        "original_currency",
+        "exchange_rate",
+    ] {
+        assert_eq!(body["data"][field], transfer[field], "{}", field);
+    }
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_split_transfer_legs_pay_transfer_fees() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+
+    let mut account_ids = Vec::new();
+    for username in [
+        "splitfeesender",
+        "splitfeefirst",
+        "splitfeesecond",
+        "splitfeecollector",
+    ] {
+        let user = register_user(&user_service, username).await;
+        account_ids.push(
+            account_service
+                .get_accounts_by_user_id(user.id)
+                .await
+                .unwrap()[0]
+                .id,
+        );
+    }
+    let (sender, first, second, fee_account) = (
+        account_ids[0],
+        account_ids[1],
+        account_ids[2],
+        account_ids[3],
+    );
+    let transaction_service = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            fee_account_id: Some(fee_account),
+            ..TransactionConfig::default()
+        },
+    );
+    FeeService::new(pool.clone())
+        .set_rule(SetFeeRuleRequest {
+            transaction_type: "TRANSFER".to_string(),
+            currency: None,
+            flat_amount: Decimal::from(1),
+            percent: Decimal::from(1),
+        })
+        .await
+        .unwrap();
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: sender,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let context = RequestContext::default();
+    let balance = |id| {
+        let account_service = &account_service;
+        async move { account_service.get_account_by_id(id).await.unwrap().balance }
+    };
+    let split = |amounts: Vec<(Uuid, i64)>| {
+        transaction_service.process_split_transfer(
+            SplitTransferRequest {
+                sender_account_id: sender,
+                legs: amounts
+                    .into_iter()
+                    .map(|(receiver_account_id, amount)| SplitTransferLeg {
+                        receiver_account_id,
+                        amount: Decimal::from(amount),
+                    })
+                    .collect(),
+                description: None,
+            },
+            &context,
+        )
+    };
+
+    let transfer = transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: sender,
+                receiver_account_id: first,
+                amount: Decimal::from(20),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            },
+            &context,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        transfer.fee_amount,
+        Some(Decimal::from_str("1.20").unwrap())
+    );
+
+    // Each leg pays what a transfer of its amount would
+    let response = split(vec![(first, 20), (second, 30)]).await.unwrap();
+    assert_eq!(response.legs[0].fee_amount, transfer.fee_amount);
+    assert_eq!(response.legs[0].fee_currency.as_deref(), Some("USD"));
+    assert_eq!(
+        response.legs[1].fee_amount,
+        Some(Decimal::from_str("1.30").unwrap())
+    );
+    assert_eq!(balance(sender).await, Decimal::from_str("26.30").unwrap());
+    assert_eq!(
+        balance(fee_account).await,
+        Decimal::from_str("3.70").unwrap()
+    );
+
+    // 26 is covered, but not with the fees of 2.26
+    let result = split(vec![(first, 13), (second, 13)]).await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(ref msg)) if msg == "Insufficient funds"),
+        "{:?}",
+        result
+    );
+    assert_eq!(balance(sender).await, Decimal::from_str("26.30").unwrap());
+    assert_eq!(
+        balance(fee_account).await,
+        Decimal::from_str("3.70").unwrap()
+    );
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_reversal_refunds_transfer_fee() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+
+    let mut account_ids = Vec::new();
+    for username in ["refundsender", "refundreceiver", "refundcollector"] {
+        let user = register_user(&user_service, username).await;
+        account_ids.push(
+            account_service
+                .get_accounts_by_user_id(user.id)
+                .await
+                .unwrap()[0]
+                .id,
+        );
+    }
+    let (sender, receiver, fee_account) = (account_ids[0], account_ids[1], account_ids[2]);
+    FeeService::new(pool.clone())
+        .set_rule(SetFeeRuleRequest {
+            transaction_type: "TRANSFER".to_string(),
+            currency: None,
+            flat_amount: Decimal::from(1),
+            percent: Decimal::from(1),
+        })
+        .await
+        .unwrap();
+    // With a fee account, and without one, where the fee leaves the ledger
+    let with_fee_account = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            fee_account_id: Some(fee_account),
+            ..TransactionConfig::default()
+        },
+    );
+    let without_fee_account = create_transaction_service(pool.clone());
+    with_fee_account
+        .process_deposit(DepositRequest {
+            account_id: sender,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let context = RequestContext::default();
+    let balance = |id| {
+        let account_service = &account_service;
+        async move { account_service.get_account_by_id(id).await.unwrap().balance }
+    };
+
+    for (transaction_service, refunded_from) in [
+        (&with_fee_account, Some(fee_account)),
+        (&without_fee_account, None),
+    ] {
+        let transfer = transaction_service
+            .process_transfer(
+                TransferRequest {
+                    sender_account_id: sender,
+                    receiver_account_id: receiver,
+                    amount: Decimal::from(20),
+                    description: None,
+                    category: None,
+                    allow_duplicate: false,
+                },
+                &context,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            transfer.fee_amount,
+            Some(Decimal::from_str("1.20").unwrap())
+        );
+        assert_eq!(balance(sender).await, Decimal::from_str("78.80").unwrap());
+
+        // The sender gets the amount and the fee back
+        transaction_service
+            .reverse_transaction(transfer.id, &context)
+            .await
+            .unwrap();
+        assert_eq!(balance(sender).await, Decimal::from(100));
+        assert_eq!(balance(receiver).await, Decimal::ZERO);
+        assert_eq!(balance(fee_account).await, Decimal::ZERO);
+
+        // The refund undoes the FEE transaction, which is now reversed
+        let (fee_status, refund_sender, refund_amount): (String, Option<Uuid>, String) =
+            sqlx::query_as(
+                r#"
+                SELECT f.status, r.sender_account_id, r.amount::TEXT
+                FROM transactions f
+                JOIN transactions r ON r.reverses_transaction_id = f.id AND f.reversed_by = r.id
+                WHERE f.parent_transaction_id = $1 AND r.transaction_type = 'FEE'
+                "#,
+            )
+            .bind(transfer.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(fee_status, "REVERSED");
+        assert_eq!(refund_sender, refunded_from);
+        assert_eq!(
+            Decimal::from_str(&refund_amount).unwrap(),
+            Decimal::from_str("1.20").unwrap()
+        );
+    }
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_reverse_fee_keeps_the_transfer() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+
+    let mut account_ids = Vec::new();
+    for username in ["waivesender", "waivereceiver", "waivecollector"] {
+        let user = register_user(&user_service, username).await;
+        account_ids.push(
+            account_service
+                .get_accounts_by_user_id(user.id)
+                .await
+                .unwrap()[0]
+                .id,
+        );
+    }
+    let (sender, receiver, fee_account) = (account_ids[0], account_ids[1], account_ids[2]);
+    let transaction_service = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            transfer_fee_flat: Decimal::from(2),
+            fee_account_id: Some(fee_account),
+            ..TransactionConfig::default()
+        },
+    );
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: sender,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let context = RequestContext::default();
+    let balance = |id| {
+        let account_service = &account_service;
+        async move { account_service.get_account_by_id(id).await.unwrap().balance }
+    };
+    let rejection = |result: Result<_, AppError>| match result {
+        Err(AppError::Rejected { code, .. }) => code,
+        other => panic!("Expected a rejection, got {:?}", other),
+    };
+
+    let transfer = transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: sender,
+                receiver_account_id: receiver,
+                amount: Decimal::from(30),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            },
+            &context,
+        )
+        .await
+        .unwrap();
+    assert_eq!(balance(sender).await, Decimal::from(68));
+
+    // The fee comes back to the sender; the transfer stands
+    let refund = transaction_service
+        .reverse_fee(transfer.id, &context)
+        .await
+        .unwrap();
+    assert_eq!(refund.transaction_type, "FEE");
+    assert_eq!(refund.status, "COMPLETED");
+    assert_eq!(refund.sender_account_id, Some(fee_account));
+    assert_eq!(refund.receiver_account_id, Some(sender));
+    assert_eq!(refund.amount, Decimal::from(2));
+    assert_eq!(balance(sender).await, Decimal::from(70));
+    assert_eq!(balance(receiver).await, Decimal::from(30));
+    assert_eq!(balance(fee_account).await, Decimal::ZERO);
+    let principal = transaction_service
+        .get_transaction_by_id(transfer.id)
+        .await
+        .unwrap();
+    assert_eq!(principal.status, "COMPLETED");
+    assert_eq!(principal.amount, Decimal::from(30));
+
+    // A fee is refunded once
+    let result = transaction_service.reverse_fee(transfer.id, &context).await;
+    assert_eq!(rejection(result), "TRANSACTION_ALREADY_REVERSED");
+    assert_eq!(balance(sender).await, Decimal::from(70));
+
+    // Reversing the transfer later gives back the amount only
+    transaction_service
+        .reverse_transaction(transfer.id, &context)
+        .await
+        .unwrap();
+    assert_eq!(balance(sender).await, Decimal::from(100));
+    assert_eq!(balance(receiver).await, Decimal::ZERO);
+
+    // A deposit carries no fee, and an unknown transaction none either
+    let deposit_id: Uuid = sqlx::query_scalar(
+        "SELECT id FROM transactions WHERE receiver_account_id = $1 AND transaction_type = 'DEPOSIT'",
+    )
+    .bind(sender)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let result = transaction_service.reverse_fee(deposit_id, &context).await;
+    assert_eq!(rejection(result), "TRANSACTION_NOT_REVERSIBLE");
+    let result = transaction_service
+        .reverse_fee(Uuid::new_v4(), &context)
+        .await;
+    assert!(matches!(result, Err(AppError::NotFound(_))), "{:?}", result);
+
+    teardown(&db_url).await;
+}
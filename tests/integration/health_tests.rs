@@ -0,0 +1,28 @@
+use crate::integration::setup::{create_app, send_request, setup, teardown};
+use axum::http::{Method, StatusCode};
+
+#[tokio::test]
+async fn test_readiness_check_reports_database_status() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+
+    let (status, _, body) = send_request(&app, Method::GET, "/health/ready", None, &[], None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["database"], "up");
+    let size = body["pool"]["size"].as_u64().unwrap();
+    assert!(size >= 1, "{}", body);
+    assert_eq!(
+        body["pool"]["active"].as_u64().unwrap() + body["pool"]["idle"].as_u64().unwrap(),
+        size
+    );
+
+    // Without a database, the service isn't ready but is still alive
+    pool.close().await;
+    let (status, _, body) = send_request(&app, Method::GET, "/health/ready", None, &[], None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{}", body);
+    assert_eq!(body["database"], "down");
+    let (status, _, _) = send_request(&app, Method::GET, "/", None, &[], None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,287 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, register_user, send_request, setup, teardown,
+};
+use axum::http::{Method, StatusCode};
+use rust_decimal::Decimal;
+use serde_json::json;
+use sqlx::PgPool;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::utils::error::AppError;
+use txn_manager::{CaptureHoldRequest, CreateHoldRequest, DepositRequest};
+use uuid::Uuid;
+
+/// Opens an account with 100 in it for a new user and returns its ID
+async fn funded_account(pool: &PgPool, username: &str) -> Uuid {
+    let user = register_user(&create_user_service(pool.clone()), username).await;
+    let account_id = create_account_service(pool.clone())
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    create_transaction_service(pool.clone())
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    account_id
+}
+
+async fn held_amount(pool: &PgPool, account_id: Uuid) -> Decimal {
+    let held: String = sqlx::query_scalar("SELECT held_amount::TEXT FROM accounts WHERE id = $1")
+        .bind(account_id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    held.parse().unwrap()
+}
+
+fn rejection<T: std::fmt::Debug>(result: Result<T, AppError>) -> &'static str {
+    match result {
+        Err(AppError::Rejected { code, .. }) => code,
+        other => panic!("Expected a rejection, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_partial_capture_releases_the_rest() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let account_service = create_account_service(pool.clone());
+
+    let account = funded_account(&pool, "holdowner").await;
+    let merchant = funded_account(&pool, "holdmerchant").await;
+    let token = login_token(&app, "holdowner").await;
+    let merchant_token = login_token(&app, "holdmerchant").await;
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/holds",
+        Some(&token),
+        &[],
+        Some(json!({ "account_id": account, "amount": "60", "description": "Hotel" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "ACTIVE");
+    assert_eq!(body["data"]["amount"], "60.00");
+    let hold_id = body["data"]["id"].as_str().unwrap().to_string();
+    assert_eq!(held_amount(&pool, account).await, Decimal::from(60));
+
+    // Held funds stay on the account but can't be spent
+    assert_eq!(
+        account_service
+            .get_account_by_id(account)
+            .await
+            .unwrap()
+            .balance,
+        Decimal::from(100)
+    );
+    for (path, request) in [
+        (
+            "withdrawal",
+            json!({ "account_id": account, "amount": "50" }),
+        ),
+        (
+            "transfer",
+            json!({ "sender_account_id": account, "receiver_account_id": merchant, "amount": "50" }),
+        ),
+    ] {
+        let (status, _, body) = send_request(
+            &app,
+            Method::POST,
+            &format!("/api/v1/transactions/{}", path),
+            Some(&token),
+            &[],
+            Some(request),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    }
+
+    // Only the account owner may capture the hold
+    let capture_uri = format!("/api/v1/transactions/holds/{}/capture", hold_id);
+    let capture = json!({ "amount": "25", "receiver_account_id": merchant });
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        &capture_uri,
+        Some(&merchant_token),
+        &[],
+        Some(capture.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        &capture_uri,
+        Some(&token),
+        &[],
+        Some(capture),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let hold = &body["data"]["hold"];
+    let transaction = &body["data"]["transaction"];
+    assert_eq!(hold["status"], "CAPTURED");
+    assert_eq!(hold["captured_amount"], "25.00");
+    assert_eq!(hold["transaction_id"], transaction["id"]);
+    assert_eq!(transaction["transaction_type"], "TRANSFER");
+    assert_eq!(transaction["amount"], "25.00");
+    assert_eq!(transaction["description"], "Hotel");
+
+    // The 35 that wasn't captured is available again
+    assert_eq!(held_amount(&pool, account).await, Decimal::ZERO);
+    assert_eq!(
+        account_service
+            .get_account_by_id(account)
+            .await
+            .unwrap()
+            .balance,
+        Decimal::from(75)
+    );
+    assert_eq!(
+        account_service
+            .get_account_by_id(merchant)
+            .await
+            .unwrap()
+            .balance,
+        Decimal::from(125)
+    );
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/withdrawal",
+        Some(&token),
+        &[],
+        Some(json!({ "account_id": account, "amount": "75" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // A captured hold can't be captured again
+    let (status, _, body) =
+        send_request(&app, Method::POST, &capture_uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "HOLD_NOT_ACTIVE");
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_hold_release_and_rejections() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let account = funded_account(&pool, "releaseowner").await;
+    let other = funded_account(&pool, "releaseother").await;
+    let hold = |amount: i64| {
+        let transaction_service = &transaction_service;
+        async move {
+            transaction_service
+                .create_hold(CreateHoldRequest {
+                    account_id: account,
+                    amount: Decimal::from(amount),
+                    description: None,
+                })
+                .await
+        }
+    };
+
+    let first = hold(70).await.unwrap();
+    assert!(matches!(hold(40).await, Err(AppError::BadRequest(_))));
+
+    // Releasing frees the funds once; a second release changes nothing
+    let released = transaction_service.release_hold(first.id).await.unwrap();
+    assert_eq!(released.status, "RELEASED");
+    assert_eq!(held_amount(&pool, account).await, Decimal::ZERO);
+    let result = transaction_service.release_hold(first.id).await;
+    assert_eq!(rejection(result), "HOLD_NOT_ACTIVE");
+    let result = transaction_service
+        .capture_hold(
+            first.id,
+            CaptureHoldRequest::default(),
+            &RequestContext::default(),
+        )
+        .await;
+    assert_eq!(rejection(result), "HOLD_NOT_ACTIVE");
+    assert_eq!(held_amount(&pool, account).await, Decimal::ZERO);
+
+    // Over the API as well, and only by the owner
+    let second = hold(40).await.unwrap();
+    let release_uri = format!("/api/v1/transactions/holds/{}/release", second.id);
+    let other_token = login_token(&app, "releaseother").await;
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        &release_uri,
+        Some(&other_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let token = login_token(&app, "releaseowner").await;
+    let (status, _, body) =
+        send_request(&app, Method::POST, &release_uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "RELEASED");
+    let (status, _, body) =
+        send_request(&app, Method::POST, &release_uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "HOLD_NOT_ACTIVE");
+
+    // A capture may not exceed the hold, and a failed capture keeps it active
+    let third = hold(30).await.unwrap();
+    let result = transaction_service
+        .capture_hold(
+            third.id,
+            CaptureHoldRequest {
+                amount: Some(Decimal::from(31)),
+                receiver_account_id: Some(other),
+                ..Default::default()
+            },
+            &RequestContext::default(),
+        )
+        .await;
+    assert_eq!(rejection(result), "CAPTURE_EXCEEDS_HOLD");
+    assert_eq!(held_amount(&pool, account).await, Decimal::from(30));
+
+    // Without a receiver the whole hold is captured as a withdrawal
+    let captured = transaction_service
+        .capture_hold(
+            third.id,
+            CaptureHoldRequest::default(),
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(captured.transaction.transaction_type, "WITHDRAWAL");
+    assert_eq!(captured.hold.captured_amount, Some(Decimal::from(30)));
+    assert_eq!(held_amount(&pool, account).await, Decimal::ZERO);
+    assert_eq!(
+        transaction_service
+            .account_service
+            .get_account_by_id(account)
+            .await
+            .unwrap()
+            .balance,
+        Decimal::from(70)
+    );
+
+    let result = transaction_service.release_hold(Uuid::new_v4()).await;
+    assert!(matches!(result, Err(AppError::NotFound(_))));
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,361 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, register_user, send_request, setup, teardown,
+};
+use axum::http::{Method, StatusCode};
+use rust_decimal::Decimal;
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::{DepositRequest, IdempotencyKey, TransferRequest};
+use uuid::Uuid;
+
+async fn sent_transfers(pool: &PgPool, account_id: Uuid) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE sender_account_id = $1")
+        .bind(account_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[test]
+fn test_idempotency_key_hash_covers_endpoint_and_body() {
+    let user_id = Uuid::new_v4();
+    let body = json!({ "amount": "10.00" });
+
+    let key = IdempotencyKey::new(user_id, " retry-1 ", "transfer", &body).unwrap();
+    assert_eq!(key.key, "retry-1");
+    assert_eq!(
+        key,
+        IdempotencyKey::new(user_id, "retry-1", "transfer", &body).unwrap()
+    );
+    assert_ne!(
+        key.request_hash,
+        IdempotencyKey::new(user_id, "retry-1", "deposit", &body)
+            .unwrap()
+            .request_hash
+    );
+    assert_ne!(
+        key.request_hash,
+        IdempotencyKey::new(
+            user_id,
+            "retry-1",
+            "transfer",
+            &json!({ "amount": "10.01" })
+        )
+        .unwrap()
+        .request_hash
+    );
+
+    assert!(IdempotencyKey::new(user_id, "  ", "transfer", &body).is_err());
+    assert!(IdempotencyKey::new(user_id, &"k".repeat(256), "transfer", &body).is_err());
+}
+
+#[tokio::test]
+async fn test_transfer_replayed_with_idempotency_key() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let sender = register_user(&user_service, "retrysender").await;
+    let receiver = register_user(&user_service, "retryreceiver").await;
+    let sender_account = account_service
+        .get_accounts_by_user_id(sender.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let receiver_account = account_service
+        .get_accounts_by_user_id(receiver.id)
+        .await
+        .unwrap()[0]
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: sender_account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let token = login_token(&app, "retrysender").await;
+
+    let transfer = |amount: &str| {
+        Some(json!({
+            "sender_account_id": sender_account,
+            "receiver_account_id": receiver_account,
+            "amount": amount
+        }))
+    };
+    let key = [("Idempotency-Key", "transfer-0001")];
+
+    // The retry gets the original response and the money moves once
+    let (status, _, first) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&token),
+        &key,
+        transfer("30"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", first);
+    let (status, _, retry) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&token),
+        &key,
+        transfer("30"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", retry);
+    assert_eq!(retry["data"], first["data"]);
+    assert_eq!(sent_transfers(&pool, sender_account).await, 1);
+    let balance = account_service
+        .get_account_by_id(sender_account)
+        .await
+        .unwrap();
+    assert_eq!(balance.balance, Decimal::from(70));
+
+    // The same key can't be reused for another amount or another endpoint
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&token),
+        &key,
+        transfer("31"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/withdrawal",
+        Some(&token),
+        &key,
+        Some(json!({ "account_id": sender_account, "amount": "30" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // A request that fails leaves its key unused, so it can be retried later
+    let short_key = [("Idempotency-Key", "transfer-0002")];
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&token),
+        &short_key,
+        transfer("500"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: sender_account,
+            amount: Decimal::from(500),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&token),
+        &short_key,
+        transfer("500"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(sent_transfers(&pool, sender_account).await, 2);
+
+    // Keys belong to the user: another user's identical key is independent
+    let receiver_token = login_token(&app, "retryreceiver").await;
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/deposit",
+        Some(&receiver_token),
+        &key,
+        Some(json!({ "account_id": receiver_account, "amount": "5" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["transaction_type"], "DEPOSIT");
+
+    // Without a key every request is booked, once it opts out of the
+    // duplicate check
+    for _ in 0..2 {
+        let mut body = transfer("1");
+        body.as_mut().unwrap()["allow_duplicate"] = true.into();
+        let (status, _, _) = send_request(
+            &app,
+            Method::POST,
+            "/api/v1/transactions/transfer",
+            Some(&token),
+            &[],
+            body,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    assert_eq!(sent_transfers(&pool, sender_account).await, 4);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_concurrent_retries_book_once() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = Arc::new(create_transaction_service(pool.clone()));
+
+    let sender = register_user(&user_service, "racesender").await;
+    let receiver = register_user(&user_service, "racereceiver").await;
+    let sender_account = account_service
+        .get_accounts_by_user_id(sender.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let receiver_account = account_service
+        .get_accounts_by_user_id(receiver.id)
+        .await
+        .unwrap()[0]
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: sender_account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    let request = TransferRequest {
+        sender_account_id: sender_account,
+        receiver_account_id: receiver_account,
+        amount: Decimal::from(10),
+        description: None,
+        category: None,
+        allow_duplicate: false,
+    };
+    let key = IdempotencyKey::new(sender.id, "race-key", "transfer", &request).unwrap();
+
+    let attempts = (0..5).map(|_| {
+        let service = transaction_service.clone();
+        let request = request.clone();
+        let key = key.clone();
+        tokio::spawn(async move {
+            service
+                .process_transfer_idempotent(request, &RequestContext::default(), Some(&key))
+                .await
+        })
+    });
+    let mut ids = Vec::new();
+    for attempt in attempts.collect::<Vec<_>>() {
+        ids.push(attempt.await.unwrap().unwrap().id);
+    }
+    ids.dedup();
+    assert_eq!(ids.len(), 1);
+
+    assert_eq!(sent_transfers(&pool, sender_account).await, 1);
+    let balance = account_service
+        .get_account_by_id(sender_account)
+        .await
+        .unwrap();
+    assert_eq!(balance.balance, Decimal::from(90));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_deposit_key_books_once_until_expired() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+
+    let user = register_user(&user_service, "retrydepositor").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let token = login_token(&app, "retrydepositor").await;
+    let key = [("Idempotency-Key", "deposit-0001")];
+    let deposit = || Some(json!({ "account_id": account_id, "amount": "25" }));
+
+    let mut responses = Vec::new();
+    for _ in 0..2 {
+        let (status, _, body) = send_request(
+            &app,
+            Method::POST,
+            "/api/v1/transactions/deposit",
+            Some(&token),
+            &key,
+            deposit(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        responses.push(body);
+    }
+    assert_eq!(responses[0]["data"], responses[1]["data"]);
+    let balance = account_service.get_account_by_id(account_id).await.unwrap();
+    assert_eq!(balance.balance, Decimal::from(25));
+
+    // The key records the transaction it booked
+    let recorded: Option<Uuid> = sqlx::query_scalar(
+        "SELECT transaction_id FROM idempotency_keys WHERE user_id = $1 AND key = $2",
+    )
+    .bind(user.id)
+    .bind("deposit-0001")
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        recorded.map(|id| id.to_string()).as_deref(),
+        responses[0]["data"]["id"].as_str()
+    );
+
+    // Once the key has expired, the same request is booked again
+    sqlx::query(
+        "UPDATE idempotency_keys SET created_at = NOW() - INTERVAL '25 hours' WHERE user_id = $1",
+    )
+    .bind(user.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/deposit",
+        Some(&token),
+        &key,
+        deposit(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_ne!(body["data"]["id"], responses[0]["data"]["id"]);
+    let balance = account_service.get_account_by_id(account_id).await.unwrap();
+    assert_eq!(balance.balance, Decimal::from(50));
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,198 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, promote_to_admin, register_user, send_request, setup, teardown,
+};
+use axum::http::{Method, StatusCode};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::sync::atomic::Ordering;
+use txn_manager::services::integrity_service::CHECKS_WITH_VIOLATIONS;
+use txn_manager::{DepositRequest, IntegrityConfig, IntegrityService, ViolationKind};
+use uuid::Uuid;
+
+/// An integrity service reading two rows per query, so scans span several batches
+fn small_batch_service(pool: PgPool) -> IntegrityService {
+    IntegrityService::new(
+        pool,
+        IntegrityConfig {
+            check_interval: None,
+            batch_size: 2,
+        },
+    )
+}
+
+/// Creates a few users, each with a default account funded by a deposit
+async fn seed_consistent_data(pool: &PgPool) -> Vec<Uuid> {
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let mut accounts = Vec::new();
+    for name in ["integrityone", "integritytwo", "integritythree"] {
+        let user = register_user(&user_service, name).await;
+        let account = account_service
+            .get_accounts_by_user_id(user.id)
+            .await
+            .unwrap()[0]
+            .id;
+        transaction_service
+            .process_deposit(DepositRequest {
+                account_id: account,
+                amount: Decimal::from(50),
+                currency: None,
+                exchange_rate: None,
+                description: None,
+                category: None,
+                external_reference: None,
+            })
+            .await
+            .unwrap();
+        accounts.push(account);
+    }
+
+    accounts
+}
+
+/// Drops the constraints that would otherwise stop us from planting bad rows
+async fn disable_constraints(pool: &PgPool) {
+    for statement in [
+        "ALTER TABLE transactions DROP CONSTRAINT transactions_sender_account_id_fkey",
+        "ALTER TABLE transactions DROP CONSTRAINT transactions_receiver_account_id_fkey",
+        "ALTER TABLE transactions DROP CONSTRAINT transactions_status_check",
+        "ALTER TABLE transactions DROP CONSTRAINT transaction_not_self",
+        "ALTER TABLE accounts DROP CONSTRAINT balance_non_negative",
+    ] {
+        sqlx::query(statement).execute(pool).await.unwrap();
+    }
+}
+
+async fn insert_transaction(
+    pool: &PgPool,
+    sender: Option<Uuid>,
+    receiver: Option<Uuid>,
+    transaction_type: &str,
+    status: &str,
+) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO transactions \
+         (id, sender_account_id, receiver_account_id, amount, currency, transaction_type, status) \
+         VALUES ($1, $2, $3, 10, 'USD', $4, $5)",
+    )
+    .bind(id)
+    .bind(sender)
+    .bind(receiver)
+    .bind(transaction_type)
+    .bind(status)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+#[tokio::test]
+async fn test_integrity_check_passes_on_consistent_data() {
+    let (pool, db_url) = setup().await;
+    seed_consistent_data(&pool).await;
+    let integrity_service = small_batch_service(pool.clone());
+
+    let report = integrity_service.run_check().await.unwrap();
+    assert!(
+        report.is_clean(),
+        "Unexpected violations: {:?}",
+        report.violations
+    );
+    assert_eq!(report.transactions_scanned, 3);
+    assert_eq!(report.accounts_scanned, 3);
+
+    // The report is persisted
+    let latest = integrity_service.latest_report().await.unwrap();
+    assert_eq!(latest.id, report.id);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_integrity_check_detects_each_violation_class() {
+    let (pool, db_url) = setup().await;
+    let accounts = seed_consistent_data(&pool).await;
+    disable_constraints(&pool).await;
+
+    let missing_sender = insert_transaction(
+        &pool,
+        Some(Uuid::new_v4()),
+        Some(accounts[0]),
+        "TRANSFER",
+        "COMPLETED",
+    )
+    .await;
+    let missing_receiver =
+        insert_transaction(&pool, None, Some(Uuid::new_v4()), "DEPOSIT", "COMPLETED").await;
+    let invalid_status =
+        insert_transaction(&pool, None, Some(accounts[1]), "DEPOSIT", "LOST").await;
+    let incomplete_transfer =
+        insert_transaction(&pool, Some(accounts[1]), None, "TRANSFER", "COMPLETED").await;
+    sqlx::query("UPDATE accounts SET balance = -5 WHERE id = $1")
+        .bind(accounts[2])
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let checks_before = CHECKS_WITH_VIOLATIONS.load(Ordering::Relaxed);
+    let report = small_batch_service(pool.clone()).run_check().await.unwrap();
+
+    let found = |kind: ViolationKind, entity_id: Uuid| {
+        report
+            .violations
+            .iter()
+            .any(|v| v.kind == kind && v.entity_id == entity_id)
+    };
+    assert!(found(ViolationKind::MissingAccount, missing_sender));
+    assert!(found(ViolationKind::MissingAccount, missing_receiver));
+    assert!(found(ViolationKind::InvalidStatus, invalid_status));
+    assert!(found(
+        ViolationKind::IncompleteTransfer,
+        incomplete_transfer
+    ));
+    assert!(found(ViolationKind::NegativeBalance, accounts[2]));
+    assert_eq!(report.violation_count, 5, "{:?}", report.violations);
+    assert_eq!(report.transactions_scanned, 7);
+
+    // Found violations are counted for alerting
+    assert!(CHECKS_WITH_VIOLATIONS.load(Ordering::Relaxed) > checks_before);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_latest_integrity_report_endpoint() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    register_user(&user_service, "integrityadmin").await;
+    register_user(&user_service, "integrityuser").await;
+    promote_to_admin(&pool, "integrityadmin").await;
+    let admin_token = login_token(&app, "integrityadmin").await;
+    let user_token = login_token(&app, "integrityuser").await;
+    let uri = "/api/v1/admin/integrity/latest";
+
+    // Regular users may not see reports
+    let (status, _, _) = send_request(&app, Method::GET, uri, Some(&user_token), &[], None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Nothing to show before the first sweep
+    let (status, _, _) = send_request(&app, Method::GET, uri, Some(&admin_token), &[], None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let report = small_batch_service(pool.clone()).run_check().await.unwrap();
+
+    let (status, _, body) =
+        send_request(&app, Method::GET, uri, Some(&admin_token), &[], None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["id"], report.id.to_string());
+    assert_eq!(body["data"]["accounts_scanned"], 2);
+    assert_eq!(body["data"]["violation_count"], 0);
+    assert_eq!(body["data"]["violations"], serde_json::json!([]));
+
+    teardown(&db_url).await;
+}
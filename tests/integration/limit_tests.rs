@@ -0,0 +1,454 @@
+use crate::integration::setup::{
+    create_account_service, create_account_service_with_config, create_app,
+    create_transaction_service, create_user_service, login_token, promote_to_admin, register_user,
+    send_request, setup, teardown,
+};
+use axum::http::{Method, StatusCode};
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::str::FromStr;
+use txn_manager::config::AccountConfig;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::utils::error::AppError;
+use txn_manager::{AccountLimits, DepositRequest, TransferRequest, WithdrawalRequest};
+
+fn amount(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap()
+}
+
+#[tokio::test]
+async fn test_withdrawals_and_transfers_respect_account_limits() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let user = register_user(&user_service, "limited").await;
+    let payee = register_user(&user_service, "limitpayee").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account_id = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(500),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    // Accounts start without caps
+    assert_eq!(
+        account_service.get_limits(account_id).await.unwrap(),
+        AccountLimits::default()
+    );
+    let limits = AccountLimits {
+        per_transaction_limit: Some(Decimal::from(60)),
+        daily_limit: Some(Decimal::from(100)),
+        ..AccountLimits::default()
+    };
+    assert_eq!(
+        account_service
+            .set_limits(account_id, limits.clone())
+            .await
+            .unwrap(),
+        limits
+    );
+
+    let withdraw = |value: &str| WithdrawalRequest {
+        account_id,
+        amount: amount(value),
+        description: None,
+        category: None,
+        destination_id: None,
+        external_reference: None,
+    };
+    let transfer = |value: &str| TransferRequest {
+        sender_account_id: account_id,
+        receiver_account_id: payee_account_id,
+        amount: amount(value),
+        description: None,
+        category: None,
+        allow_duplicate: true,
+    };
+    let context = RequestContext::default();
+
+    let result = transaction_service
+        .process_withdrawal(withdraw("60.01"), &context)
+        .await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(ref msg)) if msg == "Transaction limit exceeded"),
+        "{:?}",
+        result
+    );
+
+    // Just under the daily cap
+    for value in ["40", "30", "29.99"] {
+        transaction_service
+            .process_withdrawal(withdraw(value), &context)
+            .await
+            .unwrap();
+    }
+
+    let result = transaction_service
+        .process_withdrawal(withdraw("0.02"), &context)
+        .await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(ref msg)) if msg == "Daily limit exceeded"),
+        "{:?}",
+        result
+    );
+
+    // Reaching the cap exactly is allowed
+    transaction_service
+        .process_transfer(transfer("0.01"), &context)
+        .await
+        .unwrap();
+
+    // Transfers count towards the same daily limit
+    let result = transaction_service
+        .process_transfer(transfer("0.01"), &context)
+        .await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(ref msg)) if msg == "Daily limit exceeded"),
+        "{:?}",
+        result
+    );
+
+    // Rejected attempts moved no money
+    let account = account_service.get_account_by_id(account_id).await.unwrap();
+    assert_eq!(account.balance, Decimal::from(400));
+
+    // Transactions older than 24 hours no longer count
+    sqlx::query(
+        "UPDATE transactions SET created_at = NOW() - INTERVAL '25 hours' WHERE sender_account_id = $1",
+    )
+    .bind(account_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    transaction_service
+        .process_withdrawal(withdraw("60"), &context)
+        .await
+        .unwrap();
+
+    let result = account_service
+        .set_limits(
+            account_id,
+            AccountLimits {
+                per_transaction_limit: Some(Decimal::ZERO),
+                ..AccountLimits::default()
+            },
+        )
+        .await;
+    assert!(
+        matches!(result, Err(AppError::Validation(_))),
+        "{:?}",
+        result
+    );
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_daily_withdrawal_and_transfer_limits_reset_at_utc_midnight() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let user = register_user(&user_service, "dailycapped").await;
+    let payee = register_user(&user_service, "dailypayee").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account_id = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(500),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    account_service
+        .set_limits(
+            account_id,
+            AccountLimits {
+                daily_withdrawal_limit: Some(Decimal::from(100)),
+                daily_transfer_limit: Some(Decimal::from(50)),
+                ..AccountLimits::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let withdraw = |value: &str| WithdrawalRequest {
+        account_id,
+        amount: amount(value),
+        description: None,
+        category: None,
+        destination_id: None,
+        external_reference: None,
+    };
+    let transfer = |value: &str| TransferRequest {
+        sender_account_id: account_id,
+        receiver_account_id: payee_account_id,
+        amount: amount(value),
+        description: None,
+        category: None,
+        allow_duplicate: false,
+    };
+    let context = RequestContext::default();
+    let move_to = |id, moment: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query(&format!(
+                "UPDATE transactions SET created_at = {} WHERE id = $1",
+                moment
+            ))
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+    };
+    let midnight = "date_trunc('day', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'";
+    let before_midnight =
+        "date_trunc('day', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' - INTERVAL '1 second'";
+
+    // Withdrawn a second before midnight UTC: yesterday's, so it doesn't count
+    let yesterday = transaction_service
+        .process_withdrawal(withdraw("80"), &context)
+        .await
+        .unwrap();
+    move_to(yesterday.id, before_midnight).await;
+
+    // Withdrawn exactly at midnight UTC: today's
+    let today = transaction_service
+        .process_withdrawal(withdraw("70"), &context)
+        .await
+        .unwrap();
+    move_to(today.id, midnight).await;
+
+    // Reaching the cap exactly is allowed
+    transaction_service
+        .process_withdrawal(withdraw("30"), &context)
+        .await
+        .unwrap();
+    let result = transaction_service
+        .process_withdrawal(withdraw("0.01"), &context)
+        .await;
+    assert!(
+        matches!(result, Err(AppError::LimitExceeded(ref msg)) if msg == "Daily withdrawal limit of 100 exceeded"),
+        "{:?}",
+        result
+    );
+
+    // Transfers have their own cap, untouched by today's withdrawals
+    transaction_service
+        .process_transfer(transfer("50"), &context)
+        .await
+        .unwrap();
+    let result = transaction_service
+        .process_transfer(transfer("0.01"), &context)
+        .await;
+    assert!(
+        matches!(result, Err(AppError::LimitExceeded(ref msg)) if msg == "Daily transfer limit of 50 exceeded"),
+        "{:?}",
+        result
+    );
+
+    // Rejected attempts moved no money
+    let account = account_service.get_account_by_id(account_id).await.unwrap();
+    assert_eq!(account.balance, Decimal::from(270));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_new_accounts_get_default_limits() {
+    let (pool, db_url) = setup().await;
+    let user = register_user(&create_user_service(pool.clone()), "defaultlimits").await;
+    let default_limits = AccountLimits {
+        per_transaction_limit: Some(Decimal::from(1000)),
+        daily_limit: Some(Decimal::from(5000)),
+        ..AccountLimits::default()
+    };
+    let account_service = create_account_service_with_config(
+        pool.clone(),
+        AccountConfig {
+            default_limits: default_limits.clone(),
+            ..AccountConfig::default()
+        },
+    );
+
+    let account = account_service
+        .create_account(user.id, "EUR".to_string())
+        .await
+        .unwrap();
+    assert_eq!(
+        account_service.get_limits(account.id).await.unwrap(),
+        default_limits
+    );
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_account_limits_endpoints() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let owner = register_user(&user_service, "limitowner").await;
+    register_user(&user_service, "limitstranger").await;
+    register_user(&user_service, "limitadmin").await;
+    promote_to_admin(&pool, "limitadmin").await;
+    let account_id = create_account_service(pool.clone())
+        .get_accounts_by_user_id(owner.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let owner_token = login_token(&app, "limitowner").await;
+    let stranger_token = login_token(&app, "limitstranger").await;
+    let admin_token = login_token(&app, "limitadmin").await;
+    let limits_uri = format!("/api/v1/accounts/{}/limits", account_id);
+    let admin_uri = format!("/api/v1/admin/accounts/{}/limits", account_id);
+    let body = json!({ "per_transaction_limit": "250.00", "daily_limit": "1000.00" });
+
+    // Only admins change limits
+    let (status, _, _) = send_request(
+        &app,
+        Method::PUT,
+        &admin_uri,
+        Some(&owner_token),
+        &[],
+        Some(body.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, response) = send_request(
+        &app,
+        Method::PUT,
+        &admin_uri,
+        Some(&admin_token),
+        &[],
+        Some(body),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", response);
+
+    let (status, _, response) = send_request(
+        &app,
+        Method::GET,
+        &limits_uri,
+        Some(&owner_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", response);
+    assert_eq!(response["data"]["per_transaction_limit"], "250");
+    assert_eq!(response["data"]["daily_limit"], "1000");
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        &limits_uri,
+        Some(&stranger_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::PUT,
+        &admin_uri,
+        Some(&admin_token),
+        &[],
+        Some(json!({ "per_transaction_limit": "-5", "daily_limit": null })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Administrators can also set them on the account itself
+    let body = json!({
+        "per_transaction_limit": null,
+        "daily_limit": null,
+        "daily_withdrawal_limit": "20",
+        "daily_transfer_limit": null,
+    });
+    let (status, _, _) = send_request(
+        &app,
+        Method::PUT,
+        &limits_uri,
+        Some(&owner_token),
+        &[],
+        Some(body.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, response) = send_request(
+        &app,
+        Method::PUT,
+        &limits_uri,
+        Some(&admin_token),
+        &[],
+        Some(body),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", response);
+    assert_eq!(response["data"]["daily_withdrawal_limit"], "20");
+    assert_eq!(response["data"]["per_transaction_limit"], json!(null));
+
+    create_transaction_service(pool.clone())
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let (status, _, response) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/withdrawal",
+        Some(&owner_token),
+        &[],
+        Some(json!({ "account_id": account_id, "amount": "25" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", response);
+    assert_eq!(response["error"], "LIMIT_EXCEEDED");
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,100 @@
+use crate::integration::setup::{create_user_service, register_user, setup, teardown};
+use chrono::{TimeZone, Utc};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use txn_manager::utils::locale::Locale;
+
+/// Renders a small fixed statement (date and signed amount per line) for a locale
+fn render_fixture_statement(locale: Locale) -> String {
+    let lines = [
+        (
+            Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap(),
+            "1234.56",
+        ),
+        (
+            Utc.with_ymd_and_hms(2024, 3, 15, 18, 0, 0).unwrap(),
+            "-89.10",
+        ),
+        (
+            Utc.with_ymd_and_hms(2024, 12, 31, 23, 59, 59).unwrap(),
+            "1000000.00",
+        ),
+    ];
+
+    lines
+        .iter()
+        .map(|(date, amount)| {
+            format!(
+                "{};{}",
+                locale.format_date(*date),
+                locale.format_amount(Decimal::from_str(amount).unwrap())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_fixture_statement_en_us() {
+    assert_eq!(
+        render_fixture_statement(Locale::EnUs),
+        "03/01/2024;1,234.56\n03/15/2024;-89.10\n12/31/2024;1,000,000.00"
+    );
+}
+
+#[test]
+fn test_fixture_statement_de_de() {
+    assert_eq!(
+        render_fixture_statement(Locale::DeDe),
+        "01.03.2024;1.234,56\n15.03.2024;-89,10\n31.12.2024;1.000.000,00"
+    );
+}
+
+#[test]
+fn test_locale_parsing() {
+    // Tags are matched case-insensitively and canonicalized
+    assert_eq!(Locale::from_str("de-de").unwrap(), Locale::DeDe);
+    assert_eq!(Locale::from_str("EN-us").unwrap().as_str(), "en-US");
+
+    // Anything outside the curated set is rejected
+    assert!(Locale::from_str("de").is_err());
+    assert!(Locale::from_str("xx-YY").is_err());
+    assert!(Locale::from_str("de_DE").is_err());
+}
+
+#[test]
+fn test_format_amount_small_values() {
+    assert_eq!(
+        Locale::DeDe.format_amount(Decimal::from_str("0.5").unwrap()),
+        "0,5"
+    );
+    assert_eq!(Locale::EnUs.format_amount(Decimal::from(999)), "999");
+    assert_eq!(Locale::EnUs.format_amount(Decimal::from(-1000)), "-1,000");
+}
+
+#[tokio::test]
+async fn test_user_locale_preference() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let user = register_user(&user_service, "localeuser").await;
+
+    // New users default to en-US
+    assert_eq!(user.locale, "en-US");
+
+    let updated = user_service
+        .update_user(user.id, None, None, Some(Locale::DeDe))
+        .await
+        .unwrap();
+    assert_eq!(updated.locale, "de-DE");
+    assert_eq!(updated.first_name.as_deref(), Some("Test"));
+
+    // Omitting the locale keeps the stored preference
+    let unchanged = user_service
+        .update_user(user.id, Some("Renamed".to_string()), None, None)
+        .await
+        .unwrap();
+    assert_eq!(unchanged.locale, "de-DE");
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,119 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_user_service, login_token, register_user,
+    send_request, setup, teardown,
+};
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use serde_json::json;
+use tower::ServiceExt;
+use txn_manager::middleware::metrics::CONTENT_TYPE;
+use uuid::Uuid;
+
+/// Scrapes `/metrics` without a token and returns the exposition text
+async fn scrape(app: &Router) -> String {
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/metrics")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], CONTENT_TYPE);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+/// The value of the sample with exactly these name and labels, 0 if absent
+fn sample(metrics: &str, series: &str) -> f64 {
+    metrics
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| {
+            let (name, value) = line.rsplit_once(' ')?;
+            (name == series).then(|| value.parse().unwrap())
+        })
+        .unwrap_or(0.0)
+}
+
+#[tokio::test]
+async fn test_metrics_count_requests_and_transactions() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+
+    let user = register_user(&create_user_service(pool.clone()), "metricsuser").await;
+    let account_id = create_account_service(pool.clone())
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let token = login_token(&app, "metricsuser").await;
+
+    // Other tests share the process-wide registry, so only increases are checked
+    let before = scrape(&app).await;
+    let account_ok =
+        r#"http_requests_total{method="GET",route="/api/v1/accounts/:id",status="2xx"}"#;
+    let account_missing =
+        r#"http_requests_total{method="GET",route="/api/v1/accounts/:id",status="4xx"}"#;
+    let account_latency =
+        r#"http_request_duration_seconds_count{method="GET",route="/api/v1/accounts/:id"}"#;
+    let account_all = r#"http_request_duration_seconds_bucket{method="GET",route="/api/v1/accounts/:id",le="+Inf"}"#;
+    let deposits = r#"transactions_total{type="DEPOSIT",status="COMPLETED"}"#;
+
+    for _ in 0..2 {
+        let (status, _, _) = send_request(
+            &app,
+            Method::GET,
+            &format!("/api/v1/accounts/{}", account_id),
+            Some(&token),
+            &[],
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/accounts/{}", Uuid::new_v4()),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/deposit",
+        Some(&token),
+        &[],
+        Some(json!({ "account_id": account_id, "amount": "10" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let after = scrape(&app).await;
+    assert!(
+        after.contains("# TYPE http_requests_total counter"),
+        "{}",
+        after
+    );
+    assert!(
+        after.contains("# TYPE http_request_duration_seconds histogram"),
+        "{}",
+        after
+    );
+    assert!(sample(&after, account_ok) >= sample(&before, account_ok) + 2.0);
+    assert!(sample(&after, account_missing) >= sample(&before, account_missing) + 1.0);
+    assert!(sample(&after, account_latency) >= sample(&before, account_latency) + 3.0);
+    assert_eq!(sample(&after, account_all), sample(&after, account_latency));
+    assert!(sample(&after, deposits) >= sample(&before, deposits) + 1.0);
+
+    // Routes are labelled by pattern, never by the ids in the path
+    assert!(!after.contains(&account_id.to_string()), "{}", after);
+
+    teardown(&db_url).await;
+}
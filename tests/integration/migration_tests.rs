@@ -0,0 +1,34 @@
+use crate::integration::setup::{setup_unmigrated, teardown};
+use txn_manager::{migration_status, run_migrations};
+
+#[tokio::test]
+async fn test_run_migrations_on_fresh_database() {
+    let (pool, db_url) = setup_unmigrated().await;
+
+    // Nothing has been applied to a brand new database
+    let status = migration_status(&pool).await.unwrap();
+    assert!(status.applied.is_empty());
+    assert!(status.dirty.is_empty());
+    assert!(!status.pending.is_empty());
+    let expected_versions: Vec<i64> = status.pending.iter().map(|m| m.version).collect();
+
+    // The first run applies every pending migration in order
+    let report = run_migrations(&pool).await.unwrap();
+    let applied_versions: Vec<i64> = report.applied.iter().map(|m| m.version).collect();
+    assert_eq!(applied_versions, expected_versions);
+    assert_eq!(report.applied[0].description, "initial schema");
+
+    let status = migration_status(&pool).await.unwrap();
+    assert!(status.pending.is_empty());
+    assert_eq!(status.applied.len(), expected_versions.len());
+
+    // A second run is a no-op
+    let report = run_migrations(&pool).await.unwrap();
+    assert!(report.applied.is_empty());
+
+    // Reports serialize for reuse by health and diagnostics output
+    let json = serde_json::to_value(&status).unwrap();
+    assert!(json["pending"].as_array().unwrap().is_empty());
+
+    teardown(&db_url).await;
+}
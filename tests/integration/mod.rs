@@ -1,4 +1,37 @@
+pub mod account_number_tests;
 pub mod account_tests;
+pub mod budget_tests;
+pub mod config_tests;
+pub mod contract_tests;
+pub mod cors_tests;
+pub mod destination_tests;
+pub mod diagnostics_tests;
+pub mod event_stream_tests;
+pub mod exchange_rate_tests;
+pub mod fee_tests;
+pub mod health_tests;
+pub mod hold_tests;
+pub mod idempotency_tests;
+pub mod integrity_tests;
+pub mod limit_tests;
+pub mod locale_tests;
+pub mod metrics_tests;
+pub mod migration_tests;
+pub mod money_tests;
+pub mod notification_tests;
+pub mod outbox_tests;
+pub mod request_context_tests;
+pub mod request_log_tests;
+pub mod response_tests;
+#[cfg(feature = "sandbox")]
+pub mod sandbox_tests;
+pub mod scheduled_transfer_tests;
 pub mod setup;
+pub mod standing_order_tests;
+pub mod timestamp_tests;
+pub mod transaction_export_tests;
+pub mod transaction_import_tests;
 pub mod transaction_tests;
+pub mod usage_tests;
 pub mod user_tests;
+pub mod webhook_tests;
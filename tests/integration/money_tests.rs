@@ -0,0 +1,185 @@
+use proptest::prelude::*;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::str::FromStr;
+use txn_manager::utils::currency::{
+    from_minor_units, is_valid_iso4217, normalize_currency, validate_currency, AmountUnits,
+};
+use txn_manager::utils::money::{convert, percentage_of, prorate, round_to};
+
+/// Source files on the money path, which must never mention a float type
+const MONEY_PATH: [&str; 5] = [
+    "src/utils/money.rs",
+    "src/utils/currency.rs",
+    "src/services/transaction_service.rs",
+    "src/models/transaction.rs",
+    "src/models/account.rs",
+];
+
+fn dec(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap()
+}
+
+/// Integer division rounding half to even, the fixed-point reference for `MidpointNearestEven`
+fn div_half_even(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator.div_euclid(denominator);
+    let remainder = numerator.rem_euclid(denominator) * 2;
+    if remainder > denominator || (remainder == denominator && quotient % 2 != 0) {
+        quotient + 1
+    } else {
+        quotient
+    }
+}
+
+/// Integer division rounding half away from zero, for non-negative operands
+fn div_half_up(numerator: i128, denominator: i128) -> i128 {
+    (numerator * 2 + denominator) / (denominator * 2)
+}
+
+#[test]
+fn test_money_path_has_no_floating_point() {
+    for path in MONEY_PATH {
+        let source =
+            std::fs::read_to_string(format!("{}/{}", env!("CARGO_MANIFEST_DIR"), path)).unwrap();
+        let floats: Vec<_> = source
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .filter(|token| *token == "f32" || *token == "f64")
+            .collect();
+        assert!(floats.is_empty(), "{} uses {:?}", path, floats);
+    }
+}
+
+#[test]
+fn test_money_known_values() {
+    let even = RoundingStrategy::MidpointNearestEven;
+    let away = RoundingStrategy::MidpointAwayFromZero;
+
+    assert_eq!(
+        percentage_of(dec("200.00"), dec("1.5"), 2, even).unwrap(),
+        dec("3.00")
+    );
+    assert_eq!(
+        percentage_of(dec("12.50"), dec("1"), 2, even).unwrap(),
+        dec("0.12")
+    );
+    assert_eq!(
+        percentage_of(dec("12.50"), dec("1"), 2, away).unwrap(),
+        dec("0.13")
+    );
+    assert_eq!(
+        prorate(dec("30.00"), dec("12"), dec("31"), 2, even).unwrap(),
+        dec("11.61")
+    );
+    assert_eq!(
+        convert(dec("100"), dec("0.857"), 2, even).unwrap(),
+        dec("85.70")
+    );
+    assert_eq!(
+        convert(dec("1000"), dec("151.235"), 0, even).unwrap(),
+        dec("151235")
+    );
+    assert_eq!(round_to(dec("10.1"), 2, even).to_string(), "10.10");
+
+    // Zero periods and overflow are errors rather than panics
+    assert!(prorate(dec("10"), dec("1"), Decimal::ZERO, 2, even).is_err());
+    assert!(convert(Decimal::MAX, dec("2"), 2, even).is_err());
+    assert!(percentage_of(Decimal::MAX, dec("150"), 2, even).is_err());
+}
+
+#[test]
+fn test_minor_units_use_currency_scale() {
+    assert_eq!(
+        from_minor_units(dec("1050"), "USD").unwrap().to_string(),
+        "10.50"
+    );
+    assert_eq!(
+        from_minor_units(dec("1050"), "JPY").unwrap().to_string(),
+        "1050"
+    );
+    assert_eq!(
+        from_minor_units(dec("1050"), "KWD").unwrap().to_string(),
+        "1.050"
+    );
+    assert_eq!(
+        from_minor_units(dec("1050.00"), "USD").unwrap(),
+        dec("10.50")
+    );
+    assert!(from_minor_units(dec("1050.5"), "USD").is_err());
+
+    // Major units are the default and pass through untouched
+    assert_eq!(AmountUnits::default(), AmountUnits::Major);
+    assert_eq!(AmountUnits::parse("Minor").unwrap(), AmountUnits::Minor);
+    assert!(AmountUnits::parse("cents").is_err());
+    assert_eq!(
+        AmountUnits::Major.to_major(dec("1050"), "USD").unwrap(),
+        dec("1050")
+    );
+}
+
+#[test]
+fn test_currency_codes_are_iso4217() {
+    for code in ["USD", "EUR", "JPY", "KWD", "CHF", "XAU", "XOF"] {
+        assert!(is_valid_iso4217(code), "{}", code);
+        assert!(validate_currency(code).is_ok(), "{}", code);
+    }
+
+    // Three characters alone don't make a currency
+    for code in ["XXX", "XTS", "ABC", "123", "US", "USDD", "", "US D"] {
+        assert!(!is_valid_iso4217(code), "{}", code);
+        assert!(validate_currency(code).is_err(), "{}", code);
+    }
+
+    // Lowercase codes are accepted in requests but stored in uppercase
+    assert!(!is_valid_iso4217("usd"));
+    assert!(validate_currency("usd").is_ok());
+    assert!(validate_currency("xxx").is_err());
+    assert_eq!(normalize_currency("usd"), "USD");
+    assert_eq!(normalize_currency(" Eur "), "EUR");
+}
+
+proptest! {
+    #[test]
+    fn prop_percentage_matches_fixed_point(cents in 0i64..1_000_000_000_000, basis_points in 0i64..100_000) {
+        let amount = Decimal::new(cents, 2);
+        let percent = Decimal::new(basis_points, 2);
+        let fee = percentage_of(amount, percent, 2, RoundingStrategy::MidpointNearestEven).unwrap();
+
+        // cents * bp / 10_000 is the fee in cents
+        let expected = div_half_even(i128::from(cents) * i128::from(basis_points), 10_000);
+        prop_assert_eq!(fee, Decimal::from_i128_with_scale(expected, 2));
+    }
+
+    #[test]
+    fn prop_prorate_matches_fixed_point(cents in 0i64..1_000_000_000_000, part in 0i64..366, whole in 1i64..366) {
+        let amount = Decimal::new(cents, 2);
+        let share = prorate(
+            amount,
+            Decimal::from(part),
+            Decimal::from(whole),
+            2,
+            RoundingStrategy::ToZero,
+        )
+        .unwrap();
+
+        let expected = i128::from(cents) * i128::from(part) / i128::from(whole);
+        prop_assert_eq!(share, Decimal::from_i128_with_scale(expected, 2));
+
+        // The whole period is the whole amount
+        let all = prorate(amount, Decimal::from(whole), Decimal::from(whole), 2, RoundingStrategy::ToZero).unwrap();
+        prop_assert_eq!(all, amount);
+    }
+
+    #[test]
+    fn prop_convert_matches_fixed_point(cents in 0i64..1_000_000_000_000, rate_micros in 1i64..1_000_000_000) {
+        let converted = convert(
+            Decimal::new(cents, 2),
+            Decimal::new(rate_micros, 6),
+            2,
+            RoundingStrategy::MidpointAwayFromZero,
+        )
+        .unwrap();
+
+        let expected = div_half_up(i128::from(cents) * i128::from(rate_micros), 1_000_000);
+        prop_assert_eq!(converted, Decimal::from_i128_with_scale(expected, 2));
+        prop_assert_eq!(converted.scale(), 2);
+    }
+}
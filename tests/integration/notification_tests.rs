@@ -0,0 +1,233 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_user_service, login_token, register_user,
+    send_request, setup, teardown,
+};
+use async_trait::async_trait;
+use axum::http::{Method, StatusCode};
+use rust_decimal::Decimal;
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::utils::email::{EmailMessage, EmailSender};
+use txn_manager::{
+    AccountConfig, AccountService, DepositRequest, NotificationConfig, NotificationService,
+    TransactionConfig, TransactionService, TransferRequest,
+};
+use uuid::Uuid;
+
+/// Email sender that keeps messages in memory, optionally refusing some recipients
+#[derive(Default)]
+struct CapturingSender {
+    sent: Mutex<Vec<EmailMessage>>,
+    failing_recipient: Option<String>,
+}
+
+#[async_trait]
+impl EmailSender for CapturingSender {
+    async fn send(&self, message: &EmailMessage) -> Result<(), String> {
+        if self.failing_recipient.as_deref() == Some(message.to.as_str()) {
+            return Err("Mailbox unavailable".to_string());
+        }
+        self.sent.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
+
+/// A transaction service that sends receipts for transfers above 100
+fn service_with_receipts(pool: PgPool, sender: Arc<CapturingSender>) -> TransactionService {
+    let notifications = NotificationService::new(
+        pool.clone(),
+        sender,
+        NotificationConfig {
+            transfer_receipt_threshold: Some(Decimal::from(100)),
+        },
+    );
+    TransactionService::new(
+        pool.clone(),
+        AccountService::new(pool, AccountConfig::default()),
+        TransactionConfig::default(),
+    )
+    .with_notifications(Arc::new(notifications))
+}
+
+/// Registers a sender and a receiver, funding the sender's default account
+async fn funded_pair(pool: &PgPool, service: &TransactionService) -> (Uuid, Uuid) {
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let sender = register_user(&user_service, "receiptsender").await;
+    let receiver = register_user(&user_service, "receiptreceiver").await;
+    let sender_account = account_service
+        .get_accounts_by_user_id(sender.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let receiver_account = account_service
+        .get_accounts_by_user_id(receiver.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    service
+        .process_deposit(DepositRequest {
+            account_id: sender_account,
+            amount: Decimal::from(1000),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    (sender_account, receiver_account)
+}
+
+async fn transfer(service: &TransactionService, from: Uuid, to: Uuid, amount: i64) -> Uuid {
+    service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: from,
+                receiver_account_id: to,
+                amount: Decimal::from(amount),
+                description: Some("Rent".to_string()),
+                category: None,
+                allow_duplicate: true,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap()
+        .id
+}
+
+/// Waits for the background task to record `expected` deliveries for a transaction
+async fn wait_for_deliveries(
+    pool: &PgPool,
+    transaction_id: Uuid,
+    expected: usize,
+) -> Vec<(String, String)> {
+    for _ in 0..50 {
+        let deliveries: Vec<(String, String)> = sqlx::query_as(
+            "SELECT kind, status FROM email_deliveries WHERE transaction_id = $1 ORDER BY kind",
+        )
+        .bind(transaction_id)
+        .fetch_all(pool)
+        .await
+        .unwrap();
+        if deliveries.len() >= expected {
+            return deliveries;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!(
+        "Expected {} email deliveries for {}",
+        expected, transaction_id
+    );
+}
+
+async fn delivery_count(pool: &PgPool) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM email_deliveries")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_transfer_receipts_sent_above_threshold() {
+    let (pool, db_url) = setup().await;
+    let sender = Arc::new(CapturingSender::default());
+    let service = service_with_receipts(pool.clone(), sender.clone());
+    let (from, to) = funded_pair(&pool, &service).await;
+
+    // At the threshold nothing is sent
+    transfer(&service, from, to, 100).await;
+    let large = transfer(&service, from, to, 250).await;
+
+    let deliveries = wait_for_deliveries(&pool, large, 2).await;
+    assert_eq!(
+        deliveries,
+        vec![
+            ("TRANSFER_RECEIVED".to_string(), "SENT".to_string()),
+            ("TRANSFER_SENT".to_string(), "SENT".to_string()),
+        ]
+    );
+    assert_eq!(delivery_count(&pool).await, 2);
+
+    let sent = sender.sent.lock().unwrap().clone();
+    let to_sender = sent
+        .iter()
+        .find(|m| m.to == "receiptsender@example.com")
+        .unwrap();
+    assert!(to_sender.subject.contains("you sent 250"));
+    assert!(to_sender.body.contains(&large.to_string()));
+    assert!(to_sender.body.contains("Rent"));
+    let to_receiver = sent
+        .iter()
+        .find(|m| m.to == "receiptreceiver@example.com")
+        .unwrap();
+    assert!(to_receiver.subject.contains("You received 250"));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_transfer_receipts_respect_preferences_and_failures() {
+    let (pool, db_url) = setup().await;
+    let sender = Arc::new(CapturingSender {
+        sent: Mutex::default(),
+        failing_recipient: Some("receiptsender@example.com".to_string()),
+    });
+    let service = service_with_receipts(pool.clone(), sender.clone());
+    let (from, to) = funded_pair(&pool, &service).await;
+
+    // A failed delivery is recorded, and neither the transfer nor the other email suffers
+    let first = transfer(&service, from, to, 300).await;
+    let deliveries = wait_for_deliveries(&pool, first, 2).await;
+    assert!(deliveries.contains(&("TRANSFER_SENT".to_string(), "FAILED".to_string())));
+    assert!(deliveries.contains(&("TRANSFER_RECEIVED".to_string(), "SENT".to_string())));
+    let error: Option<String> = sqlx::query_scalar(
+        "SELECT error FROM email_deliveries WHERE transaction_id = $1 AND status = 'FAILED'",
+    )
+    .bind(first)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(error.as_deref(), Some("Mailbox unavailable"));
+
+    // Receivers who opted out are skipped
+    let app = create_app(pool.clone(), &db_url);
+    let token = login_token(&app, "receiptreceiver").await;
+    let uri = "/api/v1/users/me/notification-preferences";
+    let (status, _, body) = send_request(&app, Method::GET, uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["transfer_receipts"], true);
+    let (status, _, body) = send_request(
+        &app,
+        Method::PUT,
+        uri,
+        Some(&token),
+        &[],
+        Some(json!({ "transfer_receipts": false })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["transfer_receipts"], false);
+
+    let second = transfer(&service, from, to, 300).await;
+    let deliveries = wait_for_deliveries(&pool, second, 1).await;
+    assert_eq!(
+        deliveries,
+        vec![("TRANSFER_SENT".to_string(), "FAILED".to_string())]
+    );
+    assert!(sender
+        .sent
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|m| m.to != "receiptreceiver@example.com" || !m.body.contains(&second.to_string())));
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,178 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, promote_to_admin, register_user, send_request, setup, teardown,
+};
+use async_trait::async_trait;
+use axum::http::{Method, StatusCode};
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::models::outbox::{ACCOUNT_CREATED, TRANSACTION_COMPLETED, USER_REGISTERED};
+use txn_manager::utils::events::EventSink;
+use txn_manager::{DepositRequest, OutboxEvent, OutboxService, WithdrawalRequest};
+
+/// Event sink keeping what it is given, refusing everything while `failing` is set
+#[derive(Default)]
+struct RecordingSink {
+    events: Mutex<Vec<OutboxEvent>>,
+    failing: AtomicBool,
+}
+
+impl RecordingSink {
+    fn event_types(&self) -> Vec<String> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| event.event_type.clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl EventSink for RecordingSink {
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), String> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err("sink unavailable".to_string());
+        }
+        self.events.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_committed_changes_are_published_once() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let sink = Arc::new(RecordingSink::default());
+    let outbox_service = OutboxService::new(pool.clone()).with_sink(sink.clone());
+
+    let user = register_user(&user_service, "outboxuser").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let deposit = transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    // A rolled back withdrawal leaves no event behind
+    let overdraft = transaction_service
+        .process_withdrawal(
+            WithdrawalRequest {
+                account_id: account,
+                amount: Decimal::from(500),
+                description: None,
+                category: None,
+                destination_id: None,
+                external_reference: None,
+            },
+            &RequestContext::default(),
+        )
+        .await;
+    assert!(overdraft.is_err());
+
+    assert_eq!(outbox_service.publish_pending().await.unwrap(), 3);
+    assert_eq!(
+        sink.event_types(),
+        [USER_REGISTERED, ACCOUNT_CREATED, TRANSACTION_COMPLETED]
+    );
+    let events = sink.events.lock().unwrap().clone();
+    assert_eq!(events[0].aggregate_id, user.id);
+    assert_eq!(events[0].payload["user_id"], json!(user.id));
+    assert!(events[0].payload.get("email").is_none());
+    assert_eq!(events[1].aggregate_id, account);
+    assert_eq!(events[1].payload["currency"], "USD");
+    assert_eq!(events[2].aggregate_id, deposit.id);
+    assert_eq!(events[2].payload["event"], TRANSACTION_COMPLETED);
+    assert_eq!(events[2].payload["amount"], "100.00");
+
+    // Published events are not handed over again
+    assert_eq!(outbox_service.publish_pending().await.unwrap(), 0);
+    assert_eq!(sink.event_types().len(), 3);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_refused_events_are_retried_and_replayed_on_request() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let sink = Arc::new(RecordingSink::default());
+    let outbox_service = OutboxService::new(pool.clone()).with_sink(sink.clone());
+    let started = Utc::now() - Duration::minutes(1);
+
+    register_user(&user_service, "outboxadmin").await;
+    promote_to_admin(&pool, "outboxadmin").await;
+    let admin_token = login_token(&app, "outboxadmin").await;
+
+    // Events the sink refuses stay in the outbox until it takes them
+    sink.failing.store(true, Ordering::SeqCst);
+    assert_eq!(outbox_service.publish_pending().await.unwrap(), 0);
+    sink.failing.store(false, Ordering::SeqCst);
+    assert_eq!(outbox_service.publish_pending().await.unwrap(), 2);
+
+    register_user(&user_service, "outboxmember").await;
+    let member_token = login_token(&app, "outboxmember").await;
+    let replay = |token: String, body: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            send_request(
+                &app,
+                Method::POST,
+                "/api/v1/admin/events/replay",
+                Some(token.as_str()),
+                &[],
+                Some(body),
+            )
+            .await
+        }
+    };
+    let range = json!({ "from": started, "to": Utc::now() + Duration::minutes(1) });
+
+    // Only administrators can replay, over a non-empty range
+    let (status, _, _) = replay(member_token, range.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, body) = replay(
+        admin_token.clone(),
+        json!({ "from": Utc::now(), "to": started }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    // Only the published events are queued again; the member's are still waiting
+    let (status, _, body) = replay(admin_token, range).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["replayed"], 2);
+
+    assert_eq!(outbox_service.publish_pending().await.unwrap(), 4);
+    assert_eq!(
+        sink.event_types(),
+        [
+            USER_REGISTERED,
+            ACCOUNT_CREATED,
+            USER_REGISTERED,
+            ACCOUNT_CREATED,
+            USER_REGISTERED,
+            ACCOUNT_CREATED
+        ]
+    );
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,76 @@
+use axum::extract::FromRequestParts;
+use axum::http::Request;
+use std::net::IpAddr;
+use txn_manager::middleware::request_context::{IpCidr, RequestContext, TrustedProxies};
+
+fn ip(value: &str) -> IpAddr {
+    value.parse().unwrap()
+}
+
+#[test]
+fn test_cidr_parsing_and_matching() {
+    let range: IpCidr = "10.0.0.0/8".parse().unwrap();
+    assert!(range.contains(ip("10.1.2.3")));
+    assert!(!range.contains(ip("11.0.0.1")));
+    // IPv4-mapped IPv6 peers (dual-stack listeners) match IPv4 ranges
+    assert!(range.contains(ip("::ffff:10.1.2.3")));
+
+    let host: IpCidr = "192.168.1.5".parse().unwrap();
+    assert!(host.contains(ip("192.168.1.5")));
+    assert!(!host.contains(ip("192.168.1.6")));
+
+    let v6: IpCidr = "fd00::/8".parse().unwrap();
+    assert!(v6.contains(ip("fd12::1")));
+    assert!(!v6.contains(ip("10.0.0.1")));
+
+    assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+    assert!("not-an-ip/8".parse::<IpCidr>().is_err());
+    assert!("10.0.0.0/8, bogus".parse::<TrustedProxies>().is_err());
+}
+
+#[test]
+fn test_forwarded_for_ignored_from_untrusted_peer() {
+    let proxies: TrustedProxies = "10.0.0.0/8".parse().unwrap();
+
+    // A direct client can't spoof its address with X-Forwarded-For
+    let client = proxies.resolve_client_ip(ip("203.0.113.7"), Some("198.51.100.1"));
+    assert_eq!(client, ip("203.0.113.7"));
+
+    // With no proxies configured the header is never trusted
+    let client = TrustedProxies::default().resolve_client_ip(ip("10.0.0.1"), Some("198.51.100.1"));
+    assert_eq!(client, ip("10.0.0.1"));
+}
+
+#[test]
+fn test_forwarded_for_honored_from_trusted_proxy() {
+    let proxies: TrustedProxies = "10.0.0.0/8, 172.16.0.0/12".parse().unwrap();
+
+    let client = proxies.resolve_client_ip(ip("10.0.0.1"), Some("198.51.100.1"));
+    assert_eq!(client, ip("198.51.100.1"));
+
+    // Trusted hops are skipped; entries left of the first untrusted hop are client-controlled
+    let client =
+        proxies.resolve_client_ip(ip("10.0.0.1"), Some("1.1.1.1, 198.51.100.1, 172.16.0.9"));
+    assert_eq!(client, ip("198.51.100.1"));
+
+    // Without the header the proxy itself is the best we know
+    let client = proxies.resolve_client_ip(ip("10.0.0.1"), None);
+    assert_eq!(client, ip("10.0.0.1"));
+}
+
+#[tokio::test]
+async fn test_request_context_without_connection_info() {
+    // Without a peer address the forwarding header has nothing to vouch for it
+    let request = Request::builder()
+        .header("X-Forwarded-For", "198.51.100.1")
+        .header("User-Agent", "context-test/1.0")
+        .body(())
+        .unwrap();
+    let (mut parts, _) = request.into_parts();
+
+    let context = RequestContext::from_request_parts(&mut parts, &())
+        .await
+        .unwrap();
+    assert_eq!(context.ip_address, None);
+    assert_eq!(context.user_agent.as_deref(), Some("context-test/1.0"));
+}
@@ -0,0 +1,123 @@
+use crate::integration::setup::{create_app, login_token, send_request, setup, teardown};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use serde_json::json;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::fmt::MakeWriter;
+use txn_manager::middleware::request_log::{Redactor, REDACTED};
+
+/// Collects everything the subscriber writes so a test can inspect it
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedLogs {
+    fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for CapturedLogs {
+    type Writer = CapturedLogs;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[test]
+fn test_redactor_hides_sensitive_values() {
+    let redactor = Redactor::new(&["password", "token", "api_key", "authorization"]);
+
+    let mut body = json!({
+        "username": "alice",
+        "password": "hunter22",
+        "profile": { "new_password": "hunter23", "tags": [{ "refresh_token": "abc" }] }
+    });
+    redactor.redact_json(&mut body);
+    assert_eq!(body["username"], "alice");
+    assert_eq!(body["password"], REDACTED);
+    assert_eq!(body["profile"]["new_password"], REDACTED);
+    assert_eq!(body["profile"]["tags"][0]["refresh_token"], REDACTED);
+
+    let mut headers = HeaderMap::new();
+    headers.insert("authorization", HeaderValue::from_static("Bearer abc.def"));
+    headers.insert("x-api-key", HeaderValue::from_static("k-123"));
+    headers.insert("user-agent", HeaderValue::from_static("curl/8.0"));
+    let logged = redactor.redact_headers(&headers);
+    assert!(!logged.contains("abc.def"));
+    assert!(!logged.contains("k-123"));
+    assert!(logged.contains("curl/8.0"));
+
+    let uri: Uri = "/api/v1/things?page=2&Token=xyz".parse().unwrap();
+    assert_eq!(
+        redactor.redact_uri(&uri),
+        format!("/api/v1/things?page=2&Token={}", REDACTED)
+    );
+
+    // Bodies that can't be parsed are never logged verbatim
+    assert_eq!(redactor.redact_body(b"password=hunter22"), "<17 bytes>");
+}
+
+#[tokio::test]
+async fn test_login_is_logged_without_password() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+
+    let logs = CapturedLogs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_writer(logs.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/register",
+        None,
+        &[],
+        Some(json!({
+            "username": "loggeduser",
+            "email": "loggeduser@example.com",
+            "password": "securepassword",
+            "first_name": "Logged"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let token = login_token(&app, "loggeduser").await;
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let logs = logs.contents();
+    assert!(logs.contains("HTTP request"), "{}", logs);
+    assert!(logs.contains("/api/v1/users/login"));
+    assert!(logs.contains("loggeduser@example.com"));
+    assert!(logs.contains(REDACTED));
+    assert!(!logs.contains("securepassword"), "{}", logs);
+    // Neither the issued token nor the Authorization header carrying it
+    assert!(!logs.contains(&token), "{}", logs);
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,153 @@
+use crate::integration::setup::{
+    create_app, create_user_service, login_token, register_user, send_request, setup, teardown,
+};
+use axum::http::{Method, StatusCode};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use std::collections::BTreeSet;
+use txn_manager::utils::response::{select_fields, SelectableFields, Sparse};
+use txn_manager::{AccountResponse, TransactionResponse};
+use uuid::Uuid;
+
+fn sample_transaction() -> TransactionResponse {
+    TransactionResponse {
+        id: Uuid::new_v4(),
+        sender_account_id: Some(Uuid::new_v4()),
+        receiver_account_id: Some(Uuid::new_v4()),
+        amount: Decimal::from(25),
+        currency: "USD".to_string(),
+        transaction_type: "TRANSFER".to_string(),
+        status: "COMPLETED".to_string(),
+        description: Some("Lunch".to_string()),
+        created_at: Utc::now(),
+        balance_after: None,
+        fee_amount: None,
+        fee_currency: None,
+        original_amount: None,
+        original_currency: None,
+        exchange_rate: None,
+        external_reference: None,
+        category: None,
+    }
+}
+
+fn sample_account() -> AccountResponse {
+    AccountResponse {
+        id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        account_number: Some("4829103755".to_string()),
+        balance: Decimal::from(10),
+        currency: "USD".to_string(),
+        balance_scale: 2,
+        status: "ACTIVE".to_string(),
+        closed_at: None,
+        created_at: Utc::now(),
+        balance_as_of: None,
+    }
+}
+
+/// Asserts that a type's field allowlist matches the keys it actually serializes
+fn assert_fields_match<T: serde::Serialize + SelectableFields>(item: &T) {
+    let value = serde_json::to_value(item).unwrap();
+    let serialized: BTreeSet<&str> = value
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    let allowed: BTreeSet<&str> = T::FIELDS.iter().copied().collect();
+    assert_eq!(serialized, allowed);
+}
+
+#[test]
+fn test_selectable_fields_match_response_structs() {
+    assert_fields_match(&sample_transaction());
+    assert_fields_match(&sample_account());
+}
+
+#[test]
+fn test_select_fields_subset() {
+    let items = select_fields(vec![sample_transaction()], Some("id, amount,created_at")).unwrap();
+    let json = serde_json::to_value(&items).unwrap();
+    let keys: BTreeSet<&str> = json[0]
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    assert_eq!(keys, BTreeSet::from(["id", "amount", "created_at"]));
+}
+
+#[test]
+fn test_select_fields_unknown_field_is_rejected() {
+    let result = select_fields(vec![sample_transaction()], Some("id,balance"));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_select_fields_without_selection_keeps_full_shape() {
+    let transaction = sample_transaction();
+    let expected = serde_json::to_string(&transaction).unwrap();
+
+    for fields in [None, Some(""), Some(" , ")] {
+        let items = select_fields(vec![sample_transaction()], fields).unwrap();
+        assert!(matches!(items[0], Sparse::Full(_)));
+    }
+
+    let items = select_fields(vec![transaction], None).unwrap();
+    assert_eq!(serde_json::to_string(&items[0]).unwrap(), expected);
+}
+
+#[tokio::test]
+async fn test_account_list_field_selection() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let app = create_app(pool.clone(), &db_url);
+    register_user(&user_service, "fieldsuser").await;
+    let token = login_token(&app, "fieldsuser").await;
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/accounts?fields=id,balance",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "success");
+    let account = body["data"]["items"][0].as_object().unwrap();
+    assert_eq!(account.len(), 2);
+    assert!(account.contains_key("id") && account.contains_key("balance"));
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/accounts?fields=id,password_hash",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().contains("password_hash"));
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/accounts",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["data"]["items"][0].as_object().unwrap().len(),
+        AccountResponse::FIELDS.len()
+    );
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,279 @@
+use crate::integration::setup::{
+    create_account_service, create_transaction_service, create_user_service, login_token,
+    promote_to_admin, register_user, send_request, setup, teardown, test_config,
+};
+use axum::http::{Method, StatusCode};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use txn_manager::api::create_router;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::{DepositRequest, SandboxConfig, TransferRequest};
+
+fn amount(value: &Value) -> Decimal {
+    Decimal::from_str(value.as_str().unwrap()).unwrap()
+}
+
+#[test]
+fn test_sandbox_config_refuses_production() {
+    let enabled = SandboxConfig {
+        faucet_enabled: true,
+        ..SandboxConfig::default()
+    };
+    assert!(enabled.check_environment("production").is_err());
+    assert!(enabled.check_environment(" Production ").is_err());
+    assert!(enabled.check_environment("staging").is_ok());
+
+    // A disabled faucet is fine anywhere
+    assert!(SandboxConfig::default()
+        .check_environment("production")
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_sandbox_routes_absent_in_production() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+
+    let user = register_user(&user_service, "prodfaucet").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    promote_to_admin(&pool, "prodfaucet").await;
+
+    let mut production = test_config(&db_url);
+    production.app_env = "production".to_string();
+    let mut disabled = test_config(&db_url);
+    disabled.sandbox.faucet_enabled = false;
+
+    for config in [production, disabled] {
+        let app = create_router(&config, pool.clone());
+        let token = login_token(&app, "prodfaucet").await;
+
+        let (status, _, _) = send_request(
+            &app,
+            Method::POST,
+            "/api/v1/sandbox/faucet",
+            Some(&token),
+            &[],
+            Some(json!({ "account_id": account, "amount": "100" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _, _) = send_request(
+            &app,
+            Method::DELETE,
+            "/api/v1/admin/sandbox/transactions",
+            Some(&token),
+            &[],
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    let balance = account_service.get_account_by_id(account).await.unwrap();
+    assert_eq!(balance.balance, Decimal::ZERO);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_sandbox_faucet_and_cleanup_round_trip() {
+    let (pool, db_url) = setup().await;
+    let mut config = test_config(&db_url);
+    config.sandbox.faucet_hourly_limit = 2;
+    let app = create_router(&config, pool.clone());
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "faucetuser").await;
+    let other = register_user(&user_service, "faucetother").await;
+    register_user(&user_service, "faucetadmin").await;
+    promote_to_admin(&pool, "faucetadmin").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let other_account = account_service
+        .get_accounts_by_user_id(other.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    // Real funds, which the cleanup must leave alone
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account,
+            amount: Decimal::from(50),
+            currency: None,
+            exchange_rate: None,
+            description: Some("Payroll".to_string()),
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    let token = login_token(&app, "faucetuser").await;
+    let admin_token = login_token(&app, "faucetadmin").await;
+    let faucet =
+        |account_id, amount: &str| Some(json!({ "account_id": account_id, "amount": amount }));
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/sandbox/faucet",
+        Some(&token),
+        &[],
+        faucet(account, "100"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["transaction_type"], "DEPOSIT");
+    assert_eq!(body["data"]["description"], "[SANDBOX] Faucet deposit");
+
+    // Over the cap, into someone else's account, and past the hourly limit
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/sandbox/faucet",
+        Some(&token),
+        &[],
+        faucet(account, "1000.01"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/sandbox/faucet",
+        Some(&token),
+        &[],
+        faucet(other_account, "10"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/sandbox/faucet",
+        Some(&token),
+        &[],
+        faucet(account, "25"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/sandbox/faucet",
+        Some(&token),
+        &[],
+        faucet(account, "25"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["error"], "TOO_MANY_REQUESTS");
+
+    // Faucet deposits stand out in the account's listing
+    let listing_uri = format!("/api/v1/transactions/account/{}", account);
+    let (_, _, body) = send_request(&app, Method::GET, &listing_uri, Some(&token), &[], None).await;
+    let descriptions: Vec<&str> = body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|transaction| transaction["description"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        descriptions
+            .iter()
+            .filter(|d| d.starts_with("[SANDBOX]"))
+            .count(),
+        2
+    );
+    let balance = account_service.get_account_by_id(account).await.unwrap();
+    assert_eq!(balance.balance, Decimal::from(175));
+
+    // Only administrators may purge
+    let purge_uri = "/api/v1/admin/sandbox/transactions";
+    let (status, _, _) =
+        send_request(&app, Method::DELETE, purge_uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::DELETE,
+        purge_uri,
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["transactions_deleted"], 2);
+    assert_eq!(body["data"]["accounts_adjusted"], 1);
+
+    let balance = account_service.get_account_by_id(account).await.unwrap();
+    assert_eq!(balance.balance, Decimal::from(50));
+    let history = account_service
+        .get_balance_history(account, None, None)
+        .await
+        .unwrap();
+    let last = history.last().unwrap();
+    assert_eq!(last.balance, Decimal::from(50));
+    assert_eq!(last.transaction_id, None);
+    let (_, _, body) = send_request(&app, Method::GET, &listing_uri, Some(&token), &[], None).await;
+    let remaining = body["data"]["items"].as_array().unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0]["description"], "Payroll");
+    assert_eq!(amount(&remaining[0]["amount"]), Decimal::from(50));
+
+    // Test funds that were spent can't be taken back, and nothing is purged
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/sandbox/faucet",
+        Some(&token),
+        &[],
+        faucet(account, "100"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account,
+                receiver_account_id: other_account,
+                amount: Decimal::from(120),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    let (status, _, _) = send_request(
+        &app,
+        Method::DELETE,
+        purge_uri,
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let balance = account_service.get_account_by_id(account).await.unwrap();
+    assert_eq!(balance.balance, Decimal::from(30));
+
+    teardown(&db_url).await;
+}
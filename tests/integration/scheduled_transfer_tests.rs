@@ -0,0 +1,350 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, register_user, send_request, setup, teardown,
+};
+use axum::http::{Method, StatusCode};
+use axum::Router;
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use txn_manager::{DepositRequest, ScheduledTransferService};
+use uuid::Uuid;
+
+/// Opens an account with 100 in it for a new user and returns its ID
+async fn funded_account(pool: &PgPool, username: &str) -> Uuid {
+    let user = register_user(&create_user_service(pool.clone()), username).await;
+    let account_id = create_account_service(pool.clone())
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    create_transaction_service(pool.clone())
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    account_id
+}
+
+async fn balance(pool: &PgPool, account_id: Uuid) -> Decimal {
+    create_account_service(pool.clone())
+        .get_account_by_id(account_id)
+        .await
+        .unwrap()
+        .balance
+}
+
+/// Schedules a transfer an hour from now over the API
+async fn schedule(app: &Router, token: &str, request: Value) -> (StatusCode, Value) {
+    let mut request = request;
+    request["execute_at"] = json!(Utc::now() + Duration::hours(1));
+    let (status, _, body) = send_request(
+        app,
+        Method::POST,
+        "/api/v1/transactions/schedule",
+        Some(token),
+        &[],
+        Some(request),
+    )
+    .await;
+    (status, body)
+}
+
+/// Makes every scheduled transfer due
+async fn make_due(pool: &PgPool) {
+    sqlx::query("UPDATE scheduled_transactions SET execute_at = NOW() - INTERVAL '1 minute'")
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_scheduled_transfer_runs_when_due() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let scheduled_transfer_service =
+        ScheduledTransferService::new(pool.clone(), create_transaction_service(pool.clone()));
+
+    let sender = funded_account(&pool, "schedsender").await;
+    let receiver = funded_account(&pool, "schedreceiver").await;
+    let token = login_token(&app, "schedsender").await;
+
+    let (status, body) = schedule(
+        &app,
+        &token,
+        json!({
+            "sender_account_id": sender,
+            "receiver_account_id": receiver,
+            "amount": "40",
+            "description": "Rent"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "SCHEDULED");
+    assert_eq!(body["data"]["amount"], "40.00");
+    let id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/transactions/schedule",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["id"], id.as_str());
+
+    // Nothing runs before its time
+    assert_eq!(scheduled_transfer_service.execute_due().await.unwrap(), 0);
+    assert_eq!(balance(&pool, sender).await, Decimal::from(100));
+
+    make_due(&pool).await;
+    assert_eq!(scheduled_transfer_service.execute_due().await.unwrap(), 1);
+    assert_eq!(balance(&pool, sender).await, Decimal::from(60));
+    assert_eq!(balance(&pool, receiver).await, Decimal::from(140));
+
+    let (status, transaction_id): (String, Option<Uuid>) = sqlx::query_as(
+        "SELECT status, transaction_id FROM scheduled_transactions WHERE id = $1::UUID",
+    )
+    .bind(&id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(status, "EXECUTED");
+    let transaction = create_transaction_service(pool.clone())
+        .get_transaction_by_id(transaction_id.unwrap())
+        .await
+        .unwrap();
+    assert_eq!(transaction.transaction_type, "TRANSFER");
+    assert_eq!(transaction.description.as_deref(), Some("Rent"));
+
+    // An executed transfer runs only once and can no longer be cancelled
+    assert_eq!(scheduled_transfer_service.execute_due().await.unwrap(), 0);
+    assert_eq!(balance(&pool, sender).await, Decimal::from(60));
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/transactions/schedule",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"].as_array().unwrap().is_empty());
+    let (status, _, body) = send_request(
+        &app,
+        Method::DELETE,
+        &format!("/api/v1/transactions/schedule/{}", id),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "SCHEDULED_TRANSFER_NOT_PENDING");
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_scheduled_transfer_failure_and_cancellation() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let scheduled_transfer_service =
+        ScheduledTransferService::new(pool.clone(), create_transaction_service(pool.clone()));
+
+    let sender = funded_account(&pool, "schedpoor").await;
+    let receiver = funded_account(&pool, "schedother").await;
+    let token = login_token(&app, "schedpoor").await;
+    let other_token = login_token(&app, "schedother").await;
+
+    // Funds are only checked when the transfer runs
+    let (status, body) = schedule(
+        &app,
+        &token,
+        json!({ "sender_account_id": sender, "receiver_account_id": receiver, "amount": "500" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let too_large = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = schedule(
+        &app,
+        &token,
+        json!({ "sender_account_id": sender, "receiver_account_id": receiver, "amount": "10" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let cancel_uri = format!(
+        "/api/v1/transactions/schedule/{}",
+        body["data"]["id"].as_str().unwrap()
+    );
+
+    // Only the user who scheduled a transfer can see and cancel it
+    let (status, _, _) = send_request(
+        &app,
+        Method::DELETE,
+        &cancel_uri,
+        Some(&other_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _, body) =
+        send_request(&app, Method::DELETE, &cancel_uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "CANCELLED");
+    let (status, _, body) =
+        send_request(&app, Method::DELETE, &cancel_uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "SCHEDULED_TRANSFER_NOT_PENDING");
+
+    // Scheduling needs the sender's owner and a time in the future
+    let (status, _) = schedule(
+        &app,
+        &other_token,
+        json!({ "sender_account_id": sender, "receiver_account_id": receiver, "amount": "10" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/schedule",
+        Some(&token),
+        &[],
+        Some(json!({
+            "sender_account_id": sender,
+            "receiver_account_id": receiver,
+            "amount": "10",
+            "execute_at": Utc::now() - Duration::minutes(1)
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // The cancelled transfer is skipped and the unfunded one fails
+    make_due(&pool).await;
+    assert_eq!(scheduled_transfer_service.execute_due().await.unwrap(), 1);
+    let (status, transaction_id, error): (String, Option<Uuid>, Option<String>) = sqlx::query_as(
+        "SELECT status, transaction_id, error FROM scheduled_transactions WHERE id = $1::UUID",
+    )
+    .bind(&too_large)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(status, "FAILED");
+    assert!(transaction_id.is_none());
+    assert!(error.unwrap().contains("Insufficient funds"));
+    assert_eq!(balance(&pool, sender).await, Decimal::from(100));
+    assert_eq!(balance(&pool, receiver).await, Decimal::from(100));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_scheduled_transfer_expires_unless_run_in_time() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let scheduled_transfer_service =
+        ScheduledTransferService::new(pool.clone(), create_transaction_service(pool.clone()));
+
+    let sender = funded_account(&pool, "expirysender").await;
+    let receiver = funded_account(&pool, "expiryreceiver").await;
+    let token = login_token(&app, "expirysender").await;
+    let execute_at = Utc::now() + Duration::hours(1);
+    let request = |expires_at| {
+        json!({
+            "sender_account_id": sender,
+            "receiver_account_id": receiver,
+            "amount": "25",
+            "execute_at": execute_at,
+            "expires_at": expires_at
+        })
+    };
+
+    // The deadline has to leave time to run the transfer
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/schedule",
+        Some(&token),
+        &[],
+        Some(request(execute_at)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/schedule",
+        Some(&token),
+        &[],
+        Some(request(execute_at + Duration::hours(1))),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["data"]["expires_at"].is_string(), "{}", body);
+    let id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // Due but not yet expired
+    let clock = Utc::now() + Duration::minutes(90);
+    assert_eq!(
+        scheduled_transfer_service
+            .expire_overdue(clock)
+            .await
+            .unwrap(),
+        0
+    );
+
+    // Three hours on, the transfer was never run and is voided instead
+    let clock = Utc::now() + Duration::hours(3);
+    assert_eq!(
+        scheduled_transfer_service
+            .expire_overdue(clock)
+            .await
+            .unwrap(),
+        1
+    );
+    let status: String =
+        sqlx::query_scalar("SELECT status FROM scheduled_transactions WHERE id = $1::UUID")
+            .bind(&id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(status, "EXPIRED");
+
+    // An expired transfer never runs, even once it is due
+    make_due(&pool).await;
+    assert_eq!(scheduled_transfer_service.execute_due().await.unwrap(), 0);
+    assert_eq!(balance(&pool, sender).await, Decimal::from(100));
+    assert_eq!(balance(&pool, receiver).await, Decimal::from(100));
+    let (status, _, body) = send_request(
+        &app,
+        Method::DELETE,
+        &format!("/api/v1/transactions/schedule/{}", id),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "SCHEDULED_TRANSFER_NOT_PENDING");
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,454 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, register_user, send_request, setup, teardown,
+};
+use axum::http::{Method, StatusCode};
+use axum::Router;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use txn_manager::{DepositRequest, RecurrenceInterval, StandingOrderService};
+use uuid::Uuid;
+
+/// Opens an account with 100 in it for a new user and returns its ID
+async fn funded_account(pool: &PgPool, username: &str) -> Uuid {
+    let user = register_user(&create_user_service(pool.clone()), username).await;
+    let account_id = create_account_service(pool.clone())
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    deposit(pool, account_id, 100).await;
+    account_id
+}
+
+async fn deposit(pool: &PgPool, account_id: Uuid, amount: i64) {
+    create_transaction_service(pool.clone())
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(amount),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+}
+
+async fn balance(pool: &PgPool, account_id: Uuid) -> Decimal {
+    create_account_service(pool.clone())
+        .get_account_by_id(account_id)
+        .await
+        .unwrap()
+        .balance
+}
+
+/// Makes the next occurrence of every standing order due
+async fn make_due(pool: &PgPool) {
+    sqlx::query("UPDATE standing_orders SET next_run_at = NOW() - INTERVAL '1 minute'")
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+/// Sends a standing order request and returns the status and response body
+async fn call(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    token: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let (status, _, body) = send_request(app, method, uri, Some(token), &[], body).await;
+    (status, body)
+}
+
+fn utc(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, 9, 0, 0).unwrap()
+}
+
+#[test]
+fn test_recurrence_intervals() {
+    let start = utc(2024, 1, 31);
+    assert_eq!(
+        RecurrenceInterval::DAILY.nth_after(start, 1),
+        utc(2024, 2, 1)
+    );
+    assert_eq!(
+        RecurrenceInterval::WEEKLY.nth_after(start, 2),
+        utc(2024, 2, 14)
+    );
+
+    // Monthly occurrences keep the day of the month where it exists
+    assert_eq!(
+        RecurrenceInterval::MONTHLY.nth_after(start, 1),
+        utc(2024, 2, 29)
+    );
+    assert_eq!(
+        RecurrenceInterval::MONTHLY.nth_after(start, 2),
+        utc(2024, 3, 31)
+    );
+    assert_eq!(
+        RecurrenceInterval::MONTHLY.nth_after(start, 3),
+        utc(2024, 4, 30)
+    );
+}
+
+#[tokio::test]
+async fn test_standing_order_runs_and_retries_after_failure() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let standing_order_service =
+        StandingOrderService::new(pool.clone(), create_transaction_service(pool.clone()));
+
+    let sender = funded_account(&pool, "ordersender").await;
+    let receiver = funded_account(&pool, "orderreceiver").await;
+    let token = login_token(&app, "ordersender").await;
+
+    let (status, body) = call(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/recurring",
+        &token,
+        Some(json!({
+            "sender_account_id": sender,
+            "receiver_account_id": receiver,
+            "amount": "30",
+            "interval": "MONTHLY",
+            "description": "Allowance"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "ACTIVE");
+    assert_eq!(body["data"]["amount"], "30.00");
+    let order_id: Uuid = body["data"]["id"].as_str().unwrap().parse().unwrap();
+
+    // The first occurrence is due right away, the next one a month later
+    assert_eq!(standing_order_service.execute_due().await.unwrap(), 1);
+    assert_eq!(standing_order_service.execute_due().await.unwrap(), 0);
+    assert_eq!(balance(&pool, sender).await, Decimal::from(70));
+    let (_, body) = call(
+        &app,
+        Method::GET,
+        "/api/v1/transactions/recurring",
+        &token,
+        None,
+    )
+    .await;
+    let next_run_at: DateTime<Utc> = body["data"][0]["next_run_at"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(next_run_at > Utc::now() + Duration::days(27), "{}", body);
+
+    for _ in 0..2 {
+        make_due(&pool).await;
+        assert_eq!(standing_order_service.execute_due().await.unwrap(), 1);
+    }
+    assert_eq!(balance(&pool, sender).await, Decimal::from(10));
+
+    // Without the funds the occurrence fails, but the order carries on
+    make_due(&pool).await;
+    assert_eq!(standing_order_service.execute_due().await.unwrap(), 1);
+    assert_eq!(balance(&pool, sender).await, Decimal::from(10));
+    let (_, body) = call(
+        &app,
+        Method::GET,
+        "/api/v1/transactions/recurring",
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(body["data"][0]["status"], "ACTIVE");
+    assert!(body["data"][0]["last_error"]
+        .as_str()
+        .unwrap()
+        .contains("Insufficient funds"));
+
+    // The next cycle is attempted as usual
+    deposit(&pool, sender, 50).await;
+    make_due(&pool).await;
+    assert_eq!(standing_order_service.execute_due().await.unwrap(), 1);
+    assert_eq!(balance(&pool, sender).await, Decimal::from(30));
+    assert_eq!(balance(&pool, receiver).await, Decimal::from(220));
+    let error: Option<String> =
+        sqlx::query_scalar("SELECT last_error FROM standing_orders WHERE id = $1")
+            .bind(order_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(error.is_none());
+
+    // Every attempt is recorded, and each transfer points back to the order
+    let runs: Vec<(i32, String)> = sqlx::query_as(
+        "SELECT occurrence, status FROM standing_order_runs WHERE standing_order_id = $1 ORDER BY occurrence",
+    )
+    .bind(order_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let statuses: Vec<&str> = runs.iter().map(|(_, status)| status.as_str()).collect();
+    assert_eq!(
+        statuses,
+        ["EXECUTED", "EXECUTED", "EXECUTED", "FAILED", "EXECUTED"]
+    );
+    let linked: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE standing_order_id = $1")
+            .bind(order_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(linked, 4);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_standing_order_pause_resume_and_delete() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let standing_order_service =
+        StandingOrderService::new(pool.clone(), create_transaction_service(pool.clone()));
+
+    let sender = funded_account(&pool, "pausesender").await;
+    let receiver = funded_account(&pool, "pausereceiver").await;
+    let token = login_token(&app, "pausesender").await;
+    let other_token = login_token(&app, "pausereceiver").await;
+    let request = |interval: &str, end_date: Option<String>| {
+        json!({
+            "sender_account_id": sender,
+            "receiver_account_id": receiver,
+            "amount": "10",
+            "interval": interval,
+            "end_date": end_date
+        })
+    };
+
+    let (status, _) = call(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/recurring",
+        &token,
+        Some(request(
+            "DAILY",
+            Some((Utc::now() - Duration::days(1)).date_naive().to_string()),
+        )),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = call(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/recurring",
+        &token,
+        Some(request("WEEKLY", None)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let uri = format!(
+        "/api/v1/transactions/recurring/{}",
+        body["data"]["id"].as_str().unwrap()
+    );
+
+    // Only the owner can manage the order
+    let (status, _) = call(
+        &app,
+        Method::POST,
+        &format!("{}/pause", uri),
+        &other_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = call(&app, Method::POST, &format!("{}/pause", uri), &token, None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "PAUSED");
+    let (status, body) = call(&app, Method::POST, &format!("{}/pause", uri), &token, None).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "STANDING_ORDER_NOT_ACTIVE");
+
+    // A paused order doesn't run, and resuming skips what it missed
+    make_due(&pool).await;
+    assert_eq!(standing_order_service.execute_due().await.unwrap(), 0);
+    let (status, body) = call(&app, Method::POST, &format!("{}/resume", uri), &token, None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "ACTIVE");
+    let next_run_at: DateTime<Utc> = body["data"]["next_run_at"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(next_run_at > Utc::now(), "{}", body);
+    assert_eq!(standing_order_service.execute_due().await.unwrap(), 0);
+    assert_eq!(balance(&pool, sender).await, Decimal::from(100));
+    let (status, body) = call(&app, Method::POST, &format!("{}/resume", uri), &token, None).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "STANDING_ORDER_NOT_PAUSED");
+
+    // An order ending today completes after its only occurrence
+    let (status, body) = call(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/recurring",
+        &token,
+        Some(request("DAILY", Some(Utc::now().date_naive().to_string()))),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(standing_order_service.execute_due().await.unwrap(), 1);
+    assert_eq!(balance(&pool, sender).await, Decimal::from(90));
+    let (_, body) = call(
+        &app,
+        Method::GET,
+        "/api/v1/transactions/recurring",
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(body["data"][1]["status"], "COMPLETED", "{}", body);
+
+    // Deleted orders are gone for good
+    let (status, _) = call(&app, Method::DELETE, &uri, &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call(&app, Method::DELETE, &uri, &token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, body) = call(
+        &app,
+        Method::GET,
+        "/api/v1/transactions/recurring",
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 1, "{}", body);
+    make_due(&pool).await;
+    assert_eq!(standing_order_service.execute_due().await.unwrap(), 0);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_upcoming_standing_order_runs() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+
+    let sender = funded_account(&pool, "upcomingsender").await;
+    let receiver = funded_account(&pool, "upcomingreceiver").await;
+    let token = login_token(&app, "upcomingsender").await;
+    let starts_at = Utc.timestamp_opt(Utc::now().timestamp(), 0).unwrap() + Duration::days(10);
+    let create = |end_date: Option<String>| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            let (status, body) = call(
+                &app,
+                Method::POST,
+                "/api/v1/transactions/recurring",
+                &token,
+                Some(json!({
+                    "sender_account_id": sender,
+                    "receiver_account_id": receiver,
+                    "amount": "10",
+                    "interval": "MONTHLY",
+                    "starts_at": starts_at,
+                    "end_date": end_date
+                })),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            body["data"]["id"].as_str().unwrap().to_string()
+        }
+    };
+    let monthly = create(None).await;
+    let ending = create(Some(
+        (starts_at + Duration::days(20)).date_naive().to_string(),
+    ))
+    .await;
+    let paused = create(None).await;
+    let (status, _) = call(
+        &app,
+        Method::POST,
+        &format!("/api/v1/transactions/recurring/{}/pause", paused),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let upcoming = |query: &'static str| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            call(
+                &app,
+                Method::GET,
+                &format!("/api/v1/recurring/upcoming{}", query),
+                &token,
+                None,
+            )
+            .await
+        }
+    };
+    let run_dates = |body: &Value, id: &str| -> Vec<DateTime<Utc>> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|order| order["id"] == id)
+            .unwrap_or_else(|| panic!("{} is not listed: {}", id, body))["run_dates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|date| date.as_str().unwrap().parse().unwrap())
+            .collect()
+    };
+
+    // The default window of 30 days holds the first run only; paused orders
+    // aren't listed
+    let (status, body) = upcoming("").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2, "{}", body);
+    assert_eq!(run_dates(&body, &monthly), vec![starts_at]);
+    assert_eq!(run_dates(&body, &ending), vec![starts_at]);
+
+    // 45 days reach the run a month later, except past the end date
+    let (status, body) = upcoming("?days=45").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(
+        run_dates(&body, &monthly),
+        vec![
+            starts_at,
+            RecurrenceInterval::MONTHLY.nth_after(starts_at, 1)
+        ]
+    );
+    assert_eq!(run_dates(&body, &ending), vec![starts_at]);
+
+    // Nothing falls within 5 days, and the window is bounded
+    let (_, body) = upcoming("?days=5").await;
+    assert!(run_dates(&body, &monthly).is_empty(), "{}", body);
+    for query in ["?days=0", "?days=367"] {
+        let (status, _) = upcoming(query).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+
+    // Other users see only their own orders
+    let other_token = login_token(&app, "upcomingreceiver").await;
+    let (_, body) = call(
+        &app,
+        Method::GET,
+        "/api/v1/recurring/upcoming",
+        &other_token,
+        None,
+    )
+    .await;
+    assert_eq!(body["data"], json!([]));
+
+    teardown(&db_url).await;
+}
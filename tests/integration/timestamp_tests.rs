@@ -0,0 +1,164 @@
+use crate::integration::setup::{
+    create_account_service, create_transaction_service, create_user_service, register_user, setup,
+    teardown,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::{
+    BudgetPeriod, BudgetService, DepositRequest, NotificationPreferences, SetBudgetRequest,
+    TransferRequest,
+};
+use uuid::Uuid;
+
+/// Returns a row's (created_at, updated_at)
+async fn timestamps(pool: &PgPool, table: &str, id: Uuid) -> (DateTime<Utc>, DateTime<Utc>) {
+    sqlx::query_as(&format!(
+        "SELECT created_at, updated_at FROM {} WHERE id = $1",
+        table
+    ))
+    .bind(id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+async fn updated_at(pool: &PgPool, table: &str, id: Uuid) -> DateTime<Utc> {
+    timestamps(pool, table, id).await.1
+}
+
+#[tokio::test]
+async fn test_updated_at_moves_on_every_update() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let budget_service = BudgetService::new(pool.clone());
+
+    let user = register_user(&user_service, "touchuser").await;
+    let payee = register_user(&user_service, "touchpayee").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    // Users: profile and notification preference updates
+    let before = updated_at(&pool, "users", user.id).await;
+    user_service
+        .update_user(user.id, Some("Renamed".to_string()), None, None)
+        .await
+        .unwrap();
+    let after_profile = updated_at(&pool, "users", user.id).await;
+    assert!(after_profile > before);
+
+    user_service
+        .update_notification_preferences(
+            user.id,
+            NotificationPreferences {
+                transfer_receipts: false,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(updated_at(&pool, "users", user.id).await > after_profile);
+
+    // Accounts: direct balance updates
+    let before = updated_at(&pool, "accounts", account).await;
+    account_service
+        .update_balance(account, Decimal::from(500))
+        .await
+        .unwrap();
+    let after_direct = updated_at(&pool, "accounts", account).await;
+    assert!(after_direct > before);
+
+    // Accounts and transactions: balance and status updates inside a deposit
+    let deposit = transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    assert!(updated_at(&pool, "accounts", account).await > after_direct);
+    let (created, updated) = timestamps(&pool, "transactions", deposit.id).await;
+    assert!(updated > created);
+
+    // Transactions: origin, category and status updates inside a transfer
+    let payee_before = updated_at(&pool, "accounts", payee_account).await;
+    let context = RequestContext {
+        ip_address: Some("203.0.113.7".to_string()),
+        user_agent: Some("touch-test".to_string()),
+        ..Default::default()
+    };
+    let transfer = transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account,
+                receiver_account_id: payee_account,
+                amount: Decimal::from(25),
+                description: None,
+                category: Some("rent".to_string()),
+                allow_duplicate: false,
+            },
+            &context,
+        )
+        .await
+        .unwrap();
+    let (created, updated) = timestamps(&pool, "transactions", transfer.id).await;
+    assert!(updated > created);
+    assert!(updated_at(&pool, "accounts", payee_account).await > payee_before);
+
+    // Statements that don't mention updated_at still move it, like the origin
+    // and category updates TransactionService issues on their own
+    for statement in [
+        "UPDATE transactions SET ip_address = '198.51.100.1', user_agent = 'later' WHERE id = $1",
+        "UPDATE transactions SET category = 'utilities' WHERE id = $1",
+    ] {
+        let before = updated_at(&pool, "transactions", transfer.id).await;
+        sqlx::query(statement)
+            .bind(transfer.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(updated_at(&pool, "transactions", transfer.id).await > before);
+    }
+
+    // Even an explicit value is overridden by the database clock
+    sqlx::query("UPDATE users SET updated_at = '2000-01-01T00:00:00Z' WHERE id = $1")
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert!(updated_at(&pool, "users", user.id).await > after_profile);
+
+    // Budgets: replacing a limit
+    let request = |limit| SetBudgetRequest {
+        category: "rent".to_string(),
+        period: BudgetPeriod::MONTHLY,
+        limit: Decimal::from(limit),
+    };
+    let budget = budget_service
+        .set_budget(account, request(100))
+        .await
+        .unwrap();
+    let replaced = budget_service
+        .set_budget(account, request(200))
+        .await
+        .unwrap();
+    assert_eq!(replaced.id, budget.id);
+    assert!(replaced.updated_at > budget.updated_at);
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,244 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, promote_to_admin, register_user, send_request, setup, teardown,
+};
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use rust_decimal::Decimal;
+use tower::ServiceExt;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::utils::csv::CsvRecordReader;
+use txn_manager::{DepositRequest, TransferRequest};
+use uuid::Uuid;
+
+const HEADER: &str = "id,sender_account_id,receiver_account_id,amount,currency,transaction_type,status,description,created_at,updated_at";
+
+/// Fetches a CSV export, returning the status, content type and body text
+async fn get_csv(app: &Router, uri: &str, token: &str) -> (StatusCode, String, String) {
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        content_type,
+        String::from_utf8(bytes.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_admin_transaction_csv_stream() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let user = register_user(&user_service, "exportuser").await;
+    register_user(&user_service, "exportadmin").await;
+    promote_to_admin(&pool, "exportadmin").await;
+    let token = login_token(&app, "exportuser").await;
+    let admin_token = login_token(&app, "exportadmin").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    // Enough rows to span several body chunks: 100 a day for 10 days in January
+    sqlx::query(
+        r#"
+        INSERT INTO transactions
+            (id, receiver_account_id, amount, currency, transaction_type, status, description,
+             created_at, updated_at)
+        SELECT gen_random_uuid(), $1, n + 1, 'USD', 'DEPOSIT', 'COMPLETED', 'Salary, "January"',
+               TIMESTAMPTZ '2024-01-01 00:00:00+00' + (n / 100) * INTERVAL '1 day',
+               TIMESTAMPTZ '2024-01-01 00:00:00+00' + (n / 100) * INTERVAL '1 day'
+        FROM generate_series(0, 999) AS n
+        "#,
+    )
+    .bind(account)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let uri = "/api/v1/admin/transactions/stream.csv";
+    let (status, _, _) = send_request(&app, Method::GET, uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, content_type, body) = get_csv(&app, uri, &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("text/csv"));
+    let lines: Vec<&str> = body.split_terminator("\r\n").collect();
+    assert_eq!(lines[0], HEADER);
+    assert_eq!(lines.len(), 1001);
+    assert!(body.len() > 64 * 1024);
+
+    // Records follow the header's columns, and awkward descriptions are quoted
+    let record = lines[1];
+    assert!(record.contains(r#","Salary, ""January""","#));
+    let id = record.split(',').next().unwrap();
+    assert!(Uuid::parse_str(id).is_ok());
+    assert_eq!(record.split(',').nth(2), Some(account.to_string().as_str()));
+
+    // Date ranges are inclusive of both days
+    let (status, _, body) = get_csv(
+        &app,
+        &format!("{}?from=2024-01-03&to=2024-01-04", uri),
+        &admin_token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let lines: Vec<&str> = body.split_terminator("\r\n").collect();
+    assert_eq!(lines.len(), 201);
+    assert!(lines[1..]
+        .iter()
+        .all(|line| line.contains(",2024-01-03T") || line.contains(",2024-01-04T")));
+
+    let (status, _, _) = get_csv(
+        &app,
+        &format!("{}?from=2024-01-04&to=2024-01-03", uri),
+        &admin_token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_account_transaction_csv_export() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let user = register_user(&user_service, "accountexport").await;
+    let payee = register_user(&user_service, "exportpayee").await;
+    let token = login_token(&app, "accountexport").await;
+    let payee_token = login_token(&app, "exportpayee").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    // More rows than a page of the transaction history holds
+    sqlx::query(
+        r#"
+        INSERT INTO transactions
+            (id, receiver_account_id, amount, currency, transaction_type, status, description,
+             created_at, updated_at)
+        SELECT gen_random_uuid(), $1, 1, 'USD', 'DEPOSIT', 'COMPLETED', NULL,
+               TIMESTAMPTZ '2024-01-01 00:00:00+00' + n * INTERVAL '1 hour',
+               TIMESTAMPTZ '2024-01-01 00:00:00+00' + n * INTERVAL '1 hour'
+        FROM generate_series(0, 149) AS n
+        "#,
+    )
+    .bind(account)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let description = "Rent, March\nincl. \"parking\"";
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account,
+            amount: Decimal::from(500),
+            currency: None,
+            exchange_rate: None,
+            description: Some(description.to_string()),
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account,
+                receiver_account_id: payee_account,
+                amount: Decimal::from(20),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+
+    let uri = format!("/api/v1/accounts/{}/transactions/export", account);
+    let (status, _, _) = send_request(&app, Method::GET, &uri, Some(&payee_token), &[], None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, headers, _) = send_request(&app, Method::GET, &uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        headers[header::CONTENT_DISPOSITION],
+        format!(
+            "attachment; filename=\"account-{}-transactions.csv\"",
+            account
+        )
+    );
+
+    let (status, content_type, body) = get_csv(&app, &uri, &token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("text/csv"));
+    let mut reader = CsvRecordReader::new(1024);
+    let mut records = reader.feed(body.as_bytes()).unwrap();
+    records.extend(reader.finish().unwrap());
+    assert_eq!(
+        records[0],
+        [
+            "id",
+            "date",
+            "type",
+            "amount",
+            "currency",
+            "counterparty_account_id",
+            "status",
+            "description"
+        ]
+    );
+    assert_eq!(records.len(), 153);
+
+    // Newest first; the transfer names the payee and the deposit's
+    // description survives the quoting
+    assert_eq!(records[1][2], "TRANSFER");
+    assert_eq!(records[1][5], payee_account.to_string());
+    assert_eq!(records[2][2], "DEPOSIT");
+    assert_eq!(records[2][3], "500.00");
+    assert_eq!(records[2][5], "");
+    assert_eq!(records[2][7], description);
+
+    // Date filters are inclusive days
+    let (status, _, body) = get_csv(
+        &app,
+        &format!("{}?from=2024-01-02&to=2024-01-03", uri),
+        &token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let mut reader = CsvRecordReader::new(1024);
+    let mut records = reader.feed(body.as_bytes()).unwrap();
+    records.extend(reader.finish().unwrap());
+    assert_eq!(records.len(), 49);
+
+    teardown(&db_url).await;
+}
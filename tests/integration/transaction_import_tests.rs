@@ -0,0 +1,225 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, register_user, send_request, setup, teardown,
+};
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::Router;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+use txn_manager::utils::csv::CsvRecordReader;
+use txn_manager::DepositRequest;
+use uuid::Uuid;
+
+const BOUNDARY: &str = "import-test-boundary";
+
+/// Uploads a CSV file as the `file` part of a multipart body
+async fn upload(app: &Router, uri: &str, token: &str, csv: &str) -> (StatusCode, Value) {
+    let body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"history.csv\"\r\n\
+         Content-Type: text/csv\r\n\r\n{csv}\r\n--{b}--\r\n",
+        b = BOUNDARY,
+        csv = csv
+    );
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("Authorization", format!("Bearer {}", token))
+        .header(
+            "Content-Type",
+            format!("multipart/form-data; boundary={}", BOUNDARY),
+        )
+        .body(Body::from(body))
+        .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+async fn imported_count(pool: &PgPool, account_id: Uuid) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM imported_transactions WHERE account_id = $1")
+        .bind(account_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[test]
+fn test_csv_record_reader_across_chunks() {
+    let mut reader = CsvRecordReader::new(1024);
+    let mut records = Vec::new();
+    for chunk in [
+        "da",
+        "te,desc\r\n2024-01-0",
+        "1,\"two\nlines, quoted\"\n",
+        "x,y",
+    ] {
+        records.extend(reader.feed(chunk.as_bytes()).unwrap());
+    }
+    records.extend(reader.finish().unwrap());
+
+    assert_eq!(
+        records,
+        vec![
+            vec!["date".to_string(), "desc".to_string()],
+            vec!["2024-01-01".to_string(), "two\nlines, quoted".to_string()],
+            vec!["x".to_string(), "y".to_string()],
+        ]
+    );
+
+    // A record longer than the limit is rejected rather than buffered
+    let mut reader = CsvRecordReader::new(16);
+    assert!(reader.feed("a".repeat(64).as_bytes()).is_err());
+}
+
+#[tokio::test]
+async fn test_import_reports_rows_and_skips_reuploads() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+
+    let user = register_user(&user_service, "importuser").await;
+    register_user(&user_service, "importother").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let token = login_token(&app, "importuser").await;
+    let uri = format!("/api/v1/accounts/{}/transactions/import", account);
+
+    let csv = "Date,Amount,Direction,Description,Category\n\
+               2024-01-05,4.50,OUT,Coffee,Food\n\
+               2024-01-05,4.50,OUT,Coffee,Food\n\
+               2024-01-06,1200,in,Salary,\n\
+               2024-13-01,10,OUT,Bad date,\n\
+               2024-01-07,-3,OUT,Negative,\n\
+               2024-01-07,3,SIDEWAYS,Unknown,\n";
+    let (status, body) = upload(&app, &uri, &token, csv).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let summary = &body["data"];
+    assert_eq!(summary["rows_read"], 6);
+    // Identical rows within one file are both kept
+    assert_eq!(summary["imported"], 3);
+    assert_eq!(summary["duplicates"], 0);
+    assert_eq!(summary["failed"], 3);
+    let failed_rows: Vec<u64> = summary["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["row"].as_u64().unwrap())
+        .collect();
+    assert_eq!(failed_rows, vec![5, 6, 7]);
+    assert_eq!(imported_count(&pool, account).await, 3);
+
+    // Resuming the session with the same file (plus one new row) only adds the new row
+    let session_id = summary["session_id"].as_str().unwrap().to_string();
+    let resumed = format!("{}2024-01-08,20,OUT,Books,\n", csv);
+    let (status, body) = upload(
+        &app,
+        &format!("{}?session_id={}", uri, session_id),
+        &token,
+        &resumed,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["session_id"], session_id.as_str());
+    assert_eq!(body["data"]["imported"], 1);
+    assert_eq!(body["data"]["duplicates"], 3);
+    assert_eq!(imported_count(&pool, account).await, 4);
+
+    let totals: (i64, i64, i64) = sqlx::query_as(
+        "SELECT rows_imported, rows_duplicate, rows_failed FROM import_sessions WHERE id = $1",
+    )
+    .bind(Uuid::parse_str(&session_id).unwrap())
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(totals, (4, 3, 6));
+
+    // Imported history never touches the ledger
+    let account_after = account_service.get_account_by_id(account).await.unwrap();
+    assert_eq!(account_after.balance, Decimal::ZERO);
+    let ledger: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(ledger, 0);
+
+    // A file without the required columns is rejected outright
+    let (status, _) = upload(&app, &uri, &token, "date,amount\n2024-01-01,5\n").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Unknown sessions and other users' accounts are refused
+    let (status, _) = upload(
+        &app,
+        &format!("{}?session_id={}", uri, Uuid::new_v4()),
+        &token,
+        csv,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let other_token = login_token(&app, "importother").await;
+    let (status, _) = upload(&app, &uri, &other_token, csv).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Without a file part the upload is a bad request
+    let (status, _, _) = send_request(&app, Method::POST, &uri, Some(&token), &[], None).await;
+    assert!(status.is_client_error());
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_import_currency_precision() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "importprecision").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account,
+            amount: Decimal::from(10),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let token = login_token(&app, "importprecision").await;
+    let uri = format!("/api/v1/accounts/{}/transactions/import", account);
+
+    // More decimal places than the account currency allows is a row error
+    let csv = "date,amount,direction\n2024-02-01,1.005,OUT\n2024-02-01,1.25,OUT\n";
+    let (status, body) = upload(&app, &uri, &token, csv).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["imported"], 1);
+    assert_eq!(body["data"]["failed"], 1);
+    assert_eq!(body["data"]["errors"][0]["row"], 2);
+
+    let balance = account_service.get_account_by_id(account).await.unwrap();
+    assert_eq!(balance.balance, Decimal::from(10));
+
+    teardown(&db_url).await;
+}
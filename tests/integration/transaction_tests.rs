@@ -1,8 +1,22 @@
 use crate::integration::setup::{
-    create_account_service, create_transaction_service, create_user_service, setup, teardown,
+    create_account_service, create_app, create_transaction_service,
+    create_transaction_service_with_config, create_user_service, login_token, promote_to_admin,
+    register_user, send_request, setup, teardown, test_config,
 };
+use axum::extract::connect_info::MockConnectInfo;
+use axum::http::{header, Method, StatusCode};
 use rust_decimal::Decimal;
-use txn_manager::{CreateUserRequest, DepositRequest, TransferRequest, WithdrawalRequest};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use txn_manager::api::create_router;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::utils::error::AppError;
+use txn_manager::{
+    AccountLimits, CreateTransactionRequest, CreateUserRequest, DepositRequest, PayoutRequest,
+    SplitTransferLeg, SplitTransferRequest, TransactionConfig, TransferRequest, WithdrawalRequest,
+};
+use uuid::Uuid;
 
 #[tokio::test]
 async fn test_deposit_transaction() {
@@ -36,7 +50,11 @@ async fn test_deposit_transaction() {
     let deposit_request = DepositRequest {
         account_id: account.id,
         amount: Decimal::from(100),
+        currency: None,
+        exchange_rate: None,
         description: Some("Test deposit".to_string()),
+        category: None,
+        external_reference: None,
     };
 
     let deposit_result = transaction_service.process_deposit(deposit_request).await;
@@ -92,7 +110,11 @@ async fn test_withdrawal_transaction() {
     let deposit_request = DepositRequest {
         account_id: account.id,
         amount: Decimal::from(200),
+        currency: None,
+        exchange_rate: None,
         description: Some("Initial deposit".to_string()),
+        category: None,
+        external_reference: None,
     };
 
     transaction_service
@@ -105,10 +127,13 @@ async fn test_withdrawal_transaction() {
         account_id: account.id,
         amount: Decimal::from(50),
         description: Some("Test withdrawal".to_string()),
+        category: None,
+        destination_id: None,
+        external_reference: None,
     };
 
     let withdrawal_result = transaction_service
-        .process_withdrawal(withdrawal_request)
+        .process_withdrawal(withdrawal_request, &RequestContext::default())
         .await;
     assert!(
         withdrawal_result.is_ok(),
@@ -131,10 +156,13 @@ async fn test_withdrawal_transaction() {
         account_id: account.id,
         amount: Decimal::from(1000),
         description: Some("Test excessive withdrawal".to_string()),
+        category: None,
+        destination_id: None,
+        external_reference: None,
     };
 
     let withdrawal_result = transaction_service
-        .process_withdrawal(withdrawal_request)
+        .process_withdrawal(withdrawal_request, &RequestContext::default())
         .await;
     assert!(
         withdrawal_result.is_err(),
@@ -194,7 +222,11 @@ async fn test_transfer_transaction() {
     let deposit_request = DepositRequest {
         account_id: sender_account.id,
         amount: Decimal::from(500),
+        currency: None,
+        exchange_rate: None,
         description: Some("Initial funding".to_string()),
+        category: None,
+        external_reference: None,
     };
 
     transaction_service
@@ -208,9 +240,13 @@ async fn test_transfer_transaction() {
         receiver_account_id: receiver_account.id,
         amount: Decimal::from(200),
         description: Some("Test transfer".to_string()),
+        category: None,
+        allow_duplicate: false,
     };
 
-    let transfer_result = transaction_service.process_transfer(transfer_request).await;
+    let transfer_result = transaction_service
+        .process_transfer(transfer_request, &RequestContext::default())
+        .await;
     assert!(
         transfer_result.is_ok(),
         "Transfer failed: {:?}",
@@ -246,9 +282,13 @@ async fn test_transfer_transaction() {
         receiver_account_id: receiver_account.id,
         amount: Decimal::from(1000),
         description: Some("Test excessive transfer".to_string()),
+        category: None,
+        allow_duplicate: false,
     };
 
-    let transfer_result = transaction_service.process_transfer(transfer_request).await;
+    let transfer_result = transaction_service
+        .process_transfer(transfer_request, &RequestContext::default())
+        .await;
     assert!(
         transfer_result.is_err(),
         "Transfer with insufficient funds should fail"
@@ -257,3 +297,3841 @@ async fn test_transfer_transaction() {
     // Clean up test environment
     teardown(&db_url).await;
 }
+
+#[tokio::test]
+async fn test_deposit_with_matching_currency() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "depositmatch").await;
+    let account = account_service
+        .create_account(user.id, "EUR".to_string())
+        .await
+        .unwrap();
+
+    // A deposit that states the account currency is booked as-is
+    let deposit_result = transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account.id,
+            amount: Decimal::from(75),
+            currency: Some("EUR".to_string()),
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await;
+    assert!(
+        deposit_result.is_ok(),
+        "Deposit failed: {:?}",
+        deposit_result.err()
+    );
+    assert_eq!(deposit_result.unwrap().currency, "EUR");
+
+    let updated_account = account_service.get_account_by_id(account.id).await.unwrap();
+    assert_eq!(updated_account.balance, Decimal::from(75));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_deposit_with_mismatched_currency_is_rejected() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "depositmismatch").await;
+    let accounts = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap();
+    let account = &accounts[0];
+
+    // Conversion is disabled by default, so a rate does not help either
+    let deposit_result = transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account.id,
+            amount: Decimal::from(100),
+            currency: Some("EUR".to_string()),
+            exchange_rate: Some(Decimal::from_str("1.10").unwrap()),
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await;
+    assert!(
+        deposit_result.is_err(),
+        "Deposit in a foreign currency should be rejected"
+    );
+
+    let updated_account = account_service.get_account_by_id(account.id).await.unwrap();
+    assert_eq!(updated_account.balance, Decimal::ZERO);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_deposit_with_foreign_currency_converted_when_enabled() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            allow_foreign_currency_deposits: true,
+            ..Default::default()
+        },
+    );
+
+    let user = register_user(&user_service, "depositconvert").await;
+    let accounts = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap();
+    let account = &accounts[0];
+
+    // A rate is still mandatory when conversion is enabled
+    let missing_rate = transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account.id,
+            amount: Decimal::from(100),
+            currency: Some("EUR".to_string()),
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await;
+    assert!(
+        missing_rate.is_err(),
+        "Conversion without a rate should fail"
+    );
+
+    let deposit_response = transaction_service
+        .process_deposit(DepositRequest {
+            account_id: account.id,
+            amount: Decimal::from(100),
+            currency: Some("EUR".to_string()),
+            exchange_rate: Some(Decimal::from_str("1.10").unwrap()),
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(deposit_response.currency, "USD");
+    assert_eq!(deposit_response.amount, Decimal::from(110));
+
+    let updated_account = account_service.get_account_by_id(account.id).await.unwrap();
+    assert_eq!(updated_account.balance, Decimal::from(110));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_large_transfer_requires_verified_users() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            verified_transfer_threshold: Some(Decimal::from(1000)),
+            ..Default::default()
+        },
+    );
+
+    let sender = register_user(&user_service, "bigsender").await;
+    let receiver = register_user(&user_service, "bigreceiver").await;
+    let sender_account = account_service
+        .get_accounts_by_user_id(sender.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let receiver_account = account_service
+        .get_accounts_by_user_id(receiver.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: sender_account,
+            amount: Decimal::from(5000),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    sqlx::query("UPDATE users SET email_verified = TRUE WHERE id = $1")
+        .bind(sender.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let transfer = |amount: i64| TransferRequest {
+        sender_account_id: sender_account,
+        receiver_account_id: receiver_account,
+        amount: Decimal::from(amount),
+        description: None,
+        category: None,
+        allow_duplicate: false,
+    };
+
+    // Amounts up to the threshold are not affected
+    assert!(transaction_service
+        .process_transfer(transfer(1000), &RequestContext::default())
+        .await
+        .is_ok());
+
+    // Above the threshold the unverified receiver blocks the transfer
+    let blocked = transaction_service
+        .process_transfer(transfer(2000), &RequestContext::default())
+        .await;
+    assert!(matches!(blocked, Err(AppError::Forbidden(_))));
+
+    sqlx::query("UPDATE users SET email_verified = TRUE WHERE id = $1")
+        .bind(receiver.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert!(transaction_service
+        .process_transfer(transfer(2000), &RequestContext::default())
+        .await
+        .is_ok());
+
+    let receiver_balance = account_service
+        .get_account_by_id(receiver_account)
+        .await
+        .unwrap()
+        .balance;
+    assert_eq!(receiver_balance, Decimal::from(3000));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_transaction_listing_includes_link_header() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let app = create_app(pool.clone(), &db_url);
+
+    let user = register_user(&user_service, "linkheader").await;
+    let accounts = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap();
+    let account_id = accounts[0].id;
+
+    for _ in 0..5 {
+        transaction_service
+            .process_deposit(DepositRequest {
+                account_id,
+                amount: Decimal::from(10),
+                currency: None,
+                exchange_rate: None,
+                description: None,
+                category: None,
+                external_reference: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    let token = login_token(&app, "linkheader").await;
+    let (status, headers, body) = send_request(
+        &app,
+        Method::GET,
+        &format!(
+            "/api/v1/transactions/account/{}?limit=2&fields=id",
+            account_id
+        ),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["items"].as_array().unwrap().len(), 2);
+    assert_eq!(body["data"]["total_count"], 5);
+    assert_eq!(body["data"]["limit"], 2);
+    assert_eq!(body["data"]["offset"], 0);
+
+    // First page of five: next and last exist, prev does not
+    let link = headers.get(header::LINK).unwrap().to_str().unwrap();
+    let path = format!("/api/v1/transactions/account/{}", account_id);
+    assert!(link.contains(&format!(
+        "<{}?fields=id&limit=2&offset=2>; rel=\"next\"",
+        path
+    )));
+    assert!(link.contains(&format!(
+        "<{}?fields=id&limit=2&offset=4>; rel=\"last\"",
+        path
+    )));
+    assert!(link.contains(&format!(
+        "<{}?fields=id&limit=2&offset=0>; rel=\"first\"",
+        path
+    )));
+    assert!(!link.contains("rel=\"prev\""));
+    // v1 carries the total in the body; only the envelope-free v2 API sends the header
+    assert!(headers.get("x-total-count").is_none());
+
+    // The last page links back but not forward
+    let (_, headers, _) = send_request(
+        &app,
+        Method::GET,
+        &format!("{}?limit=2&offset=4", path),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    let link = headers.get(header::LINK).unwrap().to_str().unwrap();
+    assert!(link.contains(&format!("<{}?limit=2&offset=2>; rel=\"prev\"", path)));
+    assert!(!link.contains("rel=\"next\""));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_transaction_listing_filters() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let app = create_app(pool.clone(), &db_url);
+
+    let user = register_user(&user_service, "filterowner").await;
+    let payee = register_user(&user_service, "filterpayee").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account_id = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    let deposit = transaction_service
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let mut transfers = Vec::new();
+    for _ in 0..3 {
+        let transfer = transaction_service
+            .process_transfer(
+                TransferRequest {
+                    sender_account_id: account_id,
+                    receiver_account_id: payee_account_id,
+                    amount: Decimal::from(10),
+                    description: None,
+                    category: None,
+                    allow_duplicate: true,
+                },
+                &RequestContext::default(),
+            )
+            .await
+            .unwrap();
+        transfers.push(transfer.id);
+    }
+    transaction_service
+        .process_withdrawal(
+            WithdrawalRequest {
+                account_id,
+                amount: Decimal::from(5),
+                description: None,
+                category: None,
+                destination_id: None,
+                external_reference: None,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+
+    // The deposit and the first transfer happened in 2023
+    sqlx::query("UPDATE transactions SET created_at = '2023-06-01T12:00:00Z' WHERE id = ANY($1)")
+        .bind(vec![deposit.id, transfers[0]])
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let token = login_token(&app, "filterowner").await;
+    let list = |query: &str| {
+        let app = app.clone();
+        let token = token.clone();
+        let uri = format!("/api/v2/transactions/account/{}?{}", account_id, query);
+        async move {
+            let (status, headers, body) =
+                send_request(&app, Method::GET, &uri, Some(&token), &[], None).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            let total = headers["x-total-count"].to_str().unwrap().to_string();
+            let ids: Vec<String> = body
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| t["id"].as_str().unwrap().to_string())
+                .collect();
+            (ids, total)
+        }
+    };
+
+    // Type and start time combined: only this year's transfers, newest first
+    let (ids, total) = list("transaction_type=TRANSFER&from=2024-01-01T00:00:00Z").await;
+    assert_eq!(
+        ids,
+        vec![transfers[2].to_string(), transfers[1].to_string()]
+    );
+    assert_eq!(total, "2");
+
+    // A closed window with a status
+    let (ids, _) = list("from=2023-01-01T00:00:00Z&to=2023-12-31T23:59:59Z&status=COMPLETED").await;
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&deposit.id.to_string()));
+
+    // Filters that match nothing give an empty page, not an error
+    let (ids, total) = list("status=FAILED").await;
+    assert!(ids.is_empty());
+    assert_eq!(total, "0");
+    let (ids, _) = list("transaction_type=DEPOSIT&from=2024-01-01T00:00:00Z").await;
+    assert!(ids.is_empty());
+    let (ids, _) = list("to=2020-01-01T00:00:00Z").await;
+    assert!(ids.is_empty());
+
+    // No filters: everything
+    let (ids, total) = list("").await;
+    assert_eq!(ids.len(), 5);
+    assert_eq!(total, "5");
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        &format!(
+            "/api/v1/transactions/account/{}?from=2024-02-01T00:00:00Z&to=2024-01-01T00:00:00Z",
+            account_id
+        ),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    teardown(&db_url).await;
+}
+#[tokio::test]
+async fn test_account_transactions_filter_by_amount_and_time() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let accounts = funded_accounts(&pool, &["amountfilter", "amountpayee"], 1000).await;
+    let (account_id, payee_account_id) = (accounts[0], accounts[1]);
+    let withdraw = |amount: i64| {
+        let transaction_service = &transaction_service;
+        async move {
+            transaction_service
+                .process_withdrawal(
+                    WithdrawalRequest {
+                        account_id,
+                        amount: Decimal::from(amount),
+                        description: None,
+                        category: None,
+                        destination_id: None,
+                        external_reference: None,
+                    },
+                    &RequestContext::default(),
+                )
+                .await
+                .unwrap()
+                .id
+        }
+    };
+    let large = withdraw(150).await;
+    let small = withdraw(50).await;
+    let april = withdraw(200).await;
+    let failed = withdraw(300).await;
+    let transfer = transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account_id,
+                receiver_account_id: payee_account_id,
+                amount: Decimal::from(120),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap()
+        .id;
+
+    // Everything but the deposit happened in March, except one withdrawal
+    // booked right as April began; one of the March withdrawals failed
+    sqlx::query("UPDATE transactions SET created_at = '2024-03-10T12:00:00Z' WHERE id = ANY($1)")
+        .bind(vec![large, small, failed, transfer])
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE transactions SET created_at = '2024-04-01T00:00:00Z' WHERE id = $1")
+        .bind(april)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE transactions SET status = 'FAILED' WHERE id = $1")
+        .bind(failed)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let token = login_token(&app, "amountfilter").await;
+    let list = |query: &str| {
+        let app = app.clone();
+        let token = token.clone();
+        let uri = format!("/api/v2/transactions/account/{}?{}", account_id, query);
+        async move {
+            let (status, _, body) =
+                send_request(&app, Method::GET, &uri, Some(&token), &[], None).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            let mut ids: Vec<Uuid> = body
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| Uuid::parse_str(t["id"].as_str().unwrap()).unwrap())
+                .collect();
+            ids.sort();
+            ids
+        }
+    };
+    let sorted = |mut ids: Vec<Uuid>| {
+        ids.sort();
+        ids
+    };
+
+    // Each filter on its own; amount bounds are inclusive
+    assert_eq!(list("min_amount=200").await.len(), 3);
+    assert_eq!(list("max_amount=120").await, sorted(vec![small, transfer]));
+    assert_eq!(list("status=FAILED").await, vec![failed]);
+    assert_eq!(list("transaction_type=WITHDRAWAL").await.len(), 4);
+    assert_eq!(list("from=2024-04-01T00:00:00Z").await.len(), 2);
+    assert_eq!(
+        list("to=2024-04-01T00:00:00Z").await,
+        sorted(vec![large, small, failed, transfer])
+    );
+
+    // `from` is inclusive and `to` exclusive, so months don't overlap
+    assert_eq!(
+        list("from=2024-04-01T00:00:00Z&to=2024-05-01T00:00:00Z").await,
+        vec![april]
+    );
+    assert_eq!(
+        list("from=2024-03-01T00:00:00Z&to=2024-04-01T00:00:00Z").await,
+        sorted(vec![large, small, failed, transfer])
+    );
+
+    // Completed withdrawals in March over 100
+    assert_eq!(
+        list(
+            "transaction_type=WITHDRAWAL&status=COMPLETED&from=2024-03-01T00:00:00Z\
+             &to=2024-04-01T00:00:00Z&min_amount=100"
+        )
+        .await,
+        vec![large]
+    );
+
+    // Unknown types and statuses are validation errors, inverted ranges bad requests
+    for (query, error) in [
+        ("transaction_type=PAYMENT", "VALIDATION_ERROR"),
+        ("status=completed", "VALIDATION_ERROR"),
+        ("min_amount=100&max_amount=50", "BAD_REQUEST"),
+    ] {
+        let (status, _, body) = send_request(
+            &app,
+            Method::GET,
+            &format!("/api/v1/transactions/account/{}?{}", account_id, query),
+            Some(&token),
+            &[],
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert_eq!(body["error"], error);
+    }
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_account_transactions_cursor_pagination() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let account_id = funded_accounts(&pool, &["cursorpager"], 1000).await[0];
+    let withdraw = |amount: i64| {
+        let transaction_service = &transaction_service;
+        async move {
+            transaction_service
+                .process_withdrawal(
+                    WithdrawalRequest {
+                        account_id,
+                        amount: Decimal::from(amount),
+                        description: None,
+                        category: None,
+                        destination_id: None,
+                        external_reference: None,
+                    },
+                    &RequestContext::default(),
+                )
+                .await
+                .unwrap()
+                .id
+        }
+    };
+    for amount in [10, 20, 30, 40] {
+        withdraw(amount).await;
+    }
+
+    // Rows sharing a timestamp must still be split across pages exactly once
+    sqlx::query("UPDATE transactions SET created_at = '2024-03-10T12:00:00Z'")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let token = login_token(&app, "cursorpager").await;
+    let page = |query: String| {
+        let app = app.clone();
+        let token = token.clone();
+        let uri = format!("/api/v1/transactions/account/{}?{}", account_id, query);
+        async move {
+            let (status, _, body) =
+                send_request(&app, Method::GET, &uri, Some(&token), &[], None).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            let ids: Vec<String> = body["data"]["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| t["id"].as_str().unwrap().to_string())
+                .collect();
+            (
+                ids,
+                body["data"]["next_cursor"].as_str().map(str::to_string),
+            )
+        }
+    };
+
+    let (mut seen, mut cursor) = page("limit=2".to_string()).await;
+    assert_eq!(seen.len(), 2);
+
+    // A transaction arriving mid-listing is newer than the cursor and not picked up
+    let newcomer = withdraw(50).await;
+
+    while let Some(next) = cursor {
+        let (ids, next_cursor) = page(format!("limit=2&cursor={}", next)).await;
+        assert!(!ids.is_empty());
+        seen.extend(ids);
+        cursor = next_cursor;
+    }
+    assert_eq!(seen.len(), 5);
+    let mut unique = seen.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), 5);
+    assert!(!seen.contains(&newcomer.to_string()));
+
+    // The v2 tree returns the cursor as a header
+    let (status, headers, body) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v2/transactions/account/{}?limit=5", account_id),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_array().unwrap().len(), 5);
+    let next = headers["x-next-cursor"].to_str().unwrap().to_string();
+    let (ids, last_cursor) = page(format!("limit=5&cursor={}", next)).await;
+    assert_eq!(ids.len(), 1);
+    assert_eq!(last_cursor, None);
+
+    // Anything that doesn't decode to a cursor is rejected
+    for cursor in ["not-a-cursor!", "Zm9vfGJhcg"] {
+        let (status, _, body) = send_request(
+            &app,
+            Method::GET,
+            &format!(
+                "/api/v1/transactions/account/{}?cursor={}",
+                account_id, cursor
+            ),
+            Some(&token),
+            &[],
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert_eq!(body["error"], "BAD_REQUEST");
+    }
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_account_history_carries_running_balance() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let accounts = funded_accounts(&pool, &["runner", "runpeer"], 100).await;
+    let (account_id, peer_id) = (accounts[0], accounts[1]);
+    transaction_service
+        .process_withdrawal(
+            WithdrawalRequest {
+                account_id,
+                amount: Decimal::from(30),
+                description: None,
+                category: None,
+                destination_id: None,
+                external_reference: None,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    for (sender_account_id, receiver_account_id, amount) in
+        [(account_id, peer_id, 20), (peer_id, account_id, 5)]
+    {
+        transaction_service
+            .process_transfer(
+                TransferRequest {
+                    sender_account_id,
+                    receiver_account_id,
+                    amount: Decimal::from(amount),
+                    description: None,
+                    category: None,
+                    allow_duplicate: false,
+                },
+                &RequestContext::default(),
+            )
+            .await
+            .unwrap();
+    }
+
+    // Newest first: +5, -20, -30, +100
+    let token = login_token(&app, "runner").await;
+    let balances = |query: &'static str| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            let (status, _, body) = send_request(
+                &app,
+                Method::GET,
+                &format!("/api/v1/transactions/account/{}?{}", account_id, query),
+                Some(&token),
+                &[],
+                None,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            body["data"]["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| Decimal::from_str(t["balance_after"].as_str().unwrap()).unwrap())
+                .collect::<Vec<_>>()
+        }
+    };
+    let expected = |values: &[i64]| values.iter().map(|v| Decimal::from(*v)).collect::<Vec<_>>();
+    assert_eq!(balances("").await, expected(&[55, 50, 70, 100]));
+
+    // Later pages and filtered listings still start from the real balance
+    assert_eq!(balances("limit=2&offset=2").await, expected(&[70, 100]));
+    assert_eq!(
+        balances("transaction_type=TRANSFER").await,
+        expected(&[55, 50])
+    );
+
+    // The counterparty sees the same transfers from its own side
+    let peer_history = transaction_service
+        .get_transactions_by_account_id(peer_id, &Default::default(), None, None, None)
+        .await
+        .unwrap();
+    let peer_balances: Vec<Decimal> = peer_history
+        .iter()
+        .map(|t| t.balance_after.unwrap())
+        .collect();
+    assert_eq!(peer_balances, expected(&[115, 120, 100]));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_list_my_transactions_across_accounts() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let accounts = funded_accounts(&pool, &["mineowner", "mineother"], 100).await;
+    let (main_id, other_id) = (accounts[0], accounts[1]);
+    let owner_id = account_service
+        .get_account_by_id(main_id)
+        .await
+        .unwrap()
+        .user_id;
+    let savings_id = account_service
+        .create_account(owner_id, "USD".to_string())
+        .await
+        .unwrap()
+        .id;
+    for (sender_account_id, receiver_account_id, amount) in [
+        (main_id, savings_id, 30),
+        (main_id, other_id, 10),
+        (other_id, savings_id, 5),
+    ] {
+        transaction_service
+            .process_transfer(
+                TransferRequest {
+                    sender_account_id,
+                    receiver_account_id,
+                    amount: Decimal::from(amount),
+                    description: None,
+                    category: None,
+                    allow_duplicate: false,
+                },
+                &RequestContext::default(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let token = login_token(&app, "mineowner").await;
+    let rows = |query: &'static str| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            let (status, _, body) = send_request(
+                &app,
+                Method::GET,
+                &format!("/api/v1/transactions/mine?{}", query),
+                Some(&token),
+                &[],
+                None,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            body["data"].clone()
+        }
+    };
+    let sides = |data: &serde_json::Value| {
+        data["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| {
+                (
+                    Uuid::parse_str(t["account_id"].as_str().unwrap()).unwrap(),
+                    t["direction"].as_str().unwrap().to_string(),
+                    Decimal::from_str(t["amount"].as_str().unwrap()).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let side = |account_id: Uuid, direction: &str, amount: i64| {
+        (account_id, direction.to_string(), Decimal::from(amount))
+    };
+
+    // Newest first; the transfer between the owner's own accounts shows from both sides
+    let all = rows("").await;
+    assert_eq!(all["total_count"], 5);
+    assert_eq!(
+        sides(&all),
+        [
+            side(savings_id, "INCOMING", 5),
+            side(main_id, "OUTGOING", 10),
+            side(main_id, "OUTGOING", 30),
+            side(savings_id, "INCOMING", 30),
+            side(main_id, "INCOMING", 100),
+        ]
+    );
+
+    let page = rows("limit=2&offset=2").await;
+    assert_eq!(
+        sides(&page),
+        [
+            side(main_id, "OUTGOING", 30),
+            side(savings_id, "INCOMING", 30)
+        ]
+    );
+
+    // Filters apply as for a single account; the other user's deposit is not listed
+    let deposits = rows("transaction_type=DEPOSIT").await;
+    assert_eq!(deposits["total_count"], 1);
+    assert_eq!(sides(&deposits), [side(main_id, "INCOMING", 100)]);
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/transactions/mine?cursor=abc",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_account_summary_totals_listed_transactions() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let accounts = funded_accounts(&pool, &["sumowner", "sumpeer"], 100).await;
+    let (owner_id, peer_id) = (accounts[0], accounts[1]);
+    for (sender_account_id, receiver_account_id, amount) in
+        [(owner_id, peer_id, "30"), (peer_id, owner_id, "12.50")]
+    {
+        transaction_service
+            .process_transfer(
+                TransferRequest {
+                    sender_account_id,
+                    receiver_account_id,
+                    amount: Decimal::from_str(amount).unwrap(),
+                    description: None,
+                    category: None,
+                    allow_duplicate: false,
+                },
+                &RequestContext::default(),
+            )
+            .await
+            .unwrap();
+    }
+    transaction_service
+        .process_withdrawal(
+            WithdrawalRequest {
+                account_id: owner_id,
+                amount: Decimal::from_str("20.25").unwrap(),
+                description: None,
+                category: None,
+                destination_id: None,
+                external_reference: None,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+
+    // A failed transfer, and a deposit from before the summarized period
+    sqlx::query(
+        r#"
+        INSERT INTO transactions
+            (id, sender_account_id, receiver_account_id, amount, currency, transaction_type,
+             status, created_at, updated_at)
+        VALUES (gen_random_uuid(), $1, $2, 5, 'USD', 'TRANSFER', 'FAILED', NOW(), NOW()),
+               (gen_random_uuid(), NULL, $1, 1000, 'USD', 'DEPOSIT', 'COMPLETED',
+                TIMESTAMPTZ '2020-06-01 00:00:00+00', TIMESTAMPTZ '2020-06-01 00:00:00+00')
+        "#,
+    )
+    .bind(owner_id)
+    .bind(peer_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let token = login_token(&app, "sumowner").await;
+    let get = |uri: String| {
+        let app = app.clone();
+        let token = token.clone();
+        async move { send_request(&app, Method::GET, &uri, Some(&token), &[], None).await }
+    };
+    let decimal = |value: &serde_json::Value| Decimal::from_str(value.as_str().unwrap()).unwrap();
+    type Totals = BTreeMap<(String, String), (i64, Decimal, Decimal)>;
+    let summary_totals = |data: &serde_json::Value| -> Totals {
+        data["totals"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|group| {
+                let (amount_in, amount_out) =
+                    (decimal(&group["amount_in"]), decimal(&group["amount_out"]));
+                assert_eq!(decimal(&group["net_change"]), amount_in - amount_out);
+                (
+                    (
+                        group["transaction_type"].as_str().unwrap().to_string(),
+                        group["status"].as_str().unwrap().to_string(),
+                    ),
+                    (group["count"].as_i64().unwrap(), amount_in, amount_out),
+                )
+            })
+            .collect()
+    };
+
+    let period = "from=2021-01-01T00:00:00Z";
+    let (status, _, summary) = get(format!(
+        "/api/v1/transactions/account/{}/summary?{}",
+        owner_id, period
+    ))
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", summary);
+    assert_eq!(summary["data"]["currency"], "USD");
+    let (status, _, listing) = get(format!(
+        "/api/v1/transactions/account/{}?limit=1000&{}",
+        owner_id, period
+    ))
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", listing);
+
+    // The summary adds up the listed transactions of the same period
+    let mut listed = Totals::new();
+    for transaction in listing["data"]["items"].as_array().unwrap() {
+        let totals = listed
+            .entry((
+                transaction["transaction_type"]
+                    .as_str()
+                    .unwrap()
+                    .to_string(),
+                transaction["status"].as_str().unwrap().to_string(),
+            ))
+            .or_insert((0, Decimal::ZERO, Decimal::ZERO));
+        totals.0 += 1;
+        if transaction["receiver_account_id"] == owner_id.to_string() {
+            totals.1 += decimal(&transaction["amount"]);
+        } else {
+            totals.2 += decimal(&transaction["amount"]);
+        }
+    }
+    assert_eq!(summary_totals(&summary["data"]), listed);
+
+    let group = |data: &serde_json::Value, transaction_type: &str, status: &str| {
+        summary_totals(data)[&(transaction_type.to_string(), status.to_string())]
+    };
+    assert_eq!(
+        group(&summary["data"], "TRANSFER", "COMPLETED"),
+        (2, Decimal::from_str("12.5").unwrap(), Decimal::from(30))
+    );
+    assert_eq!(
+        group(&summary["data"], "TRANSFER", "FAILED"),
+        (1, Decimal::ZERO, Decimal::from(5))
+    );
+    assert_eq!(
+        group(&summary["data"], "WITHDRAWAL", "COMPLETED"),
+        (1, Decimal::ZERO, Decimal::from_str("20.25").unwrap())
+    );
+
+    // Without a period every transaction counts
+    let (_, _, summary) = get(format!("/api/v1/transactions/account/{}/summary", owner_id)).await;
+    assert_eq!(
+        group(&summary["data"], "DEPOSIT", "COMPLETED"),
+        (2, Decimal::from(1100), Decimal::ZERO)
+    );
+
+    let (status, _, _) = get(format!(
+        "/api/v1/transactions/account/{}/summary?from=2024-02-01T00:00:00Z&to=2024-01-01T00:00:00Z",
+        owner_id
+    ))
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let peer_token = login_token(&app, "sumpeer").await;
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/transactions/account/{}/summary", owner_id),
+        Some(&peer_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_search_transactions_by_description() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let account_id = funded_accounts(&pool, &["searcher"], 10).await[0];
+    let mut ids = Vec::new();
+    for description in [
+        "Rent March",
+        "Miete für Wohnung – ÜBERWEISUNG",
+        "50% off_sale",
+        "500 off sale",
+        "RENT april",
+    ] {
+        let deposit = transaction_service
+            .process_deposit(DepositRequest {
+                account_id,
+                amount: Decimal::from(1),
+                currency: None,
+                exchange_rate: None,
+                description: Some(description.to_string()),
+                category: None,
+                external_reference: None,
+            })
+            .await
+            .unwrap();
+        ids.push(deposit.id);
+    }
+    let search = |query: &'static str| {
+        let transaction_service = &transaction_service;
+        async move {
+            transaction_service
+                .search_transactions(account_id, query, None, None)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|t| t.id)
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // Case-insensitive substring matches, newest first
+    assert_eq!(search("rent").await, vec![ids[4], ids[0]]);
+    assert_eq!(search("überweisung").await, vec![ids[1]]);
+    assert_eq!(search("FÜR wohnung").await, vec![ids[1]]);
+
+    // Wildcard characters in the query match only themselves
+    assert_eq!(search("50%").await, vec![ids[2]]);
+    assert_eq!(search("off_sale").await, vec![ids[2]]);
+    assert_eq!(search("%").await, vec![ids[2]]);
+    assert!(search("nothing like this").await.is_empty());
+
+    // The listing accepts the search as `q`, alongside its other filters
+    let token = login_token(&app, "searcher").await;
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/transactions/account/{}?q=Rent&limit=1", account_id),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["total_count"], 2);
+    assert_eq!(body["data"]["items"][0]["description"], "RENT april");
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_admin_lists_all_transactions() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let accounts = funded_accounts(&pool, &["listadmin", "listalice", "listbob"], 100).await;
+    promote_to_admin(&pool, "listadmin").await;
+    transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: accounts[1],
+                receiver_account_id: accounts[2],
+                amount: Decimal::from(30),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+
+    // Regular users are turned away
+    let user_token = login_token(&app, "listalice").await;
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/admin/transactions",
+        Some(&user_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    // Administrators see every user's transactions, newest first
+    let admin_token = login_token(&app, "listadmin").await;
+    let list = |query: &'static str| {
+        let app = app.clone();
+        let token = admin_token.clone();
+        async move {
+            let (status, _, body) = send_request(
+                &app,
+                Method::GET,
+                &format!("/api/v1/admin/transactions{}", query),
+                Some(&token),
+                &[],
+                None,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            body["data"].clone()
+        }
+    };
+    let page = list("").await;
+    assert_eq!(page["total_count"], 4);
+    assert_eq!(page["items"][0]["transaction_type"], "TRANSFER");
+
+    // The usual filters and paging apply
+    let page = list("?transaction_type=DEPOSIT&limit=2&offset=2").await;
+    assert_eq!(page["total_count"], 3);
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_transaction_origin_visible_only_to_admins() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    // Requests arrive through a proxy at 10.0.0.1, which is trusted
+    let mut config = test_config(&db_url);
+    config.trusted_proxies = "10.0.0.0/8".parse().unwrap();
+    let proxied_app = create_router(&config, pool.clone())
+        .layer(MockConnectInfo(SocketAddr::from(([10, 0, 0, 1], 443))));
+    // A client connecting directly tries to spoof its address
+    let direct_app = create_router(&config, pool.clone())
+        .layer(MockConnectInfo(SocketAddr::from(([203, 0, 113, 7], 50000))));
+
+    let sender = register_user(&user_service, "originsender").await;
+    let receiver = register_user(&user_service, "originreceiver").await;
+    register_user(&user_service, "originadmin").await;
+    promote_to_admin(&pool, "originadmin").await;
+
+    let sender_account = account_service
+        .get_accounts_by_user_id(sender.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let receiver_account = account_service
+        .get_accounts_by_user_id(receiver.id)
+        .await
+        .unwrap()[0]
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: sender_account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    let sender_token = login_token(&proxied_app, "originsender").await;
+    let (status, _, body) = send_request(
+        &proxied_app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&sender_token),
+        &[
+            ("X-Forwarded-For", "198.51.100.23"),
+            ("User-Agent", "origin-test/1.0"),
+        ],
+        Some(serde_json::json!({
+            "sender_account_id": sender_account,
+            "receiver_account_id": receiver_account,
+            "amount": "10.00"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "Transfer failed: {}", body);
+    assert!(body["data"].get("ip_address").is_none());
+    let proxied_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, _, body) = send_request(
+        &direct_app,
+        Method::POST,
+        "/api/v1/transactions/withdrawal",
+        Some(&sender_token),
+        &[("X-Forwarded-For", "198.51.100.99")],
+        Some(serde_json::json!({ "account_id": sender_account, "amount": "5.00" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "Withdrawal failed: {}", body);
+    let direct_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // Admins see where each transaction came from
+    let admin_token = login_token(&proxied_app, "originadmin").await;
+    let (status, _, body) = send_request(
+        &proxied_app,
+        Method::GET,
+        &format!("/api/v1/admin/transactions/{}", proxied_id),
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["ip_address"], "198.51.100.23");
+    assert_eq!(body["data"]["user_agent"], "origin-test/1.0");
+    assert_eq!(body["data"]["id"], proxied_id.as_str());
+
+    let (_, _, body) = send_request(
+        &proxied_app,
+        Method::GET,
+        &format!("/api/v1/admin/transactions/{}", direct_id),
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(body["data"]["ip_address"], "203.0.113.7");
+
+    // Neither the initiator nor the counterparty can see the origin
+    let receiver_token = login_token(&proxied_app, "originreceiver").await;
+    for token in [&sender_token, &receiver_token] {
+        let (status, _, body) = send_request(
+            &proxied_app,
+            Method::GET,
+            &format!("/api/v1/transactions/{}", proxied_id),
+            Some(token),
+            &[],
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["data"].get("ip_address").is_none());
+        assert!(body["data"].get("user_agent").is_none());
+
+        let (status, _, _) = send_request(
+            &proxied_app,
+            Method::GET,
+            &format!("/api/v1/admin/transactions/{}", proxied_id),
+            Some(token),
+            &[],
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_amounts_normalized_to_currency_scale() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "amountscale").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    // 10.1 and 10.10 are the same amount and must look the same everywhere
+    let mut responses = Vec::new();
+    for amount in ["10.1", "10.10"] {
+        let response = transaction_service
+            .process_deposit(DepositRequest {
+                account_id,
+                amount: Decimal::from_str(amount).unwrap(),
+                currency: None,
+                exchange_rate: None,
+                description: None,
+                category: None,
+                external_reference: None,
+            })
+            .await
+            .unwrap();
+        responses.push(response);
+    }
+
+    for response in &responses {
+        assert_eq!(serde_json::to_value(response).unwrap()["amount"], "10.10");
+
+        let fetched = transaction_service
+            .get_transaction_by_id(response.id)
+            .await
+            .unwrap();
+        assert_eq!(serde_json::to_value(&fetched).unwrap()["amount"], "10.10");
+    }
+
+    let stored: Vec<String> =
+        sqlx::query_scalar("SELECT amount::TEXT FROM transactions WHERE id = ANY($1)")
+            .bind(responses.iter().map(|r| r.id).collect::<Vec<_>>())
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(stored.len(), 2);
+    assert_eq!(stored[0], stored[1]);
+
+    // Zero-decimal currencies drop the fraction entirely
+    let yen_account = account_service
+        .create_account(user.id, "JPY".to_string())
+        .await
+        .unwrap();
+    let response = transaction_service
+        .process_deposit(DepositRequest {
+            account_id: yen_account.id,
+            amount: Decimal::from_str("1500.0").unwrap(),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(serde_json::to_value(&response).unwrap()["amount"], "1500");
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_amounts_accepted_in_minor_units() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let app = create_app(pool.clone(), &db_url);
+
+    let user = register_user(&user_service, "minorunits").await;
+    let payee = register_user(&user_service, "minorpayee").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account_id = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let yen_account = account_service
+        .create_account(user.id, "JPY".to_string())
+        .await
+        .unwrap();
+    let token = login_token(&app, "minorunits").await;
+    let post = |path: &str, units: Option<&'static str>, body: serde_json::Value| {
+        let app = app.clone();
+        let token = token.clone();
+        let uri = format!("/api/v1/transactions/{}", path);
+        async move {
+            let headers: Vec<(&str, &str)> = units
+                .map(|units| vec![("Amount-Units", units)])
+                .unwrap_or_default();
+            send_request(&app, Method::POST, &uri, Some(&token), &headers, Some(body)).await
+        }
+    };
+
+    // 1050 cents is 10.50 USD, while 1050 yen stays 1050
+    let (status, _, body) = post(
+        "deposit",
+        Some("minor"),
+        serde_json::json!({ "account_id": account_id, "amount": 1050 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["amount"], "10.50");
+    let (status, _, body) = post(
+        "deposit",
+        Some("minor"),
+        serde_json::json!({ "account_id": yen_account.id, "amount": 1050 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["amount"], "1050");
+
+    let (status, _, body) = post(
+        "transfer",
+        Some("minor"),
+        serde_json::json!({
+            "sender_account_id": account_id,
+            "receiver_account_id": payee_account_id,
+            "amount": "250"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["amount"], "2.50");
+    assert_eq!(
+        account_service
+            .get_account_by_id(account_id)
+            .await
+            .unwrap()
+            .balance,
+        Decimal::from_str("8.00").unwrap()
+    );
+
+    // Without the header amounts are major units, as before
+    let (status, _, body) = post(
+        "withdrawal",
+        None,
+        serde_json::json!({ "account_id": account_id, "amount": "1" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["amount"], "1.00");
+
+    // Minor units are whole numbers, and the header has two values
+    let (status, _, _) = post(
+        "withdrawal",
+        Some("minor"),
+        serde_json::json!({ "account_id": account_id, "amount": "10.5" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _, _) = post(
+        "withdrawal",
+        Some("cents"),
+        serde_json::json!({ "account_id": account_id, "amount": "1" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    teardown(&db_url).await;
+}
+#[tokio::test]
+async fn test_strict_amount_precision_rejects_excess_digits() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let strict_service = create_transaction_service(pool.clone());
+    let lenient_service = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            strict_amount_precision: false,
+            ..Default::default()
+        },
+    );
+
+    let user = register_user(&user_service, "strictscale").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let deposit = |amount: &str| DepositRequest {
+        account_id,
+        amount: Decimal::from_str(amount).unwrap(),
+        currency: None,
+        exchange_rate: None,
+        description: None,
+        category: None,
+        external_reference: None,
+    };
+
+    // Trailing zeros are not extra precision
+    let response = strict_service
+        .process_deposit(deposit("5.1000"))
+        .await
+        .unwrap();
+    assert_eq!(response.amount.to_string(), "5.10");
+
+    // By default, the error names the currency and the decimal places it allows
+    match strict_service
+        .process_deposit(deposit("10.123456789"))
+        .await
+    {
+        Err(AppError::Validation(message)) => assert!(
+            message.contains("2 decimal places allowed for USD"),
+            "{}",
+            message
+        ),
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+    let rejected = strict_service
+        .process_withdrawal(
+            WithdrawalRequest {
+                account_id,
+                amount: Decimal::from_str("1.005").unwrap(),
+                description: None,
+                category: None,
+                destination_id: None,
+                external_reference: None,
+            },
+            &RequestContext::default(),
+        )
+        .await;
+    assert!(
+        matches!(rejected, Err(AppError::Validation(_))),
+        "{:?}",
+        rejected
+    );
+
+    // Without strict mode the amount is rounded half-even
+    let response = lenient_service
+        .process_deposit(deposit("1.005"))
+        .await
+        .unwrap();
+    assert_eq!(response.amount.to_string(), "1.00");
+
+    // An amount that rounds to nothing is rejected either way
+    let rejected = lenient_service.process_deposit(deposit("0.004")).await;
+    assert!(matches!(rejected, Err(AppError::BadRequest(_))));
+
+    let account = account_service.get_account_by_id(account_id).await.unwrap();
+    assert_eq!(account.balance, Decimal::from_str("6.10").unwrap());
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_payout_debits_account() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "payoutuser").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    let payout = transaction_service
+        .process_payout(
+            PayoutRequest {
+                account_id,
+                amount: Decimal::from_str("40.25").unwrap(),
+                description: Some("Supplier payout".to_string()),
+                category: None,
+                destination_id: None,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(payout.transaction_type, "WITHDRAWAL");
+    assert_eq!(payout.status, "COMPLETED");
+    assert_eq!(payout.sender_account_id, Some(account_id));
+    assert_eq!(payout.receiver_account_id, None);
+    assert_eq!(payout.amount, Decimal::from_str("40.25").unwrap());
+
+    let account = account_service.get_account_by_id(account_id).await.unwrap();
+    assert_eq!(account.balance, Decimal::from_str("59.75").unwrap());
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_payout_rolls_back_on_insufficient_funds() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "payoutshort").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(30),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    let result = transaction_service
+        .process_payout(
+            PayoutRequest {
+                account_id,
+                amount: Decimal::from(31),
+                description: None,
+                category: None,
+                destination_id: None,
+            },
+            &RequestContext::default(),
+        )
+        .await;
+    assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+    // Neither the balance nor the ledger shows any trace of the failed payout
+    let account = account_service.get_account_by_id(account_id).await.unwrap();
+    assert_eq!(account.balance, Decimal::from(30));
+    let withdrawals: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE sender_account_id = $1")
+            .bind(account_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(withdrawals, 0);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_generic_transaction_currency_comes_from_accounts() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let sender = register_user(&user_service, "genericsender").await;
+    let receiver = register_user(&user_service, "genericreceiver").await;
+    let sender_account = account_service
+        .get_accounts_by_user_id(sender.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let receiver_account = account_service
+        .get_accounts_by_user_id(receiver.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    let request = |transaction_type: &str, currency: Option<&str>| CreateTransactionRequest {
+        transaction_type: transaction_type.to_string(),
+        sender_account_id: (transaction_type != "DEPOSIT").then_some(sender_account),
+        receiver_account_id: (transaction_type != "WITHDRAWAL").then_some(
+            if transaction_type == "DEPOSIT" {
+                sender_account
+            } else {
+                receiver_account
+            },
+        ),
+        amount: Decimal::from(10),
+        currency: currency.map(str::to_string),
+        description: None,
+        category: None,
+        allow_duplicate: false,
+    };
+    let context = RequestContext::default();
+
+    // Matching (in any case) and omitted currencies are accepted
+    let deposit = transaction_service
+        .create_transaction(request("DEPOSIT", Some("usd")), &context)
+        .await
+        .unwrap();
+    assert_eq!(deposit.currency, "USD");
+    transaction_service
+        .create_transaction(request("DEPOSIT", None), &context)
+        .await
+        .unwrap();
+    let transfer = transaction_service
+        .create_transaction(request("TRANSFER", None), &context)
+        .await
+        .unwrap();
+    assert_eq!(transfer.currency, "USD");
+
+    // A currency that disagrees with the account is rejected for every type
+    for transaction_type in ["DEPOSIT", "TRANSFER", "WITHDRAWAL"] {
+        let result = transaction_service
+            .create_transaction(request(transaction_type, Some("EUR")), &context)
+            .await;
+        match result {
+            Err(AppError::BadRequest(message)) => assert!(
+                message.contains("does not match account currency USD"),
+                "{}: {}",
+                transaction_type,
+                message
+            ),
+            other => panic!(
+                "{}: expected a currency mismatch, got {:?}",
+                transaction_type, other
+            ),
+        }
+    }
+
+    // Nothing was booked by the rejected requests
+    let sender_balance = account_service
+        .get_account_by_id(sender_account)
+        .await
+        .unwrap()
+        .balance;
+    assert_eq!(sender_balance, Decimal::from(10));
+
+    // Deployments may choose to ignore the field altogether
+    let lenient_service = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            ignore_request_currency: true,
+            ..TransactionConfig::default()
+        },
+    );
+    let withdrawal = lenient_service
+        .create_transaction(request("WITHDRAWAL", Some("EUR")), &context)
+        .await
+        .unwrap();
+    assert_eq!(withdrawal.currency, "USD");
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_description_is_stored_verbatim() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "injectuser").await;
+    let payee = register_user(&user_service, "injectpayee").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    // Quotes, backslashes, dollar quoting and control characters around NUL
+    let descriptions = [
+        "'); DROP TABLE transactions; --",
+        r#"It's a "test" \' with backslashes \\"#,
+        "$$; DELETE FROM accounts; $$ $tag$ body $tag$",
+        "E'\\x00' \u{1}start\u{7}bell\u{1b}[31mred\u{7f}end",
+        "line one\nline two\r\n\ttabbed",
+    ];
+    let mut ids = Vec::new();
+    for description in descriptions {
+        let deposit = transaction_service
+            .process_deposit(DepositRequest {
+                account_id: account,
+                amount: Decimal::from(20),
+                currency: None,
+                exchange_rate: None,
+                description: Some(description.to_string()),
+                category: None,
+                external_reference: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(deposit.description.as_deref(), Some(description));
+        ids.push(deposit.id);
+    }
+
+    let transfer = transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: account,
+                receiver_account_id: payee_account,
+                amount: Decimal::from(10),
+                description: Some(descriptions[1].to_string()),
+                category: None,
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    ids.push(transfer.id);
+
+    // Read back from the database rather than from the responses
+    let expected = descriptions.iter().chain([&descriptions[1]]);
+    for (id, description) in ids.iter().zip(expected) {
+        let stored = transaction_service
+            .get_transaction_by_id(*id)
+            .await
+            .unwrap();
+        assert_eq!(stored.description.as_deref(), Some(*description));
+    }
+
+    // The table is intact and balances moved as usual
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 6);
+    let balance = account_service
+        .get_account_by_id(account)
+        .await
+        .unwrap()
+        .balance;
+    assert_eq!(balance, Decimal::from(90));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_split_transfer_pays_every_leg() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let app = create_app(pool.clone(), &db_url);
+
+    let payer = register_user(&user_service, "splitpayer").await;
+    let mut payees = Vec::new();
+    for username in ["splitpayee1", "splitpayee2", "splitpayee3"] {
+        let user = register_user(&user_service, username).await;
+        payees.push(
+            account_service
+                .get_accounts_by_user_id(user.id)
+                .await
+                .unwrap()[0]
+                .id,
+        );
+    }
+    let source = account_service
+        .get_accounts_by_user_id(payer.id)
+        .await
+        .unwrap()[0]
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: source,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    let split = transaction_service
+        .process_split_transfer(
+            SplitTransferRequest {
+                sender_account_id: source,
+                legs: vec![
+                    SplitTransferLeg {
+                        receiver_account_id: payees[0],
+                        amount: Decimal::from_str("10.50").unwrap(),
+                    },
+                    SplitTransferLeg {
+                        receiver_account_id: payees[1],
+                        amount: Decimal::from(20),
+                    },
+                    SplitTransferLeg {
+                        receiver_account_id: payees[2],
+                        amount: Decimal::from_str("69.5").unwrap(),
+                    },
+                ],
+                description: Some("Dinner".to_string()),
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(split.total, Decimal::from(100));
+    assert_eq!(split.legs.len(), 3);
+    assert!(split
+        .legs
+        .iter()
+        .all(|leg| leg.status == "COMPLETED" && leg.sender_account_id == Some(source)));
+
+    // The source pays the total once and each payee receives their share
+    let balance = |id| {
+        let account_service = &account_service;
+        async move { account_service.get_account_by_id(id).await.unwrap().balance }
+    };
+    assert_eq!(balance(source).await, Decimal::ZERO);
+    assert_eq!(
+        balance(payees[0]).await,
+        Decimal::from_str("10.50").unwrap()
+    );
+    assert_eq!(balance(payees[1]).await, Decimal::from(20));
+    assert_eq!(
+        balance(payees[2]).await,
+        Decimal::from_str("69.50").unwrap()
+    );
+
+    // Every leg is a transfer linked to the split
+    let linked: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM transactions WHERE split_transfer_id = $1 AND transaction_type = 'TRANSFER'",
+    )
+    .bind(split.id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(linked, 3);
+
+    // Only the owner of the source account may split from it
+    let token = login_token(&app, "splitpayee1").await;
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/split",
+        Some(&token),
+        &[],
+        Some(serde_json::json!({
+            "sender_account_id": source,
+            "legs": [{ "receiver_account_id": payees[0], "amount": "1" }]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_split_transfer_rolls_back_when_total_exceeds_funds() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let payer = register_user(&user_service, "splitshort").await;
+    let payee = register_user(&user_service, "splitshortpayee").await;
+    let source = account_service
+        .get_accounts_by_user_id(payer.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let euro_account = account_service
+        .create_account(payee.id, "EUR".to_string())
+        .await
+        .unwrap()
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: source,
+            amount: Decimal::from(50),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    let split = |legs: Vec<(Uuid, i64)>| SplitTransferRequest {
+        sender_account_id: source,
+        legs: legs
+            .into_iter()
+            .map(|(receiver_account_id, amount)| SplitTransferLeg {
+                receiver_account_id,
+                amount: Decimal::from(amount),
+            })
+            .collect(),
+        description: None,
+    };
+
+    // Each leg is affordable on its own, but not all of them together
+    let result = transaction_service
+        .process_split_transfer(
+            split(vec![(payee_account, 30), (payee_account, 21)]),
+            &RequestContext::default(),
+        )
+        .await;
+    assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+    // A leg in another currency, back to the source, or with no amount fails the whole split
+    for legs in [
+        vec![(payee_account, 10), (euro_account, 10)],
+        vec![(payee_account, 10), (source, 10)],
+        vec![(payee_account, 10), (payee_account, 0)],
+        vec![],
+    ] {
+        let result = transaction_service
+            .process_split_transfer(split(legs), &RequestContext::default())
+            .await;
+        assert!(result.is_err());
+    }
+
+    // Nothing was booked
+    let account = account_service.get_account_by_id(source).await.unwrap();
+    assert_eq!(account.balance, Decimal::from(50));
+    let payee_balance = account_service
+        .get_account_by_id(payee_account)
+        .await
+        .unwrap();
+    assert_eq!(payee_balance.balance, Decimal::ZERO);
+    let booked: (i64, i64) = sqlx::query_as(
+        "SELECT (SELECT COUNT(*) FROM transactions WHERE sender_account_id = $1), \
+                (SELECT COUNT(*) FROM split_transfers)",
+    )
+    .bind(source)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(booked, (0, 0));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_batch_transfer_books_all_or_nothing() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let accounts = funded_accounts(
+        &pool,
+        &["batchpayer", "batchpayee1", "batchpayee2", "batchpayee3"],
+        100,
+    )
+    .await;
+    let (source, payees) = (accounts[0], &accounts[1..]);
+    let token = login_token(&app, "batchpayer").await;
+    let balance = |id| {
+        let account_service = &account_service;
+        async move { account_service.get_account_by_id(id).await.unwrap().balance }
+    };
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/batch",
+        Some(&token),
+        &[],
+        Some(serde_json::json!({
+            "sender_account_id": source,
+            "transfers": [
+                { "receiver_account_id": payees[0], "amount": "30", "description": "Salary Alice" },
+                { "receiver_account_id": payees[1], "amount": "20.5", "description": "Salary Bob" },
+                { "receiver_account_id": payees[2], "amount": "10" }
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["total"], serde_json::json!({ "USD": "60.50" }));
+    let legs = body["data"]["transactions"].as_array().unwrap();
+    assert_eq!(legs.len(), 3);
+    assert!(legs
+        .iter()
+        .all(|leg| leg["sender_account_id"] == source.to_string()));
+    assert_eq!(legs[0]["description"], "Salary Alice");
+    assert_eq!(legs[1]["description"], "Salary Bob");
+    assert!(legs[2]["description"].is_null());
+    assert!(legs.iter().all(|leg| leg["status"] == "COMPLETED"));
+    assert_eq!(balance(source).await, Decimal::from_str("39.50").unwrap());
+    assert_eq!(
+        balance(payees[1]).await,
+        Decimal::from_str("120.50").unwrap()
+    );
+
+    // Only the owner of the source account may pay from it
+    let payee_token = login_token(&app, "batchpayee1").await;
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/batch",
+        Some(&payee_token),
+        &[],
+        Some(serde_json::json!({
+            "sender_account_id": source,
+            "transfers": [{ "receiver_account_id": payees[0], "amount": "1" }]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // A batch the source can't cover in full books nothing
+    let batch = |transfers: Vec<(Uuid, &str)>| {
+        transfers
+            .into_iter()
+            .map(|(receiver_account_id, amount)| TransferRequest {
+                sender_account_id: source,
+                receiver_account_id,
+                amount: Decimal::from_str(amount).unwrap(),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            })
+            .collect::<Vec<_>>()
+    };
+    let result = transaction_service
+        .process_batch_transfer(
+            batch(vec![(payees[0], "30"), (payees[1], "10")]),
+            &RequestContext::default(),
+        )
+        .await;
+    assert!(matches!(result, Err(AppError::BadRequest(_))));
+    assert_eq!(balance(source).await, Decimal::from_str("39.50").unwrap());
+    assert_eq!(balance(payees[0]).await, Decimal::from(130));
+
+    // A batch holds at most 100 transfers
+    let result = transaction_service
+        .process_batch_transfer(
+            batch(vec![(payees[0], "0.01"); 101]),
+            &RequestContext::default(),
+        )
+        .await;
+    assert!(matches!(result, Err(AppError::Validation(_))));
+    let full = transaction_service
+        .process_batch_transfer(
+            batch(vec![(payees[0], "0.01"); 100]),
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(full.len(), 100);
+    assert_eq!(balance(source).await, Decimal::from_str("38.50").unwrap());
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_transfer_batch_from_several_senders() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let accounts = funded_accounts(&pool, &["multipayer", "multipayee1", "multipayee2"], 100).await;
+    let (main, payees) = (accounts[0], &accounts[1..]);
+    let owner = account_service
+        .get_account_by_id(main)
+        .await
+        .unwrap()
+        .user_id;
+    let savings = account_service
+        .create_account(owner, "USD".to_string())
+        .await
+        .unwrap()
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: savings,
+            amount: Decimal::from(50),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let token = login_token(&app, "multipayer").await;
+    let balance = |id| {
+        let account_service = &account_service;
+        async move { account_service.get_account_by_id(id).await.unwrap().balance }
+    };
+    let transfer = |sender: Uuid, receiver: Uuid, amount: &str| {
+        serde_json::json!({
+            "sender_account_id": sender,
+            "receiver_account_id": receiver,
+            "amount": amount,
+        })
+    };
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/batch-transfer",
+        Some(&token),
+        &[],
+        Some(serde_json::json!({
+            "transfers": [
+                transfer(main, payees[0], "30"),
+                transfer(savings, payees[1], "20"),
+                transfer(main, savings, "10"),
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["total"], serde_json::json!({ "USD": "60.00" }));
+    let transactions = body["data"]["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 3);
+    assert_eq!(transactions[1]["sender_account_id"], savings.to_string());
+    assert!(transactions.iter().all(|t| t["status"] == "COMPLETED"));
+    assert_eq!(balance(main).await, Decimal::from(60));
+    assert_eq!(balance(savings).await, Decimal::from(40));
+    assert_eq!(balance(payees[0]).await, Decimal::from(130));
+    assert_eq!(balance(payees[1]).await, Decimal::from(120));
+
+    // Every sender must belong to the caller
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/batch-transfer",
+        Some(&token),
+        &[],
+        Some(serde_json::json!({
+            "transfers": [
+                transfer(main, payees[0], "1"),
+                transfer(payees[1], main, "1"),
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(balance(main).await, Decimal::from(60));
+
+    // A batch needs transfers, and each transfer a sender
+    for invalid in [
+        serde_json::json!({ "sender_account_id": main, "transfers": [] }),
+        serde_json::json!({ "transfers": [{ "receiver_account_id": payees[0], "amount": "1" }] }),
+    ] {
+        let (status, _, body) = send_request(
+            &app,
+            Method::POST,
+            "/api/v1/transactions/batch",
+            Some(&token),
+            &[],
+            Some(invalid),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    }
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_transfer_batch_rolls_back_on_failure() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let accounts = funded_accounts(
+        &pool,
+        &["rollbackpayer", "rollbackpayee1", "rollbackpayee2"],
+        100,
+    )
+    .await;
+    let (source, payees) = (accounts[0], &accounts[1..]);
+    let transfer = |receiver_account_id, amount| TransferRequest {
+        sender_account_id: source,
+        receiver_account_id,
+        amount: Decimal::from(amount),
+        description: None,
+        category: None,
+        allow_duplicate: false,
+    };
+
+    // The third transfer overdraws the source, which the first two don't
+    let token = login_token(&app, "rollbackpayer").await;
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/batch-transfer",
+        Some(&token),
+        &[],
+        Some(serde_json::json!({
+            "transfers": [
+                transfer(payees[0], 50),
+                transfer(payees[1], 40),
+                transfer(payees[0], 20),
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(body["error"], "BAD_REQUEST");
+    assert_eq!(body["message"], "Insufficient funds");
+
+    let result = transaction_service
+        .process_batch_transfer(
+            vec![
+                transfer(payees[0], 50),
+                transfer(payees[1], 40),
+                transfer(payees[0], 20),
+            ],
+            &RequestContext::default(),
+        )
+        .await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(ref msg)) if msg == "Insufficient funds"),
+        "{:?}",
+        result
+    );
+
+    for account_id in &accounts {
+        let account = account_service
+            .get_account_by_id(*account_id)
+            .await
+            .unwrap();
+        assert_eq!(account.balance, Decimal::from(100));
+    }
+    let booked: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE sender_account_id = $1")
+            .bind(source)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(booked, 0);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_transfer_between_currencies_converts_at_stored_rate() {
+    let (pool, db_url) = setup().await;
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let accounts = funded_accounts(&pool, &["fxsender", "fxreceiver"], 100).await;
+    let sender_account = accounts[0];
+    let receiver = account_service
+        .get_account_by_id(accounts[1])
+        .await
+        .unwrap();
+    let euro_account = account_service
+        .create_account(receiver.user_id, "EUR".to_string())
+        .await
+        .unwrap()
+        .id;
+    sqlx::query(
+        "INSERT INTO exchange_rates (base_currency, quote_currency, rate) VALUES ('USD', 'EUR', 0.9225)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let balance = |id| {
+        let account_service = &account_service;
+        async move { account_service.get_account_by_id(id).await.unwrap().balance }
+    };
+    let transfer = |sender_account_id, receiver_account_id| TransferRequest {
+        sender_account_id,
+        receiver_account_id,
+        amount: Decimal::from(10),
+        description: None,
+        category: None,
+        allow_duplicate: false,
+    };
+
+    // 10 USD at 0.9225 is 9.225 EUR, rounded half-even to 9.22
+    let converted = transaction_service
+        .process_transfer(
+            transfer(sender_account, euro_account),
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(converted.amount, Decimal::from_str("9.22").unwrap());
+    assert_eq!(converted.currency, "EUR");
+    assert_eq!(balance(sender_account).await, Decimal::from(90));
+    assert_eq!(
+        balance(euro_account).await,
+        Decimal::from_str("9.22").unwrap()
+    );
+
+    // What the sender paid and the rate are kept with the transaction
+    let (original_amount, original_currency, rate): (String, String, String) = sqlx::query_as(
+        r#"
+        SELECT original_amount::TEXT, original_currency, exchange_rate::TEXT
+        FROM transactions WHERE id = $1
+        "#,
+    )
+    .bind(converted.id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        Decimal::from_str(&original_amount).unwrap(),
+        Decimal::from(10)
+    );
+    assert_eq!(original_currency, "USD");
+    assert_eq!(
+        Decimal::from_str(&rate).unwrap(),
+        Decimal::from_str("0.9225").unwrap()
+    );
+
+    // Both sides of the conversion are shown, in the response and in either
+    // account's history
+    assert_eq!(converted.original_amount, Some(Decimal::from(10)));
+    assert_eq!(converted.original_amount.unwrap().scale(), 2);
+    assert_eq!(converted.original_currency.as_deref(), Some("USD"));
+    assert_eq!(
+        converted.exchange_rate,
+        Some(Decimal::from_str("0.9225").unwrap())
+    );
+    for account_id in [sender_account, euro_account] {
+        let history = transaction_service
+            .get_transactions_by_account_id(account_id, &Default::default(), None, None, None)
+            .await
+            .unwrap();
+        let listed = history.iter().find(|t| t.id == converted.id).unwrap();
+        assert_eq!(listed.original_amount, converted.original_amount);
+        assert_eq!(listed.original_currency, converted.original_currency);
+        assert_eq!(listed.exchange_rate, converted.exchange_rate);
+    }
+    let history = transaction_service
+        .get_transactions_by_user_id(receiver.user_id, None, None, &Default::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        history[0].transaction.original_currency.as_deref(),
+        Some("USD")
+    );
+
+    // Without a rate in either direction, nothing moves
+    let yen_account = account_service
+        .create_account(receiver.user_id, "JPY".to_string())
+        .await
+        .unwrap()
+        .id;
+    let result = transaction_service
+        .process_transfer(
+            transfer(sender_account, yen_account),
+            &RequestContext::default(),
+        )
+        .await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(_))),
+        "{:?}",
+        result
+    );
+    assert_eq!(balance(sender_account).await, Decimal::from(90));
+
+    // A reversal gives the sender back exactly what it paid
+    let reversal = transaction_service
+        .reverse_transaction(converted.id, &RequestContext::default())
+        .await
+        .unwrap();
+    assert_eq!(reversal.reversal.amount, Decimal::from(10));
+    assert_eq!(reversal.reversal.currency, "USD");
+    assert_eq!(reversal.original.original_currency.as_deref(), Some("USD"));
+    assert_eq!(reversal.reversal.original_currency.as_deref(), Some("EUR"));
+    let history = transaction_service
+        .get_transactions_by_account_id(euro_account, &Default::default(), None, None, None)
+        .await
+        .unwrap();
+    let refund = history
+        .iter()
+        .find(|t| t.id == reversal.reversal.id)
+        .unwrap();
+    assert_eq!(refund.original_amount, reversal.reversal.original_amount);
+    assert_eq!(
+        refund.original_amount,
+        Some(Decimal::from_str("9.22").unwrap())
+    );
+    assert_eq!(refund.original_currency.as_deref(), Some("EUR"));
+    assert_eq!(refund.exchange_rate, None);
+    assert_eq!(balance(sender_account).await, Decimal::from(100));
+    assert_eq!(balance(euro_account).await, Decimal::ZERO);
+
+    teardown(&db_url).await;
+}
+
+/// Registers users with a funded default account each and returns the account IDs
+async fn funded_accounts(pool: &sqlx::PgPool, usernames: &[&str], amount: i64) -> Vec<Uuid> {
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let mut accounts = Vec::new();
+    for username in usernames {
+        let user = register_user(&user_service, username).await;
+        let account_id = account_service
+            .get_accounts_by_user_id(user.id)
+            .await
+            .unwrap()[0]
+            .id;
+        transaction_service
+            .process_deposit(DepositRequest {
+                account_id,
+                amount: Decimal::from(amount),
+                currency: None,
+                exchange_rate: None,
+                description: None,
+                category: None,
+                external_reference: None,
+            })
+            .await
+            .unwrap();
+        accounts.push(account_id);
+    }
+    accounts
+}
+
+#[tokio::test]
+async fn test_reverse_transfer_moves_funds_back() {
+    let (pool, db_url) = setup().await;
+
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let app = create_app(pool.clone(), &db_url);
+
+    let accounts = funded_accounts(&pool, &["revsender", "revreceiver"], 100).await;
+    let transfer = transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: accounts[0],
+                receiver_account_id: accounts[1],
+                amount: Decimal::from(30),
+                description: Some("Wrong payee".to_string()),
+                category: None,
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    let sender_token = login_token(&app, "revsender").await;
+    let receiver_token = login_token(&app, "revreceiver").await;
+    let reverse = |id: Uuid, token: String| {
+        let app = app.clone();
+        async move {
+            send_request(
+                &app,
+                Method::POST,
+                &format!("/api/v1/transactions/{}/reverse", id),
+                Some(&token),
+                &[],
+                None,
+            )
+            .await
+        }
+    };
+
+    // Only the sender may undo the transfer
+    let (status, _, _) = reverse(transfer.id, receiver_token.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, body) = reverse(transfer.id, sender_token.clone()).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let original = &body["data"]["original"];
+    let reversal = &body["data"]["reversal"];
+    assert_eq!(original["id"], transfer.id.to_string());
+    assert_eq!(original["status"], "REVERSED");
+    assert_eq!(reversal["status"], "COMPLETED");
+    assert_eq!(reversal["transaction_type"], "TRANSFER");
+    assert_eq!(reversal["sender_account_id"], accounts[1].to_string());
+    assert_eq!(reversal["receiver_account_id"], accounts[0].to_string());
+    assert_eq!(reversal["amount"], "30.00");
+
+    let balance = |id| {
+        let account_service = &account_service;
+        async move { account_service.get_account_by_id(id).await.unwrap().balance }
+    };
+    assert_eq!(balance(accounts[0]).await, Decimal::from(100));
+    assert_eq!(balance(accounts[1]).await, Decimal::from(100));
+
+    // The two transactions point at each other
+    let reversal_id = Uuid::parse_str(reversal["id"].as_str().unwrap()).unwrap();
+    let links: (Option<Uuid>, Option<Uuid>) = sqlx::query_as(
+        r#"
+        SELECT o.reversed_by, r.reverses_transaction_id
+        FROM transactions o, transactions r
+        WHERE o.id = $1 AND r.id = $2
+        "#,
+    )
+    .bind(transfer.id)
+    .bind(reversal_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(links, (Some(reversal_id), Some(transfer.id)));
+
+    // Both show up in the sender's history
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/transactions/account/{}", accounts[0]),
+        Some(&sender_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let statuses: Vec<(String, String)> = body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| {
+            (
+                t["id"].as_str().unwrap().to_string(),
+                t["status"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert!(statuses.contains(&(transfer.id.to_string(), "REVERSED".to_string())));
+    assert!(statuses.contains(&(reversal_id.to_string(), "COMPLETED".to_string())));
+
+    // A transfer is reversed once, and a reversal can't be reversed
+    let (status, _, body) = reverse(transfer.id, sender_token.clone()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "TRANSACTION_ALREADY_REVERSED");
+    let (status, _, body) = reverse(reversal_id, receiver_token).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "TRANSACTION_NOT_REVERSIBLE");
+    assert_eq!(balance(accounts[0]).await, Decimal::from(100));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_reversal_rejections() {
+    let (pool, db_url) = setup().await;
+
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let app = create_app(pool.clone(), &db_url);
+
+    let accounts = funded_accounts(&pool, &["rejsender", "rejreceiver", "rejother"], 50).await;
+    let transfer = |sender: usize, receiver: usize, amount: i64| {
+        let transaction_service = &transaction_service;
+        let accounts = &accounts;
+        async move {
+            transaction_service
+                .process_transfer(
+                    TransferRequest {
+                        sender_account_id: accounts[sender],
+                        receiver_account_id: accounts[receiver],
+                        amount: Decimal::from(amount),
+                        description: None,
+                        category: None,
+                        allow_duplicate: false,
+                    },
+                    &RequestContext::default(),
+                )
+                .await
+                .unwrap()
+        }
+    };
+    let rejection = |result: Result<_, AppError>| match result {
+        Err(AppError::Rejected { code, .. }) => code,
+        other => panic!("Expected a rejection, got {:?}", other),
+    };
+
+    // The receiver has spent most of what they were sent
+    let spent = transfer(0, 1, 40).await;
+    transfer(1, 2, 60).await;
+    let result = transaction_service
+        .reverse_transaction(spent.id, &RequestContext::default())
+        .await;
+    assert_eq!(rejection(result), "REVERSAL_INSUFFICIENT_FUNDS");
+    let balance = |id| {
+        let account_service = &account_service;
+        async move { account_service.get_account_by_id(id).await.unwrap().balance }
+    };
+    assert_eq!(balance(accounts[0]).await, Decimal::from(10));
+    assert_eq!(balance(accounts[1]).await, Decimal::from(30));
+
+    // Only completed transfers can be reversed
+    let failed = transfer(2, 0, 5).await;
+    sqlx::query("UPDATE transactions SET status = 'FAILED' WHERE id = $1")
+        .bind(failed.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let result = transaction_service
+        .reverse_transaction(failed.id, &RequestContext::default())
+        .await;
+    assert_eq!(rejection(result), "TRANSACTION_NOT_COMPLETED");
+
+    let deposit_id: Uuid = sqlx::query_scalar(
+        "SELECT id FROM transactions WHERE receiver_account_id = $1 AND transaction_type = 'DEPOSIT'",
+    )
+    .bind(accounts[0])
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let result = transaction_service
+        .reverse_transaction(deposit_id, &RequestContext::default())
+        .await;
+    assert_eq!(rejection(result), "TRANSACTION_NOT_REVERSIBLE");
+
+    let result = transaction_service
+        .reverse_transaction(Uuid::new_v4(), &RequestContext::default())
+        .await;
+    assert!(matches!(result, Err(AppError::NotFound(_))));
+
+    // Money can't be moved back out of a frozen account
+    let frozen = transfer(2, 0, 5).await;
+    sqlx::query("UPDATE accounts SET status = 'FROZEN' WHERE id = $1")
+        .bind(accounts[0])
+        .execute(&pool)
+        .await
+        .unwrap();
+    let result = transaction_service
+        .reverse_transaction(frozen.id, &RequestContext::default())
+        .await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(ref msg)) if msg == "Account is frozen"),
+        "{:?}",
+        result
+    );
+    assert_eq!(balance(accounts[0]).await, Decimal::from(20));
+    assert_eq!(balance(accounts[2]).await, Decimal::from(100));
+
+    // Admins may reverse anyone's transfer
+    let small = transfer(1, 2, 5).await;
+    promote_to_admin(&pool, "rejsender").await;
+    let admin_token = login_token(&app, "rejsender").await;
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        &format!("/api/v1/transactions/{}/reverse", small.id),
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(balance(accounts[1]).await, Decimal::from(30));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_transfer_fee_charged_to_sender() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let sender = register_user(&user_service, "feesender").await;
+    let receiver = register_user(&user_service, "feereceiver").await;
+    let collector = register_user(&user_service, "feecollector").await;
+    let sender_account = account_service
+        .get_accounts_by_user_id(sender.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let receiver_account = account_service
+        .get_accounts_by_user_id(receiver.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let fee_account = account_service
+        .get_accounts_by_user_id(collector.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let transaction_service = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            transfer_fee_flat: Decimal::from_str("0.50").unwrap(),
+            transfer_fee_percent: Decimal::ONE,
+            fee_account_id: Some(fee_account),
+            ..Default::default()
+        },
+    );
+
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: sender_account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let transfer = |amount: i64| TransferRequest {
+        sender_account_id: sender_account,
+        receiver_account_id: receiver_account,
+        amount: Decimal::from(amount),
+        description: None,
+        category: None,
+        allow_duplicate: false,
+    };
+
+    // 99 plus a fee of 0.50 + 0.99 is more than the sender has
+    let result = transaction_service
+        .process_transfer(transfer(99), &RequestContext::default())
+        .await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(ref msg)) if msg == "Insufficient funds"),
+        "{:?}",
+        result
+    );
+
+    let response = transaction_service
+        .process_transfer(transfer(50), &RequestContext::default())
+        .await
+        .unwrap();
+    assert_eq!(response.amount, Decimal::from(50));
+    assert_eq!(response.fee_amount, Some(Decimal::from(1)));
+
+    // What left the sender is exactly what the receiver and the fee account got
+    let balance = |id| account_service.get_account_by_id(id);
+    let sender_balance = balance(sender_account).await.unwrap().balance;
+    let receiver_balance = balance(receiver_account).await.unwrap().balance;
+    let fee_balance = balance(fee_account).await.unwrap().balance;
+    assert_eq!(sender_balance, Decimal::from(49));
+    assert_eq!(receiver_balance, Decimal::from(50));
+    assert_eq!(fee_balance, Decimal::from(1));
+    assert_eq!(
+        Decimal::from(100) - sender_balance,
+        receiver_balance + fee_balance
+    );
+
+    // The fee is its own transaction, linked to the transfer
+    let (fee_id, fee_type, fee_sender, fee_amount, parent_id): (
+        Uuid,
+        String,
+        Uuid,
+        String,
+        Option<Uuid>,
+    ) = sqlx::query_as(
+        r#"
+        SELECT id, transaction_type, sender_account_id, amount::TEXT, parent_transaction_id
+        FROM transactions WHERE receiver_account_id = $1
+        "#,
+    )
+    .bind(fee_account)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(fee_type, "FEE");
+    assert_eq!(fee_sender, sender_account);
+    assert_eq!(Decimal::from_str(&fee_amount).unwrap(), Decimal::from(1));
+    assert_eq!(parent_id, Some(response.id));
+
+    // The sender's history lists both, and its running balance ends at the
+    // actual balance
+    let history = transaction_service
+        .get_transactions_by_account_id(sender_account, &Default::default(), None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0].balance_after, Some(Decimal::from(49)));
+    let listed_transfer = history.iter().find(|t| t.id == response.id).unwrap();
+    assert_eq!(listed_transfer.fee_amount, Some(Decimal::from(1)));
+    assert!(history.iter().any(|t| t.id == fee_id));
+
+    // Fees can't be created by hand
+    let result = transaction_service
+        .create_transaction(
+            CreateTransactionRequest {
+                sender_account_id: Some(sender_account),
+                receiver_account_id: Some(fee_account),
+                amount: Decimal::ONE,
+                currency: None,
+                transaction_type: "FEE".to_string(),
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await;
+    assert!(
+        matches!(result, Err(AppError::BadRequest(_))),
+        "{:?}",
+        result
+    );
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_transaction_amount_bounds() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            min_transaction_amount: Decimal::from(1),
+            max_transaction_amount: Decimal::from(1000),
+            ..TransactionConfig::default()
+        },
+    );
+
+    let user = register_user(&user_service, "boundsuser").await;
+    let payee = register_user(&user_service, "boundspayee").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let context = RequestContext::default();
+
+    // Both limits are inclusive
+    for amount in [Decimal::from(1), Decimal::from(1000)] {
+        transaction_service
+            .process_deposit(DepositRequest {
+                account_id: account,
+                amount,
+                currency: None,
+                exchange_rate: None,
+                description: None,
+                category: None,
+                external_reference: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    let expect_rejected = |result: Result<(), AppError>, expected: &str| match result {
+        Err(AppError::Validation(message)) => {
+            assert!(
+                message.contains(expected),
+                "unexpected message: {}",
+                message
+            )
+        }
+        other => panic!("expected a validation error, got {:?}", other),
+    };
+
+    // The specialized entry points
+    let too_small = Decimal::from_str("0.5").unwrap();
+    let too_large = Decimal::from(1001);
+    for (amount, expected) in [
+        (too_small, "at least 1"),
+        (too_large, "must not exceed 1000"),
+    ] {
+        let result = transaction_service
+            .process_deposit(DepositRequest {
+                account_id: account,
+                amount,
+                currency: None,
+                exchange_rate: None,
+                description: None,
+                category: None,
+                external_reference: None,
+            })
+            .await;
+        expect_rejected(result.map(|_| ()), expected);
+
+        let result = transaction_service
+            .process_withdrawal(
+                WithdrawalRequest {
+                    account_id: account,
+                    amount,
+                    description: None,
+                    category: None,
+                    destination_id: None,
+                    external_reference: None,
+                },
+                &context,
+            )
+            .await;
+        expect_rejected(result.map(|_| ()), expected);
+
+        let result = transaction_service
+            .process_transfer(
+                TransferRequest {
+                    sender_account_id: account,
+                    receiver_account_id: payee_account,
+                    amount,
+                    description: None,
+                    category: None,
+                    allow_duplicate: false,
+                },
+                &context,
+            )
+            .await;
+        expect_rejected(result.map(|_| ()), expected);
+    }
+
+    // The generic endpoint is held to the same bounds
+    for transaction_type in ["DEPOSIT", "TRANSFER", "WITHDRAWAL"] {
+        for (amount, expected) in [
+            (too_small, "at least 1"),
+            (too_large, "must not exceed 1000"),
+        ] {
+            let request = CreateTransactionRequest {
+                transaction_type: transaction_type.to_string(),
+                sender_account_id: (transaction_type != "DEPOSIT").then_some(account),
+                receiver_account_id: match transaction_type {
+                    "DEPOSIT" => Some(account),
+                    "TRANSFER" => Some(payee_account),
+                    _ => None,
+                },
+                amount,
+                currency: None,
+                description: None,
+                category: None,
+                allow_duplicate: false,
+            };
+            let result = transaction_service
+                .create_transaction(request, &context)
+                .await;
+            expect_rejected(result.map(|_| ()), expected);
+        }
+    }
+
+    // Nothing was booked by the rejected requests
+    let balance = account_service
+        .get_account_by_id(account)
+        .await
+        .unwrap()
+        .balance;
+    assert_eq!(balance, Decimal::from(1001));
+
+    // The default ceiling keeps absurd amounts away from the balance columns
+    let result = create_transaction_service(pool.clone())
+        .process_deposit(DepositRequest {
+            account_id: account,
+            amount: Decimal::from_str("100000000000000000000").unwrap(),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await;
+    expect_rejected(result.map(|_| ()), "must not exceed 1000000000000");
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_service_rejects_non_positive_amounts() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let user = register_user(&user_service, "nonpositiveuser").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+
+    // Called directly, the service can't rely on request validation
+    for amount in [Decimal::ZERO, Decimal::from(-5)] {
+        let result = transaction_service
+            .process_deposit(DepositRequest {
+                account_id: account,
+                amount,
+                currency: None,
+                exchange_rate: None,
+                description: None,
+                category: None,
+                external_reference: None,
+            })
+            .await;
+        match result {
+            Err(AppError::BadRequest(message)) => {
+                assert_eq!(message, "Amount must be positive")
+            }
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+    }
+
+    // Nothing was booked
+    let balance = account_service
+        .get_account_by_id(account)
+        .await
+        .unwrap()
+        .balance;
+    assert_eq!(balance, Decimal::ZERO);
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE receiver_account_id = $1")
+            .bind(account)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(count, 0);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_deposit_and_withdrawal_bounds() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            min_deposit_amount: Some(Decimal::from(10)),
+            max_deposit_amount: Some(Decimal::from(500)),
+            max_withdrawal_amount: Some(Decimal::from(100)),
+            ..TransactionConfig::default()
+        },
+    );
+
+    let user = register_user(&user_service, "kindboundsuser").await;
+    let account = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let context = RequestContext::default();
+    let deposit = |amount: i64| DepositRequest {
+        account_id: account,
+        amount: Decimal::from(amount),
+        currency: None,
+        exchange_rate: None,
+        description: None,
+        category: None,
+        external_reference: None,
+    };
+    let withdrawal = |amount: i64| WithdrawalRequest {
+        account_id: account,
+        amount: Decimal::from(amount),
+        description: None,
+        category: None,
+        destination_id: None,
+        external_reference: None,
+    };
+    let expect_rejected = |result: Result<(), AppError>, expected: &str| match result {
+        Err(AppError::Validation(message)) => assert_eq!(message, expected),
+        other => panic!("expected a validation error, got {:?}", other),
+    };
+
+    // Both limits are inclusive
+    for amount in [10, 500] {
+        transaction_service
+            .process_deposit(deposit(amount))
+            .await
+            .unwrap();
+    }
+    let result = transaction_service.process_deposit(deposit(9)).await;
+    expect_rejected(result.map(|_| ()), "Deposit amount must be at least 10");
+    let result = transaction_service.process_deposit(deposit(501)).await;
+    expect_rejected(result.map(|_| ()), "Deposit amount must not exceed 500");
+
+    // Withdrawals have their own bounds; small ones stay allowed
+    transaction_service
+        .process_withdrawal(withdrawal(100), &context)
+        .await
+        .unwrap();
+    transaction_service
+        .process_withdrawal(withdrawal(1), &context)
+        .await
+        .unwrap();
+    let result = transaction_service
+        .process_withdrawal(withdrawal(101), &context)
+        .await;
+    expect_rejected(result.map(|_| ()), "Withdrawal amount must not exceed 100");
+
+    let balance = account_service
+        .get_account_by_id(account)
+        .await
+        .unwrap()
+        .balance;
+    assert_eq!(balance, Decimal::from(409));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_transactions_are_found_by_external_reference() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let account_service = create_account_service(pool.clone());
+
+    let accounts = funded_accounts(&pool, &["refowner", "refstranger"], 100).await;
+    let account_id = accounts[0];
+    let token = login_token(&app, "refowner").await;
+    let stranger_token = login_token(&app, "refstranger").await;
+    let post = |uri: &'static str, body: serde_json::Value| {
+        let (app, token) = (app.clone(), token.clone());
+        async move { send_request(&app, Method::POST, uri, Some(&token), &[], Some(body)).await }
+    };
+
+    let (status, _, body) = post(
+        "/api/v1/transactions/deposit",
+        serde_json::json!({
+            "account_id": account_id,
+            "amount": "25",
+            "external_reference": "psp-deposit-1",
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["external_reference"], "psp-deposit-1");
+    let deposit_id = body["data"]["id"].clone();
+
+    // A reference identifies one transaction only, and a rejected duplicate books nothing
+    let (status, _, body) = post(
+        "/api/v1/transactions/withdrawal",
+        serde_json::json!({
+            "account_id": account_id,
+            "amount": "10",
+            "external_reference": "psp-deposit-1",
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+    let balance = account_service
+        .get_account_by_id(account_id)
+        .await
+        .unwrap()
+        .balance;
+    assert_eq!(balance, Decimal::from(125));
+
+    let (status, _, body) = post(
+        "/api/v1/transactions/withdrawal",
+        serde_json::json!({
+            "account_id": account_id,
+            "amount": "10",
+            "external_reference": "psp-payout-1",
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["external_reference"], "psp-payout-1");
+
+    let (status, _, body) = post(
+        "/api/v1/transactions/deposit",
+        serde_json::json!({ "account_id": account_id, "amount": "1", "external_reference": "" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    // Only owners of the account involved may look a reference up
+    let lookup = |token: String, reference: &'static str| {
+        let app = app.clone();
+        async move {
+            let uri = format!("/api/v1/transactions/by-reference/{}", reference);
+            send_request(&app, Method::GET, &uri, Some(&token), &[], None).await
+        }
+    };
+    let (status, _, body) = lookup(token.clone(), "psp-deposit-1").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["id"], deposit_id);
+    assert_eq!(body["data"]["external_reference"], "psp-deposit-1");
+    let (status, _, _) = lookup(stranger_token, "psp-deposit-1").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = lookup(token.clone(), "psp-unknown").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Account histories carry the references too
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/transactions/account/{}", account_id),
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let references: Vec<_> = body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|transaction| transaction["external_reference"].as_str())
+        .collect();
+    assert_eq!(
+        references,
+        [Some("psp-payout-1"), Some("psp-deposit-1"), None]
+    );
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_duplicate_transfer_is_rejected() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+    let context = RequestContext::default();
+
+    let accounts = funded_accounts(&pool, &["dupsender", "dupreceiver"], 100).await;
+    let token = login_token(&app, "dupsender").await;
+    let transfer = |description: &str, allow_duplicate: Option<bool>| {
+        let (app, token) = (app.clone(), token.clone());
+        let mut body = serde_json::json!({
+            "sender_account_id": accounts[0],
+            "receiver_account_id": accounts[1],
+            "amount": "10",
+            "description": description,
+        });
+        if let Some(allow_duplicate) = allow_duplicate {
+            body["allow_duplicate"] = allow_duplicate.into();
+        }
+        async move {
+            send_request(
+                &app,
+                Method::POST,
+                "/api/v1/transactions/transfer",
+                Some(&token),
+                &[],
+                Some(body),
+            )
+            .await
+        }
+    };
+
+    let (status, _, first) = transfer("Rent", None).await;
+    assert_eq!(status, StatusCode::OK, "{}", first);
+    let first_id = first["data"]["id"].as_str().unwrap().to_string();
+
+    // The same transfer moments later is refused and books nothing
+    let (status, _, body) = transfer("Rent", None).await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+    assert_eq!(body["error"], "DUPLICATE_SUSPECTED");
+    assert_eq!(body["details"], first_id.as_str());
+    let balance = |id: Uuid| {
+        let account_service = &account_service;
+        async move { account_service.get_account_by_id(id).await.unwrap().balance }
+    };
+    assert_eq!(balance(accounts[0]).await, Decimal::from(90));
+
+    // Another description, or opting out, goes through
+    let (status, _, body) = transfer("Groceries", None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, _, body) = transfer("Rent", Some(true)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(balance(accounts[0]).await, Decimal::from(70));
+
+    // Once the window has passed, the same transfer is a new one
+    sqlx::query(
+        "UPDATE transactions SET created_at = NOW() - INTERVAL '31 seconds'
+         WHERE sender_account_id = $1",
+    )
+    .bind(accounts[0])
+    .execute(&pool)
+    .await
+    .unwrap();
+    let (status, _, body) = transfer("Rent", None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // Identical transfers racing each other: the second waits for the first
+    // and then finds it
+    let request = TransferRequest {
+        sender_account_id: accounts[1],
+        receiver_account_id: accounts[0],
+        amount: Decimal::from(5),
+        description: None,
+        category: None,
+        allow_duplicate: false,
+    };
+    let (a, b) = tokio::join!(
+        transaction_service.process_transfer(request.clone(), &context),
+        transaction_service.process_transfer(request.clone(), &context),
+    );
+    let (booked, rejected) = match (a, b) {
+        (Ok(booked), Err(rejected)) | (Err(rejected), Ok(booked)) => (booked, rejected),
+        other => panic!("Expected exactly one transfer to be booked: {:?}", other),
+    };
+    assert!(
+        matches!(rejected, AppError::DuplicateSuspected { transaction_id } if transaction_id == booked.id),
+        "{:?}",
+        rejected
+    );
+
+    // The check can be turned off
+    let unchecked = create_transaction_service_with_config(
+        pool.clone(),
+        TransactionConfig {
+            duplicate_transfer_window: None,
+            ..TransactionConfig::default()
+        },
+    );
+    unchecked.process_transfer(request, &context).await.unwrap();
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_validate_transfer_runs_checks_without_booking() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let payer = register_user(&user_service, "validatepayer").await;
+    let payee = register_user(&user_service, "validatepayee").await;
+    let payer_account = account_service
+        .get_accounts_by_user_id(payer.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    transaction_service
+        .process_deposit(DepositRequest {
+            account_id: payer_account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let token = login_token(&app, "validatepayer").await;
+    let payee_token = login_token(&app, "validatepayee").await;
+
+    let validate = |token: String, receiver: Uuid, amount: &'static str| {
+        let app = app.clone();
+        async move {
+            send_request(
+                &app,
+                Method::POST,
+                "/api/v1/transactions/transfer/validate",
+                Some(&token),
+                &[],
+                Some(serde_json::json!({
+                    "sender_account_id": payer_account,
+                    "receiver_account_id": receiver,
+                    "amount": amount,
+                    "category": "rent"
+                })),
+            )
+            .await
+        }
+    };
+    let statuses = |body: &serde_json::Value| -> BTreeMap<String, String> {
+        body["data"]["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|check| {
+                (
+                    check["check"].as_str().unwrap().to_string(),
+                    check["status"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    };
+    let transaction_count = || {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM transactions")
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+    let booked = transaction_count().await;
+
+    // A transfer that would go through passes every check
+    let (status, _, body) = validate(token.clone(), payee_account, "40").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["valid"], true);
+    let checks = statuses(&body);
+    assert_eq!(
+        checks.keys().map(String::as_str).collect::<Vec<_>>(),
+        [
+            "accounts_active",
+            "amount",
+            "amount_conversion",
+            "category",
+            "distinct_accounts",
+            "duplicate",
+            "limits",
+            "receiver_account",
+            "sender_account",
+            "sufficient_funds",
+            "verified_owners",
+        ]
+    );
+    assert!(checks.values().all(|status| status == "PASSED"), "{}", body);
+    assert_eq!(body["data"]["amount"], "40.00");
+    assert_eq!(body["data"]["currency"], "USD");
+
+    // Each failing check is reported with the error the transfer would get
+    let (status, _, body) = validate(token.clone(), payee_account, "150").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["valid"], false);
+    let failed: Vec<_> = body["data"]["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|check| check["status"] == "FAILED")
+        .collect();
+    assert_eq!(failed.len(), 1, "{}", body);
+    assert_eq!(failed[0]["check"], "sufficient_funds");
+    assert_eq!(failed[0]["error"], "BAD_REQUEST");
+    assert_eq!(failed[0]["message"], "Insufficient funds");
+
+    account_service
+        .set_limits(
+            payer_account,
+            AccountLimits {
+                per_transaction_limit: Some(Decimal::from(30)),
+                ..AccountLimits::default()
+            },
+        )
+        .await
+        .unwrap();
+    let (_, _, body) = validate(token.clone(), payee_account, "40").await;
+    let checks = statuses(&body);
+    assert_eq!(checks["limits"], "FAILED");
+    assert_eq!(checks["sufficient_funds"], "PASSED");
+
+    // Checks needing an account that doesn't exist are skipped
+    let (_, _, body) = validate(token.clone(), Uuid::new_v4(), "20").await;
+    let checks = statuses(&body);
+    assert_eq!(checks["receiver_account"], "FAILED");
+    for check in [
+        "accounts_active",
+        "amount_conversion",
+        "limits",
+        "sufficient_funds",
+    ] {
+        assert_eq!(checks[check], "SKIPPED", "{}", check);
+    }
+
+    // Only the sender's owner may validate, as only they may transfer
+    let (status, _, _) = validate(payee_token, payee_account, "20").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Nothing was booked, and nothing is locked: validating doesn't wait for
+    // a transfer holding the sender
+    assert_eq!(transaction_count().await, booked);
+    let mut lock = pool.begin().await.unwrap();
+    sqlx::query("SELECT id FROM accounts WHERE id = $1 FOR UPDATE")
+        .bind(payer_account)
+        .execute(&mut *lock)
+        .await
+        .unwrap();
+    let (status, _, body) = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        validate(token.clone(), payee_account, "20"),
+    )
+    .await
+    .expect("Validation waited for the sender's lock");
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["valid"], true);
+    lock.rollback().await.unwrap();
+
+    // A validated transfer isn't mistaken for a duplicate of itself
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&token),
+        &[],
+        Some(serde_json::json!({
+            "sender_account_id": payer_account,
+            "receiver_account_id": payee_account,
+            "amount": "20"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(
+        account_service
+            .get_account_by_id(payer_account)
+            .await
+            .unwrap()
+            .balance,
+        Decimal::from(80)
+    );
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_update_transaction_description() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    let payer = register_user(&user_service, "relabelpayer").await;
+    let payee = register_user(&user_service, "relabelpayee").await;
+    register_user(&user_service, "relabelstranger").await;
+    let payer_account = account_service
+        .get_accounts_by_user_id(payer.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let payee_account = account_service
+        .get_accounts_by_user_id(payee.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let deposit = transaction_service
+        .process_deposit(DepositRequest {
+            account_id: payer_account,
+            amount: Decimal::from(100),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    let transfer = transaction_service
+        .process_transfer(
+            TransferRequest {
+                sender_account_id: payer_account,
+                receiver_account_id: payee_account,
+                amount: Decimal::from(40),
+                description: Some("Dinner".to_string()),
+                category: None,
+                allow_duplicate: false,
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap();
+    let payer_token = login_token(&app, "relabelpayer").await;
+    let payee_token = login_token(&app, "relabelpayee").await;
+    let stranger_token = login_token(&app, "relabelstranger").await;
+
+    let update = |token: String, id: Uuid, body: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            send_request(
+                &app,
+                Method::PATCH,
+                &format!("/api/v1/transactions/{}", id),
+                Some(&token),
+                &[],
+                Some(body),
+            )
+            .await
+        }
+    };
+    let updated_at = |id: Uuid| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, chrono::DateTime<chrono::Utc>>(
+                "SELECT updated_at FROM transactions WHERE id = $1",
+            )
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        }
+    };
+    let balances = || async {
+        (
+            account_service
+                .get_account_by_id(payer_account)
+                .await
+                .unwrap()
+                .balance,
+            account_service
+                .get_account_by_id(payee_account)
+                .await
+                .unwrap()
+                .balance,
+        )
+    };
+    let before = updated_at(transfer.id).await;
+    let balances_before = balances().await;
+
+    // Either side of a transfer may relabel it
+    let (status, _, body) = update(
+        payer_token.clone(),
+        transfer.id,
+        serde_json::json!({ "description": "Dinner with Sam" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["description"], "Dinner with Sam");
+    assert_eq!(body["data"]["amount"], "40.00");
+    assert!(updated_at(transfer.id).await > before);
+    let (status, _, body) = update(
+        payee_token.clone(),
+        transfer.id,
+        serde_json::json!({ "description": null }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["data"]["description"].is_null(), "{}", body);
+    assert_eq!(
+        transaction_service
+            .get_transaction_by_id(transfer.id)
+            .await
+            .unwrap()
+            .description,
+        None
+    );
+    assert_eq!(balances().await, balances_before);
+
+    // Only the description can change, and it must be given
+    for body in [
+        serde_json::json!({ "amount": "1" }),
+        serde_json::json!({ "description": "Rent", "status": "FAILED" }),
+        serde_json::json!({}),
+    ] {
+        let (status, _, response) = update(payer_token.clone(), transfer.id, body.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", body, response);
+    }
+
+    let (status, _, _) = update(
+        stranger_token,
+        transfer.id,
+        serde_json::json!({ "description": "Mine now" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = update(
+        payer_token.clone(),
+        Uuid::new_v4(),
+        serde_json::json!({ "description": "Missing" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Only completed transactions can be relabelled
+    sqlx::query("UPDATE transactions SET status = 'FAILED' WHERE id = $1")
+        .bind(deposit.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let (status, _, body) = update(
+        payer_token,
+        deposit.id,
+        serde_json::json!({ "description": "Salary" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(body["error"], "TRANSACTION_NOT_COMPLETED");
+
+    teardown(&db_url).await;
+}
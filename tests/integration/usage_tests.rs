@@ -0,0 +1,205 @@
+use crate::integration::setup::{
+    create_account_service, create_user_service, login_token, promote_to_admin, register_user,
+    send_request, setup, teardown, test_config,
+};
+use axum::http::{Method, StatusCode};
+use chrono::{NaiveDate, Utc};
+use std::sync::Arc;
+use txn_manager::api::{create_router_with_usage, transaction_feed};
+use txn_manager::services::usage_service::{UsageAggregator, UsageKey, UsageService};
+use uuid::Uuid;
+
+fn key(user_id: Uuid, route: &str, usage_date: NaiveDate) -> UsageKey {
+    UsageKey {
+        user_id,
+        route: route.to_string(),
+        usage_date,
+    }
+}
+
+#[test]
+fn test_usage_aggregation() {
+    let aggregator = UsageAggregator::default();
+    let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+    let monday = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let tuesday = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+
+    aggregator.record(alice, "/api/v1/accounts/:id", monday);
+    aggregator.record(alice, "/api/v1/accounts/:id", monday);
+    aggregator.record(alice, "/api/v1/accounts/:id", tuesday);
+    aggregator.record(alice, "/api/v1/accounts", monday);
+    aggregator.record(bob, "/api/v1/accounts/:id", monday);
+
+    let counts = aggregator.drain();
+    assert_eq!(counts.len(), 4);
+    assert_eq!(counts[&key(alice, "/api/v1/accounts/:id", monday)], 2);
+    assert_eq!(counts[&key(alice, "/api/v1/accounts/:id", tuesday)], 1);
+    assert_eq!(counts[&key(alice, "/api/v1/accounts", monday)], 1);
+    assert_eq!(counts[&key(bob, "/api/v1/accounts/:id", monday)], 1);
+
+    // Draining empties the aggregator; restoring merges with newer counts
+    assert!(aggregator.drain().is_empty());
+    aggregator.record(bob, "/api/v1/accounts/:id", monday);
+    aggregator.restore(counts);
+    assert_eq!(
+        aggregator.drain()[&key(bob, "/api/v1/accounts/:id", monday)],
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_usage_flush_accumulates_and_survives_failure() {
+    let (pool, db_url) = setup().await;
+    let usage_service = UsageService::new(pool.clone());
+    let aggregator = usage_service.aggregator();
+    let user_id = Uuid::new_v4();
+    let today = Utc::now().date_naive();
+
+    aggregator.record(user_id, "/api/v1/accounts", today);
+    aggregator.record(user_id, "/api/v1/accounts", today);
+    assert_eq!(usage_service.flush().await.unwrap(), 1);
+
+    // A second flush adds to the stored count instead of replacing it
+    aggregator.record(user_id, "/api/v1/accounts", today);
+    usage_service.flush().await.unwrap();
+    let usage = usage_service
+        .get_usage(Some(user_id), today, today)
+        .await
+        .unwrap();
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].request_count, 3);
+
+    // Nothing pending means nothing to write
+    assert_eq!(usage_service.flush().await.unwrap(), 0);
+
+    // A failed flush keeps its counts for the next attempt
+    aggregator.record(user_id, "/api/v1/transactions", today);
+    sqlx::query("ALTER TABLE api_usage RENAME TO api_usage_offline")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert!(usage_service.flush().await.is_err());
+    sqlx::query("ALTER TABLE api_usage_offline RENAME TO api_usage")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(usage_service.flush().await.unwrap(), 1);
+
+    let usage = usage_service
+        .get_usage(Some(user_id), today, today)
+        .await
+        .unwrap();
+    assert_eq!(usage.len(), 2);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_usage_is_recorded_per_route_template() {
+    let (pool, db_url) = setup().await;
+    let usage_service = Arc::new(UsageService::new(pool.clone()));
+    let config = test_config(&db_url);
+    let app = create_router_with_usage(
+        &config,
+        pool.clone(),
+        usage_service.clone(),
+        transaction_feed(&config),
+    );
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+
+    let user = register_user(&user_service, "usageuser").await;
+    register_user(&user_service, "usageadmin").await;
+    promote_to_admin(&pool, "usageadmin").await;
+    let token = login_token(&app, "usageuser").await;
+    let admin_token = login_token(&app, "usageadmin").await;
+
+    // Two different accounts, one route template
+    let first = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let second = account_service
+        .create_account(user.id, "EUR".to_string())
+        .await
+        .unwrap()
+        .id;
+    for id in [first, second] {
+        let (status, _, _) = send_request(
+            &app,
+            Method::GET,
+            &format!("/api/v1/accounts/{}", id),
+            Some(&token),
+            &[],
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    send_request(
+        &app,
+        Method::GET,
+        "/api/v2/users/me",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    usage_service.flush().await.unwrap();
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me/usage",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let usage = body["data"].as_array().unwrap();
+    let count_for = |route: &str| {
+        usage
+            .iter()
+            .find(|record| record["route"] == route)
+            .map(|record| record["request_count"].as_i64().unwrap())
+    };
+    assert_eq!(count_for("/api/v1/accounts/:id"), Some(2));
+    assert_eq!(count_for("/api/v2/users/me"), Some(1));
+    // Unauthenticated requests are not counted, and raw paths never appear
+    assert_eq!(count_for("/api/v1/users/login"), None);
+    assert!(usage.iter().all(|record| !record["route"]
+        .as_str()
+        .unwrap()
+        .contains(&first.to_string())));
+    assert!(usage
+        .iter()
+        .all(|record| record["user_id"] == user.id.to_string()));
+
+    // Admins can see everyone's usage, or narrow it to one user
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        &format!("/api/v1/admin/usage?user_id={}", user.id),
+        Some(&admin_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), usage.len());
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/admin/usage",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    teardown(&db_url).await;
+}
@@ -1,5 +1,25 @@
-use crate::integration::setup::{create_account_service, create_user_service, setup, teardown};
-use txn_manager::{CreateUserRequest, LoginRequest};
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, promote_to_admin, register_user, send_request, setup, teardown,
+};
+use async_trait::async_trait;
+use axum::http::{Method, StatusCode};
+use axum::response::IntoResponse;
+use chrono::Utc;
+use futures_util::future::join_all;
+use rust_decimal::Decimal;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use txn_manager::middleware::request_context::RequestContext;
+use txn_manager::utils::auth::validate_jwt;
+use txn_manager::utils::email::{EmailMessage, EmailSender};
+use txn_manager::utils::error::AppError;
+use txn_manager::utils::totp::{code_at_step, time_step};
+use txn_manager::{
+    AccountConfig, AccountService, CreateUserRequest, DepositRequest, LoginOutcome, LoginRequest,
+    NotificationConfig, NotificationService, UserService,
+};
+use uuid::Uuid;
 
 #[tokio::test]
 async fn test_user_registration_and_login() {
@@ -37,14 +57,18 @@ async fn test_user_registration_and_login() {
         password: "securepassword".to_string(),
     };
 
-    let login_result = user_service.login(login_request).await;
+    let login_result = user_service
+        .login(login_request, &RequestContext::default())
+        .await;
     assert!(
         login_result.is_ok(),
         "Login failed: {:?}",
         login_result.err()
     );
 
-    let login_response = login_result.unwrap();
+    let Ok(LoginOutcome::Session(login_response)) = login_result else {
+        panic!("Login should start a session");
+    };
     assert_eq!(login_response.user.username, "testuser");
     assert_eq!(login_response.user.email, "test@example.com");
     assert!(!login_response.token.is_empty(), "JWT token is empty");
@@ -55,7 +79,9 @@ async fn test_user_registration_and_login() {
         password: "wrongpassword".to_string(),
     };
 
-    let login_result = user_service.login(login_request).await;
+    let login_result = user_service
+        .login(login_request, &RequestContext::default())
+        .await;
     assert!(
         login_result.is_err(),
         "Login should fail with incorrect password"
@@ -138,6 +164,46 @@ async fn test_duplicate_user() {
     teardown(&db_url).await;
 }
 
+#[tokio::test]
+async fn test_duplicate_username_insert_is_a_conflict() {
+    let (pool, db_url) = setup().await;
+    let user_service = create_user_service(pool.clone());
+    register_user(&user_service, "racer").await;
+
+    // An insert the existence check didn't catch trips the unique key instead
+    let err = sqlx::query(
+        r#"
+        INSERT INTO users (id, username, email, password_hash)
+        VALUES ($1, 'racer', 'racer2@example.com', 'hash')
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .execute(&pool)
+    .await
+    .map_err(AppError::from)
+    .unwrap_err();
+    assert!(matches!(err, AppError::Conflict(_)), "{:?}", err);
+    assert_eq!(err.into_response().status(), StatusCode::CONFLICT);
+
+    // Registrations racing for the same name: one wins, the others conflict
+    let results = join_all((0..4).map(|i| {
+        user_service.create_user(CreateUserRequest {
+            username: "samename".to_string(),
+            email: format!("samename{}@example.com", i),
+            password: "securepassword".to_string(),
+            first_name: None,
+            last_name: None,
+        })
+    }))
+    .await;
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+    for err in results.into_iter().filter_map(Result::err) {
+        assert_eq!(err.into_response().status(), StatusCode::CONFLICT);
+    }
+
+    teardown(&db_url).await;
+}
+
 #[tokio::test]
 async fn test_user_login() {
     // Set up test environment
@@ -163,13 +229,17 @@ async fn test_user_login() {
         password: "securepassword".to_string(),
     };
 
-    let login_result = user_service.login(login_request).await;
+    let login_result = user_service
+        .login(login_request, &RequestContext::default())
+        .await;
     assert!(
         login_result.is_ok(),
         "Login should succeed with correct credentials"
     );
 
-    let login_response = login_result.unwrap();
+    let Ok(LoginOutcome::Session(login_response)) = login_result else {
+        panic!("Login should start a session");
+    };
     assert!(
         !login_response.token.is_empty(),
         "JWT token should be returned"
@@ -183,7 +253,9 @@ async fn test_user_login() {
         password: "wrongpassword".to_string(),
     };
 
-    let failed_login_result = user_service.login(failed_login_request).await;
+    let failed_login_result = user_service
+        .login(failed_login_request, &RequestContext::default())
+        .await;
     assert!(
         failed_login_result.is_err(),
         "Login should fail with incorrect password"
@@ -195,7 +267,9 @@ async fn test_user_login() {
         password: "anypassword".to_string(),
     };
 
-    let nonexistent_login_result = user_service.login(nonexistent_login_request).await;
+    let nonexistent_login_result = user_service
+        .login(nonexistent_login_request, &RequestContext::default())
+        .await;
     assert!(
         nonexistent_login_result.is_err(),
         "Login should fail with non-existent user"
@@ -245,3 +319,942 @@ async fn test_get_user_profile() {
     // Clean up test environment
     teardown(&db_url).await;
 }
+
+#[tokio::test]
+async fn test_login_history_records_attempts() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let app = create_app(pool.clone(), &db_url);
+    register_user(&user_service, "historyuser").await;
+
+    // A failed attempt against the real user is recorded too
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/login",
+        None,
+        &[("User-Agent", "history-test/1.0")],
+        Some(serde_json::json!({ "username": "historyuser", "password": "wrongpassword" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/login",
+        None,
+        &[("User-Agent", "history-test/1.0")],
+        Some(serde_json::json!({ "username": "historyuser", "password": "securepassword" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let token = body["data"]["token"].as_str().unwrap().to_string();
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me/login-history",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Newest first: the successful login, then the failed one
+    let events = body["data"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["success"], true);
+    assert_eq!(events[0]["user_agent"], "history-test/1.0");
+    assert_eq!(events[1]["success"], false);
+
+    // The endpoint requires authentication
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me/login-history",
+        None,
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Profile routes work behind the same authentication
+    let token = login_token(&app, "historyuser").await;
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["username"], "historyuser");
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_refresh_token_rotation_and_logout() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let app = create_app(pool.clone(), &db_url);
+    register_user(&user_service, "refreshuser").await;
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/login",
+        None,
+        &[],
+        Some(serde_json::json!({ "username": "refreshuser", "password": "securepassword" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let access = body["data"]["token"].as_str().unwrap().to_string();
+    let refresh = body["data"]["refresh_token"].as_str().unwrap().to_string();
+    let refresh_with = |token: &str| Some(serde_json::json!({ "refresh_token": token }));
+
+    // Each kind of token only works where it belongs
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me",
+        Some(&refresh),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/refresh",
+        None,
+        &[],
+        refresh_with(&access),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Refreshing issues a working pair and retires the old refresh token
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/refresh",
+        None,
+        &[],
+        refresh_with(&refresh),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["user"]["username"], "refreshuser");
+    let new_access = body["data"]["token"].as_str().unwrap().to_string();
+    let new_refresh = body["data"]["refresh_token"].as_str().unwrap().to_string();
+    assert_ne!(new_refresh, refresh);
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me",
+        Some(&new_access),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/refresh",
+        None,
+        &[],
+        refresh_with(&refresh),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Logging out revokes the refresh token, and doing it twice is harmless
+    for _ in 0..2 {
+        let (status, _, _) = send_request(
+            &app,
+            Method::POST,
+            "/api/v1/users/logout",
+            None,
+            &[],
+            refresh_with(&new_refresh),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/refresh",
+        None,
+        &[],
+        refresh_with(&new_refresh),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // A refresh token past its recorded expiry is refused
+    let LoginOutcome::Session(login) = user_service
+        .login(
+            LoginRequest {
+                username: "refreshuser".to_string(),
+                password: "securepassword".to_string(),
+            },
+            &RequestContext::default(),
+        )
+        .await
+        .unwrap()
+    else {
+        panic!("Login should start a session");
+    };
+    sqlx::query("UPDATE refresh_tokens SET expires_at = NOW() - INTERVAL '1 hour'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert!(user_service
+        .refresh_session(&login.refresh_token)
+        .await
+        .is_err());
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_logout_revokes_access_token() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let app = create_app(pool.clone(), &db_url);
+    register_user(&user_service, "logoutuser").await;
+    let token = login_token(&app, "logoutuser").await;
+    let other_session = login_token(&app, "logoutuser").await;
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/logout",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // The token is refused everywhere, while other sessions keep working
+    for uri in ["/api/v1/users/me", "/api/v2/accounts"] {
+        let (status, _, _) = send_request(&app, Method::GET, uri, Some(&token), &[], None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", uri);
+    }
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me",
+        Some(&other_session),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Logging out needs a token of some kind
+    let (status, _, _) =
+        send_request(&app, Method::POST, "/api/v1/users/logout", None, &[], None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Revocations are kept only until the token would have expired
+    assert_eq!(user_service.purge_expired_revocations().await.unwrap(), 0);
+    sqlx::query("UPDATE revoked_tokens SET expires_at = NOW() - INTERVAL '1 second'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(user_service.purge_expired_revocations().await.unwrap(), 1);
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM revoked_tokens")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_access_token_expiry_is_configurable() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = UserService::new(
+        pool.clone(),
+        "test_secret".to_string(),
+        AccountService::new(pool.clone(), AccountConfig::default()),
+    )
+    .with_jwt_expiry(chrono::Duration::seconds(1));
+    register_user(&user_service, "expiryuser").await;
+
+    let login = user_service
+        .login(
+            LoginRequest {
+                username: "expiryuser".to_string(),
+                password: "securepassword".to_string(),
+            },
+            &RequestContext::default(),
+        )
+        .await;
+    let Ok(LoginOutcome::Session(session)) = login else {
+        panic!("Login should start a session");
+    };
+    let claims = validate_jwt(&session.token, "test_secret").unwrap().claims;
+    assert_eq!(claims.exp - claims.iat, 1);
+
+    // Rejected as soon as it expires, with no grace period
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    match validate_jwt(&session.token, "test_secret") {
+        Err(AppError::Auth(message)) => {
+            assert!(message.contains("ExpiredSignature"), "{}", message)
+        }
+        other => panic!(
+            "expected an expired token, got {:?}",
+            other.map(|data| data.claims)
+        ),
+    }
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_change_password() {
+    let (pool, db_url) = setup().await;
+
+    let user_service = create_user_service(pool.clone());
+    let app = create_app(pool.clone(), &db_url);
+    register_user(&user_service, "passworduser").await;
+    let token = login_token(&app, "passworduser").await;
+    let change = |current: &str, new: &str| {
+        let app = app.clone();
+        let token = token.clone();
+        let body = serde_json::json!({ "current_password": current, "new_password": new });
+        async move {
+            send_request(
+                &app,
+                Method::PUT,
+                "/api/v1/users/password",
+                Some(&token),
+                &[],
+                Some(body),
+            )
+            .await
+        }
+    };
+    let login = |password: &str| {
+        let app = app.clone();
+        let body = serde_json::json!({ "username": "passworduser", "password": password });
+        async move {
+            send_request(
+                &app,
+                Method::POST,
+                "/api/v1/users/login",
+                None,
+                &[],
+                Some(body),
+            )
+            .await
+            .0
+        }
+    };
+
+    let (status, _, _) = change("wrongpassword", "brandnewpassword").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = change("securepassword", "short").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(login("securepassword").await, StatusCode::OK);
+
+    let (status, _, body) = change("securepassword", "brandnewpassword").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // Only the new password logs in from now on
+    assert_eq!(login("securepassword").await, StatusCode::UNAUTHORIZED);
+    assert_eq!(login("brandnewpassword").await, StatusCode::OK);
+
+    let (status, _, _) = send_request(
+        &app,
+        Method::PUT,
+        "/api/v1/users/password",
+        None,
+        &[],
+        Some(serde_json::json!({ "current_password": "brandnewpassword", "new_password": "another1" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    teardown(&db_url).await;
+}
+#[tokio::test]
+async fn test_admin_bulk_user_creation() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+
+    register_user(&user_service, "bulkadmin").await;
+    register_user(&user_service, "existing").await;
+    promote_to_admin(&pool, "bulkadmin").await;
+    let admin_token = login_token(&app, "bulkadmin").await;
+    let user_token = login_token(&app, "existing").await;
+
+    let entry = |username: &str, password: &str| {
+        serde_json::json!({
+            "username": username,
+            "email": format!("{}@corp.example.com", username),
+            "password": password,
+            "first_name": "Onboarded"
+        })
+    };
+    let batch = serde_json::json!([
+        entry("alice.corp", "securepassword"),
+        entry("existing", "securepassword"),
+        entry("bob.corp", "short"),
+        entry("carol.corp", "anothersecret"),
+        entry("alice.corp", "securepassword"),
+    ]);
+
+    // Regular users can't onboard anyone
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/admin/users/bulk",
+        Some(&user_token),
+        &[],
+        Some(batch.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/admin/users/bulk",
+        Some(&admin_token),
+        &[],
+        Some(batch),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let results = body["data"].as_array().unwrap();
+    let statuses: Vec<&str> = results
+        .iter()
+        .map(|result| result["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        statuses,
+        vec!["CREATED", "CONFLICT", "INVALID", "CREATED", "CONFLICT"]
+    );
+    assert_eq!(results[1]["username"], "existing");
+    assert_eq!(results[1]["index"], 1);
+    assert!(results[1]["error"].is_string());
+    assert!(results[1].get("user").is_none());
+    assert!(!body.to_string().contains("securepassword"));
+
+    // Created users have their own password and a default account
+    let carol_id = uuid::Uuid::parse_str(results[3]["user"]["id"].as_str().unwrap()).unwrap();
+    let accounts = account_service
+        .get_accounts_by_user_id(carol_id)
+        .await
+        .unwrap();
+    assert_eq!(accounts.len(), 1);
+    let hashes: Vec<String> = sqlx::query_scalar(
+        "SELECT password_hash FROM users WHERE username IN ('alice.corp', 'carol.corp')",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(hashes.len(), 2);
+    assert_ne!(hashes[0], hashes[1]);
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/login",
+        None,
+        &[],
+        Some(serde_json::json!({ "username": "carol.corp", "password": "anothersecret" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Empty batches are refused outright
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/admin/users/bulk",
+        Some(&admin_token),
+        &[],
+        Some(serde_json::json!([])),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_admin_erases_user_but_keeps_their_transactions() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let transaction_service = create_transaction_service(pool.clone());
+
+    register_user(&user_service, "erasureadmin").await;
+    let user = register_user(&user_service, "forgetme").await;
+    promote_to_admin(&pool, "erasureadmin").await;
+    let admin_token = login_token(&app, "erasureadmin").await;
+    let user_token = login_token(&app, "forgetme").await;
+
+    let account_id = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let deposit = transaction_service
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(25),
+            currency: None,
+            exchange_rate: None,
+            description: Some("Paycheck".to_string()),
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+
+    // Only administrators may erase users
+    let uri = format!("/api/v1/admin/users/{}/erase", user.id);
+    let (status, _, _) = send_request(&app, Method::POST, &uri, Some(&user_token), &[], None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, body) =
+        send_request(&app, Method::POST, &uri, Some(&admin_token), &[], None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["id"], user.id.to_string());
+    assert!(!body.to_string().contains("forgetme"));
+    assert!(body["data"]["first_name"].is_null());
+
+    // The personal data is gone from the database
+    let (username, email, password_hash): (String, String, String) =
+        sqlx::query_as("SELECT username, email, password_hash FROM users WHERE id = $1")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(username, format!("erased-{}", user.id.simple()));
+    assert!(email.ends_with("@erased.invalid"));
+    assert!(password_hash.is_empty());
+    let logins: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM login_history WHERE user_id = $1")
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(logins, 0);
+
+    // The money trail is still there, under the same anonymous user ID
+    let accounts = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap();
+    assert_eq!(accounts[0].balance, Decimal::from(25));
+    let transaction = transaction_service
+        .get_transaction_by_id(deposit.id)
+        .await
+        .unwrap();
+    assert_eq!(transaction.receiver_account_id, Some(account_id));
+
+    // The user is signed out everywhere and can't sign in again
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me",
+        Some(&user_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/login",
+        None,
+        &[],
+        Some(serde_json::json!({ "username": "forgetme", "password": "securepassword" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Erasure happens once
+    let (status, _, _) =
+        send_request(&app, Method::POST, &uri, Some(&admin_token), &[], None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_deactivated_user_cannot_log_in_until_reactivated() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let context = RequestContext::default();
+
+    register_user(&user_service, "reactivator").await;
+    let user = register_user(&user_service, "leaving").await;
+    promote_to_admin(&pool, "reactivator").await;
+    let admin_token = login_token(&app, "reactivator").await;
+    let user_token = login_token(&app, "leaving").await;
+    let login = || {
+        user_service.login(
+            LoginRequest {
+                username: "leaving".to_string(),
+                password: "securepassword".to_string(),
+            },
+            &context,
+        )
+    };
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::DELETE,
+        "/api/v1/users/me?freeze_accounts=true",
+        Some(&user_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["id"], user.id.to_string());
+
+    // The user can't sign in, and the token they had stops working
+    let result = login().await;
+    assert!(matches!(result, Err(AppError::Auth(_))), "{:?}", result);
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me",
+        Some(&user_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Nothing is deleted: the user is still found and their accounts are frozen
+    assert_eq!(
+        user_service.get_user_by_id(user.id).await.unwrap().username,
+        "leaving"
+    );
+    let accounts = account_service
+        .get_accounts_by_user_id(user.id)
+        .await
+        .unwrap();
+    assert_eq!(accounts[0].status, "FROZEN");
+    let result = user_service.deactivate_user(user.id, false).await;
+    assert!(matches!(result, Err(AppError::Conflict(_))), "{:?}", result);
+
+    // Only administrators reactivate users
+    let uri = format!("/api/v1/admin/users/{}/reactivate", user.id);
+    let (status, _, body) =
+        send_request(&app, Method::POST, &uri, Some(&admin_token), &[], None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(matches!(login().await, Ok(LoginOutcome::Session(_))));
+    let (status, _, _) =
+        send_request(&app, Method::POST, &uri, Some(&admin_token), &[], None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    teardown(&db_url).await;
+}
+
+/// Email sender that keeps messages in memory
+#[derive(Default)]
+struct CapturingSender {
+    sent: Mutex<Vec<EmailMessage>>,
+}
+
+#[async_trait]
+impl EmailSender for CapturingSender {
+    async fn send(&self, message: &EmailMessage) -> Result<(), String> {
+        self.sent.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
+
+/// Looks up the verification token a user was sent
+async fn verification_token(pool: &sqlx::PgPool, username: &str) -> Option<String> {
+    sqlx::query_scalar("SELECT verification_token FROM users WHERE username = $1")
+        .bind(username)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn verify(app: &axum::Router, token: &str) -> (StatusCode, serde_json::Value) {
+    let (status, _, body) = send_request(
+        app,
+        Method::GET,
+        &format!("/api/v1/users/verify?token={}", token),
+        None,
+        &[],
+        None,
+    )
+    .await;
+    (status, body)
+}
+
+#[tokio::test]
+async fn test_verify_email_with_emailed_token() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let sender = Arc::new(CapturingSender::default());
+    let user_service = UserService::new(
+        pool.clone(),
+        "test_secret".to_string(),
+        AccountService::new(pool.clone(), AccountConfig::default()),
+    )
+    .with_notifications(Arc::new(NotificationService::new(
+        pool.clone(),
+        sender.clone(),
+        NotificationConfig::default(),
+    )));
+
+    let user = register_user(&user_service, "verifyme").await;
+    assert!(!user.email_verified);
+
+    // The token arrives by email, sent in the background
+    let mut message = None;
+    for _ in 0..50 {
+        message = sender.sent.lock().unwrap().first().cloned();
+        if message.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let message = message.expect("No verification email was sent");
+    assert_eq!(message.to, user.email);
+    let token = verification_token(&pool, "verifyme").await.unwrap();
+    assert_eq!(token.len(), 64);
+    assert!(message.body.contains(&token));
+
+    let (status, body) = verify(&app, &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["email_verified"], true);
+    let verified = user_service.get_user_by_id(user.id).await.unwrap();
+    assert!(verified.email_verified);
+    let kind: String = sqlx::query_scalar("SELECT kind FROM email_deliveries WHERE user_id = $1")
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(kind, "EMAIL_VERIFICATION");
+
+    // Tokens are single-use
+    let (status, body) = verify(&app, &token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body["message"],
+        "Invalid or already used verification token"
+    );
+    assert_eq!(verification_token(&pool, "verifyme").await, None);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_expired_verification_token_is_rejected() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let user = register_user(&user_service, "lateverifier").await;
+    let token = verification_token(&pool, "lateverifier").await.unwrap();
+
+    // Tokens are issued for a day
+    let ttl_hours: f64 = sqlx::query_scalar(
+        r#"
+        SELECT EXTRACT(EPOCH FROM verification_token_expires_at - created_at)::FLOAT8 / 3600
+        FROM users WHERE id = $1
+        "#,
+    )
+    .bind(user.id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!((ttl_hours - 24.0).abs() < 0.01, "{}", ttl_hours);
+
+    sqlx::query(
+        "UPDATE users SET verification_token_expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1",
+    )
+    .bind(user.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let (status, body) = verify(&app, &token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["message"], "Verification token has expired");
+    let user = user_service.get_user_by_id(user.id).await.unwrap();
+    assert!(!user.email_verified);
+
+    let (status, _) = verify(&app, "not-a-token").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_totp_two_factor_login() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    register_user(&create_user_service(pool.clone()), "totpuser").await;
+    let token = login_token(&app, "totpuser").await;
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/me/totp",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let secret = body["data"]["secret"].as_str().unwrap().to_string();
+    assert_eq!(
+        body["data"]["provisioning_uri"],
+        format!(
+            "otpauth://totp/txn-manager:totpuser?secret={}&issuer=txn-manager&algorithm=SHA1&digits=6&period=30",
+            secret
+        )
+    );
+    let step = time_step(Utc::now());
+    let code = |step| code_at_step(&secret, step).unwrap();
+    let verify = |code: String| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            send_request(
+                &app,
+                Method::POST,
+                "/api/v1/users/me/totp/verify",
+                Some(&token),
+                &[],
+                Some(serde_json::json!({ "code": code })),
+            )
+            .await
+        }
+    };
+
+    // Setup is only confirmed by a code from the new secret
+    let (status, _, _) = verify(code(step + 5)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _, body) = verify(code(step)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["totp_enabled"], true);
+    let (status, _, _) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/me/totp",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // The password alone no longer signs the user in
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/users/login",
+        None,
+        &[],
+        Some(serde_json::json!({ "username": "totpuser", "password": "securepassword" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["totp_required"], true);
+    assert!(body["data"].get("token").is_none());
+    let totp_token = body["data"]["totp_token"].as_str().unwrap().to_string();
+
+    // The pending token doesn't authenticate requests
+    let (status, _, _) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me",
+        Some(&totp_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let login_totp = |code: String| {
+        let app = app.clone();
+        let totp_token = totp_token.clone();
+        async move {
+            send_request(
+                &app,
+                Method::POST,
+                "/api/v1/users/login/totp",
+                None,
+                &[],
+                Some(serde_json::json!({ "totp_token": totp_token, "code": code })),
+            )
+            .await
+        }
+    };
+
+    // Expired: two steps back is outside the drift window
+    let (status, _, _) = login_totp(code(step - 2)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    // Reused: the code that confirmed setup
+    let (status, _, _) = login_totp(code(step)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // One step ahead is within the drift window
+    let (status, _, body) = login_totp(code(step + 1)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let access_token = body["data"]["token"].as_str().unwrap().to_string();
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/users/me",
+        Some(&access_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, _, _) = login_totp(code(step + 1)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    teardown(&db_url).await;
+}
@@ -0,0 +1,540 @@
+use crate::integration::setup::{
+    create_account_service, create_app, create_transaction_service, create_user_service,
+    login_token, register_user, send_request, setup, teardown,
+};
+use axum::http::{HeaderMap, Method, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use hmac::{Hmac, Mac};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use txn_manager::services::webhook_service::{EVENT_HEADER, SIGNATURE_HEADER};
+use txn_manager::{DepositRequest, WebhookService};
+
+/// A delivery as seen by the mock endpoint
+struct Delivery {
+    event: String,
+    signature: String,
+    body: String,
+}
+
+/// Serves a webhook endpoint on a local port that answers the first
+/// `failures` deliveries with a 500
+///
+/// # Returns
+/// The endpoint URL and a channel receiving every delivery made to it
+async fn mock_endpoint(failures: usize) -> (String, mpsc::UnboundedReceiver<Delivery>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let failures = Arc::new(AtomicUsize::new(failures));
+    let app = Router::new().route(
+        "/hooks",
+        post(move |headers: HeaderMap, body: String| async move {
+            let header = |name: &str| {
+                headers
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let _ = sender.send(Delivery {
+                event: header(EVENT_HEADER),
+                signature: header(SIGNATURE_HEADER),
+                body,
+            });
+            match failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)) {
+                Ok(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                Err(_) => StatusCode::OK,
+            }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (url, receiver)
+}
+
+async fn next_delivery(deliveries: &mut mpsc::UnboundedReceiver<Delivery>) -> Delivery {
+    tokio::time::timeout(Duration::from_secs(10), deliveries.recv())
+        .await
+        .expect("No webhook delivery arrived")
+        .unwrap()
+}
+
+/// Waits until `attempts` attempts at delivering a transaction's event are recorded
+///
+/// # Returns
+/// The delivery's status, attempts, latest response status and error, and payload
+async fn delivery_after(
+    pool: &sqlx::PgPool,
+    transaction_id: &str,
+    attempts: i32,
+) -> (String, i32, Option<i32>, Option<String>, String) {
+    let transaction_id: uuid::Uuid = transaction_id.parse().unwrap();
+    for _ in 0..100 {
+        let delivery = sqlx::query_as(
+            r#"
+            SELECT status, attempts, response_status, last_error, payload
+            FROM webhook_deliveries
+            WHERE transaction_id = $1 AND attempts = $2
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(attempts)
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+        if let Some(delivery) = delivery {
+            return delivery;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Delivery of {} did not finish", transaction_id);
+}
+
+#[tokio::test]
+async fn test_register_and_list_webhooks() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    register_user(&user_service, "hookowner").await;
+    register_user(&user_service, "hookstranger").await;
+    let token = login_token(&app, "hookowner").await;
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/webhooks",
+        Some(&token),
+        &[],
+        Some(json!({ "url": "https://example.com/hooks" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["url"], "https://example.com/hooks");
+    assert_eq!(body["data"]["secret"].as_str().unwrap().len(), 64);
+
+    // The secret is shown once, at registration
+    let (status, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/webhooks",
+        Some(&token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let webhooks = body["data"].as_array().unwrap();
+    assert_eq!(webhooks.len(), 1);
+    assert!(webhooks[0].get("secret").is_none());
+
+    for url in [
+        "ftp://example.com/hooks",
+        "not a url",
+        "https://user:pw@example.com/",
+    ] {
+        let (status, _, body) = send_request(
+            &app,
+            Method::POST,
+            "/api/v1/webhooks",
+            Some(&token),
+            &[],
+            Some(json!({ "url": url })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", url, body);
+    }
+
+    // Webhooks are private to their owner
+    let stranger_token = login_token(&app, "hookstranger").await;
+    let (_, _, body) = send_request(
+        &app,
+        Method::GET,
+        "/api/v1/webhooks",
+        Some(&stranger_token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(body["data"], json!([]));
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_completed_transactions_are_posted_to_webhooks() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let owner = register_user(&user_service, "hookpayer").await;
+    let peer = register_user(&user_service, "hookpayee").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(owner.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let peer_account_id = account_service
+        .get_accounts_by_user_id(peer.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let token = login_token(&app, "hookpayer").await;
+
+    // The first delivery fails and is retried once due
+    let (url, mut deliveries) = mock_endpoint(1).await;
+    let (_, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/webhooks",
+        Some(&token),
+        &[],
+        Some(json!({ "url": url })),
+    )
+    .await;
+    let secret = body["data"]["secret"].as_str().unwrap().to_string();
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/deposit",
+        Some(&token),
+        &[],
+        Some(json!({ "account_id": account_id, "amount": "100.00", "description": "Salary" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let deposit_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let failed = next_delivery(&mut deliveries).await;
+    let (status, _, response_status, last_error, _) = delivery_after(&pool, &deposit_id, 1).await;
+    assert_eq!(status, "PENDING");
+    assert_eq!(response_status, Some(500));
+    assert!(last_error.unwrap().contains("500"));
+    let retry_in: f64 = sqlx::query_scalar(
+        "SELECT EXTRACT(EPOCH FROM next_attempt_at - updated_at)::FLOAT8 FROM webhook_deliveries",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!((59.0..=61.0).contains(&retry_in), "{}", retry_in);
+
+    let webhook_service = WebhookService::new(pool.clone());
+    assert_eq!(webhook_service.retry_due().await.unwrap(), 0);
+    sqlx::query("UPDATE webhook_deliveries SET next_attempt_at = NOW()")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(webhook_service.retry_due().await.unwrap(), 1);
+    let retried = next_delivery(&mut deliveries).await;
+    assert_eq!(failed.body, retried.body);
+    assert_eq!(retried.event, "transaction.completed");
+
+    // The signature is the HMAC-SHA256 of the raw body, keyed with the secret
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(retried.body.as_bytes());
+    assert_eq!(
+        retried.signature,
+        format!("sha256={:x}", mac.finalize().into_bytes())
+    );
+
+    let payload: Value = serde_json::from_str(&retried.body).unwrap();
+    let mut keys: Vec<&str> = payload
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    keys.sort_unstable();
+    assert_eq!(
+        keys,
+        [
+            "amount",
+            "created_at",
+            "currency",
+            "event",
+            "receiver_account_id",
+            "sender_account_id",
+            "status",
+            "transaction_id",
+            "transaction_type",
+        ]
+    );
+    assert_eq!(payload["event"], "transaction.completed");
+    assert_eq!(payload["transaction_id"], deposit_id);
+    assert_eq!(payload["transaction_type"], "DEPOSIT");
+    assert_eq!(payload["amount"], "100.00");
+    assert_eq!(payload["currency"], "USD");
+    assert_eq!(payload["status"], "COMPLETED");
+    assert_eq!(payload["sender_account_id"], Value::Null);
+    assert_eq!(payload["receiver_account_id"], account_id.to_string());
+
+    // Both attempts are recorded on the delivery, with what was sent
+    let (status, attempts, response_status, last_error, recorded_body) =
+        delivery_after(&pool, &deposit_id, 2).await;
+    assert_eq!(status, "DELIVERED");
+    assert_eq!(attempts, 2);
+    assert_eq!(response_status, Some(200));
+    assert_eq!(last_error, None);
+    assert_eq!(recorded_body, retried.body);
+
+    // The sender of a transfer hears about it too
+    let (status, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/transactions/transfer",
+        Some(&token),
+        &[],
+        Some(json!({
+            "sender_account_id": account_id,
+            "receiver_account_id": peer_account_id,
+            "amount": "40.00",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let transfer = next_delivery(&mut deliveries).await;
+    let payload: Value = serde_json::from_str(&transfer.body).unwrap();
+    assert_eq!(payload["transaction_id"], body["data"]["id"]);
+    assert_eq!(payload["transaction_type"], "TRANSFER");
+    assert_eq!(payload["sender_account_id"], account_id.to_string());
+    assert_eq!(payload["receiver_account_id"], peer_account_id.to_string());
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_update_and_delete_webhooks() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let owner = register_user(&user_service, "hookeditor").await;
+    register_user(&user_service, "hookmeddler").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(owner.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let token = login_token(&app, "hookeditor").await;
+    let stranger_token = login_token(&app, "hookmeddler").await;
+
+    let (url, mut deliveries) = mock_endpoint(0).await;
+    let (_, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/webhooks",
+        Some(&token),
+        &[],
+        Some(json!({ "url": "https://example.com/old" })),
+    )
+    .await;
+    assert_eq!(body["data"]["enabled"], true);
+    let uri = format!("/api/v1/webhooks/{}", body["data"]["id"].as_str().unwrap());
+
+    // Only the owner sees and changes the webhook
+    for method in [Method::GET, Method::PATCH, Method::DELETE] {
+        let (status, _, _) = send_request(
+            &app,
+            method.clone(),
+            &uri,
+            Some(&stranger_token),
+            &[],
+            Some(json!({ "enabled": false })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", method);
+    }
+
+    let (status, _, body) = send_request(
+        &app,
+        Method::PATCH,
+        &uri,
+        Some(&token),
+        &[],
+        Some(json!({ "url": "ftp://example.com/hooks" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    let (status, _, body) = send_request(
+        &app,
+        Method::PATCH,
+        &uri,
+        Some(&token),
+        &[],
+        Some(json!({ "url": url, "enabled": false })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["url"], url.as_str());
+    assert_eq!(body["data"]["enabled"], false);
+    assert!(body["data"].get("secret").is_none());
+
+    // A disabled webhook gets nothing; enabled again, it gets the next event
+    let deposit = |amount: &'static str| {
+        let (app, token) = (app.clone(), token.clone());
+        async move {
+            let (status, _, body) = send_request(
+                &app,
+                Method::POST,
+                "/api/v1/transactions/deposit",
+                Some(&token),
+                &[],
+                Some(json!({ "account_id": account_id, "amount": amount })),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            body["data"]["id"].as_str().unwrap().to_string()
+        }
+    };
+    deposit("5.00").await;
+    // Let the event's background task look up webhooks before re-enabling
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let (status, _, body) = send_request(
+        &app,
+        Method::PATCH,
+        &uri,
+        Some(&token),
+        &[],
+        Some(json!({ "enabled": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["url"], url.as_str());
+    let deposit_id = deposit("7.00").await;
+    let delivery = next_delivery(&mut deliveries).await;
+    let payload: Value = serde_json::from_str(&delivery.body).unwrap();
+    assert_eq!(payload["transaction_id"], deposit_id);
+    let recorded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM webhook_deliveries")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(recorded, 1);
+
+    // Deleting the webhook takes its delivery history with it
+    delivery_after(&pool, &deposit_id, 1).await;
+    let (status, _, body) = send_request(&app, Method::DELETE, &uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, _, _) = send_request(&app, Method::GET, &uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let recorded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM webhook_deliveries")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(recorded, 0);
+
+    teardown(&db_url).await;
+}
+
+#[tokio::test]
+async fn test_dead_lettered_delivery_is_listed_and_redelivered() {
+    let (pool, db_url) = setup().await;
+    let app = create_app(pool.clone(), &db_url);
+    let user_service = create_user_service(pool.clone());
+    let account_service = create_account_service(pool.clone());
+    let owner = register_user(&user_service, "hookdebugger").await;
+    register_user(&user_service, "hookonlooker").await;
+    let account_id = account_service
+        .get_accounts_by_user_id(owner.id)
+        .await
+        .unwrap()[0]
+        .id;
+    let token = login_token(&app, "hookdebugger").await;
+    let stranger_token = login_token(&app, "hookonlooker").await;
+
+    let (url, mut deliveries) = mock_endpoint(2).await;
+    let (_, _, body) = send_request(
+        &app,
+        Method::POST,
+        "/api/v1/webhooks",
+        Some(&token),
+        &[],
+        Some(json!({ "url": url })),
+    )
+    .await;
+    let deliveries_uri = format!(
+        "/api/v1/webhooks/{}/deliveries",
+        body["data"]["id"].as_str().unwrap()
+    );
+
+    // One retry, and the delivery is dead-lettered when it fails too
+    let webhook_service =
+        WebhookService::new(pool.clone()).with_retry_schedule(vec![Duration::ZERO]);
+    let deposit = create_transaction_service(pool.clone())
+        .process_deposit(DepositRequest {
+            account_id,
+            amount: Decimal::from(30),
+            currency: None,
+            exchange_rate: None,
+            description: None,
+            category: None,
+            external_reference: None,
+        })
+        .await
+        .unwrap();
+    webhook_service
+        .send_transaction_completed(&deposit)
+        .await
+        .unwrap();
+    assert_eq!(webhook_service.retry_due().await.unwrap(), 1);
+    assert_eq!(webhook_service.retry_due().await.unwrap(), 0);
+    next_delivery(&mut deliveries).await;
+    next_delivery(&mut deliveries).await;
+
+    let (status, _, body) =
+        send_request(&app, Method::GET, &deliveries_uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let delivery = &body["data"][0];
+    assert_eq!(delivery["status"], "DEAD_LETTERED");
+    assert_eq!(delivery["transaction_id"], deposit.id.to_string());
+    assert_eq!(delivery["attempts"], 2);
+    assert_eq!(delivery["next_attempt_at"], Value::Null);
+    let attempt_log = delivery["attempt_log"].as_array().unwrap();
+    assert_eq!(attempt_log.len(), 2);
+    for (number, attempt) in (1..).zip(attempt_log) {
+        assert_eq!(attempt["attempt"], number);
+        assert_eq!(attempt["response_status"], 500);
+        assert!(attempt["latency_ms"].as_i64().unwrap() >= 0);
+        assert!(attempt["error"].as_str().unwrap().contains("500"));
+    }
+
+    // Only the owner sees the deliveries and redelivers them
+    let redeliver_uri = format!(
+        "{}/{}/redeliver",
+        deliveries_uri,
+        delivery["id"].as_str().unwrap()
+    );
+    for (method, uri) in [
+        (Method::GET, &deliveries_uri),
+        (Method::POST, &redeliver_uri),
+    ] {
+        let (status, _, _) =
+            send_request(&app, method, uri, Some(&stranger_token), &[], None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+    }
+
+    let (status, _, body) =
+        send_request(&app, Method::POST, &redeliver_uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["status"], "DELIVERED");
+    assert_eq!(body["data"]["attempts"], 3);
+    assert_eq!(body["data"]["attempt_log"][2]["response_status"], 200);
+    assert_eq!(body["data"]["attempt_log"][2]["error"], Value::Null);
+    let redelivered = next_delivery(&mut deliveries).await;
+    assert_eq!(redelivered.body, delivery["payload"].as_str().unwrap());
+
+    // Delivered events aren't sent again
+    let (status, _, _) =
+        send_request(&app, Method::POST, &redeliver_uri, Some(&token), &[], None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    teardown(&db_url).await;
+}